@@ -0,0 +1,176 @@
+//! Print GitHub Actions annotations.
+//!
+//! Annotations are workflow commands printed on the standard output (e.g.
+//! `::error file=blog/post.md,line=3,title=...::message`), which GitHub
+//! displays on the input files of pull requests.
+//!
+//! See <https://docs.github.com/en/actions/reference/workflow-commands-for-github-actions>.
+
+use std::path::Path;
+
+use crate::{
+    check::{Diagnostic, Severity},
+    config::Config,
+    error::Error,
+    util::path::PathExt,
+};
+
+/// Print an annotation for a build error.
+///
+/// If the error has a location (e.g. in a front matter or a layout), the
+/// annotation points to its file and line.
+pub(crate) fn annotate_error(error: &Error, config: &Config) {
+    let title = error.to_string();
+
+    let location = error.location();
+
+    let file = location
+        .and_then(|location| location.path.as_deref())
+        .or_else(|| error.input_path())
+        .and_then(|input_path| relative_path(input_path, config));
+
+    println!(
+        "{}",
+        workflow_command(
+            Severity::Error,
+            file,
+            location.map(|location| location.line),
+            &title,
+            &error.full_message(),
+        )
+    );
+}
+
+/// Print an annotation for a diagnostic of the `check` command.
+pub(crate) fn annotate_diagnostic(diagnostic: &Diagnostic, config: &Config) {
+    let title = format!("{} {}", diagnostic.code(), diagnostic.rule);
+
+    let file = diagnostic
+        .input_path
+        .as_ref()
+        .and_then(|input_path| relative_path(input_path, config));
+
+    println!(
+        "{}",
+        workflow_command(
+            diagnostic.severity,
+            file,
+            None,
+            &title,
+            &format!("{}: {}", diagnostic.url, diagnostic.message),
+        )
+    );
+}
+
+/// Return the path of an input file with `/` separators, relative to the
+/// current directory if possible, else to its input directory.
+///
+/// In CI workflows, the current directory is usually the root of the
+/// repository, to which annotations and reports refer.
+pub(crate) fn relative_path(input_path: &Path, config: &Config) -> Option<String> {
+    let current_dir = std::env::current_dir().and_then(|dir| dir.canonicalize());
+
+    let path = current_dir
+        .ok()
+        .and_then(|dir| input_path.strip_prefix(dir).ok())
+        .or_else(|| config.strip_input_dir(input_path))
+        .unwrap_or(input_path);
+
+    path.to_url_path()
+        .map(|path| path.trim_start_matches('/').to_owned())
+}
+
+/// Format a workflow command (e.g. `::error file=a.md,line=1,title=b::c`).
+fn workflow_command(
+    severity: Severity,
+    file: Option<String>,
+    line: Option<usize>,
+    title: &str,
+    message: &str,
+) -> String {
+    let properties: Vec<_> = file
+        .map(|file| format!("file={}", escape_property(&file)))
+        .into_iter()
+        .chain(line.map(|line| format!("line={line}")))
+        .chain([format!("title={}", escape_property(title))])
+        .collect();
+
+    format!(
+        "::{} {}::{}",
+        severity,
+        properties.join(","),
+        escape_data(message)
+    )
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check::Severity;
+
+    #[test]
+    fn workflow_command() {
+        const CASES: [(Severity, Option<&str>, Option<usize>, &str, &str, &str); 4] = [
+            (
+                Severity::Error,
+                Some("blog/post.md"),
+                None,
+                "V0021 img-alt",
+                "/blog/post/: Image \"a.png\" has no alt attribute",
+                "::error file=blog/post.md,title=V0021 img-alt::/blog/post/: Image \"a.png\" has \
+                 no alt attribute",
+            ),
+            (
+                Severity::Warning,
+                None,
+                None,
+                "a, b: c",
+                "100%\nDone",
+                "::warning title=a%2C b%3A c::100%25%0ADone",
+            ),
+            (
+                Severity::Error,
+                Some("a,b.md"),
+                None,
+                "While rendering layout",
+                "x: y",
+                "::error file=a%2Cb.md,title=While rendering layout::x: y",
+            ),
+            (
+                Severity::Error,
+                Some("post.md"),
+                Some(3),
+                "While parsing front matter",
+                "At line 3",
+                "::error file=post.md,line=3,title=While parsing front matter::At line 3",
+            ),
+        ];
+
+        for (severity, file, line, title, message, expected) in CASES {
+            let result = super::workflow_command(
+                severity,
+                file.map(ToOwned::to_owned),
+                line,
+                title,
+                message,
+            );
+            assert_eq!(
+                result, expected,
+                "\nworkflow_command({severity:?}, {file:?}, {line:?}, {title:?}, {message:?}) \
+                 expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
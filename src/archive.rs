@@ -0,0 +1,192 @@
+//! Package the output directory into an archive.
+//!
+//! Archives are deterministic: files are sorted by path, and timestamps,
+//! permissions and owners are fixed, so that building the same site twice
+//! produces identical archives.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use walkdir::WalkDir;
+
+use crate::{config::Config, error::Error};
+
+/// Archive format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArchiveFormat {
+    /// Uncompressed tar archive (`.tar`).
+    Tar,
+
+    /// Gzip-compressed tar archive (`.tar.gz` or `.tgz`).
+    TarGz,
+
+    /// Zip archive (`.zip`).
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Determine the archive format from the file name.
+    fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let file_name = path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if file_name.ends_with(".tar") {
+            Ok(Self::Tar)
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Ok(Self::TarGz)
+        } else if file_name.ends_with(".zip") {
+            Ok(Self::Zip)
+        } else {
+            Err(anyhow::anyhow!(
+                "Unknown archive extension (expected \".tar\", \".tar.gz\", \".tgz\" or \".zip\")"
+            ))
+        }
+    }
+}
+
+/// Write the output directory to an archive.
+///
+/// The site must be built before calling this function. The format is
+/// determined by the extension of `archive_path`.
+pub(super) fn write_archive(config: &Config, archive_path: &Path) -> Result<(), Error> {
+    let map_error = |error: anyhow::Error| Error::WriteArchive {
+        archive_path: archive_path.to_owned(),
+        source: error,
+    };
+
+    let Some(output_dir) = config.output_dir.as_ref() else {
+        return Err(map_error(anyhow::anyhow!("No output directory specified")));
+    };
+
+    let format = ArchiveFormat::from_path(archive_path).map_err(map_error)?;
+
+    let files = list_files(output_dir).map_err(map_error)?;
+
+    tracing::info!("Writing {} files to {:?}", files.len(), archive_path);
+
+    let file = std::fs::File::create(archive_path).map_err(|error| map_error(error.into()))?;
+
+    match format {
+        ArchiveFormat::Tar => write_tar(file, &files).map(|_| ()),
+        ArchiveFormat::TarGz => write_tar_gz(file, &files),
+        ArchiveFormat::Zip => write_zip(file, &files),
+    }
+    .map_err(map_error)
+}
+
+/// List the files of a directory, sorted by path.
+///
+/// This function returns pairs of relative paths (with `/` as separator) and
+/// absolute paths.
+fn list_files(dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry
+            .path()
+            .strip_prefix(dir)?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        files.push((path, entry.path().to_owned()));
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Write files to a tar archive.
+fn write_tar<W>(writer: W, files: &[(String, PathBuf)]) -> anyhow::Result<W>
+where
+    W: Write,
+{
+    let mut builder = tar::Builder::new(writer);
+
+    for (path, file_path) in files {
+        let content = std::fs::read(file_path)?;
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+
+        builder.append_data(&mut header, path, content.as_slice())?;
+    }
+
+    Ok(builder.into_inner()?)
+}
+
+/// Write files to a gzip-compressed tar archive.
+fn write_tar_gz<W>(writer: W, files: &[(String, PathBuf)]) -> anyhow::Result<()>
+where
+    W: Write,
+{
+    let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::best());
+    let encoder = write_tar(encoder, files)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Write files to a zip archive.
+fn write_zip<W>(writer: W, files: &[(String, PathBuf)]) -> anyhow::Result<()>
+where
+    W: Write + std::io::Seek,
+{
+    let mut zip = zip::ZipWriter::new(writer);
+
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(0o644);
+
+    for (path, file_path) in files {
+        let content = std::fs::read(file_path)?;
+
+        zip.start_file(path.as_str(), options)?;
+        zip.write_all(&content)?;
+    }
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArchiveFormat;
+
+    #[test]
+    fn format_from_path() {
+        const CASES: [(&str, Option<ArchiveFormat>); 5] = [
+            ("site.tar", Some(ArchiveFormat::Tar)),
+            ("site.tar.gz", Some(ArchiveFormat::TarGz)),
+            ("dist/site.tgz", Some(ArchiveFormat::TarGz)),
+            ("site.ZIP", Some(ArchiveFormat::Zip)),
+            ("site.rar", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = ArchiveFormat::from_path(input.as_ref()).ok();
+            assert_eq!(
+                result, expected,
+                "\nfrom_path({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
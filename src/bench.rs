@@ -0,0 +1,238 @@
+//! Benchmark the build of the site.
+
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{build, config::Config, error::Error, util::table::format_table};
+
+/// Name of the row containing the duration of whole builds.
+const TOTAL: &str = "total";
+
+/// Mean durations in seconds, indexed by kind of build and by task.
+type Baseline = BTreeMap<String, BTreeMap<String, f64>>;
+
+/// Durations of each task over several builds, in the order of the tasks.
+type Samples = Vec<(&'static str, Vec<Duration>)>;
+
+/// Build the site several times, and print the duration of each build task.
+///
+/// Cold builds start with empty output and cache directories, and warm builds
+/// reuse the files of the previous build. Output and cache files are written
+/// to a temporary directory. Mean durations are compared with the baseline
+/// file, if any, and saved to it if `save_baseline` is `true`.
+pub(super) fn bench(
+    config: Config,
+    runs: usize,
+    baseline_path: &Path,
+    save_baseline: bool,
+) -> Result<(), Error> {
+    let temp_dir = std::env::temp_dir().join(format!("vitrine-bench-{}", std::process::id()));
+
+    let config = Config {
+        output_dir: Some(temp_dir.join("output")),
+        cache_dir: config.cache_dir.as_ref().map(|_| temp_dir.join("cache")),
+        ..config
+    };
+
+    let result = run_builds(&config, runs, &temp_dir);
+
+    // Remove the temporary directory, even if a build failed
+    let result = result.and_then(|results| {
+        remove_dir(&temp_dir)?;
+        Ok(results)
+    });
+
+    let results = result?;
+
+    let baseline = read_baseline(baseline_path)?;
+
+    for (kind, samples) in results.iter() {
+        println!("Mean durations of {} builds ({} runs)", kind, runs);
+        println!(
+            "{}\n",
+            format_table(&format_rows(samples, baseline.get(*kind)))
+        );
+    }
+
+    if save_baseline {
+        write_baseline(baseline_path, &results)?;
+    }
+
+    Ok(())
+}
+
+/// Run cold builds, then warm builds.
+fn run_builds(
+    config: &Config,
+    runs: usize,
+    temp_dir: &Path,
+) -> Result<Vec<(&'static str, Samples)>, Error> {
+    let mut cold_samples = Samples::new();
+
+    for _ in 0..runs {
+        remove_dir(temp_dir)?;
+        add_samples(&mut cold_samples, run_build(config)?);
+    }
+
+    let mut warm_samples = Samples::new();
+
+    for _ in 0..runs {
+        add_samples(&mut warm_samples, run_build(config)?);
+    }
+
+    Ok(vec![("cold", cold_samples), ("warm", warm_samples)])
+}
+
+/// Remove a directory and its content, if it exists.
+fn remove_dir(dir: &Path) -> Result<(), Error> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    std::fs::remove_dir_all(dir).map_err(|error| Error::Bench {
+        source: error.into(),
+    })
+}
+
+/// Build the site, and return the duration of each task and of the build.
+fn run_build(config: &Config) -> Result<Vec<(&'static str, Duration)>, Error> {
+    let start_time = Instant::now();
+
+    let mut durations = build::bench(config)?;
+
+    durations.push((TOTAL, start_time.elapsed()));
+
+    Ok(durations)
+}
+
+/// Add the durations of a build to samples.
+fn add_samples(samples: &mut Samples, durations: Vec<(&'static str, Duration)>) {
+    for (task, duration) in durations {
+        match samples.iter_mut().find(|(name, _)| *name == task) {
+            Some((_, durations)) => durations.push(duration),
+            None => samples.push((task, vec![duration])),
+        }
+    }
+}
+
+/// Format the rows of a table of durations.
+fn format_rows(samples: &Samples, baseline: Option<&BTreeMap<String, f64>>) -> Vec<[String; 6]> {
+    let header = ["Task", "Mean", "p50", "p90", "Baseline", "Change"].map(str::to_owned);
+
+    std::iter::once(header)
+        .chain(samples.iter().map(|(task, durations)| {
+            let mean = mean(durations);
+            let baseline = baseline.and_then(|baseline| baseline.get(*task)).copied();
+
+            [
+                task.to_string(),
+                format_seconds(mean),
+                format_seconds(percentile(durations, 50.0).as_secs_f64()),
+                format_seconds(percentile(durations, 90.0).as_secs_f64()),
+                baseline
+                    .map(format_seconds)
+                    .unwrap_or_else(|| "-".to_owned()),
+                baseline
+                    .filter(|&baseline| baseline > 0.0)
+                    .map(|baseline| format!("{:+.1}%", (mean / baseline - 1.0) * 100.0))
+                    .unwrap_or_else(|| "-".to_owned()),
+            ]
+        }))
+        .collect()
+}
+
+/// Compute the mean of durations, in seconds.
+fn mean(durations: &[Duration]) -> f64 {
+    if durations.is_empty() {
+        return 0.0;
+    }
+    durations.iter().sum::<Duration>().as_secs_f64() / durations.len() as f64
+}
+
+/// Compute a percentile of durations using the nearest-rank method.
+fn percentile(durations: &[Duration], percent: f64) -> Duration {
+    let mut durations = durations.to_vec();
+    durations.sort();
+
+    let rank = (percent / 100.0 * durations.len() as f64).ceil() as usize;
+
+    durations
+        .get(rank.clamp(1, durations.len().max(1)) - 1)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Format a duration in seconds as milliseconds.
+fn format_seconds(seconds: f64) -> String {
+    format!("{:.2} ms", seconds * 1000.0)
+}
+
+/// Read the baseline file, if it exists.
+fn read_baseline(baseline_path: &Path) -> Result<Baseline, Error> {
+    if !baseline_path.exists() {
+        return Ok(Baseline::new());
+    }
+
+    std::fs::read_to_string(baseline_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Ok(serde_json::from_str(&content)?))
+        .map_err(|error| Error::Bench {
+            source: error.context(format!("While reading baseline {baseline_path:?}")),
+        })
+}
+
+/// Write the mean durations to the baseline file.
+fn write_baseline(baseline_path: &Path, results: &[(&str, Samples)]) -> Result<(), Error> {
+    let baseline: Baseline = results
+        .iter()
+        .map(|(kind, samples)| {
+            (
+                kind.to_string(),
+                samples
+                    .iter()
+                    .map(|(task, durations)| (task.to_string(), mean(durations)))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    tracing::info!("Writing {:?}", baseline_path);
+
+    serde_json::to_string_pretty(&baseline)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Ok(std::fs::write(baseline_path, content)?))
+        .map_err(|error| Error::Bench {
+            source: error.context(format!("While writing baseline {baseline_path:?}")),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[test]
+    fn percentile() {
+        const CASES: [(&[u64], f64, u64); 5] = [
+            (&[], 50.0, 0),
+            (&[10], 90.0, 10),
+            (&[30, 10, 20], 50.0, 20),
+            (&[10, 20, 30, 40, 50, 60, 70, 80, 90, 100], 90.0, 90),
+            (&[10, 20, 30, 40, 50, 60, 70, 80, 90, 100], 50.0, 50),
+        ];
+
+        for (input, percent, expected) in CASES {
+            let durations: Vec<Duration> =
+                input.iter().copied().map(Duration::from_millis).collect();
+            let result = super::percentile(&durations, percent);
+            let expected = Duration::from_millis(expected);
+            assert_eq!(
+                result, expected,
+                "\npercentile({input:?}, {percent:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
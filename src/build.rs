@@ -2,38 +2,93 @@
 //!
 //! Each submodule implements functions that represent a build task.
 
+mod a11y;
+mod absolute_urls;
+mod activitypub;
+mod analytics;
+mod api;
+mod blogroll;
+mod breadcrumbs;
+mod cache;
+mod collections;
+mod color_scheme;
 mod contents;
+mod copy_paths;
+mod csp;
 mod data_cascade;
+mod directives;
+mod documents;
+mod events;
+mod external_links;
 mod feed;
+mod fonts;
+mod fragments;
 mod front_matter;
+mod front_matter_defaults;
+mod front_matter_schema;
+mod generate_pages;
+mod git;
 mod global_data;
+mod hosting;
+mod html_fragments;
 mod ignore;
+mod images;
+mod import_map;
+mod integrity;
 mod layouts;
 mod markdown;
 mod minify_css;
 mod minify_html;
 mod minify_js;
 mod minify_json;
+mod minify_svg;
 mod minify_xml;
 mod navigation;
+mod newsletter;
+mod page_weight;
+mod precompress;
+mod preload;
 mod read_file;
+mod sanitize;
 mod scss;
 mod sitemap;
+mod symlinks;
 mod syntax_highlight;
+mod tailwind;
 mod taxonomies;
+mod timings;
+mod tokens;
 mod typescript;
+mod unused_assets;
 mod url;
 mod write_file;
 
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
     path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
 };
 
 use serde::{Deserialize, Serialize};
 use walkdir::{DirEntry, WalkDir};
 
-pub(self) use crate::{config::Config, error::Error};
+use self::timings::Timings;
+pub(crate) use self::{
+    hosting::HostingProvider,
+    preload::{font_type, PreloadHint},
+    symlinks::SymlinkPolicy,
+};
+use crate::{
+    check::{Checks, Diagnostic},
+    config::Config,
+    error::Error,
+    list::ListEntry,
+    report::PageReport,
+    site::{Page, Site},
+    util::{path::PathExt, url::UrlPath},
+};
 
 /// Build entry.
 ///
@@ -84,6 +139,27 @@ impl Entry {
     fn input_path_buf(&self) -> Option<PathBuf> {
         self.input_file.as_ref().map(|v| v.path().to_owned())
     }
+
+    /// Get the base URL of relative URLs found in the entry content.
+    ///
+    /// Pages are written as `{url}/index.html`, so relative URLs start from
+    /// `{url}/`.
+    fn base_dir(&self) -> UrlPath {
+        UrlPath::from(format!("{}/", self.url.trim_end_matches('/')))
+    }
+
+    /// Get the modification time of the input file, if any.
+    ///
+    /// For reproducible builds, the `source_date_epoch` of the configuration
+    /// is returned instead.
+    fn modified(&self, config: &Config) -> Option<SystemTime> {
+        let input_file = self.input_file.as_ref()?;
+
+        match config.source_date_epoch {
+            Some(epoch) => Some(SystemTime::UNIX_EPOCH + Duration::from_secs(epoch)),
+            None => input_file.metadata().ok()?.modified().ok(),
+        }
+    }
 }
 
 /// Metadata associated to a build entry.
@@ -103,6 +179,14 @@ struct EntryData {
     #[serde(default)]
     date: Option<String>,
 
+    /// Entry date of last modification.
+    #[serde(default)]
+    updated: Option<String>,
+
+    /// Entry contributors.
+    #[serde(default)]
+    contributors: Option<Vec<String>>,
+
     /// Associated contents.
     #[serde(default)]
     contents: HashMap<String, String>,
@@ -139,67 +223,428 @@ enum EntrySitemap {
 }
 
 /// Build the site from given configuration.
-pub(super) fn build(config: &Config) -> Result<(), Error> {
+///
+/// This function returns the pages of the built site, which can be queried to
+/// generate custom artifacts.
+pub fn build(config: &Config) -> Result<Site, Error> {
+    build_with(config, &Timings::default(), None, |_| Ok(())).map(|(site, _)| site)
+}
+
+/// Files used to render each page, other than their input file.
+#[derive(Clone, Debug, Default)]
+struct Dependencies {
+    /// Templates used to render each page.
+    templates: self::layouts::Dependencies,
+
+    /// Files included in each page.
+    includes: self::directives::Dependencies,
+}
+
+impl Dependencies {
+    /// Add the dependencies of other pages, replacing existing ones.
+    fn extend(&mut self, other: Self) {
+        self.templates.extend(other.templates);
+        self.includes.extend(other.includes);
+    }
+}
+
+/// State of a watch session, kept between builds.
+#[derive(Debug, Default)]
+pub(super) struct Session {
+    /// Files used to render each page, known after a first build.
+    dependencies: Option<Dependencies>,
+}
+
+impl Session {
+    /// Build the whole site.
+    pub(super) fn build(&mut self, config: &Config) -> Result<Site, Error> {
+        crate::hooks::run_before_build(config)?;
+
+        let (site, dependencies) = build_with(config, &Timings::default(), None, |_| Ok(()))?;
+
+        self.dependencies = Some(dependencies);
+
+        crate::hooks::run_after_build(config)?;
+
+        Ok(site)
+    }
+
+    /// Build the site again after files changed.
+    ///
+    /// If only layout files or included files changed, only the pages that
+    /// use them are rendered and written. Otherwise, the whole site is built.
+    /// The whole site is also built if commands are run before builds, since
+    /// they may change any input file.
+    pub(super) fn rebuild(
+        &mut self,
+        config: &Config,
+        changed_paths: &[PathBuf],
+    ) -> Result<(), Error> {
+        let Some(pages) = self
+            .affected_pages(config, changed_paths)
+            .filter(|_| config.run_before_build.is_empty())
+        else {
+            self.build(config)?;
+            return Ok(());
+        };
+
+        tracing::info!("Rendering {} pages using changed files", pages.len());
+
+        let (_, dependencies) = build_with(config, &Timings::default(), Some(&pages), |_| Ok(()))?;
+
+        // Files used by a page may have changed (e.g. a new `include`)
+        if let Some(previous) = self.dependencies.as_mut() {
+            previous.extend(dependencies);
+        }
+
+        crate::hooks::run_after_build(config)?;
+
+        Ok(())
+    }
+
+    /// Return the files included in pages (e.g. by the `code` directive),
+    /// known after a first build.
+    pub(super) fn included_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = self
+            .dependencies
+            .iter()
+            .flat_map(|dependencies| dependencies.includes.paths())
+            .collect();
+
+        paths.sort();
+        paths
+    }
+
+    /// Return the input paths of the pages that use changed layout files or
+    /// included files.
+    ///
+    /// Returns `None` if other files changed, or if the files used by pages
+    /// are unknown.
+    fn affected_pages(
+        &self,
+        config: &Config,
+        changed_paths: &[PathBuf],
+    ) -> Option<HashSet<PathBuf>> {
+        let dependencies = self.dependencies.as_ref()?;
+
+        let mut templates = HashSet::new();
+        let mut includes = HashSet::new();
+
+        for path in changed_paths {
+            if self::directives::is_included_path(path, config)
+                || dependencies.includes.contains(path)
+            {
+                includes.insert(path.to_owned());
+                continue;
+            }
+
+            let template = config
+                .layouts_dir
+                .as_ref()
+                .and_then(|layouts_dir| path.strip_prefix(layouts_dir).ok())
+                .and_then(self::layouts::template_name)?;
+
+            templates.insert(template);
+        }
+
+        let mut pages = dependencies.templates.pages_using(&templates);
+        pages.extend(dependencies.includes.pages_using(&includes));
+
+        Some(pages)
+    }
+}
+
+/// Build the site, and measure the weight of each page and its assets.
+pub(super) fn report(config: &Config) -> Result<Vec<PageReport>, Error> {
+    let mut weights = self::page_weight::Weights::new(config);
+
+    build_with(config, &Timings::default(), None, |entry| {
+        weights.add_entry(entry)
+    })?;
+
+    Ok(weights.into_reports())
+}
+
+/// Build the site, and measure the duration of each build task.
+pub(super) fn bench(config: &Config) -> Result<Vec<(&'static str, Duration)>, Error> {
+    let timings = Timings::default();
+
+    build_with(config, &timings, None, |_| Ok(()))?;
+
+    Ok(timings.into_durations())
+}
+
+/// Build the site, and pass each output [`Entry`] to a function.
+///
+/// If `selection` is specified, only the pages with these input paths are
+/// rendered and written. This function returns the built site and the files
+/// used by each page.
+fn build_with<F>(
+    config: &Config,
+    timings: &Timings,
+    selection: Option<&HashSet<PathBuf>>,
+    mut inspect: F,
+) -> Result<(Site, Dependencies), Error>
+where
+    F: FnMut(&Entry) -> Result<(), Error>,
+{
     let start_time = std::time::Instant::now();
 
+    let mut num_output_files: usize = 0;
+
+    let mut pages = Vec::new();
+
+    let dependencies = process(config, timings, selection, true, |entry| {
+        // Write output files
+        let entry = if config.output_dir.is_some() {
+            timings.measure("write", || -> Result<_, Error> {
+                let entry = self::write_file::write_entry(entry, config)?;
+                num_output_files += 1;
+
+                // Write precompressed files
+                num_output_files += self::precompress::write_entry(&entry, config)?;
+
+                Ok(entry)
+            })?
+        } else {
+            entry
+        };
+
+        inspect(&entry)?;
+
+        // Keep pages for queries
+        if entry.format == "html" {
+            let input_path = entry.input_path_buf();
+            let data = entry.data.unwrap_or_default();
+
+            pages.push(Page {
+                url: entry.url,
+                title: data.title.to_owned(),
+                date: data.date.to_owned(),
+                content: entry.content.unwrap_or_default(),
+                data: serde_json::to_value(data).map_err(|error| Error::CollectPages {
+                    input_path,
+                    source: error.into(),
+                })?,
+            });
+        }
+
+        Ok(())
+    })?;
+
+    let duration = start_time.elapsed().as_secs_f64();
+
+    tracing::info!(
+        "Wrote {} files in {:.2} seconds",
+        num_output_files,
+        duration
+    );
+
+    Ok((Site::new(pages), dependencies))
+}
+
+/// Build the site without writing files, and check the generated pages.
+pub(super) fn check(config: &Config, checks: &Checks) -> Result<Vec<Diagnostic>, Error> {
+    let mut diagnostics = Vec::new();
+
+    // Pages kept for checks that require the whole site
+    let mut pages = Vec::new();
+
+    process(config, &Timings::default(), None, true, |entry| {
+        if entry.format != "html" {
+            return Ok(());
+        }
+        if checks.a11y {
+            diagnostics.extend(self::a11y::check_entry(&entry)?);
+        }
+        if checks.fragments {
+            pages.push(entry);
+        }
+        Ok(())
+    })?;
+
+    if checks.fragments {
+        diagnostics.extend(self::fragments::check_entries(&pages, config)?);
+    }
+
+    Ok(diagnostics)
+}
+
+/// Scan the site without rendering layouts nor writing files, and return the
+/// output entries.
+pub(super) fn list(config: &Config) -> Result<Vec<ListEntry>, Error> {
+    let mut list_entries = Vec::new();
+
+    process(config, &Timings::default(), None, false, |entry| {
+        let input_path = entry.input_path_buf();
+        let data = entry.data.unwrap_or_default();
+        let title = data.title.to_owned();
+
+        let data = serde_json::to_value(data).map_err(|error| Error::CollectPages {
+            input_path: input_path.to_owned(),
+            source: error.into(),
+        })?;
+
+        list_entries.push(ListEntry {
+            url: entry.url,
+            format: entry.format,
+            input_path,
+            title,
+            data,
+        });
+
+        Ok(())
+    })?;
+
+    Ok(list_entries)
+}
+
+/// Run the build tasks, and pass each resulting [`Entry`] to a function.
+///
+/// The duration of each build task is added to `timings`. If `selection` is
+/// specified, only the pages with these input paths are rendered and passed to
+/// the function. If `render` is `false`, layouts are not rendered. This
+/// function returns the templates used by each rendered page, and the files
+/// included in each page.
+///
+/// If `config.keep_going` is enabled, entries that fail are skipped, so that
+/// other pages are still built, and their errors are reported at the end.
+fn process<F>(
+    config: &Config,
+    timings: &Timings,
+    selection: Option<&HashSet<PathBuf>>,
+    render: bool,
+    mut f: F,
+) -> Result<Dependencies, Error>
+where
+    F: FnMut(Entry) -> Result<(), Error>,
+{
     let ignore_matcher = self::ignore::Matcher::new(config)?;
 
-    let markdown_parser = self::markdown::Parser::new(config);
+    let markdown_parser = timings.measure("setup", || self::markdown::Parser::new(config))?;
+    let markdown_parser = Arc::new(markdown_parser);
 
     let scss_compiler = self::scss::Compiler::new();
 
-    let layout_engine = config
-        .layouts_dir
-        .is_some()
-        .then(|| self::layouts::Engine::new(config))
-        .transpose()?;
+    let layout_engine = timings.measure("setup", || {
+        (config.layouts_dir.is_some() || self::layouts::theme_layouts_dir(config).is_some())
+            .then(|| self::layouts::Engine::new(config, markdown_parser.clone()))
+            .transpose()
+    })?;
 
     let html_minifier = self::minify_html::Minifier::new();
 
-    let global_data = global_data::read(config)?;
+    let token_replacer = timings.measure("setup", || self::tokens::Replacer::new(config))?;
 
-    let mut num_output_files: usize = 0;
+    let directive_expander = self::directives::Expander::new(config);
+
+    // Errors of the entries skipped with `keep_going`
+    let failed_entries = RefCell::new(Vec::new());
+
+    let skip_failed = |entry: Result<Entry, Error>| match entry {
+        Err(error) if config.keep_going => {
+            failed_entries.borrow_mut().push(error);
+            None
+        },
+        entry => Some(entry),
+    };
+
+    // Check whether an entry must be rendered and output
+    let is_selected = |entry: &Entry| match selection {
+        Some(selection) => entry
+            .input_path()
+            .is_some_and(|input_path| selection.contains(input_path)),
+        None => true,
+    };
+
+    let global_data = timings.measure("global_data", || global_data::read(config))?;
+
+    let analytics_snippet = self::analytics::create_snippet(config)?;
+
+    let import_map_snippet = self::import_map::create_snippet(config);
 
     debug_assert!(config.input_dir.is_absolute());
 
-    let entries = WalkDir::new(&config.input_dir)
-        .into_iter()
-        .filter_entry(|entry| {
-            // Skip hidden and ignored files and directories
-            entry.depth() == 0
-                || (entry
-                    .file_name()
-                    .to_str()
-                    .map(|file_name| !file_name.starts_with(".") && !file_name.starts_with("_"))
-                    .unwrap_or(false)
-                    && !config.input_ignore_paths.contains(&entry.path().to_owned())
-                    && !ignore_matcher
-                        .is_match(entry.path().strip_prefix(&config.input_dir).unwrap()))
-        })
-        .filter_map(|result| {
-            // Ignore errors (e.g. permission denied)
-            result.ok()
-        })
-        .filter(|entry| {
-            // Keep only files, ignore directories
-            entry.file_type().is_file()
+    let ignore_matcher = &ignore_matcher;
+
+    // The policy is checked when validating the configuration
+    let symlink_policy = config.symlinks.parse::<SymlinkPolicy>().unwrap_or_default();
+
+    // Files of the theme are overridden by files of the site, and files of
+    // additional input directories by files of the previous ones
+    let entries = config
+        .input_dirs()
+        .flat_map(|input_dir| {
+            // Sort files, so that the order of pages (e.g. in sitemaps) does not depend on
+            // the file system
+            WalkDir::new(input_dir)
+                .follow_links(symlink_policy == SymlinkPolicy::Follow)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_entry(move |entry| {
+                    // Skip hidden and ignored files and directories
+                    entry.depth() == 0
+                        || (entry
+                            .file_name()
+                            .to_str()
+                            .map(|file_name| {
+                                !file_name.starts_with(".") && !file_name.starts_with("_")
+                            })
+                            .unwrap_or(false)
+                            && !config.input_ignore_paths.contains(&entry.path().to_owned())
+                            && !ignore_matcher
+                                .is_match(entry.path().strip_prefix(input_dir).unwrap()))
+                })
+                .filter_map(|result| {
+                    // Report symbolic link cycles, ignore other errors (e.g. permission denied)
+                    if let Some(ancestor) = result
+                        .as_ref()
+                        .err()
+                        .and_then(|error| error.loop_ancestor())
+                    {
+                        tracing::warn!("Skipping symbolic link to its ancestor {:?}", ancestor);
+                    }
+                    result.ok()
+                })
+                .filter(move |entry| {
+                    // Keep only files, ignore directories, and keep symbolic links to report
+                    entry.file_type().is_file()
+                        || (symlink_policy == SymlinkPolicy::Error && entry.path_is_symlink())
+                })
+                .filter(move |entry| {
+                    // Skip files that exist in an input directory of higher precedence
+                    let path = entry.path().strip_prefix(input_dir).unwrap();
+                    config
+                        .input_dirs()
+                        .take_while(|dir| *dir != input_dir)
+                        .all(|dir| !dir.join(path).exists())
+                })
+                .map(move |entry| (input_dir, entry))
         })
-        .map(|entry| {
+        .map(|(input_dir, entry)| {
+            if symlink_policy == SymlinkPolicy::Error && entry.path_is_symlink() {
+                return Err(Error::ReadInput {
+                    input_path: Some(entry.path().to_owned()),
+                    source: anyhow::anyhow!(
+                        "Symbolic links are not allowed (symlinks = \"error\")"
+                    ),
+                });
+            }
+
             // Create build `Entry` from walkdir's `DirEntry`
             let path = entry.path();
             let file_name = PathBuf::from(entry.file_name());
 
-            // `strip_prefix()` should not fail since `config.input_dir` is the base path
-            // and normalized
+            // `strip_prefix()` should not fail since `input_dir` is the base path and
+            // normalized
             let url = path
-                .strip_prefix(&config.input_dir)
+                .strip_prefix(input_dir)
                 .unwrap()
-                .components()
-                .fold(String::new(), |mut url, component| {
-                    url.push('/');
-                    url.push_str(component.as_os_str().to_str().unwrap());
-                    url
-                });
+                .to_url_path()
+                .ok_or_else(|| Error::ReadInput {
+                    input_path: Some(path.to_owned()),
+                    source: anyhow::anyhow!("Path is not valid UTF-8"),
+                })?;
 
             // Determine the format from the file extension
             let format = file_name
@@ -217,108 +662,422 @@ pub(super) fn build(config: &Config) -> Result<(), Error> {
         })
         .map(|entry| {
             // Read content
-            entry.and_then(|entry| match entry.format.as_str() {
-                "css" | "html" | "js" | "json" | "md" | "scss" | "toml" | "ts" | "xml" | "yaml" => {
-                    self::read_file::read_entry(entry)
-                },
-                // Other files will be copied directly
-                _ => Ok(entry),
+            entry.and_then(|entry| {
+                timings.measure("read", || match entry.format.as_str() {
+                    "css" | "html" | "js" | "json" | "jsx" | "md" | "scss" | "toml" | "ts"
+                    | "tsx" | "xml" | "yaml" => self::read_file::read_entry(entry),
+                    // SVG images are read only to be minified
+                    "svg" if config.minify => self::read_file::read_entry(entry),
+                    // Other files will be copied directly
+                    _ => Ok(entry),
+                })
             })
         })
         .map(|entry| {
             // Parse metadata
             entry.and_then(|entry| match entry.format.as_str() {
-                "html" | "md" => self::front_matter::parse_entry(entry),
-                "json" | "toml" | "yaml" => self::data_cascade::parse_entry(entry),
+                "html" | "md" => {
+                    timings.measure("front_matter", || self::front_matter::parse_entry(entry))
+                },
+                "json" | "toml" | "yaml" => {
+                    timings.measure("data_cascade", || self::data_cascade::parse_entry(entry))
+                },
                 _ => Ok(entry),
             })
         });
 
+    // Generate pages from data
+    let generated_entries = timings.measure("generate_pages", || {
+        self::generate_pages::generate_entries(config, &global_data)
+    })?;
+
+    // Generate blogroll
+    let blogroll_entries = timings.measure("blogroll", || {
+        self::blogroll::create_entries(config, &global_data)
+    })?;
+
+    let entries = entries.chain(
+        generated_entries
+            .into_iter()
+            .chain(blogroll_entries)
+            .map(Ok),
+    );
+
     // Apply data cascade
-    let entries = self::data_cascade::cascade_entries(entries)?;
+    let entries = timings.measure("data_cascade", || {
+        self::data_cascade::cascade_entries(entries.filter_map(&skip_failed))
+    })?;
+
+    let entries = entries.map(|entry| {
+        // Compute default front matter fields
+        entry.and_then(|entry| match entry.format.as_str() {
+            "html" | "md" => timings.measure("front_matter_defaults", || {
+                self::front_matter_defaults::apply_entry(entry, config)
+            }),
+            _ => Ok(entry),
+        })
+    });
+
+    // Validate front matters
+    let entries = timings.measure("front_matter_schema", || {
+        self::front_matter_schema::validate_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Read git history
+    let git_history = timings.measure("git", || self::git::read_history(config));
 
     let entries = entries
+        .map(|entry| {
+            // Read dates and contributors from git
+            entry.map(|entry| match entry.format.as_str() {
+                "html" | "md" => {
+                    timings.measure("git", || self::git::apply_entry(entry, &git_history))
+                },
+                _ => entry,
+            })
+        })
         .map(|entry| {
             // Normalize URLs
             entry.and_then(|entry| match entry.format.as_str() {
-                "html" | "md" => self::url::normalize_entry(entry),
+                "html" | "md" => timings.measure("url", || self::url::normalize_entry(entry)),
+                _ => Ok(entry),
+            })
+        })
+        .map(|entry| {
+            // Apply collection settings
+            entry.map(|entry| match entry.format.as_str() {
+                "html" | "md" => timings.measure("collections", || {
+                    self::collections::apply_entry(entry, config)
+                }),
+                _ => entry,
+            })
+        })
+        .map(|entry| {
+            // Expand directives (e.g. includes)
+            entry.and_then(|entry| match entry.format.as_str() {
+                "html" | "md" => {
+                    timings.measure("directives", || directive_expander.expand_entry(entry))
+                },
                 _ => Ok(entry),
             })
         })
         .map(|entry| {
             // Parse/compile Markdown/SCSS/TypeScript
             entry.and_then(|entry| match entry.format.as_str() {
-                "md" => markdown_parser.parse_entry(entry),
-                "scss" => scss_compiler.compile_entry(entry),
-                "ts" | "tsx" => self::typescript::compile_entry(entry),
+                "md" => timings.measure("markdown", || {
+                    markdown_parser
+                        .parse_entry(entry)
+                        .and_then(|entry| self::sanitize::sanitize_entry(entry, config))
+                }),
+                "scss" => timings.measure("scss", || scss_compiler.compile_entry(entry)),
+                "jsx" | "ts" | "tsx" => timings.measure("typescript", || {
+                    self::typescript::compile_entry(entry, config)
+                }),
                 _ => Ok(entry),
             })
         });
 
     // Bundle entries
-    let entries = self::contents::bundle_entries(entries)?;
+    let entries = timings.measure("contents", || {
+        self::contents::bundle_entries(entries.filter_map(&skip_failed))
+    })?;
 
     // Group entries using taxonomies
-    let (entries, global_data) = self::taxonomies::group_entries(entries, config, global_data)?;
+    let (entries, global_data) = timings.measure("taxonomies", || {
+        self::taxonomies::group_entries(entries.filter_map(&skip_failed), config, global_data)
+    })?;
+
+    // Group entries into collections
+    let (entries, global_data) = timings.measure("collections", || {
+        self::collections::group_entries(entries.filter_map(&skip_failed), config, global_data)
+    })?;
+
+    // List documents with their metadata
+    let (entries, global_data) = timings.measure("documents", || {
+        self::documents::list_entries(entries.filter_map(&skip_failed), config, global_data)
+    })?;
+
+    // List events and generate the calendar
+    let (entries, global_data) = timings.measure("events", || {
+        self::events::list_entries(entries.filter_map(&skip_failed), config, global_data)
+    })?;
+
+    // Generate JSON content API
+    let entries = timings.measure("api", || {
+        self::api::create_entries(entries.filter_map(&skip_failed), config, &global_data)
+    })?;
+
+    // Generate ActivityPub documents
+    let entries = timings.measure("activitypub", || {
+        self::activitypub::create_entries(entries.filter_map(&skip_failed), config)
+    })?;
 
     // Generate navigation tree
-    let entries = self::navigation::create_navigation_entries(entries, config)?;
+    let (entries, navigation) = timings.measure("navigation", || {
+        self::navigation::create_navigation_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Compute breadcrumb trails
+    let (entries, breadcrumbs) = timings.measure("breadcrumbs", || {
+        self::breadcrumbs::create_breadcrumbs(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Copy files located outside of the input directory
+    let copied_entries =
+        timings.measure("copy_paths", || self::copy_paths::create_entries(config))?;
+
+    let entries = entries.chain(copied_entries.into_iter().map(Ok));
+
+    // Copy pages to be rendered as emails
+    let entries = timings.measure("newsletter", || {
+        self::newsletter::create_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Detect URL collisions, and map input files to URLs for the `url_for` and
+    // `asset_url` layout functions
+    let entries = timings.measure("url", || -> Result<_, Error> {
+        let entries: Vec<_> = entries.filter_map(&skip_failed).collect::<Result<_, _>>()?;
+        self::url::check_collisions(&entries)?;
+        if let Some(layout_engine) = layout_engine.as_ref() {
+            layout_engine.set_urls(self::url::map_urls(&entries));
+        }
+        Ok(entries.into_iter().map(Ok::<_, Error>))
+    })?;
+
+    let entries = entries.map(|entry| {
+        // Render layouts
+        if let Some(layout_engine) = layout_engine.as_ref() {
+            entry.and_then(|entry| match entry.format.as_str() {
+                "html" if render && is_selected(&entry) => timings.measure("layouts", || {
+                    layout_engine.render_entry(
+                        entry,
+                        &global_data,
+                        &breadcrumbs,
+                        navigation.as_ref(),
+                    )
+                }),
+                _ => Ok(entry),
+            })
+        } else {
+            entry
+        }
+    });
+
+    // Download remote images
+    let entries = timings.measure("images", || {
+        self::images::localize_entries(entries.filter_map(&skip_failed), config)
+    })?;
 
     let entries = entries
         .map(|entry| {
-            // Render layouts
-            if let Some(layout_engine) = layout_engine.as_ref() {
-                entry.and_then(|entry| match entry.format.as_str() {
-                    "html" => layout_engine.render_entry(entry, &global_data),
-                    _ => Ok(entry),
-                })
-            } else {
-                entry
-            }
+            // Add image attributes
+            entry.and_then(|entry| match entry.format.as_str() {
+                "html" => timings.measure("images", || self::images::rewrite_entry(entry, config)),
+                _ => Ok(entry),
+            })
+        })
+        .map(|entry| {
+            // Decorate external links
+            entry.and_then(|entry| match entry.format.as_str() {
+                "html" => timings.measure("external_links", || {
+                    self::external_links::decorate_entry(entry, config)
+                }),
+                _ => Ok(entry),
+            })
+        })
+        .map(|entry| {
+            // Insert analytics snippet
+            entry.and_then(|entry| match entry.format.as_str() {
+                "html" => timings.measure("analytics", || {
+                    self::analytics::insert_entry(entry, analytics_snippet.as_deref())
+                }),
+                _ => Ok(entry),
+            })
+        })
+        .map(|entry| {
+            // Insert import map
+            entry.and_then(|entry| match entry.format.as_str() {
+                "html" => timings.measure("import_map", || {
+                    self::import_map::insert_entry(entry, import_map_snippet.as_deref())
+                }),
+                _ => Ok(entry),
+            })
+        })
+        .map(|entry| {
+            // Link color scheme stylesheet
+            entry.and_then(|entry| match entry.format.as_str() {
+                "html" => timings.measure("color_scheme", || {
+                    self::color_scheme::link_stylesheet_entry(entry, config)
+                }),
+                _ => Ok(entry),
+            })
+        })
+        .map(|entry| {
+            // Link syntax highlight stylesheets
+            entry.and_then(|entry| match entry.format.as_str() {
+                "html" => timings.measure("syntax_highlight", || {
+                    self::syntax_highlight::link_stylesheet_entry(entry, config)
+                }),
+                _ => Ok(entry),
+            })
         })
-        .chain(self::syntax_highlight::create_stylesheet_entries(config));
+        .chain(timings.measure("syntax_highlight", || {
+            self::syntax_highlight::create_stylesheet_entries(config)
+        }))
+        .chain(timings.measure("color_scheme", || {
+            self::color_scheme::create_entries(config)
+        }));
 
     // Rewrite URLs
-    let entries = self::url::rewrite_url_entries(entries, config)?;
+    let entries = timings.measure("url", || {
+        self::url::rewrite_url_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Generate Tailwind CSS stylesheets from rendered pages
+    let entries = timings.measure("tailwind", || {
+        self::tailwind::compile_entries(entries.filter_map(&skip_failed), config, render)
+    })?;
+
+    // Self-host fonts, subset to the characters of rendered pages
+    let entries = timings.measure("fonts", || {
+        self::fonts::create_entries(entries.filter_map(&skip_failed), config, render)
+    })?;
+
+    // Insert preload hints of critical assets
+    let entries = timings.measure("preload", || {
+        self::preload::insert_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Extract fragments of pages
+    let entries = timings.measure("fragments", || {
+        self::html_fragments::create_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Detect unused assets
+    let entries = timings.measure("unused_assets", || {
+        self::unused_assets::detect_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Render email versions of pages
+    let entries = timings.measure("newsletter", || {
+        self::newsletter::render_entries(entries.filter_map(&skip_failed), config)
+    })?;
 
     // Generate feeds
-    let entries = self::feed::create_feeds_entries(entries, config)?;
+    let entries = timings.measure("feed", || {
+        self::feed::create_feeds_entries(entries.filter_map(&skip_failed), config)
+    })?;
 
     // Generate a sitemap
-    self::sitemap::create_sitemap_entries(entries, config)?
+    let entries = timings.measure("sitemap", || {
+        self::sitemap::create_sitemap_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Make URLs of feeds, sitemaps and meta tags absolute
+    let entries = timings
+        .measure("absolute_urls", || {
+            self::absolute_urls::rewrite_entries(entries.filter_map(&skip_failed), config)
+        })?
+        .map(|entry| {
+            let Some(token_replacer) = token_replacer.as_ref() else {
+                return entry;
+            };
+            // Replace tokens
+            entry
+                .and_then(|entry| timings.measure("tokens", || token_replacer.replace_entry(entry)))
+        })
         .map(|entry| {
             if !config.minify {
                 return entry;
             }
             // Minify CSS/HTML/JS
-            entry.and_then(|entry| match entry.format.as_str() {
-                "css" => self::minify_css::minify_entry(entry),
-                "html" => html_minifier.minify_entry(entry),
-                "js" => self::minify_js::minify_entry(entry),
-                "json" => self::minify_json::minify_entry(entry),
-                "xml" => self::minify_xml::minify_entry(entry),
-                _ => Ok(entry),
+            entry.and_then(|entry| {
+                timings.measure("minify", || match entry.format.as_str() {
+                    "css" => self::minify_css::minify_entry(entry),
+                    "html" => html_minifier.minify_entry(entry),
+                    "js" => self::minify_js::minify_entry(entry),
+                    "json" => self::minify_json::minify_entry(entry),
+                    "svg" => self::minify_svg::minify_entry(entry, config),
+                    "xml" => self::minify_xml::minify_entry(entry),
+                    _ => Ok(entry),
+                })
             })
-        })
+        });
+
+    // Add subresource integrity hashes
+    let entries = timings.measure("integrity", || {
+        self::integrity::add_integrity_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Generate Content Security Policies
+    let (entries, header_rules) = timings.measure("csp", || {
+        self::csp::create_csp_entries(entries.filter_map(&skip_failed), config)
+    })?;
+
+    // Generate configuration files of hosting providers
+    timings
+        .measure("hosting", || {
+            self::hosting::create_hosting_entries(entries, header_rules, config)
+        })?
+        .filter_map(&skip_failed)
         .try_for_each(|entry| {
-            // Write output files
             entry.and_then(|entry| {
-                tracing::debug!("{:#?}", entry);
-                if config.output_dir.is_some() {
-                    self::write_file::write_entry(entry, config)?;
-                    num_output_files += 1;
+                if !is_selected(&entry) {
+                    return Ok(());
                 }
-                Ok(())
+                tracing::debug!("{:#?}", entry);
+                f(entry)
             })
         })?;
 
-    let duration = start_time.elapsed().as_secs_f64();
+    let failed_entries = failed_entries.into_inner();
 
-    tracing::info!(
-        "Wrote {} files in {:.2} seconds",
-        num_output_files,
-        duration
-    );
+    if !failed_entries.is_empty() {
+        report_failed_entries(&failed_entries, config);
+        return Err(Error::KeepGoing {
+            count: failed_entries.len(),
+        });
+    }
+
+    Ok(Dependencies {
+        templates: layout_engine
+            .map(self::layouts::Engine::into_dependencies)
+            .unwrap_or_default(),
+        includes: directive_expander.into_dependencies(),
+    })
+}
+
+/// Report the errors of failed entries, grouped by input file.
+fn report_failed_entries(errors: &[Error], config: &Config) {
+    let mut errors_by_path: BTreeMap<Option<&Path>, Vec<&Error>> = BTreeMap::new();
+
+    for error in errors {
+        errors_by_path
+            .entry(error.input_path())
+            .or_default()
+            .push(error);
+    }
 
-    Ok(())
+    for (input_path, errors) in errors_by_path {
+        match input_path {
+            Some(input_path) => tracing::error!("{} in {:?}", errors_count(&errors), input_path),
+            None => tracing::error!("{} in generated entries", errors_count(&errors)),
+        }
+
+        for error in errors {
+            tracing::error!("  {}", error.full_message());
+
+            if config.github_annotations {
+                crate::annotations::annotate_error(error, config);
+            }
+        }
+    }
+}
+
+/// Format a number of errors (e.g. `1 error`, `2 errors`).
+fn errors_count(errors: &[&Error]) -> String {
+    match errors.len() {
+        1 => "1 error".to_owned(),
+        count => format!("{count} errors"),
+    }
 }
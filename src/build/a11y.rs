@@ -0,0 +1,260 @@
+//! Check accessibility of HTML pages.
+
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use super::{Entry, Error};
+use crate::check::Diagnostic;
+
+/// Selector of elements that must have an accessible name.
+const NAMED_SELECTOR: &str = "a[href], button";
+
+/// Selector of elements which text gives a name to [`NAMED_SELECTOR`].
+const NAMED_TEXT_SELECTOR: &str = "a[href], a[href] *, button, button *";
+
+/// Selector of headings.
+const HEADING_SELECTOR: &str = "h1, h2, h3, h4, h5, h6";
+
+/// State of the accessibility audit of a page.
+#[derive(Debug, Default)]
+struct State {
+    /// Whether the page has a non-empty `html[lang]` attribute.
+    has_lang: bool,
+
+    /// Identifiers found so far.
+    ids: HashSet<String>,
+
+    /// Level of the previous heading.
+    heading_level: Option<usize>,
+
+    /// Open elements that must have an accessible name, and whether a name
+    /// was found.
+    named_elements: Vec<(String, bool)>,
+
+    /// Problems found.
+    diagnostics: Vec<(&'static str, String)>,
+}
+
+impl State {
+    /// Mark open elements as having an accessible name.
+    fn set_named(&mut self) {
+        for (_, named) in self.named_elements.iter_mut() {
+            *named = true;
+        }
+    }
+
+    /// Report a problem.
+    fn report(&mut self, rule: &'static str, message: String) {
+        self.diagnostics.push((rule, message));
+    }
+}
+
+/// Check accessibility of a [`Entry`].
+///
+/// This function reports images without `alt` attribute, links and buttons
+/// without accessible name, duplicate `id` attributes, skipped heading levels
+/// (e.g. `<h4>` after `<h2>`) and missing `lang` attribute on `<html>`.
+pub(super) fn check_entry(entry: &Entry) -> Result<Vec<Diagnostic>, Error> {
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(Vec::new());
+    };
+
+    let state = Rc::new(RefCell::new(State::default()));
+
+    lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![
+            lol_html::element!("html", |element| {
+                let lang = element.get_attribute("lang").unwrap_or_default();
+                state.borrow_mut().has_lang = !lang.trim().is_empty();
+                Ok(())
+            }),
+            lol_html::element!("img", |element| {
+                let mut state = state.borrow_mut();
+                match element.get_attribute("alt") {
+                    Some(alt) if !alt.trim().is_empty() => state.set_named(),
+                    Some(_) => {},
+                    None => {
+                        let src = element.get_attribute("src").unwrap_or_default();
+                        state.report("img-alt", format!("Image {src:?} has no alt attribute"));
+                    },
+                }
+                Ok(())
+            }),
+            lol_html::element!("[id]", |element| {
+                let id = element.get_attribute("id").unwrap_or_default();
+                let mut state = state.borrow_mut();
+                if !state.ids.insert(id.to_owned()) {
+                    state.report("duplicate-id", format!("Duplicate id {id:?}"));
+                }
+                Ok(())
+            }),
+            lol_html::element!(HEADING_SELECTOR, |element| {
+                let level = heading_level(&element.tag_name()).unwrap_or_default();
+                let mut state = state.borrow_mut();
+                if let Some(previous_level) = state.heading_level {
+                    if level > previous_level + 1 {
+                        state.report(
+                            "heading-order",
+                            format!("Heading level jumps from h{previous_level} to h{level}"),
+                        );
+                    }
+                }
+                state.heading_level = Some(level);
+                Ok(())
+            }),
+            lol_html::element!(NAMED_SELECTOR, |element| {
+                let has_label = ["aria-label", "aria-labelledby", "title"]
+                    .into_iter()
+                    .filter_map(|name| element.get_attribute(name))
+                    .any(|value| !value.trim().is_empty());
+
+                let tag_name = element.tag_name();
+
+                state
+                    .borrow_mut()
+                    .named_elements
+                    .push((tag_name, has_label));
+
+                let Some(end_tag_handlers) = element.end_tag_handlers() else {
+                    return Ok(());
+                };
+
+                let state = Rc::clone(&state);
+
+                end_tag_handlers.push(Box::new(move |_| {
+                    let mut state = state.borrow_mut();
+                    if let Some((tag_name, false)) = state.named_elements.pop() {
+                        let (rule, message) = match tag_name.as_str() {
+                            "a" => ("empty-link", "Link has no accessible name"),
+                            _ => ("empty-button", "Button has no accessible name"),
+                        };
+                        state.report(rule, message.to_owned());
+                    }
+                    Ok(())
+                }));
+
+                Ok(())
+            }),
+            lol_html::text!(NAMED_TEXT_SELECTOR, |text| {
+                if !text.as_str().trim().is_empty() {
+                    state.borrow_mut().set_named();
+                }
+                Ok(())
+            }),
+        ],
+        ..lol_html::RewriteStrSettings::default()
+    })
+    .map_err(|error| Error::CheckAccessibility {
+        input_path: entry.input_path_buf(),
+        source: error.into(),
+    })?;
+
+    let mut state = state.borrow_mut();
+
+    if !state.has_lang {
+        state.report(
+            "html-lang",
+            "Page has no lang attribute on <html>".to_owned(),
+        );
+    }
+
+    Ok(state
+        .diagnostics
+        .drain(..)
+        .map(|(rule, message)| {
+            Diagnostic::new(rule, message, entry.url.to_owned(), entry.input_path_buf())
+        })
+        .collect())
+}
+
+/// Return the level of a heading tag name (e.g. `2` for `h2`).
+fn heading_level(tag_name: &str) -> Option<usize> {
+    tag_name
+        .strip_prefix(['h', 'H'])
+        .and_then(|level| level.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Entry;
+    use crate::check::Severity;
+
+    #[test]
+    fn check_entry() {
+        const CASES: [(&str, &[&str]); 8] = [
+            (
+                r#"<html lang="en"><h1>Title</h1><h2>Section</h2></html>"#,
+                &[],
+            ),
+            (r#"<html><p>Text</p></html>"#, &["html-lang"]),
+            (
+                r#"<html lang="en"><img src="a.png"><img src="b.png" alt=""></html>"#,
+                &["img-alt"],
+            ),
+            (
+                concat!(
+                    r#"<html lang="en"><a href="/"></a>"#,
+                    r#"<a href="/"><img src="a.png" alt="Home"></a></html>"#,
+                ),
+                &["empty-link"],
+            ),
+            (
+                r#"<html lang="en"><button> </button><button aria-label="Close"></button></html>"#,
+                &["empty-button"],
+            ),
+            (
+                r#"<html lang="en"><a href="/"><span>Home</span></a></html>"#,
+                &[],
+            ),
+            (
+                r#"<html lang="en"><p id="a"></p><p id="b"></p><p id="a"></p></html>"#,
+                &["duplicate-id"],
+            ),
+            (
+                r#"<html lang="en"><h1>Title</h1><h3>Section</h3><h2>Section</h2></html>"#,
+                &["heading-order"],
+            ),
+        ];
+
+        for (input, expected) in CASES {
+            let entry = Entry {
+                url: "/".to_owned(),
+                format: "html".to_owned(),
+                content: Some(input.to_owned()),
+                ..Default::default()
+            };
+
+            let result: Vec<_> = super::check_entry(&entry)
+                .unwrap()
+                .into_iter()
+                .map(|diagnostic| diagnostic.rule)
+                .collect();
+
+            assert_eq!(
+                result, expected,
+                "\ncheck_entry({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn check_entry_severity() {
+        let entry = Entry {
+            url: "/".to_owned(),
+            format: "html".to_owned(),
+            content: Some("<h2>A</h2><h4>B</h4><img src=\"a.png\">".to_owned()),
+            ..Default::default()
+        };
+
+        let result: Vec<_> = super::check_entry(&entry)
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.severity))
+            .collect();
+
+        assert_eq!(result, [
+            ("heading-order", Severity::Warning),
+            ("img-alt", Severity::Error),
+            ("html-lang", Severity::Warning),
+        ]);
+    }
+}
@@ -0,0 +1,364 @@
+//! Make URLs absolute where required.
+
+use std::collections::HashSet;
+
+use quick_xml::{
+    events::{BytesCData, BytesStart, BytesText, Event},
+    Reader, Writer,
+};
+
+use super::{Config, Entry, Error};
+use crate::util::url::resolve_url;
+
+/// Selector of HTML elements with URLs that must be absolute.
+const HTML_SELECTOR: &str = concat!(
+    "link[rel=\"canonical\"][href],",
+    "meta[property=\"og:url\"][content],",
+    "meta[property=\"og:image\"][content],",
+    "meta[property=\"og:image:url\"][content],",
+    "meta[property=\"og:audio\"][content],",
+    "meta[property=\"og:video\"][content],",
+    "meta[name=\"twitter:image\"][content]"
+);
+
+/// XML elements which text is a URL (e.g. `<id>` of Atom feeds, `<loc>` of
+/// sitemaps).
+const XML_URL_ELEMENTS: [&str; 5] = ["icon", "id", "loc", "logo", "uri"];
+
+/// XML attributes containing URLs (e.g. `<link href="...">` of Atom feeds).
+const XML_URL_ATTRIBUTES: [&str; 1] = ["href"];
+
+/// XML elements which text may be escaped HTML (e.g. `<content type="html">`
+/// of Atom feeds, `<description>` of RSS feeds).
+const XML_HTML_ELEMENTS: [&str; 4] = ["content", "description", "encoded", "summary"];
+
+/// HTML attributes containing URLs, in the escaped HTML of XML elements.
+const HTML_URL_ATTRIBUTES: [&str; 2] = ["href", "src"];
+
+/// Prepend `site_url` to root-relative URLs of feeds, sitemaps and `<meta>`
+/// tags of pages.
+///
+/// If `site_url` is not specified, root-relative URLs of feeds and sitemaps are
+/// reported, since feed readers and search engines cannot resolve them.
+pub(super) fn rewrite_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    // Feeds of the site and of collections, and the sitemap
+    let xml_urls: HashSet<&str> = config
+        .feeds
+        .iter()
+        .chain(
+            config
+                .collections
+                .iter()
+                .filter_map(|collection| collection.feed.as_ref()),
+        )
+        .map(|feed_config| feed_config.url.as_str())
+        .chain(config.sitemap.as_ref().map(|sitemap| sitemap.url.as_str()))
+        .collect();
+
+    let entries: Vec<Entry> = entries
+        .into_iter()
+        .map(|entry| {
+            let Some(content) = entry.content.as_ref() else {
+                return Ok(entry);
+            };
+
+            let is_xml = entry.format == "xml" && xml_urls.contains(entry.url.as_str());
+
+            let Some(site_url) = config.site_url.as_ref() else {
+                if is_xml {
+                    warn_relative_urls(&entry.url, content);
+                }
+                return Ok(entry);
+            };
+
+            let content = match entry.format.as_str() {
+                "html" => rewrite_html(content, site_url),
+                _ if is_xml => rewrite_xml(content, |url| absolute_url(site_url, url)),
+                _ => return Ok(entry),
+            }
+            .map_err(|error| Error::MakeAbsoluteUrls {
+                source: error.context(format!("While rewriting {:?}", entry.url)),
+            })?;
+
+            Ok(Entry {
+                content: Some(content),
+                ..entry
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Report the first root-relative URL of a XML document, if any.
+fn warn_relative_urls(url: &str, content: &str) {
+    let mut relative_url = None;
+
+    let _ = rewrite_xml(content, |href| {
+        if relative_url.is_none() && is_root_relative(href) {
+            relative_url = Some(href.to_owned());
+        }
+        None
+    });
+
+    if let Some(relative_url) = relative_url {
+        tracing::warn!(
+            "{:?} contains relative URLs (e.g. {:?}), set site_url to make them absolute",
+            url,
+            relative_url
+        );
+    }
+}
+
+/// Make the URLs of `<meta>` and canonical `<link>` elements absolute.
+fn rewrite_html(content: &str, site_url: &str) -> anyhow::Result<String> {
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!(HTML_SELECTOR, |element| {
+            let attribute = if element.tag_name() == "link" {
+                "href"
+            } else {
+                "content"
+            };
+
+            if let Some(url) = element
+                .get_attribute(attribute)
+                .and_then(|url| absolute_url(site_url, &url))
+            {
+                element.set_attribute(attribute, &url)?;
+            }
+
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(content)
+}
+
+/// Rewrite the URLs of a XML document (texts of [`XML_URL_ELEMENTS`], values
+/// of [`XML_URL_ATTRIBUTES`], and URLs of the escaped HTML of
+/// [`XML_HTML_ELEMENTS`]).
+///
+/// URLs are kept if `rewrite` returns `None`.
+fn rewrite_xml<F>(content: &str, mut rewrite: F) -> anyhow::Result<String>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut reader = Reader::from_str(content);
+    let mut writer = Writer::new(Vec::new());
+
+    // Determine whether the current text is the content of an URL element
+    let mut is_url_text = false;
+
+    // Determine whether the current text is escaped HTML
+    let mut is_html_text = false;
+
+    loop {
+        let event = match reader.read_event()? {
+            Event::Eof => break,
+            Event::Start(element) => {
+                is_url_text = XML_URL_ELEMENTS
+                    .iter()
+                    .any(|name| element.local_name().as_ref() == name.as_bytes());
+                is_html_text = is_html_element(&element)?;
+                Event::Start(rewrite_attributes(element, &mut rewrite)?)
+            },
+            Event::Empty(element) => Event::Empty(rewrite_attributes(element, &mut rewrite)?),
+            Event::Text(text) if is_url_text => {
+                let url = text.unescape()?;
+                match rewrite(url.trim()) {
+                    Some(url) => Event::Text(BytesText::new(&url).into_owned()),
+                    None => Event::Text(text),
+                }
+            },
+            Event::Text(text) if is_html_text => {
+                let html = rewrite_html_urls(&text.unescape()?, &mut rewrite)?;
+                Event::Text(BytesText::new(&html).into_owned())
+            },
+            Event::CData(text) if is_html_text => {
+                let html = String::from_utf8(text.into_inner().into_owned())?;
+                let html = rewrite_html_urls(&html, &mut rewrite)?;
+                Event::CData(BytesCData::new(html).into_owned())
+            },
+            Event::End(element) => {
+                is_url_text = false;
+                is_html_text = false;
+                Event::End(element)
+            },
+            event => event,
+        };
+
+        writer.write_event(event)?;
+    }
+
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Rewrite the URLs of the [`XML_URL_ATTRIBUTES`] of a XML element.
+fn rewrite_attributes<F>(element: BytesStart, rewrite: &mut F) -> anyhow::Result<BytesStart>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let name = String::from_utf8(element.name().as_ref().to_vec())?;
+    let mut result = BytesStart::new(name);
+
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        let key = String::from_utf8(attribute.key.as_ref().to_vec())?;
+        let value = attribute.unescape_value()?;
+
+        let value = if XML_URL_ATTRIBUTES.contains(&key.as_str()) {
+            rewrite(&value).map(Into::into).unwrap_or(value)
+        } else {
+            value
+        };
+
+        result.push_attribute((key.as_str(), value.as_ref()));
+    }
+
+    Ok(result)
+}
+
+/// Determine whether the text of a XML element is escaped HTML.
+///
+/// Texts of `<content>` and `<summary>` elements of Atom feeds are HTML only if
+/// their type is `html`.
+fn is_html_element(element: &BytesStart) -> anyhow::Result<bool> {
+    let name = element.local_name();
+
+    if !XML_HTML_ELEMENTS
+        .iter()
+        .any(|html_name| name.as_ref() == html_name.as_bytes())
+    {
+        return Ok(false);
+    }
+
+    if matches!(name.as_ref(), b"description" | b"encoded") {
+        return Ok(true);
+    }
+
+    Ok(element
+        .try_get_attribute("type")?
+        .is_some_and(|attribute| attribute.value.as_ref() == b"html"))
+}
+
+/// Rewrite the URLs of the [`HTML_URL_ATTRIBUTES`] of a HTML fragment.
+fn rewrite_html_urls<F>(content: &str, rewrite: &mut F) -> anyhow::Result<String>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("[href], [src]", |element| {
+            for attribute in HTML_URL_ATTRIBUTES {
+                if let Some(url) = element
+                    .get_attribute(attribute)
+                    .and_then(|url| rewrite(&url))
+                {
+                    element.set_attribute(attribute, &url)?;
+                }
+            }
+
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(content)
+}
+
+/// Determine whether a URL starts with `/` but not with `//`.
+fn is_root_relative(url: &str) -> bool {
+    url.starts_with('/') && !url.starts_with("//")
+}
+
+/// Prepend `site_url` to a root-relative URL.
+///
+/// Returns `None` if the URL is not root-relative (e.g. already absolute).
+fn absolute_url(site_url: &str, url: &str) -> Option<String> {
+    let url = url.trim();
+    is_root_relative(url).then(|| resolve_url(site_url, url))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn absolute_url() {
+        const CASES: [(&str, &str, Option<&str>); 5] = [
+            (
+                "https://example.com",
+                "/blog/post",
+                Some("https://example.com/blog/post"),
+            ),
+            (
+                "https://example.com/",
+                "/image.png?v=1",
+                Some("https://example.com/image.png?v=1"),
+            ),
+            ("https://example.com", "https://other.com/", None),
+            ("https://example.com", "//cdn.example.com/image.png", None),
+            ("https://example.com", "image.png", None),
+        ];
+
+        for (site_url, url, expected) in CASES {
+            let result = super::absolute_url(site_url, url);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\nabsolute_url({site_url:?}, {url:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rewrite_xml() {
+        let content = concat!(
+            "<feed><id>/feed.xml</id><entry><id>/post</id>",
+            "<link href=\"/post\" rel=\"alternate\"/><title>/title</title></entry></feed>"
+        );
+
+        let result =
+            super::rewrite_xml(content, |url| super::absolute_url("https://a.com", url)).unwrap();
+
+        assert_eq!(
+            result,
+            concat!(
+                "<feed><id>https://a.com/feed.xml</id><entry><id>https://a.com/post</id>",
+                "<link href=\"https://a.com/post\" rel=\"alternate\"/><title>/title</title>",
+                "</entry></feed>"
+            )
+        );
+    }
+
+    #[test]
+    fn rewrite_xml_html() {
+        let content = concat!(
+            "<feed><entry><content type=\"html\">&lt;a href=\"/post\"&gt;Post&lt;/a&gt;",
+            "&lt;img src=\"/image.png\"&gt;</content>",
+            "<summary>&lt;a href=\"/post\"&gt;</summary></entry></feed>",
+            "<rss><channel><item><description><![CDATA[<a href=\"/post\">Post</a>]]>",
+            "</description></item></channel></rss>"
+        );
+
+        let result =
+            super::rewrite_xml(content, |url| super::absolute_url("https://a.com", url)).unwrap();
+
+        assert_eq!(
+            result,
+            concat!(
+                "<feed><entry><content type=\"html\">&lt;a ",
+                "href=&quot;https://a.com/post&quot;&gt;Post&lt;/a&gt;",
+                "&lt;img src=&quot;https://a.com/image.png&quot;&gt;</content>",
+                "<summary>&lt;a href=\"/post\"&gt;</summary></entry></feed>",
+                "<rss><channel><item><description>",
+                "<![CDATA[<a href=\"https://a.com/post\">Post</a>]]>",
+                "</description></item></channel></rss>"
+            )
+        );
+    }
+}
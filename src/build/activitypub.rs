@@ -0,0 +1,238 @@
+//! Generate ActivityPub documents.
+
+use super::{Config, Entry, Error};
+use crate::util::{
+    date_time::{parse_offset, DateTime},
+    url::{site_base_url, Url},
+};
+
+/// JSON-LD context of ActivityStreams documents.
+const ACTIVITYSTREAMS_CONTEXT: &str = "https://www.w3.org/ns/activitystreams";
+
+/// JSON-LD context of the public key of actors.
+const SECURITY_CONTEXT: &str = "https://w3id.org/security/v1";
+
+/// Audience of public activities.
+const PUBLIC_AUDIENCE: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+/// URL of the WebFinger document.
+const WEBFINGER_URL: &str = "/.well-known/webfinger";
+
+/// Generate the WebFinger document, the actor, its inbox and its outbox.
+///
+/// The outbox contains a `Create` activity for each recent post, i.e. each
+/// page with a date, most recent first. Static hosting cannot answer WebFinger
+/// queries, so the same document is returned for any `resource`.
+///
+/// The generated files follow the [ActivityPub](https://www.w3.org/TR/activitypub/)
+/// and [WebFinger](https://www.rfc-editor.org/rfc/rfc7033) specifications.
+pub(super) fn create_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let Some(activitypub_config) = config.activitypub.as_ref() else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    // The time zone is checked when validating the configuration
+    let timezone = config
+        .timezone
+        .as_ref()
+        .and_then(|timezone| parse_offset(timezone).ok());
+
+    let site_url = site_base_url(config.site_url.as_deref(), &config.base_url).unwrap_or_default();
+    let absolute_url = |url: &str| format!("{site_url}{url}");
+
+    // The domain is checked when validating the configuration
+    let subject =
+        acct(&activitypub_config.username, &site_url).ok_or_else(|| Error::CreateActivityPub {
+            source: anyhow::anyhow!("Missing domain in URL {:?}", site_url),
+        })?;
+
+    let actor_id = absolute_url(&activitypub_config.actor_url);
+    let inbox_id = absolute_url(&activitypub_config.inbox_url);
+    let outbox_id = absolute_url(&activitypub_config.outbox_url);
+
+    // Posts
+    let mut posts: Vec<(DateTime, &Entry)> = entries
+        .iter()
+        .filter(|entry| {
+            entry.format == "html"
+                && activitypub_config.collection.as_ref().map_or(true, |name| {
+                    super::collections::collection_name(entry) == Some(name.as_str())
+                })
+        })
+        .filter_map(|entry| {
+            let date = entry.data.as_ref()?.date.as_ref()?;
+            Some((date, entry))
+        })
+        .map(|(date, entry)| -> anyhow::Result<_> {
+            Ok((DateTime::parse_in_timezone(date, timezone)?, entry))
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|error| Error::CreateActivityPub { source: error })?;
+
+    posts.sort_by(|x, y| y.0.cmp(&x.0).then_with(|| x.1.url.cmp(&y.1.url)));
+    posts.truncate(activitypub_config.limit);
+
+    let activities: Vec<serde_json::Value> = posts
+        .into_iter()
+        .map(|(date, entry)| {
+            let url = absolute_url(&entry.url);
+            let published = date.to_rfc3339();
+
+            serde_json::json!({
+                "id": format!("{url}#create"),
+                "type": "Create",
+                "actor": actor_id,
+                "published": published,
+                "to": [PUBLIC_AUDIENCE],
+                "object": {
+                    "id": url,
+                    "type": "Article",
+                    "name": entry.data.as_ref().and_then(|data| data.title.as_ref()),
+                    "content": entry.content,
+                    "url": url,
+                    "published": published,
+                    "attributedTo": actor_id,
+                    "to": [PUBLIC_AUDIENCE],
+                },
+            })
+        })
+        .collect();
+
+    let mut actor = serde_json::json!({
+        "@context": [ACTIVITYSTREAMS_CONTEXT, SECURITY_CONTEXT],
+        "id": actor_id,
+        "type": "Person",
+        "preferredUsername": activitypub_config.username,
+        "name": activitypub_config.name,
+        "summary": activitypub_config.summary,
+        "url": site_url,
+        "inbox": inbox_id,
+        "outbox": outbox_id,
+    });
+
+    if let Some(icon) = activitypub_config.icon.as_ref() {
+        actor["icon"] = serde_json::json!({ "type": "Image", "url": icon });
+    }
+
+    if let Some(public_key_pem) = activitypub_config.public_key_pem.as_ref() {
+        actor["publicKey"] = serde_json::json!({
+            "id": format!("{actor_id}#main-key"),
+            "owner": actor_id,
+            "publicKeyPem": public_key_pem,
+        });
+    }
+
+    let files = [
+        (
+            WEBFINGER_URL.to_owned(),
+            serde_json::json!({
+                "subject": subject,
+                "aliases": [actor_id],
+                "links": [
+                    {
+                        "rel": "self",
+                        "type": "application/activity+json",
+                        "href": actor_id,
+                    },
+                    {
+                        "rel": "http://webfinger.net/rel/profile-page",
+                        "type": "text/html",
+                        "href": site_url,
+                    },
+                ],
+            }),
+        ),
+        (activitypub_config.actor_url.to_owned(), actor),
+        (
+            activitypub_config.inbox_url.to_owned(),
+            serde_json::json!({
+                "@context": ACTIVITYSTREAMS_CONTEXT,
+                "id": inbox_id,
+                "type": "OrderedCollection",
+                "totalItems": 0,
+                "orderedItems": [],
+            }),
+        ),
+        (
+            activitypub_config.outbox_url.to_owned(),
+            serde_json::json!({
+                "@context": ACTIVITYSTREAMS_CONTEXT,
+                "id": outbox_id,
+                "type": "OrderedCollection",
+                "totalItems": activities.len(),
+                "orderedItems": activities,
+            }),
+        ),
+    ];
+
+    for (url, value) in files {
+        let content = if config.minify {
+            serde_json::to_string(&value)
+        } else {
+            serde_json::to_string_pretty(&value)
+        }
+        .map_err(|error| Error::CreateActivityPub {
+            source: error.into(),
+        })?;
+
+        entries.push(Entry {
+            url,
+            format: "json".to_owned(),
+            content: Some(content),
+            ..Default::default()
+        });
+    }
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Return the WebFinger subject of an actor (e.g. `acct:blog@example.com`).
+///
+/// Returns `None` if the URL of the site has no domain.
+fn acct(username: &str, site_url: &str) -> Option<String> {
+    let authority = Url::from(site_url).normalize().authority?;
+
+    if authority.host.is_empty() {
+        return None;
+    }
+
+    Some(match authority.port {
+        Some(port) => format!("acct:{}@{}:{}", username, authority.host, port),
+        None => format!("acct:{}@{}", username, authority.host),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn acct() {
+        const CASES: [(&str, &str, Option<&str>); 4] = [
+            ("blog", "https://example.com", Some("acct:blog@example.com")),
+            (
+                "me",
+                "https://Example.com/blog",
+                Some("acct:me@example.com"),
+            ),
+            (
+                "me",
+                "http://localhost:8000",
+                Some("acct:me@localhost:8000"),
+            ),
+            ("blog", "/blog", None),
+        ];
+
+        for (username, site_url, expected) in CASES {
+            let result = super::acct(username, site_url);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\nacct({username:?}, {site_url:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Insert analytics snippets.
+
+use super::{Config, Entry, Error};
+
+/// Create the analytics snippet specified in the configuration, if any.
+///
+/// The snippet is either the raw `snippet` of the configuration, or the
+/// tracking code of a known `provider` for the `site_id`.
+pub(super) fn create_snippet(config: &Config) -> Result<Option<String>, Error> {
+    let Some(analytics_config) = config.analytics.as_ref() else {
+        return Ok(None);
+    };
+
+    let snippet = match (
+        analytics_config.provider.as_deref(),
+        analytics_config.site_id.as_deref(),
+        analytics_config.snippet.as_ref(),
+    ) {
+        (None, _, Some(snippet)) => snippet.to_owned(),
+        (Some(provider), Some(site_id), None) => {
+            // Site identifiers are inserted in attributes and scripts as is
+            if !site_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || ['-', '.', '_'].contains(&c))
+            {
+                return Err(Error::CreateAnalyticsSnippet {
+                    source: anyhow::anyhow!("Invalid analytics.site_id {:?}", site_id),
+                });
+            }
+
+            provider_snippet(provider, site_id)
+                .ok_or_else(|| anyhow::anyhow!("Unknown analytics provider {:?}", provider))
+                .map_err(|error| Error::CreateAnalyticsSnippet { source: error })?
+        },
+        (Some(_), None, None) => {
+            return Err(Error::CreateAnalyticsSnippet {
+                source: anyhow::anyhow!("Missing analytics.site_id"),
+            });
+        },
+        _ => {
+            return Err(Error::CreateAnalyticsSnippet {
+                source: anyhow::anyhow!("Expected either analytics.provider or analytics.snippet"),
+            });
+        },
+    };
+
+    Ok(Some(snippet))
+}
+
+/// Insert the analytics snippet at the end of the `<head>` of a [`Entry`].
+pub(super) fn insert_entry(entry: Entry, snippet: Option<&str>) -> Result<Entry, Error> {
+    let Some(snippet) = snippet else {
+        return Ok(entry);
+    };
+
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(entry);
+    };
+
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("head", |element| {
+            element.append(snippet, lol_html::html_content::ContentType::Html);
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })
+    .map_err(|error| Error::InsertAnalytics {
+        input_path: entry.input_path_buf(),
+        source: error.into(),
+    })?;
+
+    Ok(Entry {
+        content: Some(content),
+        ..entry
+    })
+}
+
+/// Return the tracking code of a provider.
+fn provider_snippet(provider: &str, site_id: &str) -> Option<String> {
+    match provider {
+        "goatcounter" => Some(format!(
+            "<script data-goatcounter=\"https://{site_id}.goatcounter.com/count\" async \
+             src=\"https://gc.zgo.at/count.js\"></script>"
+        )),
+        "google" => Some(format!(
+            "<script async src=\"https://www.googletagmanager.com/gtag/js?id={site_id}\"></script>\
+             <script>window.dataLayer=window.dataLayer||[];\
+             function gtag(){{dataLayer.push(arguments);}}\
+             gtag('js',new Date());gtag('config','{site_id}');</script>"
+        )),
+        "plausible" => Some(format!(
+            "<script defer data-domain=\"{site_id}\" \
+             src=\"https://plausible.io/js/script.js\"></script>"
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{AnalyticsConfig, Config};
+
+    #[test]
+    fn create_snippet() {
+        let config =
+            |provider: Option<&str>, site_id: Option<&str>, snippet: Option<&str>| Config {
+                analytics: Some(AnalyticsConfig {
+                    provider: provider.map(str::to_owned),
+                    site_id: site_id.map(str::to_owned),
+                    snippet: snippet.map(str::to_owned),
+                }),
+                ..Default::default()
+            };
+
+        let result = super::create_snippet(&config(Some("plausible"), Some("example.com"), None))
+            .unwrap()
+            .unwrap();
+        assert!(result.contains("data-domain=\"example.com\""));
+
+        let result = super::create_snippet(&config(None, None, Some("<script></script>")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, "<script></script>");
+
+        assert!(super::create_snippet(&config(Some("unknown"), Some("a"), None)).is_err());
+        assert!(super::create_snippet(&config(Some("plausible"), None, None)).is_err());
+        assert!(super::create_snippet(&config(Some("plausible"), Some("a\"b"), None)).is_err());
+        assert!(super::create_snippet(&config(None, None, None)).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+//! Generate a JSON content API.
+
+use super::{Config, Entry, Error};
+use crate::util::slug::SlugStrategy;
+
+/// Generate JSON files mirroring the content of the site.
+///
+/// The files are located under `api.url_prefix` (e.g. `/api`):
+///
+/// - `index.json` links to the listings below;
+/// - `content/{url}/index.json` contains the `url`, the front matter `data` and
+///   the HTML `content` of each page;
+/// - `pages/{n}.json` lists all pages;
+/// - `collections/{name}/{n}.json` lists the pages of each collection;
+/// - `taxonomies/{taxonomy}.json` lists the terms of each taxonomy, and
+///   `taxonomies/{taxonomy}/{term}/{n}.json` the pages of each term.
+///
+/// Listings are paginated like collections: they contain the `items`, the
+/// `page` number, `num_pages`, `previous_url` and `next_url`. Each item
+/// contains the `url`, the front matter `data`, and the `api_url` of the page.
+pub(super) fn create_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+    global_data: &serde_json::Value,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let Some(api_config) = config.api.as_ref() else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    let url_prefix = api_config.url_prefix.trim_end_matches('/');
+
+    let slug_strategy = config
+        .slug_strategy
+        .parse::<SlugStrategy>()
+        .unwrap_or_default();
+
+    let mut files: Vec<(String, serde_json::Value)> = Vec::new();
+
+    // Pages
+    let mut pages: Vec<&Entry> = entries
+        .iter()
+        .filter(|entry| entry.format == "html")
+        .collect();
+    pages.sort_by(|x, y| x.url.cmp(&y.url));
+
+    let pages: Vec<serde_json::Value> = pages
+        .into_iter()
+        .map(|entry| {
+            serde_json::json!({
+                "url": entry.url,
+                "data": entry.data,
+                "content": entry.content,
+            })
+        })
+        .collect();
+
+    let items = pages.iter().map(|page| item(url_prefix, page)).collect();
+    let pages_url = paginate(
+        &mut files,
+        &format!("{url_prefix}/pages"),
+        items,
+        api_config.paginate,
+    );
+
+    for page in pages {
+        let url = content_url(url_prefix, page["url"].as_str().unwrap_or_default());
+        files.push((url, page));
+    }
+
+    // Collections
+    let mut collections = serde_json::Map::new();
+
+    if let Some(collection_pages) = global_data.get("collections").and_then(|v| v.as_object()) {
+        for (name, pages) in collection_pages {
+            let items = pages
+                .as_array()
+                .map(|pages| pages.iter().map(|page| item(url_prefix, page)).collect())
+                .unwrap_or_default();
+            let url = paginate(
+                &mut files,
+                &format!("{url_prefix}/collections/{name}"),
+                items,
+                api_config.paginate,
+            );
+            collections.insert(name.to_owned(), url.into());
+        }
+    }
+
+    // Taxonomies
+    let mut taxonomies = serde_json::Map::new();
+
+    if let Some(taxonomy_terms) = global_data.get("taxonomies").and_then(|v| v.as_object()) {
+        for (taxonomy, terms) in taxonomy_terms {
+            let mut terms: Vec<_> = terms
+                .as_object()
+                .map(|terms| terms.iter().collect())
+                .unwrap_or_default();
+            terms.sort_by(|x, y| x.0.cmp(y.0));
+
+            let terms: Vec<serde_json::Value> = terms
+                .into_iter()
+                .map(|(term, pages)| {
+                    let pages = pages.as_array().map(Vec::as_slice).unwrap_or_default();
+                    let items = pages.iter().map(|page| item(url_prefix, page)).collect();
+                    let url = paginate(
+                        &mut files,
+                        &format!(
+                            "{url_prefix}/taxonomies/{taxonomy}/{}",
+                            slug_strategy.slugify(term)
+                        ),
+                        items,
+                        api_config.paginate,
+                    );
+                    serde_json::json!({ "term": term, "count": pages.len(), "url": url })
+                })
+                .collect();
+
+            let url = format!("{url_prefix}/taxonomies/{taxonomy}.json");
+            files.push((url.to_owned(), terms.into()));
+            taxonomies.insert(taxonomy.to_owned(), url.into());
+        }
+    }
+
+    files.push((
+        format!("{url_prefix}/index.json"),
+        serde_json::json!({
+            "pages": pages_url,
+            "collections": collections,
+            "taxonomies": taxonomies,
+        }),
+    ));
+
+    for (url, value) in files {
+        let content = if config.minify {
+            serde_json::to_string(&value)
+        } else {
+            serde_json::to_string_pretty(&value)
+        }
+        .map_err(|error| Error::CreateApi {
+            source: error.into(),
+        })?;
+
+        entries.push(Entry {
+            url,
+            format: "json".to_owned(),
+            content: Some(content),
+            ..Default::default()
+        });
+    }
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Return the URL of the JSON file of a page.
+fn content_url(url_prefix: &str, url: &str) -> String {
+    match url.trim_matches('/') {
+        "" => format!("{url_prefix}/content/index.json"),
+        path => format!("{url_prefix}/content/{path}/index.json"),
+    }
+}
+
+/// Convert a page (with `url` and `data` keys) to an item of a listing.
+fn item(url_prefix: &str, page: &serde_json::Value) -> serde_json::Value {
+    let url = page["url"].as_str().unwrap_or_default();
+
+    serde_json::json!({
+        "url": url,
+        "data": page["data"],
+        "api_url": content_url(url_prefix, url),
+    })
+}
+
+/// Add the paginated listing files of items, and return the URL of the first
+/// one.
+fn paginate(
+    files: &mut Vec<(String, serde_json::Value)>,
+    url_prefix: &str,
+    items: Vec<serde_json::Value>,
+    paginate: usize,
+) -> String {
+    let chunks: Vec<_> = items.chunks(paginate).collect();
+    let num_pages = chunks.len().max(1);
+
+    let page_url = |page: usize| format!("{url_prefix}/{page}.json");
+
+    for page in 1..=num_pages {
+        files.push((
+            page_url(page),
+            serde_json::json!({
+                "items": chunks.get(page - 1).copied().unwrap_or_default(),
+                "page": page,
+                "num_pages": num_pages,
+                "previous_url": (page > 1).then(|| page_url(page - 1)),
+                "next_url": (page < num_pages).then(|| page_url(page + 1)),
+            }),
+        ));
+    }
+
+    page_url(1)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn content_url() {
+        const CASES: [(&str, &str, &str); 4] = [
+            ("/api", "/", "/api/content/index.json"),
+            ("/api", "/blog/hello", "/api/content/blog/hello/index.json"),
+            ("/api", "/blog/", "/api/content/blog/index.json"),
+            ("", "/about", "/content/about/index.json"),
+        ];
+
+        for (url_prefix, url, expected) in CASES {
+            let result = super::content_url(url_prefix, url);
+            assert_eq!(
+                result, expected,
+                "\ncontent_url({url_prefix:?}, {url:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Generate a blogroll.
+
+use quick_xml::{events::Event, se::Serializer, Reader};
+use serde::{Deserialize, Serialize};
+
+use super::{cache::Cache, Config, Entry, EntryData, Error};
+use crate::util::{feed::opml, html::escape, http};
+
+/// Preamble of the XML file.
+const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>";
+
+/// Feed listed in the blogroll.
+#[derive(Debug, Deserialize, Serialize)]
+struct Feed {
+    /// URL of the feed (RSS or Atom).
+    feed_url: String,
+
+    /// Title of the feed.
+    #[serde(default)]
+    title: Option<String>,
+
+    /// URL of the site.
+    #[serde(default)]
+    site_url: Option<String>,
+
+    /// Description of the feed.
+    #[serde(default)]
+    description: Option<String>,
+}
+
+/// Create the OPML file and the HTML page of the blogroll.
+///
+/// The feeds are read from global data. If `blogroll.fetch_titles` is set,
+/// feeds without title are downloaded to read their title. The HTML page is
+/// then processed like other pages (e.g. its layout is rendered).
+pub(super) fn create_entries(
+    config: &Config,
+    global_data: &serde_json::Value,
+) -> Result<Vec<Entry>, Error> {
+    let Some(blogroll_config) = config.blogroll.as_ref() else {
+        return Ok(Vec::new());
+    };
+
+    let data = global_data
+        .get(&blogroll_config.data_key)
+        .cloned()
+        .ok_or_else(|| Error::CreateBlogroll {
+            source: anyhow::anyhow!(
+                "Expected a list of feeds in global data {:?}",
+                blogroll_config.data_key
+            ),
+        })?;
+
+    let mut feeds: Vec<Feed> =
+        serde_json::from_value(data).map_err(|error| Error::CreateBlogroll {
+            source: error.into(),
+        })?;
+
+    if blogroll_config.fetch_titles {
+        let cache = Cache::new(config, "blogroll", "");
+
+        for feed in feeds.iter_mut().filter(|feed| feed.title.is_none()) {
+            feed.title = match cache.as_ref() {
+                Some(cache) => {
+                    cache.get_or_try_insert_with(&feed.feed_url, || fetch_title(&feed.feed_url))
+                },
+                None => fetch_title(&feed.feed_url),
+            };
+        }
+    }
+
+    let opml_entry = Entry {
+        url: blogroll_config.opml_url.to_owned(),
+        format: "xml".to_owned(),
+        content: Some(render_opml(&feeds, &blogroll_config.title, config.minify)?),
+        ..Default::default()
+    };
+
+    let mut extra = serde_json::Map::new();
+
+    if let Some(layout) = blogroll_config.layout.as_ref() {
+        extra.insert(
+            config.layouts.layout_key.to_owned(),
+            layout.to_owned().into(),
+        );
+    }
+
+    extra.insert(
+        "feeds".to_owned(),
+        serde_json::to_value(&feeds).map_err(|error| Error::CreateBlogroll {
+            source: error.into(),
+        })?,
+    );
+
+    let page_entry = Entry {
+        url: blogroll_config.url.to_owned(),
+        format: "html".to_owned(),
+        content: Some(render_html(&feeds)),
+        data: Some(EntryData {
+            title: Some(blogroll_config.title.to_owned()),
+            extra: extra.into(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    Ok(Vec::from([opml_entry, page_entry]))
+}
+
+/// Download a feed and return its title.
+///
+/// Returns `None` if the feed cannot be downloaded or has no title.
+fn fetch_title(url: &str) -> Option<String> {
+    tracing::info!("Fetching feed {:?}", url);
+
+    http::get(url)
+        .map_err(|error| tracing::warn!("Could not fetch feed {:?}: {}", url, error))
+        .ok()
+        .and_then(|xml| feed_title(&xml))
+}
+
+/// Return the title of a RSS or Atom feed.
+///
+/// The title of the feed is the first `<title>` element, which precedes the
+/// titles of the items.
+fn feed_title(xml: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut is_inside = false;
+    let mut title = String::new();
+
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(bytes) if bytes.local_name().as_ref() == b"title" => is_inside = true,
+            Event::Text(text) if is_inside => title.push_str(&text.unescape().ok()?),
+            Event::CData(text) if is_inside => {
+                title.push_str(&String::from_utf8_lossy(&text.into_inner()))
+            },
+            Event::End(_) if is_inside => {
+                let title = title.trim();
+                return (!title.is_empty()).then(|| title.to_owned());
+            },
+            Event::Eof => return None,
+            _ => {},
+        }
+    }
+}
+
+/// Render the OPML file of the blogroll.
+fn render_opml(feeds: &[Feed], title: &str, minify: bool) -> Result<String, Error> {
+    let document = opml::Opml {
+        version: opml::VERSION,
+        head: opml::Head {
+            title: Some(title.to_owned()),
+        },
+        body: opml::Body {
+            outline: feeds
+                .iter()
+                .map(|feed| opml::Outline {
+                    r#type: "rss",
+                    text: feed
+                        .title
+                        .to_owned()
+                        .unwrap_or_else(|| feed.feed_url.to_owned()),
+                    title: feed.title.to_owned(),
+                    xml_url: feed.feed_url.to_owned(),
+                    html_url: feed.site_url.to_owned(),
+                    description: feed.description.to_owned(),
+                })
+                .collect(),
+        },
+    };
+
+    let mut buffer = String::new();
+
+    let mut serializer = Serializer::with_root(&mut buffer, Some("opml")).map_err(|error| {
+        Error::CreateBlogroll {
+            source: error.into(),
+        }
+    })?;
+
+    if !minify {
+        serializer.indent(' ', 2);
+    }
+
+    document
+        .serialize(serializer)
+        .map_err(|error| Error::CreateBlogroll {
+            source: error.into(),
+        })?;
+
+    Ok(if minify {
+        format!("{}{}", XML_DECLARATION, buffer)
+    } else {
+        format!("{}\n{}", XML_DECLARATION, buffer)
+    })
+}
+
+/// Render the list of feeds of the blogroll page.
+///
+/// Each item links to the site (or the feed if the site URL is unknown) and
+/// to the feed.
+fn render_html(feeds: &[Feed]) -> String {
+    let mut html = String::from("<ul class=\"blogroll\">\n");
+
+    for feed in feeds {
+        let title = feed.title.as_ref().unwrap_or(&feed.feed_url);
+        let url = feed.site_url.as_ref().unwrap_or(&feed.feed_url);
+
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> (<a href=\"{}\">feed</a>)",
+            escape(url),
+            escape(title),
+            escape(&feed.feed_url)
+        ));
+
+        if let Some(description) = feed.description.as_ref() {
+            html.push_str(&format!(": {}", escape(description)));
+        }
+
+        html.push_str("</li>\n");
+    }
+
+    html.push_str("</ul>\n");
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn feed_title() {
+        const CASES: [(&str, Option<&str>); 5] = [
+            (
+                "<channel><title>A &amp; B</title><item><title>C</title></item></channel>",
+                Some("A & B"),
+            ),
+            (
+                "<feed xmlns=\"http://www.w3.org/2005/Atom\"><title>Atom</title></feed>",
+                Some("Atom"),
+            ),
+            (
+                "<rss><channel><title><![CDATA[Blog <3]]></title></channel></rss>",
+                Some("Blog <3"),
+            ),
+            ("<rss><channel><title/></channel></rss>", None),
+            ("<rss><channel></channel></rss>", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::feed_title(input);
+            let expected = expected.map(str::to_owned);
+            assert_eq!(
+                result, expected,
+                "\nfeed_title({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
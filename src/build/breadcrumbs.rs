@@ -0,0 +1,130 @@
+//! Compute breadcrumb trails.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use super::{Config, Entry, Error};
+
+/// Breadcrumb trails, indexed by page URL.
+pub(super) type Breadcrumbs = HashMap<String, Vec<Ancestor>>;
+
+/// Ancestor of a page in a breadcrumb trail.
+#[derive(Clone, Debug, Serialize)]
+pub(super) struct Ancestor {
+    /// Title of the ancestor page.
+    title: Option<String>,
+
+    /// URL of the ancestor page.
+    url: String,
+}
+
+/// Compute the breadcrumb trail of each page.
+///
+/// The ancestors of a page are the index pages (e.g. `docs/index.md`) of the
+/// directories containing the page, from the root to the parent directory.
+/// Directories without index page are skipped.
+pub(super) fn create_breadcrumbs(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<(impl Iterator<Item = Result<Entry, Error>>, Breadcrumbs), Error> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let relative_path = |entry: &Entry| -> Option<PathBuf> {
+        entry
+            .input_path()
+            .and_then(|path| config.strip_input_dir(path))
+            .map(|path| path.to_owned())
+    };
+
+    // Index pages, indexed by directory
+    let mut index_pages: HashMap<PathBuf, Ancestor> = HashMap::new();
+
+    for entry in entries.iter().filter(|entry| entry.format == "html") {
+        let Some(path) = relative_path(entry) else {
+            continue;
+        };
+
+        if path.file_stem().is_some_and(|stem| stem == "index") {
+            // Keep the first page when the index page is duplicated (e.g.
+            // pagination)
+            index_pages
+                .entry(path.parent().unwrap_or(Path::new("")).to_owned())
+                .or_insert_with(|| Ancestor {
+                    title: entry.data.as_ref().and_then(|data| data.title.to_owned()),
+                    url: entry.url.to_owned(),
+                });
+        }
+    }
+
+    let breadcrumbs = entries
+        .iter()
+        .filter(|entry| entry.format == "html")
+        .filter_map(|entry| {
+            let path = relative_path(entry)?;
+
+            let ancestors = ancestor_dirs(&path)
+                .into_iter()
+                .filter_map(|dir| index_pages.get(dir))
+                .filter(|ancestor| ancestor.url != entry.url)
+                .cloned()
+                .collect();
+
+            Some((entry.url.to_owned(), ancestors))
+        })
+        .collect();
+
+    let entries = entries.into_iter().map(Ok);
+
+    Ok((entries, breadcrumbs))
+}
+
+/// Return the ancestor directories of a page, from the root to the parent.
+///
+/// The directory of an index page is not an ancestor of the page.
+fn ancestor_dirs(path: &Path) -> Vec<&Path> {
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+
+    let dir = if path.file_stem().is_some_and(|stem| stem == "index") {
+        match dir.parent() {
+            Some(dir) => dir,
+            None => return Vec::new(),
+        }
+    } else {
+        dir
+    };
+
+    let mut dirs: Vec<&Path> = dir.ancestors().collect();
+    dirs.reverse();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    #[test]
+    fn ancestor_dirs() {
+        const CASES: [(&str, &[&str]); 5] = [
+            ("index.md", &[]),
+            ("about.md", &[""]),
+            ("docs/index.md", &[""]),
+            ("docs/install.md", &["", "docs"]),
+            ("docs/guide/index.md", &["", "docs"]),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::ancestor_dirs(Path::new(input));
+            let expected: Vec<&Path> = expected.iter().map(Path::new).collect();
+            assert_eq!(
+                result, expected,
+                "\nancestor_dirs({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Cache build results across builds.
+
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use super::Config;
+
+/// Cache of build results stored in files.
+///
+/// Results are stored in the cache directory, in files named after a hash of
+/// the input, the options that determine the result (e.g. syntax highlight
+/// classes) and the version of Vitrine.
+#[derive(Debug)]
+pub(super) struct Cache {
+    /// Directory of cached results.
+    dir: PathBuf,
+
+    /// Options that determine the results.
+    options: String,
+}
+
+impl Cache {
+    /// Create a cache in a subdirectory of the cache directory (e.g.
+    /// `.vitrine-cache/markdown`).
+    ///
+    /// Returns `None` if `cache_dir` is not specified in the configuration.
+    pub(super) fn new<S>(config: &Config, name: &str, options: S) -> Option<Self>
+    where
+        S: Into<String>,
+    {
+        config.cache_dir.as_ref().map(|cache_dir| Self {
+            dir: cache_dir.join(name),
+            options: options.into(),
+        })
+    }
+
+    /// Return the cached result of an input, or compute and cache it.
+    ///
+    /// Failing to read or write a cache file is not an error: the result is
+    /// computed again.
+    pub(super) fn get_or_insert_with<F>(&self, input: &str, f: F) -> String
+    where
+        F: FnOnce() -> String,
+    {
+        self.get_or_try_insert_with(input, || Some(f()))
+            .unwrap_or_default()
+    }
+
+    /// Return the cached result of an input, or compute it and cache it if
+    /// the computation succeeds.
+    ///
+    /// Failed computations (e.g. network errors) are attempted again in the
+    /// next builds.
+    pub(super) fn get_or_try_insert_with<F>(&self, input: &str, f: F) -> Option<String>
+    where
+        F: FnOnce() -> Option<String>,
+    {
+        let path = self.dir.join(self.key(input));
+
+        if let Ok(result) = std::fs::read_to_string(&path) {
+            return Some(result);
+        }
+
+        let result = f()?;
+
+        if let Err(error) = write_file(&path, &result) {
+            tracing::warn!("Could not write cache file {:?}: {}", path, error);
+        }
+
+        Some(result)
+    }
+
+    /// Compute the file name of the cached result of an input.
+    fn key(&self, input: &str) -> String {
+        let mut hasher = Sha256::new();
+        for part in [env!("CARGO_PKG_VERSION"), &self.options, input] {
+            // Prefix each part with its length to avoid ambiguities
+            hasher.update(part.len().to_le_bytes());
+            hasher.update(part);
+        }
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Write a cache file.
+///
+/// The content is written to a temporary file first, so that other processes
+/// never read a partially written file.
+fn write_file(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::config::Config;
+
+    #[test]
+    fn get_or_insert_with() {
+        let dir = assert_fs::TempDir::new().unwrap();
+
+        let config = Config {
+            cache_dir: Some(dir.path().to_owned()),
+            ..Default::default()
+        };
+
+        let cache = super::Cache::new(&config, "test", "options").unwrap();
+        let num_calls = Cell::new(0);
+
+        let compute = |input: &str| {
+            cache.get_or_insert_with(input, || {
+                num_calls.set(num_calls.get() + 1);
+                input.to_uppercase()
+            })
+        };
+
+        assert_eq!(compute("a"), "A");
+        assert_eq!(compute("a"), "A");
+        assert_eq!(compute("b"), "B");
+        assert_eq!(num_calls.get(), 2);
+
+        // Failed computations are not cached
+        assert_eq!(cache.get_or_try_insert_with("c", || None), None);
+        assert_eq!(
+            cache.get_or_try_insert_with("c", || Some("C".to_owned())),
+            Some("C".to_owned())
+        );
+
+        // Different options give different results
+        let cache = super::Cache::new(&config, "test", "other").unwrap();
+        assert_eq!(
+            cache.get_or_insert_with("a", || "other".to_owned()),
+            "other"
+        );
+    }
+}
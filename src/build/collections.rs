@@ -0,0 +1,556 @@
+//! Group pages into collections.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use super::{Config, Entry, EntryData, Error};
+use crate::config::CollectionConfig;
+
+/// Name of the metadata key containing the collection name.
+const COLLECTION_KEY: &str = "collection";
+
+/// Name of the metadata key containing the next page of the collection.
+const NEXT_KEY: &str = "next";
+
+/// Name of the metadata key containing the pagination.
+const PAGINATION_KEY: &str = "pagination";
+
+/// Name of the metadata key containing the previous page of the collection.
+const PREVIOUS_KEY: &str = "previous";
+
+/// Apply collection settings to a [`Entry`].
+///
+/// Collections are specified under the `collections` key in the
+/// configuration. A page belongs to the first collection whose directory
+/// contains the page, or whose name is given in the `collection` field of the
+/// metadata. The page then receives the URL prefix and the default layout of
+/// the collection.
+///
+/// The index page of a collection (e.g. `blog/index.md`) is not part of the
+/// collection, but its URL is prefixed.
+pub(super) fn apply_entry(entry: Entry, config: &Config) -> Entry {
+    let path = entry
+        .input_path()
+        .and_then(|path| config.strip_input_dir(path))
+        .map(|path| path.to_owned());
+
+    let name = collection_name(&entry).map(str::to_owned);
+
+    let Some(collection) = config.collections.iter().find(|collection| {
+        name.as_ref() == Some(&collection.name)
+            || collection
+                .directory
+                .as_ref()
+                .zip(path.as_ref())
+                .is_some_and(|(directory, path)| path.starts_with(directory))
+    }) else {
+        return entry;
+    };
+
+    let mut data = entry.data.unwrap_or_default();
+
+    // Pages with an explicit URL keep it
+    let url = match (collection.url_prefix.as_ref(), data.url.as_ref()) {
+        (Some(url_prefix), None) => {
+            prefix_url(&entry.url, collection.directory.as_deref(), url_prefix)
+        },
+        _ => entry.url,
+    };
+
+    if index_url(collection).as_ref() != Some(&url) {
+        let extra = extra_mut(&mut data);
+
+        extra
+            .entry(COLLECTION_KEY)
+            .or_insert_with(|| collection.name.to_owned().into());
+
+        if let Some(layout) = collection.layout.as_ref() {
+            extra
+                .entry(&config.layouts.layout_key)
+                .or_insert_with(|| layout.to_owned().into());
+        }
+    }
+
+    Entry {
+        url,
+        data: Some(data),
+        ..entry
+    }
+}
+
+/// Group entries into collections.
+///
+/// This function saves the sorted pages of each collection in the global data
+/// under the key `collections`. Each page receives the `url` and `title` of
+/// the `previous` and `next` pages. For collections with pagination, the index
+/// page is duplicated once per listing page, and each copy receives a
+/// `pagination` field with the `items` of the listing page, the `page` number,
+/// the number of pages `num_pages`, and the `previous_url` and `next_url`.
+pub(super) fn group_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+    mut global_data: serde_json::Value,
+) -> Result<
+    (
+        impl Iterator<Item = Result<Entry, Error>>,
+        serde_json::Value,
+    ),
+    Error,
+> {
+    let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let mut collections = serde_json::Map::new();
+
+    for collection in config.collections.iter() {
+        let mut pages = entries
+            .iter()
+            .filter(|entry| {
+                entry.format == "html" && collection_name(entry) == Some(collection.name.as_str())
+            })
+            .map(page_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| Error::GroupCollections {
+                source: error.into(),
+            })?;
+
+        sort_pages(&mut pages, collection)
+            .map_err(|error| Error::GroupCollections { source: error })?;
+
+        // Link each page to the previous and next pages
+        let mut links: HashMap<String, [(&str, Option<serde_json::Value>); 2]> = pages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, page)| {
+                let link = |page: &serde_json::Value| {
+                    serde_json::json!({ "url": page["url"], "title": page["data"]["title"] })
+                };
+                let previous = index.checked_sub(1).map(|index| link(&pages[index]));
+                let next = pages.get(index + 1).map(link);
+                let url = page["url"].as_str()?.to_owned();
+                Some((url, [(PREVIOUS_KEY, previous), (NEXT_KEY, next)]))
+            })
+            .collect();
+
+        for entry in entries.iter_mut() {
+            if entry.format != "html" || collection_name(entry) != Some(collection.name.as_str()) {
+                continue;
+            }
+
+            let Some(links) = links.remove(&entry.url) else {
+                continue;
+            };
+
+            let mut data = entry.data.take().unwrap_or_default();
+            let extra = extra_mut(&mut data);
+            for (key, link) in links {
+                if let Some(link) = link {
+                    extra.insert(key.to_owned(), link);
+                }
+            }
+            entry.data = Some(data);
+        }
+
+        if let Some(paginate) = collection.paginate {
+            let paginated_entries = paginate_entries(&mut entries, collection, &pages, paginate)
+                .map_err(|error| Error::GroupCollections { source: error })?;
+            entries.extend(paginated_entries);
+        }
+
+        collections.insert(collection.name.to_owned(), pages.into());
+    }
+
+    let entries = entries.into_iter().map(Ok);
+
+    let mut global_data = global_data.as_object_mut().cloned().unwrap_or_default();
+    global_data.insert("collections".to_owned(), collections.into());
+
+    Ok((entries, global_data.into()))
+}
+
+/// Sort entries using the order of a collection.
+pub(super) fn sort_entries(
+    entries: &mut [&Entry],
+    collection: &CollectionConfig,
+) -> anyhow::Result<()> {
+    let mut pages = entries
+        .iter()
+        .map(|entry| page_value(entry))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    sort_pages(&mut pages, collection)?;
+
+    let positions: HashMap<&str, usize> = pages
+        .iter()
+        .enumerate()
+        .filter_map(|(index, page)| Some((page["url"].as_str()?, index)))
+        .collect();
+
+    entries.sort_by_key(|entry| positions.get(entry.url.as_str()).copied());
+
+    Ok(())
+}
+
+/// Return the name of the collection of a [`Entry`], if any.
+pub(super) fn collection_name(entry: &Entry) -> Option<&str> {
+    entry
+        .data
+        .as_ref()
+        .and_then(|data| data.extra.get(COLLECTION_KEY))
+        .and_then(|value| value.as_str())
+}
+
+/// Set the pagination of the index page of a collection.
+///
+/// Returns the copies of the index page for the listing pages after the first
+/// one.
+fn paginate_entries(
+    entries: &mut [Entry],
+    collection: &CollectionConfig,
+    pages: &[serde_json::Value],
+    paginate: usize,
+) -> anyhow::Result<Vec<Entry>> {
+    let Some(index_url) = index_url(collection) else {
+        anyhow::bail!(
+            "Collection {:?} requires a directory or a URL prefix for pagination",
+            collection.name
+        );
+    };
+
+    let Some(index_entry) = entries
+        .iter_mut()
+        .find(|entry| entry.format == "html" && entry.url == index_url)
+    else {
+        tracing::warn!(
+            "Collection {:?} has no index page at {:?} for pagination",
+            collection.name,
+            index_url
+        );
+        return Ok(Vec::new());
+    };
+
+    let chunks: Vec<_> = pages.chunks(paginate).collect();
+    let num_pages = chunks.len().max(1);
+
+    let page_url = |page: usize| match page {
+        1 => index_url.to_owned(),
+        _ => format!("{}/page/{}", index_url.trim_end_matches('/'), page),
+    };
+
+    let mut paginated_entries = Vec::new();
+
+    for page in 1..=num_pages {
+        let pagination = serde_json::json!({
+            "items": chunks.get(page - 1).copied().unwrap_or_default(),
+            "page": page,
+            "num_pages": num_pages,
+            "previous_url": (page > 1).then(|| page_url(page - 1)),
+            "next_url": (page < num_pages).then(|| page_url(page + 1)),
+        });
+
+        let mut entry = Entry {
+            url: page_url(page),
+            ..index_entry.clone()
+        };
+
+        let mut data = entry.data.unwrap_or_default();
+        extra_mut(&mut data).insert(PAGINATION_KEY.to_owned(), pagination);
+        entry.data = Some(data);
+
+        if page == 1 {
+            *index_entry = entry;
+        } else {
+            paginated_entries.push(entry);
+        }
+    }
+
+    Ok(paginated_entries)
+}
+
+/// Return the URL of the index page of a collection, if any.
+fn index_url(collection: &CollectionConfig) -> Option<String> {
+    collection
+        .url_prefix
+        .as_ref()
+        .map(|url_prefix| match url_prefix.trim_end_matches('/') {
+            "" => "/".to_owned(),
+            url_prefix => url_prefix.to_owned(),
+        })
+        .or_else(|| {
+            collection
+                .directory
+                .as_ref()
+                .map(|directory| format!("/{}", directory.trim_matches('/')))
+        })
+}
+
+/// Replace the directory of a URL by a prefix.
+///
+/// URLs located outside the directory keep their last component only (e.g.
+/// `/misc/hello` becomes `/articles/hello`).
+fn prefix_url(url: &str, directory: Option<&str>, url_prefix: &str) -> String {
+    let url_prefix = url_prefix.trim_end_matches('/');
+
+    let rest = directory
+        .and_then(|directory| url.strip_prefix(&format!("/{}", directory.trim_matches('/'))))
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        .unwrap_or_else(|| url.rfind('/').map_or(url, |index| &url[index..]));
+
+    match format!("{url_prefix}{rest}") {
+        url if url.is_empty() => "/".to_owned(),
+        url => url,
+    }
+}
+
+/// Sort pages of a collection.
+///
+/// Pages are sorted using the `compare` function of the collection if any, or
+/// else by the `sort_by` metadata key. Ties are broken by URL.
+fn sort_pages(
+    pages: &mut [serde_json::Value],
+    collection: &CollectionConfig,
+) -> anyhow::Result<()> {
+    try_sort_by(pages, |a, b| compare_pages(a, b, collection))
+}
+
+/// Sort a slice using a fallible comparison function.
+///
+/// Unlike [`slice::sort_by`], this function stops on the first error, and does
+/// not panic if the function does not define a total order. Elements are
+/// inserted one by one using a binary search, which keeps the sort stable.
+fn try_sort_by<T, F>(values: &mut [T], mut compare: F) -> anyhow::Result<()>
+where
+    F: FnMut(&T, &T) -> anyhow::Result<Ordering>,
+{
+    for i in 1..values.len() {
+        let (mut low, mut high) = (0, i);
+
+        while low < high {
+            let middle = (low + high) / 2;
+            if compare(&values[i], &values[middle])? == Ordering::Less {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        values[low..=i].rotate_right(1);
+    }
+
+    Ok(())
+}
+
+/// Compare two pages of a collection.
+fn compare_pages(
+    a: &serde_json::Value,
+    b: &serde_json::Value,
+    collection: &CollectionConfig,
+) -> anyhow::Result<Ordering> {
+    let ordering = if let Some(compare) = collection.compare.as_ref() {
+        // The function returns a negative number if `a` comes before `b`
+        let result: f64 = compare.call_2(a, b)?;
+        result
+            .partial_cmp(&0.0)
+            .ok_or_else(|| anyhow::anyhow!("Compare function returned {}", result))?
+    } else if let Some(sort_by) = collection.sort_by.as_ref() {
+        compare_by_key(a, b, sort_by)
+    } else {
+        Ordering::Equal
+    };
+
+    Ok(ordering.then_with(|| compare_values(&a["url"], &b["url"])))
+}
+
+/// Compare two pages by a metadata key.
+///
+/// The key can be prefixed with `-` for descending order. Pages without the
+/// key are placed last.
+fn compare_by_key(a: &serde_json::Value, b: &serde_json::Value, sort_by: &str) -> Ordering {
+    let (key, descending) = match sort_by.strip_prefix('-') {
+        Some(key) => (key, true),
+        None => (sort_by, false),
+    };
+
+    let value = |page: &serde_json::Value| {
+        page.get("data")
+            .and_then(|data| data.get(key))
+            .filter(|value| !value.is_null())
+            .cloned()
+    };
+
+    match (value(a), value(b)) {
+        (Some(a), Some(b)) if descending => compare_values(&b, &a),
+        (Some(a), Some(b)) => compare_values(&a, &b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Compare two metadata values.
+///
+/// Values of different types are ordered by type, so that the order is total.
+fn compare_values(a: &serde_json::Value, b: &serde_json::Value) -> Ordering {
+    let rank = |value: &serde_json::Value| match value {
+        serde_json::Value::Null => 0,
+        serde_json::Value::Bool(_) => 1,
+        serde_json::Value::Number(_) => 2,
+        serde_json::Value::String(_) => 3,
+        serde_json::Value::Array(_) => 4,
+        serde_json::Value::Object(_) => 5,
+    };
+
+    match (a, b) {
+        (serde_json::Value::Number(a), serde_json::Value::Number(b)) => a
+            .as_f64()
+            .unwrap_or(f64::NAN)
+            .total_cmp(&b.as_f64().unwrap_or(f64::NAN)),
+        (serde_json::Value::String(a), serde_json::Value::String(b)) => a.cmp(b),
+        (a, b) => rank(a)
+            .cmp(&rank(b))
+            .then_with(|| a.to_string().cmp(&b.to_string())),
+    }
+}
+
+/// Convert a [`Entry`] to a page value for layouts.
+fn page_value(entry: &Entry) -> serde_json::Result<serde_json::Value> {
+    Ok(serde_json::json!({
+        "url": entry.url,
+        "content": entry.content,
+        "data": entry
+            .data
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?
+            .unwrap_or_else(|| serde_json::Value::from(serde_json::Map::new())),
+    }))
+}
+
+/// Return the additional fields of metadata as a mutable map.
+fn extra_mut(data: &mut EntryData) -> &mut serde_json::Map<String, serde_json::Value> {
+    if !data.extra.is_object() {
+        data.extra = serde_json::Value::Object(serde_json::Map::new());
+    }
+    data.extra.as_object_mut().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn prefix_url() {
+        const CASES: [(&str, Option<&str>, &str, &str); 5] = [
+            ("/blog/hello", Some("blog"), "/articles", "/articles/hello"),
+            ("/blog", Some("blog/"), "/articles/", "/articles"),
+            ("/blog/2024/hello", Some("blog"), "/", "/2024/hello"),
+            ("/blog", Some("blog"), "/", "/"),
+            ("/misc/hello", Some("blog"), "/articles", "/articles/hello"),
+        ];
+
+        for (url, directory, url_prefix, expected) in CASES {
+            let result = super::prefix_url(url, directory, url_prefix);
+            assert_eq!(
+                result, expected,
+                "\nprefix_url({url:?}, {directory:?}, {url_prefix:?}) expected {expected:?} but \
+                 received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sort_pages() {
+        use crate::config::CollectionConfig;
+
+        let page =
+            |url: &str, data: serde_json::Value| serde_json::json!({ "url": url, "data": data });
+
+        let mut pages = [
+            page(
+                "/c",
+                serde_json::json!({ "date": "2024-01-02", "weight": 2 }),
+            ),
+            page("/b", serde_json::json!({})),
+            page(
+                "/a",
+                serde_json::json!({ "date": "2024-03-04", "weight": 10 }),
+            ),
+            page("/d", serde_json::json!({ "date": "2024-01-02" })),
+        ];
+
+        let urls = |pages: &[serde_json::Value]| -> Vec<String> {
+            pages
+                .iter()
+                .map(|page| page["url"].as_str().unwrap().to_owned())
+                .collect()
+        };
+
+        let collection = |sort_by: Option<&str>| CollectionConfig {
+            sort_by: sort_by.map(str::to_owned),
+            ..Default::default()
+        };
+
+        super::sort_pages(&mut pages, &collection(Some("-date"))).unwrap();
+        assert_eq!(urls(&pages), ["/a", "/c", "/d", "/b"]);
+
+        super::sort_pages(&mut pages, &collection(Some("weight"))).unwrap();
+        assert_eq!(urls(&pages), ["/c", "/a", "/b", "/d"]);
+
+        super::sort_pages(&mut pages, &collection(None)).unwrap();
+        assert_eq!(urls(&pages), ["/a", "/b", "/c", "/d"]);
+    }
+
+    #[test]
+    fn try_sort_by() {
+        use std::cmp::Ordering;
+
+        // Not a total order: every element is less than the others
+        let mut values = [3, 1, 2, 5, 4];
+        super::try_sort_by(&mut values, |_, _| Ok(Ordering::Less)).unwrap();
+
+        let mut values = [3, 1, 2, 5, 4, 1];
+        super::try_sort_by(&mut values, |a, b| Ok(a.cmp(b))).unwrap();
+        assert_eq!(values, [1, 1, 2, 3, 4, 5]);
+
+        let mut calls = 0;
+        let result = super::try_sort_by(&mut values, |_, _| {
+            calls += 1;
+            Err(anyhow::anyhow!("Failure"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn compare_values() {
+        use std::cmp::Ordering;
+
+        let cases = [
+            (serde_json::json!(9), serde_json::json!(10), Ordering::Less),
+            (
+                serde_json::json!("9"),
+                serde_json::json!("10"),
+                Ordering::Greater,
+            ),
+            (
+                serde_json::json!(10),
+                serde_json::json!("9"),
+                Ordering::Less,
+            ),
+            (
+                serde_json::json!("9"),
+                serde_json::json!(9),
+                Ordering::Greater,
+            ),
+            (
+                serde_json::json!(null),
+                serde_json::json!(false),
+                Ordering::Less,
+            ),
+        ];
+
+        for (a, b, expected) in cases {
+            let result = super::compare_values(&a, &b);
+            assert_eq!(
+                result, expected,
+                "\ncompare_values({a:?}, {b:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
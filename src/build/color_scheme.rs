@@ -0,0 +1,191 @@
+//! Generate a stylesheet of light and dark color schemes.
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::{syntax_highlight, Config, Entry, Error};
+use crate::config::ColorSchemeConfig;
+
+/// Attribute of the root element that overrides the preferred color scheme,
+/// if no color scheme is configured.
+const DEFAULT_THEME_ATTRIBUTE: &str = "data-theme";
+
+/// Return the attribute of the root element that overrides the preferred
+/// color scheme (e.g. `data-theme`).
+///
+/// Its value is either `light` or `dark` (e.g. `<html data-theme="dark">`).
+pub(super) fn theme_attribute(config: &Config) -> &str {
+    config
+        .color_scheme
+        .as_ref()
+        .map_or(DEFAULT_THEME_ATTRIBUTE, |color_scheme| {
+            &color_scheme.attribute
+        })
+}
+
+/// Create the color scheme stylesheet specified in the configuration, if any.
+///
+/// The stylesheet defines the palette variables of the light scheme on the
+/// root element, and those of the dark scheme when the user prefers a dark
+/// color scheme. The attribute of the root element (e.g. `data-theme`)
+/// overrides the preference. Syntax highlight rules of a light and a dark
+/// theme follow the same convention.
+pub(super) fn create_entries(config: &Config) -> impl Iterator<Item = Result<Entry, Error>> {
+    config
+        .color_scheme
+        .as_ref()
+        .map(|color_scheme_config| {
+            let mut content = create_palette_css(color_scheme_config);
+
+            if let Some(theme) = color_scheme_config.syntax_theme.as_ref() {
+                let theme_set = syntax_highlight::load_theme_set(config)?;
+
+                content.push('\n');
+                content.push_str(&syntax_highlight::create_themes_css(
+                    &theme_set,
+                    theme,
+                    color_scheme_config.syntax_dark_theme.as_deref(),
+                    &config.syntax_highlight.css_prefix,
+                    &color_scheme_config.attribute,
+                )?);
+            }
+
+            Ok(Entry {
+                content: Some(content),
+                url: color_scheme_config.url.to_owned(),
+                format: "css".to_owned(),
+                ..Default::default()
+            })
+        })
+        .into_iter()
+}
+
+/// Link the color scheme stylesheet in a [`Entry`].
+///
+/// This function inserts a `<link rel="stylesheet">` element at the end of the
+/// `<head>` element if `color_scheme.link` is enabled.
+pub(super) fn link_stylesheet_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
+    let Some(color_scheme_config) = config
+        .color_scheme
+        .as_ref()
+        .filter(|color_scheme| color_scheme.link)
+    else {
+        return Ok(entry);
+    };
+
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(entry);
+    };
+
+    // Absolute URLs are made relative later if `relative_urls` is enabled
+    let href = if config.relative_urls {
+        color_scheme_config.url.to_owned()
+    } else {
+        format!("{}{}", config.base_url, color_scheme_config.url)
+    };
+
+    let link = format!(
+        "<link rel=\"stylesheet\" href=\"{}\">",
+        href.replace('&', "&amp;").replace('"', "&quot;")
+    );
+
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("head", |element| {
+            element.append(&link, lol_html::html_content::ContentType::Html);
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })
+    .map_err(|error| Error::LinkColorScheme {
+        input_path: entry.input_path_buf(),
+        source: error.into(),
+    })?;
+
+    Ok(Entry {
+        content: Some(content),
+        ..entry
+    })
+}
+
+/// Create the CSS rules of the palette variables.
+///
+/// The `color-scheme` property is only set if a dark palette is specified, so
+/// that browsers do not render form controls in dark mode on light pages. The
+/// light palette is defined on the root element, so it applies by default.
+fn create_palette_css(color_scheme_config: &ColorSchemeConfig) -> String {
+    let attribute = &color_scheme_config.attribute;
+    let has_dark = !color_scheme_config.dark.is_empty();
+
+    let light = declarations(&color_scheme_config.light);
+    let dark = declarations(&color_scheme_config.dark);
+
+    let mut css = String::new();
+
+    css.push_str(":root {\n");
+    if has_dark {
+        css.push_str("  color-scheme: light dark;\n");
+    }
+    css.push_str(&light);
+    css.push_str("}\n");
+
+    if has_dark {
+        // Declarations of the nested rule are indented twice
+        let nested_dark: String = dark.lines().map(|line| format!("  {line}\n")).collect();
+
+        css.push_str(&format!(
+            "@media (prefers-color-scheme: dark) {{\n  :root:not([{attribute}=\"light\"]) \
+             {{\n{nested_dark}  }}\n}}\n:root[{attribute}=\"light\"] {{\n  color-scheme: \
+             light;\n}}\n:root[{attribute}=\"dark\"] {{\n  color-scheme: dark;\n{dark}}}\n"
+        ));
+    }
+
+    css
+}
+
+/// Format CSS custom properties (e.g. `  --bg: #fff;`), sorted by name.
+///
+/// Names are given without the leading `--`.
+fn declarations(variables: &HashMap<String, String>) -> String {
+    variables
+        .iter()
+        .map(|(name, value)| (name.trim_start_matches("--"), value.trim()))
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(name, value)| format!("  --{name}: {value};\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::config::ColorSchemeConfig;
+
+    #[test]
+    fn create_palette_css() {
+        let color_scheme_config = |dark: &[(&str, &str)]| ColorSchemeConfig {
+            light: HashMap::from([
+                ("--fg".to_owned(), "#000".to_owned()),
+                ("bg".to_owned(), "#fff".to_owned()),
+            ]),
+            dark: dark
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+
+        let result = super::create_palette_css(&color_scheme_config(&[]));
+
+        assert_eq!(result, ":root {\n  --bg: #fff;\n  --fg: #000;\n}\n");
+
+        let result = super::create_palette_css(&color_scheme_config(&[("bg", "#000")]));
+
+        assert_eq!(
+            result,
+            ":root {\n  color-scheme: light dark;\n  --bg: #fff;\n  --fg: #000;\n}\n@media \
+             (prefers-color-scheme: dark) {\n  :root:not([data-theme=\"light\"]) {\n    --bg: \
+             #000;\n  }\n}\n:root[data-theme=\"light\"] {\n  color-scheme: \
+             light;\n}\n:root[data-theme=\"dark\"] {\n  color-scheme: dark;\n  --bg: #000;\n}\n"
+        );
+    }
+}
@@ -6,6 +6,7 @@ use std::{
 };
 
 use super::{Entry, EntryData, Error};
+use crate::util::path::PathExt;
 
 /// Bundle contents.
 ///
@@ -64,7 +65,7 @@ pub(super) fn bundle_entries(
                     let content = if path == "." {
                         entry.content.clone().unwrap_or_default()
                     } else {
-                        let path = dir.join(&path).canonicalize().map_err(|error| {
+                        let path = dir.join_url_path(path).canonicalize().map_err(|error| {
                             anyhow::anyhow!(error).context(format!("Entry {:?}", path))
                         })?;
 
@@ -0,0 +1,179 @@
+//! Copy files located outside of the input directory.
+
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobMatcher};
+use walkdir::WalkDir;
+
+use super::{Config, Entry, Error};
+use crate::{config::CopyPathConfig, util::path::PathExt};
+
+/// Formats of the files that can be minified.
+const MINIFY_FORMATS: [&str; 5] = ["css", "js", "json", "svg", "xml"];
+
+/// Create a [`Entry`] for each file matched by the `copy_paths` of the
+/// configuration.
+///
+/// The entries are copied as is, unless `minify` is `true` for the path, in
+/// which case CSS, JavaScript, JSON, SVG and XML files are read to be
+/// minified. Other build tasks (e.g. layouts) do not apply to these entries.
+pub(super) fn create_entries(config: &Config) -> Result<Vec<Entry>, Error> {
+    let mut entries = Vec::new();
+
+    for copy_path in config.copy_paths.iter() {
+        let to = copy_path.to.as_str();
+
+        if !to.starts_with('/') {
+            return Err(Error::CopyPaths {
+                source: anyhow::anyhow!("Expected an URL path starting with `/`, received {to:?}"),
+            });
+        }
+
+        let (base, matcher) = split_pattern(&copy_path.from).map_err(|error| Error::CopyPaths {
+            source: error.context(format!("While parsing {:?}", copy_path.from)),
+        })?;
+
+        if !base.exists() {
+            return Err(Error::CopyPaths {
+                source: anyhow::anyhow!("No such file or directory {:?}", base),
+            });
+        }
+
+        // A single file is copied to `to`, or into `to` if it ends with `/`
+        if base.is_file() && matcher.is_none() {
+            let url = match to.strip_suffix('/') {
+                Some(dir) => base
+                    .file_name()
+                    .and_then(|file_name| file_name.to_str())
+                    .map(|file_name| format!("{dir}/{file_name}")),
+                None => Some(to.to_owned()),
+            };
+            entries.extend(create_entry(&base, url, copy_path)?);
+            continue;
+        }
+
+        // Files of a directory are copied into `to`, preserving their structure
+        for entry in WalkDir::new(&base)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+        {
+            let path = entry.path().strip_prefix(&base).unwrap();
+
+            if matcher
+                .as_ref()
+                .is_some_and(|matcher| !matcher.is_match(path))
+            {
+                continue;
+            }
+
+            let url = path
+                .to_url_path()
+                .map(|url| format!("{}{}", to.trim_end_matches('/'), url));
+            entries.extend(create_entry(entry.path(), url, copy_path)?);
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Create a [`Entry`] for a copied file, and read it if it has to be
+/// minified.
+fn create_entry(
+    path: &Path,
+    url: Option<String>,
+    copy_path: &CopyPathConfig,
+) -> Result<Option<Entry>, Error> {
+    let Some(url) = url else {
+        tracing::warn!("Skipping {:?}, which is not valid UTF-8", path);
+        return Ok(None);
+    };
+
+    let Some(Ok(input_file)) = WalkDir::new(path).into_iter().next() else {
+        return Ok(None);
+    };
+
+    // Other formats are left empty, to copy the file as is
+    let format = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .filter(|extension| copy_path.minify && MINIFY_FORMATS.contains(extension))
+        .unwrap_or_default()
+        .to_owned();
+
+    let entry = Entry {
+        url,
+        format,
+        input_file: Some(input_file),
+        ..Default::default()
+    };
+
+    if entry.format.is_empty() {
+        return Ok(Some(entry));
+    }
+
+    super::read_file::read_entry(entry).map(Some)
+}
+
+/// Split a path pattern into its base directory, without glob characters, and
+/// a matcher for paths relative to this directory.
+///
+/// Returns no matcher if the pattern has no glob characters (e.g.
+/// `static/fonts`).
+fn split_pattern(pattern: &str) -> anyhow::Result<(PathBuf, Option<GlobMatcher>)> {
+    let is_glob = |segment: &str| segment.contains(['*', '?', '[', '{']);
+
+    let segments: Vec<&str> = pattern.split(['/', std::path::MAIN_SEPARATOR]).collect();
+
+    let Some(index) = segments.iter().position(|segment| is_glob(segment)) else {
+        return Ok((PathBuf::from(pattern), None));
+    };
+
+    let base = match segments[..index].join("/") {
+        base if base.is_empty() && pattern.starts_with(['/', std::path::MAIN_SEPARATOR]) => {
+            "/".to_owned()
+        },
+        base if base.is_empty() => ".".to_owned(),
+        base => base,
+    };
+
+    let matcher = GlobBuilder::new(&segments[index..].join("/"))
+        .literal_separator(true)
+        .build()?
+        .compile_matcher();
+
+    Ok((PathBuf::from(base), Some(matcher)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    #[test]
+    fn split_pattern() {
+        const CASES: [(&str, &str, Option<&str>, bool); 5] = [
+            ("static/fonts", "static/fonts", None, false),
+            ("static/**", "static", Some("a/b.woff2"), true),
+            ("static/*.json", "static", Some("a.json"), true),
+            ("static/*.json", "static", Some("a/b.json"), false),
+            ("*.txt", ".", Some("robots.txt"), true),
+        ];
+
+        for (input, base, path, expected) in CASES {
+            let (result_base, matcher) = super::split_pattern(input).unwrap();
+            let result = match (path, matcher) {
+                (Some(path), Some(matcher)) => matcher.is_match(path),
+                (None, matcher) => matcher.is_some(),
+                (Some(_), None) => false,
+            };
+            assert_eq!(
+                (result_base.as_path(), result),
+                (Path::new(base), expected),
+                "\nsplit_pattern({input:?}) expected {:?} but received {:?}",
+                (base, expected),
+                (result_base, result)
+            );
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Generate Content Security Policies.
+//!
+//! See <https://developer.mozilla.org/en-US/docs/Web/HTTP/CSP>.
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+use super::{hosting::HeaderRule, Config, Entry, Error};
+use crate::util::html;
+
+/// Generate the Content Security Policy of HTML pages.
+///
+/// If `content_security_policy` is specified in the configuration, this
+/// function computes the SHA-256 hashes of inline `<script>` and `<style>`
+/// elements and `style` attributes in each page, and adds them to the
+/// `script-src` and `style-src` directives of the base policy. The resulting
+/// policy is inserted in the `<head>` of the page as a `<meta>` element, and/or
+/// listed in a headers file.
+///
+/// This function also returns the policy of each page as header rules, which
+/// are added to the files of hosting providers.
+///
+/// This function must be called after the content of entries is final (e.g.
+/// after minification).
+#[allow(clippy::type_complexity)]
+pub(super) fn create_csp_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<(impl Iterator<Item = Result<Entry, Error>>, Vec<HeaderRule>), Error> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let Some(csp_config) = config.content_security_policy.as_ref() else {
+        return Ok((entries.into_iter().map(Ok).chain(None), Vec::new()));
+    };
+
+    let mut header_rules = Vec::new();
+
+    let entries = entries
+        .into_iter()
+        .map(|entry| {
+            if entry.format != "html" {
+                return Ok(entry);
+            }
+
+            let Some(content) = entry.content.as_ref() else {
+                return Ok(entry);
+            };
+
+            let (script_hashes, style_hashes) = hash_inline_elements(content).map_err(|error| {
+                Error::CreateContentSecurityPolicy {
+                    input_path: entry.input_path_buf(),
+                    source: error,
+                }
+            })?;
+
+            let policy = create_policy(&csp_config.policy, &script_hashes, &style_hashes);
+
+            header_rules.push(HeaderRule {
+                url: entry.url.to_owned(),
+                headers: vec![("Content-Security-Policy".to_owned(), policy.to_owned())],
+            });
+
+            if !csp_config.meta {
+                return Ok(entry);
+            }
+
+            let meta = format!(
+                "<meta http-equiv=\"Content-Security-Policy\" content=\"{}\">",
+                policy.replace('&', "&amp;").replace('"', "&quot;")
+            );
+
+            let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+                element_content_handlers: vec![lol_html::element!("head", |element| {
+                    element.prepend(&meta, lol_html::html_content::ContentType::Html);
+                    Ok(())
+                })],
+                ..lol_html::RewriteStrSettings::default()
+            })
+            .map_err(|error| Error::CreateContentSecurityPolicy {
+                input_path: entry.input_path_buf(),
+                source: error.into(),
+            })?;
+
+            Ok(Entry {
+                content: Some(content),
+                ..entry
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let headers_entry = csp_config.headers_url.as_ref().map(|url| Entry {
+        url: url.to_owned(),
+        content: Some(super::hosting::headers_file(
+            &header_rules,
+            &config.base_url,
+        )),
+        ..Default::default()
+    });
+
+    Ok((
+        entries.into_iter().map(Ok).chain(headers_entry.map(Ok)),
+        header_rules,
+    ))
+}
+
+/// Compute the hashes of inline `<script>` and `<style>` elements.
+///
+/// The hashes of `style` attributes are added to the style hashes, preceded by
+/// `unsafe-hashes`, which browsers require to apply them.
+fn hash_inline_elements<S>(content: S) -> anyhow::Result<(Vec<String>, Vec<String>)>
+where
+    S: AsRef<str>,
+{
+    let mut script_hashes = Vec::new();
+    let mut style_hashes = Vec::new();
+    let mut script_buffer = String::new();
+    let mut style_buffer = String::new();
+    let mut has_style_attributes = false;
+
+    lol_html::rewrite_str(content.as_ref(), lol_html::RewriteStrSettings {
+        element_content_handlers: vec![
+            lol_html::text!("script:not([src])", |text| {
+                script_buffer.push_str(text.as_str());
+                if text.last_in_text_node() {
+                    if !script_buffer.is_empty() {
+                        script_hashes.push(hash(&script_buffer));
+                    }
+                    script_buffer.clear();
+                }
+                Ok(())
+            }),
+            lol_html::text!("style", |text| {
+                style_buffer.push_str(text.as_str());
+                if text.last_in_text_node() {
+                    if !style_buffer.is_empty() {
+                        style_hashes.push(hash(&style_buffer));
+                    }
+                    style_buffer.clear();
+                }
+                Ok(())
+            }),
+            lol_html::element!("[style]", |element| {
+                if let Some(style) = element.get_attribute("style") {
+                    style_hashes.push(hash(html::unescape(&style)));
+                    has_style_attributes = true;
+                }
+                Ok(())
+            }),
+        ],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    script_hashes.sort();
+    script_hashes.dedup();
+    style_hashes.sort();
+    style_hashes.dedup();
+
+    if has_style_attributes {
+        style_hashes.insert(0, "unsafe-hashes".to_owned());
+    }
+
+    Ok((script_hashes, style_hashes))
+}
+
+/// Add sources (e.g. hashes) to the `script-src` and `style-src` directives of
+/// a policy.
+///
+/// If a directive is missing, it is created from the `default-src` directive
+/// (or `'self'`), so that the hashes do not relax the policy.
+fn create_policy<S>(policy: S, script_hashes: &[String], style_hashes: &[String]) -> String
+where
+    S: AsRef<str>,
+{
+    let mut directives: Vec<(String, String)> = policy
+        .as_ref()
+        .split(';')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .map(|directive| {
+            let (name, value) = directive.split_once(' ').unwrap_or((directive, ""));
+            (name.to_ascii_lowercase(), value.trim().to_owned())
+        })
+        .collect();
+
+    let default_src = directives
+        .iter()
+        .find(|(name, _)| name == "default-src")
+        .map(|(_, value)| value.to_owned())
+        .unwrap_or_else(|| "'self'".to_owned());
+
+    for (directive_name, hashes) in [("script-src", script_hashes), ("style-src", style_hashes)] {
+        if hashes.is_empty() {
+            continue;
+        }
+
+        let index = match directives
+            .iter()
+            .position(|(name, _)| name == directive_name)
+        {
+            Some(index) => index,
+            None => {
+                directives.push((directive_name.to_owned(), default_src.clone()));
+                directives.len() - 1
+            },
+        };
+
+        // `'none'` is only allowed in an otherwise empty source list
+        let value = &mut directives[index].1;
+        *value = value
+            .split_whitespace()
+            .filter(|source| !source.eq_ignore_ascii_case("'none'"))
+            .map(str::to_owned)
+            .chain(hashes.iter().map(|hash| format!("'{hash}'")))
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    directives
+        .iter()
+        .map(|(name, value)| {
+            if value.is_empty() {
+                name.to_owned()
+            } else {
+                format!("{name} {}", value.trim())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Compute the hash source of a content, e.g. `sha256-{base64}`.
+fn hash<S>(content: S) -> String
+where
+    S: AsRef<str>,
+{
+    let digest = Sha256::digest(content.as_ref().as_bytes());
+    format!(
+        "sha256-{}",
+        base64::engine::general_purpose::STANDARD.encode(digest)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn create_policy() {
+        let hashes = ["sha256-a".to_owned()];
+
+        const CASES: [(&str, bool, bool, &str); 5] = [
+            ("default-src 'self'", false, false, "default-src 'self'"),
+            (
+                "default-src 'self'",
+                true,
+                false,
+                "default-src 'self'; script-src 'self' 'sha256-a'",
+            ),
+            (
+                "default-src 'none'; style-src https://c.org; upgrade-insecure-requests",
+                true,
+                true,
+                "default-src 'none'; style-src https://c.org 'sha256-a'; \
+                 upgrade-insecure-requests; script-src 'sha256-a'",
+            ),
+            (
+                "default-src 'self'; script-src 'NONE'; style-src 'none'",
+                true,
+                false,
+                "default-src 'self'; script-src 'sha256-a'; style-src 'none'",
+            ),
+            (
+                "img-src *",
+                false,
+                true,
+                "img-src *; style-src 'self' 'sha256-a'",
+            ),
+        ];
+
+        for (input, scripts, styles, expected) in CASES {
+            let script_hashes = if scripts { &hashes[..] } else { &[] };
+            let style_hashes = if styles { &hashes[..] } else { &[] };
+            let result = super::create_policy(input, script_hashes, style_hashes);
+            assert_eq!(
+                result, expected,
+                "\ncreate_policy({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn hash_inline_elements() {
+        let (script_hashes, style_hashes) = super::hash_inline_elements(
+            "<script>alert(1)</script><script \
+             src=\"a.js\"></script><style>p{}</style><script>alert(2)</script><script>alert(1)</\
+             script>",
+        )
+        .unwrap();
+
+        let mut expected = [super::hash("alert(1)"), super::hash("alert(2)")];
+        expected.sort();
+        assert_eq!(script_hashes, expected);
+        assert_eq!(style_hashes, [super::hash("p{}")]);
+    }
+
+    #[test]
+    fn hash_inline_elements_style_attributes() {
+        let (script_hashes, style_hashes) = super::hash_inline_elements(
+            "<p style=\"color: red\">a</p><p style=\"content: &quot;b&quot;\">b</p><p \
+             style=\"color: red\">c</p>",
+        )
+        .unwrap();
+
+        let mut expected = vec![super::hash("color: red"), super::hash("content: \"b\"")];
+        expected.sort();
+        expected.insert(0, "unsafe-hashes".to_owned());
+        assert!(script_hashes.is_empty());
+        assert_eq!(style_hashes, expected);
+    }
+}
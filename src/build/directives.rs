@@ -0,0 +1,654 @@
+//! Expand directives in pages.
+//!
+//! A directive (e.g. `{{< include "install.md" >}}`) is replaced by its result
+//! before the content of the page is parsed. A directive whose content is
+//! wrapped in comments (e.g. `{{</* include "install.md" */>}}`) is escaped,
+//! and output without the comments.
+//!
+//! The following directives are supported:
+//!
+//! - `include`: insert a file of the includes directory. Optional arguments
+//!   select a range of lines (e.g. `lines="3-10"`) or a named region (e.g.
+//!   `region="setup"`), delimited by `ANCHOR: setup` and `ANCHOR_END: setup`
+//!   markers.
+//! - `code`: insert a source file as a Markdown code block (e.g. `{{< code
+//!   file="examples/main.rs" region="setup" lang="rust" >}}`). The path is
+//!   relative to the page, or to the input directory if it starts with `/`. The
+//!   `lines` and `region` arguments select part of the file, as for `include`,
+//!   and `title` sets the title of the code block.
+//! - `image`: insert a `<picture>` element for a local image (e.g. `{{< image
+//!   "photo.jpg" alt="A photo" caption="Caption" >}}`). The path is relative to
+//!   the page, or to the input directory if it starts with `/`.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use super::{
+    images::{render_picture, resolve_image_path},
+    Config, Entry, Error,
+};
+use crate::util::path::PathExt;
+
+/// Opening delimiter of directives.
+const OPEN: &str = "{{<";
+
+/// Closing delimiter of directives.
+const CLOSE: &str = ">}}";
+
+/// Maximum depth of nested includes, to detect include cycles.
+const MAX_DEPTH: usize = 16;
+
+/// Marker of the start of a named region in included files.
+const ANCHOR_START: &str = "ANCHOR:";
+
+/// Marker of the end of a named region in included files.
+const ANCHOR_END: &str = "ANCHOR_END:";
+
+/// Files included in each page, indexed by input path.
+///
+/// When an included file changes, only the pages that include it must be
+/// rendered again.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Dependencies(HashMap<PathBuf, HashSet<PathBuf>>);
+
+impl Dependencies {
+    /// Add the dependencies of other pages, replacing existing ones.
+    pub(super) fn extend(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    /// Check if a file is included in any page.
+    pub(super) fn contains(&self, path: &Path) -> bool {
+        self.0.values().any(|paths| paths.contains(path))
+    }
+
+    /// Return the files included in any page.
+    pub(super) fn paths(&self) -> HashSet<PathBuf> {
+        self.0.values().flatten().cloned().collect()
+    }
+
+    /// Return the input paths of the pages that include any of given files.
+    pub(super) fn pages_using(&self, paths: &HashSet<PathBuf>) -> HashSet<PathBuf> {
+        self.0
+            .iter()
+            .filter(|(_, page_paths)| !page_paths.is_disjoint(paths))
+            .map(|(input_path, _)| input_path.to_owned())
+            .collect()
+    }
+}
+
+/// Expander of directives.
+#[derive(Debug)]
+pub(super) struct Expander {
+    /// Directory of included files.
+    includes_dir: Option<PathBuf>,
+
+    /// Input directories, by order of precedence.
+    input_dirs: Vec<PathBuf>,
+
+    /// Files included in each page.
+    dependencies: RefCell<Dependencies>,
+}
+
+/// Directive parsed from a page (e.g. `include "install.md" lines="3-10"`).
+#[derive(Debug, Default, PartialEq)]
+struct Directive {
+    /// Name of the directive.
+    name: String,
+
+    /// Positional arguments.
+    arguments: Vec<String>,
+
+    /// Named arguments.
+    options: HashMap<String, String>,
+}
+
+impl Expander {
+    /// Create a directive expander.
+    pub(super) fn new(config: &Config) -> Self {
+        Self {
+            includes_dir: config.includes_dir.to_owned(),
+            input_dirs: config.input_dirs().cloned().collect(),
+            dependencies: RefCell::default(),
+        }
+    }
+
+    /// Expand the directives in the content of a [`Entry`].
+    pub(super) fn expand_entry(&self, entry: Entry) -> Result<Entry, Error> {
+        let mut paths = HashSet::new();
+
+        let dir = entry.input_path().and_then(|path| path.parent());
+
+        let content = entry
+            .content
+            .as_ref()
+            .filter(|content| content.contains(OPEN))
+            .map(|content| self.expand(content, dir, 0, &mut paths))
+            .transpose()
+            .map_err(|error| Error::ExpandDirectives {
+                input_path: entry.input_path_buf(),
+                source: error,
+            })?;
+
+        // Record the files included in the page, even if there are none
+        if let Some(input_path) = entry.input_path() {
+            self.dependencies
+                .borrow_mut()
+                .0
+                .insert(input_path.to_owned(), paths);
+        }
+
+        match content {
+            Some(content) => Ok(Entry {
+                content: Some(content),
+                ..entry
+            }),
+            None => Ok(entry),
+        }
+    }
+
+    /// Consume the expander, and return the files included in each page.
+    pub(super) fn into_dependencies(self) -> Dependencies {
+        self.dependencies.into_inner()
+    }
+
+    /// Expand the directives of a string, in a page located in `dir`.
+    ///
+    /// Paths of included files are added to `paths`.
+    fn expand(
+        &self,
+        content: &str,
+        dir: Option<&Path>,
+        depth: usize,
+        paths: &mut HashSet<PathBuf>,
+    ) -> anyhow::Result<String> {
+        let mut output = String::with_capacity(content.len());
+        let mut rest = content;
+
+        while let Some(start) = rest.find(OPEN) {
+            output.push_str(&rest[..start]);
+
+            let tail = &rest[start + OPEN.len()..];
+
+            let end = tail.find(CLOSE).ok_or_else(|| {
+                let line = tail.lines().next().unwrap_or_default();
+                anyhow::anyhow!("Unclosed directive {OPEN}{line}")
+            })?;
+
+            let input = &tail[..end];
+            rest = &tail[end + CLOSE.len()..];
+
+            // Escaped directive
+            if let Some(input) = input
+                .strip_prefix("/*")
+                .and_then(|input| input.strip_suffix("*/"))
+            {
+                output.push_str(OPEN);
+                output.push_str(input);
+                output.push_str(CLOSE);
+                continue;
+            }
+
+            let directive = Directive::parse(input)?;
+
+            let result = match directive.name.as_str() {
+                "include" => self.include(&directive, dir, depth, paths),
+                "code" => self.code(&directive, dir, paths),
+                "image" => self.image(&directive, dir),
+                name => Err(anyhow::anyhow!("Unknown directive {name:?}")),
+            }
+            .with_context(|| format!("While expanding {OPEN}{input}{CLOSE}"))?;
+
+            output.push_str(&result);
+        }
+
+        output.push_str(rest);
+
+        Ok(output)
+    }
+
+    /// Expand an `include` directive.
+    fn include(
+        &self,
+        directive: &Directive,
+        dir: Option<&Path>,
+        depth: usize,
+        paths: &mut HashSet<PathBuf>,
+    ) -> anyhow::Result<String> {
+        let file = directive.argument("file")?;
+
+        let includes_dir = self
+            .includes_dir
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Cannot include {file:?} without includes_dir"))?;
+
+        if depth >= MAX_DEPTH {
+            anyhow::bail!("Too many nested includes (maximum {MAX_DEPTH})");
+        }
+
+        let path = includes_dir.join(file).normalize();
+
+        if !path.starts_with(includes_dir) {
+            anyhow::bail!("Expected a file located in includes_dir, received {file:?}");
+        }
+
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("While reading {path:?}"))?;
+
+        paths.insert(path.to_owned());
+
+        let content = select(&content, directive)?;
+
+        // Included files may include other files
+        self.expand(&content, dir, depth + 1, paths)
+    }
+
+    /// Expand an `image` directive.
+    fn image(&self, directive: &Directive, dir: Option<&Path>) -> anyhow::Result<String> {
+        let file = directive.argument("file")?;
+        let alt = directive
+            .options
+            .get("alt")
+            .map(String::as_str)
+            .unwrap_or_default();
+        let caption = directive.options.get("caption").map(String::as_str);
+
+        let path = self
+            .input_dirs
+            .iter()
+            .filter_map(|input_dir| resolve_image_path(file, dir.unwrap_or(input_dir), input_dir))
+            .find(|path| path.is_file())
+            .ok_or_else(|| anyhow::anyhow!("No image found for {file:?}"))?;
+
+        // The image URL is rewritten later, as other local paths
+        Ok(render_picture(&path, file, alt, caption))
+    }
+
+    /// Expand a `code` directive.
+    fn code(
+        &self,
+        directive: &Directive,
+        dir: Option<&Path>,
+        paths: &mut HashSet<PathBuf>,
+    ) -> anyhow::Result<String> {
+        let file = directive.argument("file")?;
+
+        let path = self
+            .input_dirs
+            .iter()
+            .map(|input_dir| match file.strip_prefix('/') {
+                Some(file) => input_dir.join(file),
+                None => dir.unwrap_or(input_dir).join(file),
+            })
+            .find(|path| path.is_file())
+            .ok_or_else(|| anyhow::anyhow!("No file found for {file:?}"))?;
+
+        // Paths of dependencies are absolute, to be compared with changed paths
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("While reading {path:?}"))?;
+
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("While reading {path:?}"))?;
+
+        paths.insert(path.to_owned());
+
+        let content = dedent(&select(&content, directive)?);
+
+        let mut info = directive
+            .options
+            .get("lang")
+            .map(|lang| lang.as_str())
+            .or_else(|| path.extension().and_then(|extension| extension.to_str()))
+            .unwrap_or_default()
+            .to_owned();
+
+        if let Some(title) = directive.options.get("title") {
+            info.push_str(&format!(",title=\"{}\"", escape_title(title)));
+        }
+
+        // Backticks are not allowed in the info string of a backtick fence
+        let fence_char = if info.contains('`') { '~' } else { '`' };
+
+        // The fence must be longer than any sequence of its character in the
+        // code
+        let fence = fence_char
+            .to_string()
+            .repeat(max_repeat(&content, fence_char).max(2) + 1);
+
+        Ok(format!("{fence}{info}\n{content}{fence}\n"))
+    }
+}
+
+impl Directive {
+    /// Parse the content of a directive, between the delimiters.
+    ///
+    /// Arguments are separated by whitespace, and may be quoted. Named
+    /// arguments use the `key="value"` syntax.
+    fn parse(input: &str) -> anyhow::Result<Self> {
+        let input = input.trim();
+        let (name, mut rest) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("Invalid directive name {name:?}");
+        }
+
+        let mut directive = Self {
+            name: name.to_owned(),
+            ..Default::default()
+        };
+
+        rest = rest.trim_start();
+
+        while !rest.is_empty() {
+            let key_end = rest
+                .find(|c: char| c == '=' || c == '"' || c.is_whitespace())
+                .unwrap_or(rest.len());
+
+            let (key, value_rest) = match rest[key_end..].strip_prefix('=') {
+                Some(value_rest) => (Some(&rest[..key_end]), value_rest),
+                None => (None, rest),
+            };
+
+            let (value, tail) = match value_rest.strip_prefix('"') {
+                Some(value_rest) => {
+                    let end = value_rest
+                        .find('"')
+                        .ok_or_else(|| anyhow::anyhow!("Unclosed quote in {input:?}"))?;
+                    (&value_rest[..end], &value_rest[end + 1..])
+                },
+                None => value_rest.split_at(
+                    value_rest
+                        .find(char::is_whitespace)
+                        .unwrap_or(value_rest.len()),
+                ),
+            };
+
+            match key {
+                Some("") => anyhow::bail!("Missing argument name in {input:?}"),
+                Some(key) => {
+                    directive.options.insert(key.to_owned(), value.to_owned());
+                },
+                None => directive.arguments.push(value.to_owned()),
+            }
+
+            rest = tail.trim_start();
+        }
+
+        Ok(directive)
+    }
+
+    /// Return an argument given by name, or else the first positional
+    /// argument.
+    fn argument(&self, name: &str) -> anyhow::Result<&str> {
+        self.options
+            .get(name)
+            .or_else(|| self.arguments.first())
+            .map(|value| value.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing argument {name:?}"))
+    }
+}
+
+/// Select the part of an included file requested by the `region` and `lines`
+/// arguments of a directive.
+///
+/// Lines are counted from the start of the region, if any.
+fn select(content: &str, directive: &Directive) -> anyhow::Result<String> {
+    let content = match directive.options.get("region") {
+        Some(name) => select_region(content, name)
+            .ok_or_else(|| anyhow::anyhow!("Region {name:?} not found"))?,
+        None => content.to_owned(),
+    };
+
+    match directive.options.get("lines") {
+        Some(range) => {
+            let (start, end) = parse_line_range(range)
+                .ok_or_else(|| anyhow::anyhow!("Invalid line range {range:?}"))?;
+            Ok(content
+                .lines()
+                .enumerate()
+                .filter(|(index, _)| *index + 1 >= start && end.map_or(true, |end| *index < end))
+                .map(|(_, line)| format!("{line}\n"))
+                .collect())
+        },
+        None => Ok(content),
+    }
+}
+
+/// Return the lines of a named region, without region markers.
+///
+/// Returns `None` if the region is not found. If several regions have the
+/// same name, their lines are concatenated.
+fn select_region(content: &str, name: &str) -> Option<String> {
+    let mut output = None;
+    let mut inside = false;
+
+    for line in content.lines() {
+        match parse_anchor(line) {
+            Some((true, anchor)) if anchor == name => {
+                inside = true;
+                output.get_or_insert_with(String::new);
+            },
+            Some((false, anchor)) if anchor == name => inside = false,
+            // Markers of other regions are removed
+            Some(_) => {},
+            None if inside => {
+                let output = output.get_or_insert_with(String::new);
+                output.push_str(line);
+                output.push('\n');
+            },
+            None => {},
+        }
+    }
+
+    output
+}
+
+/// Remove the indentation common to all non-empty lines.
+fn dedent(content: &str) -> String {
+    let indent = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default();
+
+    content
+        .lines()
+        .map(|line| format!("{}\n", line.get(indent..).unwrap_or_default()))
+        .collect()
+}
+
+/// Return the length of the longest sequence of a character in a string.
+fn max_repeat(content: &str, c: char) -> usize {
+    content
+        .split(|other: char| other != c)
+        .map(|sequence| sequence.len())
+        .max()
+        .unwrap_or_default()
+}
+
+/// Escape the title of a `code` directive, to be quoted in the info string
+/// of a code fence.
+fn escape_title(title: &str) -> String {
+    title
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(['\n', '\r'], " ")
+}
+
+/// Parse a region marker (e.g. `// ANCHOR: setup`), and return whether it
+/// starts the region, and the name of the region.
+fn parse_anchor(line: &str) -> Option<(bool, &str)> {
+    let (is_start, rest) = match line.find(ANCHOR_END) {
+        Some(index) => (false, &line[index + ANCHOR_END.len()..]),
+        None => {
+            let index = line.find(ANCHOR_START)?;
+            (true, &line[index + ANCHOR_START.len()..])
+        },
+    };
+
+    rest.split_whitespace().next().map(|name| (is_start, name))
+}
+
+/// Parse a range of lines (e.g. `3-10`, `3-`, `-10` or `3`), and return the
+/// first and last lines, counted from 1.
+fn parse_line_range(input: &str) -> Option<(usize, Option<usize>)> {
+    let parse = |value: &str| value.trim().parse::<usize>().ok().filter(|&line| line > 0);
+
+    match input.split_once('-') {
+        Some((start, end)) => {
+            let start = match start.trim() {
+                "" => 1,
+                start => parse(start)?,
+            };
+            let end = match end.trim() {
+                "" => None,
+                end => Some(parse(end)?),
+            };
+            end.map_or(true, |end| start <= end).then_some((start, end))
+        },
+        None => parse(input).map(|line| (line, Some(line))),
+    }
+}
+
+/// Check if a path is located in the includes directory.
+pub(super) fn is_included_path(path: &Path, config: &Config) -> bool {
+    config
+        .includes_dir
+        .as_ref()
+        .is_some_and(|includes_dir| path.starts_with(includes_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::Directive;
+
+    #[test]
+    fn parse_directive() {
+        let cases = [
+            (
+                " include \"install.md\" ",
+                Some(Directive {
+                    name: "include".to_owned(),
+                    arguments: vec!["install.md".to_owned()],
+                    ..Default::default()
+                }),
+            ),
+            (
+                "include file=\"a b.md\" lines=3-10",
+                Some(Directive {
+                    name: "include".to_owned(),
+                    options: HashMap::from([
+                        ("file".to_owned(), "a b.md".to_owned()),
+                        ("lines".to_owned(), "3-10".to_owned()),
+                    ]),
+                    ..Default::default()
+                }),
+            ),
+            ("", None),
+            ("include \"install.md", None),
+            ("include =a", None),
+        ];
+
+        for (input, expected) in cases {
+            let result = Directive::parse(input).ok();
+            assert_eq!(
+                result, expected,
+                "\nDirective::parse({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn select_region() {
+        const CONTENT: &str = concat!(
+            "fn main() {\n",            //
+            "    // ANCHOR: setup\n",   //
+            "    let a = 1;\n",         //
+            "    // ANCHOR: print\n",   //
+            "    println!(\"{a}\");\n", //
+            "    // ANCHOR_END: print\n",
+            "    // ANCHOR_END: setup\n",
+            "}\n"
+        );
+
+        const CASES: [(&str, Option<&str>); 3] = [
+            ("setup", Some("    let a = 1;\n    println!(\"{a}\");\n")),
+            ("print", Some("    println!(\"{a}\");\n")),
+            ("unknown", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::select_region(CONTENT, input);
+            let expected = expected.map(|expected| expected.to_owned());
+            assert_eq!(
+                result, expected,
+                "\nselect_region({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn dedent() {
+        const CASES: [(&str, &str); 4] = [
+            ("", ""),
+            ("a\n  b\n", "a\n  b\n"),
+            ("    a\n\n      b\n", "a\n\n  b\n"),
+            ("\tif a {\n\t\tb();\n\t}", "if a {\n\tb();\n}\n"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::dedent(input);
+            assert_eq!(
+                result, expected,
+                "\ndedent({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn escape_title() {
+        const CASES: [(&str, &str); 3] = [
+            ("main.rs", "main.rs"),
+            (r#"a "b""#, r#"a \"b\""#),
+            ("a\\b\nc", r"a\\b c"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::escape_title(input);
+            assert_eq!(
+                result, expected,
+                "\nescape_title({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_line_range() {
+        const CASES: [(&str, Option<(usize, Option<usize>)>); 7] = [
+            ("3-10", Some((3, Some(10)))),
+            ("3-", Some((3, None))),
+            ("-10", Some((1, Some(10)))),
+            ("5", Some((5, Some(5)))),
+            ("10-3", None),
+            ("0", None),
+            ("a-b", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::parse_line_range(input);
+            assert_eq!(
+                result, expected,
+                "\nparse_line_range({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! List documents (e.g. PDF files) with their metadata.
+//!
+//! Metadata are extracted on a best-effort basis, without a full parser: the
+//! title, the subject and the page count of PDF files are read from their
+//! uncompressed objects, and those of office documents (e.g. `.docx`, `.odt`)
+//! from the metadata files of their archive.
+
+use std::{borrow::Cow, fs::File, io::Read, path::Path};
+
+use quick_xml::{events::Event, Reader};
+use zip::ZipArchive;
+
+use super::{Config, Entry, Error};
+
+/// Metadata of a document.
+#[derive(Debug, Default, PartialEq)]
+struct Metadata {
+    /// Title of the document.
+    title: Option<String>,
+
+    /// Description (or subject) of the document.
+    description: Option<String>,
+
+    /// Number of pages (or slides) of the document.
+    pages: Option<usize>,
+}
+
+/// List the documents of the input directories in the global data.
+///
+/// Documents are the files whose extension is listed in `documents.formats`.
+/// The list is saved in the global data under `site.files`, so that layouts
+/// can render download listings. Each item contains the `url`, the file
+/// `name`, the `format` (i.e. the extension) and the `size` in bytes of the
+/// document, and its `title`, `description` and `pages` when they are found.
+pub(super) fn list_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+    global_data: serde_json::Value,
+) -> Result<
+    (
+        impl Iterator<Item = Result<Entry, Error>>,
+        serde_json::Value,
+    ),
+    Error,
+> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let files: Vec<serde_json::Value> = entries
+        .iter()
+        .filter(|entry| config.documents.formats.contains(&entry.format))
+        .filter_map(|entry| {
+            let input_file = entry.input_file.as_ref()?;
+            let path = input_file.path();
+
+            let size = input_file.metadata().ok().map(|metadata| metadata.len());
+
+            let metadata = read_metadata(path, &entry.format)
+                .map_err(|error| tracing::warn!("Cannot read metadata of {:?}: {}", path, error))
+                .unwrap_or_default();
+
+            Some(serde_json::json!({
+                "url": entry.url,
+                "name": input_file.file_name().to_string_lossy(),
+                "format": entry.format,
+                "size": size,
+                "title": metadata.title,
+                "description": metadata.description,
+                "pages": metadata.pages,
+            }))
+        })
+        .collect();
+
+    // Keep other fields of the `site` variable (e.g. from global data)
+    let mut global_data = global_data.as_object().cloned().unwrap_or_default();
+    let site = global_data
+        .entry("site")
+        .or_insert_with(|| serde_json::Map::new().into());
+    if let Some(site) = site.as_object_mut() {
+        site.insert("files".to_owned(), files.into());
+    }
+
+    Ok((entries.into_iter().map(Ok), global_data.into()))
+}
+
+/// Read the metadata of a document, given its format.
+fn read_metadata(path: &Path, format: &str) -> anyhow::Result<Metadata> {
+    Ok(match format {
+        "pdf" => read_pdf(&std::fs::read(path)?),
+        "docx" | "pptx" | "xlsx" => {
+            let mut archive = ZipArchive::new(File::open(path)?)?;
+            let core = read_archive_file(&mut archive, "docProps/core.xml");
+            let app = read_archive_file(&mut archive, "docProps/app.xml");
+            Metadata {
+                title: xml_text(&core, "dc:title"),
+                description: xml_text(&core, "dc:description"),
+                pages: ["Pages", "Slides"]
+                    .iter()
+                    .find_map(|name| xml_text(&app, name)?.parse().ok()),
+            }
+        },
+        "odp" | "ods" | "odt" => {
+            let mut archive = ZipArchive::new(File::open(path)?)?;
+            let meta = read_archive_file(&mut archive, "meta.xml");
+            Metadata {
+                title: xml_text(&meta, "dc:title"),
+                description: xml_text(&meta, "dc:description"),
+                pages: xml_attribute(&meta, "meta:document-statistic", "meta:page-count")
+                    .and_then(|pages| pages.parse().ok()),
+            }
+        },
+        _ => Metadata::default(),
+    })
+}
+
+/// Read a file of a ZIP archive, or an empty string if it is missing.
+fn read_archive_file(archive: &mut ZipArchive<File>, name: &str) -> String {
+    let mut content = String::new();
+
+    if let Ok(mut file) = archive.by_name(name) {
+        let _ = file.read_to_string(&mut content);
+    }
+
+    content
+}
+
+/// Return the text of the first XML element with a given name (e.g.
+/// `dc:title`), if not empty.
+fn xml_text(xml: &str, name: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut is_inside = false;
+
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(bytes) if bytes.name().as_ref() == name.as_bytes() => is_inside = true,
+            Event::Text(text) if is_inside => {
+                let text = text.unescape().ok()?;
+                let text = text.trim();
+                return (!text.is_empty()).then(|| text.to_owned());
+            },
+            Event::End(_) if is_inside => return None,
+            Event::Eof => return None,
+            _ => {},
+        }
+    }
+}
+
+/// Return the value of an attribute of the first XML element with a given
+/// name.
+fn xml_attribute(xml: &str, name: &str, attribute: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(bytes) | Event::Empty(bytes)
+                if bytes.name().as_ref() == name.as_bytes() =>
+            {
+                return bytes
+                    .try_get_attribute(attribute)
+                    .ok()??
+                    .unescape_value()
+                    .ok()
+                    .map(Cow::into_owned);
+            },
+            Event::Eof => return None,
+            _ => {},
+        }
+    }
+}
+
+/// Read the metadata of a PDF file.
+///
+/// The title and the subject are read from the document information
+/// dictionary, and the pages are counted from the page objects. Objects of
+/// compressed object streams are not read.
+fn read_pdf(content: &[u8]) -> Metadata {
+    let pages = find_all(content, b"/Type")
+        .filter(|index| {
+            let rest = trim_start(&content[index + b"/Type".len()..]);
+            // Exclude page tree nodes (i.e. `/Type /Pages`)
+            rest.starts_with(b"/Page") && !rest.get(5).is_some_and(u8::is_ascii_alphanumeric)
+        })
+        .count();
+
+    Metadata {
+        title: pdf_string(content, b"/Title"),
+        description: pdf_string(content, b"/Subject"),
+        pages: (pages > 0).then_some(pages),
+    }
+}
+
+/// Return the first non-empty string value of a key in a PDF file.
+fn pdf_string(content: &[u8], key: &[u8]) -> Option<String> {
+    find_all(content, key).find_map(|index| {
+        let rest = trim_start(&content[index + key.len()..]);
+
+        let bytes = match rest {
+            [b'(', rest @ ..] => parse_literal_string(rest)?,
+            [b'<', rest @ ..] if !rest.starts_with(b"<") => parse_hex_string(rest)?,
+            _ => return None,
+        };
+
+        let text = decode_text_string(&bytes);
+        let text = text.trim();
+
+        (!text.is_empty()).then(|| text.to_owned())
+    })
+}
+
+/// Parse a PDF literal string (e.g. `(Title\))`), after the opening
+/// parenthesis.
+fn parse_literal_string(input: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut depth = 0;
+    let mut bytes = input.iter().copied().peekable();
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'\\' => match bytes.next()? {
+                b'n' => output.push(b'\n'),
+                b'r' => output.push(b'\r'),
+                b't' => output.push(b'\t'),
+                b'b' => output.push(0x08),
+                b'f' => output.push(0x0c),
+                // Line continuation
+                b'\r' => {
+                    bytes.next_if_eq(&b'\n');
+                },
+                b'\n' => {},
+                // Octal character code (e.g. `\351`)
+                digit @ b'0'..=b'7' => {
+                    let mut code = u32::from(digit - b'0');
+                    for _ in 0..2 {
+                        match bytes.next_if(|digit| matches!(digit, b'0'..=b'7')) {
+                            Some(digit) => code = code * 8 + u32::from(digit - b'0'),
+                            None => break,
+                        }
+                    }
+                    output.push(code as u8);
+                },
+                byte => output.push(byte),
+            },
+            b'(' => {
+                depth += 1;
+                output.push(byte);
+            },
+            b')' if depth == 0 => return Some(output),
+            b')' => {
+                depth -= 1;
+                output.push(byte);
+            },
+            _ => output.push(byte),
+        }
+    }
+
+    None
+}
+
+/// Parse a PDF hexadecimal string (e.g. `<FEFF0041>`), after the opening
+/// angle bracket.
+fn parse_hex_string(input: &[u8]) -> Option<Vec<u8>> {
+    let end = input.iter().position(|byte| *byte == b'>')?;
+
+    let digits: Vec<u8> = input[..end]
+        .iter()
+        .filter_map(|byte| char::from(*byte).to_digit(16))
+        .map(|digit| digit as u8)
+        .collect();
+
+    // A missing final digit is assumed to be 0
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+            .collect(),
+    )
+}
+
+/// Decode a PDF text string, encoded in UTF-16BE or UTF-8 if it starts with a
+/// byte order mark, or else in PDFDocEncoding (approximated by Latin-1).
+fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(bytes) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+
+    if let Some(bytes) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+
+    bytes.iter().copied().map(char::from).collect()
+}
+
+/// Return the positions of a byte sequence in a byte string.
+fn find_all<'a>(haystack: &'a [u8], needle: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .filter(move |(_, window)| *window == needle)
+        .map(|(index, _)| index)
+}
+
+/// Remove the leading whitespace of a byte string.
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metadata;
+
+    #[test]
+    fn read_pdf() {
+        const CASES: [(&str, Option<&str>, Option<&str>, Option<usize>); 4] = [
+            (
+                concat!(
+                    "1 0 obj << /Type /Pages /Kids [2 0 R 3 0 R] /Count 2 >> endobj\n",
+                    "2 0 obj << /Type /Page /Parent 1 0 R >> endobj\n",
+                    "3 0 obj << /Type/Page /Parent 1 0 R >> endobj\n",
+                    "4 0 obj << /Title (Annual \\(2024\\) report) /Subject (Results) >> endobj\n",
+                ),
+                Some("Annual (2024) report"),
+                Some("Results"),
+                Some(2),
+            ),
+            (
+                "<< /Title <FEFF00C9007400E9> >> << /Type /Page >>",
+                Some("Été"),
+                None,
+                Some(1),
+            ),
+            (
+                "<< /Title (Caf\\351) /Title () >>",
+                Some("Café"),
+                None,
+                None,
+            ),
+            ("<< /Title () /Type /Pages >>", None, None, None),
+        ];
+
+        for (input, title, description, pages) in CASES {
+            let result = super::read_pdf(input.as_bytes());
+            let expected = Metadata {
+                title: title.map(str::to_owned),
+                description: description.map(str::to_owned),
+                pages,
+            };
+            assert_eq!(
+                result, expected,
+                "\nread_pdf({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn xml_text() {
+        const CASES: [(&str, &str, Option<&str>); 4] = [
+            (
+                "<cp:coreProperties><dc:title>Report &amp; results</dc:title></cp:coreProperties>",
+                "dc:title",
+                Some("Report & results"),
+            ),
+            (
+                "<Properties><Pages>12</Pages></Properties>",
+                "Pages",
+                Some("12"),
+            ),
+            (
+                "<cp:coreProperties><dc:title/></cp:coreProperties>",
+                "dc:title",
+                None,
+            ),
+            ("<Properties><Pages>12</Pages></Properties>", "Slides", None),
+        ];
+
+        for (input, name, expected) in CASES {
+            let result = super::xml_text(input, name);
+            let expected = expected.map(str::to_owned);
+            assert_eq!(
+                result, expected,
+                "\nxml_text({input:?}, {name:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
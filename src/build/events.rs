@@ -0,0 +1,317 @@
+//! List events and generate an iCalendar file.
+
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+
+use super::{Config, Entry, Error};
+use crate::util::{
+    date_time::{is_date_only, parse_offset, DateTime},
+    url::site_base_url,
+};
+
+/// Identifier of the program that generates the iCalendar file.
+const PRODID: &str = concat!("-//Vitrine//Vitrine ", env!("CARGO_PKG_VERSION"), "//EN");
+
+/// Maximum length of a line of the iCalendar file, in bytes.
+const LINE_LENGTH: usize = 75;
+
+/// Key of the event in front matter data.
+const EVENT_KEY: &str = "event";
+
+/// Event of a page, in front matter data.
+#[derive(Debug, Deserialize)]
+struct EventData {
+    /// Start date of the event.
+    start: String,
+
+    /// End date of the event.
+    #[serde(default)]
+    end: Option<String>,
+
+    /// Location of the event.
+    #[serde(default)]
+    location: Option<String>,
+}
+
+/// Event of a page.
+#[derive(Debug)]
+struct Event {
+    /// URL of the page.
+    url: String,
+
+    /// Title of the page.
+    title: Option<String>,
+
+    /// Description of the page.
+    description: Option<String>,
+
+    /// Start date of the event.
+    start: DateTime,
+
+    /// End date of the event.
+    end: Option<DateTime>,
+
+    /// Determine whether the event lasts whole days (i.e. its dates have no
+    /// time). The end date of such events is inclusive.
+    all_day: bool,
+
+    /// Location of the event.
+    location: Option<String>,
+}
+
+impl Event {
+    /// Return the instant when the event is over.
+    fn ends_at(&self) -> DateTime {
+        let end = self.end.unwrap_or(self.start);
+
+        if self.all_day {
+            end.add_days(1)
+        } else {
+            end
+        }
+    }
+}
+
+/// List the events of pages in the global data, and generate an iCalendar
+/// file if `events` is specified in the configuration.
+///
+/// Events are saved in the global data under `site.events`, sorted by start
+/// date, and those that are not over yet under `site.upcoming_events`. Each
+/// item contains the `url`, `title` and `description` of the page, and the
+/// `start`, `end`, `all_day` and `location` of the event.
+///
+/// The generated file follows the [RFC 5545](https://www.rfc-editor.org/rfc/rfc5545) specification.
+pub(super) fn list_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+    global_data: serde_json::Value,
+) -> Result<
+    (
+        impl Iterator<Item = Result<Entry, Error>>,
+        serde_json::Value,
+    ),
+    Error,
+> {
+    let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    // The time zone is checked when validating the configuration
+    let timezone = config
+        .timezone
+        .as_ref()
+        .and_then(|timezone| parse_offset(timezone).ok());
+
+    let mut events: Vec<Event> = entries
+        .iter()
+        .filter(|entry| entry.format == "html")
+        .filter_map(|entry| {
+            let data = entry.data.as_ref()?;
+            let event = data.extra.get(EVENT_KEY)?;
+            Some((entry, data, event))
+        })
+        .map(|(entry, data, event)| -> anyhow::Result<Event> {
+            let event: EventData = serde_json::from_value(event.to_owned())?;
+
+            let parse = |date: &str| DateTime::parse_in_timezone(date, timezone);
+
+            Ok(Event {
+                url: entry.url.to_owned(),
+                title: data.title.to_owned(),
+                description: data
+                    .extra
+                    .get("description")
+                    .and_then(|description| description.as_str())
+                    .map(str::to_owned),
+                start: parse(event.start.as_str())?,
+                end: event.end.as_deref().map(&parse).transpose()?,
+                all_day: is_date_only(&event.start),
+                location: event.location,
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|error| Error::ListEvents { source: error })?;
+
+    events.sort_by(|x, y| x.start.cmp(&y.start).then_with(|| x.url.cmp(&y.url)));
+
+    let now = DateTime::from(match config.source_date_epoch {
+        Some(epoch) => SystemTime::UNIX_EPOCH + Duration::from_secs(epoch),
+        None => SystemTime::now(),
+    });
+
+    let items: Vec<serde_json::Value> = events.iter().map(event_item).collect();
+
+    let upcoming_items: Vec<serde_json::Value> = events
+        .iter()
+        .filter(|event| event.ends_at() > now)
+        .map(event_item)
+        .collect();
+
+    if let Some(events_config) = config.events.as_ref() {
+        entries.push(Entry {
+            url: events_config.url.to_owned(),
+            format: "ics".to_owned(),
+            content: Some(
+                render_calendar(&events, config, now)
+                    .map_err(|error| Error::ListEvents { source: error })?,
+            ),
+            ..Default::default()
+        });
+    }
+
+    // Keep other fields of the `site` variable (e.g. from global data)
+    let mut global_data = global_data.as_object().cloned().unwrap_or_default();
+    let site = global_data
+        .entry("site")
+        .or_insert_with(|| serde_json::Map::new().into());
+    if let Some(site) = site.as_object_mut() {
+        site.insert("events".to_owned(), items.into());
+        site.insert("upcoming_events".to_owned(), upcoming_items.into());
+    }
+
+    Ok((entries.into_iter().map(Ok), global_data.into()))
+}
+
+/// Convert an event to an item of the global data.
+fn event_item(event: &Event) -> serde_json::Value {
+    serde_json::json!({
+        "url": event.url,
+        "title": event.title,
+        "description": event.description,
+        "start": event.start.to_rfc3339(),
+        "end": event.end.map(DateTime::to_rfc3339),
+        "all_day": event.all_day,
+        "location": event.location,
+    })
+}
+
+/// Render the iCalendar file of events.
+fn render_calendar(events: &[Event], config: &Config, now: DateTime) -> anyhow::Result<String> {
+    // Events configuration is checked by the caller
+    let events_config = config.events.as_ref().unwrap();
+
+    let mut lines = Vec::from([
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        format!("PRODID:{PRODID}"),
+        "CALSCALE:GREGORIAN".to_owned(),
+    ]);
+
+    if let Some(title) = events_config.title.as_ref() {
+        lines.push(format!("X-WR-CALNAME:{}", escape_text(title)));
+    }
+
+    let stamp = now.to_utc().format("%Y%m%dT%H%M%SZ")?;
+
+    // URLs of events are absolute if the domain of the site is known
+    let url_prefix = site_base_url(config.site_url.as_deref(), &config.base_url)
+        .unwrap_or_else(|| config.base_url.to_owned());
+
+    for event in events {
+        let url = format!("{}{}", url_prefix, event.url);
+
+        lines.push("BEGIN:VEVENT".to_owned());
+        lines.push(format!("UID:{url}"));
+        lines.push(format!("DTSTAMP:{stamp}"));
+
+        if event.all_day {
+            lines.push(format!(
+                "DTSTART;VALUE=DATE:{}",
+                event.start.format("%Y%m%d")?
+            ));
+            lines.push(format!(
+                "DTEND;VALUE=DATE:{}",
+                event.ends_at().format("%Y%m%d")?
+            ));
+        } else {
+            let utc = |date: DateTime| date.to_utc().format("%Y%m%dT%H%M%SZ");
+            lines.push(format!("DTSTART:{}", utc(event.start)?));
+            if let Some(end) = event.end {
+                lines.push(format!("DTEND:{}", utc(end)?));
+            }
+        }
+
+        if let Some(title) = event.title.as_ref() {
+            lines.push(format!("SUMMARY:{}", escape_text(title)));
+        }
+
+        if let Some(description) = event.description.as_ref() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+
+        if let Some(location) = event.location.as_ref() {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+
+        lines.push(format!("URL:{url}"));
+        lines.push("END:VEVENT".to_owned());
+    }
+
+    lines.push("END:VCALENDAR".to_owned());
+
+    // Lines are delimited by CRLF
+    Ok(lines.iter().map(|line| fold_line(line) + "\r\n").collect())
+}
+
+/// Escape special characters of a text value.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Split a line longer than 75 bytes into several lines.
+///
+/// Continuation lines start with a space. Lines are not split inside UTF-8
+/// characters.
+fn fold_line(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LENGTH {
+            result.push_str("\r\n ");
+            length = 1;
+        }
+        result.push(c);
+        length += c.len_utf8();
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn escape_text() {
+        const CASES: [(&str, &str); 4] = [
+            ("Meetup", "Meetup"),
+            ("Room 1; Paris, France", "Room 1\\; Paris\\, France"),
+            ("C:\\Temp", "C:\\\\Temp"),
+            ("Line 1\nLine 2", "Line 1\\nLine 2"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::escape_text(input);
+            assert_eq!(
+                result, expected,
+                "\nescape_text({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn fold_line() {
+        let line = "a".repeat(80);
+        let expected = format!("{}\r\n {}", "a".repeat(75), "a".repeat(5));
+        assert_eq!(super::fold_line(&line), expected);
+
+        // Multibyte characters are not split
+        let line = format!("{}é", "a".repeat(74));
+        let expected = format!("{}\r\n é", "a".repeat(74));
+        assert_eq!(super::fold_line(&line), expected);
+
+        assert_eq!(super::fold_line("SUMMARY:Meetup"), "SUMMARY:Meetup");
+    }
+}
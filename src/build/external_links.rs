@@ -0,0 +1,149 @@
+//! Decorate external links.
+
+use super::{Config, Entry, Error};
+use crate::util::url::Url;
+
+/// Add attributes to external links in a [`Entry`].
+///
+/// If `external_links` is specified in the configuration, this function adds
+/// link types to the `rel` attribute, and optionally the `target="_blank"`
+/// attribute and a class, to `<a>` elements linking to another site. A link is
+/// external if it has a host (e.g. `https://example.com/`) different from the
+/// hosts of `base_url` and `sitemap.url_prefix`.
+pub(super) fn decorate_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
+    let Some(external_links_config) = config.external_links.as_ref() else {
+        return Ok(entry);
+    };
+
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(entry);
+    };
+
+    // Hosts of the site
+    let site_hosts: Vec<String> = std::iter::once(config.base_url.as_str())
+        .chain(
+            config
+                .sitemap
+                .as_ref()
+                .map(|sitemap| sitemap.url_prefix.as_str()),
+        )
+        .filter_map(host)
+        .collect();
+
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("a[href]", |element| {
+            let href = element.get_attribute("href").unwrap_or_default();
+
+            let Some(link_host) = host(&href) else {
+                return Ok(());
+            };
+
+            if site_hosts.contains(&link_host) {
+                return Ok(());
+            }
+
+            let rel = add_tokens(
+                element.get_attribute("rel").unwrap_or_default(),
+                &external_links_config.rel,
+            );
+            if !rel.is_empty() {
+                element.set_attribute("rel", &rel)?;
+            }
+
+            if external_links_config.target_blank && !element.has_attribute("target") {
+                element.set_attribute("target", "_blank")?;
+            }
+
+            if let Some(class) = external_links_config.class.as_ref() {
+                let class = add_tokens(element.get_attribute("class").unwrap_or_default(), class);
+                element.set_attribute("class", &class)?;
+            }
+
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })
+    .map_err(|error| Error::DecorateExternalLinks {
+        input_path: entry.input_path_buf(),
+        source: error.into(),
+    })?;
+
+    Ok(Entry {
+        content: Some(content),
+        ..entry
+    })
+}
+
+/// Return the normalized host of an absolute web URL.
+///
+/// Returns `None` for URLs without host and for non-web schemes (e.g.
+/// `mailto:`).
+fn host<S>(url: S) -> Option<String>
+where
+    S: AsRef<str>,
+{
+    let url = Url::from(url.as_ref().trim()).normalize();
+
+    if url
+        .scheme
+        .as_ref()
+        .is_some_and(|scheme| !matches!(scheme.as_str(), "http" | "https"))
+    {
+        return None;
+    }
+
+    url.authority
+        .map(|authority| authority.host)
+        .filter(|host| !host.is_empty())
+}
+
+/// Add space-separated tokens to a list, skipping existing ones.
+fn add_tokens<S, T>(list: S, tokens: T) -> String
+where
+    S: AsRef<str>,
+    T: AsRef<str>,
+{
+    let mut list: Vec<&str> = list.as_ref().split_whitespace().collect();
+
+    for token in tokens.as_ref().split_whitespace() {
+        if !list.contains(&token) {
+            list.push(token);
+        }
+    }
+
+    list.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn host() {
+        const CASES: [(&str, Option<&str>); 7] = [
+            ("https://Example.com/page", Some("example.com")),
+            ("//cdn.example.com/a.js", Some("cdn.example.com")),
+            ("http://user@example.com:8080", Some("example.com")),
+            ("/blog", None),
+            ("../page", None),
+            ("mailto:someone@example.com", None),
+            ("", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::host(input);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\nhost({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn add_tokens() {
+        assert_eq!(
+            super::add_tokens("nofollow noopener", "noopener noreferrer"),
+            "nofollow noopener noreferrer"
+        );
+        assert_eq!(super::add_tokens("", "external"), "external");
+    }
+}
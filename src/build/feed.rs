@@ -1,11 +1,14 @@
 //! Generate feeds.
 
-use chrono::{DateTime, Utc};
 use quick_xml::se::Serializer;
 use serde::Serialize;
 
 use super::{Config, Entry, Error};
-use crate::util::feed::atom;
+use crate::util::{
+    date_time::{parse_offset, DateTime},
+    feed::atom,
+    url::normalize_url,
+};
 
 /// Preamble of the XML file.
 const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>";
@@ -19,17 +22,47 @@ pub(super) fn create_feeds_entries(
 ) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
     let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
 
-    for feed_config in config.feeds.iter() {
-        let mut feed_entries: Vec<atom::Entry> = entries
+    // The time zone is checked when validating the configuration
+    let timezone = config
+        .timezone
+        .as_ref()
+        .and_then(|timezone| parse_offset(timezone).ok());
+
+    // Feeds of collections contain the pages of the collection only
+    let feed_configs = config
+        .feeds
+        .iter()
+        .map(|feed_config| (feed_config, None))
+        .chain(config.collections.iter().filter_map(|collection| {
+            collection
+                .feed
+                .as_ref()
+                .map(|feed_config| (feed_config, Some(collection)))
+        }));
+
+    for (feed_config, collection) in feed_configs {
+        // Generate feed only for pages
+        let mut pages: Vec<&Entry> = entries
             .iter()
+            .filter(|entry| {
+                entry.format == "html"
+                    && collection.map_or(true, |collection| {
+                        super::collections::collection_name(entry) == Some(collection.name.as_str())
+                    })
+            })
+            .collect();
+
+        // Feeds of collections follow the order of the collection
+        if let Some(collection) = collection {
+            super::collections::sort_entries(&mut pages, collection)
+                .map_err(|error| Error::CreateFeed { source: error })?;
+        }
+
+        let mut feed_entries: Vec<atom::Entry> = pages
+            .into_iter()
             .try_fold(
                 Vec::new(),
                 |mut feed_entries, entry| -> anyhow::Result<Vec<atom::Entry>> {
-                    // Generate feed only for pages
-                    if entry.format != "html" {
-                        return Ok(feed_entries);
-                    }
-
                     let include = match feed_config.filter.as_ref() {
                         Some(filter) => {
                             let data = serde_json::to_value(&entry.data)?;
@@ -48,9 +81,9 @@ pub(super) fn create_feeds_entries(
                         category: Vec::new(),
                         content: None,
                         contributor: Vec::new(),
-                        id: entry.url.to_owned(),
+                        id: normalize_url(format!("{}{}", config.base_url, entry.url)),
                         link: Vec::from([atom::Link {
-                            href: entry.url.to_owned(),
+                            href: normalize_url(format!("{}{}", config.base_url, entry.url)),
                             ..Default::default()
                         }]),
                         published: None,
@@ -60,17 +93,17 @@ pub(super) fn create_feeds_entries(
                         updated: entry
                             .data
                             .as_ref()
-                            .and_then(|data| data.date.to_owned())
+                            .and_then(|data| data.updated.as_ref().or(data.date.as_ref()))
+                            .map(|date| {
+                                // Atom requires RFC 3339 dates
+                                DateTime::parse_in_timezone(date, timezone)
+                                    .map(|date| date.to_rfc3339())
+                                    .unwrap_or_else(|_| date.to_owned())
+                            })
                             .or_else(|| {
-                                entry
-                                    .input_file
-                                    .as_ref()
-                                    .and_then(|dir_entry| dir_entry.metadata().ok())
-                                    .and_then(|metadata| metadata.modified().ok())
-                                    .map(|date| {
-                                        let date: DateTime<Utc> = date.into();
-                                        format!("{}", date.format("%+"))
-                                    })
+                                entry.modified(config).map(|date| {
+                                    DateTime::from(date).to_timezone(timezone).to_rfc3339()
+                                })
                             })
                             .unwrap_or_default(),
                         title: entry
@@ -86,8 +119,10 @@ pub(super) fn create_feeds_entries(
             )
             .map_err(|error| Error::CreateFeed { source: error })?;
 
-        // Reverse chronological order
-        feed_entries.sort_by(|x, y| y.updated.cmp(&x.updated));
+        // Reverse chronological order, unless the feed follows a collection
+        if collection.is_none() {
+            feed_entries.sort_by(|x, y| y.updated.cmp(&x.updated));
+        }
 
         let feed = atom::Feed {
             xmlns: atom::XMLNS,
@@ -123,12 +158,9 @@ pub(super) fn create_feeds_entries(
                 text: text.to_owned(),
                 ..Default::default()
             }),
-            icon: feed_config.icon.to_owned(),
-            id: feed_config
-                .id
-                .to_owned()
-                .unwrap_or_else(|| feed_config.url.to_owned()),
-            logo: feed_config.logo.to_owned(),
+            icon: feed_config.icon.as_ref().map(normalize_url),
+            id: normalize_url(feed_config.id.as_ref().unwrap_or(&feed_config.url)),
+            logo: feed_config.logo.as_ref().map(normalize_url),
             rights: feed_config.rights.to_owned(),
             subtitle: feed_config.subtitle.to_owned(),
             title: feed_config.title.to_owned(),
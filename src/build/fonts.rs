@@ -0,0 +1,557 @@
+//! Self-host remote fonts, subset to the characters used by the site.
+//!
+//! This module runs `pyftsubset` of [fontTools](https://github.com/fonttools/fonttools)
+//! under the hood.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::OnceLock,
+};
+
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use super::{
+    images::remote_file_name, preload::font_type, url::rewrite_css_urls, Config, Entry, Error,
+};
+use crate::{
+    config::{FontConfig, FontsConfig},
+    util::{command::run, http},
+};
+
+/// Font face of a remote font.
+#[derive(Debug, PartialEq)]
+struct FontFace {
+    /// Family name (e.g. `Inter`).
+    family: String,
+
+    /// Style (e.g. `normal` or `italic`).
+    style: String,
+
+    /// Weight (e.g. `400`).
+    weight: String,
+
+    /// Range of characters of the face (e.g. `U+0000-00FF`), if any.
+    ///
+    /// Stylesheets such as Google Fonts split a font into several faces of the
+    /// same family, weight and style, which differ by their range.
+    unicode_range: Option<String>,
+
+    /// URL of the font file.
+    url: String,
+}
+
+/// Download the fonts listed in `fonts.items`, subset them to the characters
+/// of the rendered pages, and generate a stylesheet of `@font-face` rules.
+///
+/// Fonts are downloaded once into the cache directory. Subset fonts are WOFF2
+/// files, written to `{fonts.url}/{family}-{weight}-{style}.{hash}.woff2`,
+/// where `hash` is a hash of their content. If `render` is `false`, fonts are
+/// not processed, since pages are not rendered.
+pub(super) fn create_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+    render: bool,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    // The cache directory is checked when validating the configuration
+    let (Some(fonts_config), Some(cache_dir)) = (
+        config.fonts.as_ref().filter(|_| render),
+        config.cache_dir.as_ref(),
+    ) else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    let dir = cache_dir.join("fonts");
+
+    let mut characters = BTreeSet::new();
+
+    for content in entries
+        .iter()
+        .filter(|entry| entry.format == "html")
+        .filter_map(|entry| entry.content.as_ref())
+    {
+        collect_characters(content, &mut characters).map_err(|error| Error::CreateFonts {
+            source: error.context("While collecting characters of pages"),
+        })?;
+    }
+
+    let text: String = characters.into_iter().collect();
+
+    let url_prefix = fonts_config.url.trim_end_matches('/');
+
+    let mut stylesheet = String::new();
+
+    for item in fonts_config.items.iter() {
+        let faces = font_faces(item, &dir).map_err(|error| Error::CreateFonts {
+            source: error.context(format!("While reading font {:?}", item.url)),
+        })?;
+
+        for face in faces {
+            let file_name =
+                subset(&face, &text, fonts_config, &dir).map_err(|error| Error::CreateFonts {
+                    source: error.context(format!("While subsetting font {:?}", face.url)),
+                })?;
+
+            let url = format!("{url_prefix}/{file_name}");
+
+            stylesheet.push_str(&font_face_rule(
+                &face,
+                &format!("{}{}", config.base_url, url),
+                &fonts_config.display,
+            ));
+
+            let Some(Ok(input_file)) = WalkDir::new(dir.join("subsets").join(&file_name))
+                .into_iter()
+                .next()
+            else {
+                continue;
+            };
+
+            entries.push(Entry {
+                url,
+                input_file: Some(input_file),
+                ..Default::default()
+            });
+        }
+    }
+
+    entries.push(Entry {
+        url: fonts_config.stylesheet_url.to_owned(),
+        format: "css".to_owned(),
+        content: Some(stylesheet),
+        ..Default::default()
+    });
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Attributes whose values are displayed by browsers.
+const DISPLAYED_ATTRIBUTES: [&str; 4] = ["alt", "title", "placeholder", "value"];
+
+/// Add the characters of the text of a HTML document to a set.
+///
+/// The values of displayed attributes (e.g. `alt` or `title`) are included.
+/// Character references (e.g. `&#233;` or `&eacute;`) are decoded. Printable
+/// ASCII characters are always added, so that subset fonts can display
+/// punctuation and digits inserted by scripts.
+fn collect_characters<S>(content: S, characters: &mut BTreeSet<char>) -> anyhow::Result<()>
+where
+    S: AsRef<str>,
+{
+    characters.extend(' '..='~');
+
+    let text = RefCell::new(String::new());
+
+    lol_html::rewrite_str(content.as_ref(), lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("*", |element| {
+            // Hidden inputs are not displayed
+            if element.tag_name() == "input"
+                && element
+                    .get_attribute("type")
+                    .is_some_and(|value| value.eq_ignore_ascii_case("hidden"))
+            {
+                return Ok(());
+            }
+
+            let mut text = text.borrow_mut();
+            for name in DISPLAYED_ATTRIBUTES {
+                if let Some(value) = element.get_attribute(name) {
+                    text.push(' ');
+                    text.push_str(&value);
+                }
+            }
+            Ok(())
+        })],
+        document_content_handlers: vec![lol_html::doc_text!(|chunk| {
+            text.borrow_mut().push_str(chunk.as_str());
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    let text = text.into_inner();
+
+    characters.extend(decode_character_references(&text).chars());
+
+    Ok(())
+}
+
+/// Decode numeric (e.g. `&#233;` or `&#xE9;`) and named (e.g. `&eacute;`)
+/// character references.
+///
+/// Unknown or malformed references are left unchanged.
+fn decode_character_references(text: &str) -> String {
+    static NAMED_REFERENCES: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+
+    // Names are stored without `&` and `;`, and legacy references without
+    // semicolon (e.g. `&amp`) are not decoded
+    let named_references = NAMED_REFERENCES.get_or_init(|| {
+        entities::ENTITIES
+            .iter()
+            .filter_map(|entity| {
+                let name = entity.entity.strip_prefix('&')?.strip_suffix(';')?;
+                Some((name, entity.characters))
+            })
+            .collect()
+    });
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(index) = rest.find('&') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+
+        let length = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '#')
+            .unwrap_or(rest.len());
+
+        let decoded = match rest[..length].strip_prefix('#') {
+            Some(number) => {
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => number.parse().ok(),
+                };
+                code.and_then(char::from_u32).map(String::from)
+            },
+            None => named_references
+                .get(&rest[..length])
+                .map(|characters| characters.to_string()),
+        }
+        .filter(|_| rest[length..].starts_with(';'));
+
+        match decoded {
+            Some(characters) => {
+                result.push_str(&characters);
+                rest = &rest[length + 1..];
+            },
+            None => result.push('&'),
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Return the font faces of a font item.
+///
+/// The URL of an item is either a font file (e.g. `.ttf` or `.woff2`), or a
+/// stylesheet of `@font-face` rules (e.g. of Google Fonts), which is
+/// downloaded into `dir`.
+fn font_faces(item: &FontConfig, dir: &Path) -> anyhow::Result<Vec<FontFace>> {
+    if font_type(&item.url).is_some() {
+        // The family is checked when validating the configuration
+        return Ok(vec![FontFace {
+            family: item.family.to_owned().unwrap_or_default(),
+            style: item.style.to_owned(),
+            weight: item.weight.to_owned(),
+            unicode_range: None,
+            url: item.url.to_owned(),
+        }]);
+    }
+
+    let path = download(&item.url, &dir.join("downloads"))?;
+    let css = std::fs::read_to_string(path)?;
+
+    let mut faces = parse_font_faces(&css);
+
+    // The family of the configuration takes precedence
+    if let Some(family) = item.family.as_ref() {
+        for face in faces.iter_mut() {
+            face.family = family.to_owned();
+        }
+    }
+
+    if faces.is_empty() {
+        anyhow::bail!("No @font-face rule found");
+    }
+
+    Ok(faces)
+}
+
+/// Parse the `@font-face` rules of a stylesheet.
+///
+/// Only the first URL of each `src` descriptor is kept.
+fn parse_font_faces(css: &str) -> Vec<FontFace> {
+    const AT_RULE: &str = "@font-face";
+
+    let mut faces = Vec::new();
+    let mut rest = css;
+
+    while let Some(index) = rest.find(AT_RULE) {
+        rest = &rest[index + AT_RULE.len()..];
+
+        let Some(block) = rest
+            .split_once('{')
+            .and_then(|(_, block)| block.split_once('}'))
+            .map(|(block, _)| block)
+        else {
+            break;
+        };
+
+        let descriptor = |name: &str| {
+            block
+                .split(';')
+                .filter_map(|declaration| declaration.split_once(':'))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.trim().to_owned())
+        };
+
+        let url = descriptor("src").and_then(|src| {
+            let url = RefCell::new(None);
+            rewrite_css_urls(&src, |href| {
+                url.borrow_mut().get_or_insert_with(|| href.to_owned());
+                None
+            });
+            url.into_inner()
+        });
+
+        let (Some(family), Some(url)) = (descriptor("font-family"), url) else {
+            continue;
+        };
+
+        faces.push(FontFace {
+            family: family.trim_matches(['"', '\'']).to_owned(),
+            style: descriptor("font-style").unwrap_or_else(|| "normal".to_owned()),
+            weight: descriptor("font-weight").unwrap_or_else(|| "400".to_owned()),
+            unicode_range: descriptor("unicode-range"),
+            url,
+        });
+    }
+
+    faces
+}
+
+/// Subset a font face to the characters of `text`, and return the file name
+/// of the WOFF2 file written in `{dir}/subsets`.
+///
+/// Subset fonts are identified by a hash of the font URL and of the text, so
+/// that fonts are only subset when the text changes.
+fn subset(
+    face: &FontFace,
+    text: &str,
+    fonts_config: &FontsConfig,
+    dir: &Path,
+) -> anyhow::Result<String> {
+    let input_path = download(&face.url, &dir.join("downloads"))?;
+
+    let subsets_dir = dir.join("subsets");
+    std::fs::create_dir_all(&subsets_dir)?;
+
+    let key = format!("{:x}", Sha256::digest(format!("{}\n{}", face.url, text)));
+    let output_path = subsets_dir.join(format!("{key}.woff2"));
+
+    if !output_path.exists() {
+        let text_path = subsets_dir.join(format!("{key}.txt"));
+        std::fs::write(&text_path, text)?;
+
+        let mut command = Command::new(&fonts_config.command);
+
+        command
+            .arg(&input_path)
+            .arg(format!("--text-file={}", text_path.display()))
+            .arg("--flavor=woff2")
+            .arg(format!("--output-file={}", output_path.display()));
+
+        let result = run(&mut command, None);
+
+        let _ = std::fs::remove_file(&text_path);
+
+        if let Err(error) = result {
+            let _ = std::fs::remove_file(&output_path);
+            return Err(error);
+        }
+    }
+
+    // Fingerprint the file, so that it can be cached forever
+    let hash = format!("{:x}", Sha256::digest(std::fs::read(&output_path)?));
+
+    let file_name = format!(
+        "{}-{}-{}.{}.woff2",
+        slug::slugify(&face.family),
+        slug::slugify(&face.weight),
+        slug::slugify(&face.style),
+        &hash[..16]
+    );
+
+    let file_path = subsets_dir.join(&file_name);
+
+    if !file_path.exists() {
+        std::fs::copy(&output_path, &file_path)?;
+    }
+
+    Ok(file_name)
+}
+
+/// Format a `@font-face` rule for a subset font.
+///
+/// The `unicode-range` descriptor is kept, so that faces of the same family,
+/// weight and style do not override each other.
+fn font_face_rule(face: &FontFace, url: &str, display: &str) -> String {
+    let unicode_range = face
+        .unicode_range
+        .as_ref()
+        .map(|unicode_range| format!("  unicode-range: {unicode_range};\n"))
+        .unwrap_or_default();
+
+    format!(
+        "@font-face {{\n  font-family: {};\n  font-style: {};\n  font-weight: {};\n  \
+         font-display: {};\n  src: url({}) format(\"woff2\");\n{}}}\n",
+        css_string(&face.family),
+        face.style,
+        face.weight,
+        display,
+        css_string(url),
+        unicode_range
+    )
+}
+
+/// Quote a CSS string.
+fn css_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Download a remote file into `dir`, unless it was downloaded before, and
+/// return its path.
+fn download(url: &str, dir: &Path) -> anyhow::Result<PathBuf> {
+    let path = dir.join(remote_file_name(url));
+
+    if !path.exists() {
+        tracing::info!("Downloading font {:?}", url);
+        http::download(url, &path)?;
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::FontFace;
+
+    #[test]
+    fn collect_characters() {
+        let mut result = BTreeSet::new();
+
+        super::collect_characters(
+            "<p title=\"Ω\" class=\"Ж\">Caf&#233; &#x2014; ĉu&rsquo;</p><img \
+             alt=\"&Auml;\"><input placeholder=\"ñ\" value=\"ß\"><input type=\"hidden\" \
+             value=\"Ю\">",
+            &mut result,
+        )
+        .unwrap();
+
+        for c in ['a', 'é', '—', 'ĉ', '’', '~', 'Ω', 'Ä', 'ñ', 'ß'] {
+            assert!(result.contains(&c), "\ncollect_characters() expected {c:?}");
+        }
+
+        for c in ['Ж', 'Ю'] {
+            assert!(
+                !result.contains(&c),
+                "\ncollect_characters() unexpected {c:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn decode_character_references() {
+        const CASES: [(&str, &str); 7] = [
+            ("Caf&#233;", "Café"),
+            ("&#x2014;&#X41;", "—A"),
+            ("&amp; &#;", "& &#;"),
+            ("&#xFFFFFFFF; &#12", "&#xFFFFFFFF; &#12"),
+            ("Caf&eacute;&nbsp;l&rsquo;a", "Café\u{a0}l’a"),
+            ("&unknown; &amp", "&unknown; &amp"),
+            ("a & b; &lt;", "a & b; <"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::decode_character_references(input);
+            assert_eq!(
+                result, expected,
+                "\ndecode_character_references({input:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn font_face_rule() {
+        let face = FontFace {
+            family: "My \"Font\"".to_owned(),
+            style: "normal".to_owned(),
+            weight: "400".to_owned(),
+            unicode_range: None,
+            url: "https://example.com/a.ttf".to_owned(),
+        };
+
+        let result = super::font_face_rule(&face, "/fonts/a.woff2", "swap");
+
+        assert_eq!(
+            result,
+            "@font-face {\n  font-family: \"My \\\"Font\\\"\";\n  font-style: normal;\n  \
+             font-weight: 400;\n  font-display: swap;\n  src: url(\"/fonts/a.woff2\") \
+             format(\"woff2\");\n}\n"
+        );
+    }
+
+    #[test]
+    fn parse_font_faces() {
+        const CSS: &str = "/* latin */\n@font-face {\n  font-family: 'Inter';\n  font-style: \
+                           italic;\n  font-weight: 700;\n  src: \
+                           url(https://fonts.gstatic.com/s/inter/v1/a.ttf) \
+                           format('truetype');\n}\n@font-face { font-family: \"Mono\"; src: \
+                           local(\"Mono\"), url(\"https://example.com/mono.woff2\"); }\n";
+
+        let result = super::parse_font_faces(CSS);
+
+        assert_eq!(result, [
+            FontFace {
+                family: "Inter".to_owned(),
+                style: "italic".to_owned(),
+                weight: "700".to_owned(),
+                unicode_range: None,
+                url: "https://fonts.gstatic.com/s/inter/v1/a.ttf".to_owned(),
+            },
+            FontFace {
+                family: "Mono".to_owned(),
+                style: "normal".to_owned(),
+                weight: "400".to_owned(),
+                unicode_range: None,
+                url: "https://example.com/mono.woff2".to_owned(),
+            },
+        ]);
+    }
+
+    #[test]
+    fn unicode_range() {
+        const CSS: &str = "/* cyrillic */\n@font-face {\n  font-family: 'Inter';\n  font-weight: \
+                           400;\n  src: url(https://example.com/cyrillic.woff2);\n  \
+                           unicode-range: U+0400-045F;\n}\n/* latin */\n@font-face {\n  \
+                           font-family: 'Inter';\n  font-weight: 400;\n  src: \
+                           url(https://example.com/latin.woff2);\n  unicode-range: \
+                           U+0000-00FF, U+2014;\n}\n";
+
+        let faces = super::parse_font_faces(CSS);
+
+        let result: Vec<String> = faces
+            .iter()
+            .map(|face| super::font_face_rule(face, "/fonts/a.woff2", "swap"))
+            .collect();
+
+        assert_eq!(result, [
+            "@font-face {\n  font-family: \"Inter\";\n  font-style: normal;\n  font-weight: \
+             400;\n  font-display: swap;\n  src: url(\"/fonts/a.woff2\") format(\"woff2\");\n  \
+             unicode-range: U+0400-045F;\n}\n",
+            "@font-face {\n  font-family: \"Inter\";\n  font-style: normal;\n  font-weight: \
+             400;\n  font-display: swap;\n  src: url(\"/fonts/a.woff2\") format(\"woff2\");\n  \
+             unicode-range: U+0000-00FF, U+2014;\n}\n",
+        ]);
+    }
+}
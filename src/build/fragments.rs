@@ -0,0 +1,265 @@
+//! Check URL fragments of links.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use super::{Config, Entry, Error};
+use crate::{
+    check::Diagnostic,
+    util::url::{Url, UrlPath},
+};
+
+/// Selector of elements that can be the target of a fragment.
+const TARGET_SELECTOR: &str = "[id], a[name]";
+
+/// Selector of links that may contain a fragment.
+const LINK_SELECTOR: &str = "a[href], area[href]";
+
+/// Check that links point to existing elements of the generated pages.
+///
+/// This function first builds an index of the `id` attributes (and `name`
+/// attributes of `<a>` elements) of each HTML page. Then, it reports links
+/// which fragment (e.g. `/blog/#intro` or `#intro`) does not match any
+/// element of the target page. Links to other sites or to files that are not
+/// HTML pages are ignored.
+pub(super) fn check_entries(entries: &[Entry], config: &Config) -> Result<Vec<Diagnostic>, Error> {
+    let base_url = config.base_url.to_owned();
+
+    // Create a mapping from page URLs to element identifiers
+    let index: HashMap<String, HashSet<String>> = entries
+        .iter()
+        .filter(|entry| entry.format == "html")
+        .map(|entry| {
+            let ids = index_entry(entry)?;
+            Ok((page_key(&entry.url).to_owned(), ids))
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let mut diagnostics = Vec::new();
+
+    for entry in entries.iter().filter(|entry| entry.format == "html") {
+        let Some(content) = entry.content.as_ref() else {
+            continue;
+        };
+
+        let page_dir = entry.base_dir();
+
+        let links = find_links(content).map_err(|error| Error::CheckFragments {
+            input_path: entry.input_path_buf(),
+            source: error,
+        })?;
+
+        for href in links {
+            let url = Url::from(href.trim());
+
+            let Some(fragment) = url.fragment.as_ref() else {
+                continue;
+            };
+
+            // An empty fragment or `#top` refer to the top of the page
+            let fragment = percent_decode(fragment);
+            if fragment.is_empty() || fragment.eq_ignore_ascii_case("top") {
+                continue;
+            }
+
+            if url.scheme.is_some() || url.authority.is_some() {
+                continue;
+            }
+
+            let target = if url.path.as_str().is_empty() {
+                // Same page, e.g. `#intro`
+                entry.url.to_owned()
+            } else {
+                let path = url.path.as_str();
+
+                let path = if base_url.is_empty() {
+                    path
+                } else {
+                    path.strip_prefix(&base_url)
+                        .filter(|path| path.starts_with('/'))
+                        .unwrap_or(path)
+                };
+
+                page_dir.join(&UrlPath::from(path)).to_string()
+            };
+
+            let Some(ids) = index.get(page_key(&target)) else {
+                continue;
+            };
+
+            if !ids.contains(&fragment) {
+                diagnostics.push(Diagnostic::new(
+                    "broken-fragment",
+                    format!("Link {href:?} points to a missing element"),
+                    entry.url.to_owned(),
+                    entry.input_path_buf(),
+                ));
+            }
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// Collect the identifiers of elements of a [`Entry`].
+fn index_entry(entry: &Entry) -> Result<HashSet<String>, Error> {
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(HashSet::new());
+    };
+
+    find_ids(content).map_err(|error| Error::CheckFragments {
+        input_path: entry.input_path_buf(),
+        source: error,
+    })
+}
+
+/// Find the identifiers of elements in HTML code.
+fn find_ids<S>(content: S) -> anyhow::Result<HashSet<String>>
+where
+    S: AsRef<str>,
+{
+    let ids = RefCell::new(HashSet::new());
+
+    lol_html::rewrite_str(content.as_ref(), lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!(TARGET_SELECTOR, |element| {
+            let id = element
+                .get_attribute("id")
+                .or_else(|| element.get_attribute("name"))
+                .unwrap_or_default();
+            ids.borrow_mut().insert(id);
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(ids.into_inner())
+}
+
+/// Find the URLs of links containing a fragment in HTML code.
+fn find_links<S>(content: S) -> anyhow::Result<Vec<String>>
+where
+    S: AsRef<str>,
+{
+    let links = RefCell::new(Vec::new());
+
+    lol_html::rewrite_str(content.as_ref(), lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!(LINK_SELECTOR, |element| {
+            if let Some(href) = element
+                .get_attribute("href")
+                .filter(|href| href.contains('#'))
+            {
+                links.borrow_mut().push(href);
+            }
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(links.into_inner())
+}
+
+/// Return the key of a page URL in the identifier index.
+///
+/// Trailing slashes are ignored, so that `/blog` and `/blog/` refer to the
+/// same page.
+fn page_key(url: &str) -> &str {
+    url.trim_end_matches('/')
+}
+
+/// Decode percent-encoded octets of a fragment (e.g. `caf%C3%A9`).
+///
+/// Invalid percent-encodings are left unchanged.
+pub(super) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let octet = match bytes[i] {
+            b'%' => value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+
+        match octet {
+            Some(octet) => {
+                result.push(octet);
+                i += 3;
+            },
+            None => {
+                result.push(bytes[i]);
+                i += 1;
+            },
+        }
+    }
+
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Entry;
+    use crate::config::Config;
+
+    #[test]
+    fn check_entries() {
+        let entries = [
+            Entry {
+                url: "/".to_owned(),
+                format: "html".to_owned(),
+                content: Some(
+                    concat!(
+                        r##"<h1 id="title">Title</h1>"##,
+                        r##"<a href="#title">A</a><a href="#missing">B</a>"##,
+                        r##"<a href="/blog/#intro">C</a><a href="/blog#outro">D</a>"##,
+                        r##"<a href="blog/#caf%C3%A9">E</a><a href="#top">F</a>"##,
+                        r##"<a href="/style.css#x">G</a><a href="https://example.com/#x">H</a>"##,
+                    )
+                    .to_owned(),
+                ),
+                ..Default::default()
+            },
+            Entry {
+                url: "/blog".to_owned(),
+                format: "html".to_owned(),
+                content: Some(
+                    r##"<h2 id="intro">Intro</h2><a name="café"></a><a href="../#title">A</a>"##
+                        .to_owned(),
+                ),
+                ..Default::default()
+            },
+        ];
+
+        let result: Vec<_> = super::check_entries(&entries, &Config::default())
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+
+        assert_eq!(result, [
+            "Link \"#missing\" points to a missing element",
+            "Link \"/blog#outro\" points to a missing element",
+        ]);
+    }
+
+    #[test]
+    fn percent_decode() {
+        const CASES: [(&str, &str); 4] = [
+            ("intro", "intro"),
+            ("caf%C3%A9", "café"),
+            ("a%20b", "a b"),
+            ("100%", "100%"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::percent_decode(input);
+            assert_eq!(
+                result, expected,
+                "\npercent_decode({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
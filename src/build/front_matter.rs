@@ -2,11 +2,15 @@
 //!
 //! A front matter is a block of metadata located at the top of a file. Front
 //! matters are enclosed in delimiters which determine the format: `---` for
-//! YAML, and `+++` for TOML.
+//! YAML, `+++` for TOML, and `{` and `}` for JSON.
 
 use serde::de::DeserializeOwned;
 
 use super::{Entry, Error};
+use crate::error::Location;
+
+/// Opening line of JSON front matters.
+const JSON_OPENING: &str = "{";
 
 /// Delimiter used for TOML front matters.
 const TOML_DELIMITER: &str = "+++";
@@ -24,9 +28,19 @@ pub(super) fn parse_entry(entry: Entry) -> Result<Entry, Error> {
         return Ok(entry);
     };
 
-    let (content, data) = parse(content).map_err(|error| Error::ParseFrontMatter {
-        input_path: entry.input_path_buf(),
-        source: error,
+    let (content, data) = parse(content).map_err(|error| {
+        let source = match error_line(&error, content) {
+            Some(line) => error.context(Location {
+                path: None,
+                line,
+                message: format!("At line {line}"),
+            }),
+            None => error,
+        };
+        Error::ParseFrontMatter {
+            input_path: entry.input_path_buf(),
+            source,
+        }
     })?;
 
     Ok(Entry {
@@ -48,6 +62,23 @@ where
 {
     let content = content.as_ref();
 
+    // JSON front matters start with a `{` line, and end with the matching `}`
+    if content.lines().next().map(str::trim_end) == Some(JSON_OPENING) {
+        let mut stream = serde_json::Deserializer::from_str(content).into_iter::<T>();
+
+        let data = stream.next().transpose()?;
+
+        // Skip the end of the closing line
+        let content = content[stream.byte_offset()..]
+            .split_once('\n')
+            .map_or("", |(_, content)| content)
+            .lines()
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        return Ok((content, data));
+    }
+
     // Use the `std::str::Lines` trait to read the content line by line
     let mut lines = content.lines().peekable();
 
@@ -76,6 +107,39 @@ where
     Ok((content.to_owned(), None))
 }
 
+/// Return the line of a front matter parsing error in the content, if known.
+///
+/// Lines of YAML and TOML errors are counted from the line following the
+/// opening delimiter.
+fn error_line(error: &anyhow::Error, content: &str) -> Option<usize> {
+    if let Some(error) = error.downcast_ref::<serde_json::Error>() {
+        return Some(error.line()).filter(|&line| line > 0);
+    }
+
+    if let Some(error) = error.downcast_ref::<serde_yaml::Error>() {
+        return error.location().map(|location| location.line() + 1);
+    }
+
+    if let Some(error) = error.downcast_ref::<toml::de::Error>() {
+        let start = error.span()?.start;
+        let mut offset = 0;
+
+        // Count the lines of the front matter before the error
+        let count = content
+            .lines()
+            .skip(1)
+            .take_while(|line| {
+                offset += line.len() + 1;
+                offset <= start
+            })
+            .count();
+
+        return Some(count + 2);
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use serde::Deserialize;
@@ -96,6 +160,29 @@ mod tests {
         assert_eq!(data.is_none(), true);
     }
 
+    #[test]
+    fn parse_json() {
+        const CONTENT: &str = concat!(
+            "{\n",                            //
+            "  \"layout\": \"post.tera\",\n", //
+            "  \"extra\": { \"a\": 1 }\n",    //
+            "}\n",                            //
+            "hello\n",                        //
+            "{{ world }}"
+        );
+        let (content, data) = super::parse::<Data, _>(CONTENT).unwrap();
+        assert_eq!(content, "hello\n{{ world }}");
+        assert_eq!(data.unwrap().layout, "post.tera");
+    }
+
+    #[test]
+    fn no_json_front_matter() {
+        const CONTENT: &str = "{{ title }}\n";
+        let (content, data) = super::parse::<Data, _>(CONTENT).unwrap();
+        assert_eq!(content, "{{ title }}\n");
+        assert!(data.is_none());
+    }
+
     #[test]
     fn parse_toml() {
         const CONTENT: &str = concat!(
@@ -121,4 +208,26 @@ mod tests {
         assert_eq!(content, "hello");
         assert_eq!(data.unwrap().layout, "post.tera");
     }
+
+    #[test]
+    fn error_line() {
+        const CASES: [(&str, Option<usize>); 4] = [
+            (
+                "{\n  \"layout\": \"post.tera\",\n  \"layout\"\n}\nhello",
+                Some(4),
+            ),
+            ("---\nlayout: post.tera\ntitle: [\n---\nhello", Some(3)),
+            ("+++\nlayout = \"post.tera\"\ntitle = \n+++\nhello", Some(3)),
+            ("hello", None),
+        ];
+
+        for (input, expected) in CASES {
+            let error = super::parse::<Data, _>(input).err();
+            let result = error.and_then(|error| super::error_line(&error, input));
+            assert_eq!(
+                result, expected,
+                "\nerror_line({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
 }
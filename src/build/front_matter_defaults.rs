@@ -0,0 +1,111 @@
+//! Compute default front matter fields.
+
+use serde::Serialize;
+
+use super::{Config, Entry, EntryData, Error};
+use crate::util::function::Function;
+
+/// Metadata of a page given to the `front_matter_defaults` function.
+#[derive(Serialize)]
+struct PageMeta<'a> {
+    /// Path of the input file, relative to the input directory (e.g.
+    /// `blog/hello.md`).
+    path: String,
+
+    /// URL of the page, before normalization (e.g. `/blog/hello.md`).
+    url: &'a str,
+
+    /// Front matter data of the page.
+    data: &'a serde_json::Value,
+}
+
+/// Compute default front matter fields of a [`Entry`].
+///
+/// The function specified in the `front_matter_defaults` key of the
+/// configuration receives the metadata of the page, and returns a map of
+/// fields (e.g. `slug` or `section` derived from the file path). Fields
+/// already present in the front matter take precedence.
+pub(super) fn apply_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
+    let Some(function) = config.front_matter_defaults.as_ref() else {
+        return Ok(entry);
+    };
+
+    let data = compute_data(&entry, function, config).map_err(|error| {
+        Error::ComputeFrontMatterDefaults {
+            input_path: entry.input_path_buf(),
+            source: error,
+        }
+    })?;
+
+    Ok(Entry {
+        data: Some(data),
+        ..entry
+    })
+}
+
+/// Call the `front_matter_defaults` function and merge its result into the
+/// data of a [`Entry`].
+fn compute_data(entry: &Entry, function: &Function, config: &Config) -> anyhow::Result<EntryData> {
+    let data = serde_json::to_value(entry.data.clone().unwrap_or_default())?;
+
+    let path = entry
+        .input_path()
+        .and_then(|path| config.strip_input_dir(path))
+        .map(|path| {
+            path.components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .unwrap_or_default();
+
+    let defaults: serde_json::Value = function.call_1(&PageMeta {
+        path,
+        url: &entry.url,
+        data: &data,
+    })?;
+
+    let defaults = match defaults {
+        serde_json::Value::Object(defaults) => defaults,
+        serde_json::Value::Null => serde_json::Map::new(),
+        value => anyhow::bail!("Expected a map, received {value}"),
+    };
+
+    Ok(serde_json::from_value(merge_defaults(data, defaults))?)
+}
+
+/// Insert default fields into data, unless they are already present.
+fn merge_defaults(
+    mut data: serde_json::Value,
+    defaults: serde_json::Map<String, serde_json::Value>,
+) -> serde_json::Value {
+    if let serde_json::Value::Object(data) = &mut data {
+        for (key, value) in defaults {
+            let field = data.entry(key).or_insert(serde_json::Value::Null);
+            if field.is_null() {
+                *field = value;
+            }
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn merge_defaults() {
+        let data = serde_json::json!({ "title": "Hello", "slug": null });
+        let defaults = serde_json::json!({
+            "title": "Default",
+            "slug": "hello",
+            "section": "blog"
+        });
+
+        let result = super::merge_defaults(data, defaults.as_object().unwrap().to_owned());
+
+        assert_eq!(
+            result,
+            serde_json::json!({ "title": "Hello", "slug": "hello", "section": "blog" })
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Validate front matter data against schemas.
+
+use super::{Config, Entry, Error};
+use crate::{config::FrontMatterFieldConfig, util::date_time::DateTime};
+
+/// Validate front matter data of pages.
+///
+/// Schemas are specified under the `front_matter_schemas` key in the
+/// configuration. Each schema applies to the pages located in a section
+/// directory, and lists constraints on fields (required, type, allowed
+/// values).
+///
+/// All violations are reported with the path of the file before an error is
+/// returned, so that they can be fixed at once.
+pub(super) fn validate_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    if config.front_matter_schemas.is_empty() {
+        return Ok(entries.into_iter().map(Ok));
+    }
+
+    let mut num_violations: usize = 0;
+
+    for entry in entries.iter() {
+        if !matches!(entry.format.as_str(), "html" | "md") {
+            continue;
+        }
+
+        let data =
+            serde_json::to_value(&entry.data).map_err(|error| Error::ValidateFrontMatter {
+                source: anyhow::anyhow!(error).context(format!("In {:?}", entry.input_path())),
+            })?;
+
+        for schema in config.front_matter_schemas.iter() {
+            let section = schema.section.trim_matches('/');
+
+            if !section.is_empty() && !entry.url.starts_with(&format!("/{section}/")) {
+                continue;
+            }
+
+            // Sort fields to report violations in a stable order
+            let mut fields: Vec<_> = schema.fields.iter().collect();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            for (name, field_config) in fields {
+                let value = data.get(name).filter(|value| !value.is_null());
+
+                let violation = validate_field(value, field_config).map_err(|error| {
+                    Error::ValidateFrontMatter {
+                        source: error.context(format!("In field {name:?}")),
+                    }
+                })?;
+
+                if let Some(violation) = violation {
+                    tracing::error!(
+                        "In {:?}: field {:?} {}",
+                        entry.input_path().unwrap_or(entry.url.as_ref()),
+                        name,
+                        violation
+                    );
+                    num_violations += 1;
+                }
+            }
+        }
+    }
+
+    if num_violations > 0 {
+        return Err(Error::ValidateFrontMatter {
+            source: anyhow::anyhow!("Found {num_violations} front matter violations"),
+        });
+    }
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Validate a field value.
+///
+/// This function returns a description of the violation, if any. Errors are
+/// returned for invalid schemas (e.g. unknown types).
+fn validate_field(
+    value: Option<&serde_json::Value>,
+    field_config: &FrontMatterFieldConfig,
+) -> anyhow::Result<Option<String>> {
+    let Some(value) = value else {
+        return Ok(field_config.required.then(|| "is required".to_owned()));
+    };
+
+    if let Some(expected_type) = field_config.r#type.as_ref() {
+        if !has_type(value, expected_type)? {
+            return Ok(Some(format!(
+                "must be of type {expected_type} (found {value})"
+            )));
+        }
+    }
+
+    if let Some(values) = field_config.values.as_ref() {
+        if !values.contains(value) {
+            let values = values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Ok(Some(format!("must be one of {values} (found {value})")));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Determine whether a value has a type.
+fn has_type(value: &serde_json::Value, expected_type: &str) -> anyhow::Result<bool> {
+    Ok(match expected_type {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "date" => value
+            .as_str()
+            .is_some_and(|value| DateTime::parse(value).is_ok()),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Unknown type {:?} (expected \"string\", \"number\", \"integer\", \"boolean\", \
+                 \"array\", \"object\" or \"date\")",
+                expected_type
+            ))
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::FrontMatterFieldConfig;
+
+    #[test]
+    fn validate_field() {
+        let field = |required: bool, r#type: Option<&str>, values: Option<serde_json::Value>| {
+            FrontMatterFieldConfig {
+                required,
+                r#type: r#type.map(str::to_owned),
+                values: values.and_then(|values| values.as_array().cloned()),
+            }
+        };
+
+        let cases = [
+            (None, field(false, None, None), None),
+            (None, field(true, None, None), Some("is required")),
+            (
+                Some(serde_json::json!("a")),
+                field(true, Some("string"), None),
+                None,
+            ),
+            (
+                Some(serde_json::json!(1)),
+                field(false, Some("string"), None),
+                Some("must be of type string (found 1)"),
+            ),
+            (
+                Some(serde_json::json!(1.5)),
+                field(false, Some("number"), None),
+                None,
+            ),
+            (
+                Some(serde_json::json!(1.5)),
+                field(false, Some("integer"), None),
+                Some("must be of type integer (found 1.5)"),
+            ),
+            (
+                Some(serde_json::json!("2024-01-31")),
+                field(false, Some("date"), None),
+                None,
+            ),
+            (
+                Some(serde_json::json!("draft")),
+                field(false, None, Some(serde_json::json!(["published"]))),
+                Some("must be one of \"published\" (found \"draft\")"),
+            ),
+        ];
+
+        for (input, field_config, expected) in cases {
+            let result = super::validate_field(input.as_ref(), &field_config).unwrap();
+            let expected = expected.map(str::to_owned);
+            assert_eq!(
+                result, expected,
+                "\nvalidate_field({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+
+        assert!(super::validate_field(
+            Some(&serde_json::json!(1)),
+            &field(false, Some("unknown"), None)
+        )
+        .is_err());
+    }
+}
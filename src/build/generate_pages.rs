@@ -0,0 +1,62 @@
+//! Generate pages from data.
+
+use serde::Deserialize;
+
+use super::{Config, Entry, EntryData, Error};
+
+/// Page returned by the `generate_pages` function.
+#[derive(Debug, Deserialize)]
+struct PageInput {
+    /// Path of the virtual input file, relative to the input directory (e.g.
+    /// `products/chair.md`).
+    path: String,
+
+    /// Content of the page, without front matter.
+    #[serde(default)]
+    content: String,
+
+    /// Front matter data of the page.
+    #[serde(default)]
+    data: Option<EntryData>,
+}
+
+/// Create a [`Entry`] for each page returned by the `generate_pages` function.
+///
+/// The function specified in the configuration receives the global data. The
+/// entries have no input file, and are then processed like entries read from
+/// input files, depending on the extension of their path.
+pub(super) fn generate_entries(
+    config: &Config,
+    global_data: &serde_json::Value,
+) -> Result<Vec<Entry>, Error> {
+    let Some(function) = config.generate_pages.as_ref() else {
+        return Ok(Vec::new());
+    };
+
+    let pages: Vec<PageInput> = function
+        .call_1(global_data)
+        .map_err(|error| Error::GeneratePages { source: error })?;
+
+    pages
+        .into_iter()
+        .map(|page| {
+            let path = page.path.trim_start_matches('/');
+
+            let format = path
+                .rsplit_once('.')
+                .map(|(_, extension)| extension)
+                .filter(|extension| ["html", "md"].contains(extension))
+                .ok_or_else(|| Error::GeneratePages {
+                    source: anyhow::anyhow!("Expected a .html or .md path, received {path:?}"),
+                })?;
+
+            Ok(Entry {
+                url: format!("/{path}"),
+                format: format.to_owned(),
+                content: Some(page.content),
+                data: page.data,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,239 @@
+//! Read page dates and authorship from git.
+//!
+//! This module calls the `git` command under the hood.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Arc, Mutex},
+};
+
+use super::{Config, Entry, EntryData};
+use crate::util::command::run_string;
+
+/// Separator between commits in the log output.
+const RECORD_SEPARATOR: char = '\x1e';
+
+/// Separator between commit fields in the log output.
+const UNIT_SEPARATOR: char = '\x1f';
+
+/// History of a file according to git.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct FileHistory {
+    /// Date of the first commit that touched the file.
+    pub(super) created: String,
+
+    /// Date of the last commit that touched the file.
+    pub(super) updated: String,
+
+    /// Names of the commit authors, in order of first contribution.
+    pub(super) contributors: Vec<String>,
+}
+
+/// Histories of files, keyed by absolute path.
+pub(super) type History = HashMap<PathBuf, FileHistory>;
+
+/// Last history read, keyed by the directory and the commit hash of `HEAD`.
+///
+/// Since the history only changes when a commit is made, it is reused across
+/// builds (e.g. in watch mode) as long as `HEAD` does not move. The directory
+/// is part of the key, since a process may build several sites (e.g. with the
+/// library API).
+static CACHE: Mutex<Option<(PathBuf, String, Arc<History>)>> = Mutex::new(None);
+
+/// Read the history of files in the input directory.
+///
+/// Returns an empty history if git metadata are disabled, or if the input
+/// directory is not located in a git repository.
+pub(super) fn read_history(config: &Config) -> Arc<History> {
+    if !config.git_metadata {
+        return Default::default();
+    }
+
+    match read_history_cached(&config.input_dir) {
+        Ok(history) => history,
+        Err(error) => {
+            tracing::warn!("Cannot read git history: {}", error);
+            Default::default()
+        },
+    }
+}
+
+/// Read the history of files, using the cache if the directory and `HEAD` did
+/// not change.
+fn read_history_cached<P>(dir: P) -> anyhow::Result<Arc<History>>
+where
+    P: AsRef<Path>,
+{
+    let dir = dir.as_ref();
+
+    let head = git(dir, ["rev-parse", "HEAD"])?.trim().to_owned();
+
+    let mut cache = CACHE
+        .lock()
+        .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+
+    if let Some((cached_dir, cached_head, history)) = cache.as_ref() {
+        if cached_dir == dir && *cached_head == head {
+            return Ok(Arc::clone(history));
+        }
+    }
+
+    let top_level = PathBuf::from(git(dir, ["rev-parse", "--show-toplevel"])?.trim());
+
+    let log = git(dir, [
+        "log",
+        "--no-renames",
+        "--name-only",
+        &format!("--format={RECORD_SEPARATOR}%aI{UNIT_SEPARATOR}%an"),
+        "--",
+        ".",
+    ])?;
+
+    let history: History = parse_log(log)
+        .into_iter()
+        .map(|(path, file_history)| (top_level.join(path), file_history))
+        .collect();
+
+    let history = Arc::new(history);
+
+    *cache = Some((dir.to_owned(), head, Arc::clone(&history)));
+
+    Ok(history)
+}
+
+/// Parse the output of `git log`.
+///
+/// Commits are expected in reverse chronological order, each one formatted as
+/// `{RECORD_SEPARATOR}{date}{UNIT_SEPARATOR}{author}` followed by the list of
+/// modified files.
+fn parse_log<S>(log: S) -> HashMap<String, FileHistory>
+where
+    S: AsRef<str>,
+{
+    let mut history: HashMap<String, FileHistory> = HashMap::new();
+
+    for record in log.as_ref().split(RECORD_SEPARATOR) {
+        let mut lines = record.lines();
+
+        let Some((date, author)) = lines
+            .next()
+            .and_then(|line| line.split_once(UNIT_SEPARATOR))
+        else {
+            continue;
+        };
+
+        for path in lines.filter(|line| !line.is_empty()) {
+            let file_history = history
+                .entry(path.to_owned())
+                .or_insert_with(|| FileHistory {
+                    updated: date.to_owned(),
+                    ..Default::default()
+                });
+
+            // Commits are read from the newest to the oldest
+            file_history.created = date.to_owned();
+
+            if let Some(index) = file_history
+                .contributors
+                .iter()
+                .position(|contributor| contributor == author)
+            {
+                file_history.contributors.remove(index);
+            }
+            file_history.contributors.insert(0, author.to_owned());
+        }
+    }
+
+    history
+}
+
+/// Run a git command in a directory and return its standard output.
+fn git<P, I, S>(dir: P, args: I) -> anyhow::Result<String>
+where
+    P: AsRef<Path>,
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    // Print non-ASCII paths as is, instead of quoting them
+    run_string(
+        Command::new("git")
+            .args(["-c", "core.quotePath=false"])
+            .arg("-C")
+            .arg(dir.as_ref())
+            .args(args),
+        None,
+    )
+}
+
+/// Fill the dates and contributors of a [`Entry`] from its git history.
+///
+/// The `date` field is set to the date of the first commit and the `updated`
+/// field to the date of the last commit, unless they are already specified
+/// (e.g. in the front matter). The `contributors` field lists the authors.
+pub(super) fn apply_entry(entry: Entry, history: &History) -> Entry {
+    let Some(file_history) = entry.input_path().and_then(|path| history.get(path)) else {
+        return entry;
+    };
+
+    let data = entry.data.unwrap_or_else(|| EntryData {
+        extra: serde_json::Map::new().into(),
+        ..Default::default()
+    });
+
+    let data = EntryData {
+        date: data.date.or_else(|| Some(file_history.created.to_owned())),
+        updated: data
+            .updated
+            .or_else(|| Some(file_history.updated.to_owned())),
+        contributors: data
+            .contributors
+            .or_else(|| Some(file_history.contributors.to_owned())),
+        ..data
+    };
+
+    Entry {
+        data: Some(data),
+        ..entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_log() {
+        const LOG: &str = concat!(
+            "\x1e2024-03-01T10:00:00+01:00\x1fAlice\n",
+            "\n",
+            "index.md\n",
+            "\x1e2024-02-01T10:00:00+01:00\x1fBob\n",
+            "\n",
+            "index.md\n",
+            "blog/post.md\n",
+            "\x1e2024-01-01T10:00:00+01:00\x1fAlice\n",
+            "\n",
+            "index.md\n",
+        );
+
+        let history = super::parse_log(LOG);
+
+        assert_eq!(
+            history.get("index.md"),
+            Some(&super::FileHistory {
+                created: "2024-01-01T10:00:00+01:00".to_owned(),
+                updated: "2024-03-01T10:00:00+01:00".to_owned(),
+                contributors: vec!["Alice".to_owned(), "Bob".to_owned()],
+            })
+        );
+
+        assert_eq!(
+            history.get("blog/post.md"),
+            Some(&super::FileHistory {
+                created: "2024-02-01T10:00:00+01:00".to_owned(),
+                updated: "2024-02-01T10:00:00+01:00".to_owned(),
+                contributors: vec!["Bob".to_owned()],
+            })
+        );
+    }
+}
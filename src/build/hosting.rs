@@ -0,0 +1,424 @@
+//! Generate configuration files of hosting providers.
+//!
+//! See <https://docs.netlify.com/routing/redirects/>,
+//! <https://developers.cloudflare.com/pages/configuration/headers/> and
+//! <https://vercel.com/docs/projects/project-configuration>.
+
+use serde::Serialize;
+
+use super::{Config, Entry, Error};
+use crate::util::url::Url;
+
+/// URLs of the files of hosting providers.
+///
+/// These files are read at the root of the deployed directory, so they do not
+/// include the path of `base_url`.
+pub(super) const HOSTING_FILE_URLS: [&str; 4] =
+    ["/_headers", "/_redirects", "/netlify.toml", "/vercel.json"];
+
+/// Hosting provider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HostingProvider {
+    /// Cloudflare Pages (`_redirects` and `_headers` files).
+    Cloudflare,
+
+    /// Netlify (`netlify.toml` file).
+    Netlify,
+
+    /// Vercel (`vercel.json` file).
+    Vercel,
+}
+
+impl std::str::FromStr for HostingProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "cloudflare" => Ok(Self::Cloudflare),
+            "netlify" => Ok(Self::Netlify),
+            "vercel" => Ok(Self::Vercel),
+            _ => Err(anyhow::anyhow!(
+                "Unknown hosting provider {:?} (expected \"cloudflare\", \"netlify\" or \
+                 \"vercel\")",
+                value
+            )),
+        }
+    }
+}
+
+impl HostingProvider {
+    /// Return the URLs of the files generated for the provider.
+    pub(crate) fn file_urls(self) -> &'static [&'static str] {
+        match self {
+            Self::Cloudflare => &["/_redirects", "/_headers"],
+            Self::Netlify => &["/netlify.toml"],
+            Self::Vercel => &["/vercel.json"],
+        }
+    }
+}
+
+/// Headers applied to a URL.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct HeaderRule {
+    /// URL to which headers apply (e.g. `/blog/`).
+    pub(super) url: String,
+
+    /// Header names and values.
+    pub(super) headers: Vec<(String, String)>,
+}
+
+/// Redirect from a URL to another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Redirect {
+    /// Source URL.
+    from: String,
+
+    /// Destination URL.
+    to: String,
+
+    /// HTTP status code.
+    status: usize,
+}
+
+/// Generate configuration files of hosting providers.
+///
+/// If `hosting` is specified in the configuration, this function creates
+/// entries for the files of each provider. Redirects and headers come from the
+/// configuration, and headers from other build tasks (e.g. Content Security
+/// Policies) are appended.
+pub(super) fn create_hosting_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    header_rules: Vec<HeaderRule>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let Some(hosting_config) = config.hosting.as_ref() else {
+        return Ok(entries.chain(Vec::new().into_iter().map(Ok)));
+    };
+
+    let providers = hosting_config
+        .providers
+        .iter()
+        .map(|provider| provider.parse::<HostingProvider>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| Error::CreateHostingFiles { source: error })?;
+
+    // URLs of the hosting configuration do not include the path of base_url
+    let base_path = Url::from(config.base_url.as_str()).path.to_string();
+
+    let prefix = |url: &str| {
+        if url.starts_with('/') {
+            format!("{base_path}{url}")
+        } else {
+            url.to_owned()
+        }
+    };
+
+    let redirects: Vec<_> = hosting_config
+        .redirects
+        .iter()
+        .map(|redirect| Redirect {
+            from: prefix(&redirect.from),
+            to: prefix(&redirect.to),
+            status: redirect.status,
+        })
+        .collect();
+
+    let header_rules: Vec<_> = hosting_config
+        .headers
+        .iter()
+        .map(|headers| {
+            let mut headers_values: Vec<_> = headers
+                .values
+                .iter()
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect();
+            // Keep the output deterministic
+            headers_values.sort();
+            HeaderRule {
+                url: headers.url.to_owned(),
+                headers: headers_values,
+            }
+        })
+        .chain(header_rules)
+        .map(|rule| HeaderRule {
+            url: prefix(&rule.url),
+            ..rule
+        })
+        .collect();
+
+    let mut hosting_entries = Vec::new();
+
+    for provider in providers {
+        match provider {
+            HostingProvider::Cloudflare => {
+                hosting_entries.push(create_entry("/_redirects", redirects_file(&redirects)));
+                hosting_entries.push(create_entry("/_headers", headers_file(&header_rules, "")));
+            },
+            HostingProvider::Netlify => {
+                let content = netlify_file(&redirects, &header_rules)
+                    .map_err(|error| Error::CreateHostingFiles { source: error })?;
+                hosting_entries.push(create_entry("/netlify.toml", content));
+            },
+            HostingProvider::Vercel => {
+                let content = vercel_file(&redirects, &header_rules)
+                    .map_err(|error| Error::CreateHostingFiles { source: error })?;
+                hosting_entries.push(create_entry("/vercel.json", content));
+            },
+        }
+    }
+
+    Ok(entries.chain(hosting_entries.into_iter().map(Ok)))
+}
+
+/// Create a generated [`Entry`].
+fn create_entry(url: &str, content: String) -> Entry {
+    Entry {
+        url: url.to_owned(),
+        content: Some(content),
+        ..Default::default()
+    }
+}
+
+/// Generate a `_redirects` file.
+fn redirects_file(redirects: &[Redirect]) -> String {
+    redirects
+        .iter()
+        .map(|redirect| format!("{} {} {}\n", redirect.from, redirect.to, redirect.status))
+        .collect()
+}
+
+/// Generate a `_headers` file.
+///
+/// The `prefix` is prepended to URLs (e.g. the base URL).
+pub(super) fn headers_file(header_rules: &[HeaderRule], prefix: &str) -> String {
+    header_rules
+        .iter()
+        .map(|rule| {
+            let headers: String = rule
+                .headers
+                .iter()
+                .map(|(name, value)| format!("  {name}: {value}\n"))
+                .collect();
+            format!("{prefix}{}\n{headers}", rule.url)
+        })
+        .collect()
+}
+
+/// Generate a `netlify.toml` file.
+fn netlify_file(redirects: &[Redirect], header_rules: &[HeaderRule]) -> anyhow::Result<String> {
+    #[derive(Serialize)]
+    struct NetlifyConfig<'a> {
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        redirects: Vec<NetlifyRedirect<'a>>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        headers: Vec<NetlifyHeaders<'a>>,
+    }
+
+    #[derive(Serialize)]
+    struct NetlifyRedirect<'a> {
+        from: &'a str,
+        to: &'a str,
+        status: usize,
+    }
+
+    #[derive(Serialize)]
+    struct NetlifyHeaders<'a> {
+        #[serde(rename = "for")]
+        url: &'a str,
+        values: toml::Table,
+    }
+
+    let config = NetlifyConfig {
+        redirects: redirects
+            .iter()
+            .map(|redirect| NetlifyRedirect {
+                from: &redirect.from,
+                to: &redirect.to,
+                status: redirect.status,
+            })
+            .collect(),
+        headers: header_rules
+            .iter()
+            .map(|rule| NetlifyHeaders {
+                url: &rule.url,
+                values: rule
+                    .headers
+                    .iter()
+                    .map(|(name, value)| (name.to_owned(), value.to_owned().into()))
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    Ok(toml::to_string(&config)?)
+}
+
+/// Generate a `vercel.json` file.
+///
+/// Redirects with status `200` are converted to rewrites, and wildcards (e.g.
+/// `/blog/*`) are converted to named parameters (e.g. `/blog/:splat*`).
+fn vercel_file(redirects: &[Redirect], header_rules: &[HeaderRule]) -> anyhow::Result<String> {
+    #[derive(Serialize)]
+    struct VercelConfig {
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        redirects: Vec<VercelRedirect>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        rewrites: Vec<VercelRewrite>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        headers: Vec<VercelHeaders>,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct VercelRedirect {
+        source: String,
+        destination: String,
+        status_code: usize,
+    }
+
+    #[derive(Serialize)]
+    struct VercelRewrite {
+        source: String,
+        destination: String,
+    }
+
+    #[derive(Serialize)]
+    struct VercelHeaders {
+        source: String,
+        headers: Vec<VercelHeader>,
+    }
+
+    #[derive(Serialize)]
+    struct VercelHeader {
+        key: String,
+        value: String,
+    }
+
+    let (rewrites, redirects): (Vec<_>, Vec<_>) = redirects
+        .iter()
+        .partition(|redirect| redirect.status == 200);
+
+    let config = VercelConfig {
+        redirects: redirects
+            .into_iter()
+            .map(|redirect| VercelRedirect {
+                source: vercel_source(&redirect.from),
+                destination: redirect.to.to_owned(),
+                status_code: redirect.status,
+            })
+            .collect(),
+        rewrites: rewrites
+            .into_iter()
+            .map(|redirect| VercelRewrite {
+                source: vercel_source(&redirect.from),
+                destination: redirect.to.to_owned(),
+            })
+            .collect(),
+        headers: header_rules
+            .iter()
+            .map(|rule| VercelHeaders {
+                source: vercel_source(&rule.url),
+                headers: rule
+                    .headers
+                    .iter()
+                    .map(|(key, value)| VercelHeader {
+                        key: key.to_owned(),
+                        value: value.to_owned(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    Ok(serde_json::to_string_pretty(&config)?)
+}
+
+/// Convert a trailing wildcard to a Vercel named parameter (e.g. `/blog/*`
+/// becomes `/blog/:splat*`).
+fn vercel_source(url: &str) -> String {
+    match url.strip_suffix('*') {
+        Some(url) => format!("{url}:splat*"),
+        None => url.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeaderRule, Redirect};
+
+    fn redirects() -> Vec<Redirect> {
+        vec![
+            Redirect {
+                from: "/old/".to_owned(),
+                to: "/new/".to_owned(),
+                status: 301,
+            },
+            Redirect {
+                from: "/app/*".to_owned(),
+                to: "/app/index.html".to_owned(),
+                status: 200,
+            },
+        ]
+    }
+
+    fn header_rules() -> Vec<HeaderRule> {
+        vec![HeaderRule {
+            url: "/*".to_owned(),
+            headers: vec![("X-Frame-Options".to_owned(), "DENY".to_owned())],
+        }]
+    }
+
+    #[test]
+    fn redirects_file() {
+        assert_eq!(
+            super::redirects_file(&redirects()),
+            "/old/ /new/ 301\n/app/* /app/index.html 200\n"
+        );
+    }
+
+    #[test]
+    fn headers_file() {
+        assert_eq!(
+            super::headers_file(&header_rules(), ""),
+            "/*\n  X-Frame-Options: DENY\n"
+        );
+    }
+
+    #[test]
+    fn netlify_file() {
+        let result = super::netlify_file(&redirects(), &header_rules()).unwrap();
+        let result: toml::Table = toml::from_str(&result).unwrap();
+
+        assert_eq!(result["redirects"][0]["from"].as_str(), Some("/old/"));
+        assert_eq!(result["redirects"][1]["status"].as_integer(), Some(200));
+        assert_eq!(result["headers"][0]["for"].as_str(), Some("/*"));
+        assert_eq!(
+            result["headers"][0]["values"]["X-Frame-Options"].as_str(),
+            Some("DENY")
+        );
+    }
+
+    #[test]
+    fn vercel_file() {
+        let result = super::vercel_file(&redirects(), &header_rules()).unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "redirects": [
+                    { "source": "/old/", "destination": "/new/", "statusCode": 301 },
+                ],
+                "rewrites": [
+                    { "source": "/app/:splat*", "destination": "/app/index.html" },
+                ],
+                "headers": [
+                    {
+                        "source": "/:splat*",
+                        "headers": [{ "key": "X-Frame-Options", "value": "DENY" }],
+                    },
+                ],
+            })
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Extract HTML fragments of pages.
+
+use std::cell::Cell;
+
+use sha2::{Digest, Sha256};
+
+use super::{Config, Entry, Error};
+
+/// Comment inserted before the extracted element.
+const START_MARKER: &str = "<!--vitrine-fragment-start-->";
+
+/// Comment inserted after the extracted element.
+const END_MARKER: &str = "<!--vitrine-fragment-end-->";
+
+/// Write elements of rendered pages (e.g. the header) to separate files, and
+/// generate a manifest that lists them.
+///
+/// Each fragment is written to `{fragments.url}/{name}.html`, so that its URL
+/// does not change between builds. The manifest maps the name of each
+/// fragment to its URL (including `base_url`) and to the SHA-256 hash of its
+/// content, so that other systems can detect changes.
+pub(super) fn create_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let Some(fragments_config) = config.fragments.as_ref() else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    let url_prefix = fragments_config.url.trim_end_matches('/');
+
+    let mut manifest = serde_json::Map::new();
+    let mut fragments = Vec::new();
+
+    for item in fragments_config.items.iter() {
+        let Some(page) = entries
+            .iter()
+            .find(|entry| entry.format == "html" && entry.url == item.page)
+        else {
+            tracing::warn!("Fragment {:?}: page {:?} not found", item.name, item.page);
+            continue;
+        };
+
+        let content = page
+            .content
+            .as_deref()
+            .map(|content| extract_fragment(content, &item.selector))
+            .transpose()
+            .map_err(|error| Error::CreateFragments {
+                source: error.context(format!("While extracting fragment {:?}", item.name)),
+            })?
+            .flatten();
+
+        let Some(content) = content else {
+            tracing::warn!(
+                "Fragment {:?}: no element matches {:?} in page {:?}",
+                item.name,
+                item.selector,
+                item.page
+            );
+            continue;
+        };
+
+        let url = format!("{}/{}.html", url_prefix, item.name);
+
+        manifest.insert(
+            item.name.to_owned(),
+            serde_json::json!({
+                "url": format!("{}{}", config.base_url, url),
+                "page": format!("{}{}", config.base_url, item.page),
+                "hash": format!("{:x}", Sha256::digest(content.as_bytes())),
+            }),
+        );
+
+        // Fragments are not pages, so they are excluded from sitemaps
+        fragments.push(Entry {
+            url,
+            format: "fragment".to_owned(),
+            content: Some(content),
+            ..Default::default()
+        });
+    }
+
+    let manifest = serde_json::Value::Object(manifest);
+
+    let content = if config.minify {
+        serde_json::to_string(&manifest)
+    } else {
+        serde_json::to_string_pretty(&manifest)
+    }
+    .map_err(|error| Error::CreateFragments {
+        source: error.into(),
+    })?;
+
+    entries.extend(fragments);
+
+    entries.push(Entry {
+        url: fragments_config.manifest_url.to_owned(),
+        format: "json".to_owned(),
+        content: Some(content),
+        ..Default::default()
+    });
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Return the first element of a HTML document that matches a selector,
+/// including its tags.
+///
+/// Returns `None` if no element matches.
+fn extract_fragment(content: &str, selector: &str) -> anyhow::Result<Option<String>> {
+    let is_found = Cell::new(false);
+
+    // Surround the element with markers, since the content of elements is not
+    // available in handlers
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!(selector, |element| {
+            if !is_found.replace(true) {
+                element.before(START_MARKER, lol_html::html_content::ContentType::Html);
+                element.after(END_MARKER, lol_html::html_content::ContentType::Html);
+            }
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    let fragment = content
+        .split_once(START_MARKER)
+        .and_then(|(_, content)| content.split_once(END_MARKER))
+        .map(|(fragment, _)| fragment.to_owned());
+
+    Ok(fragment)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn extract_fragment() {
+        const CASES: [(&str, &str, Option<&str>); 4] = [
+            (
+                "<body><header><a href=\"/\">Home</a></header><main></main></body>",
+                "body > header",
+                Some("<header><a href=\"/\">Home</a></header>"),
+            ),
+            (
+                "<nav id=\"a\"><nav id=\"b\"></nav></nav><nav id=\"c\"></nav>",
+                "nav",
+                Some("<nav id=\"a\"><nav id=\"b\"></nav></nav>"),
+            ),
+            (
+                "<footer><img src=\"/logo.png\"></footer>",
+                "img",
+                Some("<img src=\"/logo.png\">"),
+            ),
+            ("<main></main>", "header", None),
+        ];
+
+        for (content, selector, expected) in CASES {
+            let result = super::extract_fragment(content, selector).unwrap();
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\nextract_fragment({content:?}, {selector:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
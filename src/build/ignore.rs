@@ -1,40 +1,169 @@
 //! Ignore input files or paths.
+//!
+//! Files are ignored if they match the `ignore` globs of the configuration, or
+//! the patterns of the `.gitignore` and `.vitrineignore` files located in the
+//! input directory.
 
 use std::path::Path;
 
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
 
 use super::{Config, Error};
 
+/// Files containing gitignore patterns, in the input directory.
+const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".vitrineignore"];
+
 /// Path pattern matcher for ignored files.
 pub(super) struct Matcher {
     /// Group of globs.
     glob_set: GlobSet,
+
+    /// Group of globs translated from the patterns of ignore files.
+    ignore_file_glob_set: GlobSet,
+
+    /// Determine whether each glob of ignore files is negated (i.e. starts with
+    /// `!`), in the same order.
+    negated: Vec<bool>,
 }
 
 impl Matcher {
     /// Create a path pattern matcher.
     pub(super) fn new(config: &Config) -> Result<Self, Error> {
+        let glob_set = config
+            .ignore
+            .iter()
+            .try_fold(GlobSetBuilder::new(), |mut builder, pattern| {
+                builder.add(Glob::new(pattern)?);
+                Ok(builder)
+            })
+            .and_then(|builder| builder.build())
+            .map_err(|error| Error::NewIgnoreMatcher {
+                source: error.into(),
+            })?;
+
+        let mut builder = GlobSetBuilder::new();
+        let mut negated = Vec::new();
+
+        for file_name in IGNORE_FILE_NAMES {
+            let path = config.input_dir.join(file_name);
+
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+
+            for (globs, is_negated) in content.lines().filter_map(translate_pattern) {
+                for glob in globs {
+                    builder.add(
+                        GlobBuilder::new(&glob)
+                            .literal_separator(true)
+                            .build()
+                            .map_err(|error| Error::NewIgnoreMatcher {
+                                source: anyhow::anyhow!(error)
+                                    .context(format!("While reading {:?}", path)),
+                            })?,
+                    );
+                    negated.push(is_negated);
+                }
+            }
+        }
+
+        let ignore_file_glob_set = builder.build().map_err(|error| Error::NewIgnoreMatcher {
+            source: error.into(),
+        })?;
+
         Ok(Self {
-            glob_set: config
-                .ignore
-                .iter()
-                .try_fold(GlobSetBuilder::new(), |mut builder, pattern| {
-                    builder.add(Glob::new(pattern)?);
-                    Ok(builder)
-                })
-                .and_then(|builder| builder.build())
-                .map_err(|error| Error::NewIgnoreMatcher {
-                    source: error.into(),
-                })?,
+            glob_set,
+            ignore_file_glob_set,
+            negated,
         })
     }
 
     /// Check if a file is ignored.
+    ///
+    /// As with git, the last matching pattern of ignore files decides whether
+    /// a file is ignored.
     pub(super) fn is_match<P>(&self, path: P) -> bool
     where
         P: AsRef<Path>,
     {
+        let path = path.as_ref();
+
         self.glob_set.is_match(path)
+            || self
+                .ignore_file_glob_set
+                .matches(path)
+                .into_iter()
+                .max()
+                .is_some_and(|index| !self.negated[index])
+    }
+}
+
+/// Translate a line of an ignore file to globs relative to the input
+/// directory, and whether the pattern is negated.
+///
+/// Returns `None` for empty lines and comments. Patterns without slash (except
+/// a trailing one) match at any depth. A trailing slash is dropped, so that
+/// the pattern also matches files.
+fn translate_pattern(line: &str) -> Option<(Vec<String>, bool)> {
+    let line = line.trim_end();
+
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (pattern, negated) = match line.strip_prefix('!') {
+        Some(pattern) => (pattern, true),
+        None => (line.strip_prefix('\\').unwrap_or(line), false),
+    };
+
+    let pattern = pattern.trim_end_matches('/');
+
+    if pattern.is_empty() {
+        return None;
+    }
+
+    let pattern = match pattern.strip_prefix('/') {
+        Some(pattern) => pattern.to_owned(),
+        None if pattern.contains('/') => pattern.to_owned(),
+        None => format!("**/{pattern}"),
+    };
+
+    // Also match the content of directories
+    Some((vec![format!("{pattern}/**"), pattern], negated))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn translate_pattern() {
+        const CASES: [(&str, Option<(&[&str], bool)>); 7] = [
+            ("", None),
+            ("# comment", None),
+            (
+                "node_modules/",
+                Some((&["**/node_modules/**", "**/node_modules"], false)),
+            ),
+            ("*.swp", Some((&["**/*.swp/**", "**/*.swp"], false))),
+            ("/build", Some((&["build/**", "build"], false))),
+            (
+                "docs/*.tmp",
+                Some((&["docs/*.tmp/**", "docs/*.tmp"], false)),
+            ),
+            (
+                "!keep.swp",
+                Some((&["**/keep.swp/**", "**/keep.swp"], true)),
+            ),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::translate_pattern(input);
+            let expected = expected.map(|(globs, negated)| {
+                (globs.iter().map(|glob| glob.to_string()).collect(), negated)
+            });
+            assert_eq!(
+                result, expected,
+                "\ntranslate_pattern({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
     }
 }
@@ -0,0 +1,423 @@
+//! Add attributes to images, render pictures, and download remote images.
+//!
+//! This module uses [`imagesize`] under the hood.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use super::{fragments::percent_decode, Config, Entry, Error};
+use crate::util::{
+    html::{escape, unescape},
+    http,
+};
+
+/// Alternative formats of images in `<picture>` elements, by order of
+/// preference, with their MIME types.
+const PICTURE_FORMATS: [(&str, &str); 2] = [("avif", "image/avif"), ("webp", "image/webp")];
+
+/// Add attributes to `<img>` elements in a [`Entry`].
+///
+/// If `images.dimensions` is enabled, this function reads the intrinsic
+/// dimensions of local images and adds the `width` and `height` attributes when
+/// both are missing. Image paths are resolved relative to the input file, or to
+/// the input directory if they start with `/`.
+///
+/// If `images.lazy_loading` (resp. `images.async_decoding`) is enabled, the
+/// `loading="lazy"` (resp. `decoding="async"`) attribute is added when missing.
+pub(super) fn rewrite_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
+    let images_config = &config.images;
+
+    if !images_config.dimensions && !images_config.lazy_loading && !images_config.async_decoding {
+        return Ok(entry);
+    }
+
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(entry);
+    };
+
+    let dir = entry
+        .input_path()
+        .and_then(|path| path.parent())
+        .unwrap_or(&config.input_dir);
+
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("img[src]", |element| {
+            if images_config.dimensions
+                && !element.has_attribute("width")
+                && !element.has_attribute("height")
+            {
+                let src = unescape(&element.get_attribute("src").unwrap_or_default());
+
+                if let Some((width, height)) = config.input_dirs().find_map(|input_dir| {
+                    resolve_image_path(&src, dir, input_dir).and_then(|path| read_dimensions(&path))
+                }) {
+                    element.set_attribute("width", &width.to_string())?;
+                    element.set_attribute("height", &height.to_string())?;
+                }
+            }
+
+            if images_config.lazy_loading && !element.has_attribute("loading") {
+                element.set_attribute("loading", "lazy")?;
+            }
+
+            if images_config.async_decoding && !element.has_attribute("decoding") {
+                element.set_attribute("decoding", "async")?;
+            }
+
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })
+    .map_err(|error| Error::RewriteImages {
+        input_path: entry.input_path_buf(),
+        source: error.into(),
+    })?;
+
+    Ok(Entry {
+        content: Some(content),
+        ..entry
+    })
+}
+
+/// Download remote images referenced by `<img>` elements, and rewrite their
+/// `src` attributes to local URLs.
+///
+/// If `images.localize_remote` is enabled, each remote image is downloaded
+/// once into the cache directory, in a file named after a hash of its URL, and
+/// an [`Entry`] is created to copy it under `images.remote_url_prefix`. Images
+/// that cannot be downloaded keep their remote URL. The `srcset` attribute is
+/// left unchanged.
+pub(super) fn localize_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    // The cache directory is checked when validating the configuration
+    let Some(cache_dir) = config
+        .cache_dir
+        .as_ref()
+        .filter(|_| config.images.localize_remote)
+    else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    let dir = cache_dir.join("images");
+    let url_prefix = config.images.remote_url_prefix.trim_end_matches('/');
+
+    // File names of downloaded images, indexed by remote URL
+    let mut downloads: BTreeMap<String, Option<String>> = BTreeMap::new();
+
+    for entry in entries.iter_mut() {
+        if entry.format != "html" {
+            continue;
+        }
+
+        let Some(content) = entry.content.as_ref() else {
+            continue;
+        };
+
+        let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+            element_content_handlers: vec![lol_html::element!("img[src]", |element| {
+                let src = element.get_attribute("src").unwrap_or_default();
+
+                let Some(url) = remote_url(&src) else {
+                    return Ok(());
+                };
+
+                let Some(file_name) = downloads
+                    .entry(url.to_owned())
+                    .or_insert_with(|| download(&url, &dir))
+                    .as_ref()
+                else {
+                    return Ok(());
+                };
+
+                element.set_attribute("src", &format!("{url_prefix}/{file_name}"))?;
+
+                if config.images.dimensions
+                    && !element.has_attribute("width")
+                    && !element.has_attribute("height")
+                {
+                    if let Some((width, height)) = read_dimensions(dir.join(file_name)) {
+                        element.set_attribute("width", &width.to_string())?;
+                        element.set_attribute("height", &height.to_string())?;
+                    }
+                }
+
+                Ok(())
+            })],
+            ..lol_html::RewriteStrSettings::default()
+        })
+        .map_err(|error| Error::RewriteImages {
+            input_path: entry.input_path_buf(),
+            source: error.into(),
+        })?;
+
+        entry.content = Some(content);
+    }
+
+    // Downloaded images are copied as is
+    for file_name in downloads.into_values().flatten() {
+        let Some(Ok(input_file)) = WalkDir::new(dir.join(&file_name)).into_iter().next() else {
+            continue;
+        };
+
+        entries.push(Entry {
+            url: format!("{url_prefix}/{file_name}"),
+            input_file: Some(input_file),
+            ..Default::default()
+        });
+    }
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Return the absolute URL of a remote image from its `src` attribute.
+///
+/// Protocol-relative URLs (e.g. `//example.com/a.png`) are resolved with
+/// `https:`. Returns `None` for local images.
+fn remote_url(src: &str) -> Option<String> {
+    let src = src.trim();
+
+    if src.starts_with("//") {
+        return Some(format!("https:{src}"));
+    }
+
+    ["http://", "https://"]
+        .iter()
+        .any(|scheme| src.starts_with(scheme))
+        .then(|| src.to_owned())
+}
+
+/// Compute the file name of a downloaded image or font.
+///
+/// The name is a hash of the URL, followed by the extension of the URL path, if
+/// any (e.g. `.png`).
+pub(super) fn remote_file_name(url: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(url));
+
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+
+    let extension = path
+        .rsplit_once('/')
+        .and_then(|(_, name)| name.rsplit_once('.'))
+        .map(|(_, extension)| extension)
+        .filter(|extension| {
+            !extension.is_empty()
+                && extension.len() <= 5
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+
+    match extension {
+        Some(extension) => format!("{hash}.{}", extension.to_ascii_lowercase()),
+        None => hash,
+    }
+}
+
+/// Download a remote image into `dir`, unless it was downloaded before, and
+/// return its file name.
+///
+/// Returns `None` if the image cannot be downloaded.
+fn download(url: &str, dir: &Path) -> Option<String> {
+    let file_name = remote_file_name(url);
+    let path = dir.join(&file_name);
+
+    if path.exists() {
+        return Some(file_name);
+    }
+
+    tracing::info!("Downloading image {:?}", url);
+
+    http::download(url, &path)
+        .map_err(|error| tracing::warn!("Could not download image {:?}: {}", url, error))
+        .ok()
+        .map(|_| file_name)
+}
+
+/// Render a `<picture>` element for a local image.
+///
+/// `path` is the image file and `src` its URL. Images are not converted: a
+/// `<source>` is added for each AVIF or WebP version found next to the image
+/// (e.g. `photo.avif` for `photo.jpg`). The `<img>` element has the intrinsic
+/// dimensions of the image and is lazy loaded. If a caption is given, the
+/// picture is wrapped in a `<figure>`.
+pub(super) fn render_picture(path: &Path, src: &str, alt: &str, caption: Option<&str>) -> String {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+
+    let mut html = String::from("<picture>");
+
+    // URLs of alternative versions replace the extension of the image URL
+    if let Some(stem) = src
+        .strip_suffix(extension)
+        .filter(|_| !extension.is_empty())
+    {
+        for (format, mime_type) in PICTURE_FORMATS {
+            if format == extension || !path.with_extension(format).is_file() {
+                continue;
+            }
+
+            html.push_str(&format!(
+                "<source type=\"{mime_type}\" srcset=\"{}{format}\">",
+                escape(stem)
+            ));
+        }
+    }
+
+    html.push_str(&format!(
+        "<img src=\"{}\" alt=\"{}\"",
+        escape(src),
+        escape(alt)
+    ));
+
+    if let Some((width, height)) = read_dimensions(path) {
+        html.push_str(&format!(" width=\"{width}\" height=\"{height}\""));
+    }
+
+    html.push_str(" loading=\"lazy\" decoding=\"async\"></picture>");
+
+    match caption {
+        Some(caption) => format!(
+            "<figure>{html}<figcaption>{}</figcaption></figure>",
+            escape(caption)
+        ),
+        None => html,
+    }
+}
+
+/// Resolve the local path of an image from its `src` attribute.
+///
+/// Percent-encoded characters (e.g. `my%20photo.png`) are decoded. Returns
+/// `None` for remote URLs.
+pub(super) fn resolve_image_path<S, P, Q>(src: S, dir: P, input_dir: Q) -> Option<PathBuf>
+where
+    S: AsRef<str>,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let src = src.as_ref().trim();
+
+    // Remove query and fragment
+    let src = src.split(['?', '#']).next().unwrap_or_default();
+
+    if src.is_empty() || src.contains("://") || src.starts_with("//") || src.starts_with("data:") {
+        return None;
+    }
+
+    let src = percent_decode(src);
+
+    Some(match src.strip_prefix('/') {
+        Some(src) => input_dir.as_ref().join(src),
+        None => dir.as_ref().join(src),
+    })
+}
+
+/// Read the intrinsic dimensions of an image file.
+fn read_dimensions<P>(path: P) -> Option<(usize, usize)>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    imagesize::size(path)
+        .map_err(|error| tracing::debug!("Cannot read size of image {:?}: {}", path, error))
+        .ok()
+        .map(|size| (size.width, size.height))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn remote_url() {
+        const CASES: [(&str, Option<&str>); 5] = [
+            (
+                "https://example.com/a.png",
+                Some("https://example.com/a.png"),
+            ),
+            ("http://example.com/a.png", Some("http://example.com/a.png")),
+            ("//example.com/a.png", Some("https://example.com/a.png")),
+            ("/images/a.png", None),
+            ("data:image/png;base64,", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::remote_url(input);
+            let expected = expected.map(str::to_owned);
+            assert_eq!(
+                result, expected,
+                "\nremote_url({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn remote_file_name() {
+        const CASES: [(&str, Option<&str>); 5] = [
+            ("https://example.com/a.png", Some("png")),
+            ("https://example.com/a.JPG?size=2", Some("jpg")),
+            ("https://example.com/photo", None),
+            ("https://example.com/v1.2/photo", None),
+            ("https://example.com/a.png/../b.tar.gz#x", Some("gz")),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::remote_file_name(input);
+            let extension = result.split_once('.').map(|(_, extension)| extension);
+            assert_eq!(
+                extension, expected,
+                "\nremote_file_name({input:?}) expected extension {expected:?} but received \
+                 {result:?}"
+            );
+            assert_eq!(result, super::remote_file_name(input));
+        }
+    }
+
+    #[test]
+    fn render_picture() {
+        let dir = assert_fs::TempDir::new().unwrap();
+
+        // PNG signature and header of a 3x2 image
+        std::fs::write(dir.join("photo.png"), [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x06, 0x00, 0x00,
+            0x00,
+        ])
+        .unwrap();
+        std::fs::write(dir.join("photo.webp"), "").unwrap();
+
+        let cases = [
+            (
+                "photo.png",
+                None,
+                "<picture><source type=\"image/webp\" srcset=\"/photo.webp\"><img \
+                 src=\"/photo.png\" alt=\"A &quot;photo&quot;\" width=\"3\" height=\"2\" \
+                 loading=\"lazy\" decoding=\"async\"></picture>",
+            ),
+            (
+                "photo.webp",
+                Some("Caption"),
+                "<figure><picture><img src=\"/photo.webp\" alt=\"A &quot;photo&quot;\" \
+                 loading=\"lazy\" \
+                 decoding=\"async\"></picture><figcaption>Caption</figcaption></figure>",
+            ),
+        ];
+
+        for (file_name, caption, expected) in cases {
+            let src = format!("/{file_name}");
+            let result = super::render_picture(&dir.join(file_name), &src, "A \"photo\"", caption);
+            assert_eq!(
+                result, expected,
+                "\nrender_picture({file_name:?}, {caption:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
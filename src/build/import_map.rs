@@ -0,0 +1,121 @@
+//! Insert the import map of module scripts.
+
+use std::collections::HashMap;
+
+use super::{Config, Entry, Error};
+
+/// Create the `<script type="importmap">` element specified in the
+/// configuration, if any.
+///
+/// Addresses and scopes starting with `/` are prefixed with `base_url`, so
+/// that they resolve to the same files when serving and in production.
+pub(super) fn create_snippet(config: &Config) -> Option<String> {
+    let import_map_config = config.import_map.as_ref()?;
+
+    let prefix_url = |url: &str| {
+        if url.starts_with('/') && !url.starts_with("//") {
+            format!("{}{}", config.base_url, url)
+        } else {
+            url.to_owned()
+        }
+    };
+
+    let imports = |imports: &HashMap<String, String>| -> serde_json::Map<_, _> {
+        imports
+            .iter()
+            .map(|(specifier, address)| (specifier.to_owned(), prefix_url(address).into()))
+            .collect()
+    };
+
+    let mut import_map = serde_json::Map::new();
+
+    import_map.insert(
+        "imports".to_owned(),
+        imports(&import_map_config.imports).into(),
+    );
+
+    if !import_map_config.scopes.is_empty() {
+        let scopes: serde_json::Map<_, _> = import_map_config
+            .scopes
+            .iter()
+            .map(|(scope, scope_imports)| (prefix_url(scope), imports(scope_imports).into()))
+            .collect();
+
+        import_map.insert("scopes".to_owned(), scopes.into());
+    }
+
+    // Prevent addresses from closing the element
+    let json = serde_json::Value::Object(import_map)
+        .to_string()
+        .replace("</", "<\\/");
+
+    Some(format!("<script type=\"importmap\">{json}</script>"))
+}
+
+/// Insert the import map at the beginning of the `<head>` of a [`Entry`].
+///
+/// Browsers ignore import maps inserted after the first module script, so the
+/// import map is inserted before any other element.
+pub(super) fn insert_entry(entry: Entry, snippet: Option<&str>) -> Result<Entry, Error> {
+    let Some(snippet) = snippet else {
+        return Ok(entry);
+    };
+
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(entry);
+    };
+
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("head", |element| {
+            element.prepend(snippet, lol_html::html_content::ContentType::Html);
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })
+    .map_err(|error| Error::InsertImportMap {
+        input_path: entry.input_path_buf(),
+        source: error.into(),
+    })?;
+
+    Ok(Entry {
+        content: Some(content),
+        ..entry
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::config::{Config, ImportMapConfig};
+
+    #[test]
+    fn create_snippet() {
+        let config = Config {
+            base_url: "/blog".to_owned(),
+            import_map: Some(ImportMapConfig {
+                imports: HashMap::from([
+                    ("lit".to_owned(), "/vendor/lit.js".to_owned()),
+                    ("a/".to_owned(), "https://cdn.example.com/a/".to_owned()),
+                    ("b".to_owned(), "</script>".to_owned()),
+                ]),
+                scopes: HashMap::from([(
+                    "/legacy/".to_owned(),
+                    HashMap::from([("lit".to_owned(), "//cdn.example.com/lit.js".to_owned())]),
+                )]),
+            }),
+            ..Default::default()
+        };
+
+        let result = super::create_snippet(&config).unwrap();
+
+        assert_eq!(
+            result,
+            "<script type=\"importmap\">{\"imports\":{\"a/\":\"https://cdn.example.com/a/\",\
+             \"b\":\"<\\/script>\",\"lit\":\"/blog/vendor/lit.js\"},\"scopes\":{\"/blog/legacy/\"\
+             :{\"lit\":\"//cdn.example.com/lit.js\"}}}</script>"
+        );
+
+        assert!(super::create_snippet(&Config::default()).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+//! Add subresource integrity hashes.
+//!
+//! See <https://www.w3.org/TR/SRI/>.
+
+use std::collections::HashMap;
+
+use base64::Engine;
+use sha2::{Digest, Sha384};
+
+use super::{Config, Entry, Error};
+use crate::util::url::Url;
+
+/// Add `integrity` attributes to scripts and stylesheets.
+///
+/// If `subresource_integrity` is enabled in the configuration, this function
+/// computes the SHA-384 hash of each CSS and JavaScript entry. Then, it adds
+/// the `integrity` and `crossorigin` attributes to `<script src>` and `<link
+/// rel="stylesheet">` elements referencing these entries in HTML pages.
+///
+/// This function must be called after the content of entries is final (e.g.
+/// after minification).
+pub(super) fn add_integrity_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let base_url = config.base_url.to_owned();
+    let enabled = config.subresource_integrity;
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    // Create a mapping from URLs to integrity hashes
+    let hashes: HashMap<String, String> = entries
+        .iter()
+        .filter(|_| enabled)
+        .filter(|entry| matches!(entry.format.as_str(), "css" | "js"))
+        .filter_map(|entry| {
+            entry
+                .content
+                .as_ref()
+                .map(|content| (entry.url.to_owned(), hash(content)))
+        })
+        .collect();
+
+    let entries = entries.into_iter().map(move |entry| {
+        if hashes.is_empty() || entry.format != "html" {
+            return Ok(entry);
+        }
+
+        let Some(content) = entry.content.as_ref() else {
+            return Ok(entry);
+        };
+
+        let page_dir = entry.base_dir();
+
+        // Find the hash of the entry referenced by a URL
+        let find_hash = |href: &str| -> Option<&String> {
+            let href = href.trim();
+
+            let href = if base_url.is_empty() {
+                href
+            } else {
+                href.strip_prefix(&base_url)
+                    .filter(|href| href.starts_with('/'))
+                    .unwrap_or(href)
+            };
+
+            let url = Url::from(href);
+
+            if url.scheme.is_some() || url.authority.is_some() {
+                return None;
+            }
+
+            hashes.get(page_dir.join(&url.path).as_str())
+        };
+
+        let add_integrity = |element: &mut lol_html::html_content::Element,
+                             attribute: &str|
+         -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            if element.has_attribute("integrity") {
+                return Ok(());
+            }
+
+            let Some(hash) = element
+                .get_attribute(attribute)
+                .and_then(|href| find_hash(&href))
+            else {
+                return Ok(());
+            };
+
+            element.set_attribute("integrity", hash)?;
+
+            if !element.has_attribute("crossorigin") {
+                element.set_attribute("crossorigin", "anonymous")?;
+            }
+
+            Ok(())
+        };
+
+        let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+            element_content_handlers: vec![
+                lol_html::element!("script[src]", |element| add_integrity(element, "src")),
+                lol_html::element!("link[rel=\"stylesheet\" i][href]", |element| {
+                    add_integrity(element, "href")
+                }),
+            ],
+            ..lol_html::RewriteStrSettings::default()
+        })
+        .map_err(|error| Error::AddIntegrity {
+            input_path: entry.input_path_buf(),
+            source: error.into(),
+        })?;
+
+        Ok(Entry {
+            content: Some(content),
+            ..entry
+        })
+    });
+
+    Ok(entries)
+}
+
+/// Compute the integrity metadata of a content, e.g. `sha384-{base64}`.
+fn hash<S>(content: S) -> String
+where
+    S: AsRef<str>,
+{
+    let digest = Sha384::digest(content.as_ref().as_bytes());
+    format!(
+        "sha384-{}",
+        base64::engine::general_purpose::STANDARD.encode(digest)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn hash() {
+        // See <https://www.w3.org/TR/SRI/#introduction>
+        assert_eq!(
+            super::hash("alert('Hello, world.');"),
+            "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO"
+        );
+    }
+}
@@ -2,11 +2,23 @@
 //!
 //! This module uses [`tera`] under the hood.
 
-use std::collections::HashMap;
+mod builtins;
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
 
 use tera::Tera;
+use walkdir::WalkDir;
 
-use super::{Config, Entry, Error};
+use super::{
+    breadcrumbs::Breadcrumbs, markdown::Parser, navigation::Navigation, url::Urls, Config, Entry,
+    Error,
+};
+use crate::{error::Location, util::function::Memo};
 
 /// Layout engine.
 pub(super) struct Engine {
@@ -21,56 +33,129 @@ pub(super) struct Engine {
 
     /// Tera template engine.
     tera: Tera,
+
+    /// Output URLs of input files, used by the `url_for` and `asset_url`
+    /// functions.
+    urls: Arc<OnceLock<Urls>>,
+
+    /// Directories containing the template files, by order of precedence.
+    layouts_dirs: Vec<PathBuf>,
+
+    /// Templates used by each layout, including the layout itself.
+    layout_templates: RefCell<HashMap<String, HashSet<String>>>,
+
+    /// Templates used to render each page.
+    dependencies: RefCell<Dependencies>,
+}
+
+/// Templates used to render each page, indexed by input path.
+///
+/// When a template changes, only the pages that use it must be rendered again.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Dependencies(HashMap<PathBuf, HashSet<String>>);
+
+impl Dependencies {
+    /// Add the dependencies of other pages, replacing existing ones.
+    pub(super) fn extend(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    /// Return the input paths of the pages that use any of given templates.
+    pub(super) fn pages_using(&self, templates: &HashSet<String>) -> HashSet<PathBuf> {
+        self.0
+            .iter()
+            .filter(|(_, page_templates)| !page_templates.is_disjoint(templates))
+            .map(|(input_path, _)| input_path.to_owned())
+            .collect()
+    }
 }
 
 impl Engine {
     /// Create and configure a layout engine.
-    pub(super) fn new(config: &Config) -> Result<Self, Error> {
-        config
+    ///
+    /// The Markdown parser is shared with the `markdown` filter.
+    pub(super) fn new(config: &Config, markdown_parser: Arc<Parser>) -> Result<Self, Error> {
+        // Layouts of the site take precedence over those of the theme
+        let layouts_dirs: Vec<PathBuf> = config
             .layouts_dir
-            .as_ref()
-            .map(|layouts_dir| {
-                let mut tera =
-                    Tera::new(layouts_dir.join("**").join("*").to_str().ok_or_else(|| {
-                        Error::NewLayoutEngine {
-                            source: anyhow::anyhow!("Invalid layouts_dir"),
-                        }
-                    })?)
+            .iter()
+            .cloned()
+            .chain(theme_layouts_dir(config))
+            .collect();
+
+        (!layouts_dirs.is_empty())
+            .then(|| {
+                let mut tera = Tera::default();
+
+                // Templates added last replace those with the same name
+                tera.add_template_files(find_template_files(layouts_dirs.iter().rev()))
                     .map_err(|error| Error::NewLayoutEngine {
                         source: error.into(),
                     })?;
 
+                let urls = Arc::new(OnceLock::new());
+
+                // Built-in filters can be replaced by custom filters of the same name
+                builtins::register(&mut tera, config, markdown_parser, urls.to_owned());
+
+                // Results of pure filters, functions and testers
+                let memo = Memo::default();
+
+                // Return the memo of a function, if it is memoized
+                let memo_of = |name: &String| {
+                    config
+                        .layouts
+                        .memoize
+                        .contains(name)
+                        .then(|| memo.to_owned())
+                };
+
                 for (name, filter) in config.layouts.filters.iter() {
                     let filter = filter.to_owned();
+                    let key = format!("filter {name}");
+                    let memo = memo_of(name);
                     let filter = move |value: &tera::Value,
                                        args: &HashMap<String, tera::Value>|
                           -> tera::Result<tera::Value> {
-                        filter
-                            .call_2(value, args)
-                            .map_err(|error| tera::Error::msg(error.to_string()))
+                        let call = || filter.call_2(value, args);
+                        let result = match memo.as_ref() {
+                            Some(memo) => memo.get_or_call(&key, &(value, args), call),
+                            None => call(),
+                        };
+                        result.map_err(|error| tera::Error::msg(error.to_string()))
                     };
                     tera.register_filter(name, filter);
                 }
 
                 for (name, function) in config.layouts.functions.iter() {
                     let function = function.to_owned();
+                    let key = format!("function {name}");
+                    let memo = memo_of(name);
                     let function =
                         move |args: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
-                            function
-                                .call_1(args)
-                                .map_err(|error| tera::Error::msg(error.to_string()))
+                            let call = || function.call_1(args);
+                            let result = match memo.as_ref() {
+                                Some(memo) => memo.get_or_call(&key, args, call),
+                                None => call(),
+                            };
+                            result.map_err(|error| tera::Error::msg(error.to_string()))
                         };
                     tera.register_function(name, function);
                 }
 
                 for (name, tester) in config.layouts.testers.iter() {
                     let tester = tester.to_owned();
+                    let key = format!("tester {name}");
+                    let memo = memo_of(name);
                     let tester = move |value: Option<&tera::Value>,
                                        args: &[tera::Value]|
                           -> tera::Result<bool> {
-                        tester
-                            .call_2(&value, args)
-                            .map_err(|error| tera::Error::msg(error.to_string()))
+                        let call = || tester.call_2(&value, args);
+                        let result = match memo.as_ref() {
+                            Some(memo) => memo.get_or_call(&key, &(value, args), call),
+                            None => call(),
+                        };
+                        result.map_err(|error| tera::Error::msg(error.to_string()))
                     };
                     tera.register_tester(name, tester);
                 }
@@ -80,6 +165,10 @@ impl Engine {
                     layout_key: config.layouts.layout_key.to_owned(),
                     page_key: config.layouts.page_key.to_owned(),
                     tera,
+                    urls,
+                    layouts_dirs,
+                    layout_templates: RefCell::default(),
+                    dependencies: RefCell::default(),
                 })
             })
             .unwrap_or_else(|| {
@@ -89,16 +178,28 @@ impl Engine {
             })
     }
 
+    /// Set the output URLs of input files, once all entries are known.
+    ///
+    /// The URLs can only be set once.
+    pub(super) fn set_urls(&self, urls: Urls) {
+        let _ = self.urls.set(urls);
+    }
+
     /// Render the layout of a [`Entry`].
     ///
     /// This function extracts the `layout` property from the metadata to
     /// determine the layout file. The metadata fields and the content are
-    /// merged into a single context for the layout engine. The rendered output
-    /// replaces the `content` property in the build entry.
+    /// merged into a single context for the layout engine. The page variable
+    /// contains the `url` and the `ancestors` of the page (breadcrumb trail).
+    /// If the navigation is enabled, the `site` variable contains the site
+    /// navigation under `nav`. The rendered output replaces the `content`
+    /// property in the build entry.
     pub(super) fn render_entry(
         &self,
         entry: Entry,
         provided_data: &serde_json::Value,
+        breadcrumbs: &Breadcrumbs,
+        navigation: Option<&Navigation>,
     ) -> Result<Entry, Error> {
         // Get metadata
         let mut data = if let Some(entry_data) = entry.data.as_ref() {
@@ -125,6 +226,14 @@ impl Engine {
             return Ok(entry);
         };
 
+        if let Some(input_path) = entry.input_path() {
+            // Record the templates used by the page
+            self.dependencies
+                .borrow_mut()
+                .0
+                .insert(input_path.to_owned(), self.templates_of(layout));
+        }
+
         if !self.content_key.is_empty() {
             // Add content to the metadata
             if let Some(content) = entry.content.as_ref() {
@@ -135,14 +244,48 @@ impl Engine {
 
         if !self.page_key.is_empty() {
             // Add page data
+            let ancestors = serde_json::to_value(
+                breadcrumbs
+                    .get(&entry.url)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            )
+            .map_err(|error| Error::RenderLayout {
+                input_path: entry.input_path_buf(),
+                layout: Some(layout.to_owned()),
+                source: error.into(),
+            })?;
+
             data.as_object_mut().map(|map| {
                 map.insert(
                     self.page_key.to_owned(),
-                    tera::Map::from_iter([("url".to_owned(), entry.url.to_owned().into())]).into(),
+                    tera::Map::from_iter([
+                        ("url".to_owned(), entry.url.to_owned().into()),
+                        ("ancestors".to_owned(), ancestors),
+                    ])
+                    .into(),
                 )
             });
         }
 
+        if let Some(navigation) = navigation {
+            // Add site navigation, flagged for the current page
+            let nav = navigation.items_for_page(&entry.url);
+            let nav = serde_json::to_value(nav).map_err(|error| Error::RenderLayout {
+                input_path: entry.input_path_buf(),
+                layout: Some(layout.to_owned()),
+                source: error.into(),
+            })?;
+
+            // Keep other fields of the `site` variable (e.g. from global data)
+            if let Some(map) = data.as_object_mut() {
+                let site = map.entry("site").or_insert_with(|| tera::Map::new().into());
+                if let Some(site) = site.as_object_mut() {
+                    site.insert("nav".to_owned(), nav);
+                }
+            }
+        }
+
         let content = self
             .render(layout, data)
             .map_err(|error| Error::RenderLayout {
@@ -157,6 +300,43 @@ impl Engine {
         })
     }
 
+    /// Return the templates used to render each page.
+    pub(super) fn into_dependencies(self) -> Dependencies {
+        self.dependencies.into_inner()
+    }
+
+    /// Return the templates used by a layout, including the layout itself.
+    ///
+    /// Templates are found by following the `extends`, `include` and `import`
+    /// tags of the template files.
+    fn templates_of(&self, layout: &str) -> HashSet<String> {
+        if let Some(templates) = self.layout_templates.borrow().get(layout) {
+            return templates.to_owned();
+        }
+
+        let mut templates = HashSet::new();
+        let mut names = vec![layout.to_owned()];
+
+        while let Some(name) = names.pop() {
+            if !templates.insert(name.to_owned()) {
+                continue;
+            }
+            // Missing templates fail at render time
+            let source = self
+                .layouts_dirs
+                .iter()
+                .find_map(|dir| std::fs::read_to_string(dir.join(&name)).ok())
+                .unwrap_or_default();
+            names.extend(find_template_references(&source));
+        }
+
+        self.layout_templates
+            .borrow_mut()
+            .insert(layout.to_owned(), templates.to_owned());
+
+        templates
+    }
+
     /// Render a layout given data.
     fn render<L, D>(&self, layout: L, data: D) -> anyhow::Result<String>
     where
@@ -167,8 +347,345 @@ impl Engine {
 
         let context = tera::Context::from_serialize(&data)?;
 
-        let output = self.tera.render(&layout, &context)?;
+        let output = self
+            .tera
+            .render(layout, &context)
+            .map_err(|error| self.locate_error(error))?;
 
         Ok(output)
     }
+
+    /// Add the location of a rendering error (template, line and column) and
+    /// an excerpt of the template source.
+    ///
+    /// Tera does not report positions of rendering errors, so the location is
+    /// the first occurrence of the missing variable, filter, function, test or
+    /// template in the template where the error happened.
+    fn locate_error(&self, error: tera::Error) -> anyhow::Error {
+        let messages: Vec<String> =
+            std::iter::successors(Some(&error as &dyn std::error::Error), |error| {
+                error.source()
+            })
+            .map(ToString::to_string)
+            .collect();
+
+        let (Some(template), Some(subject)) = parse_error_messages(&messages) else {
+            return error.into();
+        };
+
+        let location = self
+            .layouts_dirs
+            .iter()
+            .find_map(|dir| {
+                let path = dir.join(&template);
+                std::fs::read_to_string(&path)
+                    .ok()
+                    .map(|source| (path, source))
+            })
+            .and_then(|(path, source)| {
+                let (line, message) = excerpt(&template, &source, &subject)?;
+                Some(Location {
+                    path: Some(path),
+                    line,
+                    message,
+                })
+            });
+
+        match location {
+            Some(location) => anyhow::Error::from(error).context(location),
+            None => error.into(),
+        }
+    }
+}
+
+/// Return the layouts directory of the theme, if any.
+pub(super) fn theme_layouts_dir(config: &Config) -> Option<PathBuf> {
+    config
+        .theme
+        .as_ref()
+        .map(|theme| theme.join("_layouts"))
+        .filter(|dir| dir.is_dir())
+}
+
+/// Find the template files in layouts directories, with their names.
+///
+/// Hidden files are ignored.
+fn find_template_files<'a>(
+    layouts_dirs: impl Iterator<Item = &'a PathBuf>,
+) -> Vec<(PathBuf, Option<String>)> {
+    layouts_dirs
+        .flat_map(|layouts_dir| {
+            WalkDir::new(layouts_dir)
+                .into_iter()
+                .filter_entry(|entry| {
+                    entry.depth() == 0
+                        || entry
+                            .file_name()
+                            .to_str()
+                            .is_some_and(|file_name| !file_name.starts_with('.'))
+                })
+                .filter_map(|result| {
+                    // Ignore errors (e.g. permission denied)
+                    result.ok()
+                })
+                .filter(|entry| entry.file_type().is_file())
+                .filter_map(move |entry| {
+                    let name = entry
+                        .path()
+                        .strip_prefix(layouts_dir)
+                        .ok()
+                        .and_then(template_name)?;
+                    Some((entry.into_path(), Some(name)))
+                })
+        })
+        .collect()
+}
+
+/// Return the name of the template at a path relative to the layouts
+/// directory (e.g. `partials/header.tera`).
+pub(super) fn template_name(path: &Path) -> Option<String> {
+    path.components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()
+        .map(|components| components.join("/"))
+        .filter(|name| !name.is_empty())
+}
+
+/// Find the names of the templates referenced in a template source by
+/// `extends`, `include` and `import` tags.
+fn find_template_references(source: &str) -> Vec<String> {
+    source
+        .split("{%")
+        .skip(1)
+        .filter_map(|tag| tag.split_once("%}").map(|(tag, _)| tag))
+        .map(|tag| tag.trim_matches(|c: char| c == '-' || c.is_whitespace()))
+        .filter_map(|tag| tag.split_once(char::is_whitespace))
+        .filter_map(|(keyword, arguments)| match keyword {
+            "extends" | "include" => Some(arguments),
+            // Ignore the namespace after `as`
+            "import" => arguments.split(" as ").next(),
+            _ => None,
+        })
+        .flat_map(find_string_literals)
+        .collect()
+}
+
+/// Find the string literals in a tag (e.g. `["a.tera", 'b.tera']`).
+fn find_string_literals(input: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = input.chars();
+
+    while let Some(quote) = chars.find(|c| matches!(c, '"' | '\'' | '`')) {
+        literals.push(chars.by_ref().take_while(|&c| c != quote).collect());
+    }
+
+    literals
+}
+
+/// Find the template where a rendering error happened, and the name of the
+/// variable, filter, function, test or template that caused it, in the
+/// messages of a Tera error (from the outermost to the innermost).
+fn parse_error_messages(messages: &[String]) -> (Option<String>, Option<String>) {
+    const TEMPLATE_PATTERNS: [&str; 3] = [
+        "while rendering '",
+        "(error happened in '",
+        "Failed to render '",
+    ];
+
+    const SUBJECT_PATTERNS: [(&str, char); 8] = [
+        ("Variable `", '`'),
+        ("Filter '", '\''),
+        ("Filter call '", '\''),
+        ("Function '", '\''),
+        ("Function call '", '\''),
+        ("Test '", '\''),
+        ("Test call '", '\''),
+        ("Template '", '\''),
+    ];
+
+    let quoted = |message: &str, pattern: &str, quote: char| {
+        let (_, rest) = message.split_once(pattern)?;
+        rest.split_once(quote).map(|(name, _)| name.to_owned())
+    };
+
+    let mut template = None;
+    let mut subject = None;
+
+    // Inner messages are more precise
+    for message in messages {
+        if let Some(name) = TEMPLATE_PATTERNS
+            .iter()
+            .find_map(|pattern| quoted(message, pattern, '\''))
+        {
+            template = Some(name);
+        }
+
+        if let Some(name) = SUBJECT_PATTERNS
+            .iter()
+            .filter(|(pattern, _)| message.starts_with(pattern))
+            .find_map(|(pattern, quote)| quoted(message, pattern, *quote))
+        {
+            subject = Some(name);
+        }
+    }
+
+    (template, subject)
+}
+
+/// Return an excerpt of a template source at the first occurrence of a name
+/// inside a tag (e.g. `{{ name }}`), with its line and column.
+///
+/// Returns a tuple (`line`, `excerpt`).
+fn excerpt(template: &str, source: &str, name: &str) -> Option<(usize, String)> {
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '/');
+
+    let start = source
+        .match_indices(name)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let before = &source[..index];
+            let after = &source[index + name.len()..];
+
+            // The name is not part of a longer name
+            let is_whole = !before.ends_with(is_name_char) && !after.starts_with(is_name_char);
+
+            // The last opening tag is not closed
+            let is_in_tag = before
+                .rfind("{{")
+                .max(before.rfind("{%"))
+                .is_some_and(|open| {
+                    !before[open..].contains("}}") && !before[open..].contains("%}")
+                });
+
+            is_whole && is_in_tag
+        })?;
+
+    let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |index| start + index);
+
+    let line = source[..start].matches('\n').count() + 1;
+    let column = source[line_start..start].chars().count() + 1;
+    let text = source[line_start..line_end].trim_end();
+
+    let margin = " ".repeat(line.to_string().len());
+    let padding = " ".repeat(column - 1);
+    let marker = "^".repeat(name.chars().count());
+
+    Some((
+        line,
+        format!(
+            "{margin}--> {template}:{line}:{column}\n{margin} |\n{line} | {text}\n{margin} | \
+             {padding}{marker}"
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_error_messages() {
+        const CASES: [(&[&str], Option<&str>, Option<&str>); 4] = [
+            (
+                &[
+                    "Failed to render 'page.tera'",
+                    "Variable `page.author` not found in context while rendering 'page.tera'",
+                ],
+                Some("page.tera"),
+                Some("page.author"),
+            ),
+            (
+                &[
+                    "Failed to render 'page.tera' (error happened in 'base.tera').",
+                    "Filter 'unknown' not found",
+                ],
+                Some("base.tera"),
+                Some("unknown"),
+            ),
+            (
+                &[
+                    "Failed to render 'page.tera'",
+                    "Function call 'url_for' failed",
+                    "File not found",
+                ],
+                Some("page.tera"),
+                Some("url_for"),
+            ),
+            (&["Template 'page.tera' not found"], None, Some("page.tera")),
+        ];
+
+        for (input, template, subject) in CASES {
+            let messages: Vec<String> = input.iter().map(ToString::to_string).collect();
+            let result = super::parse_error_messages(&messages);
+            let expected = (template.map(String::from), subject.map(String::from));
+            assert_eq!(
+                result, expected,
+                "\nparse_error_messages({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn excerpt() {
+        let source = "<title>{{ title }}</title>\n<p>{{ page.author | upper }} {{ author }}</p>\n";
+
+        assert_eq!(
+            super::excerpt("page.tera", source, "author"),
+            Some((
+                2,
+                concat!(
+                    " --> page.tera:2:33\n",
+                    "  |\n",
+                    "2 | <p>{{ page.author | upper }} {{ author }}</p>\n",
+                    "  |                                 ^^^^^^",
+                )
+                .to_owned()
+            ))
+        );
+
+        assert_eq!(
+            super::excerpt("page.tera", source, "upper"),
+            Some((
+                2,
+                concat!(
+                    " --> page.tera:2:21\n",
+                    "  |\n",
+                    "2 | <p>{{ page.author | upper }} {{ author }}</p>\n",
+                    "  |                     ^^^^^",
+                )
+                .to_owned()
+            ))
+        );
+
+        assert_eq!(super::excerpt("page.tera", source, "date"), None);
+    }
+
+    #[test]
+    fn find_template_references() {
+        const CASES: [(&str, &[&str]); 5] = [
+            ("{{ content }}", &[]),
+            ("{% extends \"base.tera\" %}", &["base.tera"]),
+            (
+                "{%- include 'partials/nav.tera' -%}",
+                &["partials/nav.tera"],
+            ),
+            ("{% include [\"a.tera\", \"b.tera\"] ignore missing %}", &[
+                "a.tera", "b.tera",
+            ]),
+            (
+                "{% import \"macros.tera\" as macros %}{% if x %}{% endif %}",
+                &["macros.tera"],
+            ),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::find_template_references(input);
+            assert_eq!(
+                result, expected,
+                "\nfind_template_references({input:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
 }
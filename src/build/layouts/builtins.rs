@@ -0,0 +1,345 @@
+//! Built-in filters and functions of the layout engine.
+//!
+//! These complement the built-in filters of [`tera`] (e.g. `truncate`,
+//! `group_by`), so that common tasks do not require script functions.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+};
+
+use tera::Tera;
+
+use crate::{
+    build::{images::render_picture, markdown::Parser, url::Urls},
+    config::Config,
+    util::{
+        date_time::{parse_offset, DateTime},
+        path::PathExt,
+        slug::SlugStrategy,
+        url::Url,
+    },
+};
+
+/// Register the built-in filters and functions.
+pub(super) fn register(
+    tera: &mut Tera,
+    config: &Config,
+    markdown_parser: Arc<Parser>,
+    urls: Arc<OnceLock<Urls>>,
+) {
+    // Display internationalized domain names in Unicode
+    tera.register_filter(
+        "url_to_unicode",
+        |value: &tera::Value, _: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+            let url = tera::from_value::<String>(value.to_owned())?;
+            Ok(Url::from(url).to_unicode().to_string().into())
+        },
+    );
+
+    // Format dates, e.g. `{{ date | format_date(format="%d/%m/%Y") }}`
+    let timezone = config
+        .timezone
+        .as_ref()
+        .and_then(|timezone| parse_offset(timezone).ok());
+    tera.register_filter(
+        "format_date",
+        move |value: &tera::Value,
+              args: &HashMap<String, tera::Value>|
+              -> tera::Result<tera::Value> {
+            let date = tera::from_value::<String>(value.to_owned())?;
+            let mut date = DateTime::parse_in_timezone(date, timezone)
+                .map_err(|error| tera::Error::msg(error.to_string()))?;
+            if let Some(offset) = args.get("offset") {
+                let offset = tera::from_value::<String>(offset.to_owned())?;
+                date = date.with_offset(
+                    parse_offset(offset).map_err(|error| tera::Error::msg(error.to_string()))?,
+                );
+            }
+            let format = args
+                .get("format")
+                .map(|format| tera::from_value::<String>(format.to_owned()))
+                .transpose()?;
+            Ok(match format {
+                Some(format) => date
+                    .format(format)
+                    .map_err(|error| tera::Error::msg(error.to_string()))?,
+                None => date.to_rfc3339(),
+            }
+            .into())
+        },
+    );
+
+    // Convert strings to slugs according to the configured strategy
+    let slug_strategy = config
+        .slug_strategy
+        .parse::<SlugStrategy>()
+        .unwrap_or_default();
+    tera.register_filter(
+        "slugify",
+        move |value: &tera::Value, _: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+            let value = tera::from_value::<String>(value.to_owned())?;
+            Ok(slug_strategy.slugify(value).into())
+        },
+    );
+
+    // Render Markdown, e.g. `{{ summary | markdown(inline=true) }}`
+    tera.register_filter("markdown", MarkdownFilter(markdown_parser));
+
+    // Serialize to JSON, e.g. `{{ page | jsonify(pretty=true) }}`
+    tera.register_filter(
+        "jsonify",
+        |value: &tera::Value, args: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+            let result = if bool_arg(args, "pretty")? {
+                serde_json::to_string_pretty(value)
+            } else {
+                serde_json::to_string(value)
+            };
+            result
+                .map(tera::Value::from)
+                .map_err(|error| tera::Error::msg(error.to_string()))
+        },
+    );
+
+    // Select items, e.g. `{{ posts | where(attribute="tags", value="rust") }}`
+    tera.register_filter(
+        "where",
+        |value: &tera::Value, args: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+            let attribute = args
+                .get("attribute")
+                .map(|attribute| tera::from_value::<String>(attribute.to_owned()))
+                .transpose()?
+                .ok_or_else(|| tera::Error::msg("The `where` filter requires an `attribute`"))?;
+            let items = tera::from_value::<Vec<tera::Value>>(value.to_owned())?;
+            Ok(filter_where(items, &attribute, args.get("value")).into())
+        },
+    );
+
+    // Relative URLs are computed later, when rewriting URLs
+    let base_url = if config.relative_urls {
+        String::new()
+    } else {
+        config.base_url.to_owned()
+    };
+
+    // Resolve input file paths to URLs, e.g. `{{ url_for(path="blog/post.md") }}`
+    for name in ["url_for", "asset_url"] {
+        let urls = urls.to_owned();
+        let input_dirs: Vec<PathBuf> = config.input_dirs().cloned().collect();
+        let base_url = base_url.to_owned();
+        tera.register_function(
+            name,
+            move |args: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+                let path = args
+                    .get("path")
+                    .map(|path| tera::from_value::<String>(path.to_owned()))
+                    .transpose()?
+                    .ok_or_else(|| tera::Error::msg(format!("`{name}` requires a `path`")))?;
+                let url = urls
+                    .get()
+                    .and_then(|urls| resolve_url(urls, &input_dirs, &path))
+                    .ok_or_else(|| tera::Error::msg(format!("No input file found for {path:?}")))?;
+                Ok(format!("{base_url}{url}").into())
+            },
+        );
+    }
+
+    // Render pictures, e.g. `{{ image(path="photo.jpg", alt="A photo") }}`
+    tera.register_function("image", ImageFunction {
+        urls,
+        input_dirs: config.input_dirs().cloned().collect(),
+        base_url,
+    });
+}
+
+/// Return the output URL of an input file, given its path relative to the
+/// input directory (or the theme directory).
+fn resolve_url<'a>(urls: &'a Urls, input_dirs: &[PathBuf], path: &str) -> Option<&'a str> {
+    input_dirs
+        .iter()
+        .filter_map(|input_dir| input_dir.join_url_path(path).canonicalize().ok())
+        .find_map(|path| urls.get(&path))
+        .map(String::as_str)
+}
+
+/// Filter that renders Markdown to HTML.
+///
+/// The output is marked as safe, so that it is not escaped.
+struct MarkdownFilter(Arc<Parser>);
+
+impl tera::Filter for MarkdownFilter {
+    fn filter(
+        &self,
+        value: &tera::Value,
+        args: &HashMap<String, tera::Value>,
+    ) -> tera::Result<tera::Value> {
+        let input = tera::from_value::<String>(value.to_owned())?;
+        let output = self.0.render(&input);
+        Ok(if bool_arg(args, "inline")? {
+            strip_paragraph(&output).to_owned()
+        } else {
+            output
+        }
+        .into())
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Function that renders a `<picture>` element for an image of the input
+/// directory (or the theme directory).
+///
+/// The output is marked as safe, so that it is not escaped.
+struct ImageFunction {
+    /// Output URLs of input files.
+    urls: Arc<OnceLock<Urls>>,
+
+    /// Input directories, by order of precedence.
+    input_dirs: Vec<PathBuf>,
+
+    /// Prefix of the image URLs.
+    base_url: String,
+}
+
+impl tera::Function for ImageFunction {
+    fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+        let path = string_arg(args, "path")?
+            .ok_or_else(|| tera::Error::msg("`image` requires a `path`"))?;
+        let alt = string_arg(args, "alt")?.unwrap_or_default();
+        let caption = string_arg(args, "caption")?;
+
+        let (file, url) = self
+            .input_dirs
+            .iter()
+            .filter_map(|input_dir| input_dir.join_url_path(&path).canonicalize().ok())
+            .find_map(|file| {
+                let url = self.urls.get()?.get(&file)?.to_owned();
+                Some((file, url))
+            })
+            .ok_or_else(|| tera::Error::msg(format!("No input file found for {path:?}")))?;
+
+        let src = format!("{}{url}", self.base_url);
+
+        Ok(render_picture(&file, &src, &alt, caption.as_deref()).into())
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+/// Get a string argument, if any.
+fn string_arg(args: &HashMap<String, tera::Value>, name: &str) -> tera::Result<Option<String>> {
+    args.get(name)
+        .map(|value| tera::from_value::<String>(value.to_owned()))
+        .transpose()
+}
+
+/// Get a boolean argument, `false` if missing.
+fn bool_arg(args: &HashMap<String, tera::Value>, name: &str) -> tera::Result<bool> {
+    args.get(name)
+        .map(|value| tera::from_value::<bool>(value.to_owned()))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Remove the `<p>` element that encloses a single paragraph.
+fn strip_paragraph(html: &str) -> &str {
+    html.trim_end()
+        .strip_prefix("<p>")
+        .and_then(|html| html.strip_suffix("</p>"))
+        .filter(|html| !html.contains("<p>"))
+        .unwrap_or(html)
+}
+
+/// Keep the items whose attribute matches a value.
+///
+/// The attribute is a dot-separated path (e.g. `data.tags`). If the attribute
+/// is an array, items are kept if it contains the value. Without a value,
+/// items are kept if the attribute is truthy.
+fn filter_where(
+    items: Vec<tera::Value>,
+    attribute: &str,
+    value: Option<&tera::Value>,
+) -> Vec<tera::Value> {
+    items
+        .into_iter()
+        .filter(|item| {
+            let attribute = attribute
+                .split('.')
+                .try_fold(item, |item, key| item.get(key));
+            match (attribute, value) {
+                (Some(tera::Value::Array(array)), Some(value)) => array.contains(value),
+                (Some(attribute), Some(value)) => attribute == value,
+                (Some(attribute), None) => is_truthy(attribute),
+                (None, _) => false,
+            }
+        })
+        .collect()
+}
+
+/// Check whether a value is truthy, as in a `{% if %}` block.
+fn is_truthy(value: &tera::Value) -> bool {
+    match value {
+        tera::Value::Null => false,
+        tera::Value::Bool(value) => *value,
+        tera::Value::Number(value) => value.as_f64().is_some_and(|value| value != 0.0),
+        tera::Value::String(value) => !value.is_empty(),
+        tera::Value::Array(value) => !value.is_empty(),
+        tera::Value::Object(value) => !value.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    #[test]
+    fn strip_paragraph() {
+        const CASES: [(&str, &str); 3] = [
+            ("<p>Hello <em>world</em></p>\n", "Hello <em>world</em>"),
+            ("<p>a</p>\n<p>b</p>\n", "<p>a</p>\n<p>b</p>\n"),
+            ("<h1>Title</h1>\n", "<h1>Title</h1>\n"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::strip_paragraph(input);
+            assert_eq!(
+                result, expected,
+                "\nstrip_paragraph({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn filter_where() {
+        let items = vec![
+            json!({ "title": "a", "data": { "tags": ["rust", "web"], "draft": true } }),
+            json!({ "title": "b", "data": { "tags": ["web"], "lang": "en" } }),
+            json!({ "title": "c", "data": { "lang": "fr" } }),
+        ];
+
+        let cases = [
+            ("data.tags", Some(json!("web")), vec!["a", "b"]),
+            ("data.tags", Some(json!("rust")), vec!["a"]),
+            ("data.lang", Some(json!("fr")), vec!["c"]),
+            ("data.draft", None, vec!["a"]),
+            ("data.missing", None, vec![]),
+        ];
+
+        for (attribute, value, expected) in cases {
+            let result: Vec<_> = super::filter_where(items.to_owned(), attribute, value.as_ref())
+                .iter()
+                .filter_map(|item| item["title"].as_str().map(str::to_owned))
+                .collect();
+            assert_eq!(
+                result, expected,
+                "\nfilter_where({attribute:?}, {value:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
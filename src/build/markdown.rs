@@ -2,23 +2,49 @@
 //!
 //! This module uses [`markdown_it`] under the hood.
 
+mod abbreviations;
+mod admonitions;
+mod attribute_lists;
+mod definition_lists;
 mod math;
 mod syntax_highlight;
 
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
 
 use markdown_it::{parser::extset::MarkdownItExt, MarkdownIt};
+use sha2::{Digest, Sha256};
+use syntect::parsing::SyntaxSet;
+use walkdir::WalkDir;
 
-use super::{Config, Entry, Error};
-use crate::util::function::Function;
+use super::{cache::Cache, Config, Entry, Error};
+use crate::util::{function::Function, slug::SlugStrategy};
 
 /// Context stored in [`MarkdownIt`].
 #[derive(Debug)]
-pub(self) struct Context {
+struct Context {
+    /// Admonitions configuration
+    pub(self) admonitions: AdmonitionsContext,
+
+    /// Strategy for heading anchors
+    pub(self) slug_strategy: SlugStrategy,
+
     /// Syntax highlight configuration
     pub(self) syntax_highlight: SyntaxHighlightContext,
 }
 
+/// Admonitions configuration for Markdown.
+#[derive(Debug)]
+struct AdmonitionsContext {
+    /// CSS class of admonition `<aside>` elements
+    pub(self) class: String,
+
+    /// CSS class of admonition titles
+    pub(self) title_class: String,
+}
+
 /// Syntax highlight configuration for Markdown.
 #[derive(Debug)]
 pub(self) struct SyntaxHighlightContext {
@@ -31,8 +57,17 @@ pub(self) struct SyntaxHighlightContext {
     /// Prefix for syntax highlight CSS classes
     pub(self) css_prefix: String,
 
+    /// Display line numbers in all code blocks
+    pub(self) line_numbers: bool,
+
+    /// Add a copy button to code blocks
+    pub(self) copy_button: bool,
+
     /// Syntax highlight HTML formatter
     pub(self) formatter: Option<Function>,
+
+    /// Syntax definitions
+    pub(self) syntax_set: SyntaxSet,
 }
 
 impl MarkdownItExt for Context {}
@@ -41,36 +76,66 @@ impl MarkdownItExt for Context {}
 pub(super) struct Parser {
     // Markdown-it parser.
     parser: MarkdownIt,
+
+    // Cache of rendered Markdown.
+    cache: Option<Cache>,
 }
 
 impl Parser {
     /// Create and configure a Markdown parser.
-    pub(super) fn new(config: &Config) -> Self {
+    pub(super) fn new(config: &Config) -> Result<Self, Error> {
+        let syntax_set = super::syntax_highlight::load_syntax_set(config)?;
+
+        let slug_strategy = config
+            .slug_strategy
+            .parse::<SlugStrategy>()
+            .unwrap_or_default();
+
         let mut parser = MarkdownIt::new();
         markdown_it::plugins::cmark::add(&mut parser);
         markdown_it::plugins::html::add(&mut parser);
+        admonitions::add(&mut parser);
         math::add(&mut parser);
         markdown_it::plugins::extra::strikethrough::add(&mut parser);
         markdown_it::plugins::extra::beautify_links::add(&mut parser);
         markdown_it::plugins::extra::linkify::add(&mut parser);
         markdown_it::plugins::extra::tables::add(&mut parser);
+        definition_lists::add(&mut parser);
         syntax_highlight::add(&mut parser);
         markdown_it::plugins::extra::typographer::add(&mut parser);
         markdown_it::plugins::extra::smartquotes::add(&mut parser);
-        markdown_it::plugins::extra::heading_anchors::add(&mut parser, |s| slug::slugify(s));
+        markdown_it::plugins::extra::heading_anchors::add(&mut parser, slug_strategy.slugify_fn());
         markdown_it_footnote::add(&mut parser);
+        attribute_lists::add(&mut parser);
+        abbreviations::add(&mut parser);
 
         // Context to be used in Markdown rules
         parser.ext.insert(Context {
+            admonitions: AdmonitionsContext {
+                class: config.admonitions.class.to_owned(),
+                title_class: config.admonitions.title_class.to_owned(),
+            },
+            slug_strategy,
             syntax_highlight: SyntaxHighlightContext {
                 code_attributes: config.syntax_highlight.code_attributes.to_owned(),
                 pre_attributes: config.syntax_highlight.pre_attributes.to_owned(),
                 css_prefix: config.syntax_highlight.css_prefix.to_owned(),
+                line_numbers: config.syntax_highlight.line_numbers,
+                copy_button: config.syntax_highlight.copy_button,
                 formatter: config.syntax_highlight.formatter.as_ref().cloned(),
+                syntax_set,
             },
         });
 
-        Self { parser }
+        // Results of the syntax highlight formatter function cannot be cached
+        let cache = config
+            .syntax_highlight
+            .formatter
+            .is_none()
+            .then(|| Cache::new(config, "markdown", cache_options(config)))
+            .flatten();
+
+        Ok(Self { parser, cache })
     }
 
     /// Parse Markdown content in a [`Entry`].
@@ -79,10 +144,8 @@ impl Parser {
     /// property. The `format` property is set to `html`.
     pub(super) fn parse_entry(&self, entry: Entry) -> Result<Entry, Error> {
         if let Some(content) = entry.content {
-            let content = self.parse(content);
-
             return Ok(Entry {
-                content: Some(content),
+                content: Some(self.render(&content)),
                 format: "html".to_owned(),
                 ..entry
             });
@@ -91,6 +154,14 @@ impl Parser {
         Ok(entry)
     }
 
+    /// Render a Markdown string to HTML, using the cache if enabled.
+    pub(super) fn render(&self, input: &str) -> String {
+        match self.cache.as_ref() {
+            Some(cache) => cache.get_or_insert_with(input, || self.parse(input)),
+            None => self.parse(input),
+        }
+    }
+
     /// Parse a Markdown string and return a HTML string.
     fn parse<S>(&self, input: S) -> String
     where
@@ -102,6 +173,53 @@ impl Parser {
     }
 }
 
+/// Return the options that determine the HTML rendered from Markdown.
+fn cache_options(config: &Config) -> String {
+    let syntax_highlight = &config.syntax_highlight;
+
+    serde_json::json!({
+        "admonitions": {
+            "class": config.admonitions.class,
+            "title_class": config.admonitions.title_class,
+        },
+        "slug_strategy": config.slug_strategy,
+        "syntax_highlight": {
+            "code_attributes": syntax_highlight.code_attributes.iter().collect::<BTreeMap<_, _>>(),
+            "pre_attributes": syntax_highlight.pre_attributes.iter().collect::<BTreeMap<_, _>>(),
+            "css_prefix": syntax_highlight.css_prefix,
+            "line_numbers": syntax_highlight.line_numbers,
+            "copy_button": syntax_highlight.copy_button,
+            "syntaxes_dir": syntax_highlight.syntaxes_dir,
+            "syntaxes": syntax_highlight.syntaxes_dir.as_deref().map(syntaxes_hash),
+        },
+    })
+    .to_string()
+}
+
+/// Return a hash of the paths and contents of the files of a syntaxes
+/// directory, so that pages are rendered again when a syntax changes.
+fn syntaxes_hash(syntaxes_dir: &Path) -> String {
+    let mut hasher = Sha256::new();
+
+    for entry in WalkDir::new(syntaxes_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let path = entry
+            .path()
+            .strip_prefix(syntaxes_dir)
+            .unwrap_or(entry.path());
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(std::fs::read(entry.path()).unwrap_or_default());
+        hasher.update([0]);
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::config::Config;
@@ -224,7 +342,46 @@ mod tests {
         ];
 
         let config = Config::default();
-        let parser = super::Parser::new(&config);
+        let parser = super::Parser::new(&config).unwrap();
+
+        for (input, expected) in CASES {
+            let result = parser.parse(input);
+            assert_eq!(
+                result.trim().to_owned(),
+                expected.to_owned(),
+                "\nparse({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_admonitions() {
+        const CASES: [(&str, &str); 3] = [
+            (
+                concat!(
+                    "> [!NOTE]\n", //
+                    "> Content"
+                ),
+                "<aside class=\"admonition admonition-note\">\n<p \
+                 class=\"admonition-title\">Note</p>\n<p>Content</p>\n</aside>",
+            ),
+            (
+                concat!(
+                    "> [!WARNING] Be careful\n", //
+                    ">\n",                       //
+                    "> - List"
+                ),
+                "<aside class=\"admonition admonition-warning\">\n<p \
+                 class=\"admonition-title\">Be careful</p>\n<ul>\n<li>List</li>\n</ul>\n</aside>",
+            ),
+            (
+                "> [!NOTE]Content",
+                "<blockquote>\n<p>[!NOTE]Content</p>\n</blockquote>",
+            ),
+        ];
+
+        let config = Config::default();
+        let parser = super::Parser::new(&config).unwrap();
 
         for (input, expected) in CASES {
             let result = parser.parse(input);
@@ -235,4 +392,124 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_extended_syntax() {
+        const CASES: [(&str, &str); 5] = [
+            (
+                concat!(
+                    "Term\n",           //
+                    ": Definition 1\n", //
+                    ": Definition 2"
+                ),
+                "<dl>\n<dt>Term</dt>\n<dd>Definition 1</dd>\n<dd>Definition 2</dd>\n</dl>",
+            ),
+            (
+                concat!(
+                    "HTML page\n", //
+                    "\n",          //
+                    "*[HTML]: HyperText Markup Language"
+                ),
+                "<p><abbr title=\"HyperText Markup Language\">HTML</abbr> page</p>",
+            ),
+            (
+                "## Heading {#custom .big}",
+                "<h2 id=\"custom\" class=\"big\">Heading</h2>",
+            ),
+            (
+                "## Heading {.big}",
+                "<h2 id=\"heading\" class=\"big\">Heading</h2>",
+            ),
+            (
+                concat!(
+                    "- List\n", //
+                    "\n",       //
+                    "{: .compact}"
+                ),
+                "<ul class=\"compact\">\n<li>List</li>\n</ul>",
+            ),
+        ];
+
+        let config = Config::default();
+        let parser = super::Parser::new(&config).unwrap();
+
+        for (input, expected) in CASES {
+            let result = parser.parse(input);
+            assert_eq!(
+                result.trim().to_owned(),
+                expected.to_owned(),
+                "\nparse({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_code_fence_annotations() {
+        const CASES: [(&str, &str); 3] = [
+            (
+                concat!(
+                    "```txt,title=\"a <b>.txt\"\n", //
+                    "a\n",                          //
+                    "```"
+                ),
+                "<figure class=\"code-block\"><figcaption class=\"code-title\">a \
+                 &lt;b&gt;.txt</figcaption><pre class=\"code language-txt\"><code class=\"code \
+                 language-txt\"><span class=\"text plain\">a\n</span></code></pre></figure>",
+            ),
+            (
+                concat!(
+                    "```txt,linenos,hl_lines=2\n", //
+                    "a\n",                         //
+                    "b\n",                         //
+                    "```"
+                ),
+                "<pre class=\"code language-txt\"><code class=\"code language-txt\"><span \
+                 class=\"line\"><span class=\"line-number\">1</span><span class=\"text \
+                 plain\">a\n</span></span><span class=\"line line-highlight\"><span \
+                 class=\"line-number\">2</span><span class=\"text \
+                 plain\">b\n</span></span></code></pre>",
+            ),
+            (
+                concat!(
+                    "```txt,diff\n", //
+                    "+a\n",          //
+                    "-b\n",          //
+                    "```"
+                ),
+                "<pre class=\"code language-txt\"><code class=\"code language-txt\"><span \
+                 class=\"line line-insert\"><span class=\"text plain\">+a\n</span></span><span \
+                 class=\"line line-delete\"><span class=\"text \
+                 plain\">-b\n</span></span></code></pre>",
+            ),
+        ];
+
+        let config = Config::default();
+        let parser = super::Parser::new(&config).unwrap();
+
+        for (input, expected) in CASES {
+            let result = parser.parse(input);
+            assert_eq!(
+                result.trim().to_owned(),
+                expected.to_owned(),
+                "\nparse({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn syntaxes_hash() {
+        use assert_fs::prelude::*;
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        dir.child("a.sublime-syntax").write_str("name: A").unwrap();
+
+        let first = super::syntaxes_hash(dir.path());
+
+        dir.child("a.sublime-syntax").write_str("name: B").unwrap();
+
+        let second = super::syntaxes_hash(dir.path());
+
+        assert_ne!(first, second);
+        assert_eq!(second, super::syntaxes_hash(dir.path()));
+    }
 }
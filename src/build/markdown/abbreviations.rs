@@ -0,0 +1,266 @@
+//! Abbreviations plugin for Markdown.
+//!
+//! Abbreviations are defined anywhere in the document, and each occurrence of
+//! the abbreviation in the text is wrapped in `<abbr>`:
+//!
+//! ```markdown
+//! The HTML specification is maintained by the W3C.
+//!
+//! *[HTML]: HyperText Markup Language
+//! *[W3C]: World Wide Web Consortium
+//! ```
+
+use markdown_it::{
+    parser::{
+        block::{BlockRule, BlockState},
+        core::CoreRule,
+        inline::Text,
+    },
+    MarkdownIt, Node, NodeValue, Renderer,
+};
+
+/// Add Markdown rules for abbreviations.
+pub(super) fn add(md: &mut MarkdownIt) {
+    md.block.add_rule::<AbbreviationDefinitionRule>();
+    md.add_rule::<AbbreviationRule>();
+}
+
+/// Definition of an abbreviation, which is not rendered.
+#[derive(Debug)]
+struct AbbreviationDefinition {
+    /// Abbreviation (e.g. `HTML`).
+    abbreviation: String,
+
+    /// Expansion of the abbreviation (e.g. `HyperText Markup Language`).
+    title: String,
+}
+
+impl NodeValue for AbbreviationDefinition {
+    fn render(&self, _: &Node, _: &mut dyn Renderer) {}
+}
+
+/// Render an abbreviation in `<abbr>...</abbr>`.
+#[derive(Debug)]
+struct Abbreviation {
+    /// Expansion of the abbreviation.
+    title: String,
+}
+
+impl NodeValue for Abbreviation {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        const ABBR: &str = "abbr";
+        fmt.open(ABBR, &[("title", self.title.to_owned())]);
+        fmt.contents(&node.children);
+        fmt.close(ABBR);
+    }
+}
+
+/// Abbreviation definition rule for Markdown.
+pub struct AbbreviationDefinitionRule;
+
+impl BlockRule for AbbreviationDefinitionRule {
+    fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+        // Indented lines are code blocks
+        if state.line_indent(state.line) >= 4 {
+            return None;
+        }
+
+        let (abbreviation, title) = parse_definition(state.get_line(state.line))?;
+
+        Some((
+            Node::new(AbbreviationDefinition {
+                abbreviation: abbreviation.to_owned(),
+                title: title.to_owned(),
+            }),
+            1,
+        ))
+    }
+}
+
+/// Abbreviation rule for Markdown.
+///
+/// Occurrences of defined abbreviations in text nodes are replaced by
+/// [`Abbreviation`] nodes.
+struct AbbreviationRule;
+
+impl CoreRule for AbbreviationRule {
+    fn run(root: &mut Node, _: &MarkdownIt) {
+        let mut abbreviations = Vec::new();
+
+        root.walk(|node, _| {
+            if let Some(definition) = node.cast::<AbbreviationDefinition>() {
+                abbreviations.push((
+                    definition.abbreviation.to_owned(),
+                    definition.title.to_owned(),
+                ));
+            }
+        });
+
+        if abbreviations.is_empty() {
+            return;
+        }
+
+        // Match the longest abbreviations first (e.g. `HTML5` before `HTML`)
+        abbreviations.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+
+        replace_abbreviations(root, &abbreviations);
+    }
+}
+
+/// Replace abbreviations in the text nodes of `node`, recursively.
+fn replace_abbreviations(node: &mut Node, abbreviations: &[(String, String)]) {
+    let children = std::mem::take(&mut node.children);
+
+    for mut child in children {
+        let Some(text) = child.cast::<Text>() else {
+            replace_abbreviations(&mut child, abbreviations);
+            node.children.push(child);
+            continue;
+        };
+
+        let segments = split_abbreviations(&text.content, abbreviations);
+
+        if segments.len() == 1 && segments[0].1.is_none() {
+            node.children.push(child);
+            continue;
+        }
+
+        for (content, title) in segments {
+            let text = Node::new(Text {
+                content: content.to_owned(),
+            });
+
+            match title {
+                Some(title) => {
+                    let mut abbreviation = Node::new(Abbreviation {
+                        title: title.to_owned(),
+                    });
+                    abbreviation.children.push(text);
+                    node.children.push(abbreviation);
+                },
+                None => node.children.push(text),
+            }
+        }
+    }
+}
+
+/// Split a text into segments, with the title of the abbreviation if the
+/// segment is an abbreviation.
+///
+/// Abbreviations only match whole words.
+fn split_abbreviations<'a>(
+    text: &'a str,
+    abbreviations: &'a [(String, String)],
+) -> Vec<(&'a str, Option<&'a str>)> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+
+    while index < text.len() {
+        let is_word_start = !text[..index].ends_with(is_word_char);
+
+        let matched = is_word_start
+            .then(|| {
+                abbreviations.iter().find(|(abbreviation, _)| {
+                    text[index..].starts_with(abbreviation.as_str())
+                        && !text[index + abbreviation.len()..].starts_with(is_word_char)
+                })
+            })
+            .flatten();
+
+        match matched {
+            Some((abbreviation, title)) => {
+                if start < index {
+                    segments.push((&text[start..index], None));
+                }
+                let end = index + abbreviation.len();
+                segments.push((&text[index..end], Some(title.as_str())));
+                index = end;
+                start = end;
+            },
+            None => {
+                index += text[index..].chars().next().map_or(1, char::len_utf8);
+            },
+        }
+    }
+
+    if start < text.len() || segments.is_empty() {
+        segments.push((&text[start..], None));
+    }
+
+    segments
+}
+
+/// Parse an abbreviation definition (e.g. `*[HTML]: HyperText Markup
+/// Language`), and return the abbreviation and its title.
+fn parse_definition(line: &str) -> Option<(&str, &str)> {
+    let (abbreviation, title) = line.strip_prefix("*[")?.split_once("]:")?;
+
+    let abbreviation = abbreviation.trim();
+    let title = title.trim();
+
+    if abbreviation.is_empty() || abbreviation.contains(']') {
+        return None;
+    }
+
+    Some((abbreviation, title))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_definition() {
+        const CASES: [(&str, Option<(&str, &str)>); 4] = [
+            (
+                "*[HTML]: HyperText Markup Language",
+                Some(("HTML", "HyperText Markup Language")),
+            ),
+            (
+                "*[W3C]:World Wide Web Consortium",
+                Some(("W3C", "World Wide Web Consortium")),
+            ),
+            ("*[]: Empty", None),
+            ("[HTML]: https://html.spec.whatwg.org", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::parse_definition(input);
+            assert_eq!(
+                result, expected,
+                "\nparse_definition({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn split_abbreviations() {
+        const CASES: [(&str, &[(&str, Option<&str>)]); 5] = [
+            ("", &[("", None)]),
+            ("Hello", &[("Hello", None)]),
+            ("HTML", &[("HTML", Some("HyperText Markup Language"))]),
+            ("Use HTML5 or HTML.", &[
+                ("Use ", None),
+                ("HTML5", Some("HTML version 5")),
+                (" or ", None),
+                ("HTML", Some("HyperText Markup Language")),
+                (".", None),
+            ]),
+            ("XHTML HTMLs", &[("XHTML HTMLs", None)]),
+        ];
+
+        let abbreviations = [
+            ("HTML5".to_owned(), "HTML version 5".to_owned()),
+            ("HTML".to_owned(), "HyperText Markup Language".to_owned()),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::split_abbreviations(input, &abbreviations);
+            assert_eq!(
+                result, expected,
+                "\nsplit_abbreviations({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Admonitions plugin for Markdown.
+//!
+//! Admonitions (a.k.a. callouts) use the GitHub syntax, i.e. a blockquote
+//! whose first line is a type marker and an optional title:
+//!
+//! ```markdown
+//! > [!WARNING] Optional title
+//! > Content of the admonition.
+//! ```
+
+use markdown_it::{
+    parser::{core::CoreRule, inline::Text},
+    plugins::cmark::{
+        block::{blockquote::Blockquote, paragraph::Paragraph},
+        inline::newline::{Hardbreak, Softbreak},
+    },
+    MarkdownIt, Node, NodeValue, Renderer,
+};
+
+use super::Context;
+
+/// Add a Markdown rule for admonitions.
+pub(super) fn add(md: &mut MarkdownIt) {
+    md.add_rule::<AdmonitionRule>();
+}
+
+/// Admonition rule for Markdown.
+///
+/// Blockquotes starting with a `[!TYPE]` marker are replaced by admonitions.
+struct AdmonitionRule;
+
+impl CoreRule for AdmonitionRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let context = &md.ext.get::<Context>().unwrap().admonitions;
+
+        root.walk_mut(|node, _| {
+            if !node.is::<Blockquote>() {
+                return;
+            }
+
+            let Some(paragraph) = node
+                .children
+                .first_mut()
+                .filter(|child| child.is::<Paragraph>())
+            else {
+                return;
+            };
+
+            // The marker must be alone on the first line of the paragraph
+            let is_line_end = paragraph.children.get(1).map_or(true, |child| {
+                child.is::<Softbreak>() || child.is::<Hardbreak>()
+            });
+
+            let Some((kind, title)) = paragraph
+                .children
+                .first()
+                .filter(|_| is_line_end)
+                .and_then(|child| child.cast::<Text>())
+                .and_then(|text| parse_marker(&text.content))
+            else {
+                return;
+            };
+
+            // Remove the marker and the line break
+            let length = paragraph.children.len().min(2);
+            paragraph.children.drain(..length);
+
+            if paragraph.children.is_empty() {
+                node.children.remove(0);
+            }
+
+            node.replace(Admonition {
+                kind,
+                title,
+                class: context.class.to_owned(),
+                title_class: context.title_class.to_owned(),
+            });
+        });
+    }
+}
+
+/// Render an admonition in `<aside>...</aside>`.
+#[derive(Debug)]
+struct Admonition {
+    /// Type of the admonition, in lowercase (e.g. `note`).
+    kind: String,
+
+    /// Title of the admonition.
+    title: String,
+
+    /// CSS class of the `<aside>` element.
+    class: String,
+
+    /// CSS class of the title.
+    title_class: String,
+}
+
+impl NodeValue for Admonition {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        const ASIDE: &str = "aside";
+        const P: &str = "p";
+
+        let mut attributes = node.attrs.to_owned();
+        attributes.push((
+            "class",
+            format!("{} {}-{}", self.class, self.class, self.kind),
+        ));
+
+        fmt.cr();
+        fmt.open(ASIDE, &attributes);
+        fmt.cr();
+        fmt.open(P, &[("class", self.title_class.to_owned())]);
+        fmt.text(&self.title);
+        fmt.close(P);
+        fmt.cr();
+        fmt.contents(&node.children);
+        fmt.cr();
+        fmt.close(ASIDE);
+        fmt.cr();
+    }
+}
+
+/// Parse an admonition marker (e.g. `[!NOTE] Title`).
+///
+/// Returns the type in lowercase and the title, which defaults to the type
+/// with an uppercase first letter.
+fn parse_marker(input: &str) -> Option<(String, String)> {
+    let (kind, title) = input.trim().strip_prefix("[!")?.split_once(']')?;
+
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    // A title must be separated from the marker
+    if !title.is_empty() && !title.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let kind = kind.to_ascii_lowercase();
+
+    let title = match title.trim() {
+        "" => kind[..1].to_ascii_uppercase() + &kind[1..],
+        title => title.to_owned(),
+    };
+
+    Some((kind, title))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_marker() {
+        const CASES: [(&str, Option<(&str, &str)>); 7] = [
+            ("[!NOTE]", Some(("note", "Note"))),
+            ("[!tip]", Some(("tip", "Tip"))),
+            ("[!WARNING] Be careful", Some(("warning", "Be careful"))),
+            ("[!NOTE]Title", None),
+            ("[!]", None),
+            ("[!A B]", None),
+            ("[NOTE]", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::parse_marker(input);
+            let expected = expected.map(|(kind, title)| (kind.to_owned(), title.to_owned()));
+            assert_eq!(
+                result, expected,
+                "\nparse_marker({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
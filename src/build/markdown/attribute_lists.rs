@@ -0,0 +1,265 @@
+//! Attribute lists plugin for Markdown.
+//!
+//! An attribute list (e.g. `{#id .class key="value"}`) at the end of a heading
+//! or a paragraph adds HTML attributes to this element. An attribute list
+//! alone in a paragraph (e.g. `{: .class}`) applies to the previous block
+//! (e.g. a list or a table):
+//!
+//! ```markdown
+//! ## Heading {#custom-id}
+//!
+//! - List
+//!
+//! {: .compact}
+//! ```
+
+use std::{collections::BTreeSet, sync::Mutex};
+
+use markdown_it::{
+    parser::{core::CoreRule, inline::Text},
+    plugins::cmark::{
+        block::{heading::ATXHeading, lheading::SetextHeader, paragraph::Paragraph},
+        inline::newline::{Hardbreak, Softbreak},
+    },
+    MarkdownIt, Node,
+};
+
+use super::Context;
+
+/// Names of attributes found in attribute lists.
+///
+/// Since [`Node::attrs`] requires `'static` names, each distinct name is
+/// leaked once and reused afterwards.
+static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// Add a Markdown rule for attribute lists.
+pub(super) fn add(md: &mut MarkdownIt) {
+    md.add_rule::<AttributeListRule>();
+}
+
+/// Attribute list rule for Markdown.
+struct AttributeListRule;
+
+impl CoreRule for AttributeListRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let slugify = md.ext.get::<Context>().unwrap().slug_strategy.slugify_fn();
+
+        apply_attribute_lists(root, slugify);
+    }
+}
+
+/// Move attribute lists of the children of `node` to attributes,
+/// recursively.
+///
+/// Since heading anchors are added before, the `id` of headings is computed
+/// again with `slugify`, without the attribute list.
+fn apply_attribute_lists(node: &mut Node, slugify: fn(&str) -> String) {
+    let mut index = 0;
+
+    while index < node.children.len() {
+        let child = &mut node.children[index];
+
+        let is_heading = child.is::<ATXHeading>() || child.is::<SetextHeader>();
+        let is_block = is_heading || child.is::<Paragraph>();
+
+        if let Some(mut attributes) = is_block.then(|| strip_attribute_list(child)).flatten() {
+            if is_heading && child.attrs.iter().any(|(name, _)| *name == "id") {
+                attributes.insert(0, ("id".to_owned(), slugify(&child.collect_text())));
+            }
+
+            // An attribute list alone applies to the previous block
+            if child.children.is_empty() {
+                node.children.remove(index);
+
+                if let Some(previous) = index.checked_sub(1) {
+                    set_attributes(&mut node.children[previous], attributes);
+                }
+
+                continue;
+            }
+
+            set_attributes(child, attributes);
+        }
+
+        apply_attribute_lists(&mut node.children[index], slugify);
+        index += 1;
+    }
+}
+
+/// Remove the attribute list at the end of the inline content of `node`, and
+/// return its attributes.
+fn strip_attribute_list(node: &mut Node) -> Option<Vec<(String, String)>> {
+    let text = node.children.last_mut()?.cast_mut::<Text>()?;
+
+    let content = text.content.trim_end();
+    let start = content.rfind('{')?;
+    let attributes = parse_attribute_list(content[start..].strip_suffix('}')?)?;
+
+    text.content = content[..start].trim_end().to_owned();
+
+    if text.content.is_empty() {
+        node.children.pop();
+
+        // Remove the line break before an attribute list on its own line
+        if node
+            .children
+            .last()
+            .is_some_and(|child| child.is::<Softbreak>() || child.is::<Hardbreak>())
+        {
+            node.children.pop();
+        }
+    }
+
+    Some(attributes)
+}
+
+/// Add attributes to a node.
+///
+/// Classes are appended to the existing ones, other attributes replace
+/// existing ones (e.g. the `id` of headings).
+fn set_attributes(node: &mut Node, attributes: Vec<(String, String)>) {
+    for (name, value) in attributes {
+        let name = static_name(&name);
+
+        match node.attrs.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, class)) if name == "class" => {
+                class.push(' ');
+                class.push_str(&value);
+            },
+            Some((_, existing)) => *existing = value,
+            None => node.attrs.push((name, value)),
+        }
+    }
+}
+
+/// Return a `'static` string equal to an attribute name.
+fn static_name(name: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap();
+
+    if let Some(name) = names.get(name) {
+        return name;
+    }
+
+    let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+    names.insert(name);
+    name
+}
+
+/// Parse an attribute list (e.g. `{: #id .class key="value"}`), and return
+/// the attributes.
+///
+/// Returns `None` if the input is not a valid attribute list.
+fn parse_attribute_list(input: &str) -> Option<Vec<(String, String)>> {
+    let input = input.strip_prefix('{')?;
+    let mut input = input.strip_prefix(':').unwrap_or(input).trim_start();
+
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':'))
+    };
+
+    let mut attributes = Vec::new();
+
+    while !input.is_empty() {
+        let (attribute, rest) = match input.find('=') {
+            Some(index)
+                if !input.starts_with(['#', '.'])
+                    && !input[..index].contains(char::is_whitespace) =>
+            {
+                let name = &input[..index];
+
+                if !is_name(name) {
+                    return None;
+                }
+
+                let (value, rest) = parse_value(&input[index + 1..])?;
+                ((name.to_owned(), value), rest)
+            },
+            _ => {
+                let end = input.find(char::is_whitespace).unwrap_or(input.len());
+                let (token, rest) = input.split_at(end);
+
+                let attribute = if let Some(id) = token.strip_prefix('#') {
+                    ("id".to_owned(), id.to_owned())
+                } else if let Some(class) = token.strip_prefix('.') {
+                    ("class".to_owned(), class.to_owned())
+                } else {
+                    return None;
+                };
+
+                if attribute.1.is_empty() {
+                    return None;
+                }
+
+                (attribute, rest)
+            },
+        };
+
+        attributes.push(attribute);
+        input = rest.trim_start();
+    }
+
+    (!attributes.is_empty()).then_some(attributes)
+}
+
+/// Parse an attribute value, quoted or not, and return it with the rest of
+/// the input.
+///
+/// Quotes may have been replaced by typographic quotes (e.g. `“`), which are
+/// also accepted.
+fn parse_value(input: &str) -> Option<(String, &str)> {
+    const QUOTES: [(char, char); 4] = [('"', '"'), ('\'', '\''), ('“', '”'), ('‘', '’')];
+
+    let quotes = QUOTES
+        .into_iter()
+        .find(|(open, _)| input.starts_with(*open));
+
+    match quotes {
+        Some((open, close)) => {
+            let input = &input[open.len_utf8()..];
+            let end = input.find(close)?;
+            Some((input[..end].to_owned(), &input[end + close.len_utf8()..]))
+        },
+        None => {
+            let end = input.find(char::is_whitespace).unwrap_or(input.len());
+            Some((input[..end].to_owned(), &input[end..]))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_attribute_list() {
+        const CASES: [(&str, Option<&[(&str, &str)]>); 7] = [
+            ("{#id}", Some(&[("id", "id")])),
+            ("{: .a .b}", Some(&[("class", "a"), ("class", "b")])),
+            (
+                "{#id lang=fr title=\"A title\"}",
+                Some(&[("id", "id"), ("lang", "fr"), ("title", "A title")]),
+            ),
+            ("{data-x=“y”}", Some(&[("data-x", "y")])),
+            ("{}", None),
+            ("{not an attribute}", None),
+            ("{a<b=c}", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = input
+                .strip_suffix('}')
+                .and_then(super::parse_attribute_list);
+            let expected = expected.map(|attributes| {
+                attributes
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect()
+            });
+            assert_eq!(
+                result, expected,
+                "\nparse_attribute_list({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Definition lists plugin for Markdown.
+//!
+//! Each term is followed by one or more definitions starting with `:`:
+//!
+//! ```markdown
+//! Term
+//! : Definition
+//!   continued on an indented line.
+//! ```
+
+use markdown_it::{
+    parser::{
+        block::{BlockRule, BlockState},
+        inline::InlineRoot,
+    },
+    MarkdownIt, Node, NodeValue, Renderer,
+};
+
+/// Add a Markdown rule for definition lists.
+pub(super) fn add(md: &mut MarkdownIt) {
+    md.block.add_rule::<DefinitionListRule>();
+}
+
+/// Render a definition list in `<dl>...</dl>`.
+#[derive(Debug)]
+struct DefinitionList;
+
+impl NodeValue for DefinitionList {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        const DL: &str = "dl";
+        fmt.cr();
+        fmt.open(DL, &node.attrs);
+        fmt.cr();
+        fmt.contents(&node.children);
+        fmt.cr();
+        fmt.close(DL);
+        fmt.cr();
+    }
+}
+
+/// Render a term in `<dt>...</dt>`.
+#[derive(Debug)]
+struct DefinitionTerm;
+
+impl NodeValue for DefinitionTerm {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        const DT: &str = "dt";
+        fmt.open(DT, &node.attrs);
+        fmt.contents(&node.children);
+        fmt.close(DT);
+        fmt.cr();
+    }
+}
+
+/// Render a definition in `<dd>...</dd>`.
+#[derive(Debug)]
+struct DefinitionDescription;
+
+impl NodeValue for DefinitionDescription {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        const DD: &str = "dd";
+        fmt.open(DD, &node.attrs);
+        fmt.contents(&node.children);
+        fmt.close(DD);
+        fmt.cr();
+    }
+}
+
+/// Definition list rule for Markdown.
+pub struct DefinitionListRule;
+
+impl BlockRule for DefinitionListRule {
+    fn run(state: &mut BlockState) -> Option<(Node, usize)> {
+        // Indented lines are code blocks
+        if state.line_indent(state.line) >= 4 {
+            return None;
+        }
+
+        let mut node = Node::new(DefinitionList);
+        let mut line = state.line;
+
+        // Terms may be separated by a blank line
+        while let Some(next_line) = parse_term(state, line, &mut node) {
+            line = next_line;
+
+            let is_blank = line < state.line_max && state.is_empty(line);
+
+            if !is_blank || !is_term(state, line + 1) {
+                break;
+            }
+
+            line += 1;
+        }
+
+        if node.children.is_empty() {
+            return None;
+        }
+
+        Some((node, line - state.line))
+    }
+}
+
+/// Check if a line is a term, i.e. a non-empty line followed by a
+/// definition.
+fn is_term(state: &BlockState, line: usize) -> bool {
+    line + 1 < state.line_max
+        && !state.is_empty(line)
+        && state.line_indent(line) < 4
+        && parse_definition(state.get_line(line)).is_none()
+        && parse_definition(state.get_line(line + 1)).is_some()
+}
+
+/// Parse a term and its definitions, starting at `line`, and add them to
+/// `node`.
+///
+/// Returns the line following the last definition, or `None` if `line` is not
+/// a term.
+fn parse_term(state: &BlockState, line: usize, node: &mut Node) -> Option<usize> {
+    if !is_term(state, line) {
+        return None;
+    }
+
+    node.children
+        .push(inline_node(DefinitionTerm, state, &[line]));
+
+    let mut line = line + 1;
+
+    while line < state.line_max && parse_definition(state.get_line(line)).is_some() {
+        // Indented lines continue the definition
+        let mut lines = vec![line];
+        line += 1;
+
+        while line < state.line_max && !state.is_empty(line) && state.line_indent(line) > 0 {
+            lines.push(line);
+            line += 1;
+        }
+
+        node.children
+            .push(inline_node(DefinitionDescription, state, &lines));
+    }
+
+    Some(line)
+}
+
+/// Create a node whose inline content is the text of `lines`, without the
+/// definition marker.
+fn inline_node<T>(value: T, state: &BlockState, lines: &[usize]) -> Node
+where
+    T: NodeValue,
+{
+    let mut content = String::new();
+    let mut mapping = Vec::new();
+
+    for &line in lines {
+        let text = state.get_line(line);
+        let definition = parse_definition(text).unwrap_or(text);
+        let offset = state.line_offsets[line].first_nonspace + text.len() - definition.len();
+
+        if !content.is_empty() {
+            content.push('\n');
+        }
+
+        mapping.push((content.len(), offset));
+        content.push_str(definition.trim_end());
+    }
+
+    let mut node = Node::new(value);
+    node.children
+        .push(Node::new(InlineRoot::new(content, mapping)));
+    node
+}
+
+/// Parse a definition line (e.g. `: Definition`), and return the definition.
+fn parse_definition(line: &str) -> Option<&str> {
+    let definition = line.strip_prefix(':')?;
+
+    if !definition.starts_with(char::is_whitespace) || definition.trim().is_empty() {
+        return None;
+    }
+
+    Some(definition.trim_start())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_definition() {
+        const CASES: [(&str, Option<&str>); 5] = [
+            (": Definition", Some("Definition")),
+            (":   Definition", Some("Definition")),
+            (":Definition", None),
+            (": ", None),
+            ("Term", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::parse_definition(input);
+            assert_eq!(
+                result, expected,
+                "\nparse_definition({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -10,8 +10,8 @@ use markdown_it::{
     MarkdownIt, Node, NodeValue, Renderer,
 };
 use syntect::{
-    html::{ClassStyle, ClassedHTMLGenerator},
-    parsing::SyntaxSet,
+    html::{line_tokens_to_classed_spans, ClassStyle, ClassedHTMLGenerator},
+    parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet},
     util::LinesWithEndings,
 };
 
@@ -34,7 +34,7 @@ impl CoreRule for SyntaxHighlightRule {
         // at runtime. Therefore, we use `static_lifetime()` as a workaround.
         let prefix = unsafe { crate::util::r#unsafe::static_lifetime(&context.css_prefix) };
 
-        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let syntax_set = &context.syntax_set;
 
         root.walk_mut(|node, _| {
             let (content, language) = if let Some(code_block) = node.cast::<CodeBlock>() {
@@ -50,6 +50,9 @@ impl CoreRule for SyntaxHighlightRule {
             };
 
             if let Some(content) = content {
+                let fence_info = FenceInfo::parse(language.map(|s| s.as_str()).unwrap_or_default());
+                let language = fence_info.language.as_ref();
+
                 let result = context
                     .formatter
                     .as_ref()
@@ -67,8 +70,14 @@ impl CoreRule for SyntaxHighlightRule {
                     return;
                 }
 
+                let figure = CodeBlockFigure {
+                    title: fence_info.title.to_owned(),
+                    copy_button: context.copy_button,
+                    prefix: prefix.to_owned(),
+                };
+
                 if let Some(content) = result.unwrap() {
-                    node.replace(CustomSyntaxHighlight { content });
+                    node.replace(CustomSyntaxHighlight { content, figure });
                     return;
                 }
 
@@ -76,25 +85,46 @@ impl CoreRule for SyntaxHighlightRule {
                     .and_then(|language| syntax_set.find_syntax_by_token(language))
                     .unwrap_or_else(|| syntax_set.find_syntax_plain_text());
 
-                let mut html_generator = ClassedHTMLGenerator::new_with_class_style(
-                    syntax,
-                    &syntax_set,
-                    ClassStyle::SpacedPrefixed { prefix },
-                );
-
-                for line in LinesWithEndings::from(content) {
-                    html_generator
-                        .parse_html_for_line_which_includes_newline(line)
-                        .unwrap_or_else(|error| {
-                            tracing::error!("markdown::syntax_highlight: {}", error)
-                        });
-                }
+                let line_numbers = context.line_numbers || fence_info.line_numbers;
 
-                let content = html_generator.finalize();
+                let content =
+                    if line_numbers || fence_info.diff || !fence_info.highlight_lines.is_empty() {
+                        highlight_lines(content, syntax, syntax_set, prefix, |number, line| {
+                            LineOptions {
+                                number: line_numbers.then_some(number),
+                                highlight: fence_info
+                                    .highlight_lines
+                                    .iter()
+                                    .any(|range| range.contains(&number)),
+                                diff: fence_info
+                                    .diff
+                                    .then(|| line.chars().next())
+                                    .flatten()
+                                    .filter(|c| matches!(c, '+' | '-')),
+                            }
+                        })
+                    } else {
+                        let mut html_generator = ClassedHTMLGenerator::new_with_class_style(
+                            syntax,
+                            syntax_set,
+                            ClassStyle::SpacedPrefixed { prefix },
+                        );
+
+                        for line in LinesWithEndings::from(content) {
+                            html_generator
+                                .parse_html_for_line_which_includes_newline(line)
+                                .unwrap_or_else(|error| {
+                                    tracing::error!("markdown::syntax_highlight: {}", error)
+                                });
+                        }
+
+                        html_generator.finalize()
+                    };
 
                 node.replace(BuiltinSyntaxHighlight {
                     content,
                     language: language.map(|s| s.to_owned()),
+                    figure,
                     prefix: prefix.to_owned(),
                     code_attributes: context.code_attributes.to_owned(),
                     pre_attributes: context.pre_attributes.to_owned(),
@@ -104,11 +134,313 @@ impl CoreRule for SyntaxHighlightRule {
     }
 }
 
+/// Annotations of a code fence.
+///
+/// The info string of a code fence starts with the language, followed by
+/// annotations separated by commas or whitespace, e.g.
+/// ```` ```rust,linenos,hl_lines=3-5 8 ```` or ```` ```rust linenos diff ````.
+#[derive(Debug, Default, PartialEq)]
+struct FenceInfo {
+    /// Language of the code.
+    language: Option<String>,
+
+    /// Display line numbers (`linenos`).
+    line_numbers: bool,
+
+    /// Ranges of lines to highlight (`hl_lines=3-5 8`).
+    highlight_lines: Vec<std::ops::RangeInclusive<usize>>,
+
+    /// Style lines starting with `+` or `-` (`diff`).
+    diff: bool,
+
+    /// Title displayed above the code, e.g. a file name
+    /// (`title="src/main.rs"`). Quotes and backslashes are escaped with a
+    /// backslash.
+    title: Option<String>,
+}
+
+impl FenceInfo {
+    /// Parse the info string of a code fence.
+    fn parse(info: &str) -> Self {
+        let info = info.trim();
+
+        let (language, annotations) = info
+            .find(|c: char| c == ',' || c.is_whitespace())
+            .map(|index| (&info[..index], &info[index + 1..]))
+            .unwrap_or((info, ""));
+
+        let mut fence_info = Self {
+            language: Some(language.to_owned()).filter(|language| !language.is_empty()),
+            ..Default::default()
+        };
+
+        for annotation in split_annotations(annotations) {
+            let (key, value) = annotation.split_once('=').unwrap_or((annotation, ""));
+            let value = value.trim();
+            match key.trim() {
+                "linenos" => fence_info.line_numbers = true,
+                "diff" => fence_info.diff = true,
+                "title" => {
+                    fence_info.title = Some(unescape_title(
+                        value
+                            .strip_prefix('"')
+                            .and_then(|value| value.strip_suffix('"'))
+                            .unwrap_or(value),
+                    ))
+                },
+                "hl_lines" => {
+                    fence_info.highlight_lines = value
+                        .trim_matches('"')
+                        .split_whitespace()
+                        .filter_map(|range| {
+                            let (start, end) = range.split_once('-').unwrap_or((range, range));
+                            Some(start.parse().ok()?..=end.parse().ok()?)
+                        })
+                        .collect()
+                },
+                "" => {},
+                key => tracing::warn!("markdown::syntax_highlight: unknown annotation {key:?}"),
+            }
+        }
+
+        fence_info
+    }
+}
+
+/// Split annotations separated by commas or whitespace, ignoring separators
+/// between double quotes.
+///
+/// Line numbers following `hl_lines` (e.g. `hl_lines=3-5 8`) are kept in the
+/// same annotation.
+fn split_annotations(annotations: &str) -> Vec<&str> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for (index, c) in annotations.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if !quoted && (c == ',' || c.is_whitespace()) => {
+                spans.push(start..index);
+                start = index + c.len_utf8();
+            },
+            _ => {},
+        }
+    }
+
+    spans.push(start..annotations.len());
+
+    let mut result: Vec<std::ops::Range<usize>> = Vec::new();
+
+    for span in spans.into_iter().filter(|span| !span.is_empty()) {
+        let is_lines = annotations[span.clone()]
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '-');
+
+        match result.last_mut() {
+            Some(last) if is_lines && annotations[last.clone()].starts_with("hl_lines=") => {
+                last.end = span.end
+            },
+            _ => result.push(span),
+        }
+    }
+
+    result.into_iter().map(|span| &annotations[span]).collect()
+}
+
+/// Unescape the quotes and backslashes of a title.
+fn unescape_title(title: &str) -> String {
+    let mut result = String::with_capacity(title.len());
+    let mut chars = title.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Options for rendering a line of code.
+struct LineOptions {
+    /// Line number to display.
+    number: Option<usize>,
+
+    /// Highlight the line.
+    highlight: bool,
+
+    /// Diff marker of the line (`+` or `-`).
+    diff: Option<char>,
+}
+
+/// Highlight code line by line.
+///
+/// Each line is wrapped in a `<span class="{prefix}line">` element, so that it
+/// can be numbered and styled individually. Since scopes may span multiple
+/// lines, spans that are still open at the end of a line are closed, then
+/// reopened at the beginning of the next line.
+fn highlight_lines<F>(
+    content: &str,
+    syntax: &SyntaxReference,
+    syntax_set: &SyntaxSet,
+    prefix: &'static str,
+    line_options: F,
+) -> String
+where
+    F: Fn(usize, &str) -> LineOptions,
+{
+    let mut parse_state = ParseState::new(syntax);
+    let mut scope_stack = ScopeStack::new();
+    let mut html = String::new();
+
+    for (index, line) in LinesWithEndings::from(content).enumerate() {
+        let options = line_options(index + 1, line);
+
+        let mut classes = vec![format!("{prefix}line")];
+        if options.highlight {
+            classes.push(format!("{prefix}line-highlight"));
+        }
+        match options.diff {
+            Some('+') => classes.push(format!("{prefix}line-insert")),
+            Some('-') => classes.push(format!("{prefix}line-delete")),
+            _ => {},
+        }
+
+        html.push_str(&format!("<span class=\"{}\">", classes.join(" ")));
+
+        if let Some(number) = options.number {
+            html.push_str(&format!(
+                "<span class=\"{prefix}line-number\">{number}</span>"
+            ));
+        }
+
+        // Reopen spans from previous lines
+        for scope in scope_stack.as_slice() {
+            html.push_str(&format!(
+                "<span class=\"{}\">",
+                scope_to_classes(*scope, prefix)
+            ));
+        }
+
+        let line_html = parse_state
+            .parse_line(line, syntax_set)
+            .map_err(|error| error.to_string())
+            .and_then(|ops| {
+                line_tokens_to_classed_spans(
+                    line,
+                    &ops,
+                    ClassStyle::SpacedPrefixed { prefix },
+                    &mut scope_stack,
+                )
+                .map_err(|error| error.to_string())
+            });
+
+        match line_html {
+            Ok((line_html, _)) => html.push_str(&line_html),
+            Err(error) => {
+                tracing::error!("markdown::syntax_highlight: {}", error);
+                html.push_str(&html_escape(line));
+            },
+        }
+
+        // Close spans that are still open
+        html.push_str(&"</span>".repeat(scope_stack.len()));
+
+        html.push_str("</span>");
+    }
+
+    html
+}
+
+/// Convert a scope to CSS classes, e.g. `keyword.rust` to `{prefix}keyword
+/// {prefix}rust`.
+fn scope_to_classes(scope: Scope, prefix: &str) -> String {
+    scope
+        .build_string()
+        .split('.')
+        .map(|atom| format!("{prefix}{atom}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escape special HTML characters.
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Figure wrapping a code block, with a caption and a copy button.
+#[derive(Debug)]
+struct CodeBlockFigure {
+    title: Option<String>,
+    copy_button: bool,
+    prefix: String,
+}
+
+impl CodeBlockFigure {
+    const FIGURE: &'static str = "figure";
+
+    /// Render the opening tag of the figure, the caption and the button.
+    ///
+    /// The code block is only wrapped if it has a caption or a button.
+    fn open(&self, fmt: &mut dyn Renderer) {
+        const FIGCAPTION: &str = "figcaption";
+        const BUTTON: &str = "button";
+
+        if !self.has_content() {
+            return;
+        }
+
+        fmt.open(Self::FIGURE, &[(
+            "class",
+            format!("{}code-block", self.prefix),
+        )]);
+
+        if let Some(title) = self.title.as_ref() {
+            fmt.open(FIGCAPTION, &[(
+                "class",
+                format!("{}code-title", self.prefix),
+            )]);
+            fmt.text(title);
+            fmt.close(FIGCAPTION);
+        }
+
+        if self.copy_button {
+            fmt.open(BUTTON, &[
+                ("type", "button".to_owned()),
+                ("class", format!("{}code-copy", self.prefix)),
+                ("aria-label", "Copy code".to_owned()),
+            ]);
+            fmt.text("Copy");
+            fmt.close(BUTTON);
+        }
+    }
+
+    /// Render the closing tag of the figure.
+    fn close(&self, fmt: &mut dyn Renderer) {
+        if self.has_content() {
+            fmt.close(Self::FIGURE);
+        }
+    }
+
+    /// Determine whether the code block has a caption or a button.
+    fn has_content(&self) -> bool {
+        self.title.is_some() || self.copy_button
+    }
+}
+
 /// AST node for builtin syntax highlight.
 #[derive(Debug)]
 struct BuiltinSyntaxHighlight {
     content: String,
     language: Option<String>,
+    figure: CodeBlockFigure,
     prefix: String,
     code_attributes: HashMap<String, String>,
     pre_attributes: HashMap<String, String>,
@@ -136,21 +468,30 @@ impl NodeValue for BuiltinSyntaxHighlight {
             .entry("class".to_owned())
             .or_insert_with(|| class.to_owned());
 
-        let code_attributes: Vec<_> = code_attributes
+        // Sort attributes, since the iteration order of hash maps is random
+        let mut code_attributes: Vec<_> = code_attributes
             .iter()
             .map(|(k, v)| (k.as_str(), v.to_owned()))
             .collect();
 
-        let pre_attributes: Vec<_> = pre_attributes
+        code_attributes.sort();
+
+        let mut pre_attributes: Vec<_> = pre_attributes
             .iter()
             .map(|(k, v)| (k.as_str(), v.to_owned()))
             .collect();
 
+        pre_attributes.sort();
+
+        self.figure.open(fmt);
+
         fmt.open(PRE, &pre_attributes);
         fmt.open(CODE, &code_attributes);
         fmt.text_raw(&self.content);
         fmt.close(CODE);
         fmt.close(PRE);
+
+        self.figure.close(fmt);
     }
 }
 
@@ -158,10 +499,86 @@ impl NodeValue for BuiltinSyntaxHighlight {
 #[derive(Debug)]
 struct CustomSyntaxHighlight {
     content: String,
+    figure: CodeBlockFigure,
 }
 
 impl NodeValue for CustomSyntaxHighlight {
     fn render(&self, _: &Node, fmt: &mut dyn Renderer) {
+        self.figure.open(fmt);
         fmt.text_raw(&self.content);
+        self.figure.close(fmt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FenceInfo;
+
+    #[test]
+    fn parse_fence_info() {
+        let cases = [
+            ("", FenceInfo::default()),
+            ("rust", FenceInfo {
+                language: Some("rust".to_owned()),
+                ..Default::default()
+            }),
+            ("rust,linenos,hl_lines=3-5 8", FenceInfo {
+                language: Some("rust".to_owned()),
+                line_numbers: true,
+                highlight_lines: vec![3..=5, 8..=8],
+                ..Default::default()
+            }),
+            ("diff linenos", FenceInfo {
+                language: Some("diff".to_owned()),
+                line_numbers: true,
+                ..Default::default()
+            }),
+            ("js, diff", FenceInfo {
+                language: Some("js".to_owned()),
+                diff: true,
+                ..Default::default()
+            }),
+            ("rust linenos diff", FenceInfo {
+                language: Some("rust".to_owned()),
+                line_numbers: true,
+                diff: true,
+                ..Default::default()
+            }),
+            (
+                r#"rust hl_lines=3-5 8 title="a b.rs"  linenos"#,
+                FenceInfo {
+                    language: Some("rust".to_owned()),
+                    line_numbers: true,
+                    highlight_lines: vec![3..=5, 8..=8],
+                    title: Some("a b.rs".to_owned()),
+                    ..Default::default()
+                },
+            ),
+            (r#"rust hl_lines="1 2""#, FenceInfo {
+                language: Some("rust".to_owned()),
+                highlight_lines: vec![1..=1, 2..=2],
+                ..Default::default()
+            }),
+            (r#"rust,title="src/a,b.rs",linenos"#, FenceInfo {
+                language: Some("rust".to_owned()),
+                line_numbers: true,
+                title: Some("src/a,b.rs".to_owned()),
+                ..Default::default()
+            }),
+            (r#"rust,title="a \"b\", c\\d",linenos"#, FenceInfo {
+                language: Some("rust".to_owned()),
+                line_numbers: true,
+                title: Some(r#"a "b", c\d"#.to_owned()),
+                ..Default::default()
+            }),
+        ];
+
+        for (input, expected) in cases {
+            let result = FenceInfo::parse(input);
+            assert_eq!(
+                result, expected,
+                "\nFenceInfo::parse({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
     }
 }
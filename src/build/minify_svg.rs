@@ -0,0 +1,377 @@
+//! Minify SVG code.
+//!
+//! This module uses [`quick_xml`] under the hood.
+
+use std::borrow::Cow;
+
+use quick_xml::{
+    events::{attributes::Attribute, BytesStart, Event},
+    Reader, Writer,
+};
+
+use super::{Config, Entry, Error};
+
+/// Prefixes of elements and attributes added by editors (e.g. Inkscape).
+const EDITOR_PREFIXES: [&str; 4] = ["inkscape:", "sodipodi:", "xmlns:inkscape", "xmlns:sodipodi"];
+
+/// Attributes containing lists of numbers, which whitespace can be collapsed.
+const NUMERIC_ATTRIBUTES: [&str; 4] = ["d", "points", "transform", "viewBox"];
+
+/// Attributes containing coordinates, which numbers can be rounded.
+const COORDINATES_ATTRIBUTES: [&str; 2] = ["d", "points"];
+
+/// Minify SVG content of a [`Entry`].
+///
+/// This function minifies SVG code in the `content` property. Numbers of path
+/// data are rounded if `svg.precision` is specified in the configuration.
+pub(super) fn minify_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
+    if let Some(content) = entry.content.as_ref() {
+        let content = minify(content, config.svg.precision).map_err(|error| Error::MinifySvg {
+            input_path: entry.input_path_buf(),
+            source: error,
+        })?;
+
+        return Ok(Entry {
+            content: Some(content),
+            ..entry
+        });
+    }
+
+    Ok(entry)
+}
+
+/// Minify a string containing SVG code.
+///
+/// This function removes the XML declaration, comments, processing
+/// instructions, `<metadata>` elements and editor data (e.g. `inkscape:*`
+/// attributes), as well as whitespace between elements (except in `<text>`
+/// elements). If `precision` is specified, numbers of path data are rounded to
+/// this number of decimal places.
+fn minify<S>(input: S, precision: Option<usize>) -> anyhow::Result<String>
+where
+    S: AsRef<str>,
+{
+    let input = input.as_ref();
+
+    let mut reader = Reader::from_str(input);
+
+    let mut writer = Writer::new(Vec::new());
+
+    // Depth in `<text>` elements, where whitespace is significant
+    let mut text_depth: usize = 0;
+
+    loop {
+        let event = reader.read_event()?;
+
+        match event {
+            Event::Eof => break,
+            Event::Decl(_) | Event::Comment(_) | Event::PI(_) | Event::DocType(_) => {},
+            Event::Start(bytes) => {
+                if is_removed_element(&bytes) {
+                    reader.read_to_end(bytes.name())?;
+                    continue;
+                }
+                if bytes.name().as_ref() == b"text" {
+                    text_depth += 1;
+                }
+                let bytes = minify_bytes_start(bytes, precision)?;
+                writer.write_event(Event::Start(bytes))?
+            },
+            Event::Empty(bytes) => {
+                if is_removed_element(&bytes) {
+                    continue;
+                }
+                let bytes = minify_bytes_start(bytes, precision)?;
+                writer.write_event(Event::Empty(bytes))?
+            },
+            Event::End(bytes) => {
+                if bytes.name().as_ref() == b"text" {
+                    text_depth = text_depth.saturating_sub(1);
+                }
+                writer.write_event(Event::End(bytes))?
+            },
+            Event::Text(bytes) => {
+                if text_depth == 0 && bytes.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                writer.write_event(Event::Text(bytes))?
+            },
+            _ => writer.write_event(event)?,
+        }
+    }
+
+    let output = String::from_utf8(writer.into_inner())?;
+
+    Ok(output)
+}
+
+/// Determine whether an element should be removed with its content.
+fn is_removed_element(bytes: &BytesStart) -> bool {
+    let name = bytes.name();
+    let name = String::from_utf8_lossy(name.as_ref());
+    name == "metadata"
+        || EDITOR_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+/// Remove editor attributes and unnecessary spaces in attributes.
+fn minify_bytes_start(bytes: BytesStart, precision: Option<usize>) -> anyhow::Result<BytesStart> {
+    let name = std::str::from_utf8(bytes.name().into_inner())?.to_owned();
+
+    let attributes: Vec<Attribute> = bytes
+        .attributes()
+        .map(|attribute| -> anyhow::Result<Option<Attribute>> {
+            let attribute = attribute?;
+            let key = std::str::from_utf8(attribute.key.into_inner())?;
+
+            if EDITOR_PREFIXES.iter().any(|prefix| key.starts_with(prefix)) {
+                return Ok(None);
+            }
+
+            if !NUMERIC_ATTRIBUTES.contains(&key) {
+                return Ok(Some(attribute));
+            }
+
+            let value = collapse_whitespace(std::str::from_utf8(&attribute.value)?);
+
+            let value = match precision {
+                Some(precision) if COORDINATES_ATTRIBUTES.contains(&key) => {
+                    round_numbers(&value, precision)
+                },
+                _ => value,
+            };
+
+            Ok(Some(Attribute {
+                key: attribute.key,
+                value: Cow::Owned(value.into_bytes()),
+            }))
+        })
+        .filter_map(Result::transpose)
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(BytesStart::new(name).with_attributes(attributes))
+}
+
+/// Replace sequences of whitespace by a single space.
+fn collapse_whitespace(value: &str) -> String {
+    value.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Round the numbers of a list (e.g. path data) to a number of decimal places.
+///
+/// Flags of arc commands are single digits that may be written without
+/// separator (e.g. `a1 1 0 01.5 2`), so they are read one character at a time
+/// and left unchanged.
+fn round_numbers(value: &str, precision: usize) -> String {
+    let mut result = String::with_capacity(value.len());
+
+    // Previous number, if immediately followed by the current character
+    let mut previous: Option<String> = None;
+
+    // Current path command, and index of the next argument
+    let mut command = None;
+    let mut index = 0;
+
+    let mut rest = value;
+
+    while let Some(c) = rest.chars().next() {
+        // The large arc and sweep flags are the 4th and 5th arguments
+        if matches!(command, Some('a' | 'A'))
+            && matches!(index % 7, 3 | 4)
+            && matches!(c, '0' | '1')
+        {
+            if previous.is_some() {
+                result.push(' ');
+            }
+            result.push(c);
+            rest = &rest[1..];
+            previous = None;
+            index += 1;
+            continue;
+        }
+
+        let len = number_len(rest);
+
+        if len == 0 {
+            if c.is_ascii_alphabetic() {
+                command = Some(c);
+                index = 0;
+            }
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+            previous = None;
+            continue;
+        }
+
+        let (number, after) = rest.split_at(len);
+
+        let number = if number.contains(['.', 'e', 'E']) {
+            round_number(number, precision)
+        } else {
+            number.to_owned()
+        };
+
+        // Adjacent numbers must remain distinct (e.g. `1.5.5` or `1-2`)
+        if let Some(previous) = previous.as_ref() {
+            let distinct =
+                number.starts_with('-') || (number.starts_with('.') && previous.contains('.'));
+            if !distinct {
+                result.push(' ');
+            }
+        }
+
+        result.push_str(&number);
+        previous = Some(number);
+        rest = after;
+        index += 1;
+    }
+
+    result
+}
+
+/// Round a number to a number of decimal places, using the shortest notation
+/// (e.g. `0.50` becomes `.5`).
+fn round_number(number: &str, precision: usize) -> String {
+    let Ok(number) = number.parse::<f64>() else {
+        return number.to_owned();
+    };
+
+    let number = format!("{number:.precision$}");
+
+    let number = if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number.as_str()
+    };
+
+    match number {
+        "-0" => "0".to_owned(),
+        number => {
+            if let Some(fraction) = number.strip_prefix("0.") {
+                format!(".{fraction}")
+            } else if let Some(fraction) = number.strip_prefix("-0.") {
+                format!("-.{fraction}")
+            } else {
+                number.to_owned()
+            }
+        },
+    }
+}
+
+/// Return the length of the number at the beginning of a string, or `0`.
+fn number_len(value: &str) -> usize {
+    let bytes = value.as_bytes();
+
+    let count_digits = |start: usize| {
+        bytes[start..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+
+    let mut len = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+
+    let integer_digits = count_digits(len);
+    len += integer_digits;
+
+    let mut fraction_digits = 0;
+
+    if bytes.get(len) == Some(&b'.') {
+        fraction_digits = count_digits(len + 1);
+        if integer_digits > 0 || fraction_digits > 0 {
+            len += 1 + fraction_digits;
+        }
+    }
+
+    if integer_digits == 0 && fraction_digits == 0 {
+        return 0;
+    }
+
+    if matches!(bytes.get(len), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(len + 1), Some(b'+' | b'-')));
+        let exponent_digits = count_digits(len + 1 + sign);
+        if exponent_digits > 0 {
+            len += 1 + sign + exponent_digits;
+        }
+    }
+
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn minify() {
+        const CASES: [(&str, Option<usize>, &str); 4] = [
+            (
+                concat!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                    "<!-- Generator: Inkscape -->\n",
+                    "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0  10 10\">\n",
+                    "  <metadata><rdf:RDF></rdf:RDF></metadata>\n",
+                    "  <path d=\"M 0 0\n    L 10 10\" />\n",
+                    "</svg>\n",
+                ),
+                None,
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 10 10\"><path d=\"M 0 0 \
+                 L 10 10\"/></svg>",
+            ),
+            (
+                concat!(
+                    "<svg xmlns:inkscape=\"http://www.inkscape.org/namespaces/inkscape\">",
+                    "<sodipodi:namedview pagecolor=\"#ffffff\"/>",
+                    "<g inkscape:label=\"Layer 1\" id=\"layer1\"></g>",
+                    "</svg>",
+                ),
+                None,
+                "<svg><g id=\"layer1\"></g></svg>",
+            ),
+            (
+                "<svg><text> Hello <tspan>world</tspan> </text></svg>",
+                None,
+                "<svg><text> Hello <tspan>world</tspan> </text></svg>",
+            ),
+            (
+                "<svg><path d=\"M0.123456 10.5L-0.0001,3.14159\"/></svg>",
+                Some(2),
+                "<svg><path d=\"M.12 10.5L0,3.14\"/></svg>",
+            ),
+        ];
+
+        for (input, precision, expected) in CASES {
+            let result = super::minify(input, precision).unwrap();
+            assert_eq!(
+                result, expected,
+                "\nminify({input:?}, {precision:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn round_numbers() {
+        const CASES: [(&str, usize, &str); 8] = [
+            ("M10 20", 2, "M10 20"),
+            ("M1.2345 2.3456", 2, "M1.23 2.35"),
+            ("M1.5.5", 0, "M2 0"),
+            ("M-0.5-0.25", 1, "M-.5-.2"),
+            ("a1 1 0 01 1 1", 1, "a1 1 0 01 1 1"),
+            ("M1e-3 2.5e2", 2, "M0 250"),
+            ("a1 1 0 01.56 2", 1, "a1 1 0 01.6 2"),
+            (
+                "A1 1 0 10.44.5 1 1 0 11.25 2",
+                1,
+                "A1 1 0 10.4.5 1 1 0 11.2 2",
+            ),
+        ];
+
+        for (input, precision, expected) in CASES {
+            let result = super::round_numbers(input, precision);
+            assert_eq!(
+                result, expected,
+                "\nround_numbers({input:?}, {precision}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! Generate the navigation tree.
 
 use std::{
+    cmp::Ordering,
     collections::BTreeMap,
     path::{Component, Path},
 };
@@ -27,6 +28,136 @@ pub(super) struct Data {
     pub url: String,
 }
 
+/// Site navigation (e.g. a documentation sidebar).
+///
+/// The site navigation follows the structure of the input directory. It is
+/// exposed to layouts as `site.nav`.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Navigation {
+    /// Top-level items.
+    items: Vec<NavItem>,
+}
+
+/// Item of the site navigation.
+#[derive(Clone, Debug, Default, Serialize)]
+pub(super) struct NavItem {
+    /// Title of the page.
+    title: Option<String>,
+
+    /// URL of the page, or `None` for directories without index page.
+    url: Option<String>,
+
+    /// Weight of the page, used to order items.
+    weight: Option<f64>,
+
+    /// Name of the file or directory, used to order items.
+    #[serde(skip)]
+    name: String,
+
+    /// Determine whether the item is the current page.
+    current: bool,
+
+    /// Determine whether the item is the current page or one of its ancestors.
+    active: bool,
+
+    /// Child items.
+    children: Vec<NavItem>,
+}
+
+impl Navigation {
+    /// Return the items of the site navigation, with flags for a page.
+    pub(super) fn items_for_page<S>(&self, url: S) -> Vec<NavItem>
+    where
+        S: AsRef<str>,
+    {
+        flag_items(&self.items, url.as_ref())
+    }
+}
+
+impl NavItem {
+    /// Insert an item given its path relative to the input directory.
+    ///
+    /// Index pages (e.g. `docs/index.md`) are assigned to the item of their
+    /// directory. An item already assigned to a page is left unchanged.
+    fn insert<P>(&mut self, path: P, item: NavItem)
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        let path = if path.file_stem().is_some_and(|stem| stem == "index") {
+            path.parent().unwrap_or(Path::new("")).to_owned()
+        } else {
+            path.with_extension("")
+        };
+
+        let mut node = self;
+        for component in path.components() {
+            let name = component.as_os_str().to_string_lossy();
+            node = match node.children.iter().position(|child| child.name == name) {
+                Some(index) => &mut node.children[index],
+                None => {
+                    node.children.push(NavItem {
+                        name: name.into_owned(),
+                        ..Default::default()
+                    });
+                    node.children.last_mut().unwrap()
+                },
+            };
+        }
+
+        if node.url.is_none() {
+            node.title = item.title;
+            node.url = item.url;
+            node.weight = item.weight;
+        }
+    }
+
+    /// Sort children recursively by weight, then by title or name.
+    ///
+    /// Items without weight are placed last.
+    fn sort(&mut self) {
+        self.children.sort_by(|a, b| {
+            let weight = match (a.weight, b.weight) {
+                (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            weight.then_with(|| {
+                let a = a.title.as_deref().unwrap_or(&a.name);
+                let b = b.title.as_deref().unwrap_or(&b.name);
+                a.cmp(b)
+            })
+        });
+
+        for child in self.children.iter_mut() {
+            child.sort();
+        }
+    }
+}
+
+/// Copy items, flagging the current page and its ancestors.
+fn flag_items(items: &[NavItem], url: &str) -> Vec<NavItem> {
+    items
+        .iter()
+        .map(|item| {
+            let children = flag_items(&item.children, url);
+            let current = item.url.as_deref() == Some(url);
+            let active = current || children.iter().any(|child| child.active);
+            NavItem {
+                title: item.title.to_owned(),
+                url: item.url.to_owned(),
+                weight: item.weight,
+                name: item.name.to_owned(),
+                current,
+                active,
+                children,
+            }
+        })
+        .collect()
+}
+
 impl Tree {
     /// Insert a node in the navigation tree with given entry path.
     fn insert<P>(&mut self, path: P, data: Data)
@@ -66,12 +197,27 @@ impl Tree {
 }
 
 /// Generate the navigation tree.
+///
+/// Each page receives the node of the navigation tree located at its URL.
+/// The site navigation, following the structure of the input directory, is
+/// returned for layouts.
 pub(super) fn create_navigation_entries(
     entries: impl Iterator<Item = Result<Entry, Error>>,
     config: &Config,
-) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+) -> Result<
+    (
+        impl Iterator<Item = Result<Entry, Error>>,
+        Option<Navigation>,
+    ),
+    Error,
+> {
     let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
 
+    let navigation = config
+        .navigation
+        .is_some()
+        .then(|| create_navigation(&entries, config));
+
     // Navigation tree is opt-in
     let entries = if let Some(navigation_config) = config.navigation.as_ref() {
         // Generate the entire navigation tree
@@ -120,14 +266,95 @@ pub(super) fn create_navigation_entries(
                 })
             })
             .collect::<anyhow::Result<_>>()
-            .map_err(|error| Error::CreateNavigation {
-                source: error.into(),
-            })?
+            .map_err(|error| Error::CreateNavigation { source: error })?
     } else {
         entries
     };
 
-    let entries = entries.into_iter().map(|v| Ok(v));
+    let entries = entries.into_iter().map(Ok);
+
+    Ok((entries, navigation))
+}
+
+/// Create the site navigation from the input paths of pages.
+///
+/// The home page is not part of the site navigation.
+fn create_navigation(entries: &[Entry], config: &Config) -> Navigation {
+    let mut root = NavItem::default();
+
+    for entry in entries.iter().filter(|entry| entry.format == "html") {
+        let Some(path) = entry
+            .input_path()
+            .and_then(|path| config.strip_input_dir(path))
+        else {
+            continue;
+        };
+
+        let data = entry.data.as_ref();
+
+        root.insert(path, NavItem {
+            title: data.and_then(|data| data.title.to_owned()),
+            url: Some(entry.url.to_owned()),
+            weight: data
+                .and_then(|data| data.extra.get("weight"))
+                .and_then(|weight| weight.as_f64()),
+            ..Default::default()
+        });
+    }
 
-    Ok(entries)
+    root.sort();
+
+    Navigation {
+        items: root.children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NavItem;
+
+    fn page(title: &str, url: &str, weight: Option<f64>) -> NavItem {
+        NavItem {
+            title: Some(title.to_owned()),
+            url: Some(url.to_owned()),
+            weight,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn navigation() {
+        let mut root = NavItem::default();
+        root.insert("index.md", page("Home", "/", None));
+        root.insert("docs/index.md", page("Docs", "/docs", Some(1.0)));
+        root.insert("docs/usage.md", page("Usage", "/docs/usage", Some(2.0)));
+        root.insert(
+            "docs/install.md",
+            page("Install", "/docs/install", Some(1.0)),
+        );
+        root.insert("about.md", page("About", "/about", None));
+        root.sort();
+
+        let navigation = super::Navigation {
+            items: root.children,
+        };
+
+        let items = navigation.items_for_page("/docs/usage");
+
+        let titles: Vec<_> = items
+            .iter()
+            .map(|item| item.title.as_deref().unwrap())
+            .collect();
+        assert_eq!(titles, ["Docs", "About"]);
+
+        let docs = &items[0];
+        assert!(docs.active && !docs.current);
+        let titles: Vec<_> = docs
+            .children
+            .iter()
+            .map(|item| (item.title.as_deref().unwrap(), item.current))
+            .collect();
+        assert_eq!(titles, [("Install", false), ("Usage", true)]);
+        assert!(!items[1].active);
+    }
 }
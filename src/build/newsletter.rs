@@ -0,0 +1,406 @@
+//! Render email versions of pages for newsletters.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
+use lightningcss::{
+    rules::CssRule,
+    stylesheet::{ParserOptions, PrinterOptions, StyleSheet},
+    traits::ToCss,
+};
+
+use super::{Config, Entry, Error};
+use crate::util::{
+    date_time::{parse_offset, DateTime},
+    url::{resolve_url, site_base_url},
+};
+
+/// CSS rules applied before the stylesheets of the page.
+const DEFAULT_CSS: &str = "img { max-width: 100%; height: auto; }";
+
+/// Create a copy of the selected pages, to be rendered as emails.
+///
+/// Pages are selected by URL with `newsletter.pages`, and as the latest posts
+/// with `newsletter.latest`. Each copy is located under `newsletter.url`, and
+/// is rendered with `newsletter.layout` if specified. Copies are converted by
+/// [`render_entries`] once their layout and URLs are rendered.
+pub(super) fn create_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let Some(newsletter_config) = config.newsletter.as_ref() else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    let url_prefix = newsletter_config.url.trim_end_matches('/');
+
+    // The time zone is checked when validating the configuration
+    let timezone = config
+        .timezone
+        .as_ref()
+        .and_then(|timezone| parse_offset(timezone).ok());
+
+    let mut urls: HashSet<&str> = newsletter_config.pages.iter().map(String::as_str).collect();
+
+    for url in urls.iter() {
+        if !entries
+            .iter()
+            .any(|entry| entry.format == "html" && entry.url == *url)
+        {
+            tracing::warn!("Newsletter page {:?} not found", url);
+        }
+    }
+
+    if newsletter_config.latest > 0 {
+        let mut posts: Vec<(DateTime, &Entry)> = entries
+            .iter()
+            .filter(|entry| {
+                entry.format == "html"
+                    && newsletter_config.collection.as_ref().map_or(true, |name| {
+                        super::collections::collection_name(entry) == Some(name.as_str())
+                    })
+            })
+            .filter_map(|entry| {
+                let date = entry.data.as_ref()?.date.as_ref()?;
+                Some((date, entry))
+            })
+            .map(|(date, entry)| -> anyhow::Result<_> {
+                Ok((DateTime::parse_in_timezone(date, timezone)?, entry))
+            })
+            .collect::<Result<_, _>>()
+            .map_err(|error| Error::CreateNewsletter { source: error })?;
+
+        posts.sort_by(|x, y| y.0.cmp(&x.0).then_with(|| x.1.url.cmp(&y.1.url)));
+        posts.truncate(newsletter_config.latest);
+
+        urls.extend(posts.into_iter().map(|(_, entry)| entry.url.as_str()));
+    }
+
+    let urls: HashSet<String> = urls.into_iter().map(str::to_owned).collect();
+
+    let entries: Vec<Entry> = entries
+        .into_iter()
+        .flat_map(|entry| {
+            if entry.format != "html" || !urls.contains(&entry.url) {
+                return Vec::from([entry]);
+            }
+
+            let mut copy = Entry {
+                url: email_url(url_prefix, &entry.url),
+                ..entry.to_owned()
+            };
+
+            if let Some(layout) = newsletter_config.layout.as_ref() {
+                let data = copy.data.get_or_insert_with(Default::default);
+                if !data.extra.is_object() {
+                    data.extra = serde_json::Map::new().into();
+                }
+                data.extra[&config.layouts.layout_key] = layout.to_owned().into();
+            }
+
+            // The copy comes first, so that the templates recorded for the input
+            // file are those of the page
+            Vec::from([copy, entry])
+        })
+        .collect();
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Convert the copies created by [`create_entries`] to email HTML.
+///
+/// Stylesheets of the page (`<link>` and `<style>` elements) are inlined in
+/// `style` attributes, scripts are removed, URLs are made absolute, and the
+/// content of the body is wrapped in a table of `newsletter.width` pixels.
+/// Inlined rules are applied in the order of the stylesheets, regardless of
+/// the specificity of their selectors, and `style` attributes take
+/// precedence.
+///
+/// Converted entries have the `email` format, so that they are not processed
+/// as web pages by the next stages (e.g. sitemap).
+pub(super) fn render_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let Some(newsletter_config) = config.newsletter.as_ref() else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    let url_prefix = newsletter_config.url.trim_end_matches('/');
+    // The domain is checked when validating the configuration
+    let site_url = site_base_url(config.site_url.as_deref(), &config.base_url).unwrap_or_default();
+
+    // Stylesheets by absolute URL
+    let stylesheets: HashMap<String, String> = entries
+        .iter()
+        .filter(|entry| entry.format == "css")
+        .filter_map(|entry| {
+            let content = entry.content.as_ref()?;
+            Some((format!("{}{}", site_url, entry.url), content.to_owned()))
+        })
+        .collect();
+
+    let entries: Vec<Entry> =
+        entries
+            .into_iter()
+            .map(|entry| {
+                if entry.format != "html" || !is_email_url(url_prefix, &entry.url) {
+                    return Ok(entry);
+                }
+
+                let Some(content) = entry.content.as_ref() else {
+                    return Ok(entry);
+                };
+
+                // Relative URLs start from `{url}/`, like those of pages
+                let base = format!("{}{}/", site_url, entry.url.trim_end_matches('/'));
+
+                let content = render(content, &base, &stylesheets, newsletter_config.width)
+                    .map_err(|error| Error::CreateNewsletter {
+                        source: error.context(format!("While rendering {:?}", entry.url)),
+                    })?;
+
+                Ok(Entry {
+                    format: "email".to_owned(),
+                    content: Some(content),
+                    ..entry
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Return the URL of the email version of a page.
+fn email_url(url_prefix: &str, url: &str) -> String {
+    match url.trim_matches('/') {
+        "" => format!("{url_prefix}/index.html"),
+        path => format!("{url_prefix}/{path}.html"),
+    }
+}
+
+/// Determine whether a URL is the URL of the email version of a page.
+fn is_email_url(url_prefix: &str, url: &str) -> bool {
+    url.strip_prefix(url_prefix)
+        .is_some_and(|path| path.starts_with('/') && path.ends_with(".html"))
+}
+
+/// Render the email HTML of a page.
+///
+/// URLs are resolved relative to `base`, and linked stylesheets are looked up
+/// in `stylesheets` by absolute URL.
+fn render(
+    content: &str,
+    base: &str,
+    stylesheets: &HashMap<String, String>,
+    width: usize,
+) -> anyhow::Result<String> {
+    let css = RefCell::new(String::from(DEFAULT_CSS));
+
+    let selector = super::url::ELEMENTS_URL_ATTRIBUTES
+        .iter()
+        .map(|(tag_name, attribute)| format!("{tag_name}[{attribute}]"))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![
+            lol_html::element!("link[rel~=\"stylesheet\"][href]", |element| {
+                let href = element.get_attribute("href").unwrap_or_default();
+
+                match stylesheets.get(&resolve_url(base, &href)) {
+                    Some(stylesheet) => {
+                        let mut css = css.borrow_mut();
+                        css.push('\n');
+                        css.push_str(stylesheet);
+                    },
+                    None => tracing::warn!("Cannot inline stylesheet {:?}", href),
+                }
+
+                element.remove();
+                Ok(())
+            }),
+            lol_html::element!("style", |_| {
+                css.borrow_mut().push('\n');
+                Ok(())
+            }),
+            lol_html::text!("style", |text| {
+                css.borrow_mut().push_str(text.as_str());
+                Ok(())
+            }),
+            lol_html::element!("script", |element| {
+                element.remove();
+                Ok(())
+            }),
+            lol_html::element!(selector, |element| {
+                for (tag_name, attribute) in super::url::ELEMENTS_URL_ATTRIBUTES {
+                    if tag_name != element.tag_name() {
+                        continue;
+                    }
+
+                    let Some(href) = element.get_attribute(attribute) else {
+                        continue;
+                    };
+
+                    // Keep links to the same page
+                    if href.trim().starts_with('#') {
+                        continue;
+                    }
+
+                    element.set_attribute(attribute, &resolve_url(base, &href))?;
+                }
+                Ok(())
+            }),
+            lol_html::element!("body", |element| {
+                element.prepend(
+                    &format!(
+                        concat!(
+                            "<table role=\"presentation\" width=\"100%\" cellpadding=\"0\" ",
+                            "cellspacing=\"0\" border=\"0\"><tr><td align=\"center\">",
+                            "<table role=\"presentation\" width=\"{width}\" cellpadding=\"0\" ",
+                            "cellspacing=\"0\" border=\"0\" style=\"width: 100%; max-width: \
+                             {width}px\"><tr><td align=\"left\">"
+                        ),
+                        width = width
+                    ),
+                    lol_html::html_content::ContentType::Html,
+                );
+                element.append(
+                    "</td></tr></table></td></tr></table>",
+                    lol_html::html_content::ContentType::Html,
+                );
+                Ok(())
+            }),
+        ],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    inline_css(&content, &css.into_inner())
+}
+
+/// Copy the declarations of CSS rules to the `style` attributes of the
+/// elements they match.
+///
+/// Rules that cannot be inlined (e.g. media queries, or selectors with pseudo
+/// classes) are ignored.
+fn inline_css(content: &str, css: &str) -> anyhow::Result<String> {
+    let stylesheet = StyleSheet::parse(css, ParserOptions::default())
+        .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+
+    let rules: Vec<(String, String)> = stylesheet
+        .rules
+        .0
+        .iter()
+        .filter_map(|rule| match rule {
+            CssRule::Style(rule) => Some(rule),
+            _ => None,
+        })
+        .filter_map(|rule| {
+            let selector = rule
+                .selectors
+                .to_css_string(PrinterOptions::default())
+                .ok()?;
+
+            // Skip selectors that are not supported by the HTML rewriter
+            selector.parse::<lol_html::Selector>().ok()?;
+
+            let declarations = rule
+                .declarations
+                .declarations
+                .iter()
+                .map(|property| property.to_css_string(false, PrinterOptions::default()))
+                .chain(
+                    rule.declarations
+                        .important_declarations
+                        .iter()
+                        .map(|property| property.to_css_string(true, PrinterOptions::default())),
+                )
+                .collect::<Result<Vec<_>, _>>()
+                .ok()?
+                .join("; ");
+
+            Some((selector, declarations))
+        })
+        .collect();
+
+    // Declarations are prepended in reverse order, so that the last rules and
+    // the existing `style` attributes take precedence
+    let element_content_handlers = rules
+        .iter()
+        .rev()
+        .map(|(selector, declarations)| {
+            lol_html::element!(selector, move |element| {
+                let style = match element.get_attribute("style") {
+                    Some(style) if !style.trim().is_empty() => format!("{declarations}; {style}"),
+                    _ => declarations.to_owned(),
+                };
+                element.set_attribute("style", &style)?;
+                Ok(())
+            })
+        })
+        .collect();
+
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers,
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn email_url() {
+        const CASES: [(&str, &str, &str); 3] = [
+            ("/newsletter", "/", "/newsletter/index.html"),
+            ("/newsletter", "/blog/post", "/newsletter/blog/post.html"),
+            ("/newsletter", "/blog/", "/newsletter/blog.html"),
+        ];
+
+        for (url_prefix, url, expected) in CASES {
+            let result = super::email_url(url_prefix, url);
+            assert_eq!(
+                result, expected,
+                "\nemail_url({url_prefix:?}, {url:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn inline_css() {
+        const CASES: [(&str, &str, &str); 3] = [
+            (
+                "<p>A</p><p class=\"b\">B</p>",
+                "p { color: red } .b { color: blue }",
+                "<p style=\"color: red\">A</p><p class=\"b\" style=\"color: red; color: \
+                 blue\">B</p>",
+            ),
+            (
+                "<p style=\"color: green\">A</p>",
+                "p { color: red; margin: 0 }",
+                "<p style=\"color: red; margin: 0; color: green\">A</p>",
+            ),
+            (
+                "<a href=\"/\">A</a>",
+                "a:hover { color: red } @media (width < 600px) { a { color: blue } }",
+                "<a href=\"/\">A</a>",
+            ),
+        ];
+
+        for (content, css, expected) in CASES {
+            let result = super::inline_css(content, css).unwrap();
+            assert_eq!(
+                result, expected,
+                "\ninline_css({content:?}, {css:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Measure the weight of pages and their assets.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use super::{Config, Entry, Error};
+use crate::{
+    report::{PageReport, Size},
+    util::file_content::FileContent,
+};
+
+/// Elements and attributes that load an asset with the page.
+const ASSET_ATTRIBUTES: [(&str, &str); 7] = [
+    ("link[rel~=\"stylesheet\"]", "href"),
+    ("script", "src"),
+    ("img", "src"),
+    ("source", "src"),
+    ("video", "src"),
+    ("video", "poster"),
+    ("audio", "src"),
+];
+
+/// Sizes of the entries of a site.
+pub(super) struct Weights<'a> {
+    /// Site configuration.
+    config: &'a Config,
+
+    /// Format and size of each entry, indexed by URL.
+    sizes: HashMap<String, (String, Size)>,
+
+    /// URL, size and asset URLs of each page.
+    pages: Vec<(String, Size, Vec<String>)>,
+}
+
+impl<'a> Weights<'a> {
+    /// Create an empty set of sizes.
+    pub(super) fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            sizes: HashMap::new(),
+            pages: Vec::new(),
+        }
+    }
+
+    /// Measure the size of a [`Entry`], and find the assets of pages.
+    pub(super) fn add_entry(&mut self, entry: &Entry) -> Result<(), Error> {
+        let size = entry_size(entry, self.config).map_err(|error| Error::MeasurePageWeight {
+            input_path: entry.input_path_buf(),
+            source: error,
+        })?;
+
+        if entry.format == "html" {
+            let content = entry.content.as_deref().unwrap_or_default();
+
+            let assets = find_asset_urls(content).map_err(|error| Error::MeasurePageWeight {
+                input_path: entry.input_path_buf(),
+                source: error,
+            })?;
+
+            let base = entry.base_dir();
+
+            let mut assets: Vec<String> = assets
+                .iter()
+                .filter_map(|href| {
+                    super::unused_assets::resolve_url(href, &base, &self.config.base_url)
+                })
+                .collect();
+
+            // An asset is loaded once per page
+            assets.sort();
+            assets.dedup();
+
+            self.pages.push((entry.url.to_owned(), size, assets));
+        } else {
+            self.sizes
+                .insert(entry.url.to_owned(), (entry.format.to_owned(), size));
+        }
+
+        Ok(())
+    }
+
+    /// Sum the sizes of each page and its assets.
+    ///
+    /// Assets that are not part of the site (e.g. external scripts) are
+    /// ignored.
+    pub(super) fn into_reports(self) -> Vec<PageReport> {
+        self.pages
+            .into_iter()
+            .map(|(url, html, assets)| {
+                let mut report = PageReport {
+                    url,
+                    html,
+                    ..Default::default()
+                };
+
+                for (format, size) in assets.iter().filter_map(|url| self.sizes.get(url)) {
+                    match format.as_str() {
+                        "css" => report.css += *size,
+                        "js" => report.js += *size,
+                        _ => report.other += *size,
+                    }
+                }
+
+                report
+            })
+            .collect()
+    }
+}
+
+/// Measure the size of a [`Entry`].
+///
+/// Copied files are read only if their transfer size must be estimated.
+fn entry_size(entry: &Entry, config: &Config) -> anyhow::Result<Size> {
+    let estimate_transfer =
+        config.precompress.is_some() && super::precompress::is_compressible(&entry.format);
+
+    let file_content;
+    let content: &[u8] = match (entry.content.as_ref(), entry.input_path()) {
+        (Some(content), _) => content.as_bytes(),
+        (None, Some(input_path)) if estimate_transfer => {
+            file_content = FileContent::read(input_path, config.mmap_threshold)?;
+            &file_content
+        },
+        (None, Some(input_path)) => {
+            let raw = std::fs::metadata(input_path)?.len() as usize;
+            return Ok(Size {
+                raw,
+                transfer: config.precompress.as_ref().map(|_| raw),
+            });
+        },
+        (None, None) => &[],
+    };
+
+    Ok(Size {
+        raw: content.len(),
+        transfer: super::precompress::transfer_size(content, &entry.format, config)?,
+    })
+}
+
+/// Find the URLs of the assets loaded by a page.
+fn find_asset_urls(content: &str) -> anyhow::Result<Vec<String>> {
+    let urls = RefCell::new(Vec::new());
+
+    let urls = &urls;
+
+    let element_content_handlers = ASSET_ATTRIBUTES
+        .iter()
+        .map(|(selector, attribute)| {
+            lol_html::element!(format!("{selector}[{attribute}]"), move |element| {
+                if let Some(href) = element.get_attribute(attribute) {
+                    urls.borrow_mut().push(href);
+                }
+                Ok(())
+            })
+        })
+        .collect();
+
+    lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers,
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(urls.take())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn find_asset_urls() {
+        const CONTENT: &str = concat!(
+            "<link rel=\"icon\" href=\"/favicon.png\">",
+            "<link rel=\"stylesheet\" href=\"/style.css\">",
+            "<script src=\"main.js\"></script>",
+            "<a href=\"/about/\"><img src=\"logo.png\"></a>",
+        );
+
+        let result = super::find_asset_urls(CONTENT).unwrap();
+
+        assert_eq!(result, ["/style.css", "main.js", "logo.png"]);
+    }
+}
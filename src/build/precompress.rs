@@ -0,0 +1,178 @@
+//! Write precompressed output files.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use super::{Config, Entry, Error};
+use crate::util::file_content::FileContent;
+
+/// Formats of text files that benefit from compression.
+const COMPRESSIBLE_FORMATS: [&str; 6] = ["css", "html", "js", "json", "svg", "xml"];
+
+/// Write gzip and Brotli compressed versions of the output file of a
+/// [`Entry`].
+///
+/// If `precompress` is specified in the configuration, this function writes
+/// `{output_path}.gz` and/or `{output_path}.br` next to the output file of
+/// text entries (e.g. HTML, CSS) larger than `precompress.min_size`. It must
+/// be called after the output file is written.
+///
+/// This function returns the number of files written.
+pub(super) fn write_entry(entry: &Entry, config: &Config) -> Result<usize, Error> {
+    let Some(precompress_config) = config.precompress.as_ref() else {
+        return Ok(0);
+    };
+
+    if !is_compressible(&entry.format) {
+        return Ok(0);
+    }
+
+    let output_path = super::write_file::output_path(entry, config)?;
+
+    // Read copied files (e.g. SVG images) from the output directory
+    let file_content;
+    let content: &[u8] = match entry.content.as_ref() {
+        Some(content) => content.as_bytes(),
+        None => {
+            file_content =
+                FileContent::read(&output_path, config.mmap_threshold).map_err(|error| {
+                    Error::WriteOutput {
+                        output_path: output_path.to_owned(),
+                        source: error.into(),
+                    }
+                })?;
+            &file_content
+        },
+    };
+
+    if content.len() < precompress_config.min_size {
+        return Ok(0);
+    }
+
+    let mut num_files = 0;
+
+    if precompress_config.gzip {
+        let output_path = append_extension(&output_path, "gz");
+        write_file(&output_path, gzip(content))?;
+        num_files += 1;
+    }
+
+    if precompress_config.brotli {
+        let output_path = append_extension(&output_path, "br");
+        write_file(&output_path, brotli(content))?;
+        num_files += 1;
+    }
+
+    Ok(num_files)
+}
+
+/// Check if a format benefits from compression.
+pub(super) fn is_compressible(format: &str) -> bool {
+    COMPRESSIBLE_FORMATS.contains(&format)
+}
+
+/// Estimate the transfer size of a content.
+///
+/// If `precompress` is specified in the configuration, this function returns
+/// the size of the smallest version written by [`write_entry`] (e.g. the
+/// Brotli compressed file). It returns `None` otherwise.
+pub(super) fn transfer_size(
+    content: &[u8],
+    format: &str,
+    config: &Config,
+) -> std::io::Result<Option<usize>> {
+    let Some(precompress_config) = config.precompress.as_ref() else {
+        return Ok(None);
+    };
+
+    let mut size = content.len();
+
+    if !is_compressible(format) || size < precompress_config.min_size {
+        return Ok(Some(size));
+    }
+
+    if precompress_config.gzip {
+        size = size.min(gzip(content)?.len());
+    }
+
+    if precompress_config.brotli {
+        size = size.min(brotli(content)?.len());
+    }
+
+    Ok(Some(size))
+}
+
+/// Write a compressed content to a file.
+fn write_file(output_path: &Path, content: std::io::Result<Vec<u8>>) -> Result<(), Error> {
+    tracing::info!("Writing {:?}", output_path);
+
+    content
+        .and_then(|content| std::fs::write(output_path, content))
+        .map_err(|error| Error::WriteOutput {
+            output_path: output_path.to_owned(),
+            source: error.into(),
+        })
+}
+
+/// Append an extension to a path (e.g. `index.html` becomes `index.html.gz`).
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    path.into()
+}
+
+/// Compress a content using gzip with the best compression level.
+fn gzip(content: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+/// Compress a content using Brotli with the best compression level.
+fn brotli(content: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+    encoder.write_all(content)?;
+    Ok(encoder.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    #[test]
+    fn append_extension() {
+        assert_eq!(
+            super::append_extension("/_site/index.html".as_ref(), "gz"),
+            std::path::PathBuf::from("/_site/index.html.gz")
+        );
+    }
+
+    #[test]
+    fn gzip() {
+        let input = "<p>Hello, world!</p>".repeat(100);
+        let compressed = super::gzip(input.as_bytes()).unwrap();
+        assert!(compressed.len() < input.len());
+
+        let mut result = String::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut result)
+            .unwrap();
+        assert_eq!(result, input);
+    }
+
+    #[test]
+    fn brotli() {
+        let input = "<p>Hello, world!</p>".repeat(100);
+        let compressed = super::brotli(input.as_bytes()).unwrap();
+        assert!(compressed.len() < input.len());
+
+        let mut result = String::new();
+        brotli::Decompressor::new(compressed.as_slice(), 4096)
+            .read_to_string(&mut result)
+            .unwrap();
+        assert_eq!(result, input);
+    }
+}
@@ -0,0 +1,363 @@
+//! Insert preload hints of critical assets.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use super::{unused_assets::resolve_url, url::rewrite_css_urls, Config, Entry, Error};
+use crate::util::{html, url::UrlPath};
+
+/// Hint given to browsers for an asset type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum PreloadHint {
+    /// Fetch the asset early, for the current page (`rel="preload"`).
+    #[default]
+    Preload,
+
+    /// Fetch the asset with a low priority, for next pages
+    /// (`rel="prefetch"`).
+    Prefetch,
+
+    /// Do not insert hints.
+    None,
+}
+
+impl std::str::FromStr for PreloadHint {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "preload" => Ok(Self::Preload),
+            "prefetch" => Ok(Self::Prefetch),
+            "none" => Ok(Self::None),
+            _ => Err(anyhow::anyhow!(
+                "Unknown preload hint {:?} (expected \"preload\", \"prefetch\" or \"none\")",
+                value
+            )),
+        }
+    }
+}
+
+impl PreloadHint {
+    /// Return the value of the `rel` attribute, if any.
+    fn rel(&self) -> Option<&'static str> {
+        match self {
+            Self::Preload => Some("preload"),
+            Self::Prefetch => Some("prefetch"),
+            Self::None => None,
+        }
+    }
+}
+
+/// Critical assets of a page.
+#[derive(Debug, Default, PartialEq)]
+struct CriticalAssets {
+    /// URL of the first stylesheet.
+    stylesheet: Option<String>,
+
+    /// URL of the first image (e.g. a hero image).
+    image: Option<String>,
+
+    /// URLs already hinted by the page.
+    hinted: Vec<String>,
+}
+
+/// Insert `<link rel="preload">` or `<link rel="prefetch">` hints at the
+/// beginning of the `<head>` of pages.
+///
+/// Hints are enabled by the `preload` key in the configuration. For each page,
+/// the critical assets are the first stylesheet, the first image, and the
+/// fonts referenced by `url()` functions in this stylesheet. Assets already
+/// hinted by the page are skipped.
+pub(super) fn insert_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let Some(preload_config) = config.preload.as_ref() else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    let parse_hint = |value: &str, key: &str| {
+        value
+            .parse::<PreloadHint>()
+            .map_err(|error| Error::InsertPreloadHints {
+                input_path: None,
+                source: error.context(format!("While parsing preload.{key}")),
+            })
+    };
+
+    let stylesheet_hint = parse_hint(&preload_config.stylesheet, "stylesheet")?;
+    let image_hint = parse_hint(&preload_config.image, "image")?;
+    let fonts_hint = parse_hint(&preload_config.fonts, "fonts")?;
+
+    // Fonts referenced by each stylesheet, keyed by URL. URLs are kept
+    // unescaped, like attribute values read from pages
+    let fonts: HashMap<String, Vec<String>> = entries
+        .iter()
+        .filter(|entry| entry.format == "css")
+        .filter_map(|entry| {
+            let content = entry.content.as_ref()?;
+            let base = UrlPath::from(entry.url.as_str());
+            let fonts = find_font_urls(content)
+                .iter()
+                .filter_map(|href| resolve_url(href, &base, &config.base_url))
+                .map(|url| format!("{}{}", config.base_url, url))
+                .collect();
+            Some((entry.url.to_owned(), fonts))
+        })
+        .collect();
+
+    let entries: Vec<_> = entries
+        .into_iter()
+        .map(|entry| {
+            if entry.format != "html" {
+                return Ok(entry);
+            }
+
+            let Some(content) = entry.content.as_ref() else {
+                return Ok(entry);
+            };
+
+            let assets =
+                find_critical_assets(content).map_err(|error| Error::InsertPreloadHints {
+                    input_path: entry.input_path_buf(),
+                    source: error,
+                })?;
+
+            let base = entry.base_dir();
+
+            let stylesheet_fonts = assets
+                .stylesheet
+                .as_ref()
+                .and_then(|href| resolve_url(href, &base, &config.base_url))
+                .and_then(|url| fonts.get(&url));
+
+            let hints: String = [
+                (stylesheet_hint, assets.stylesheet.as_ref(), "style"),
+                (image_hint, assets.image.as_ref(), "image"),
+            ]
+            .into_iter()
+            .filter_map(|(hint, href, kind)| Some((hint, href?, kind)))
+            .chain(
+                stylesheet_fonts
+                    .into_iter()
+                    .flatten()
+                    .map(|href| (fonts_hint, href, "font")),
+            )
+            .filter(|(_, href, _)| !assets.hinted.contains(*href))
+            .filter_map(|(hint, href, kind)| create_hint(hint, href, kind))
+            .collect();
+
+            if hints.is_empty() {
+                return Ok(entry);
+            }
+
+            let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+                element_content_handlers: vec![lol_html::element!("head", |element| {
+                    element.prepend(&hints, lol_html::html_content::ContentType::Html);
+                    Ok(())
+                })],
+                ..lol_html::RewriteStrSettings::default()
+            })
+            .map_err(|error| Error::InsertPreloadHints {
+                input_path: entry.input_path_buf(),
+                source: error.into(),
+            })?;
+
+            Ok(Entry {
+                content: Some(content),
+                ..entry
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Find the critical assets referenced in HTML code.
+fn find_critical_assets<S>(content: S) -> anyhow::Result<CriticalAssets>
+where
+    S: AsRef<str>,
+{
+    let assets = RefCell::new(CriticalAssets::default());
+
+    lol_html::rewrite_str(content.as_ref(), lol_html::RewriteStrSettings {
+        element_content_handlers: vec![
+            lol_html::element!("link[rel=stylesheet][href]", |element| {
+                let mut assets = assets.borrow_mut();
+                if assets.stylesheet.is_none() {
+                    assets.stylesheet = element
+                        .get_attribute("href")
+                        .map(|href| html::unescape(&href));
+                }
+                Ok(())
+            }),
+            lol_html::element!("img[src]", |element| {
+                let mut assets = assets.borrow_mut();
+                if assets.image.is_none() {
+                    assets.image = element
+                        .get_attribute("src")
+                        .filter(|src| !src.starts_with("data:"))
+                        .map(|src| html::unescape(&src));
+                }
+                Ok(())
+            }),
+            lol_html::element!(
+                "link[rel=preload][href], link[rel=prefetch][href]",
+                |element| {
+                    assets.borrow_mut().hinted.extend(
+                        element
+                            .get_attribute("href")
+                            .map(|href| html::unescape(&href)),
+                    );
+                    Ok(())
+                }
+            ),
+        ],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(assets.into_inner())
+}
+
+/// Find the URLs of fonts referenced by `url()` functions in CSS code.
+fn find_font_urls<S>(content: S) -> Vec<String>
+where
+    S: AsRef<str>,
+{
+    let urls = RefCell::new(Vec::new());
+
+    rewrite_css_urls(content.as_ref(), |href| {
+        if font_type(href).is_some() {
+            urls.borrow_mut().push(href.to_owned());
+        }
+        None
+    });
+
+    urls.into_inner()
+}
+
+/// Return the MIME type of a font from its URL.
+pub(crate) fn font_type(href: &str) -> Option<&'static str> {
+    let path = href.split(['?', '#']).next().unwrap_or_default();
+
+    match UrlPath::from(path)
+        .extension()?
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "otf" => Some("font/otf"),
+        "ttf" => Some("font/ttf"),
+        "woff" => Some("font/woff"),
+        "woff2" => Some("font/woff2"),
+        _ => None,
+    }
+}
+
+/// Create a `<link>` element that hints an asset.
+///
+/// The `href` is escaped before insertion. Returns `None` if the hint is
+/// [`PreloadHint::None`].
+fn create_hint(hint: PreloadHint, href: &str, kind: &str) -> Option<String> {
+    let rel = hint.rel()?;
+
+    // Fonts are always fetched in CORS mode
+    let attributes = match font_type(href).filter(|_| kind == "font") {
+        Some(font_type) => format!(" type=\"{font_type}\" crossorigin"),
+        None => String::new(),
+    };
+
+    Some(format!(
+        "<link rel=\"{rel}\" href=\"{}\" as=\"{kind}\"{attributes}>",
+        html::escape(href)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CriticalAssets, PreloadHint};
+
+    #[test]
+    fn find_critical_assets() {
+        const CONTENT: &str = concat!(
+            "<link rel=\"preload\" href=\"/hero.jpg\" as=\"image\">",
+            "<link rel=\"stylesheet\" href=\"/style.css\">",
+            "<link rel=\"stylesheet\" href=\"/print.css\">",
+            "<img src=\"data:image/png;base64,AA==\">",
+            "<img src=\"/hero.jpg\"><img src=\"/other.jpg\">",
+        );
+
+        let result = super::find_critical_assets(CONTENT).unwrap();
+
+        assert_eq!(result, CriticalAssets {
+            stylesheet: Some("/style.css".to_owned()),
+            image: Some("/hero.jpg".to_owned()),
+            hinted: vec!["/hero.jpg".to_owned()],
+        });
+    }
+
+    #[test]
+    fn find_critical_assets_unescape() {
+        const CONTENT: &str = concat!(
+            "<link rel=\"preload\" href=\"/a.woff2?x=1&amp;y=2\" as=\"font\">",
+            "<link rel=\"stylesheet\" href=\"/style.css?x=1&amp;y=2\">",
+            "<img src=\"/a.jpg?w=1&amp;h=2\">",
+        );
+
+        let result = super::find_critical_assets(CONTENT).unwrap();
+
+        assert_eq!(result, CriticalAssets {
+            stylesheet: Some("/style.css?x=1&y=2".to_owned()),
+            image: Some("/a.jpg?w=1&h=2".to_owned()),
+            hinted: vec!["/a.woff2?x=1&y=2".to_owned()],
+        });
+    }
+
+    #[test]
+    fn find_font_urls() {
+        const CONTENT: &str = "@font-face { src: url(\"a.woff2\") format(\"woff2\"), \
+                               url(a.ttf?v=1); }\nbody { background: url(bg.png); }";
+
+        let result = super::find_font_urls(CONTENT);
+
+        assert_eq!(result, ["a.woff2", "a.ttf?v=1"]);
+    }
+
+    #[test]
+    fn create_hint() {
+        const CASES: [(PreloadHint, &str, &str, Option<&str>); 4] = [
+            (
+                PreloadHint::Preload,
+                "/style.css",
+                "style",
+                Some("<link rel=\"preload\" href=\"/style.css\" as=\"style\">"),
+            ),
+            (
+                PreloadHint::Prefetch,
+                "/a.jpg?w=1&h=2",
+                "image",
+                Some("<link rel=\"prefetch\" href=\"/a.jpg?w=1&amp;h=2\" as=\"image\">"),
+            ),
+            (
+                PreloadHint::Preload,
+                "/fonts/a.woff2",
+                "font",
+                Some(
+                    "<link rel=\"preload\" href=\"/fonts/a.woff2\" as=\"font\" \
+                     type=\"font/woff2\" crossorigin>",
+                ),
+            ),
+            (PreloadHint::None, "/style.css", "style", None),
+        ];
+
+        for (hint, href, kind, expected) in CASES {
+            let result = super::create_hint(hint, href, kind);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\ncreate_hint({hint:?}, {href:?}, {kind:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Sanitize HTML content.
+
+use std::collections::HashSet;
+
+use super::{Config, Entry, Error};
+
+/// Elements removed with their content, even if they are allowed.
+const DANGEROUS_TAGS: [&str; 17] = [
+    "embed",
+    "frame",
+    "frameset",
+    "iframe",
+    "math",
+    "noembed",
+    "noframes",
+    "noscript",
+    "object",
+    "plaintext",
+    "script",
+    "style",
+    "svg",
+    "template",
+    "textarea",
+    "title",
+    "xmp",
+];
+
+/// Attributes containing URLs.
+const URL_ATTRIBUTES: [&str; 7] = [
+    "action",
+    "cite",
+    "data",
+    "formaction",
+    "href",
+    "poster",
+    "src",
+];
+
+/// Allowed URL schemes.
+const URL_SCHEMES: [&str; 4] = ["http", "https", "mailto", "tel"];
+
+/// Sanitize the HTML content of a [`Entry`].
+///
+/// If `sanitize` is specified in the configuration, this function removes
+/// elements and attributes that are not explicitly allowed, event handler
+/// attributes (e.g. `onclick`), URLs with schemes other than [`URL_SCHEMES`]
+/// (e.g. `javascript:`), and comments. Disallowed elements are replaced by
+/// their content, except elements such as `<script>` which are removed
+/// entirely.
+pub(super) fn sanitize_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
+    let Some(sanitize_config) = config.sanitize.as_ref() else {
+        return Ok(entry);
+    };
+
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(entry);
+    };
+
+    let tags: HashSet<&str> = sanitize_config.tags.iter().map(String::as_str).collect();
+
+    let attributes: HashSet<&str> = sanitize_config
+        .attributes
+        .iter()
+        .map(String::as_str)
+        .collect();
+
+    let content = sanitize(content, &tags, &attributes).map_err(|error| Error::Sanitize {
+        input_path: entry.input_path_buf(),
+        source: error,
+    })?;
+
+    Ok(Entry {
+        content: Some(content),
+        ..entry
+    })
+}
+
+/// Sanitize an HTML string.
+fn sanitize<S>(
+    content: S,
+    tags: &HashSet<&str>,
+    attributes: &HashSet<&str>,
+) -> anyhow::Result<String>
+where
+    S: AsRef<str>,
+{
+    let content = lol_html::rewrite_str(content.as_ref(), lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("*", |element| {
+            let tag_name = element.tag_name().to_ascii_lowercase();
+
+            if DANGEROUS_TAGS.contains(&tag_name.as_str()) {
+                element.remove();
+                return Ok(());
+            }
+
+            if !tags.contains(tag_name.as_str()) {
+                element.remove_and_keep_content();
+                return Ok(());
+            }
+
+            let names: Vec<String> = element
+                .attributes()
+                .iter()
+                .map(|attribute| attribute.name())
+                .collect();
+
+            for name in names {
+                let allowed = attributes.contains(name.as_str())
+                    && !name.starts_with("on")
+                    && (!URL_ATTRIBUTES.contains(&name.as_str())
+                        || element
+                            .get_attribute(&name)
+                            .is_some_and(|value| is_safe_url(&value)));
+
+                if !allowed {
+                    element.remove_attribute(&name);
+                }
+            }
+
+            Ok(())
+        })],
+        document_content_handlers: vec![lol_html::doc_comments!(|comment| {
+            comment.remove();
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(content)
+}
+
+/// Check if a URL is relative or uses an allowed scheme.
+///
+/// Browsers ignore tabs and newlines in URLs and decode character references in
+/// attributes, so any URL containing `:` or `&` before the path is rejected
+/// unless it explicitly starts with an allowed scheme.
+fn is_safe_url<S>(url: S) -> bool
+where
+    S: AsRef<str>,
+{
+    let url: String = url
+        .as_ref()
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+
+    let url = url.trim_start_matches(|c: char| c.is_ascii_control() || c == ' ');
+
+    let prefix = url.split(['/', '?', '#']).next().unwrap_or_default();
+
+    if !prefix.contains([':', '&']) {
+        return true;
+    }
+
+    prefix
+        .split_once(':')
+        .is_some_and(|(scheme, _)| URL_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    #[test]
+    fn sanitize() {
+        const CASES: [(&str, &str); 14] = [
+            ("<p>Hello</p>", "<p>Hello</p>"),
+            ("<p onclick=\"alert(1)\">Hello</p>", "<p>Hello</p>"),
+            ("<script>alert(1)</script><p>Hello</p>", "<p>Hello</p>"),
+            ("<blink>Hello</blink>", "Hello"),
+            ("<a href=\"javascript:alert(1)\">Link</a>", "<a>Link</a>"),
+            ("<a href=\"java\tscript:alert(1)\">Link</a>", "<a>Link</a>"),
+            (
+                "<a href=\"&#106;avascript:alert(1)\">Link</a>",
+                "<a>Link</a>",
+            ),
+            (
+                "<a href=\"https://example.com\" style=\"color: red\">Link</a>",
+                "<a href=\"https://example.com\">Link</a>",
+            ),
+            (
+                "<!-- Comment --><img src=\"./a.png\" alt=\"A\">",
+                "<img src=\"./a.png\" alt=\"A\">",
+            ),
+            (
+                "<title><img src=x onerror=alert(1)></title><p>Hello</p>",
+                "<p>Hello</p>",
+            ),
+            (
+                "<xmp><img src=x onerror=alert(1)></xmp><p>Hello</p>",
+                "<p>Hello</p>",
+            ),
+            (
+                "<noembed><img src=x onerror=alert(1)></noembed><p>Hello</p>",
+                "<p>Hello</p>",
+            ),
+            (
+                "<noframes><img src=x onerror=alert(1)></noframes><p>Hello</p>",
+                "<p>Hello</p>",
+            ),
+            (
+                "<p>Hello</p><plaintext><img src=x onerror=alert(1)>",
+                "<p>Hello</p>",
+            ),
+        ];
+
+        let config = crate::config::SanitizeConfig::default();
+        let tags: HashSet<&str> = config.tags.iter().map(String::as_str).collect();
+        let attributes: HashSet<&str> = config.attributes.iter().map(String::as_str).collect();
+
+        for (input, expected) in CASES {
+            let result = super::sanitize(input, &tags, &attributes).unwrap();
+            assert_eq!(
+                result, expected,
+                "\nsanitize({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn is_safe_url() {
+        const CASES: [(&str, bool); 9] = [
+            ("https://example.com", true),
+            ("mailto:someone@example.com", true),
+            ("/blog", true),
+            ("./page?a=1&b=2", true),
+            ("#section", true),
+            ("javascript:alert(1)", false),
+            (" JavaScript:alert(1)", false),
+            ("data:text/html,<script>", false),
+            ("&#x6A;avascript:alert(1)", false),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::is_safe_url(input);
+            assert_eq!(
+                result, expected,
+                "\nis_safe_url({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -5,6 +5,7 @@
 use grass::Options;
 
 use super::{Entry, Error};
+use crate::util::url::UrlPath;
 
 /// SCSS compiler.
 pub(super) struct Compiler<'o> {
@@ -31,12 +32,9 @@ impl Compiler<'_> {
             })?;
 
             // Change extension to `css`
-            let url = entry
-                .url
-                .rsplit_once('.')
-                .filter(|(_, extension)| ["css", "scss"].contains(extension))
-                .map(|(stem, _)| [stem, "css"].join("."))
-                .unwrap_or([entry.url.as_str(), "css"].join("."));
+            let url = UrlPath::from(entry.url.as_str())
+                .with_extension("css")
+                .to_string();
 
             return Ok(Entry {
                 content: Some(content),
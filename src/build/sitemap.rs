@@ -1,10 +1,13 @@
 //! Generate a sitemap.
 
-use chrono::{DateTime, Utc};
 use quick_xml::se::Serializer;
 use serde::Serialize;
 
 use super::{Config, Entry, EntrySitemap, Error};
+use crate::util::{
+    date_time::{parse_offset, DateTime},
+    url::normalize_url,
+};
 
 /// Preamble of the XML file.
 const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>";
@@ -33,18 +36,30 @@ struct SitemapUrl {
 /// Generate a sitemap from page entries.
 ///
 /// The generated file follows the [sitemap protocol](https://www.sitemaps.org/protocol.html).
+///
+/// Documents (e.g. PDF files) are also listed if `sitemap.documents` is
+/// enabled.
 pub(super) fn create_sitemap_entries(
     entries: impl Iterator<Item = Result<Entry, Error>>,
     config: &Config,
 ) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
     let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
 
+    // The time zone is checked when validating the configuration
+    let timezone = config
+        .timezone
+        .as_ref()
+        .and_then(|timezone| parse_offset(timezone).ok());
+
     // Sitemap is opt-in
     if let Some(sitemap_config) = config.sitemap.as_ref() {
         let urlset: Vec<SitemapUrl> =
             entries.iter().try_fold(Vec::new(), |mut urlset, entry| {
-                // Generate sitemap only for pages
-                if entry.format != "html" {
+                // Generate sitemap only for pages, and documents if enabled
+                let is_document =
+                    sitemap_config.documents && config.documents.formats.contains(&entry.format);
+
+                if entry.format != "html" && !is_document {
                     return Ok(urlset);
                 }
 
@@ -80,23 +95,28 @@ pub(super) fn create_sitemap_entries(
 
                 // Fallback to defaults for unspecified fields
                 let sitemap_url = SitemapUrl {
-                    loc: format!(
+                    loc: normalize_url(format!(
                         "{}{}{}",
                         sitemap_config.url_prefix, config.base_url, entry.url
-                    ),
+                    )),
                     lastmod: sitemap_url
                         .lastmod
-                        .or_else(|| entry.data.as_ref().and_then(|data| data.date.to_owned()))
                         .or_else(|| {
                             entry
-                                .input_file
+                                .data
                                 .as_ref()
-                                .and_then(|dir_entry| dir_entry.metadata().ok())
-                                .and_then(|metadata| metadata.modified().ok())
+                                .and_then(|data| data.updated.as_ref().or(data.date.as_ref()))
                                 .map(|date| {
-                                    let date: DateTime<Utc> = date.into();
-                                    format!("{}", date.format("%+"))
+                                    // Sitemaps require W3C Datetime dates
+                                    DateTime::parse_in_timezone(date, timezone)
+                                        .map(|date| date.to_rfc3339())
+                                        .unwrap_or_else(|_| date.to_owned())
                                 })
+                        })
+                        .or_else(|| {
+                            entry
+                                .modified(config)
+                                .map(|date| DateTime::from(date).to_timezone(timezone).to_rfc3339())
                         }),
                     changefreq: sitemap_url
                         .changefreq
@@ -0,0 +1,33 @@
+//! Handle symbolic links in input directories.
+
+/// Policy for symbolic links found in input directories.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum SymlinkPolicy {
+    /// Follow symbolic links, and process their targets like input files.
+    ///
+    /// Links to an ancestor directory are skipped with a warning.
+    Follow,
+
+    /// Ignore symbolic links.
+    #[default]
+    Skip,
+
+    /// Fail the build if a symbolic link is found.
+    Error,
+}
+
+impl std::str::FromStr for SymlinkPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "follow" => Ok(Self::Follow),
+            "skip" => Ok(Self::Skip),
+            "error" => Ok(Self::Error),
+            _ => Err(anyhow::anyhow!(
+                "Unknown symlink policy {:?} (expected \"follow\", \"skip\" or \"error\")",
+                value
+            )),
+        }
+    }
+}
@@ -2,45 +2,198 @@
 //!
 //! This module uses [`syntect`] under the hood.
 
-use syntect::html::{css_for_theme_with_class_style, ClassStyle};
+use syntect::{
+    highlighting::ThemeSet,
+    html::{css_for_theme_with_class_style, ClassStyle},
+    parsing::SyntaxSet,
+};
 
 use super::{Config, Entry, Error};
 
 /// Create CSS stylesheet entries for syntax highlighting.
 ///
-/// This function reads `syntax_highlight_stylesheets` in the configuration, and
-/// generates CSS files corresponding to specified themes. The CSS files will be
-/// written at specified URLs.
+/// This function reads `syntax_highlight.stylesheets` in the configuration,
+/// and generates CSS files corresponding to specified themes. If a dark theme
+/// is specified, its rules apply when the user prefers a dark color scheme, or
+/// when the root element has a `data-theme="dark"` attribute (see
+/// [`super::color_scheme::theme_attribute`]). The CSS files will be written at
+/// specified URLs.
 pub(super) fn create_stylesheet_entries<'config>(
     config: &'config Config,
 ) -> impl Iterator<Item = Result<Entry, Error>> + 'config {
-    config.syntax_highlight.stylesheets.iter().map(|entry| {
-        create_css(&entry.theme, &entry.prefix)
-            .map_err(|error| Error::CreateSyntaxHighlightStylesheet {
-                source: anyhow::anyhow!(error),
-            })
-            .and_then(move |content| {
-                content.ok_or_else(|| Error::CreateSyntaxHighlightStylesheet {
-                    source: anyhow::anyhow!("Syntax highlight theme {:?} not found", entry.theme)
-                        .context(format!("Available themes: {:?}", get_themes())),
-                })
-            })
-            .map(|content| {
-                // The produced stylesheet might contain invalid characters
-                // See <https://github.com/trishume/syntect/issues/308>
-                escape_css(content)
-            })
+    // Themes are only loaded if there is a stylesheet to create
+    let theme_set = std::cell::OnceCell::new();
+
+    config
+        .syntax_highlight
+        .stylesheets
+        .iter()
+        .map(move |entry| {
+            let theme_set = match theme_set.get() {
+                Some(theme_set) => theme_set,
+                None => {
+                    let _ = theme_set.set(load_theme_set(config)?);
+                    theme_set.get().unwrap()
+                },
+            };
+
+            create_themes_css(
+                theme_set,
+                &entry.theme,
+                entry.dark_theme.as_deref(),
+                &entry.prefix,
+                super::color_scheme::theme_attribute(config),
+            )
             .map(|content| Entry {
                 content: Some(content),
                 url: entry.url.to_owned(),
                 format: "css".to_owned(),
                 ..Default::default()
             })
+        })
+}
+
+/// Create a CSS string for a light theme and an optional dark theme.
+///
+/// Rules of the dark theme apply when the user prefers a dark color scheme,
+/// unless the root element has the attribute `{attribute}="light"`. They also
+/// apply when the root element has the attribute `{attribute}="dark"`.
+pub(super) fn create_themes_css(
+    theme_set: &ThemeSet,
+    theme: &str,
+    dark_theme: Option<&str>,
+    prefix: &str,
+    attribute: &str,
+) -> Result<String, Error> {
+    let create_theme_css = |theme: &str| {
+        create_css(theme_set, theme, prefix)
+            .map_err(|error| Error::CreateSyntaxHighlightStylesheet {
+                source: anyhow::anyhow!(error),
+            })
+            .and_then(|content| {
+                content.ok_or_else(|| Error::CreateSyntaxHighlightStylesheet {
+                    source: anyhow::anyhow!("Syntax highlight theme {:?} not found", theme)
+                        .context(format!("Available themes: {:?}", get_themes(theme_set))),
+                })
+            })
+    };
+
+    let mut content = create_theme_css(theme)?;
+
+    if let Some(dark_theme) = dark_theme {
+        let dark_content = create_theme_css(dark_theme)?;
+        content.push_str(&format!(
+            "\n@media (prefers-color-scheme: dark) {{\n{}}}\n\n{}",
+            scope_css(
+                &dark_content,
+                &format!(":root:not([{attribute}=\"light\"])")
+            ),
+            scope_css(&dark_content, &format!(":root[{attribute}=\"dark\"]"))
+        ));
+    }
+
+    // The produced stylesheet might contain invalid characters
+    // See <https://github.com/trishume/syntect/issues/308>
+    Ok(escape_css(content))
+}
+
+/// Link syntax highlight CSS stylesheets in a [`Entry`].
+///
+/// This function inserts a `<link rel="stylesheet">` element at the end of the
+/// `<head>` element for each stylesheet with `link` enabled in the
+/// configuration.
+pub(super) fn link_stylesheet_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
+    let links: String = config
+        .syntax_highlight
+        .stylesheets
+        .iter()
+        .filter(|stylesheet| stylesheet.link)
+        .map(|stylesheet| {
+            // Absolute URLs are made relative later if `relative_urls` is enabled
+            let href = if config.relative_urls {
+                stylesheet.url.to_owned()
+            } else {
+                format!("{}{}", config.base_url, stylesheet.url)
+            };
+            format!(
+                "<link rel=\"stylesheet\" href=\"{}\">",
+                href.replace('&', "&amp;").replace('"', "&quot;")
+            )
+        })
+        .collect();
+
+    if links.is_empty() {
+        return Ok(entry);
+    }
+
+    let Some(content) = entry.content.as_ref() else {
+        return Ok(entry);
+    };
+
+    let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("head", |element| {
+            element.append(&links, lol_html::html_content::ContentType::Html);
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
     })
+    .map_err(|error| Error::LinkSyntaxHighlightStylesheet {
+        input_path: entry.input_path_buf(),
+        source: error.into(),
+    })?;
+
+    Ok(Entry {
+        content: Some(content),
+        ..entry
+    })
+}
+
+/// Load syntax definitions.
+///
+/// This function loads the default syntax definitions of [`syntect`], extended
+/// with `.sublime-syntax` files from `syntax_highlight.syntaxes_dir`.
+pub(super) fn load_syntax_set(config: &Config) -> Result<SyntaxSet, Error> {
+    let Some(syntaxes_dir) = config.syntax_highlight.syntaxes_dir.as_ref() else {
+        return Ok(SyntaxSet::load_defaults_newlines());
+    };
+
+    let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+
+    builder
+        .add_from_folder(syntaxes_dir, true)
+        .map_err(|error| Error::LoadSyntaxHighlight {
+            source: anyhow::anyhow!(error)
+                .context(format!("While loading syntaxes from {:?}", syntaxes_dir)),
+        })?;
+
+    Ok(builder.build())
+}
+
+/// Load themes.
+///
+/// This function loads the default themes of [`syntect`], extended with
+/// `.tmTheme` files from `syntax_highlight.themes_dir`.
+pub(super) fn load_theme_set(config: &Config) -> Result<ThemeSet, Error> {
+    let mut theme_set = ThemeSet::load_defaults();
+
+    if let Some(themes_dir) = config.syntax_highlight.themes_dir.as_ref() {
+        theme_set
+            .add_from_folder(themes_dir)
+            .map_err(|error| Error::LoadSyntaxHighlight {
+                source: anyhow::anyhow!(error)
+                    .context(format!("While loading themes from {:?}", themes_dir)),
+            })?;
+    }
+
+    Ok(theme_set)
 }
 
 /// Create a CSS string for syntax highlighting.
-fn create_css<ST, SP>(theme_key: ST, prefix: SP) -> Result<Option<String>, syntect::Error>
+fn create_css<ST, SP>(
+    theme_set: &ThemeSet,
+    theme_key: ST,
+    prefix: SP,
+) -> Result<Option<String>, syntect::Error>
 where
     ST: AsRef<str>,
     SP: AsRef<str>,
@@ -52,7 +205,7 @@ where
     // at runtime. Therefore, we use `static_lifetime()` as a workaround.
     let prefix = unsafe { crate::util::r#unsafe::static_lifetime(prefix.as_ref()) };
 
-    syntect::highlighting::ThemeSet::load_defaults()
+    theme_set
         .themes
         .get(theme_key)
         .map(|theme| css_for_theme_with_class_style(theme, ClassStyle::SpacedPrefixed { prefix }))
@@ -60,11 +213,28 @@ where
 }
 
 /// Get the list of themes for syntax highlighting.
-fn get_themes() -> Vec<String> {
-    syntect::highlighting::ThemeSet::load_defaults()
-        .themes
-        .keys()
-        .map(|v| v.to_owned())
+fn get_themes(theme_set: &ThemeSet) -> Vec<String> {
+    theme_set.themes.keys().map(|v| v.to_owned()).collect()
+}
+
+/// Prefix the selectors of the rules of a CSS string generated by [`syntect`]
+/// with a scope (e.g. `:root[data-theme="dark"]`).
+///
+/// Selectors of [`syntect`] are written on a single line, ending with `{`.
+fn scope_css(content: &str, scope: &str) -> String {
+    content
+        .lines()
+        .map(|line| match line.strip_suffix('{') {
+            Some(selectors) if !line.starts_with('@') => {
+                let selectors: Vec<_> = selectors
+                    .trim()
+                    .split(',')
+                    .map(|selector| format!("{scope} {}", selector.trim()))
+                    .collect();
+                format!("{} {{\n", selectors.join(", "))
+            },
+            _ => format!("{line}\n"),
+        })
         .collect()
 }
 
@@ -74,3 +244,29 @@ fn get_themes() -> Vec<String> {
 fn escape_css(content: String) -> String {
     content.replace("c++", "c\\+\\+")
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn scope_css() {
+        const CASES: [(&str, &str); 2] = [
+            (
+                "/*\n * theme \"a\" generated by syntect\n */\n\n.code {\n color: #ffffff;\n}\n",
+                "/*\n * theme \"a\" generated by syntect\n */\n\n:root .code {\n color: \
+                 #ffffff;\n}\n",
+            ),
+            (
+                ".comment, .string .quoted {\n font-style: italic;\n}\n",
+                ":root .comment, :root .string .quoted {\n font-style: italic;\n}\n",
+            ),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::scope_css(input, ":root");
+            assert_eq!(
+                result, expected,
+                "\nscope_css({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Compile Tailwind CSS stylesheets.
+//!
+//! This module runs the standalone executable of Tailwind CSS, which does not
+//! require Node.js. The content files are given with the `--content` option
+//! of Tailwind CSS v3.
+
+use std::{path::Path, process::Command};
+
+use super::{Config, Entry, Error};
+use crate::{config::TailwindConfig, util::command::run};
+
+/// Compile the stylesheets listed in `tailwind.stylesheets`.
+///
+/// Rendered pages are written to a temporary directory, which is given as
+/// content to Tailwind CSS, so that only the utility classes used by the site
+/// are generated. If `render` is `false`, stylesheets are left unchanged,
+/// since pages are not rendered.
+pub(super) fn compile_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+    render: bool,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let mut entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    let Some(tailwind_config) = config.tailwind.as_ref().filter(|_| render) else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    // The directory is removed when dropped
+    let work_dir = tempfile::Builder::new()
+        .prefix("vitrine-tailwind-")
+        .tempdir()
+        .map_err(|error| Error::CompileTailwind {
+            input_path: None,
+            source: anyhow::anyhow!(error).context("While creating a working directory"),
+        })?;
+
+    compile_stylesheets(&mut entries, tailwind_config, work_dir.path())?;
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Compile the stylesheets in place, using a working directory.
+fn compile_stylesheets(
+    entries: &mut [Entry],
+    tailwind_config: &TailwindConfig,
+    work_dir: &Path,
+) -> Result<(), Error> {
+    let is_stylesheet =
+        |entry: &Entry| entry.format == "css" && tailwind_config.stylesheets.contains(&entry.url);
+
+    if !entries.iter().any(is_stylesheet) {
+        tracing::warn!(
+            "Tailwind CSS: no stylesheet found among {:?}",
+            tailwind_config.stylesheets
+        );
+        return Ok(());
+    }
+
+    let pages_dir = work_dir.join("pages");
+
+    write_pages(entries, &pages_dir).map_err(|error| Error::CompileTailwind {
+        input_path: None,
+        source: error.context("While writing rendered pages"),
+    })?;
+
+    let content = pages_dir.join("*.html");
+
+    for entry in entries.iter_mut().filter(|entry| is_stylesheet(entry)) {
+        let Some(input) = entry.content.as_ref() else {
+            continue;
+        };
+
+        let output = compile(input, &content, tailwind_config, work_dir).map_err(|error| {
+            Error::CompileTailwind {
+                input_path: entry.input_path_buf(),
+                source: error,
+            }
+        })?;
+
+        entry.content = Some(output);
+    }
+
+    Ok(())
+}
+
+/// Write the content of HTML pages to a directory, one file per page.
+fn write_pages(entries: &[Entry], pages_dir: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(pages_dir)?;
+
+    for (index, content) in entries
+        .iter()
+        .filter(|entry| entry.format == "html")
+        .filter_map(|entry| entry.content.as_ref())
+        .enumerate()
+    {
+        std::fs::write(pages_dir.join(format!("{index}.html")), content)?;
+    }
+
+    Ok(())
+}
+
+/// Compile a stylesheet with Tailwind CSS, using the classes found in the
+/// files matching `content`.
+fn compile(
+    input: &str,
+    content: &Path,
+    tailwind_config: &TailwindConfig,
+    work_dir: &Path,
+) -> anyhow::Result<String> {
+    let input_path = work_dir.join("input.css");
+    let output_path = work_dir.join("output.css");
+
+    std::fs::write(&input_path, input)?;
+
+    let mut command = Command::new(&tailwind_config.command);
+
+    command
+        .arg("--input")
+        .arg(&input_path)
+        .arg("--output")
+        .arg(&output_path)
+        .arg("--content")
+        .arg(content);
+
+    if let Some(config_file) = tailwind_config.config_file.as_ref() {
+        command.arg("--config").arg(config_file);
+    }
+
+    run(&mut command, None)?;
+
+    Ok(std::fs::read_to_string(&output_path)?)
+}
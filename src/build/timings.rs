@@ -0,0 +1,86 @@
+//! Measure the duration of build tasks.
+
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+/// Durations of build tasks.
+///
+/// Since build tasks are chained lazily, a task may run while another task is
+/// measured (e.g. a task that collects all entries runs the previous tasks).
+/// The duration of such nested tasks is only counted once, for the nested task.
+#[derive(Debug, Default)]
+pub(super) struct Timings {
+    /// Total duration of each task, in the order tasks first complete.
+    durations: RefCell<Vec<(&'static str, Duration)>>,
+
+    /// Duration of nested tasks, for each task being measured.
+    nested: RefCell<Vec<Duration>>,
+}
+
+impl Timings {
+    /// Run a function, and add its duration to a task.
+    pub(super) fn measure<T, F>(&self, task: &'static str, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        self.nested.borrow_mut().push(Duration::ZERO);
+
+        let start_time = Instant::now();
+        let result = f();
+        let elapsed = start_time.elapsed();
+
+        let nested = {
+            let mut nested = self.nested.borrow_mut();
+            let duration = nested.pop().unwrap_or_default();
+            if let Some(parent) = nested.last_mut() {
+                *parent += elapsed;
+            }
+            duration
+        };
+
+        let duration = elapsed.saturating_sub(nested);
+
+        let mut durations = self.durations.borrow_mut();
+
+        match durations.iter_mut().find(|(name, _)| *name == task) {
+            Some((_, total)) => *total += duration,
+            None => durations.push((task, duration)),
+        }
+
+        result
+    }
+
+    /// Return the duration of each task, in the order tasks first complete.
+    pub(super) fn into_durations(self) -> Vec<(&'static str, Duration)> {
+        self.durations.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    #[test]
+    fn measure() {
+        let timings = super::Timings::default();
+
+        let result = timings.measure("outer", || {
+            std::thread::sleep(Duration::from_millis(10));
+            timings.measure("inner", || std::thread::sleep(Duration::from_millis(20)));
+            42
+        });
+
+        assert_eq!(result, 42);
+
+        let durations = timings.into_durations();
+
+        assert_eq!(
+            durations.iter().map(|(task, _)| *task).collect::<Vec<_>>(),
+            ["inner", "outer"]
+        );
+        assert!(durations[0].1 >= Duration::from_millis(20));
+        assert!(durations[1].1 >= Duration::from_millis(10));
+    }
+}
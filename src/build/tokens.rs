@@ -0,0 +1,208 @@
+//! Replace tokens in text outputs.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use super::{Config, Entry, Error};
+use crate::util::{date_time::DateTime, html};
+
+/// Name of the token replaced by the date of the build.
+const BUILD_DATE_TOKEN: &str = "BUILD_DATE";
+
+/// Token replacer.
+pub(super) struct Replacer {
+    /// Delimiter before the name of a token.
+    prefix: String,
+
+    /// Delimiter after the name of a token.
+    suffix: String,
+
+    /// Values of tokens, keyed by name.
+    values: HashMap<String, String>,
+}
+
+impl Replacer {
+    /// Create a token replacer from the configuration.
+    ///
+    /// Returns `None` if token substitution is not configured.
+    pub(super) fn new(config: &Config) -> Result<Option<Self>, Error> {
+        let Some(tokens_config) = config.tokens.as_ref() else {
+            return Ok(None);
+        };
+
+        let build_date = DateTime::from(match config.source_date_epoch {
+            Some(epoch) => SystemTime::UNIX_EPOCH + Duration::from_secs(epoch),
+            None => SystemTime::now(),
+        });
+
+        let mut values = HashMap::from([(BUILD_DATE_TOKEN.to_owned(), build_date.to_rfc3339())]);
+
+        for name in tokens_config.env.iter() {
+            let value = std::env::var(name).map_err(|error| Error::ReplaceTokens {
+                source: anyhow::anyhow!(error)
+                    .context(format!("While reading environment variable {name:?}")),
+            })?;
+            values.insert(name.to_owned(), value);
+        }
+
+        // Values of the configuration take precedence
+        values.extend(
+            tokens_config
+                .values
+                .iter()
+                .map(|(name, value)| (name.to_owned(), value.to_owned())),
+        );
+
+        Ok(Some(Self {
+            prefix: tokens_config.prefix.to_owned(),
+            suffix: tokens_config.suffix.to_owned(),
+            values,
+        }))
+    }
+
+    /// Replace the tokens of an entry by their values, escaped according to
+    /// the format of the entry.
+    ///
+    /// Unknown tokens are kept and reported.
+    pub(super) fn replace_entry(&self, entry: Entry) -> Result<Entry, Error> {
+        let Some(content) = entry.content.as_ref() else {
+            return Ok(entry);
+        };
+
+        if !content.contains(&self.prefix) {
+            return Ok(entry);
+        }
+
+        let content = replace_tokens(content, &self.prefix, &self.suffix, |name| {
+            let value = self.values.get(name);
+            if value.is_none() {
+                tracing::warn!("Unknown token {:?} in {:?}", name, entry.url);
+            }
+            value.map(|value| escape_value(value, &entry.format))
+        });
+
+        Ok(Entry {
+            content: Some(content),
+            ..entry
+        })
+    }
+}
+
+/// Replace the tokens of a content (e.g. `%%VERSION%%`) by the values
+/// returned by `lookup`.
+///
+/// Names of tokens are made of ASCII letters, digits and underscores. Tokens
+/// preceded by a backslash, and tokens for which `lookup` returns `None`, are
+/// kept (without the backslash).
+fn replace_tokens<F>(content: &str, prefix: &str, suffix: &str, mut lookup: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(index) = rest.find(prefix) {
+        let (before, after) = rest.split_at(index);
+        let after = &after[prefix.len()..];
+
+        let name = after.find(suffix).map(|end| &after[..end]).filter(|name| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+
+        let Some(name) = name else {
+            // Not a token, e.g. a prefix without suffix
+            result.push_str(before);
+            result.push_str(prefix);
+            rest = after;
+            continue;
+        };
+
+        rest = &after[name.len() + suffix.len()..];
+
+        // Escaped tokens are kept, without the backslash
+        let (before, value) = match before.strip_suffix('\\') {
+            Some(before) => (before, None),
+            None => (before, lookup(name)),
+        };
+
+        result.push_str(before);
+
+        match value {
+            Some(value) => result.push_str(&value),
+            None => {
+                result.push_str(prefix);
+                result.push_str(name);
+                result.push_str(suffix);
+            },
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Escape the value of a token for a given format.
+///
+/// Values are escaped as HTML text in HTML and XML documents, and as string
+/// contents in JSON and JavaScript files. Other formats are not escaped.
+fn escape_value(value: &str, format: &str) -> String {
+    match format {
+        "email" | "fragment" | "html" | "svg" | "xml" => html::escape(value),
+        "js" | "json" => {
+            let value = serde_json::Value::String(value.to_owned()).to_string();
+            value[1..value.len() - 1].to_owned()
+        },
+        _ => value.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn replace_tokens() {
+        const CASES: [(&str, &str); 7] = [
+            ("v%%VERSION%%", "v1.0"),
+            ("%%VERSION%% %%VERSION%%", "1.0 1.0"),
+            ("\\%%VERSION%% %%VERSION%%", "%%VERSION%% 1.0"),
+            ("%%UNKNOWN%%", "%%UNKNOWN%%"),
+            ("width: 50%% and %%VERSION%%", "width: 50%% and 1.0"),
+            ("%% VERSION %%", "%% VERSION %%"),
+            ("C:\\%%", "C:\\%%"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::replace_tokens(input, "%%", "%%", |name| {
+                (name == "VERSION").then(|| "1.0".to_owned())
+            });
+            assert_eq!(
+                result, expected,
+                "\nreplace_tokens({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn escape_value() {
+        const CASES: [(&str, &str, &str); 4] = [
+            (
+                "<b>\"a\" & b</b>",
+                "html",
+                "&lt;b&gt;&quot;a&quot; &amp; b&lt;/b&gt;",
+            ),
+            ("\"a\"\n", "json", "\\\"a\\\"\\n"),
+            ("\"a\"", "js", "\\\"a\\\""),
+            ("<b>", "css", "<b>"),
+        ];
+
+        for (value, format, expected) in CASES {
+            let result = super::escape_value(value, format);
+            assert_eq!(
+                result, expected,
+                "\nescape_value({value:?}, {format:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
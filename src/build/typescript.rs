@@ -1,7 +1,8 @@
-//! Compile TypeScript code.
+//! Compile TypeScript and JSX code.
 //!
 //! This module uses [`swc_core`] under the hood.
 
+use base64::Engine;
 use swc_core::{
     common::{
         comments::SingleThreadedComments, errors::Handler, sync::Lrc, FileName, Mark, SourceMap,
@@ -9,46 +10,43 @@ use swc_core::{
     },
     ecma::{
         codegen::{text_writer::JsWriter, Emitter},
-        parser::{lexer::Lexer, Parser, StringInput, Syntax, TsSyntax},
+        parser::{lexer::Lexer, EsSyntax, Parser, StringInput, Syntax, TsSyntax},
         transforms::{
             base::{fixer::fixer, hygiene::hygiene, resolver},
+            react::{self, react},
             typescript::strip,
         },
         visit::FoldWith,
     },
 };
 
-use super::{Entry, Error};
+use super::{Config, Entry, Error};
+use crate::{config::TypescriptConfig, util::url::UrlPath};
 
-/// Compile TypeScript content of a [`Entry`] to JavaScript content.
+/// Compile TypeScript or JSX content of a [`Entry`] to JavaScript content.
 ///
 /// This function transpiles the TypeScript code to JavaScript in the `content`
 /// property. The `format` property is set to `js`.
-pub(super) fn compile_entry(entry: Entry) -> Result<Entry, Error> {
+pub(super) fn compile_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
     let Some(content) = entry.content else {
         return Ok(entry);
     };
 
-    let tsx = match entry.format.as_str() {
-        "tsx" => true,
-        _ => false,
-    };
-
-    let content = compile(content, tsx).map_err(|error| Error::CompileTypescript {
-        input_path: entry
-            .input_file
-            .as_ref()
-            .map(|entry| entry.path().to_owned()),
-        source: error,
-    })?;
+    let content =
+        compile(content, &entry.format, &entry.url, &config.typescript).map_err(|error| {
+            Error::CompileTypescript {
+                input_path: entry
+                    .input_file
+                    .as_ref()
+                    .map(|entry| entry.path().to_owned()),
+                source: error,
+            }
+        })?;
 
     // Change extension to `js`
-    let url = entry
-        .url
-        .rsplit_once('.')
-        .filter(|(_, extension)| ["js", "jsx", "ts", "tsx"].contains(extension))
-        .map(|(stem, _)| [stem, "js"].join("."))
-        .unwrap_or([entry.url.as_str(), "js"].join("."));
+    let url = UrlPath::from(entry.url.as_str())
+        .with_extension("js")
+        .to_string();
 
     Ok(Entry {
         content: Some(content),
@@ -58,28 +56,48 @@ pub(super) fn compile_entry(entry: Entry) -> Result<Entry, Error> {
     })
 }
 
-/// Compile a TypeScript content to a JavaScript content.
+/// Compile a TypeScript (`ts` or `tsx`) or JSX (`jsx`) content to a
+/// JavaScript content.
+///
+/// The `name` of the script (e.g. its URL) is used in source maps.
 ///
 /// <https://github.com/swc-project/swc/blob/main/crates/swc_ecma_transforms_typescript/examples
 /// /ts_to_js.rs>
-fn compile<S>(input: S, tsx: bool) -> anyhow::Result<String>
+fn compile<S>(
+    input: S,
+    format: &str,
+    name: &str,
+    typescript_config: &TypescriptConfig,
+) -> anyhow::Result<String>
 where
     S: AsRef<str>,
 {
     let input = input.as_ref();
 
+    let is_typescript = matches!(format, "ts" | "tsx");
+    let is_jsx = matches!(format, "jsx" | "tsx");
+
     let cm: Lrc<SourceMap> = Default::default();
     let handler = Handler::with_emitter_writer(Box::new(std::io::stderr()), Some(cm.clone()));
 
-    let fm = cm.new_source_file(FileName::Anon, input.into());
+    let fm = cm.new_source_file(FileName::Custom(name.to_owned()), input.into());
 
     let comments = SingleThreadedComments::default();
 
-    let lexer = Lexer::new(
+    let syntax = if is_typescript {
         Syntax::Typescript(TsSyntax {
-            tsx,
+            tsx: is_jsx,
+            ..Default::default()
+        })
+    } else {
+        Syntax::Es(EsSyntax {
+            jsx: is_jsx,
             ..Default::default()
-        }),
+        })
+    };
+
+    let lexer = Lexer::new(
+        syntax,
         Default::default(),
         StringInput::from(&*fm),
         Some(&comments),
@@ -104,10 +122,30 @@ where
         // as it might produce runtime declarations.
 
         // Conduct identifier scope analysis
-        let program = program.fold_with(&mut resolver(unresolved_mark, top_level_mark, true));
+        let mut program = program.fold_with(&mut resolver(unresolved_mark, top_level_mark, true));
+
+        // Transform JSX elements to function calls, before removing types, so
+        // that imports used by JSX elements (e.g. `React`) are kept
+        if is_jsx {
+            let options = react::Options {
+                pragma: Some(Lrc::new(typescript_config.jsx_factory.to_owned())),
+                pragma_frag: Some(Lrc::new(typescript_config.jsx_fragment.to_owned())),
+                ..Default::default()
+            };
+
+            program = program.fold_with(&mut react(
+                cm.clone(),
+                Some(&comments),
+                options,
+                top_level_mark,
+                unresolved_mark,
+            ));
+        }
 
         // Remove typescript types
-        let program = program.fold_with(&mut strip(top_level_mark));
+        if is_typescript {
+            program = program.fold_with(&mut strip(top_level_mark));
+        }
 
         // Fix up any identifiers with the same name, but different contexts
         let program = program.fold_with(&mut hygiene());
@@ -116,12 +154,18 @@ where
         let program = program.fold_with(&mut fixer(Some(&comments)));
 
         let mut buf = vec![];
+        let mut mappings = vec![];
         {
             let mut emitter = Emitter {
                 cfg: swc_core::ecma::codegen::Config::default(),
                 cm: cm.clone(),
                 comments: Some(&comments),
-                wr: JsWriter::new(cm.clone(), "\n", &mut buf, None),
+                wr: JsWriter::new(
+                    cm.clone(),
+                    "\n",
+                    &mut buf,
+                    typescript_config.source_maps.then_some(&mut mappings),
+                ),
             };
 
             emitter
@@ -129,18 +173,34 @@ where
                 .map_err(|error| anyhow::anyhow!(error))?;
         }
 
-        String::from_utf8(buf).map_err(|error| error.into())
+        let mut output = String::from_utf8(buf)?;
+
+        if typescript_config.source_maps {
+            let mut source_map = vec![];
+
+            cm.build_source_map(&mappings).to_writer(&mut source_map)?;
+
+            output.push_str(&format!(
+                "//# sourceMappingURL=data:application/json;base64,{}\n",
+                base64::engine::general_purpose::STANDARD.encode(source_map)
+            ));
+        }
+
+        Ok(output)
     })
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::config::TypescriptConfig;
+
     #[test]
     fn compile() {
         const CASES: [(&str, &str); 1] = [("const s: string = \"abc\";", "const s = \"abc\";\n")];
 
         for (input, expected) in CASES {
-            let result = super::compile(input, false).unwrap();
+            let result =
+                super::compile(input, "ts", "/a.ts", &TypescriptConfig::default()).unwrap();
             assert_eq!(
                 result,
                 expected.to_owned(),
@@ -148,4 +208,48 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn compile_jsx() {
+        const CASES: [(&str, &str, &str); 3] = [
+            (
+                "const a = <p>Hi</p>;",
+                "jsx",
+                "React.createElement(\"p\", null, \"Hi\")",
+            ),
+            (
+                "const a: string = <><b /></>;",
+                "tsx",
+                "React.createElement(React.Fragment, null, /*#__PURE__*/ \
+                 React.createElement(\"b\", null))",
+            ),
+            (
+                "import React from \"react\";\nexport const A = () => <i />;",
+                "tsx",
+                "import React from \"react\";",
+            ),
+        ];
+
+        for (input, format, expected) in CASES {
+            let result = super::compile(input, format, "/a", &TypescriptConfig::default()).unwrap();
+            assert!(
+                result.contains(expected),
+                "\ncompile({input:?}, {format:?}) expected {expected:?} in {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn compile_source_maps() {
+        let typescript_config = TypescriptConfig {
+            source_maps: true,
+            ..Default::default()
+        };
+
+        let result =
+            super::compile("let a: number = 1;", "ts", "/a.ts", &typescript_config).unwrap();
+
+        assert!(result.starts_with("let a = 1;\n"));
+        assert!(result.contains("//# sourceMappingURL=data:application/json;base64,"));
+    }
 }
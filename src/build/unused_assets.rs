@@ -0,0 +1,283 @@
+//! Detect unused assets.
+
+use std::{cell::RefCell, collections::HashSet};
+
+use globset::{Glob, GlobSetBuilder};
+
+use super::{
+    fragments::percent_decode,
+    url::{rewrite_css_urls, rewrite_srcset, ELEMENTS_URL_ATTRIBUTES, META_URL_SELECTOR},
+    Config, Entry, Error,
+};
+use crate::util::url::{Url, UrlPath};
+
+/// Detect assets that are not referenced by any page or stylesheet.
+///
+/// Detection is enabled by the `unused_assets` key in the configuration.
+/// Assets are input files other than pages (e.g. images, fonts, scripts). This
+/// function collects the URLs found in pages (e.g. `src` attributes, `url()`
+/// functions in styles) and stylesheets (`url()` functions and `@import`
+/// rules). Assets that are not referenced are reported, and skipped if
+/// `unused_assets.skip` is `true`.
+pub(super) fn detect_entries(
+    entries: impl Iterator<Item = Result<Entry, Error>>,
+    config: &Config,
+) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
+    let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
+
+    // Detection is opt-in
+    let Some(unused_assets_config) = config.unused_assets.as_ref() else {
+        return Ok(entries.into_iter().map(Ok));
+    };
+
+    let keep = unused_assets_config
+        .keep
+        .iter()
+        .try_fold(GlobSetBuilder::new(), |mut builder, pattern| {
+            builder.add(Glob::new(pattern)?);
+            Ok(builder)
+        })
+        .and_then(|builder| builder.build())
+        .map_err(|error| Error::DetectUnusedAssets {
+            source: error.into(),
+        })?;
+
+    // Collect the URLs referenced by pages and stylesheets
+    let mut references: HashSet<String> = HashSet::new();
+
+    for entry in entries.iter() {
+        let Some(content) = entry.content.as_ref() else {
+            continue;
+        };
+
+        let (hrefs, base) = match entry.format.as_str() {
+            "html" => {
+                let hrefs = find_html_urls(content).map_err(|error| Error::DetectUnusedAssets {
+                    source: error.context(format!("In {:?}", entry.input_path())),
+                })?;
+                let base = entry.base_dir();
+                (hrefs, base)
+            },
+            "css" => (find_css_urls(content), UrlPath::from(entry.url.as_str())),
+            _ => continue,
+        };
+
+        references.extend(
+            hrefs
+                .iter()
+                .filter_map(|href| resolve_url(href, &base, &config.base_url)),
+        );
+    }
+
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|entry| {
+            // Generated files are not assets
+            let Some(path) = entry
+                .input_path()
+                .and_then(|path| config.strip_input_dir(path))
+            else {
+                return true;
+            };
+
+            if entry.format == "html" || references.contains(&entry.url) || keep.is_match(path) {
+                return true;
+            }
+
+            tracing::warn!("Unused asset {:?}", path);
+
+            !unused_assets_config.skip
+        })
+        .collect();
+
+    Ok(entries.into_iter().map(Ok))
+}
+
+/// Find the URLs referenced in HTML code.
+fn find_html_urls<S>(content: S) -> anyhow::Result<Vec<String>>
+where
+    S: AsRef<str>,
+{
+    let urls = RefCell::new(Vec::new());
+    let style_buffer = RefCell::new(String::new());
+
+    let urls = &urls;
+    let style_buffer = &style_buffer;
+
+    let mut element_content_handlers: Vec<_> = ELEMENTS_URL_ATTRIBUTES
+        .iter()
+        .map(|(tag_name, attribute)| {
+            lol_html::element!(format!("{tag_name}[{attribute}]"), move |element| {
+                if let Some(href) = element.get_attribute(attribute) {
+                    urls.borrow_mut().push(href);
+                }
+                Ok(())
+            })
+        })
+        .collect();
+
+    element_content_handlers.extend([
+        lol_html::element!("img[srcset], source[srcset]", |element| {
+            if let Some(srcset) = element.get_attribute("srcset") {
+                rewrite_srcset(&srcset, |href| {
+                    urls.borrow_mut().push(href.to_owned());
+                    None
+                });
+            }
+            Ok(())
+        }),
+        lol_html::element!(META_URL_SELECTOR, |element| {
+            if let Some(content) = element.get_attribute("content") {
+                urls.borrow_mut().push(content);
+            }
+            Ok(())
+        }),
+        lol_html::element!("*[style]", |element| {
+            if let Some(style) = element.get_attribute("style") {
+                urls.borrow_mut().extend(find_css_urls(style));
+            }
+            Ok(())
+        }),
+        lol_html::text!("style", |text| {
+            style_buffer.borrow_mut().push_str(text.as_str());
+
+            if text.last_in_text_node() {
+                let style = style_buffer.take();
+                urls.borrow_mut().extend(find_css_urls(style));
+            }
+
+            Ok(())
+        }),
+    ]);
+
+    lol_html::rewrite_str(content.as_ref(), lol_html::RewriteStrSettings {
+        element_content_handlers,
+        ..lol_html::RewriteStrSettings::default()
+    })?;
+
+    Ok(urls.take())
+}
+
+/// Find the URLs referenced in CSS code.
+///
+/// URLs are given by `url()` functions and `@import` rules.
+fn find_css_urls<S>(content: S) -> Vec<String>
+where
+    S: AsRef<str>,
+{
+    const IMPORT: &str = "@import";
+
+    let content = content.as_ref();
+
+    let urls = RefCell::new(Vec::new());
+
+    rewrite_css_urls(content, |href| {
+        urls.borrow_mut().push(href.to_owned());
+        None
+    });
+
+    let mut urls = urls.into_inner();
+
+    // `@import "style.css"` rules without `url()`
+    let mut rest = content;
+    while let Some(index) = rest.find(IMPORT) {
+        rest = rest[index + IMPORT.len()..].trim_start();
+
+        if let Some(quote @ ('"' | '\'')) = rest.chars().next() {
+            if let Some(end) = rest[1..].find(quote) {
+                urls.push(rest[1..end + 1].to_owned());
+            }
+        }
+    }
+
+    urls
+}
+
+/// Resolve a referenced URL to an absolute path of the site.
+///
+/// Returns `None` for URLs of other sites and data URLs.
+pub(super) fn resolve_url(href: &str, base: &UrlPath, base_url: &str) -> Option<String> {
+    let href = href.trim();
+
+    // Remove the prefix of absolute URLs of the site
+    let href = if base_url.is_empty() {
+        href
+    } else {
+        href.strip_prefix(base_url)
+            .filter(|path| path.starts_with('/'))
+            .unwrap_or(href)
+    };
+
+    let url = Url::from(href);
+
+    if url.scheme.is_some() || url.authority.is_some() || url.path.as_str().is_empty() {
+        return None;
+    }
+
+    Some(percent_decode(base.join(&url.path).as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::url::UrlPath;
+
+    #[test]
+    fn find_html_urls() {
+        const CONTENT: &str = concat!(
+            "<link rel=\"stylesheet\" href=\"/style.css\">",
+            "<img src=\"a.png\" srcset=\"b.png 2x, c.png 3x\">",
+            "<div style=\"background: url('d.png')\"></div>",
+            "<style>body { background: url(e.png); }</style>",
+        );
+
+        let mut result = super::find_html_urls(CONTENT).unwrap();
+        result.sort();
+
+        assert_eq!(result, [
+            "/style.css",
+            "a.png",
+            "b.png",
+            "c.png",
+            "d.png",
+            "e.png"
+        ]);
+    }
+
+    #[test]
+    fn find_css_urls() {
+        const CONTENT: &str =
+            "@import \"base.css\";\n@import url(theme.css);\nbody { background: url(\"bg.png\"); }";
+
+        let mut result = super::find_css_urls(CONTENT);
+        result.sort();
+
+        assert_eq!(result, ["base.css", "bg.png", "theme.css"]);
+    }
+
+    #[test]
+    fn resolve_url() {
+        const CASES: [(&str, &str, &str, Option<&str>); 6] = [
+            ("a.png", "/blog/", "", Some("/blog/a.png")),
+            ("../a.png", "/css/style.css", "", Some("/a.png")),
+            ("/blog/a.png", "/", "/blog", Some("/a.png")),
+            (
+                "https://example.com/a.png",
+                "/",
+                "https://example.com",
+                Some("/a.png"),
+            ),
+            ("https://other.org/a.png", "/", "", None),
+            ("my%20image.png", "/", "", Some("/my image.png")),
+        ];
+
+        for (href, base, base_url, expected) in CASES {
+            let result = super::resolve_url(href, &UrlPath::from(base), base_url);
+            let expected = expected.map(str::to_owned);
+            assert_eq!(
+                result, expected,
+                "\nresolve_url({href:?}, {base:?}, {base_url:?}) expected {expected:?} but \
+                 received {result:?}"
+            );
+        }
+    }
+}
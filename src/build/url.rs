@@ -1,16 +1,22 @@
 //! Normalize URLs.
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     path::PathBuf,
 };
 
-use super::{Config, Entry, Error};
+use unicode_normalization::UnicodeNormalization;
+
+use super::{fragments::percent_decode, Config, Entry, Error};
+use crate::util::{
+    path::PathExt,
+    url::{Url, UrlPath},
+};
 
 /// List of elements and their attributes containing URLs.
 ///
 /// See <https://html.spec.whatwg.org/multipage/indices.html#attributes-3>.
-const ELEMENTS_URL_ATTRIBUTES: [(&str, &str); 18] = [
+pub(super) const ELEMENTS_URL_ATTRIBUTES: [(&str, &str); 18] = [
     ("blockquote", "cite"),
     ("del", "cite"),
     ("ins", "cite"),
@@ -31,6 +37,18 @@ const ELEMENTS_URL_ATTRIBUTES: [(&str, &str); 18] = [
     ("video", "src"),
 ];
 
+/// Selector of `<meta>` elements containing URLs.
+///
+/// See <https://ogp.me/> and
+/// <https://developer.x.com/en/docs/twitter-for-websites/cards/overview/markup>.
+pub(super) const META_URL_SELECTOR: &str = concat!(
+    "meta[property=\"og:image\"][content],",
+    "meta[property=\"og:image:url\"][content],",
+    "meta[property=\"og:audio\"][content],",
+    "meta[property=\"og:video\"][content],",
+    "meta[name=\"twitter:image\"][content]",
+);
+
 /// Normalize the URL of a [`Entry`].
 ///
 /// By default, the URL of a build entry is determined by the path relative to
@@ -50,14 +68,19 @@ pub(super) fn normalize_entry(entry: Entry) -> Result<Entry, Error> {
         .as_ref()
         .and_then(|data| data.url.to_owned())
         .map(|url| {
-            if url.starts_with("/") {
-                Ok(url)
-            } else {
-                Err(Error::NormalizeUrl {
+            if !url.starts_with("/") {
+                return Err(Error::NormalizeUrl {
                     input_path: entry.input_path_buf(),
                     source: anyhow::anyhow!("URL must start with /"),
-                })
+                });
             }
+
+            Url::parse(&url).map_err(|error| Error::NormalizeUrl {
+                input_path: entry.input_path_buf(),
+                source: anyhow::anyhow!(error).context(format!("Invalid URL {:?}", url)),
+            })?;
+
+            Ok(url)
         })
         .transpose()?
         .unwrap_or_else(|| normalize_url(entry.url));
@@ -65,17 +88,97 @@ pub(super) fn normalize_entry(entry: Entry) -> Result<Entry, Error> {
     Ok(Entry { url, ..entry })
 }
 
+/// Output URLs, indexed by canonical input path.
+pub(super) type Urls = HashMap<PathBuf, String>;
+
+/// Map the input files of entries to their output URLs.
+///
+/// The URLs do not include [`Config::base_url`].
+pub(super) fn map_urls(entries: &[Entry]) -> Urls {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            entry
+                .input_path()
+                .and_then(|path| path.canonicalize().ok())
+                .map(|path| (path, entry.url.to_owned()))
+        })
+        .collect()
+}
+
+/// Check that no two entries have URLs that differ only by case or Unicode
+/// normalization.
+///
+/// Such URLs are written to distinct files on case-sensitive file systems, but
+/// to the same file on macOS and Windows, and some CDNs consider them equal.
+/// The error lists the conflicting URLs and their input files.
+pub(super) fn check_collisions(entries: &[Entry]) -> Result<(), Error> {
+    // Input files of each URL, grouped by collision key
+    let mut groups: BTreeMap<String, BTreeMap<&str, Vec<String>>> = BTreeMap::new();
+
+    for entry in entries.iter() {
+        let source = entry
+            .input_path()
+            .map(|path| format!("{:?}", path))
+            .unwrap_or_else(|| "generated".to_owned());
+
+        groups
+            .entry(collision_key(&entry.url))
+            .or_default()
+            .entry(&entry.url)
+            .or_default()
+            .push(source);
+    }
+
+    let collisions: Vec<String> = groups
+        .values()
+        .filter(|urls| urls.len() > 1)
+        .map(|urls| {
+            urls.iter()
+                .map(|(url, sources)| format!("{url} ({})", sources.join(", ")))
+                .collect::<Vec<_>>()
+                .join(" vs ")
+        })
+        .collect();
+
+    if collisions.is_empty() {
+        return Ok(());
+    }
+
+    Err(Error::CheckUrlCollisions {
+        source: anyhow::anyhow!(
+            "URLs differ only by case or Unicode normalization:\n{}",
+            collisions.join("\n")
+        ),
+    })
+}
+
+/// Compute a key that is equal for URLs differing only by case or Unicode
+/// normalization.
+fn collision_key(url: &str) -> String {
+    url.nfc().collect::<String>().to_lowercase()
+}
+
 /// Replace local paths by web URLs.
 ///
 /// Source files can link to other files using local paths (e.g.
 /// `./other-page.md`). This function replaces these paths by web URLs (e.g.
 /// `/path/to/other-page`) in the HTML code. Supported attributes are specified
-/// in [`ELEMENTS_URL_ATTRIBUTES`].
+/// in [`ELEMENTS_URL_ATTRIBUTES`], in addition to `srcset` attributes and
+/// `<meta>` elements specified in [`META_URL_SELECTOR`] (e.g. `og:image`), and
+/// `url()` functions in `<style>` elements and `style` attributes.
+///
+/// If [`Config::relative_urls`] is enabled, local paths and absolute paths
+/// (e.g. `/style.css`) are replaced by URLs relative to the current page (e.g.
+/// `../style.css`) instead, and URLs of pages and directories end with
+/// `index.html` (e.g. `../about/index.html`).
 pub(super) fn rewrite_url_entries(
     entries: impl Iterator<Item = Result<Entry, Error>>,
     config: &Config,
 ) -> Result<impl Iterator<Item = Result<Entry, Error>>, Error> {
     let base_url = config.base_url.to_owned();
+    let relative_urls = config.relative_urls;
+    let input_dirs: Vec<PathBuf> = config.input_dirs().cloned().collect();
     let entries: Vec<_> = entries.collect::<Result<_, _>>()?;
 
     // Mapping from element tag names to their attributes containing URLs
@@ -95,14 +198,13 @@ pub(super) fn rewrite_url_entries(
         .join(",");
 
     // Create a mapping from absolute input paths to output URLs
-    let urls: HashMap<PathBuf, String> = entries
+    let urls = map_urls(&entries);
+
+    // URLs of pages, without trailing slash
+    let page_urls: HashSet<String> = entries
         .iter()
-        .filter_map(|entry| {
-            entry
-                .input_path()
-                .and_then(|path| path.canonicalize().ok())
-                .map(|path| (path, format!("{}{}", base_url, entry.url)))
-        })
+        .filter(|entry| entry.format == "html")
+        .map(|entry| entry.url.trim_end_matches('/').to_owned())
         .collect();
 
     let entries = entries.into_iter().map(move |entry| {
@@ -121,29 +223,164 @@ pub(super) fn rewrite_url_entries(
 
         let dir = input_path.parent().unwrap();
 
-        let content = lol_html::rewrite_str(&content, lol_html::RewriteStrSettings {
-            element_content_handlers: vec![lol_html::element!(selector, |element| {
-                let Some(attributes) = elements_url_attributes.get(element.tag_name().as_str())
-                else {
-                    return Ok(());
-                };
+        // Local paths are resolved from the directory of the page, then from the same
+        // directory in the other input directories
+        let dirs: Vec<PathBuf> = std::iter::once(dir.to_owned())
+            .chain(
+                input_dirs
+                    .iter()
+                    .find_map(|input_dir| dir.strip_prefix(input_dir).ok())
+                    .into_iter()
+                    .flat_map(|relative_dir| {
+                        input_dirs
+                            .iter()
+                            .map(move |input_dir| input_dir.join(relative_dir))
+                    }),
+            )
+            .collect();
+
+        let page_dir = entry.base_dir();
+
+        // Replace a local path by a web URL, relative to the page if `relative` is
+        // `true`
+        let rewrite_url = |href: &str, relative: bool| -> Option<String> {
+            let href = href.trim();
+
+            let local_url = Url::from(href);
+            let is_absolute = href.starts_with("/") && !href.starts_with("//");
+            let is_markdown = local_url.path.extension() == Some("md");
+
+            // Local paths are resolved from the directory of the page, absolute
+            // paths to Markdown files (e.g. `/docs/page.md`) from the input
+            // directories
+            let base_dirs = if !href.starts_with("/") && !href.contains("://") {
+                Some(&dirs)
+            } else if is_absolute && is_markdown {
+                Some(&input_dirs)
+            } else {
+                None
+            };
 
-                for attribute in attributes {
-                    if let Some(url) = element
-                        .get_attribute(attribute)
-                        .and_then(|href| {
-                            let href = href.trim();
-                            (!href.starts_with("/") && !href.contains("://"))
-                                .then(|| dir.join(href))
-                        })
-                        .and_then(|path| path.canonicalize().ok())
+            let url = base_dirs.and_then(|base_dirs| {
+                // Keep the query and the fragment (e.g. `other-page.md#intro`)
+                let path = percent_decode(local_url.path.as_str());
+
+                let url = base_dirs.iter().find_map(|dir| {
+                    dir.join_url_path(&path)
+                        .canonicalize()
+                        .ok()
                         .and_then(|path| urls.get(&path))
-                    {
-                        element.set_attribute(attribute, &url)?;
-                    }
+                });
+
+                if url.is_none() && is_markdown {
+                    tracing::warn!("Link to missing page {:?} in {:?}", href, input_path);
+                }
+
+                url.map(|url| Url {
+                    path: UrlPath::from(url.as_str()),
+                    ..local_url.to_owned()
+                })
+            });
+
+            let url = match url {
+                Some(url) => url,
+                // Absolute path
+                None if relative && is_absolute => local_url,
+                None => return None,
+            };
+
+            let url = if relative {
+                // Directories are not mapped to `index.html` from `file://`
+                let path = url.path.as_str();
+                let path = if path.ends_with('/') || page_urls.contains(path) {
+                    UrlPath::from(format!("{}/index.html", path.trim_end_matches('/')))
+                } else {
+                    url.path.to_owned()
+                };
+
+                Url {
+                    path: path.relative_to(&page_dir),
+                    ..url
+                }
+            } else {
+                Url {
+                    path: UrlPath::from(format!("{}{}", base_url, url.path)),
+                    ..url
                 }
-                Ok(())
-            })],
+            };
+
+            Some(url.to_string())
+        };
+
+        let mut style_buffer = String::new();
+
+        let content = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+            element_content_handlers: vec![
+                lol_html::element!(selector, |element| {
+                    let Some(attributes) = elements_url_attributes.get(element.tag_name().as_str())
+                    else {
+                        return Ok(());
+                    };
+
+                    for attribute in attributes {
+                        let Some(href) = element.get_attribute(attribute) else {
+                            continue;
+                        };
+
+                        if let Some(url) = rewrite_url(&href, relative_urls) {
+                            element.set_attribute(attribute, &url)?;
+                        }
+                    }
+                    Ok(())
+                }),
+                lol_html::element!("img[srcset], source[srcset]", |element| {
+                    let Some(srcset) = element.get_attribute("srcset") else {
+                        return Ok(());
+                    };
+
+                    let srcset = rewrite_srcset(&srcset, |href| rewrite_url(href, relative_urls));
+
+                    element.set_attribute("srcset", &srcset)?;
+                    Ok(())
+                }),
+                lol_html::element!(META_URL_SELECTOR, |element| {
+                    let Some(content) = element.get_attribute("content") else {
+                        return Ok(());
+                    };
+
+                    // Social media crawlers require absolute URLs
+                    if let Some(url) = rewrite_url(&content, false) {
+                        element.set_attribute("content", &url)?;
+                    }
+                    Ok(())
+                }),
+                lol_html::element!("*[style]", |element| {
+                    let Some(style) = element.get_attribute("style") else {
+                        return Ok(());
+                    };
+
+                    let style = rewrite_css_urls(&style, |href| rewrite_url(href, relative_urls));
+
+                    element.set_attribute("style", &style)?;
+                    Ok(())
+                }),
+                lol_html::text!("style", |text| {
+                    style_buffer.push_str(text.as_str());
+
+                    if text.last_in_text_node() {
+                        let style = rewrite_css_urls(&style_buffer, |href| {
+                            rewrite_url(href, relative_urls)
+                        });
+
+                        text.replace(&style, lol_html::html_content::ContentType::Html);
+                        style_buffer.clear();
+                    } else {
+                        text.remove();
+                    }
+
+                    Ok(())
+                }),
+            ],
             ..lol_html::RewriteStrSettings::default()
         })
         .map_err(|error| Error::RewriteUrl {
@@ -160,6 +397,108 @@ pub(super) fn rewrite_url_entries(
     Ok(entries)
 }
 
+/// Rewrite the URLs of `url()` functions in CSS code.
+///
+/// URLs can be unquoted (e.g. `url(image.png)`) or quoted (e.g.
+/// `url("image.png")`). URLs for which `rewrite_url` returns `None` are left
+/// unchanged. Functions whose name ends with `url` (e.g. `my-url()`) are
+/// ignored.
+pub(super) fn rewrite_css_urls<F>(css: &str, rewrite_url: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    const FUNCTION: &str = "url(";
+
+    // Lowercasing ASCII characters preserves byte offsets
+    let lowercase = css.to_ascii_lowercase();
+
+    let mut result = String::with_capacity(css.len());
+    let mut offset = 0;
+
+    while let Some(index) = lowercase[offset..]
+        .find(FUNCTION)
+        .map(|index| offset + index)
+    {
+        let (before, after) = css.split_at(index + FUNCTION.len());
+        result.push_str(&before[offset..]);
+        offset = before.len();
+
+        // The function name must not be part of a longer identifier
+        let is_identifier = css[..index]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii());
+        if is_identifier {
+            continue;
+        }
+
+        let Some(end) = after.find(')') else {
+            break;
+        };
+
+        let argument = &after[..end];
+        let trimmed = argument.trim();
+
+        let (quote, href) = match trimmed.chars().next() {
+            Some(quote @ ('"' | '\'')) if trimmed.len() >= 2 && trimmed.ends_with(quote) => {
+                (Some(quote), &trimmed[1..trimmed.len() - 1])
+            },
+            _ => (None, trimmed),
+        };
+
+        match rewrite_url(href) {
+            Some(url) => match quote {
+                Some(quote) => result.push_str(&format!("{quote}{url}{quote}")),
+                None => result.push_str(&url),
+            },
+            None => result.push_str(argument),
+        }
+
+        offset += end;
+    }
+
+    result.push_str(&css[offset..]);
+
+    result
+}
+
+/// Rewrite the URLs of a `srcset` attribute.
+///
+/// A `srcset` attribute is a comma-separated list of image candidates, each one
+/// made of a URL optionally followed by a descriptor (e.g. `image.png 2x`).
+/// URLs for which `rewrite_url` returns `None` are left unchanged. Since data
+/// URLs may contain commas, a `srcset` containing data URLs is left unchanged.
+///
+/// See <https://html.spec.whatwg.org/multipage/images.html#srcset-attributes>.
+pub(super) fn rewrite_srcset<F>(srcset: &str, rewrite_url: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if srcset.contains("data:") {
+        return srcset.to_owned();
+    }
+
+    srcset
+        .split(',')
+        .map(str::trim)
+        .filter(|candidate| !candidate.is_empty())
+        .map(|candidate| {
+            let (url, descriptor) = candidate
+                .split_once(char::is_whitespace)
+                .map(|(url, descriptor)| (url, Some(descriptor.trim())))
+                .unwrap_or((candidate, None));
+
+            let url = rewrite_url(url).unwrap_or_else(|| url.to_owned());
+
+            match descriptor {
+                Some(descriptor) => format!("{url} {descriptor}"),
+                None => url,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Normalize a URL string.
 fn normalize_url<S>(url: S) -> String
 where
@@ -167,22 +506,24 @@ where
 {
     let url = url.as_ref();
 
-    let url = if let Some((dir, file_name)) = url.rsplit_once('/') {
-        if let Some((stem, _extension)) = file_name.rsplit_once('.') {
-            if stem == "index" {
-                // `/index.{ext}` -> `/`
-                // `/dir/index.{ext}` -> `/dir`
-                if dir.is_empty() { "/" } else { dir }.to_owned()
-            } else {
-                // `/dir/page.{ext}` -> `/dir/page`
-                [dir, stem].join("/")
-            }
+    debug_assert!(url.starts_with('/'), "URL must start with /");
+
+    let url_path = UrlPath::from(url);
+
+    let url = if url_path.extension().is_none() {
+        // `/dir/page` -> `/dir/page`
+        url.to_owned()
+    } else {
+        let url_path = url_path.with_extension("");
+        if url_path.ends_with("index") {
+            // `/index.{ext}` -> `/`
+            // `/dir/index.{ext}` -> `/dir`
+            let dir = url_path.as_str().strip_suffix("/index").unwrap_or_default();
+            if dir.is_empty() { "/" } else { dir }.to_owned()
         } else {
-            // `/dir/page` -> `/dir/page`
-            url.to_owned()
+            // `/dir/page.{ext}` -> `/dir/page`
+            url_path.to_string()
         }
-    } else {
-        unreachable!("URL must start with /")
     };
 
     debug_assert!(url.starts_with("/"));
@@ -192,9 +533,28 @@ where
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn collision_key() {
+        const CASES: [(&str, &str, bool); 4] = [
+            ("/blog/Hello", "/blog/hello", true),
+            ("/caf\u{e9}", "/cafe\u{301}", true),
+            ("/CAF\u{c9}", "/cafe\u{301}", true),
+            ("/blog/hello", "/blog/hello-2", false),
+        ];
+
+        for (a, b, expected) in CASES {
+            let result = super::collision_key(a) == super::collision_key(b);
+            assert_eq!(
+                result, expected,
+                "\ncollision_key({a:?}) == collision_key({b:?}) expected {expected:?} but \
+                 received {result:?}"
+            );
+        }
+    }
+
     #[test]
     fn normalize_url() {
-        const CASES: [(&str, &str); 5] = [
+        const CASES: [(&str, &str); 7] = [
             ("/index.md", "/"),
             ("/blog.md", "/blog"),
             ("/blog/index.md", "/blog"),
@@ -211,4 +571,60 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn rewrite_srcset() {
+        const CASES: [(&str, &str); 5] = [
+            ("a.png", "/a"),
+            (
+                "data:image/png;base64,AAAA 2x",
+                "data:image/png;base64,AAAA 2x",
+            ),
+            ("a.png 1x, b.png 2x", "/a 1x, /b 2x"),
+            (
+                "a.png 480w,\n  https://c.org/b.png 800w",
+                "/a 480w, https://c.org/b.png 800w",
+            ),
+            ("a.png,b.png", "/a, /b"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::rewrite_srcset(input, |url| {
+                (!url.contains("://")).then(|| format!("/{}", url.trim_end_matches(".png")))
+            });
+            assert_eq!(
+                result, expected,
+                "\nrewrite_srcset({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rewrite_css_urls() {
+        const CASES: [(&str, &str); 5] = [
+            ("background: url(a.png)", "background: url(/a)"),
+            ("background: url( 'a.png' )", "background: url('/a')"),
+            (
+                "a { background: URL(\"a.png\") } b { src: url(https://c.org/b.png) }",
+                "a { background: URL(\"/a\") } b { src: url(https://c.org/b.png) }",
+            ),
+            ("color: red", "color: red"),
+            ("background: url(a.png", "background: url(a.png"),
+            (
+                "a: myurl(a.png) my-url(b.png) url(c.png)",
+                "a: myurl(a.png) my-url(b.png) url(/c)",
+            ),
+            ("é url(a.png) éurl(b.png)", "é url(/a) éurl(b.png)"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::rewrite_css_urls(input, |url| {
+                (!url.contains("://")).then(|| format!("/{}", url.trim_end_matches(".png")))
+            });
+            assert_eq!(
+                result, expected,
+                "\nrewrite_css_urls({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
 }
@@ -1,6 +1,9 @@
 //! Write destination files.
 
-use super::{Config, Entry, Error};
+use std::path::{Path, PathBuf};
+
+use super::{hosting::HOSTING_FILE_URLS, Config, Entry, Error};
+use crate::util::{path::PathExt, url::Url};
 
 /// Write content of a [`Entry`] to a file.
 ///
@@ -9,26 +12,7 @@ use super::{Config, Entry, Error};
 /// the format is `html` and the URL is `/blog`, the output file will be located
 /// at `/blog/index.html`.
 pub(super) fn write_entry(entry: Entry, config: &Config) -> Result<Entry, Error> {
-    debug_assert!(entry.url.starts_with("/"));
-
-    // Prepend base_url
-    let url_path = format!("{}{}", config.base_url, entry.url);
-
-    // All entry URLs should start with `/`
-    let url_path = url_path.strip_prefix("/").unwrap();
-
-    let mut output_path = config
-        .output_dir
-        .as_ref()
-        .ok_or_else(|| Error::WriteOutput {
-            output_path: "".into(),
-            source: anyhow::anyhow!("Invalid output path"),
-        })?
-        .join(url_path);
-
-    if entry.format == "html" {
-        output_path.push("index.html")
-    };
+    let output_path = output_path(&entry, config)?;
 
     tracing::info!("Writing {:?}", output_path);
 
@@ -45,15 +29,17 @@ pub(super) fn write_entry(entry: Entry, config: &Config) -> Result<Entry, Error>
 
     if let Some(content) = entry.content.as_ref() {
         // Write processed content
-        std::fs::write(&output_path, &content).map_err(|error| Error::WriteOutput {
+        write_file(&output_path, &content).map_err(|error| Error::WriteOutput {
             output_path: output_path.to_owned(),
             source: error.into(),
         })?;
     } else if let Some(input_file) = entry.input_file.as_ref() {
         // Direct file copy
-        std::fs::copy(input_file.path(), &output_path).map_err(|error| Error::WriteOutput {
-            output_path: output_path.to_owned(),
-            source: error.into(),
+        copy_file(input_file.path(), &output_path, config.hard_link_assets).map_err(|error| {
+            Error::WriteOutput {
+                output_path: output_path.to_owned(),
+                source: error.into(),
+            }
         })?;
     } else {
         unreachable!();
@@ -61,3 +47,86 @@ pub(super) fn write_entry(entry: Entry, config: &Config) -> Result<Entry, Error>
 
     Ok(entry)
 }
+
+/// Return the output file path of a [`Entry`].
+///
+/// The path is determined according to the `format` and `url` properties, and
+/// located in the output directory. Files of hosting providers are located at
+/// the root of the output directory.
+pub(super) fn output_path(entry: &Entry, config: &Config) -> Result<PathBuf, Error> {
+    debug_assert!(entry.url.starts_with("/"));
+
+    // Prepend the path of base_url (which may include a scheme and a host)
+    let url_path = if HOSTING_FILE_URLS.contains(&entry.url.as_str()) {
+        entry.url.to_owned()
+    } else {
+        format!("{}{}", Url::from(config.base_url.as_str()).path, entry.url)
+    };
+
+    // All entry URLs should start with `/`
+    let url_path = url_path.strip_prefix("/").unwrap();
+
+    let mut output_path = config
+        .output_dir
+        .as_ref()
+        .ok_or_else(|| Error::WriteOutput {
+            output_path: "".into(),
+            source: anyhow::anyhow!("Invalid output path"),
+        })?
+        .join_url_path(url_path);
+
+    if entry.format == "html" {
+        output_path.push("index.html")
+    };
+
+    Ok(output_path)
+}
+
+/// Write content to a new file.
+///
+/// The output file is removed first, since it may be a link to an input file
+/// (from a previous build with `hard_link_assets`), which must not be
+/// overwritten.
+fn write_file<P, C>(output_path: P, content: C) -> std::io::Result<()>
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    remove_file(output_path.as_ref())?;
+    std::fs::write(output_path, content)
+}
+
+/// Copy a file without loading it in memory.
+///
+/// If `hard_link` is `true`, the output file is hard-linked to the input file
+/// when possible. Otherwise, the content is copied by the operating system
+/// (which may use copy-on-write on supporting file systems).
+fn copy_file<P, Q>(input_path: P, output_path: Q, hard_link: bool) -> std::io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let input_path = input_path.as_ref();
+    let output_path = output_path.as_ref();
+
+    // The output file may be a link to the input file (from a previous build),
+    // which must not be truncated. Also, linking fails if the file exists.
+    remove_file(output_path)?;
+
+    if hard_link {
+        match std::fs::hard_link(input_path, output_path) {
+            Ok(()) => return Ok(()),
+            Err(error) => tracing::debug!("Cannot hard-link {:?}: {}", input_path, error),
+        }
+    }
+
+    std::fs::copy(input_path, output_path).map(|_| ())
+}
+
+/// Remove a file if it exists.
+fn remove_file(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,207 @@
+//! Configure and build a site programmatically.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    config::{load_config, normalize_config, validate_config, Config},
+    error::Error,
+    site::Site,
+};
+
+/// Extension run by a [`Builder`].
+pub trait Plugin {
+    /// Name of the plugin, displayed in logs and errors.
+    fn name(&self) -> &str;
+
+    /// Run the plugin after the site is built (e.g. to generate a newsletter
+    /// from the pages of the site).
+    fn after_build(&self, site: &Site) -> anyhow::Result<()>;
+}
+
+/// Site builder with fluent configuration.
+///
+/// ```no_run
+/// let site = vitrine::Builder::new()
+///     .input_dir("content")
+///     .output_dir("public")
+///     .base_url("/blog")
+///     .build()?;
+/// # Ok::<(), vitrine::Error>(())
+/// ```
+#[derive(Default)]
+pub struct Builder {
+    /// Configuration given to the site builder.
+    config: Config,
+
+    /// Plugins run after the site is built.
+    plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl Builder {
+    /// Create a builder with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder with the configuration loaded from a file (e.g.
+    /// `vitrine.config.lua`).
+    pub fn from_config_file<P>(config_path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self {
+            config: load_config(config_path)?,
+            ..Default::default()
+        })
+    }
+
+    /// Set the input directory.
+    pub fn input_dir<P>(mut self, input_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.config.input_dir = input_dir.into();
+        self
+    }
+
+    /// Set the output directory.
+    pub fn output_dir<P>(mut self, output_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.config.output_dir = Some(output_dir.into());
+        self
+    }
+
+    /// Do not write output files.
+    pub fn dry_run(mut self) -> Self {
+        self.config.output_dir = None;
+        self
+    }
+
+    /// Set the prefix of URLs (e.g. `/blog` or `https://example.com`).
+    pub fn base_url<S>(mut self, base_url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.config.base_url = base_url.into();
+        self
+    }
+
+    /// Set the absolute URL of the site (e.g. `https://example.com`), for
+    /// feeds, sitemaps and meta tags.
+    pub fn site_url<S>(mut self, site_url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.config.site_url = Some(site_url.into());
+        self
+    }
+
+    /// Set the data directory.
+    pub fn data_dir<P>(mut self, data_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.config.data_dir = Some(data_dir.into());
+        self
+    }
+
+    /// Set the layouts directory.
+    pub fn layouts_dir<P>(mut self, layouts_dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.config.layouts_dir = Some(layouts_dir.into());
+        self
+    }
+
+    /// Determine whether output files should be minified.
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.config.minify = minify;
+        self
+    }
+
+    /// Determine whether URLs should be relative.
+    pub fn relative_urls(mut self, relative_urls: bool) -> Self {
+        self.config.relative_urls = relative_urls;
+        self
+    }
+
+    /// Set the taxonomy keys (e.g. `tags`).
+    pub fn taxonomies<I, S>(mut self, taxonomies: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.taxonomies = taxonomies.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add a plugin, run after the site is built.
+    pub fn plugin<P>(mut self, plugin: P) -> Self
+    where
+        P: Plugin + 'static,
+    {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Return the normalized and validated configuration.
+    pub fn config(self) -> Result<Config, Error> {
+        let config = normalize_config(self.config)?;
+        validate_config(&config)?;
+        Ok(config)
+    }
+
+    /// Build the site, then run the plugins.
+    pub fn build(mut self) -> Result<Site, Error> {
+        let plugins = std::mem::take(&mut self.plugins);
+
+        let config = self.config()?;
+
+        let site = crate::build::build(&config)?;
+
+        for plugin in plugins.iter() {
+            tracing::info!("Running plugin {:?}", plugin.name());
+
+            plugin
+                .after_build(&site)
+                .map_err(|error| Error::RunPlugin {
+                    name: plugin.name().to_owned(),
+                    source: error,
+                })?;
+        }
+
+        Ok(site)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Builder;
+
+    #[test]
+    fn setters() {
+        let builder = Builder::new()
+            .input_dir("content")
+            .output_dir("public")
+            .base_url("/blog")
+            .minify(false)
+            .taxonomies(["tags"]);
+
+        assert_eq!(
+            builder.config.input_dir,
+            std::path::PathBuf::from("content")
+        );
+        assert_eq!(
+            builder.config.output_dir,
+            Some(std::path::PathBuf::from("public"))
+        );
+        assert_eq!(builder.config.base_url, "/blog");
+        assert!(!builder.config.minify);
+        assert_eq!(builder.config.taxonomies, ["tags"]);
+
+        assert!(builder.dry_run().config.output_dir.is_none());
+    }
+}
@@ -0,0 +1,503 @@
+//! Check the generated site for problems.
+//!
+//! Each problem is reported by a rule, identified by a code (e.g. `V0021`)
+//! and a name (e.g. `img-alt`). The level of each rule can be changed with the
+//! `diagnostics` option, like lints of `rustc`.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    annotations::{annotate_diagnostic, relative_path},
+    build,
+    cli::CheckFormat,
+    config::Config,
+    error::Error,
+};
+
+/// Version of the schema of JSON reports, incremented on breaking changes.
+const JSON_REPORT_VERSION: u64 = 1;
+
+/// URI of the schema of SARIF reports.
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Rules of the checks, with their default level.
+///
+/// Accessibility rules have codes `V002x`, and link rules have codes `V004x`.
+pub(crate) const RULES: [Rule; 7] = [
+    Rule {
+        code: "V0020",
+        name: "html-lang",
+        level: Level::Warn,
+    },
+    Rule {
+        code: "V0021",
+        name: "img-alt",
+        level: Level::Deny,
+    },
+    Rule {
+        code: "V0022",
+        name: "empty-link",
+        level: Level::Deny,
+    },
+    Rule {
+        code: "V0023",
+        name: "empty-button",
+        level: Level::Deny,
+    },
+    Rule {
+        code: "V0024",
+        name: "duplicate-id",
+        level: Level::Deny,
+    },
+    Rule {
+        code: "V0025",
+        name: "heading-order",
+        level: Level::Warn,
+    },
+    Rule {
+        code: "V0040",
+        name: "broken-fragment",
+        level: Level::Deny,
+    },
+];
+
+/// Rule that reports a kind of problem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Rule {
+    /// Stable identifier of the rule (e.g. `V0021`).
+    pub(crate) code: &'static str,
+
+    /// Human-readable identifier of the rule (e.g. `img-alt`).
+    pub(crate) name: &'static str,
+
+    /// Default level of the rule.
+    pub(crate) level: Level,
+}
+
+/// Level of a [`Rule`], set in the `diagnostics` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Level {
+    /// Problems are not reported.
+    Allow,
+    /// Problems are reported as warnings.
+    Warn,
+    /// Problems are reported as errors.
+    Deny,
+}
+
+impl std::str::FromStr for Level {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "allow" => Ok(Self::Allow),
+            "warn" => Ok(Self::Warn),
+            "deny" => Ok(Self::Deny),
+            _ => Err(anyhow::anyhow!(
+                "Unknown level {:?} (expected \"allow\", \"warn\" or \"deny\")",
+                value
+            )),
+        }
+    }
+}
+
+/// Severity of a [`Diagnostic`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    /// The problem should be fixed, but does not fail the check.
+    Warning,
+    /// The problem fails the check.
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Problem found in a page of the generated site.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Diagnostic {
+    /// Severity of the problem.
+    pub(crate) severity: Severity,
+
+    /// Name of the rule that reported the problem (e.g. `img-alt`).
+    pub(crate) rule: &'static str,
+
+    /// Description of the problem.
+    pub(crate) message: String,
+
+    /// URL of the page.
+    pub(crate) url: String,
+
+    /// Input file of the page, if any.
+    pub(crate) input_path: Option<PathBuf>,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic with the default severity of a rule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rule is not listed in [`RULES`].
+    pub(crate) fn new(
+        rule: &'static str,
+        message: String,
+        url: String,
+        input_path: Option<PathBuf>,
+    ) -> Self {
+        let level = find_rule(rule)
+            .map(|rule| rule.level)
+            .unwrap_or_else(|| panic!("Unknown rule {rule:?}"));
+
+        Self {
+            severity: match level {
+                Level::Deny => Severity::Error,
+                _ => Severity::Warning,
+            },
+            rule,
+            message,
+            url,
+            input_path,
+        }
+    }
+
+    /// Return the code of the rule that reported the problem.
+    pub(crate) fn code(&self) -> &'static str {
+        find_rule(self.rule).map_or("", |rule| rule.code)
+    }
+}
+
+/// Checks to run on the generated site.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Checks {
+    /// Check accessibility of HTML pages.
+    pub(crate) a11y: bool,
+
+    /// Check that URL fragments of links point to existing elements.
+    pub(crate) fragments: bool,
+}
+
+/// Build the site without writing files, and check the generated pages.
+///
+/// Problems are reported per page, with the level of their rule in the
+/// `diagnostics` option. If `format` is not [`CheckFormat::Text`], a report
+/// is also written to `output`, or printed if `output` is `None`. This
+/// function fails if at least one problem has the [`Severity::Error`]
+/// severity.
+pub(super) fn check(
+    config: &Config,
+    checks: &Checks,
+    format: CheckFormat,
+    output: Option<&Path>,
+) -> Result<(), Error> {
+    let diagnostics: Vec<_> = build::check(config, checks)?
+        .into_iter()
+        .filter_map(|diagnostic| apply_level(diagnostic, config).transpose())
+        .collect::<Result<_, _>>()?;
+
+    for diagnostic in diagnostics.iter() {
+        let location = diagnostic
+            .input_path
+            .as_ref()
+            .map(|input_path| format!("{} ({:?})", diagnostic.url, input_path))
+            .unwrap_or_else(|| diagnostic.url.to_owned());
+
+        match diagnostic.severity {
+            Severity::Warning => tracing::warn!(
+                "{}: {} [{} {}]",
+                location,
+                diagnostic.message,
+                diagnostic.code(),
+                diagnostic.rule
+            ),
+            Severity::Error => tracing::error!(
+                "{}: {} [{} {}]",
+                location,
+                diagnostic.message,
+                diagnostic.code(),
+                diagnostic.rule
+            ),
+        }
+
+        if config.github_annotations {
+            annotate_diagnostic(diagnostic, config);
+        }
+    }
+
+    let num_errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+
+    let num_warnings = diagnostics.len() - num_errors;
+
+    tracing::info!("Found {} errors and {} warnings", num_errors, num_warnings);
+
+    let report = match format {
+        CheckFormat::Text => None,
+        CheckFormat::Json => Some(json_report(&diagnostics, config)),
+        CheckFormat::Sarif => Some(sarif_report(&diagnostics, config)),
+    };
+
+    if let Some(report) = report {
+        write_report(&report, output)?;
+    }
+
+    if num_errors > 0 {
+        return Err(Error::Check {
+            source: anyhow::anyhow!("{num_errors} errors found"),
+        });
+    }
+
+    Ok(())
+}
+
+/// Return the rule with a given code (e.g. `V0021`) or name (e.g. `img-alt`).
+pub(crate) fn find_rule(name: &str) -> Option<&'static Rule> {
+    RULES
+        .iter()
+        .find(|rule| rule.code == name || rule.name == name)
+}
+
+/// Set the severity of a diagnostic according to the `diagnostics` option.
+///
+/// Levels given by code take precedence over levels given by name. Returns
+/// `None` if the rule is allowed, and an error if the level is invalid.
+fn apply_level(diagnostic: Diagnostic, config: &Config) -> Result<Option<Diagnostic>, Error> {
+    let level = [diagnostic.code(), diagnostic.rule]
+        .into_iter()
+        .find_map(|key| config.diagnostics.get_key_value(key))
+        .map(|(name, level)| {
+            level.parse::<Level>().map_err(|error| Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: error.context(format!("While validating diagnostics.{name}")),
+            })
+        })
+        .transpose()?;
+
+    let severity = match level {
+        Some(Level::Allow) => return Ok(None),
+        Some(Level::Warn) => Severity::Warning,
+        Some(Level::Deny) => Severity::Error,
+        None => diagnostic.severity,
+    };
+
+    Ok(Some(Diagnostic {
+        severity,
+        ..diagnostic
+    }))
+}
+
+/// Create a JSON report of diagnostics.
+///
+/// The report is an object with the following fields:
+///
+/// - `version`: version of the schema ([`JSON_REPORT_VERSION`]);
+/// - `errors` and `warnings`: number of diagnostics of each severity;
+/// - `diagnostics`: array of objects with fields `code` (e.g. `V0021`), `rule`
+///   (e.g. `img-alt`), `severity` (`error` or `warning`), `message`, `url` (URL
+///   of the page) and `input_path` (input file of the page, relative to the
+///   current directory if possible, or `null`).
+fn json_report(diagnostics: &[Diagnostic], config: &Config) -> serde_json::Value {
+    let num_errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+
+    serde_json::json!({
+        "version": JSON_REPORT_VERSION,
+        "errors": num_errors,
+        "warnings": diagnostics.len() - num_errors,
+        "diagnostics": diagnostics
+            .iter()
+            .map(|diagnostic| serde_json::json!({
+                "code": diagnostic.code(),
+                "rule": diagnostic.rule,
+                "severity": diagnostic.severity.to_string(),
+                "message": diagnostic.message,
+                "url": diagnostic.url,
+                "input_path": diagnostic
+                    .input_path
+                    .as_ref()
+                    .and_then(|input_path| relative_path(input_path, config)),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Create a SARIF 2.1.0 report of diagnostics.
+///
+/// Each diagnostic is a result located in the input file of its page, if any,
+/// and in its URL as a logical location.
+fn sarif_report(diagnostics: &[Diagnostic], config: &Config) -> serde_json::Value {
+    let rules: Vec<_> = RULES
+        .iter()
+        .map(|rule| {
+            serde_json::json!({
+                "id": rule.code,
+                "name": rule.name,
+                "defaultConfiguration": {
+                    "level": match rule.level {
+                        Level::Allow => "none",
+                        Level::Warn => "warning",
+                        Level::Deny => "error",
+                    },
+                },
+            })
+        })
+        .collect();
+
+    let results: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let mut location = serde_json::json!({
+                "logicalLocations": [{ "fullyQualifiedName": diagnostic.url }],
+            });
+
+            if let Some(uri) = diagnostic
+                .input_path
+                .as_ref()
+                .and_then(|input_path| relative_path(input_path, config))
+            {
+                location["physicalLocation"] = serde_json::json!({
+                    "artifactLocation": { "uri": uri },
+                });
+            }
+
+            serde_json::json!({
+                "ruleId": diagnostic.code(),
+                "level": diagnostic.severity.to_string(),
+                "message": { "text": diagnostic.message },
+                "locations": [location],
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Write a report to a file, or print it if `output` is `None`.
+fn write_report(report: &serde_json::Value, output: Option<&Path>) -> Result<(), Error> {
+    let map_error = |error: anyhow::Error| Error::Check {
+        source: error.context("While writing the report"),
+    };
+
+    let content = serde_json::to_string_pretty(report).map_err(|error| map_error(error.into()))?;
+
+    match output {
+        Some(output) => {
+            std::fs::write(output, content).map_err(|error| map_error(error.into()))?;
+        },
+        None => println!("{content}"),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Severity};
+    use crate::config::Config;
+
+    #[test]
+    fn apply_level() {
+        const CASES: [(&[(&str, &str)], Option<Option<Severity>>); 7] = [
+            (&[], Some(Some(Severity::Error))),
+            (&[("img-alt", "warn")], Some(Some(Severity::Warning))),
+            (&[("V0021", "allow")], Some(None)),
+            (
+                &[("V0021", "deny"), ("img-alt", "allow")],
+                Some(Some(Severity::Error)),
+            ),
+            (&[("html-lang", "allow")], Some(Some(Severity::Error))),
+            (&[("img-alt", "error")], None),
+            (&[("html-lang", "error")], Some(Some(Severity::Error))),
+        ];
+
+        for (levels, expected) in CASES {
+            let config = Config {
+                diagnostics: levels
+                    .iter()
+                    .map(|(name, level)| (name.to_string(), level.to_string()))
+                    .collect(),
+                ..Default::default()
+            };
+
+            let diagnostic = Diagnostic::new("img-alt", String::new(), "/".to_owned(), None);
+
+            let result = super::apply_level(diagnostic, &config)
+                .ok()
+                .map(|diagnostic| diagnostic.map(|diagnostic| diagnostic.severity));
+            assert_eq!(
+                result, expected,
+                "\napply_level({levels:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sarif_report() {
+        let diagnostics = [
+            Diagnostic::new("img-alt", "No alt".to_owned(), "/".to_owned(), None),
+            Diagnostic::new(
+                "heading-order",
+                "Heading".to_owned(),
+                "/blog/".to_owned(),
+                Some("/site/blog.md".into()),
+            ),
+        ];
+
+        let config = Config {
+            input_dir: "/site".into(),
+            ..Default::default()
+        };
+
+        let result = super::sarif_report(&diagnostics, &config);
+        let results = &result["runs"][0]["results"];
+
+        assert_eq!(result["version"], "2.1.0");
+        assert_eq!(results[0]["ruleId"], "V0021");
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(
+            results[0]["locations"][0]["logicalLocations"][0]["fullyQualifiedName"],
+            "/"
+        );
+        assert!(results[0]["locations"][0].get("physicalLocation").is_none());
+        assert_eq!(results[1]["level"], "warning");
+        assert_eq!(
+            results[1]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "blog.md"
+        );
+    }
+
+    #[test]
+    fn rules() {
+        for (i, rule) in super::RULES.iter().enumerate() {
+            assert!(
+                super::RULES[..i]
+                    .iter()
+                    .all(|other| other.code != rule.code && other.name != rule.name),
+                "\nduplicate rule {rule:?}"
+            );
+        }
+    }
+}
@@ -1,12 +1,16 @@
 //! Command line options.
 
-use std::path::PathBuf;
+use std::{net::IpAddr, path::PathBuf};
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 pub(super) struct Cli {
+    /// Command to run [default: build the site]
+    #[command(subcommand)]
+    pub(super) command: Option<Command>,
+
     /// Configuration file [default: "vitrine.config.lua"]
     #[arg(long)]
     pub(super) config: Option<PathBuf>,
@@ -23,6 +27,11 @@ pub(super) struct Cli {
     #[arg(long)]
     pub(super) base_url: Option<String>,
 
+    /// Absolute URL of the site, for feeds, sitemaps and meta tags (e.g.
+    /// "https://example.com")
+    #[arg(long)]
+    pub(super) site_url: Option<String>,
+
     /// Data directory [default: "_data"]
     #[arg(long)]
     pub(super) data_dir: Option<PathBuf>,
@@ -35,11 +44,248 @@ pub(super) struct Cli {
     #[arg(long)]
     pub(super) serve: bool,
 
+    /// Server address, e.g. "0.0.0.0" to accept connections from other
+    /// devices
+    #[arg(long, default_value_t = IpAddr::from([127, 0, 0, 1]))]
+    pub(super) host: IpAddr,
+
     /// Server port
     #[arg(long, default_value_t = 8000)]
     pub(super) port: u16,
 
+    /// Fail if the server port is already in use, instead of trying the next
+    /// ports
+    #[arg(long)]
+    pub(super) strict_port: bool,
+
+    /// Log the requests made to the web server, and display a summary of the
+    /// slowest and most requested paths on shutdown
+    #[arg(long)]
+    pub(super) log_requests: bool,
+
+    /// Protect the web server with HTTP basic authentication, given as
+    /// "user:password". Command line arguments are visible to other users of
+    /// the system, prefer the VITRINE_AUTH environment variable
+    #[arg(
+        long,
+        value_name = "USER:PASSWORD",
+        env = "VITRINE_AUTH",
+        hide_env_values = true
+    )]
+    pub(super) auth: Option<String>,
+
     /// Do not write output files
     #[arg(long)]
     pub(super) dry_run: bool,
+
+    /// Write the output directory to an archive (.tar, .tar.gz, .tgz or .zip)
+    #[arg(long)]
+    pub(super) archive: Option<PathBuf>,
+
+    /// Produce byte-identical output for the same input files, using the
+    /// SOURCE_DATE_EPOCH environment variable (or 0) instead of file
+    /// modification times
+    #[arg(long)]
+    pub(super) reproducible: bool,
+
+    /// Skip the pages that fail to build, and report all errors at the end
+    #[arg(long)]
+    pub(super) keep_going: bool,
+
+    /// Format of build errors and check diagnostics
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    pub(super) message_format: MessageFormat,
+}
+
+/// Formats of build errors and check diagnostics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(super) enum MessageFormat {
+    /// Log messages only
+    Human,
+
+    /// Log messages, and GitHub Actions workflow commands that annotate the
+    /// input files
+    Github,
+}
+
+#[derive(Debug, Subcommand)]
+pub(super) enum Command {
+    /// Build the site
+    Build(BuildArgs),
+
+    /// Build the site several times, and report the duration of each build task
+    Bench(BenchArgs),
+
+    /// Build the site without writing files, and check the generated pages
+    Check(CheckArgs),
+
+    /// Build the site, and deploy the output directory
+    Deploy(DeployArgs),
+
+    /// Compare two output directories, or an output directory with the
+    /// current build
+    Diff(DiffArgs),
+
+    /// Build the site, and export pages to other formats
+    Export(ExportArgs),
+
+    /// List the pages, assets, taxonomies or URLs of the site, without
+    /// rendering layouts
+    List(ListArgs),
+
+    /// Fetch the latest commits of remote themes, and update the lock file
+    Update,
+
+    /// Build the site, and manage webmentions
+    Webmention(WebmentionArgs),
+}
+
+#[derive(Debug, Args)]
+pub(super) struct BuildArgs {
+    /// Print the weight of each page and its assets, heaviest first
+    #[arg(long)]
+    pub(super) report: bool,
+
+    /// Rebuild the site when files change, without running the web server
+    #[arg(long, conflicts_with = "report")]
+    pub(super) watch: bool,
+}
+
+#[derive(Debug, Args)]
+pub(super) struct BenchArgs {
+    /// Number of cold and warm builds
+    #[arg(long, default_value_t = 5)]
+    pub(super) runs: usize,
+
+    /// File of durations to compare with
+    #[arg(long, default_value = ".vitrine-bench.json")]
+    pub(super) baseline: PathBuf,
+
+    /// Save the mean durations to the baseline file
+    #[arg(long)]
+    pub(super) save_baseline: bool,
+}
+
+#[derive(Debug, Args)]
+pub(super) struct CheckArgs {
+    /// Check accessibility of HTML pages
+    #[arg(long)]
+    pub(super) a11y: bool,
+
+    /// Check that URL fragments of links point to existing elements
+    #[arg(long)]
+    pub(super) fragments: bool,
+
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = CheckFormat::Text)]
+    pub(super) format: CheckFormat,
+
+    /// Write the report to a file instead of the standard output
+    #[arg(long)]
+    pub(super) output: Option<PathBuf>,
+}
+
+/// Formats of the report of the `check` command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(super) enum CheckFormat {
+    /// Log messages only
+    Text,
+
+    /// JSON document, with one object per diagnostic
+    Json,
+
+    /// SARIF 2.1.0 log, for code scanning tools
+    Sarif,
+}
+
+#[derive(Debug, Args)]
+pub(super) struct DeployArgs {
+    /// Display changes without deploying
+    #[arg(long)]
+    pub(super) dry_run: bool,
+
+    /// Write the files to upload and delete to a JSON file
+    #[arg(long)]
+    pub(super) diff: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub(super) struct DiffArgs {
+    /// Previous output directory
+    pub(super) old: PathBuf,
+
+    /// New output directory [default: build the site]
+    pub(super) new: Option<PathBuf>,
+
+    /// Print only the names and status of changed files
+    #[arg(long)]
+    pub(super) name_only: bool,
+}
+
+#[derive(Debug, Args)]
+pub(super) struct ExportArgs {
+    #[command(subcommand)]
+    pub(super) command: ExportCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(super) enum ExportCommand {
+    /// Print pages to PDF files with a headless browser
+    Pdf(ExportPdfArgs),
+}
+
+#[derive(Debug, Args)]
+pub(super) struct ExportPdfArgs {
+    /// URL or glob pattern of the pages to export (e.g. "/docs/**")
+    pub(super) pattern: String,
+
+    /// Directory of the PDF files
+    #[arg(long, default_value = "_pdf")]
+    pub(super) output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub(super) struct ListArgs {
+    /// Items to list
+    #[arg(value_enum)]
+    pub(super) kind: ListKind,
+
+    /// Print items as JSON
+    #[arg(long)]
+    pub(super) json: bool,
+}
+
+/// Items listed by the `list` command.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub(super) enum ListKind {
+    /// HTML pages, with their input file and title
+    Pages,
+
+    /// Other output files, with their input file
+    Assets,
+
+    /// Terms of each taxonomy, with their number of pages
+    Taxonomies,
+
+    /// URLs of all output files
+    Urls,
+}
+
+#[derive(Debug, Args)]
+pub(super) struct WebmentionArgs {
+    #[command(subcommand)]
+    pub(super) command: WebmentionCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub(super) enum WebmentionCommand {
+    /// Send webmentions for links added or removed since the last run
+    Send(WebmentionSendArgs),
+}
+
+#[derive(Debug, Args)]
+pub(super) struct WebmentionSendArgs {
+    /// Display the links to mention without sending webmentions
+    #[arg(long)]
+    pub(super) dry_run: bool,
 }
@@ -8,15 +8,26 @@ mod yaml;
 
 use std::{
     collections::HashMap,
+    net::IpAddr,
     path::{Path, PathBuf},
 };
 
+use globset::Glob;
 use serde::Deserialize;
 use vitrine_derive::{FromJs, FromLua, FromRhai};
 
 use crate::{
+    build::{font_type, HostingProvider, PreloadHint, SymlinkPolicy},
+    check::{find_rule, Level},
+    deploy::DeployTarget,
     error::Error,
-    util::{function::Function, path::PathExt},
+    util::{
+        date_time::parse_offset,
+        function::Function,
+        path::PathExt,
+        slug::SlugStrategy,
+        url::{site_base_url, Url},
+    },
 };
 
 /// Default file names for configuration files.
@@ -29,6 +40,16 @@ const DEFAULT_CONFIG_FILE_NAMES: [&str; 6] = [
     "vitrine.config.yaml",
 ];
 
+/// File names of the default configuration of a theme.
+///
+/// Scripts are not supported, since their functions could not be merged with
+/// the site configuration.
+const THEME_CONFIG_FILE_NAMES: [&str; 3] = [
+    "vitrine.config.json",
+    "vitrine.config.toml",
+    "vitrine.config.yaml",
+];
+
 /// Return the default input directory.
 fn default_input_dir() -> PathBuf {
     PathBuf::from(".")
@@ -39,11 +60,21 @@ fn default_output_dir() -> Option<PathBuf> {
     Some(PathBuf::from("_site"))
 }
 
+/// Return the default cache directory.
+fn default_cache_dir() -> Option<PathBuf> {
+    Some(PathBuf::from(".vitrine-cache"))
+}
+
 /// Return the default base URL.
 fn default_base_url() -> String {
     String::from("")
 }
 
+/// Return the default slug strategy.
+fn default_slug_strategy() -> String {
+    String::from("transliterate")
+}
+
 /// Return the default data directory.
 fn default_data_dir() -> Option<PathBuf> {
     // Returns the path only if it exists
@@ -56,6 +87,12 @@ fn default_layouts_dir() -> Option<PathBuf> {
     Some(PathBuf::from("_layouts")).filter(|path| path.exists())
 }
 
+/// Return the default includes directory.
+fn default_includes_dir() -> Option<PathBuf> {
+    // Returns the path only if it exists
+    Some(PathBuf::from("_includes")).filter(|path| path.exists())
+}
+
 /// Return the default name of the content variable in layouts.
 fn default_layouts_content_key() -> String {
     "content".to_owned()
@@ -76,11 +113,361 @@ fn default_navigation_navigation_key() -> String {
     "navigation".to_owned()
 }
 
+/// Return the default value for the `newsletter.url` option.
+fn default_newsletter_url() -> String {
+    "/newsletter".to_owned()
+}
+
+/// Return the default value for the `newsletter.width` option.
+fn default_newsletter_width() -> usize {
+    600
+}
+
+/// Return the default allowed tags for HTML sanitization.
+fn default_sanitize_tags() -> Vec<String> {
+    [
+        "a",
+        "abbr",
+        "article",
+        "aside",
+        "b",
+        "bdi",
+        "bdo",
+        "blockquote",
+        "br",
+        "button",
+        "caption",
+        "cite",
+        "code",
+        "col",
+        "colgroup",
+        "data",
+        "dd",
+        "del",
+        "details",
+        "dfn",
+        "div",
+        "dl",
+        "dt",
+        "em",
+        "figcaption",
+        "figure",
+        "footer",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "header",
+        "hr",
+        "i",
+        "img",
+        "ins",
+        "kbd",
+        "li",
+        "mark",
+        "ol",
+        "p",
+        "pre",
+        "q",
+        "rp",
+        "rt",
+        "ruby",
+        "s",
+        "samp",
+        "section",
+        "small",
+        "span",
+        "strong",
+        "sub",
+        "summary",
+        "sup",
+        "table",
+        "tbody",
+        "td",
+        "tfoot",
+        "th",
+        "thead",
+        "time",
+        "tr",
+        "ul",
+    ]
+    .iter()
+    .map(|tag| tag.to_string())
+    .collect()
+}
+
+/// Return the default allowed attributes for HTML sanitization.
+fn default_sanitize_attributes() -> Vec<String> {
+    [
+        "alt",
+        "aria-hidden",
+        "aria-label",
+        "cite",
+        "class",
+        "colspan",
+        "datetime",
+        "dir",
+        "height",
+        "href",
+        "id",
+        "lang",
+        "open",
+        "reversed",
+        "rowspan",
+        "src",
+        "start",
+        "title",
+        "type",
+        "width",
+    ]
+    .iter()
+    .map(|attribute| attribute.to_string())
+    .collect()
+}
+
 /// Return the default URL of the sitemap.
 fn default_sitemap_url() -> String {
     "/sitemap.xml".to_owned()
 }
 
+/// Return the default value for the `activitypub.actor_url` option.
+fn default_activitypub_actor_url() -> String {
+    "/activitypub/actor.json".to_owned()
+}
+
+/// Return the default value for the `activitypub.inbox_url` option.
+fn default_activitypub_inbox_url() -> String {
+    "/activitypub/inbox.json".to_owned()
+}
+
+/// Return the default value for the `activitypub.outbox_url` option.
+fn default_activitypub_outbox_url() -> String {
+    "/activitypub/outbox.json".to_owned()
+}
+
+/// Return the default value for the `activitypub.limit` option.
+fn default_activitypub_limit() -> usize {
+    20
+}
+
+/// Return the default value for the `admonitions.class` option.
+fn default_admonitions_class() -> String {
+    "admonition".to_owned()
+}
+
+/// Return the default value for the `admonitions.title_class` option.
+fn default_admonitions_title_class() -> String {
+    "admonition-title".to_owned()
+}
+
+/// Return the default value for the `api.url_prefix` option.
+fn default_api_url_prefix() -> String {
+    "/api".to_owned()
+}
+
+/// Return the default value for the `api.paginate` option.
+fn default_api_paginate() -> usize {
+    20
+}
+
+/// Return the default value for the `blogroll.data_key` option.
+fn default_blogroll_data_key() -> String {
+    "blogroll".to_owned()
+}
+
+/// Return the default value for the `blogroll.title` option.
+fn default_blogroll_title() -> String {
+    "Blogroll".to_owned()
+}
+
+/// Return the default value for the `blogroll.url` option.
+fn default_blogroll_url() -> String {
+    "/blogroll".to_owned()
+}
+
+/// Return the default value for the `blogroll.opml_url` option.
+fn default_blogroll_opml_url() -> String {
+    "/blogroll.opml".to_owned()
+}
+
+/// Return the default value for the `color_scheme.attribute` option.
+fn default_color_scheme_attribute() -> String {
+    "data-theme".to_owned()
+}
+
+/// Return the default value for the `color_scheme.url` option.
+fn default_color_scheme_url() -> String {
+    "/color-scheme.css".to_owned()
+}
+
+/// Return the default Content Security Policy.
+fn default_content_security_policy_policy() -> String {
+    "default-src 'self'".to_owned()
+}
+
+/// Return the default value for the `content_security_policy.meta` option.
+fn default_content_security_policy_meta() -> bool {
+    true
+}
+
+/// Return the default value for the `deploy.branch` option.
+fn default_deploy_branch() -> String {
+    "gh-pages".to_owned()
+}
+
+/// Return the default value for the `documents.formats` option.
+fn default_documents_formats() -> Vec<String> {
+    ["pdf", "docx", "xlsx", "pptx", "odt", "ods", "odp"]
+        .iter()
+        .map(|format| format.to_string())
+        .collect()
+}
+
+/// Return the default value for the `events.url` option.
+fn default_events_url() -> String {
+    "/events.ics".to_owned()
+}
+
+/// Return the default value for the `external_links.rel` option.
+fn default_external_links_rel() -> String {
+    "noopener noreferrer".to_owned()
+}
+
+/// Return the default value for the `fonts.command` option.
+fn default_fonts_command() -> String {
+    "pyftsubset".to_owned()
+}
+
+/// Return the default value for the `fonts.display` option.
+fn default_fonts_display() -> String {
+    "swap".to_owned()
+}
+
+/// Return the default value for the `fonts.stylesheet_url` option.
+fn default_fonts_stylesheet_url() -> String {
+    "/fonts.css".to_owned()
+}
+
+/// Return the default value for the `fonts.url` option.
+fn default_fonts_url() -> String {
+    "/fonts".to_owned()
+}
+
+/// Return the default value for the `fonts.items.style` option.
+fn default_fonts_items_style() -> String {
+    "normal".to_owned()
+}
+
+/// Return the default value for the `fonts.items.weight` option.
+fn default_fonts_items_weight() -> String {
+    "400".to_owned()
+}
+
+/// Return the default value for the `fragments.url` option.
+fn default_fragments_url() -> String {
+    "/fragments".to_owned()
+}
+
+/// Return the default value for the `fragments.manifest_url` option.
+fn default_fragments_manifest_url() -> String {
+    "/fragments/manifest.json".to_owned()
+}
+
+/// Return the default value for the `fragments.items.page` option.
+fn default_fragments_items_page() -> String {
+    "/".to_owned()
+}
+
+/// Return the default value for the `hosting.redirects.status` option.
+fn default_hosting_redirects_status() -> usize {
+    301
+}
+
+/// Return the default value for the `images.remote_url_prefix` option.
+fn default_images_remote_url_prefix() -> String {
+    String::from("/images/remote/")
+}
+
+/// Return the default value for the `pdf.browser` option.
+fn default_pdf_browser() -> String {
+    "chromium".to_owned()
+}
+
+/// Return the default value for the `precompress.gzip` option.
+fn default_precompress_gzip() -> bool {
+    true
+}
+
+/// Return the default value for the `precompress.brotli` option.
+fn default_precompress_brotli() -> bool {
+    true
+}
+
+/// Return the default value for the `precompress.min_size` option.
+fn default_precompress_min_size() -> usize {
+    1024
+}
+
+/// Return the default value for the `preload.fonts` option.
+fn default_preload_fonts() -> String {
+    "preload".to_owned()
+}
+
+/// Return the default value for the `preload.image` option.
+fn default_preload_image() -> String {
+    "preload".to_owned()
+}
+
+/// Return the default value for the `preload.stylesheet` option.
+fn default_preload_stylesheet() -> String {
+    "preload".to_owned()
+}
+
+/// Return the default value for the `tailwind.command` option.
+fn default_tailwind_command() -> String {
+    "tailwindcss".to_owned()
+}
+
+/// Return the default value for the `tokens.prefix` option.
+fn default_tokens_prefix() -> String {
+    "%%".to_owned()
+}
+
+/// Return the default value for the `tokens.suffix` option.
+fn default_tokens_suffix() -> String {
+    "%%".to_owned()
+}
+
+/// Return the default value for the `typescript.jsx_factory` option.
+fn default_typescript_jsx_factory() -> String {
+    "React.createElement".to_owned()
+}
+
+/// Return the default value for the `typescript.jsx_fragment` option.
+fn default_typescript_jsx_fragment() -> String {
+    "React.Fragment".to_owned()
+}
+
+/// Return the default value for the `unused_assets.keep` option.
+fn default_unused_assets_keep() -> Vec<String> {
+    ["CNAME", "favicon.ico", "robots.txt"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect()
+}
+
+/// Return the default value for the `webmention.state_file` option.
+fn default_webmention_state_file() -> PathBuf {
+    PathBuf::from(".vitrine-webmention.json")
+}
+
+/// Return the default policy for symbolic links.
+fn default_symlinks() -> String {
+    String::from("skip")
+}
+
 /// Return the default value for the `minify` option.
 fn default_minify() -> bool {
     true
@@ -90,7 +477,7 @@ fn default_minify() -> bool {
 ///
 /// This structure represents the configuration given to the site builder.
 #[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
-pub(crate) struct Config {
+pub struct Config {
     /// Path to the configuration file.
     #[serde(skip)]
     #[vitrine(skip)]
@@ -101,6 +488,16 @@ pub(crate) struct Config {
     #[vitrine(default = "default_input_dir")]
     pub(crate) input_dir: PathBuf,
 
+    /// Additional directories of input files (e.g. a shared Git submodule),
+    /// merged with `input_dir`.
+    ///
+    /// When several directories contain the same file, the file of
+    /// `input_dir` takes precedence, then the directories in the order of the
+    /// list, then the theme directory.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) extra_input_dirs: Vec<PathBuf>,
+
     /// Directory of output files.
     ///
     /// If set to `None`, Vitrine does not write files.
@@ -108,11 +505,76 @@ pub(crate) struct Config {
     #[vitrine(default = "default_output_dir")]
     pub(crate) output_dir: Option<PathBuf>,
 
+    /// Directory of cached build results (e.g. rendered Markdown).
+    ///
+    /// Cached results are reused across builds, as long as their input and
+    /// options are unchanged. If set to `None`, Vitrine does not cache build
+    /// results.
+    #[serde(default = "default_cache_dir")]
+    #[vitrine(default = "default_cache_dir")]
+    pub(crate) cache_dir: Option<PathBuf>,
+
     /// Prefix for URLs.
     #[serde(default = "default_base_url")]
     #[vitrine(default = "default_base_url")]
     pub(crate) base_url: String,
 
+    /// Absolute URL of the site, without path (e.g. `https://example.com`).
+    ///
+    /// It is prepended to root-relative URLs (e.g. `/blog/post`) where absolute
+    /// URLs are required, i.e. in feeds, sitemaps and `<meta>` tags of pages,
+    /// and in calendars, ActivityPub documents, newsletters and webmentions if
+    /// `base_url` does not specify the domain.
+    pub(crate) site_url: Option<String>,
+
+    /// Determine whether links between pages should be relative.
+    ///
+    /// If set to `true`, URLs are rewritten relative to the page that contains
+    /// them (e.g. `../style.css`) instead of being prefixed with
+    /// [`Config::base_url`], so that the site works from `file://` or from any
+    /// subpath. Links to pages point to their `index.html` file (e.g.
+    /// `../about/index.html`), since browsers do not open it from `file://`
+    /// directories.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) relative_urls: bool,
+
+    /// Determine whether subresource integrity hashes should be added.
+    ///
+    /// If set to `true`, `integrity` and `crossorigin` attributes are added to
+    /// `<script>` and `<link rel="stylesheet">` elements referencing scripts
+    /// and stylesheets of the site.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) subresource_integrity: bool,
+
+    /// Determine whether files copied as is should be hard-linked.
+    ///
+    /// If set to `true`, files that are not processed (e.g. images, videos) are
+    /// hard-linked into the output directory instead of being copied, falling
+    /// back to a copy when linking fails (e.g. across file systems). Output
+    /// files then share their content with input files.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) hard_link_assets: bool,
+
+    /// Minimum size in bytes of the files that are memory-mapped.
+    ///
+    /// When copied files are read (e.g. to precompress them, or to hash them
+    /// when deploying), files of at least this size are mapped in memory by
+    /// the operating system instead of being loaded in heap memory. If not
+    /// set, files are always loaded in heap memory.
+    pub(crate) mmap_threshold: Option<usize>,
+
+    /// Determine whether page dates and contributors should be read from git.
+    ///
+    /// If set to `true`, the `date`, `updated` and `contributors` fields of
+    /// pages default to the dates of the first and last commits, and to the
+    /// commit authors, of their source files.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) git_metadata: bool,
+
     /// Directory of data files.
     ///
     /// If set to `None`, Vitrine does not search for data files.
@@ -128,11 +590,120 @@ pub(crate) struct Config {
     #[vitrine(default)]
     pub(crate) global_data: serde_json::Value,
 
+    /// ActivityPub configuration.
+    pub(crate) activitypub: Option<ActivityPubConfig>,
+
+    /// Admonitions configuration.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) admonitions: AdmonitionsConfig,
+
+    /// Analytics configuration.
+    ///
+    /// Analytics are disabled when serving the site.
+    pub(crate) analytics: Option<AnalyticsConfig>,
+
+    /// JSON content API configuration.
+    pub(crate) api: Option<ApiConfig>,
+
+    /// Blogroll configuration.
+    pub(crate) blogroll: Option<BlogrollConfig>,
+
+    /// Collections configuration.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) collections: Vec<CollectionConfig>,
+
+    /// Color scheme configuration.
+    pub(crate) color_scheme: Option<ColorSchemeConfig>,
+
+    /// Files located outside of the input directory, copied to the output
+    /// directory.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) copy_paths: Vec<CopyPathConfig>,
+
+    /// Content Security Policy configuration.
+    pub(crate) content_security_policy: Option<ContentSecurityPolicyConfig>,
+
+    /// Deployment configuration.
+    pub(crate) deploy: Option<DeployConfig>,
+
+    /// Levels of the diagnostics reported by `vitrine check`, keyed by code
+    /// (e.g. `V0021`) or by rule name (e.g. `img-alt`).
+    ///
+    /// Accepted values are `allow` (the diagnostic is not reported), `warn`
+    /// (the diagnostic is reported) and `deny` (the diagnostic fails the
+    /// check).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) diagnostics: HashMap<String, String>,
+
+    /// Documents configuration.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) documents: DocumentsConfig,
+
+    /// Events calendar configuration.
+    pub(crate) events: Option<EventsConfig>,
+
+    /// External links configuration.
+    pub(crate) external_links: Option<ExternalLinksConfig>,
+
     /// Feeds configuration.
     #[serde(default)]
     #[vitrine(default)]
     pub(crate) feeds: Vec<FeedConfig>,
 
+    /// Self-hosted fonts configuration.
+    pub(crate) fonts: Option<FontsConfig>,
+
+    /// Fragments configuration.
+    pub(crate) fragments: Option<FragmentsConfig>,
+
+    /// Function that computes default front matter fields of pages.
+    ///
+    /// The function receives a map with the `path` of the input file (relative
+    /// to the input directory), the `url` and the front matter `data` of the
+    /// page, and returns a map of fields (e.g. `slug` or `section`). Fields
+    /// already present in the front matter take precedence.
+    #[serde(skip)]
+    #[vitrine(default)]
+    pub(crate) front_matter_defaults: Option<Function>,
+
+    /// Front matter schemas.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) front_matter_schemas: Vec<FrontMatterSchemaConfig>,
+
+    /// Function that generates pages from data.
+    ///
+    /// The function receives the global data, and returns a list of pages.
+    /// Each page is a map with the `path` of a virtual input file (e.g.
+    /// `products/chair.md`), its `content`, and optional front matter `data`.
+    /// Generated pages are processed like input files (e.g. Markdown is
+    /// converted and layouts are rendered).
+    #[serde(skip)]
+    #[vitrine(default)]
+    pub(crate) generate_pages: Option<Function>,
+
+    /// Hosting provider files configuration.
+    pub(crate) hosting: Option<HostingConfig>,
+
+    /// Images configuration.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) images: ImagesConfig,
+
+    /// Import map configuration.
+    pub(crate) import_map: Option<ImportMapConfig>,
+
+    /// Directory of files included in pages with the `include` directive
+    /// (e.g. `{{< include "install.md" >}}`).
+    #[serde(default = "default_includes_dir")]
+    #[vitrine(default = "default_includes_dir")]
+    pub(crate) includes_dir: Option<PathBuf>,
+
     /// Directory of layout files.
     ///
     /// If set to `None`, Vitrine does not use a layout engine.
@@ -148,24 +719,125 @@ pub(crate) struct Config {
     /// Navigation tree configuration.
     pub(crate) navigation: Option<NavigationConfig>,
 
+    /// Newsletter configuration.
+    pub(crate) newsletter: Option<NewsletterConfig>,
+
+    /// PDF export configuration.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) pdf: PdfConfig,
+
+    /// Precompression configuration.
+    pub(crate) precompress: Option<PrecompressConfig>,
+
+    /// Preload hints configuration.
+    pub(crate) preload: Option<PreloadConfig>,
+
+    /// Shell commands run after each successful build, including rebuilds in
+    /// watch mode (e.g. `npx pagefind --site _site`).
+    ///
+    /// Commands are run in order from the current directory, and receive the
+    /// input and output directories in the `VITRINE_INPUT_DIR` and
+    /// `VITRINE_OUTPUT_DIR` environment variables. They are not run if no
+    /// output directory is specified (e.g. with `--dry-run`).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) run_after_build: Vec<String>,
+
+    /// Shell commands run before each build, including rebuilds in watch mode
+    /// (e.g. `npm run tailwind`).
+    ///
+    /// Commands are run like [`Config::run_after_build`], so that they can
+    /// generate input files (e.g. stylesheets).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) run_before_build: Vec<BeforeBuildCommandConfig>,
+
+    /// HTML sanitization configuration.
+    pub(crate) sanitize: Option<SanitizeConfig>,
+
     /// Sitemap configuration.
     pub(crate) sitemap: Option<SitemapConfig>,
 
+    /// SVG optimization configuration.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) svg: SvgConfig,
+
     /// Syntax highlight configuration.
     #[serde(default)]
     #[vitrine(default)]
     pub(crate) syntax_highlight: SyntaxHighlightConfig,
 
+    /// Tailwind CSS configuration.
+    pub(crate) tailwind: Option<TailwindConfig>,
+
+    /// Strategy used to generate slugs (e.g. heading anchors).
+    ///
+    /// Accepted values are `transliterate` (Unicode characters are converted
+    /// to ASCII), `unicode` (Unicode letters are kept) and `ascii` (non-ASCII
+    /// characters are removed).
+    #[serde(default = "default_slug_strategy")]
+    #[vitrine(default = "default_slug_strategy")]
+    pub(crate) slug_strategy: String,
+
     /// Taxonomies configuration.
     #[serde(default)]
     #[vitrine(default)]
     pub(crate) taxonomies: Vec<String>,
 
+    /// Directory of a theme.
+    ///
+    /// A theme is organized like a site: its `_layouts` directory contains
+    /// layouts, and its other files are copied or processed like input files.
+    /// Its default configuration (e.g. `vitrine.config.json`) is merged with
+    /// the site configuration. Files and settings of the site take precedence
+    /// over those of the theme.
+    ///
+    /// A Git URL can also be specified (e.g.
+    /// `https://github.com/user/theme.git#v1.0`): the theme is then cloned in
+    /// the cache directory, at the commit recorded in `vitrine.lock`.
+    pub(crate) theme: Option<PathBuf>,
+
+    /// Time zone offset of the site (e.g. `+02:00`).
+    ///
+    /// Dates without offset (e.g. `2024-05-01` in front matters) are
+    /// interpreted in this time zone, and dates of feeds, sitemaps and the
+    /// `format_date` filter are converted to it. Without time zone, dates
+    /// without offset are interpreted in UTC.
+    pub(crate) timezone: Option<String>,
+
+    /// Token substitution configuration.
+    pub(crate) tokens: Option<TokensConfig>,
+
+    /// TypeScript and JSX configuration.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) typescript: TypescriptConfig,
+
+    /// Unused assets detection configuration.
+    pub(crate) unused_assets: Option<UnusedAssetsConfig>,
+
+    /// Webmention sending configuration.
+    pub(crate) webmention: Option<WebmentionConfig>,
+
     /// Ignore specific files or path patterns.
+    ///
+    /// Patterns of the `.gitignore` and `.vitrineignore` files located in the
+    /// input directory are also ignored.
     #[serde(default)]
     #[vitrine(default)]
     pub(crate) ignore: Vec<String>,
 
+    /// Policy for symbolic links in input directories.
+    ///
+    /// Accepted values are `skip` (symbolic links are ignored), `follow`
+    /// (targets are processed like input files, and cycles are skipped) and
+    /// `error` (the build fails).
+    #[serde(default = "default_symlinks")]
+    #[vitrine(default = "default_symlinks")]
+    pub(crate) symlinks: String,
+
     /// Paths to ignore from input files.
     #[serde(skip)]
     #[vitrine(skip)]
@@ -176,10 +848,43 @@ pub(crate) struct Config {
     #[vitrine(default = "default_minify")]
     pub(crate) minify: bool,
 
+    /// Server address, or `None` for the loopback address.
+    #[serde(skip)]
+    #[vitrine(skip)]
+    pub(crate) serve_host: Option<IpAddr>,
+
     /// Server port.
     #[serde(skip)]
     #[vitrine(skip)]
     pub(crate) serve_port: u16,
+
+    /// Determine whether the server should fail if its port is in use.
+    #[serde(skip)]
+    #[vitrine(skip)]
+    pub(crate) serve_strict_port: bool,
+
+    /// Determine whether the requests made to the server should be logged.
+    #[serde(skip)]
+    #[vitrine(skip)]
+    pub(crate) serve_log_requests: bool,
+
+    /// Determine whether the pages that fail to build should be skipped, and
+    /// their errors reported at the end of the build.
+    #[serde(skip)]
+    #[vitrine(skip)]
+    pub(crate) keep_going: bool,
+
+    /// Determine whether errors and diagnostics should be printed as GitHub
+    /// Actions annotations.
+    #[serde(skip)]
+    #[vitrine(skip)]
+    pub(crate) github_annotations: bool,
+
+    /// Timestamp (in seconds since the Unix epoch) used instead of the
+    /// modification times of input files, for reproducible builds.
+    #[serde(skip)]
+    #[vitrine(skip)]
+    pub(crate) source_date_epoch: Option<u64>,
 }
 
 impl Default for Config {
@@ -187,25 +892,95 @@ impl Default for Config {
         Self {
             config_path: Default::default(),
             input_dir: default_input_dir(),
+            extra_input_dirs: Default::default(),
             output_dir: default_output_dir(),
+            cache_dir: default_cache_dir(),
             base_url: default_base_url(),
+            site_url: Default::default(),
+            relative_urls: Default::default(),
+            subresource_integrity: Default::default(),
+            hard_link_assets: Default::default(),
+            mmap_threshold: Default::default(),
+            git_metadata: Default::default(),
             data_dir: default_data_dir(),
             global_data: Default::default(),
+            activitypub: Default::default(),
+            admonitions: Default::default(),
+            analytics: Default::default(),
+            api: Default::default(),
+            blogroll: Default::default(),
+            collections: Default::default(),
+            color_scheme: Default::default(),
+            copy_paths: Default::default(),
+            content_security_policy: Default::default(),
+            deploy: Default::default(),
+            diagnostics: Default::default(),
+            documents: Default::default(),
+            events: Default::default(),
+            external_links: Default::default(),
             feeds: Default::default(),
+            fonts: Default::default(),
+            fragments: Default::default(),
+            front_matter_defaults: Default::default(),
+            front_matter_schemas: Default::default(),
+            generate_pages: Default::default(),
+            hosting: Default::default(),
+            images: Default::default(),
+            import_map: Default::default(),
+            includes_dir: default_includes_dir(),
             layouts_dir: default_layouts_dir(),
             layouts: Default::default(),
             navigation: Default::default(),
+            newsletter: Default::default(),
+            pdf: Default::default(),
+            precompress: Default::default(),
+            preload: Default::default(),
+            run_after_build: Default::default(),
+            run_before_build: Default::default(),
+            sanitize: Default::default(),
             sitemap: Default::default(),
+            svg: Default::default(),
             syntax_highlight: Default::default(),
+            tailwind: Default::default(),
+            slug_strategy: default_slug_strategy(),
             taxonomies: Default::default(),
+            theme: Default::default(),
+            timezone: Default::default(),
+            tokens: Default::default(),
+            typescript: Default::default(),
+            unused_assets: Default::default(),
+            webmention: Default::default(),
             ignore: Default::default(),
+            symlinks: default_symlinks(),
             input_ignore_paths: Default::default(),
             minify: default_minify(),
+            serve_host: Default::default(),
             serve_port: Default::default(),
+            serve_strict_port: Default::default(),
+            serve_log_requests: Default::default(),
+            keep_going: Default::default(),
+            github_annotations: Default::default(),
+            source_date_epoch: Default::default(),
         }
     }
 }
 
+impl Config {
+    /// Return the directories of input files, by decreasing precedence.
+    pub(crate) fn input_dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.input_dir)
+            .chain(self.extra_input_dirs.iter())
+            .chain(self.theme.as_ref())
+    }
+
+    /// Return the path of an input file relative to its input directory, if
+    /// any.
+    pub(crate) fn strip_input_dir<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        self.input_dirs()
+            .find_map(|input_dir| path.strip_prefix(input_dir).ok())
+    }
+}
+
 /// Configuration for feed generation.
 #[derive(Debug, Default, Deserialize, FromJs, FromLua, FromRhai)]
 pub(crate) struct FeedConfig {
@@ -264,15 +1039,688 @@ pub(crate) struct FeedPersonConfig {
     pub(crate) email: Option<String>,
 }
 
-/// Configuration for the layout engine.
-#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
-pub(crate) struct LayoutsConfig {
-    /// Name of the template variable representing the content.
-    #[serde(default = "default_layouts_content_key")]
-    #[vitrine(default = "default_layouts_content_key")]
-    pub(crate) content_key: String,
+/// Configuration for a collection of pages.
+#[derive(Debug, Default, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct CollectionConfig {
+    /// Name of the collection (e.g. `blog`).
+    ///
+    /// Pages of the collection are listed in layouts under
+    /// `collections.{name}`.
+    pub(crate) name: String,
 
-    /// Name of the metadata key containing the layout name.
+    /// Directory of the pages, relative to the input directory (e.g. `blog`).
+    ///
+    /// Pages located outside the directory can join the collection with a
+    /// `collection` field in their front matter.
+    pub(crate) directory: Option<String>,
+
+    /// Default layout of the pages.
+    pub(crate) layout: Option<String>,
+
+    /// Metadata key used to sort the pages (e.g. `date`, `weight` or
+    /// `title`).
+    ///
+    /// The key can be prefixed with `-` to sort in descending order (e.g.
+    /// `-date`). Pages are sorted by URL by default.
+    pub(crate) sort_by: Option<String>,
+
+    /// Function that compares two pages, taking precedence over
+    /// [`CollectionConfig::sort_by`].
+    ///
+    /// The function returns a negative number if the first page comes before
+    /// the second one, a positive number if it comes after, or zero.
+    #[serde(skip)]
+    #[vitrine(default)]
+    pub(crate) compare: Option<Function>,
+
+    /// Prefix of the page URLs, replacing the directory (e.g. `/articles`).
+    pub(crate) url_prefix: Option<String>,
+
+    /// Number of pages per listing page.
+    ///
+    /// If set, the index page of the collection (e.g. `blog/index.md`) is
+    /// rendered once per listing page (e.g. `/blog`, `/blog/page/2`), with a
+    /// `pagination` variable.
+    pub(crate) paginate: Option<usize>,
+
+    /// Feed of the collection.
+    pub(crate) feed: Option<FeedConfig>,
+}
+
+/// Configuration for light and dark color schemes.
+///
+/// A stylesheet defines palette variables (e.g. `--bg`) for the light scheme,
+/// and for the dark scheme when the user prefers a dark color scheme. Scripts
+/// can force a scheme with an attribute of the root element (e.g.
+/// `<html data-theme="dark">`). Syntax highlight rules of a light and a dark
+/// theme can be appended to the same stylesheet.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct ColorSchemeConfig {
+    /// Palette variables of the light scheme, by name (e.g. `bg` for `--bg`).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) light: HashMap<String, String>,
+
+    /// Palette variables of the dark scheme, by name.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) dark: HashMap<String, String>,
+
+    /// Attribute of the root element that overrides the preferred color
+    /// scheme, with the value `light` or `dark`.
+    ///
+    /// Dark themes of `syntax_highlight.stylesheets` use the same attribute.
+    #[serde(default = "default_color_scheme_attribute")]
+    #[vitrine(default = "default_color_scheme_attribute")]
+    pub(crate) attribute: String,
+
+    /// Syntax highlight theme of the light scheme.
+    ///
+    /// Classes are prefixed with `syntax_highlight.css_prefix`.
+    pub(crate) syntax_theme: Option<String>,
+
+    /// Syntax highlight theme of the dark scheme.
+    pub(crate) syntax_dark_theme: Option<String>,
+
+    /// Determine whether the stylesheet should be linked in the `<head>` of
+    /// HTML pages.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) link: bool,
+
+    /// Output URL of the stylesheet.
+    #[serde(default = "default_color_scheme_url")]
+    #[vitrine(default = "default_color_scheme_url")]
+    pub(crate) url: String,
+}
+
+impl Default for ColorSchemeConfig {
+    fn default() -> Self {
+        Self {
+            light: Default::default(),
+            dark: Default::default(),
+            attribute: default_color_scheme_attribute(),
+            syntax_theme: Default::default(),
+            syntax_dark_theme: Default::default(),
+            link: Default::default(),
+            url: default_color_scheme_url(),
+        }
+    }
+}
+
+/// Configuration for Content Security Policy generation.
+///
+/// See <https://developer.mozilla.org/en-US/docs/Web/HTTP/CSP>.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct ContentSecurityPolicyConfig {
+    /// Base policy.
+    ///
+    /// Hashes of inline scripts and styles are added to the `script-src` and
+    /// `style-src` directives of each page.
+    #[serde(default = "default_content_security_policy_policy")]
+    #[vitrine(default = "default_content_security_policy_policy")]
+    pub(crate) policy: String,
+
+    /// Determine whether the policy should be added to pages as a `<meta
+    /// http-equiv="Content-Security-Policy">` element.
+    #[serde(default = "default_content_security_policy_meta")]
+    #[vitrine(default = "default_content_security_policy_meta")]
+    pub(crate) meta: bool,
+
+    /// URL of a headers file listing the policy of each page (e.g. `/_headers`
+    /// for Netlify or Cloudflare Pages).
+    ///
+    /// Policies are also added to the files generated by the `hosting`
+    /// configuration, if any. This URL must not be one of these files.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) headers_url: Option<String>,
+}
+
+/// Configuration for deployment.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct DeployConfig {
+    /// Deployment target.
+    ///
+    /// Accepted values are `rsync` (requires the `rsync` command), `s3`
+    /// (requires the `aws` command) and `github_pages` (requires the `git`
+    /// command).
+    pub(crate) target: String,
+
+    /// Destination of the files.
+    ///
+    /// Depending on the target, the destination is a `rsync` destination
+    /// (e.g. `user@example.com:/var/www`), a S3 URL (e.g.
+    /// `s3://bucket/prefix`) or a git remote URL (e.g.
+    /// `git@github.com:user/repo.git`).
+    pub(crate) destination: String,
+
+    /// Endpoint URL of a S3-compatible service (e.g. Cloudflare R2, MinIO).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) endpoint_url: Option<String>,
+
+    /// Branch of the git repository, for the `github_pages` target.
+    #[serde(default = "default_deploy_branch")]
+    #[vitrine(default = "default_deploy_branch")]
+    pub(crate) branch: String,
+
+    /// File storing the content hashes of deployed files (e.g.
+    /// `.vitrine-deploy.json`).
+    ///
+    /// If specified, changes are computed from this file instead of the
+    /// destination, which is useful when the destination cannot list its
+    /// files.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) state_file: Option<PathBuf>,
+}
+
+/// Configuration for documents (e.g. PDF files).
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct DocumentsConfig {
+    /// Extensions of the files listed in the `site.files` layout variable,
+    /// with their metadata (e.g. title, page count).
+    #[serde(default = "default_documents_formats")]
+    #[vitrine(default = "default_documents_formats")]
+    pub(crate) formats: Vec<String>,
+}
+
+impl Default for DocumentsConfig {
+    fn default() -> Self {
+        Self {
+            formats: default_documents_formats(),
+        }
+    }
+}
+
+/// Configuration for the events calendar.
+///
+/// Events are pages with an `event` map in their front matter, containing a
+/// `start` date, and optional `end` date and `location`. An iCalendar file
+/// listing the events is generated.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct EventsConfig {
+    /// URL of the iCalendar file.
+    #[serde(default = "default_events_url")]
+    #[vitrine(default = "default_events_url")]
+    pub(crate) url: String,
+
+    /// Name of the calendar.
+    pub(crate) title: Option<String>,
+}
+
+/// Configuration for external links in HTML pages.
+///
+/// A link is external if it is absolute (e.g. `https://example.com`) and its
+/// host differs from the hosts of `base_url` and `sitemap.url_prefix`.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct ExternalLinksConfig {
+    /// Link types added to the `rel` attribute of external links.
+    #[serde(default = "default_external_links_rel")]
+    #[vitrine(default = "default_external_links_rel")]
+    pub(crate) rel: String,
+
+    /// Determine whether external links should open in a new tab
+    /// (`target="_blank"`).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) target_blank: bool,
+
+    /// Class added to external links (e.g. to display an icon).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) class: Option<String>,
+}
+
+/// Configuration for files copied from outside of the input directory.
+///
+/// A file is copied to `to`, or into `to` if it ends with `/`. The files of a
+/// directory, or matching a glob pattern, are copied into `to`, preserving
+/// their paths relative to the directory (or the part of the pattern before
+/// the first glob character).
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct CopyPathConfig {
+    /// Path of a file or directory, or glob pattern (e.g. `static/**`),
+    /// relative to the current directory.
+    pub(crate) from: String,
+
+    /// Destination URL path (e.g. `/vendor/`).
+    pub(crate) to: String,
+
+    /// Determine whether CSS, JavaScript, JSON, SVG and XML files are minified,
+    /// when `minify` is enabled. Other files are copied as is.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) minify: bool,
+}
+
+/// Configuration for admonitions in Markdown (e.g. `> [!NOTE]`).
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct AdmonitionsConfig {
+    /// CSS class of `<aside>` elements.
+    ///
+    /// The type of the admonition is appended to this class as a second class
+    /// (e.g. `admonition admonition-note`).
+    #[serde(default = "default_admonitions_class")]
+    #[vitrine(default = "default_admonitions_class")]
+    pub(crate) class: String,
+
+    /// CSS class of the title of admonitions.
+    #[serde(default = "default_admonitions_title_class")]
+    #[vitrine(default = "default_admonitions_title_class")]
+    pub(crate) title_class: String,
+}
+
+impl Default for AdmonitionsConfig {
+    fn default() -> Self {
+        Self {
+            class: default_admonitions_class(),
+            title_class: default_admonitions_title_class(),
+        }
+    }
+}
+
+/// Configuration for ActivityPub documents.
+///
+/// A WebFinger document (`/.well-known/webfinger`), an actor and its outbox of
+/// recent posts are generated, so that the site can be found and followed
+/// from the fediverse. The site cannot receive activities.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct ActivityPubConfig {
+    /// User name of the actor (e.g. `blog` for `@blog@example.com`).
+    pub(crate) username: String,
+
+    /// Display name of the actor.
+    pub(crate) name: Option<String>,
+
+    /// Description of the actor, in HTML.
+    pub(crate) summary: Option<String>,
+
+    /// URL of the avatar of the actor.
+    pub(crate) icon: Option<String>,
+
+    /// Public key of the actor, in PEM format.
+    pub(crate) public_key_pem: Option<String>,
+
+    /// URL of the actor document.
+    #[serde(default = "default_activitypub_actor_url")]
+    #[vitrine(default = "default_activitypub_actor_url")]
+    pub(crate) actor_url: String,
+
+    /// URL of the inbox document, which is always empty.
+    #[serde(default = "default_activitypub_inbox_url")]
+    #[vitrine(default = "default_activitypub_inbox_url")]
+    pub(crate) inbox_url: String,
+
+    /// URL of the outbox document.
+    #[serde(default = "default_activitypub_outbox_url")]
+    #[vitrine(default = "default_activitypub_outbox_url")]
+    pub(crate) outbox_url: String,
+
+    /// Name of the collection of posts.
+    ///
+    /// If not specified, all pages with a date are posts.
+    pub(crate) collection: Option<String>,
+
+    /// Maximum number of posts in the outbox.
+    #[serde(default = "default_activitypub_limit")]
+    #[vitrine(default = "default_activitypub_limit")]
+    pub(crate) limit: usize,
+}
+
+/// Configuration for analytics.
+///
+/// The tracking code is inserted at the end of the `<head>` of each page. It
+/// is either the code of a known provider for a site, or a raw snippet.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct AnalyticsConfig {
+    /// Analytics provider (`goatcounter`, `google` or `plausible`).
+    pub(crate) provider: Option<String>,
+
+    /// Identifier of the site at the provider (e.g. the domain for
+    /// Plausible, the measurement ID for Google Analytics).
+    pub(crate) site_id: Option<String>,
+
+    /// HTML snippet, used instead of a provider.
+    pub(crate) snippet: Option<String>,
+}
+
+/// Configuration for the JSON content API.
+///
+/// JSON files mirroring the content of pages, collections and taxonomies are
+/// generated under a URL prefix, for client-side applications.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct ApiConfig {
+    /// URL prefix of the JSON files.
+    #[serde(default = "default_api_url_prefix")]
+    #[vitrine(default = "default_api_url_prefix")]
+    pub(crate) url_prefix: String,
+
+    /// Number of pages per listing file.
+    #[serde(default = "default_api_paginate")]
+    #[vitrine(default = "default_api_paginate")]
+    pub(crate) paginate: usize,
+}
+
+/// Configuration for the blogroll.
+///
+/// The blogroll is generated from a list of feeds in global data (e.g. a
+/// `_data/blogroll.yaml` file). Each feed is a map with a `feed_url`, and
+/// optional `title`, `site_url` and `description`. An OPML file and an HTML
+/// page listing the feeds are generated.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct BlogrollConfig {
+    /// Key of the list of feeds in global data.
+    #[serde(default = "default_blogroll_data_key")]
+    #[vitrine(default = "default_blogroll_data_key")]
+    pub(crate) data_key: String,
+
+    /// Title of the OPML file and the HTML page.
+    #[serde(default = "default_blogroll_title")]
+    #[vitrine(default = "default_blogroll_title")]
+    pub(crate) title: String,
+
+    /// URL of the HTML page.
+    #[serde(default = "default_blogroll_url")]
+    #[vitrine(default = "default_blogroll_url")]
+    pub(crate) url: String,
+
+    /// URL of the OPML file.
+    #[serde(default = "default_blogroll_opml_url")]
+    #[vitrine(default = "default_blogroll_opml_url")]
+    pub(crate) opml_url: String,
+
+    /// Layout of the HTML page.
+    ///
+    /// The page receives the list of feeds in its `feeds` variable.
+    pub(crate) layout: Option<String>,
+
+    /// Determine whether missing titles should be fetched from the feeds.
+    ///
+    /// Fetched titles are stored in the cache directory, if any.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) fetch_titles: bool,
+}
+
+/// Configuration for self-hosted fonts.
+///
+/// Remote fonts (e.g. of Google Fonts) are downloaded at build time into the
+/// cache directory, and subset to the characters of the rendered pages with
+/// `pyftsubset` of [fontTools](https://github.com/fonttools/fonttools). The
+/// generated stylesheet of `@font-face` rules must be linked by layouts (e.g.
+/// `<link rel="stylesheet" href="/fonts.css">`).
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct FontsConfig {
+    /// URL prefix of subset font files.
+    #[serde(default = "default_fonts_url")]
+    #[vitrine(default = "default_fonts_url")]
+    pub(crate) url: String,
+
+    /// URL of the stylesheet of `@font-face` rules.
+    #[serde(default = "default_fonts_stylesheet_url")]
+    #[vitrine(default = "default_fonts_stylesheet_url")]
+    pub(crate) stylesheet_url: String,
+
+    /// Value of the `font-display` descriptor of `@font-face` rules.
+    #[serde(default = "default_fonts_display")]
+    #[vitrine(default = "default_fonts_display")]
+    pub(crate) display: String,
+
+    /// Command of `pyftsubset`.
+    #[serde(default = "default_fonts_command")]
+    #[vitrine(default = "default_fonts_command")]
+    pub(crate) command: String,
+
+    /// Fonts to self-host.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) items: Vec<FontConfig>,
+}
+
+/// Configuration for a self-hosted font.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct FontConfig {
+    /// URL of a font file (e.g. `.ttf` or `.woff2`), or of a stylesheet of
+    /// `@font-face` rules (e.g.
+    /// `https://fonts.googleapis.com/css2?family=Inter:wght@400;700`).
+    pub(crate) url: String,
+
+    /// Family name of the font (e.g. `Inter`).
+    ///
+    /// Required for font files. For stylesheets, it replaces the family names
+    /// of `@font-face` rules.
+    pub(crate) family: Option<String>,
+
+    /// Style of a font file (e.g. `italic`).
+    #[serde(default = "default_fonts_items_style")]
+    #[vitrine(default = "default_fonts_items_style")]
+    pub(crate) style: String,
+
+    /// Weight of a font file (e.g. `700`).
+    #[serde(default = "default_fonts_items_weight")]
+    #[vitrine(default = "default_fonts_items_weight")]
+    pub(crate) weight: String,
+}
+
+/// Configuration for HTML fragments.
+///
+/// Fragments are elements of rendered pages (e.g. the header or the
+/// navigation) written to separate files with stable URLs (e.g.
+/// `/fragments/header.html`), to be included by other systems when Vitrine
+/// generates only a part of a larger site.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct FragmentsConfig {
+    /// URL prefix of fragments.
+    #[serde(default = "default_fragments_url")]
+    #[vitrine(default = "default_fragments_url")]
+    pub(crate) url: String,
+
+    /// URL of the manifest that lists the fragments.
+    #[serde(default = "default_fragments_manifest_url")]
+    #[vitrine(default = "default_fragments_manifest_url")]
+    pub(crate) manifest_url: String,
+
+    /// Fragments to extract.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) items: Vec<FragmentConfig>,
+}
+
+/// Configuration for a HTML fragment.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct FragmentConfig {
+    /// Name of the fragment, which determines its URL (e.g. `header` for
+    /// `/fragments/header.html`).
+    pub(crate) name: String,
+
+    /// URL of the page from which the fragment is extracted.
+    #[serde(default = "default_fragments_items_page")]
+    #[vitrine(default = "default_fragments_items_page")]
+    pub(crate) page: String,
+
+    /// CSS selector of the element (e.g. `body > header`).
+    ///
+    /// The first matching element is extracted, including its tags.
+    pub(crate) selector: String,
+}
+
+/// Configuration for a front matter schema.
+///
+/// Pages (HTML and Markdown files) located in the section are validated
+/// during the build, after the data cascade.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct FrontMatterSchemaConfig {
+    /// Directory of the section, relative to the input directory (e.g.
+    /// `blog/`).
+    ///
+    /// An empty string applies the schema to all pages.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) section: String,
+
+    /// Field constraints, keyed by field name.
+    pub(crate) fields: HashMap<String, FrontMatterFieldConfig>,
+}
+
+/// Configuration for a front matter field.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct FrontMatterFieldConfig {
+    /// Determine whether the field must be specified.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) required: bool,
+
+    /// Expected type of the field.
+    ///
+    /// Accepted values are `string`, `number`, `integer`, `boolean`, `array`,
+    /// `object` and `date` (a string such as `2024-01-31`).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) r#type: Option<String>,
+
+    /// Allowed values of the field.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) values: Option<Vec<serde_json::Value>>,
+}
+
+/// Configuration for hosting provider files.
+///
+/// Redirects and headers are written to the configuration files of each
+/// provider: `_redirects` and `_headers` for `cloudflare` (also supported by
+/// Netlify), `netlify.toml` for `netlify`, and `vercel.json` for `vercel`.
+#[derive(Debug, Default, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct HostingConfig {
+    /// Hosting providers for which files are generated.
+    ///
+    /// Accepted values are `cloudflare`, `netlify` and `vercel`.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) providers: Vec<String>,
+
+    /// List of redirects.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) redirects: Vec<HostingRedirectConfig>,
+
+    /// List of custom headers.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) headers: Vec<HostingHeadersConfig>,
+}
+
+/// Configuration for a redirect.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct HostingRedirectConfig {
+    /// Source URL (e.g. `/old/` or `/blog/*`).
+    pub(crate) from: String,
+
+    /// Destination URL (e.g. `/new/` or `/news/:splat`).
+    pub(crate) to: String,
+
+    /// HTTP status code.
+    ///
+    /// The status `200` rewrites the URL without redirecting.
+    #[serde(default = "default_hosting_redirects_status")]
+    #[vitrine(default = "default_hosting_redirects_status")]
+    pub(crate) status: usize,
+}
+
+/// Configuration for custom headers.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct HostingHeadersConfig {
+    /// URL to which headers apply (e.g. `/*`).
+    pub(crate) url: String,
+
+    /// Header names and values.
+    pub(crate) values: HashMap<String, String>,
+}
+
+/// Configuration for images in HTML pages.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct ImagesConfig {
+    /// Determine whether missing `width` and `height` attributes should be
+    /// added to `<img>` elements referencing local images.
+    ///
+    /// This prevents layout shifts while images are loading.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) dimensions: bool,
+
+    /// Determine whether `loading="lazy"` should be added to `<img>` elements.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) lazy_loading: bool,
+
+    /// Determine whether `decoding="async"` should be added to `<img>`
+    /// elements.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) async_decoding: bool,
+
+    /// Determine whether remote images (e.g. `https://example.com/a.png`)
+    /// should be downloaded at build time and served from the site.
+    ///
+    /// Images are downloaded once with `curl` into the cache directory, and
+    /// the `src` attributes of `<img>` elements are rewritten to local URLs.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) localize_remote: bool,
+
+    /// URL prefix of downloaded remote images.
+    #[serde(default = "default_images_remote_url_prefix")]
+    #[vitrine(default = "default_images_remote_url_prefix")]
+    pub(crate) remote_url_prefix: String,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            dimensions: Default::default(),
+            lazy_loading: Default::default(),
+            async_decoding: Default::default(),
+            localize_remote: Default::default(),
+            remote_url_prefix: default_images_remote_url_prefix(),
+        }
+    }
+}
+
+/// Configuration for the import map of pages.
+///
+/// The import map is inserted at the beginning of the `<head>` of each page,
+/// in a `<script type="importmap">` element, so that bare specifiers of
+/// module scripts (e.g. `import { html } from "lit"`) are resolved by the
+/// browser in the same way when serving and in production.
+///
+/// See <https://developer.mozilla.org/en-US/docs/Web/HTML/Element/script/type/importmap>.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct ImportMapConfig {
+    /// Addresses of module specifiers (e.g. `{ "lit": "/vendor/lit.js" }`).
+    ///
+    /// Addresses starting with `/` are URLs of the site, prefixed with
+    /// `base_url`.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) imports: HashMap<String, String>,
+
+    /// Addresses of module specifiers for scripts whose URL starts with a
+    /// prefix (e.g. `{ "/legacy/": { "lit": "/vendor/lit-2.js" } }`).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) scopes: HashMap<String, HashMap<String, String>>,
+}
+
+/// Configuration for the layout engine.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct LayoutsConfig {
+    /// Name of the template variable representing the content.
+    #[serde(default = "default_layouts_content_key")]
+    #[vitrine(default = "default_layouts_content_key")]
+    pub(crate) content_key: String,
+
+    /// Name of the metadata key containing the layout name.
     #[serde(default = "default_layouts_layout_key")]
     #[vitrine(default = "default_layouts_layout_key")]
     pub(crate) layout_key: String,
@@ -296,6 +1744,15 @@ pub(crate) struct LayoutsConfig {
     #[serde(skip)]
     #[vitrine(default)]
     pub(crate) testers: HashMap<String, Function>,
+
+    /// Names of custom filters, functions and testers to memoize.
+    ///
+    /// These functions must be pure, i.e. always return the same result for
+    /// the same arguments. Their results are memoized during the build, so
+    /// that they are called once per distinct arguments.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) memoize: Vec<String>,
 }
 
 impl Default for LayoutsConfig {
@@ -307,6 +1764,7 @@ impl Default for LayoutsConfig {
             filters: Default::default(),
             functions: Default::default(),
             testers: Default::default(),
+            memoize: Default::default(),
         }
     }
 }
@@ -320,6 +1778,168 @@ pub(crate) struct NavigationConfig {
     pub(crate) navigation_key: String,
 }
 
+/// Configuration for email versions of pages.
+///
+/// Selected pages are rendered a second time under `url` (e.g. `/blog/post` as
+/// `/newsletter/blog/post.html`), with inlined CSS, absolute URLs and a
+/// fixed-width table layout, to be sent with newsletter services.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct NewsletterConfig {
+    /// URL prefix of the email versions of pages.
+    #[serde(default = "default_newsletter_url")]
+    #[vitrine(default = "default_newsletter_url")]
+    pub(crate) url: String,
+
+    /// Layout of the email versions.
+    ///
+    /// If not specified, pages are rendered with their own layout.
+    pub(crate) layout: Option<String>,
+
+    /// URLs of the pages to render.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) pages: Vec<String>,
+
+    /// Number of latest posts (i.e. pages with a date) to render.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) latest: usize,
+
+    /// Name of the collection of posts.
+    ///
+    /// If not specified, all pages with a date are posts.
+    pub(crate) collection: Option<String>,
+
+    /// Maximum width of the content, in pixels.
+    #[serde(default = "default_newsletter_width")]
+    #[vitrine(default = "default_newsletter_width")]
+    pub(crate) width: usize,
+}
+
+/// Configuration for the export of pages to PDF.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct PdfConfig {
+    /// Command of the headless browser that prints pages (e.g. `chromium`,
+    /// `google-chrome`).
+    #[serde(default = "default_pdf_browser")]
+    #[vitrine(default = "default_pdf_browser")]
+    pub(crate) browser: String,
+}
+
+impl Default for PdfConfig {
+    fn default() -> Self {
+        Self {
+            browser: default_pdf_browser(),
+        }
+    }
+}
+
+/// Configuration for precompressed output files.
+///
+/// Compressed files are written next to text output files (HTML, CSS, JS, SVG
+/// and XML), e.g. `index.html.gz` and `index.html.br`, so that web servers can
+/// serve them directly (e.g. with nginx's `gzip_static`).
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct PrecompressConfig {
+    /// Determine whether gzip files (`.gz`) should be written.
+    #[serde(default = "default_precompress_gzip")]
+    #[vitrine(default = "default_precompress_gzip")]
+    pub(crate) gzip: bool,
+
+    /// Determine whether Brotli files (`.br`) should be written.
+    #[serde(default = "default_precompress_brotli")]
+    #[vitrine(default = "default_precompress_brotli")]
+    pub(crate) brotli: bool,
+
+    /// Minimum size of files to compress, in bytes.
+    ///
+    /// Compressing small files is rarely worth it.
+    #[serde(default = "default_precompress_min_size")]
+    #[vitrine(default = "default_precompress_min_size")]
+    pub(crate) min_size: usize,
+}
+
+impl Default for PrecompressConfig {
+    fn default() -> Self {
+        Self {
+            gzip: default_precompress_gzip(),
+            brotli: default_precompress_brotli(),
+            min_size: default_precompress_min_size(),
+        }
+    }
+}
+
+/// Configuration for preload hints of critical assets.
+///
+/// Each option is the hint inserted for an asset type: `preload` (fetch the
+/// asset early for the current page), `prefetch` (fetch the asset with a low
+/// priority for next pages) or `none`.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct PreloadConfig {
+    /// Hint for the first stylesheet of each page.
+    #[serde(default = "default_preload_stylesheet")]
+    #[vitrine(default = "default_preload_stylesheet")]
+    pub(crate) stylesheet: String,
+
+    /// Hint for the first image of each page (e.g. a hero image).
+    #[serde(default = "default_preload_image")]
+    #[vitrine(default = "default_preload_image")]
+    pub(crate) image: String,
+
+    /// Hint for the fonts referenced by the first stylesheet of each page.
+    #[serde(default = "default_preload_fonts")]
+    #[vitrine(default = "default_preload_fonts")]
+    pub(crate) fonts: String,
+}
+
+/// Configuration for a command run before builds.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct BeforeBuildCommandConfig {
+    /// Shell command.
+    pub(crate) command: String,
+
+    /// Globs of the files written by the command, relative to the input
+    /// directory (e.g. `assets/*.css`).
+    ///
+    /// Changes to these files do not trigger rebuilds in watch mode, which
+    /// would otherwise run the command again in an endless loop.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) outputs: Vec<String>,
+}
+
+/// Configuration for HTML sanitization.
+///
+/// Sanitization applies to pages rendered from Markdown, to prevent script
+/// injection from untrusted content (e.g. remote data sources).
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct SanitizeConfig {
+    /// Allowed tags.
+    ///
+    /// Other elements are replaced by their content. Elements that may run
+    /// scripts (e.g. `<script>`, `<iframe>`) are always removed.
+    #[serde(default = "default_sanitize_tags")]
+    #[vitrine(default = "default_sanitize_tags")]
+    pub(crate) tags: Vec<String>,
+
+    /// Allowed attributes.
+    ///
+    /// Event handler attributes (e.g. `onclick`) are always removed, as well
+    /// as URLs with schemes other than `http`, `https`, `mailto` and `tel`.
+    #[serde(default = "default_sanitize_attributes")]
+    #[vitrine(default = "default_sanitize_attributes")]
+    pub(crate) attributes: Vec<String>,
+}
+
+impl Default for SanitizeConfig {
+    fn default() -> Self {
+        Self {
+            tags: default_sanitize_tags(),
+            attributes: default_sanitize_attributes(),
+        }
+    }
+}
+
 /// Configuration object for sitemap generation.
 #[derive(Debug, Default, Deserialize, FromJs, FromLua, FromRhai)]
 pub(crate) struct SitemapConfig {
@@ -338,6 +1958,26 @@ pub(crate) struct SitemapConfig {
     #[serde(default = "default_sitemap_url")]
     #[vitrine(default = "default_sitemap_url")]
     pub(crate) url: String,
+
+    /// Determine whether documents (see [`DocumentsConfig::formats`]) should
+    /// be listed in the sitemap, in addition to pages.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) documents: bool,
+}
+
+/// Configuration for SVG optimization.
+///
+/// SVG images are optimized when [`Config::minify`] is enabled.
+#[derive(Debug, Default, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct SvgConfig {
+    /// Number of decimal places of coordinates in path data (`d` and `points`
+    /// attributes).
+    ///
+    /// If set to `None`, coordinates are not rounded.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) precision: Option<usize>,
 }
 
 /// Configuration for syntax highlight.
@@ -358,7 +1998,26 @@ pub(crate) struct SyntaxHighlightConfig {
     #[vitrine(default)]
     pub(crate) css_prefix: String,
 
+    /// Determine whether code blocks should display line numbers.
+    ///
+    /// Line numbers can also be enabled for a single code block with the
+    /// `linenos` annotation (e.g. ```` ```rust,linenos ````).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) line_numbers: bool,
+
+    /// Determine whether code blocks should include a copy button.
+    ///
+    /// Only the `<button>` element is generated. Scripts and styles must be
+    /// provided by layouts.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) copy_button: bool,
+
     /// Formatters for syntax highlight.
+    ///
+    /// The result is wrapped in a `<figure>` element if the code block has a
+    /// title or a copy button.
     #[serde(skip)]
     #[vitrine(default)]
     pub(crate) formatter: Option<Function>,
@@ -367,6 +2026,16 @@ pub(crate) struct SyntaxHighlightConfig {
     #[serde(default)]
     #[vitrine(default)]
     pub(crate) stylesheets: Vec<SyntaxHighlightStylesheetConfig>,
+
+    /// Directory of additional syntax definitions (`.sublime-syntax` files).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) syntaxes_dir: Option<PathBuf>,
+
+    /// Directory of additional themes (`.tmTheme` files).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) themes_dir: Option<PathBuf>,
 }
 
 /// Configuration for a syntax highlight CSS stylesheet.
@@ -382,14 +2051,162 @@ pub(crate) struct SyntaxHighlightStylesheetConfig {
     /// See <https://docs.rs/syntect/latest/syntect/highlighting/struct.ThemeSet.html>
     pub(crate) theme: String,
 
+    /// Theme name used when the user prefers a dark color scheme.
+    ///
+    /// Its rules are wrapped in a `@media (prefers-color-scheme: dark)` block,
+    /// and also apply when the root element has the `data-theme="dark"`
+    /// attribute (see [`ColorSchemeConfig::attribute`]).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) dark_theme: Option<String>,
+
+    /// Determine whether the stylesheet should be linked in the `<head>` of
+    /// HTML pages.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) link: bool,
+
     /// Output URL of the stylesheet.
     pub(crate) url: String,
 }
 
+/// Configuration for Tailwind CSS.
+///
+/// Stylesheets are compiled by the standalone executable of Tailwind CSS (v3),
+/// which does not require Node.js. Utility classes are generated for the
+/// classes used in the rendered pages of the site.
+///
+/// Tailwind CSS v4 is not supported, since it removed the `--content` option.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct TailwindConfig {
+    /// URLs of the stylesheets to compile (e.g. `/style.css`).
+    pub(crate) stylesheets: Vec<String>,
+
+    /// Tailwind CSS executable.
+    #[serde(default = "default_tailwind_command")]
+    #[vitrine(default = "default_tailwind_command")]
+    pub(crate) command: String,
+
+    /// Tailwind CSS configuration file (e.g. `tailwind.config.js`), relative
+    /// to the current directory.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) config_file: Option<PathBuf>,
+}
+
+/// Configuration for token substitution.
+///
+/// Tokens (e.g. `%%VERSION%%`) are replaced by their values in text outputs
+/// (pages, stylesheets, scripts, feeds, etc.) before they are written. Values
+/// are escaped according to the format of the output (e.g. HTML or JSON). A
+/// token preceded by a backslash (e.g. `\%%VERSION%%`) is output verbatim,
+/// without the backslash.
+///
+/// The `BUILD_DATE` token is always defined, as the date of the build (or
+/// `SOURCE_DATE_EPOCH` for reproducible builds) in RFC 3339 format.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct TokensConfig {
+    /// Delimiter before the name of a token.
+    #[serde(default = "default_tokens_prefix")]
+    #[vitrine(default = "default_tokens_prefix")]
+    pub(crate) prefix: String,
+
+    /// Delimiter after the name of a token.
+    #[serde(default = "default_tokens_suffix")]
+    #[vitrine(default = "default_tokens_suffix")]
+    pub(crate) suffix: String,
+
+    /// Values of tokens, keyed by name (e.g. `VERSION`).
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) values: HashMap<String, String>,
+
+    /// Environment variables to define as tokens of the same name (e.g.
+    /// `GITHUB_SHA`).
+    ///
+    /// The build fails if one of them is not set.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) env: Vec<String>,
+}
+
+/// Configuration for TypeScript and JSX scripts.
+///
+/// Scripts (`.ts`, `.tsx` and `.jsx` files) are compiled to JavaScript: types
+/// are removed, and JSX elements are transformed to function calls (e.g.
+/// `React.createElement("p", null, "Hello")`). Types are not checked.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct TypescriptConfig {
+    /// Function called to create JSX elements (e.g. `h` for Preact).
+    #[serde(default = "default_typescript_jsx_factory")]
+    #[vitrine(default = "default_typescript_jsx_factory")]
+    pub(crate) jsx_factory: String,
+
+    /// Component used for JSX fragments (e.g. `Fragment` for Preact).
+    #[serde(default = "default_typescript_jsx_fragment")]
+    #[vitrine(default = "default_typescript_jsx_fragment")]
+    pub(crate) jsx_fragment: String,
+
+    /// Determine whether inline source maps should be appended to compiled
+    /// scripts.
+    ///
+    /// Source maps are always appended when serving the site.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) source_maps: bool,
+}
+
+impl Default for TypescriptConfig {
+    fn default() -> Self {
+        Self {
+            jsx_factory: default_typescript_jsx_factory(),
+            jsx_fragment: default_typescript_jsx_fragment(),
+            source_maps: Default::default(),
+        }
+    }
+}
+
+/// Configuration for unused assets detection.
+///
+/// Assets are input files other than pages (e.g. images, stylesheets). An
+/// asset is unused when no page or stylesheet references it.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct UnusedAssetsConfig {
+    /// Determine whether unused assets should be skipped, instead of only
+    /// being reported.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) skip: bool,
+
+    /// Glob patterns of assets that are always kept (e.g. `robots.txt`).
+    ///
+    /// Patterns are matched against paths relative to the input directory.
+    #[serde(default = "default_unused_assets_keep")]
+    #[vitrine(default = "default_unused_assets_keep")]
+    pub(crate) keep: Vec<String>,
+}
+
+/// Configuration for sending webmentions.
+///
+/// Webmentions notify the sites linked from pages. They are sent with the
+/// `webmention send` command, and optionally after deployments.
+#[derive(Debug, Deserialize, FromJs, FromLua, FromRhai)]
+pub(crate) struct WebmentionConfig {
+    /// File storing the links already mentioned by each page.
+    #[serde(default = "default_webmention_state_file")]
+    #[vitrine(default = "default_webmention_state_file")]
+    pub(crate) state_file: PathBuf,
+
+    /// Determine whether webmentions should be sent after deploying the site.
+    #[serde(default)]
+    #[vitrine(default)]
+    pub(crate) send_after_deploy: bool,
+}
+
 /// Load configuration from a default file (e.g. `vitrine.config.json`).
 ///
 /// Default file names are specified in [`DEFAULT_CONFIG_FILE_NAMES`].
-pub(super) fn load_config_default() -> Result<Config, Error> {
+pub fn load_config_default() -> Result<Config, Error> {
     Ok(DEFAULT_CONFIG_FILE_NAMES
         .into_iter()
         .map(|file_name| Path::new(file_name))
@@ -400,7 +2217,7 @@ pub(super) fn load_config_default() -> Result<Config, Error> {
 }
 
 /// Load configuration from a file.
-pub(super) fn load_config<P>(config_path: P) -> Result<Config, Error>
+pub fn load_config<P>(config_path: P) -> Result<Config, Error>
 where
     P: AsRef<Path>,
 {
@@ -427,16 +2244,90 @@ where
             source: error.into(),
         })?;
 
+    let config = Config {
+        theme: fetch_theme(config.theme, config.cache_dir.as_deref())?,
+        ..config
+    };
+
+    let config = merge_theme_config(config_path, config)?;
+
     Ok(Config {
         config_path: Some(config_path.to_owned()),
         ..config
     })
 }
 
+/// Replace the URL of a remote theme with the directory it is cloned in.
+fn fetch_theme(theme: Option<PathBuf>, cache_dir: Option<&Path>) -> Result<Option<PathBuf>, Error> {
+    match theme {
+        Some(theme) if crate::theme::is_remote(&theme) => {
+            crate::theme::fetch(&theme.to_string_lossy(), cache_dir).map(Some)
+        },
+        theme => Ok(theme),
+    }
+}
+
+/// Merge the default configuration of the theme, if any, with the site
+/// configuration.
+///
+/// Values of the site configuration take precedence, and objects are merged
+/// recursively. The site configuration must be a data file (e.g. JSON), since
+/// the fields it defines cannot be determined from a script.
+fn merge_theme_config(config_path: &Path, config: Config) -> Result<Config, Error> {
+    let Some(theme_config_path) = config.theme.as_ref().and_then(|theme| {
+        THEME_CONFIG_FILE_NAMES
+            .into_iter()
+            .map(|file_name| theme.join(file_name))
+            .find(|path| path.exists())
+    }) else {
+        return Ok(config);
+    };
+
+    let site_data = match read_config_data(config_path) {
+        Some(site_data) => site_data.map_err(|error| Error::LoadConfig {
+            config_path: Some(config_path.to_owned()),
+            source: error,
+        })?,
+        None => {
+            tracing::warn!(
+                "Ignoring theme configuration {:?}, since {:?} is not a data file",
+                theme_config_path,
+                config_path
+            );
+            return Ok(config);
+        },
+    };
+
+    tracing::info!("Loading theme configuration from {:?}", theme_config_path);
+
+    read_config_data(&theme_config_path)
+        .unwrap_or_else(|| Err(anyhow::anyhow!("Unknown configuration file extension")))
+        .and_then(|theme_data| {
+            let data = crate::util::data::deep_merge(theme_data, site_data);
+            Ok(serde_json::from_value(data)?)
+        })
+        .map_err(|error| Error::LoadConfig {
+            config_path: Some(theme_config_path.to_owned()),
+            source: error,
+        })
+}
+
+/// Read a configuration data file (JSON, TOML or YAML) as a value.
+///
+/// Returns `None` if the file is not a data file (e.g. a script).
+fn read_config_data(config_path: &Path) -> Option<anyhow::Result<serde_json::Value>> {
+    match config_path.extension().and_then(|v| v.to_str()) {
+        Some("json") => Some(crate::util::data::json::read_file(config_path)),
+        Some("toml") => Some(crate::util::data::toml::read_file(config_path)),
+        Some("yaml") => Some(crate::util::data::yaml::read_file(config_path)),
+        _ => None,
+    }
+}
+
 /// Normalize the configuration.
 ///
 /// This function normalizes paths to make them absolute.
-pub(super) fn normalize_config(config: Config) -> Result<Config, Error> {
+pub fn normalize_config(config: Config) -> Result<Config, Error> {
     let config_path = config.config_path;
 
     // Use current directory's path to create absolute paths
@@ -468,6 +2359,20 @@ pub(super) fn normalize_config(config: Config) -> Result<Config, Error> {
             )),
         })?;
 
+    // Canonicalize additional input directories
+    let extra_input_dirs = config
+        .extra_input_dirs
+        .iter()
+        .map(|dir| dir.canonicalize())
+        .collect::<Result<_, _>>()
+        .map_err(|error| Error::LoadConfig {
+            config_path: config_path.to_owned(),
+            source: anyhow::anyhow!(error).context(format!(
+                "While normalizing extra_input_dirs: {:?}",
+                config.extra_input_dirs
+            )),
+        })?;
+
     // Normalize output directory
     let output_dir = config.output_dir.map(|output_dir| {
         // We don't use `canonicalize()` since the output directory might not exist yet
@@ -479,6 +2384,17 @@ pub(super) fn normalize_config(config: Config) -> Result<Config, Error> {
         .normalize()
     });
 
+    // Normalize cache directory
+    let cache_dir = config.cache_dir.map(|cache_dir| {
+        // We don't use `canonicalize()` since the cache directory might not exist yet
+        if cache_dir.is_absolute() {
+            cache_dir
+        } else {
+            current_dir.join(cache_dir)
+        }
+        .normalize()
+    });
+
     // Canonicalize data directory
     let data_dir = config
         .data_dir
@@ -505,6 +2421,76 @@ pub(super) fn normalize_config(config: Config) -> Result<Config, Error> {
             )),
         })?;
 
+    // Canonicalize includes directory
+    let includes_dir = config
+        .includes_dir
+        .as_ref()
+        .map(|dir| dir.canonicalize())
+        .transpose()
+        .map_err(|error| Error::LoadConfig {
+            config_path: config_path.to_owned(),
+            source: anyhow::anyhow!(error).context(format!(
+                "While normalizing includes_dir: {:?}",
+                config.includes_dir
+            )),
+        })?;
+
+    // Canonicalize theme directory, cloning remote themes if not already done
+    let theme = fetch_theme(config.theme.to_owned(), cache_dir.as_deref())?
+        .map(|dir| dir.canonicalize())
+        .transpose()
+        .map_err(|error| Error::LoadConfig {
+            config_path: config_path.to_owned(),
+            source: anyhow::anyhow!(error)
+                .context(format!("While normalizing theme: {:?}", config.theme)),
+        })?;
+
+    // Canonicalize syntax highlight directories
+    let syntaxes_dir = config
+        .syntax_highlight
+        .syntaxes_dir
+        .as_ref()
+        .map(|dir| dir.canonicalize())
+        .transpose()
+        .map_err(|error| Error::LoadConfig {
+            config_path: config_path.to_owned(),
+            source: anyhow::anyhow!(error).context(format!(
+                "While normalizing syntax_highlight.syntaxes_dir: {:?}",
+                config.syntax_highlight.syntaxes_dir
+            )),
+        })?;
+
+    let themes_dir = config
+        .syntax_highlight
+        .themes_dir
+        .as_ref()
+        .map(|dir| dir.canonicalize())
+        .transpose()
+        .map_err(|error| Error::LoadConfig {
+            config_path: config_path.to_owned(),
+            source: anyhow::anyhow!(error).context(format!(
+                "While normalizing syntax_highlight.themes_dir: {:?}",
+                config.syntax_highlight.themes_dir
+            )),
+        })?;
+
+    // Make copied paths absolute, keeping glob patterns
+    let copy_paths = config
+        .copy_paths
+        .into_iter()
+        .map(|copy_path| CopyPathConfig {
+            from: if Path::new(&copy_path.from).is_absolute() {
+                copy_path.from
+            } else {
+                current_dir
+                    .join(&copy_path.from)
+                    .to_string_lossy()
+                    .into_owned()
+            },
+            ..copy_path
+        })
+        .collect();
+
     // Paths to ignore from input files
     let mut input_ignore_paths = config.input_ignore_paths;
 
@@ -520,6 +2506,12 @@ pub(super) fn normalize_config(config: Config) -> Result<Config, Error> {
         input_ignore_paths.push(output_dir.to_owned());
     }
 
+    // Exclude cache directory
+    if let Some(cache_dir) = cache_dir.as_ref() {
+        debug_assert!(cache_dir.is_absolute());
+        input_ignore_paths.push(cache_dir.to_owned());
+    }
+
     // Exclude data directory
     if let Some(data_dir) = data_dir.as_ref() {
         debug_assert!(data_dir.is_absolute());
@@ -532,13 +2524,49 @@ pub(super) fn normalize_config(config: Config) -> Result<Config, Error> {
         input_ignore_paths.push(layouts_dir.to_owned());
     }
 
+    // Exclude includes directory
+    if let Some(includes_dir) = includes_dir.as_ref() {
+        debug_assert!(includes_dir.is_absolute());
+        input_ignore_paths.push(includes_dir.to_owned());
+    }
+
+    // Exclude the lock file of remote themes
+    input_ignore_paths.push(current_dir.join(crate::theme::LOCK_FILE_NAME));
+
+    // Exclude theme directory and theme configuration files
+    if let Some(theme) = theme.as_ref() {
+        debug_assert!(theme.is_absolute());
+        input_ignore_paths.push(theme.to_owned());
+        input_ignore_paths.extend(
+            THEME_CONFIG_FILE_NAMES
+                .into_iter()
+                .map(|file_name| theme.join(file_name)),
+        );
+    }
+
+    // Exclude syntax highlight directories
+    for dir in syntaxes_dir.iter().chain(themes_dir.iter()) {
+        debug_assert!(dir.is_absolute());
+        input_ignore_paths.push(dir.to_owned());
+    }
+
     Ok(Config {
         config_path,
         input_dir,
+        extra_input_dirs,
         output_dir,
+        cache_dir,
         data_dir,
         layouts_dir,
+        includes_dir,
+        theme,
+        copy_paths,
         input_ignore_paths,
+        syntax_highlight: SyntaxHighlightConfig {
+            syntaxes_dir,
+            themes_dir,
+            ..config.syntax_highlight
+        },
         ..config
     })
 }
@@ -546,8 +2574,453 @@ pub(super) fn normalize_config(config: Config) -> Result<Config, Error> {
 /// Validate the configuration.
 ///
 /// This function checks if the input directories are located inside the output
-/// directory.
-pub(super) fn validate_config(config: &Config) -> Result<(), Error> {
+/// directory, and if URLs are valid.
+pub fn validate_config(config: &Config) -> Result<(), Error> {
+    Url::parse(&config.base_url).map_err(|error| Error::LoadConfig {
+        config_path: config.config_path.to_owned(),
+        source: anyhow::anyhow!(error)
+            .context(format!("While validating base_url: {:?}", config.base_url)),
+    })?;
+
+    if let Some(site_url) = config.site_url.as_ref() {
+        let url = Url::parse(site_url).map_err(|error| Error::LoadConfig {
+            config_path: config.config_path.to_owned(),
+            source: anyhow::anyhow!(error)
+                .context(format!("While validating site_url: {:?}", site_url)),
+        })?;
+
+        if url.scheme.is_none()
+            || url.authority.is_none()
+            || !matches!(url.path.as_str(), "" | "/")
+            || url.query.is_some()
+            || url.fragment.is_some()
+        {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "While validating site_url: {:?} must be an absolute URL without path (e.g. \
+                     \"https://example.com\")",
+                    site_url
+                ),
+            });
+        }
+    }
+
+    config
+        .slug_strategy
+        .parse::<SlugStrategy>()
+        .map_err(|error| Error::LoadConfig {
+            config_path: config.config_path.to_owned(),
+            source: error.context("While validating slug_strategy"),
+        })?;
+
+    config
+        .symlinks
+        .parse::<SymlinkPolicy>()
+        .map_err(|error| Error::LoadConfig {
+            config_path: config.config_path.to_owned(),
+            source: error.context("While validating symlinks"),
+        })?;
+
+    if let Some(timezone) = config.timezone.as_ref() {
+        parse_offset(timezone).map_err(|error| Error::LoadConfig {
+            config_path: config.config_path.to_owned(),
+            source: error.context("While validating timezone"),
+        })?;
+    }
+
+    for (name, level) in config.diagnostics.iter() {
+        if find_rule(name).is_none() {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!("While validating diagnostics: unknown rule {:?}", name),
+            });
+        }
+
+        level.parse::<Level>().map_err(|error| Error::LoadConfig {
+            config_path: config.config_path.to_owned(),
+            source: error.context(format!("While validating diagnostics.{name}")),
+        })?;
+    }
+
+    for before_build in config.run_before_build.iter() {
+        for pattern in before_build.outputs.iter() {
+            Glob::new(pattern).map_err(|error| Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(error).context(format!(
+                    "While validating run_before_build.outputs: {:?}",
+                    pattern
+                )),
+            })?;
+        }
+    }
+
+    if let Some(import_map) = config.import_map.as_ref() {
+        let imports = import_map
+            .imports
+            .iter()
+            .chain(import_map.scopes.values().flatten());
+
+        for (specifier, address) in imports {
+            // Specifiers ending with `/` map prefixes, so their addresses must
+            // be prefixes too
+            if specifier.is_empty() || (specifier.ends_with('/') && !address.ends_with('/')) {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating import_map: invalid address {:?} for specifier {:?}",
+                        address,
+                        specifier
+                    ),
+                });
+            }
+        }
+    }
+
+    if let Some(preload) = config.preload.as_ref() {
+        for (key, hint) in [
+            ("stylesheet", &preload.stylesheet),
+            ("image", &preload.image),
+            ("fonts", &preload.fonts),
+        ] {
+            hint.parse::<PreloadHint>()
+                .map_err(|error| Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: error.context(format!("While validating preload.{key}")),
+                })?;
+        }
+    }
+
+    if let Some(tokens) = config.tokens.as_ref() {
+        if tokens.prefix.is_empty() || tokens.suffix.is_empty() {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!("tokens.prefix and tokens.suffix must not be empty"),
+            });
+        }
+    }
+
+    if let Some(activitypub) = config.activitypub.as_ref() {
+        if activitypub.username.is_empty() {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!("activitypub.username must not be empty"),
+            });
+        }
+
+        if site_base_url(config.site_url.as_deref(), &config.base_url).is_none() {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "site_url or base_url must specify the domain of the site for activitypub"
+                ),
+            });
+        }
+
+        for (key, url) in [
+            ("actor_url", &activitypub.actor_url),
+            ("inbox_url", &activitypub.inbox_url),
+            ("outbox_url", &activitypub.outbox_url),
+        ] {
+            if !url.starts_with('/') {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating activitypub.{}: {:?} must start with /",
+                        key,
+                        url
+                    ),
+                });
+            }
+        }
+    }
+
+    if let Some(color_scheme) = config.color_scheme.as_ref() {
+        if !color_scheme.url.starts_with('/') {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "While validating color_scheme.url: {:?} must start with /",
+                    color_scheme.url
+                ),
+            });
+        }
+
+        // The attribute is used unquoted in CSS selectors, so it must be a
+        // valid identifier (e.g. `data-theme`)
+        if !color_scheme
+            .attribute
+            .starts_with(|c: char| c.is_ascii_alphabetic())
+            || !color_scheme
+                .attribute
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "While validating color_scheme.attribute: invalid attribute name {:?}",
+                    color_scheme.attribute
+                ),
+            });
+        }
+
+        for (name, value) in color_scheme.light.iter().chain(color_scheme.dark.iter()) {
+            let name = name.trim_start_matches("--");
+
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                || value.contains([';', '{', '}'])
+            {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating color_scheme: invalid variable {:?}: {:?}",
+                        name,
+                        value
+                    ),
+                });
+            }
+        }
+    }
+
+    if let Some(fonts) = config.fonts.as_ref() {
+        if config.cache_dir.is_none() {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!("fonts requires cache_dir"),
+            });
+        }
+
+        for (key, url) in [
+            ("fonts.url", &fonts.url),
+            ("fonts.stylesheet_url", &fonts.stylesheet_url),
+        ] {
+            if !url.starts_with('/') {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating {}: {:?} must start with /",
+                        key,
+                        url
+                    ),
+                });
+            }
+        }
+
+        for item in fonts.items.iter() {
+            if font_type(&item.url).is_some() && item.family.is_none() {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating fonts.items: font file {:?} requires a family",
+                        item.url
+                    ),
+                });
+            }
+        }
+    }
+
+    if let Some(fragments) = config.fragments.as_ref() {
+        for (key, url) in [
+            ("fragments.url", &fragments.url),
+            ("fragments.manifest_url", &fragments.manifest_url),
+        ] {
+            if !url.starts_with('/') {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating {}: {:?} must start with /",
+                        key,
+                        url
+                    ),
+                });
+            }
+        }
+
+        for item in fragments.items.iter() {
+            if item.name.is_empty() || item.name.contains(['/', '\\']) {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating fragments.items.name: {:?} must be a non-empty file name",
+                        item.name
+                    ),
+                });
+            }
+
+            if let Err(error) = item.selector.parse::<lol_html::Selector>() {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(error).context(format!(
+                        "While validating fragments.items.selector: {:?}",
+                        item.selector
+                    )),
+                });
+            }
+        }
+    }
+
+    if let Some(newsletter) = config.newsletter.as_ref() {
+        if !newsletter.url.starts_with('/') || newsletter.url.trim_matches('/').is_empty() {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "While validating newsletter.url: {:?} must start with / and not be /",
+                    newsletter.url
+                ),
+            });
+        }
+
+        if site_base_url(config.site_url.as_deref(), &config.base_url).is_none() {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "site_url or base_url must specify the domain of the site for newsletter"
+                ),
+            });
+        }
+    }
+
+    if let Some(api) = config.api.as_ref() {
+        if !api.url_prefix.starts_with('/') {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "While validating api.url_prefix: {:?} must start with /",
+                    api.url_prefix
+                ),
+            });
+        }
+
+        if api.paginate == 0 {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!("api.paginate must be greater than 0"),
+            });
+        }
+    }
+
+    if let Some(blogroll) = config.blogroll.as_ref() {
+        for (key, url) in [("url", &blogroll.url), ("opml_url", &blogroll.opml_url)] {
+            if !url.starts_with('/') {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating blogroll.{}: {:?} must start with /",
+                        key,
+                        url
+                    ),
+                });
+            }
+        }
+    }
+
+    for collection in config.collections.iter() {
+        if let Some(url_prefix) = collection.url_prefix.as_ref() {
+            if !url_prefix.starts_with('/') {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating collections.url_prefix: {:?} must start with /",
+                        url_prefix
+                    ),
+                });
+            }
+        }
+
+        if collection.paginate == Some(0) {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!("collections.paginate must be greater than 0"),
+            });
+        }
+    }
+
+    if let Some(deploy) = config.deploy.as_ref() {
+        deploy
+            .target
+            .parse::<DeployTarget>()
+            .map_err(|error| Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: error.context("While validating deploy.target"),
+            })?;
+    }
+
+    if let Some(events) = config.events.as_ref() {
+        if !events.url.starts_with('/') {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "While validating events.url: {:?} must start with /",
+                    events.url
+                ),
+            });
+        }
+    }
+
+    if let Some(hosting) = config.hosting.as_ref() {
+        for provider in hosting.providers.iter() {
+            let provider =
+                provider
+                    .parse::<HostingProvider>()
+                    .map_err(|error| Error::LoadConfig {
+                        config_path: config.config_path.to_owned(),
+                        source: error.context("While validating hosting.providers"),
+                    })?;
+
+            // Files of hosting providers already include the policies
+            if let Some(headers_url) = config
+                .content_security_policy
+                .as_ref()
+                .and_then(|csp| csp.headers_url.as_ref())
+                .filter(|headers_url| provider.file_urls().contains(&headers_url.as_str()))
+            {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!(
+                        "While validating content_security_policy.headers_url: {:?} is also \
+                         generated by hosting, which includes the policies",
+                        headers_url
+                    ),
+                });
+            }
+        }
+    }
+
+    if config.images.localize_remote {
+        if config.cache_dir.is_none() {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!("images.localize_remote requires cache_dir"),
+            });
+        }
+
+        let url_prefix = &config.images.remote_url_prefix;
+
+        if !url_prefix.starts_with('/') {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!(
+                    "While validating images.remote_url_prefix: {:?} must start with /",
+                    url_prefix
+                ),
+            });
+        }
+    }
+
+    if let Some(sitemap) = config.sitemap.as_ref() {
+        Url::parse(&sitemap.url_prefix).map_err(|error| Error::LoadConfig {
+            config_path: config.config_path.to_owned(),
+            source: anyhow::anyhow!(error).context(format!(
+                "While validating sitemap.url_prefix: {:?}",
+                sitemap.url_prefix
+            )),
+        })?;
+    }
+
     if let Some(output_dir) = config.output_dir.as_ref() {
         // Protection against overwriting input files
         if config.input_dir.starts_with(output_dir) {
@@ -557,6 +3030,17 @@ pub(super) fn validate_config(config: &Config) -> Result<(), Error> {
             });
         }
 
+        if config
+            .extra_input_dirs
+            .iter()
+            .any(|dir| dir.starts_with(output_dir))
+        {
+            return Err(Error::LoadConfig {
+                config_path: config.config_path.to_owned(),
+                source: anyhow::anyhow!("extra_input_dirs must be located outside output_dir"),
+            });
+        }
+
         // Protection against overwriting data files
         if let Some(data_dir) = config.data_dir.as_ref() {
             if data_dir.starts_with(output_dir) {
@@ -576,6 +3060,26 @@ pub(super) fn validate_config(config: &Config) -> Result<(), Error> {
                 });
             }
         }
+
+        // Protection against overwriting included files
+        if let Some(includes_dir) = config.includes_dir.as_ref() {
+            if includes_dir.starts_with(output_dir) {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!("includes_dir must be located outside output_dir"),
+                });
+            }
+        }
+
+        // Protection against overwriting theme files
+        if let Some(theme) = config.theme.as_ref() {
+            if theme.starts_with(output_dir) {
+                return Err(Error::LoadConfig {
+                    config_path: config.config_path.to_owned(),
+                    source: anyhow::anyhow!("theme must be located outside output_dir"),
+                });
+            }
+        }
     }
 
     Ok(())
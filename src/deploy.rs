@@ -0,0 +1,264 @@
+//! Deploy the site.
+//!
+//! Each submodule implements a deployment target. Targets call external
+//! commands (e.g. `rsync`, `aws`, `git`) under the hood.
+
+mod github_pages;
+mod rsync;
+mod s3;
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::{config::Config, error::Error, util::file_content::FileContent};
+
+/// Name of the manifest file stored along deployed files.
+const MANIFEST_FILE_NAME: &str = ".vitrine-manifest.json";
+
+/// Deployment target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DeployTarget {
+    /// Synchronize files with `rsync` (e.g. over SSH).
+    Rsync,
+
+    /// Upload files to a S3-compatible bucket with the `aws` command.
+    S3,
+
+    /// Push files to a git branch (e.g. `gh-pages`).
+    GithubPages,
+}
+
+impl std::str::FromStr for DeployTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "rsync" => Ok(Self::Rsync),
+            "s3" => Ok(Self::S3),
+            "github_pages" => Ok(Self::GithubPages),
+            _ => Err(anyhow::anyhow!(
+                "Unknown deploy target {:?} (expected \"rsync\", \"s3\" or \"github_pages\")",
+                value
+            )),
+        }
+    }
+}
+
+/// Content hashes of output files.
+///
+/// Files are keyed by their path relative to the output directory (e.g.
+/// `blog/index.html`), with `/` as separator.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) struct Manifest(BTreeMap<String, String>);
+
+impl Manifest {
+    /// Compute the manifest of the files in a directory.
+    ///
+    /// Files of at least `mmap_threshold` bytes are memory-mapped to be
+    /// hashed.
+    pub(crate) fn from_dir<P>(dir: P, mmap_threshold: Option<usize>) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+
+        let mut files = BTreeMap::new();
+
+        for entry in WalkDir::new(dir) {
+            let entry = entry?;
+
+            if !entry.file_type().is_file() || entry.file_name() == MANIFEST_FILE_NAME {
+                continue;
+            }
+
+            let path = entry
+                .path()
+                .strip_prefix(dir)?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let content = FileContent::read(entry.path(), mmap_threshold)?;
+
+            files.insert(path, format!("{:x}", Sha256::digest(&*content)));
+        }
+
+        Ok(Self(files))
+    }
+
+    /// Read a manifest from a file.
+    ///
+    /// If the file does not exist, nothing was deployed yet, and an empty
+    /// manifest is returned.
+    pub(crate) fn read<P>(path: P) -> anyhow::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Write a manifest to a file.
+    pub(crate) fn write<P>(&self, path: P) -> anyhow::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(std::fs::write(path, serde_json::to_string_pretty(self)?)?)
+    }
+
+    /// Compare with the manifest of a previous deployment.
+    pub(crate) fn diff(&self, previous: &Manifest) -> ManifestDiff {
+        let upload = self
+            .0
+            .iter()
+            .filter(|(path, hash)| previous.0.get(*path) != Some(*hash))
+            .map(|(path, _)| path.to_owned())
+            .collect();
+
+        let delete = previous
+            .0
+            .keys()
+            .filter(|path| !self.0.contains_key(*path))
+            .map(|path| path.to_owned())
+            .collect();
+
+        ManifestDiff { upload, delete }
+    }
+}
+
+/// Changes between two [`Manifest`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct ManifestDiff {
+    /// Paths of files that are new or modified.
+    pub(crate) upload: Vec<String>,
+
+    /// Paths of files that no longer exist.
+    pub(crate) delete: Vec<String>,
+}
+
+/// Deploy the output directory.
+///
+/// The site must be built before calling this function. If `dry_run` is
+/// `true`, changes are displayed but not applied. If `diff_path` is specified,
+/// the changes are written to this file as JSON.
+///
+/// Changes are computed from the manifest of the previous deployment, which is
+/// read from `deploy.state_file` if specified. Otherwise, the `s3` target
+/// fetches the manifest stored in the bucket, and other targets consider all
+/// files as new.
+pub(super) fn deploy(
+    config: &Config,
+    dry_run: bool,
+    diff_path: Option<&Path>,
+) -> Result<(), Error> {
+    let Some(deploy_config) = config.deploy.as_ref() else {
+        return Err(Error::Deploy {
+            source: anyhow::anyhow!("No deploy configuration specified"),
+        });
+    };
+
+    let Some(output_dir) = config.output_dir.as_ref() else {
+        return Err(Error::Deploy {
+            source: anyhow::anyhow!("No output directory specified"),
+        });
+    };
+
+    let target: DeployTarget = deploy_config
+        .target
+        .parse()
+        .map_err(|error| Error::Deploy { source: error })?;
+
+    let manifest =
+        Manifest::from_dir(output_dir, config.mmap_threshold).map_err(|error| Error::Deploy {
+            source: error.context(format!("While reading output directory {output_dir:?}")),
+        })?;
+
+    let previous = match (deploy_config.state_file.as_ref(), target) {
+        (Some(state_file), _) => Manifest::read(state_file).map_err(|error| Error::Deploy {
+            source: error.context(format!("While reading state file {state_file:?}")),
+        })?,
+        (None, DeployTarget::S3) => self::s3::fetch_manifest(deploy_config)
+            .map_err(|error| Error::Deploy { source: error })?,
+        (None, _) => Manifest::default(),
+    };
+
+    let diff = manifest.diff(&previous);
+
+    tracing::info!(
+        "Deploying {} files to {} ({} to upload, {} to delete)",
+        manifest.0.len(),
+        deploy_config.destination,
+        diff.upload.len(),
+        diff.delete.len()
+    );
+
+    if let Some(diff_path) = diff_path {
+        serde_json::to_string_pretty(&diff)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(std::fs::write(diff_path, content)?))
+            .map_err(|error| Error::Deploy {
+                source: error.context(format!("While writing diff file {diff_path:?}")),
+            })?;
+    }
+
+    match target {
+        DeployTarget::Rsync => self::rsync::deploy(output_dir, deploy_config, dry_run),
+        DeployTarget::S3 => self::s3::deploy(output_dir, &manifest, &diff, deploy_config, dry_run),
+        DeployTarget::GithubPages => self::github_pages::deploy(output_dir, deploy_config, dry_run),
+    }
+    .map_err(|error| Error::Deploy { source: error })?;
+
+    // Remember deployed files for the next deployment
+    if let Some(state_file) = deploy_config.state_file.as_ref().filter(|_| !dry_run) {
+        manifest.write(state_file).map_err(|error| Error::Deploy {
+            source: error.context(format!("While writing state file {state_file:?}")),
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Manifest, ManifestDiff};
+
+    #[test]
+    fn diff() {
+        let previous = Manifest(
+            [("index.html", "a"), ("style.css", "b"), ("old.html", "c")]
+                .into_iter()
+                .map(|(path, hash)| (path.to_owned(), hash.to_owned()))
+                .collect(),
+        );
+
+        let current = Manifest(
+            [("index.html", "a"), ("style.css", "d"), ("new.html", "e")]
+                .into_iter()
+                .map(|(path, hash)| (path.to_owned(), hash.to_owned()))
+                .collect(),
+        );
+
+        assert_eq!(current.diff(&previous), ManifestDiff {
+            upload: vec!["new.html".to_owned(), "style.css".to_owned()],
+            delete: vec!["old.html".to_owned()],
+        });
+
+        assert_eq!(current.diff(&current), ManifestDiff::default());
+    }
+
+    #[test]
+    fn parse_target() {
+        assert_eq!(
+            "s3".parse::<super::DeployTarget>().unwrap(),
+            super::DeployTarget::S3
+        );
+        assert!("ftp".parse::<super::DeployTarget>().is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Deploy to GitHub Pages.
+//!
+//! This module calls the `git` command under the hood.
+
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use walkdir::WalkDir;
+
+use crate::{config::DeployConfig, util::command::run_string};
+
+/// Push the output directory to a git branch (e.g. `gh-pages`).
+///
+/// The destination is a git remote URL (e.g.
+/// `git@github.com:user/repo.git`). The branch is cloned in a temporary
+/// directory, its files are replaced by the output files, and a commit is
+/// pushed if anything changed. A `.nojekyll` file is added so that GitHub
+/// Pages serves the files as is.
+pub(super) fn deploy(
+    output_dir: &Path,
+    deploy_config: &DeployConfig,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let work_dir = std::env::temp_dir().join(format!("vitrine-deploy-{}", std::process::id()));
+
+    let result = deploy_from(&work_dir, output_dir, deploy_config, dry_run);
+
+    if let Err(error) = std::fs::remove_dir_all(&work_dir) {
+        tracing::debug!("Cannot remove {:?}: {}", work_dir, error);
+    }
+
+    result
+}
+
+/// Deploy using a working directory.
+fn deploy_from(
+    work_dir: &Path,
+    output_dir: &Path,
+    deploy_config: &DeployConfig,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let branch = deploy_config.branch.as_str();
+
+    if branch_exists(&deploy_config.destination, branch)? {
+        git(None, [
+            "clone",
+            "--quiet",
+            "--depth",
+            "1",
+            "--branch",
+            branch,
+            deploy_config.destination.as_str(),
+            &*work_dir.to_string_lossy(),
+        ])?;
+    } else {
+        // The branch does not exist yet
+        tracing::info!("Creating branch {:?}", branch);
+        std::fs::create_dir_all(work_dir)?;
+        git(Some(work_dir), ["init", "--quiet"])?;
+        git(Some(work_dir), ["checkout", "--quiet", "--orphan", branch])?;
+        git(Some(work_dir), [
+            "remote",
+            "add",
+            "origin",
+            deploy_config.destination.as_str(),
+        ])?;
+    }
+
+    // Replace files of the branch by output files
+    for entry in std::fs::read_dir(work_dir)? {
+        let path = entry?.path();
+        if path
+            .file_name()
+            .is_some_and(|file_name| file_name == ".git")
+        {
+            continue;
+        }
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path)?;
+        } else {
+            std::fs::remove_file(&path)?;
+        }
+    }
+
+    for entry in WalkDir::new(output_dir) {
+        let entry = entry?;
+        let path = work_dir.join(entry.path().strip_prefix(output_dir)?);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&path)?;
+        } else {
+            std::fs::copy(entry.path(), &path)?;
+        }
+    }
+
+    std::fs::write(work_dir.join(".nojekyll"), "")?;
+
+    git(Some(work_dir), ["add", "--all"])?;
+
+    let status = git(Some(work_dir), ["status", "--porcelain"])?;
+
+    if status.trim().is_empty() {
+        tracing::info!("No changes to deploy");
+        return Ok(());
+    }
+
+    for line in status.lines() {
+        tracing::info!("{}", line);
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    git(Some(work_dir), [
+        "commit",
+        "--quiet",
+        "--message",
+        "Deploy site",
+    ])?;
+    git(Some(work_dir), ["push", "--quiet", "origin", branch])?;
+
+    Ok(())
+}
+
+/// Determine whether a branch exists in a remote repository.
+///
+/// `git ls-remote --exit-code` exits with status 2 if the branch is not found.
+/// Other failures (e.g. authentication or network errors) are returned.
+fn branch_exists(destination: &str, branch: &str) -> anyhow::Result<bool> {
+    let mut command = Command::new("git");
+
+    command
+        .args(["ls-remote", "--exit-code", "--heads", destination])
+        .arg(format!("refs/heads/{branch}"));
+
+    tracing::debug!("Running {:?}", command);
+
+    let output = command.stdin(Stdio::null()).output().map_err(|error| {
+        anyhow::anyhow!(error).context(format!("While running {:?}", command.get_program()))
+    })?;
+
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(2) => Ok(false),
+        _ => Err(anyhow::anyhow!(
+            "{:?} failed: {}",
+            command.get_program(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+    }
+}
+
+/// Run a git command, in a directory if specified.
+fn git<'a, I>(dir: Option<&Path>, args: I) -> anyhow::Result<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut command = Command::new("git");
+
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+
+    command.args(args);
+
+    run_string(&mut command, None)
+}
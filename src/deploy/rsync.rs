@@ -0,0 +1,45 @@
+//! Deploy with `rsync`.
+
+use std::{path::Path, process::Command};
+
+use crate::{config::DeployConfig, util::command::run_string};
+
+/// Synchronize the output directory with a destination using `rsync`.
+///
+/// The destination can be local or remote (e.g. `user@example.com:/var/www`
+/// over SSH). Files are compared using checksums, so that only changed files
+/// are transferred. Files that no longer exist in the output directory are
+/// deleted from the destination.
+pub(super) fn deploy(
+    output_dir: &Path,
+    deploy_config: &DeployConfig,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    // A trailing slash copies the content of the directory, not the directory
+    let mut source = output_dir.as_os_str().to_owned();
+    source.push("/");
+
+    let mut command = Command::new("rsync");
+
+    command.args([
+        "--recursive",
+        "--checksum",
+        "--delete",
+        "--compress",
+        "--itemize-changes",
+    ]);
+
+    if dry_run {
+        command.arg("--dry-run");
+    }
+
+    command.arg(source).arg(&deploy_config.destination);
+
+    let output = run_string(&mut command, None)?;
+
+    for line in output.lines() {
+        tracing::info!("{}", line);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+//! Deploy to a S3-compatible bucket.
+//!
+//! This module calls the `aws` command under the hood.
+
+use std::{path::Path, process::Command};
+
+use super::{Manifest, ManifestDiff, MANIFEST_FILE_NAME};
+use crate::{config::DeployConfig, util::command::run_string};
+
+/// Fetch the manifest of the previous deployment stored in the bucket.
+pub(super) fn fetch_manifest(deploy_config: &DeployConfig) -> anyhow::Result<Manifest> {
+    let manifest_url = manifest_url(deploy_config);
+
+    let manifest = match aws(
+        deploy_config,
+        ["s3", "cp", manifest_url.as_str(), "-"],
+        None,
+    ) {
+        Ok(content) => serde_json::from_str(&content)?,
+        // A missing manifest means that nothing was deployed yet
+        Err(error) if is_not_found(&error) => Manifest::default(),
+        Err(error) => return Err(error),
+    };
+
+    Ok(manifest)
+}
+
+/// Determine whether an error of the `aws` command reports a missing object.
+///
+/// Other errors (e.g. invalid credentials or network failures) must not be
+/// mistaken for a first deployment.
+fn is_not_found(error: &anyhow::Error) -> bool {
+    let message = error.to_string();
+    message.contains("(404)") || message.contains("NoSuchKey") || message.contains("does not exist")
+}
+
+/// Upload the output directory to a S3-compatible bucket.
+///
+/// The destination is a S3 URL (e.g. `s3://bucket/prefix`). Only new and
+/// modified files of `diff` are uploaded, and only removed files are deleted.
+/// The manifest is then stored in the bucket for the next deployment.
+pub(super) fn deploy(
+    output_dir: &Path,
+    manifest: &Manifest,
+    diff: &ManifestDiff,
+    deploy_config: &DeployConfig,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let destination = deploy_config.destination.trim_end_matches('/');
+
+    let manifest_url = manifest_url(deploy_config);
+
+    if diff.upload.is_empty() && diff.delete.is_empty() {
+        tracing::info!("No changes to deploy");
+        return Ok(());
+    }
+
+    for path in diff.upload.iter() {
+        tracing::info!("Uploading {:?}", path);
+
+        if !dry_run {
+            let source = output_dir.join(path).to_string_lossy().into_owned();
+            let target = format!("{destination}/{path}");
+            aws(
+                deploy_config,
+                [
+                    "s3",
+                    "cp",
+                    "--only-show-errors",
+                    source.as_str(),
+                    target.as_str(),
+                ],
+                None,
+            )?;
+        }
+    }
+
+    for path in diff.delete.iter() {
+        tracing::info!("Deleting {:?}", path);
+
+        if !dry_run {
+            let target = format!("{destination}/{path}");
+            aws(
+                deploy_config,
+                ["s3", "rm", "--only-show-errors", target.as_str()],
+                None,
+            )?;
+        }
+    }
+
+    if !dry_run {
+        let content = serde_json::to_vec(manifest)?;
+        aws(
+            deploy_config,
+            ["s3", "cp", "-", manifest_url.as_str()],
+            Some(&content),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Return the URL of the manifest stored in the bucket.
+fn manifest_url(deploy_config: &DeployConfig) -> String {
+    format!(
+        "{}/{MANIFEST_FILE_NAME}",
+        deploy_config.destination.trim_end_matches('/')
+    )
+}
+
+/// Run an `aws` command, with the endpoint URL of the configuration.
+fn aws<'a, I>(deploy_config: &DeployConfig, args: I, input: Option<&[u8]>) -> anyhow::Result<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut command = Command::new("aws");
+
+    command.args(args);
+
+    if let Some(endpoint_url) = deploy_config.endpoint_url.as_ref() {
+        command.arg("--endpoint-url").arg(endpoint_url);
+    }
+
+    run_string(&mut command, input)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn is_not_found() {
+        const CASES: [(&str, bool); 3] = [
+            (
+                "\"aws\" failed: fatal error: An error occurred (404) when calling the HeadObject \
+                 operation: Key \".vitrine-manifest.json\" does not exist",
+                true,
+            ),
+            (
+                "\"aws\" failed: fatal error: An error occurred (403) when calling the HeadObject \
+                 operation: Forbidden",
+                false,
+            ),
+            (
+                "\"aws\" failed: Could not connect to the endpoint URL",
+                false,
+            ),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::is_not_found(&anyhow::anyhow!(input.to_owned()));
+            assert_eq!(
+                result, expected,
+                "\nis_not_found({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
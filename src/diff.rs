@@ -0,0 +1,179 @@
+//! Compare two output directories.
+
+use std::path::Path;
+
+use similar::TextDiff;
+
+use crate::{build, config::Config, deploy::Manifest, error::Error};
+
+/// Number of unchanged lines displayed around changes.
+const CONTEXT_RADIUS: usize = 3;
+
+/// Compare two output directories, and print the added, removed and modified
+/// files.
+///
+/// If `new_dir` is `None`, the site is built to a temporary directory, which
+/// is compared with `old_dir`. Unless `name_only` is `true`, a unified diff is
+/// printed for each modified text file. HTML files are split into one tag per
+/// line before being compared, so that changes in minified pages are readable.
+pub(super) fn diff(
+    config: Config,
+    old_dir: &Path,
+    new_dir: Option<&Path>,
+    name_only: bool,
+) -> Result<(), Error> {
+    let Some(new_dir) = new_dir else {
+        let temp_dir = std::env::temp_dir().join(format!("vitrine-diff-{}", std::process::id()));
+
+        let config = Config {
+            output_dir: Some(temp_dir.to_owned()),
+            ..config
+        };
+
+        let result = build::build(&config).and_then(|_| compare(old_dir, &temp_dir, name_only));
+
+        // Remove the temporary directory, even if the build failed
+        if temp_dir.exists() {
+            std::fs::remove_dir_all(&temp_dir).map_err(|error| Error::Diff {
+                source: error.into(),
+            })?;
+        }
+
+        return result;
+    };
+
+    compare(old_dir, new_dir, name_only)
+}
+
+/// Compare two directories, and print the changes.
+fn compare(old_dir: &Path, new_dir: &Path, name_only: bool) -> Result<(), Error> {
+    let read_manifest = |dir: &Path| {
+        Manifest::from_dir(dir, None).map_err(|error| Error::Diff {
+            source: error.context(format!("While reading directory {dir:?}")),
+        })
+    };
+
+    let old_manifest = read_manifest(old_dir)?;
+    let new_manifest = read_manifest(new_dir)?;
+
+    let changes = new_manifest.diff(&old_manifest);
+
+    let mut lines: Vec<(String, char)> = changes
+        .upload
+        .into_iter()
+        .map(|path| {
+            let status = if old_dir.join(&path).exists() {
+                'M'
+            } else {
+                'A'
+            };
+            (path, status)
+        })
+        .chain(changes.delete.into_iter().map(|path| (path, 'D')))
+        .collect();
+
+    lines.sort();
+
+    for (path, status) in lines.iter() {
+        println!("{status} {path}");
+    }
+
+    if name_only {
+        return Ok(());
+    }
+
+    for (path, _) in lines.iter().filter(|(_, status)| *status == 'M') {
+        // Files may differ only by whitespace between HTML tags
+        if let Some(diff) = diff_files(&old_dir.join(path), &new_dir.join(path), path)?
+            .filter(|diff| !diff.is_empty())
+        {
+            print!("\n{diff}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a unified diff of two versions of a file.
+///
+/// Returns `None` if one of the files is not valid UTF-8 (e.g. images).
+fn diff_files(old_path: &Path, new_path: &Path, path: &str) -> Result<Option<String>, Error> {
+    let read = |path: &Path| {
+        std::fs::read(path).map_err(|error| Error::Diff {
+            source: anyhow::anyhow!(error).context(format!("While reading {path:?}")),
+        })
+    };
+
+    let (Ok(old), Ok(new)) = (
+        String::from_utf8(read(old_path)?),
+        String::from_utf8(read(new_path)?),
+    ) else {
+        return Ok(None);
+    };
+
+    let (old, new) = if path.ends_with(".html") {
+        (split_html_tags(&old), split_html_tags(&new))
+    } else {
+        (old, new)
+    };
+
+    Ok(Some(
+        TextDiff::from_lines(&old, &new)
+            .unified_diff()
+            .context_radius(CONTEXT_RADIUS)
+            .header(&format!("a/{path}"), &format!("b/{path}"))
+            .to_string(),
+    ))
+}
+
+/// Put each HTML tag and text on its own line, without surrounding
+/// whitespace.
+fn split_html_tags(html: &str) -> String {
+    let mut lines = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let end = match rest.find('<') {
+            // Tag
+            Some(0) => rest.find('>').map_or(rest.len(), |index| index + 1),
+            // Text before a tag
+            Some(index) => index,
+            // Text at the end
+            None => rest.len(),
+        };
+
+        let (line, tail) = rest.split_at(end);
+        lines.push(line.trim());
+        rest = tail;
+    }
+
+    lines
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn split_html_tags() {
+        const CASES: [(&str, &str); 4] = [
+            ("", ""),
+            ("<p>Hello</p>", "<p>\nHello\n</p>\n"),
+            (
+                "<ul>\n  <li>A</li>\n  <li>B</li>\n</ul>",
+                "<ul>\n<li>\nA\n</li>\n<li>\nB\n</li>\n</ul>\n",
+            ),
+            ("Text <br> end", "Text\n<br>\nend\n"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::split_html_tags(input);
+            assert_eq!(
+                result, expected,
+                "\nsplit_html_tags({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
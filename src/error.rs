@@ -1,15 +1,19 @@
 //! Application errors.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Enumerates application errors.
 #[derive(Debug, thiserror::Error)]
-pub(crate) enum Error {
+pub enum Error {
     #[error("While loading configuration from {config_path:?}")]
     LoadConfig {
         config_path: Option<PathBuf>,
         source: anyhow::Error,
     },
+    #[error("While fetching theme {url:?}")]
+    FetchTheme { url: String, source: anyhow::Error },
+    #[error("While updating the lock file")]
+    UpdateLock { source: anyhow::Error },
     #[error("While parsing ignore globs")]
     NewIgnoreMatcher { source: anyhow::Error },
     #[error("While initializing the layout engine")]
@@ -34,11 +38,27 @@ pub(crate) enum Error {
         input_path: Option<PathBuf>,
         source: anyhow::Error,
     },
+    #[error("In {input_path:?} while computing front matter defaults")]
+    ComputeFrontMatterDefaults {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("While generating pages")]
+    GeneratePages { source: anyhow::Error },
+    #[error("While copying paths")]
+    CopyPaths { source: anyhow::Error },
+    #[error("While validating front matters")]
+    ValidateFrontMatter { source: anyhow::Error },
     #[error("In {input_path:?} while normalizing URL")]
     NormalizeUrl {
         input_path: Option<PathBuf>,
         source: anyhow::Error,
     },
+    #[error("In {input_path:?} while expanding directives")]
+    ExpandDirectives {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
     #[error("In {input_path:?} while compiling SCSS")]
     CompileScss {
         input_path: Option<PathBuf>,
@@ -49,10 +69,73 @@ pub(crate) enum Error {
         input_path: Option<PathBuf>,
         source: anyhow::Error,
     },
+    #[error("In {input_path:?} while compiling Tailwind CSS")]
+    CompileTailwind {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while creating Content Security Policy")]
+    CreateContentSecurityPolicy {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("While creating hosting provider files")]
+    CreateHostingFiles { source: anyhow::Error },
+    #[error("In {input_path:?} while adding subresource integrity hashes")]
+    AddIntegrity {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while sanitizing HTML")]
+    Sanitize {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("While creating the analytics snippet")]
+    CreateAnalyticsSnippet { source: anyhow::Error },
+    #[error("In {input_path:?} while inserting analytics")]
+    InsertAnalytics {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while inserting the import map")]
+    InsertImportMap {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while inserting preload hints")]
+    InsertPreloadHints {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while decorating external links")]
+    DecorateExternalLinks {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while rewriting images")]
+    RewriteImages {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("While loading syntax highlight definitions and themes")]
+    LoadSyntaxHighlight { source: anyhow::Error },
     #[error("While creating syntax highlight CSS stylesheet")]
     CreateSyntaxHighlightStylesheet { source: anyhow::Error },
+    #[error("In {input_path:?} while linking syntax highlight CSS stylesheets")]
+    LinkSyntaxHighlightStylesheet {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while linking the color scheme stylesheet")]
+    LinkColorScheme {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
     #[error("While grouping entries using taxonomies")]
     GroupTaxonomies { source: anyhow::Error },
+    #[error("While grouping entries into collections")]
+    GroupCollections { source: anyhow::Error },
     #[error("While bundling contents")]
     BundleContents { source: anyhow::Error },
     #[error("In {input_path:?} while rendering layout {layout:?}")]
@@ -61,12 +144,34 @@ pub(crate) enum Error {
         layout: Option<String>,
         source: anyhow::Error,
     },
+    #[error("While detecting unused assets")]
+    DetectUnusedAssets { source: anyhow::Error },
+    #[error("While creating ActivityPub documents")]
+    CreateActivityPub { source: anyhow::Error },
+    #[error("While creating content API")]
+    CreateApi { source: anyhow::Error },
+    #[error("While creating newsletter")]
+    CreateNewsletter { source: anyhow::Error },
+    #[error("While creating fragments")]
+    CreateFragments { source: anyhow::Error },
+    #[error("While creating self-hosted fonts")]
+    CreateFonts { source: anyhow::Error },
+    #[error("While replacing tokens")]
+    ReplaceTokens { source: anyhow::Error },
+    #[error("While making URLs absolute")]
+    MakeAbsoluteUrls { source: anyhow::Error },
+    #[error("While creating blogroll")]
+    CreateBlogroll { source: anyhow::Error },
+    #[error("While listing events")]
+    ListEvents { source: anyhow::Error },
     #[error("While creating feed")]
     CreateFeed { source: anyhow::Error },
     #[error("While creating navigation tree")]
     CreateNavigation { source: anyhow::Error },
     #[error("While creating sitemap")]
     CreateSitemap { source: anyhow::Error },
+    #[error("While checking URL collisions")]
+    CheckUrlCollisions { source: anyhow::Error },
     #[error("In {input_path:?} while rewriting URL")]
     RewriteUrl {
         input_path: Option<PathBuf>,
@@ -92,6 +197,11 @@ pub(crate) enum Error {
         input_path: Option<PathBuf>,
         source: anyhow::Error,
     },
+    #[error("In {input_path:?} while minifying SVG")]
+    MinifySvg {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
     #[error("In {input_path:?} while minifying XML")]
     MinifyXml {
         input_path: Option<PathBuf>,
@@ -102,8 +212,148 @@ pub(crate) enum Error {
         output_path: PathBuf,
         source: anyhow::Error,
     },
+    #[error("In {input_path:?} while collecting pages")]
+    CollectPages {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while measuring page weight")]
+    MeasurePageWeight {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("While running plugin {name:?}")]
+    RunPlugin { name: String, source: anyhow::Error },
+    #[error("While running command {command:?}")]
+    RunCommand {
+        command: String,
+        source: anyhow::Error,
+    },
+    #[error("While writing the archive {archive_path:?}")]
+    WriteArchive {
+        archive_path: PathBuf,
+        source: anyhow::Error,
+    },
     #[error("While serving files")]
     Serve { source: anyhow::Error },
     #[error("While watching files")]
     Watch { source: anyhow::Error },
+    #[error("While deploying the site")]
+    Deploy { source: anyhow::Error },
+    #[error("While sending webmentions")]
+    Webmention { source: anyhow::Error },
+    #[error("While exporting pages to PDF")]
+    ExportPdf { source: anyhow::Error },
+    #[error("While checking the site")]
+    Check { source: anyhow::Error },
+    #[error("While comparing output directories")]
+    Diff { source: anyhow::Error },
+    #[error("While listing the site")]
+    List { source: anyhow::Error },
+    #[error("While benchmarking the site")]
+    Bench { source: anyhow::Error },
+    #[error("In {input_path:?} while checking accessibility")]
+    CheckAccessibility {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("In {input_path:?} while checking URL fragments")]
+    CheckFragments {
+        input_path: Option<PathBuf>,
+        source: anyhow::Error,
+    },
+    #[error("While building the site: {count} {} failed", entries_noun(.count))]
+    KeepGoing { count: usize },
+}
+
+impl Error {
+    /// Return the message of the error followed by the messages of its
+    /// sources, separated by `: `.
+    pub(crate) fn full_message(&self) -> String {
+        std::iter::successors(Some(self as &dyn std::error::Error), |error| error.source())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(": ")
+    }
+
+    /// Return the location of the error in a file, if known.
+    ///
+    /// The location is attached as context to the source of front matter and
+    /// layout errors.
+    pub(crate) fn location(&self) -> Option<&Location> {
+        match self {
+            Self::ParseFrontMatter { source, .. } | Self::RenderLayout { source, .. } => {
+                source.downcast_ref::<Location>()
+            },
+            _ => None,
+        }
+    }
+
+    /// Return the path of the input file that caused the error, if any.
+    pub(crate) fn input_path(&self) -> Option<&Path> {
+        match self {
+            Self::ReadGlobalDataInput { input_path, .. }
+            | Self::ReadInput { input_path, .. }
+            | Self::ParseFrontMatter { input_path, .. }
+            | Self::ParseCascadeData { input_path, .. }
+            | Self::ComputeFrontMatterDefaults { input_path, .. }
+            | Self::NormalizeUrl { input_path, .. }
+            | Self::ExpandDirectives { input_path, .. }
+            | Self::CompileScss { input_path, .. }
+            | Self::CompileTypescript { input_path, .. }
+            | Self::CompileTailwind { input_path, .. }
+            | Self::CreateContentSecurityPolicy { input_path, .. }
+            | Self::AddIntegrity { input_path, .. }
+            | Self::Sanitize { input_path, .. }
+            | Self::InsertAnalytics { input_path, .. }
+            | Self::InsertImportMap { input_path, .. }
+            | Self::InsertPreloadHints { input_path, .. }
+            | Self::DecorateExternalLinks { input_path, .. }
+            | Self::RewriteImages { input_path, .. }
+            | Self::LinkSyntaxHighlightStylesheet { input_path, .. }
+            | Self::LinkColorScheme { input_path, .. }
+            | Self::RenderLayout { input_path, .. }
+            | Self::RewriteUrl { input_path, .. }
+            | Self::MinifyCss { input_path, .. }
+            | Self::MinifyHtml { input_path, .. }
+            | Self::MinifyJs { input_path, .. }
+            | Self::MinifyJson { input_path, .. }
+            | Self::MinifySvg { input_path, .. }
+            | Self::MinifyXml { input_path, .. }
+            | Self::CollectPages { input_path, .. }
+            | Self::MeasurePageWeight { input_path, .. }
+            | Self::CheckAccessibility { input_path, .. }
+            | Self::CheckFragments { input_path, .. } => input_path.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Location of an error in a file.
+#[derive(Debug)]
+pub(crate) struct Location {
+    /// Path of the file (e.g. a layout), or `None` for the input file of the
+    /// error.
+    pub(crate) path: Option<PathBuf>,
+
+    /// Line number, starting at 1.
+    pub(crate) line: usize,
+
+    /// Description of the location (e.g. an excerpt of the file).
+    pub(crate) message: String,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Return the noun `entry` or `entries` for a number of entries.
+fn entries_noun(count: &usize) -> &'static str {
+    if *count == 1 {
+        "entry"
+    } else {
+        "entries"
+    }
 }
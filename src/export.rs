@@ -0,0 +1,148 @@
+//! Export pages of the built site.
+//!
+//! Pages are printed to PDF by a headless browser (e.g. `chromium`), which
+//! loads them from a local server, so that stylesheets and images are
+//! resolved like on the web. Styles specific to print can be given with
+//! `@media print` rules.
+
+use std::{net::SocketAddr, path::Path, process::Command};
+
+use globset::GlobBuilder;
+
+use crate::{
+    config::Config,
+    error::Error,
+    site::Site,
+    util::{command::run, path::PathExt, url::Url},
+};
+
+/// Export the pages matching a URL or a glob pattern (e.g. `/docs/**`) to PDF
+/// files.
+///
+/// The site must be built before calling this function. Each page is written
+/// to `{output_dir}/{url}.pdf` (e.g. `/docs/intro` to `docs/intro.pdf`).
+pub(super) async fn export_pdf(
+    config: &Config,
+    site: &Site,
+    pattern: &str,
+    output_dir: &Path,
+) -> Result<(), Error> {
+    let map_error = |error: anyhow::Error| Error::ExportPdf { source: error };
+
+    let Some(site_dir) = config.output_dir.as_ref() else {
+        return Err(map_error(anyhow::anyhow!("No output directory specified")));
+    };
+
+    let matcher = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|error| map_error(error.into()))?
+        .compile_matcher();
+
+    let pages: Vec<&str> = site
+        .pages()
+        .map(|page| page.url.as_str())
+        .filter(|url| *url == pattern || matcher.is_match(url))
+        .collect();
+
+    if pages.is_empty() {
+        return Err(map_error(anyhow::anyhow!("No page matches {:?}", pattern)));
+    }
+
+    // Serve the output directory on a free port
+    let listener = tokio::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+        .await
+        .map_err(|error| map_error(error.into()))?;
+
+    let addr = listener
+        .local_addr()
+        .map_err(|error| map_error(error.into()))?;
+
+    let router = crate::serve::router(site_dir);
+
+    let server =
+        tokio::spawn(async move { axum::serve(listener, router.into_make_service()).await });
+
+    // Output files are located under the path of base_url
+    let base_path = Url::from(config.base_url.as_str()).path;
+
+    let result = async {
+        for url in pages {
+            let pdf_path = output_dir.join_url_path(&pdf_path(url));
+
+            if let Some(parent) = pdf_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            tracing::info!("Writing {:?}", pdf_path);
+
+            let browser = config.pdf.browser.to_owned();
+            let page_url = format!("http://{}{}{}", addr, base_path, url);
+
+            tokio::task::spawn_blocking(move || print_pdf(&browser, &page_url, &pdf_path))
+                .await??;
+        }
+
+        anyhow::Ok(())
+    }
+    .await;
+
+    server.abort();
+
+    result.map_err(map_error)
+}
+
+/// Return the path of the PDF file of a page, relative to the output
+/// directory.
+fn pdf_path(url: &str) -> String {
+    match url.trim_matches('/') {
+        "" => "index.pdf".to_owned(),
+        path => format!("{path}.pdf"),
+    }
+}
+
+/// Print a web page to a PDF file with a headless browser.
+fn print_pdf(browser: &str, url: &str, pdf_path: &Path) -> anyhow::Result<()> {
+    let mut print_to_pdf = std::ffi::OsString::from("--print-to-pdf=");
+    print_to_pdf.push(pdf_path);
+
+    let mut command = Command::new(browser);
+
+    command
+        .args(["--headless", "--disable-gpu", "--no-pdf-header-footer"])
+        .arg(print_to_pdf)
+        .arg(url);
+
+    run(&mut command, None)?;
+
+    if !pdf_path.exists() {
+        return Err(anyhow::anyhow!(
+            "{:?} did not write {:?}",
+            browser,
+            pdf_path
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn pdf_path() {
+        const CASES: [(&str, &str); 4] = [
+            ("/", "index.pdf"),
+            ("/resume", "resume.pdf"),
+            ("/docs/intro/", "docs/intro.pdf"),
+            ("/docs/", "docs.pdf"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::pdf_path(input);
+            assert_eq!(
+                result, expected,
+                "\npdf_path({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
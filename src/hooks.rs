@@ -0,0 +1,89 @@
+//! Run shell commands around builds.
+//!
+//! Commands run before builds can generate input files (e.g. stylesheets
+//! compiled by an external tool), and commands run after builds can process
+//! the output directory (e.g. to index pages for search).
+
+use std::process::{Command, Stdio};
+
+use crate::{config::Config, error::Error};
+
+/// Run the commands of the `run_before_build` option.
+///
+/// Commands are run in order, and the first command that fails stops the
+/// others and the build.
+pub(crate) fn run_before_build(config: &Config) -> Result<(), Error> {
+    run_commands(
+        config
+            .run_before_build
+            .iter()
+            .map(|before_build| before_build.command.as_str()),
+        config,
+    )
+}
+
+/// Run the commands of the `run_after_build` option, after a successful build.
+///
+/// Commands are run in order, and the first command that fails stops the
+/// others. Nothing is run if no output directory is specified.
+pub(crate) fn run_after_build(config: &Config) -> Result<(), Error> {
+    if config.output_dir.is_none() {
+        return Ok(());
+    }
+
+    run_commands(config.run_after_build.iter().map(String::as_str), config)
+}
+
+/// Run shell commands in order.
+fn run_commands<'a>(commands: impl Iterator<Item = &'a str>, config: &Config) -> Result<(), Error> {
+    for command in commands {
+        run_command(command, config).map_err(|error| Error::RunCommand {
+            command: command.to_owned(),
+            source: error,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Run a shell command, with the input and output directories in environment
+/// variables.
+///
+/// The output of the command is displayed on the console.
+fn run_command(command: &str, config: &Config) -> anyhow::Result<()> {
+    tracing::info!("Running {:?}", command);
+
+    let mut process = shell_command(command);
+
+    process
+        .env("VITRINE_INPUT_DIR", &config.input_dir)
+        .stdin(Stdio::null());
+
+    if let Some(output_dir) = config.output_dir.as_ref() {
+        process.env("VITRINE_OUTPUT_DIR", output_dir);
+    }
+
+    let status = process.status()?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("Command failed with {}", status));
+    }
+
+    Ok(())
+}
+
+/// Create a process that runs a command with the shell of the system.
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut process = Command::new("sh");
+    process.arg("-c").arg(command);
+    process
+}
+
+/// Create a process that runs a command with the shell of the system.
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut process = Command::new("cmd");
+    process.arg("/C").arg(command);
+    process
+}
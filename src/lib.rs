@@ -0,0 +1,241 @@
+//! A scriptable static site generator written in Rust.
+//!
+//! Besides the `vitrine` command, this crate can be used as a library to build
+//! a site and query its pages:
+//!
+//! ```no_run
+//! let config = vitrine::load_config("vitrine.config.json")?;
+//! let config = vitrine::normalize_config(config)?;
+//! let site = vitrine::build(&config)?;
+//!
+//! for page in site.pages_in("blog/") {
+//!     println!("{}", page.url);
+//! }
+//! # Ok::<(), vitrine::Error>(())
+//! ```
+
+mod annotations;
+mod archive;
+mod bench;
+mod build;
+mod builder;
+mod check;
+mod cli;
+mod config;
+mod deploy;
+mod diff;
+mod error;
+mod export;
+mod hooks;
+mod list;
+mod report;
+mod serve;
+mod site;
+mod theme;
+mod util;
+mod watch;
+mod webmention;
+
+use clap::Parser;
+
+pub use crate::{
+    build::build,
+    builder::{Builder, Plugin},
+    config::{load_config, load_config_default, normalize_config, validate_config, Config},
+    error::Error,
+    site::{Page, Site},
+};
+use crate::{
+    check::Checks,
+    cli::{Cli, Command, ExportCommand, MessageFormat, WebmentionCommand},
+    config::TypescriptConfig,
+};
+
+/// Run the command line interface.
+///
+/// Arguments are read from the command line.
+pub async fn run() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    // Forget the commits of remote themes, so that the latest ones are fetched
+    if let Some(Command::Update) = cli.command {
+        theme::unlock()?;
+    }
+
+    // If specified with `--config`, load the provided configuration file.
+    // Otherwise, try `vitrine.config.json`, `vitrine.config.rhai`, etc. by default.
+    let config = cli.config.map_or_else(load_config_default, load_config)?;
+
+    // Override the configuration with CLI arguments
+    let config = Config {
+        input_dir: cli.input_dir.unwrap_or(config.input_dir),
+        output_dir: cli
+            .output_dir
+            .or(config.output_dir)
+            .filter(|_| !cli.dry_run),
+        base_url: cli.base_url.unwrap_or(config.base_url),
+        site_url: cli.site_url.or(config.site_url),
+        data_dir: cli.data_dir.or(config.data_dir),
+        layouts_dir: cli.layouts_dir.or(config.layouts_dir),
+        minify: !cli.serve && config.minify,
+        analytics: config.analytics.filter(|_| !cli.serve),
+        typescript: TypescriptConfig {
+            source_maps: cli.serve || config.typescript.source_maps,
+            ..config.typescript
+        },
+        serve_host: Some(cli.host),
+        serve_port: cli.port,
+        serve_strict_port: cli.strict_port,
+        serve_log_requests: cli.log_requests,
+        keep_going: cli.keep_going,
+        github_annotations: cli.message_format == MessageFormat::Github,
+        source_date_epoch: cli.reproducible.then(source_date_epoch).transpose()?,
+        ..config
+    };
+
+    // Normalize the configuration (e.g. make paths absolute)
+    let config = normalize_config(config)?;
+
+    // Check for problems in the configuration
+    validate_config(&config)?;
+
+    tracing::debug!("{:#?}", config);
+
+    if let Some(Command::Update) = cli.command {
+        return Ok(());
+    }
+
+    if let Some(Command::Check(args)) = cli.command {
+        // Without options, run all checks
+        let all = !args.a11y && !args.fragments;
+
+        let checks = Checks {
+            a11y: all || args.a11y,
+            fragments: all || args.fragments,
+        };
+
+        check::check(&config, &checks, args.format, args.output.as_deref())?;
+
+        return Ok(());
+    }
+
+    if let Some(Command::List(args)) = cli.command {
+        list::list(&config, args.kind, args.json)?;
+
+        return Ok(());
+    }
+
+    if let Some(Command::Diff(args)) = cli.command {
+        diff::diff(config, &args.old, args.new.as_deref(), args.name_only)?;
+
+        return Ok(());
+    }
+
+    if let Some(Command::Bench(args)) = cli.command {
+        bench::bench(config, args.runs, &args.baseline, args.save_baseline)?;
+
+        return Ok(());
+    }
+
+    // Keep the layouts used by each page, to render only affected pages on changes
+    let mut session = build::Session::default();
+
+    // Build the site
+    let site = match cli.command.as_ref() {
+        Some(Command::Build(args)) if args.report => {
+            report::report(&config)?;
+            None
+        },
+        _ => Some(session.build(&config).inspect_err(|error| {
+            // Errors of skipped entries are annotated while reporting them
+            if config.github_annotations && !matches!(error, Error::KeepGoing { .. }) {
+                annotations::annotate_error(error, &config);
+            }
+        })?),
+    };
+
+    if let Some(archive_path) = cli.archive.as_ref() {
+        archive::write_archive(&config, archive_path)?;
+    }
+
+    if let Some(Command::Deploy(args)) = cli.command {
+        deploy::deploy(&config, args.dry_run, args.diff.as_deref())?;
+
+        let send_webmentions = config
+            .webmention
+            .as_ref()
+            .is_some_and(|webmention| webmention.send_after_deploy);
+
+        if let Some(site) = site.as_ref().filter(|_| send_webmentions && !args.dry_run) {
+            webmention::send(&config, site, false)?;
+        }
+
+        return Ok(());
+    }
+
+    if let Some(Command::Webmention(args)) = cli.command {
+        match args.command {
+            WebmentionCommand::Send(args) => {
+                if let Some(site) = site.as_ref() {
+                    webmention::send(&config, site, args.dry_run)?;
+                }
+            },
+        }
+
+        return Ok(());
+    }
+
+    if let Some(Command::Export(args)) = cli.command {
+        match args.command {
+            ExportCommand::Pdf(args) => {
+                if let Some(site) = site.as_ref() {
+                    export::export_pdf(&config, site, &args.pattern, &args.output).await?;
+                }
+            },
+        }
+
+        return Ok(());
+    }
+
+    if cli.serve {
+        let serve = serve::serve(&config, cli.auth.as_deref());
+        let included_paths = session.included_paths();
+        let watch = watch::watch(
+            &config,
+            &included_paths,
+            serve::shutdown_signal(),
+            |paths| session.rebuild(&config, paths),
+        );
+
+        // Both stop on `SIGINT` or `SIGTERM`
+        tokio::try_join!(serve, watch)?;
+    } else if matches!(cli.command, Some(Command::Build(ref args)) if args.watch) {
+        let included_paths = session.included_paths();
+
+        // Write to the output directory only, e.g. for another web server
+        watch::watch(
+            &config,
+            &included_paths,
+            serve::shutdown_signal(),
+            |paths| session.rebuild(&config, paths),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Read the timestamp of reproducible builds from the `SOURCE_DATE_EPOCH`
+/// environment variable, or `0` if not set.
+///
+/// See <https://reproducible-builds.org/specs/source-date-epoch/>.
+fn source_date_epoch() -> anyhow::Result<u64> {
+    match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|error| anyhow::anyhow!("Invalid SOURCE_DATE_EPOCH {value:?}: {error}")),
+        Err(std::env::VarError::NotPresent) => Ok(0),
+        Err(error) => Err(error.into()),
+    }
+}
@@ -0,0 +1,163 @@
+//! List the pages, assets, taxonomies or URLs of the site.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::Serialize;
+
+use crate::{
+    build,
+    cli::ListKind,
+    config::Config,
+    error::Error,
+    site::{Page, Site},
+};
+
+/// Output file of the site, as found without rendering layouts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ListEntry {
+    /// URL of the file.
+    pub(crate) url: String,
+
+    /// Format of the file (e.g. `html`).
+    pub(crate) format: String,
+
+    /// Input file, if any.
+    pub(crate) input_path: Option<PathBuf>,
+
+    /// Title of the page, if any.
+    pub(crate) title: Option<String>,
+
+    /// Metadata of the page (e.g. front matter fields).
+    pub(crate) data: serde_json::Value,
+}
+
+/// Listed page or asset.
+#[derive(Debug, Serialize)]
+struct Item<'a> {
+    /// URL of the file.
+    url: &'a str,
+
+    /// Input file, relative to its input directory if possible.
+    input_path: Option<PathBuf>,
+
+    /// Title of the page, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+}
+
+/// Scan the site, and print the requested items, one per line or as JSON.
+pub(super) fn list(config: &Config, kind: ListKind, json: bool) -> Result<(), Error> {
+    let mut entries = build::list(config)?;
+
+    entries.sort_by(|a, b| a.url.cmp(&b.url));
+
+    let (pages, assets): (Vec<_>, Vec<_>) =
+        entries.iter().partition(|entry| entry.format == "html");
+
+    let item = |entry: &&ListEntry| Item {
+        url: &entry.url,
+        input_path: entry.input_path.as_ref().map(|input_path| {
+            config
+                .strip_input_dir(input_path)
+                .unwrap_or(input_path)
+                .to_owned()
+        }),
+        title: entry.title.as_deref(),
+    };
+
+    let output = match kind {
+        ListKind::Pages => format_items(&pages.iter().map(item).collect::<Vec<_>>(), json)?,
+        ListKind::Assets => format_items(&assets.iter().map(item).collect::<Vec<_>>(), json)?,
+        ListKind::Taxonomies => {
+            let site = Site::new(
+                pages
+                    .iter()
+                    .map(|entry| Page {
+                        url: entry.url.to_owned(),
+                        title: entry.title.to_owned(),
+                        data: entry.data.to_owned(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            );
+
+            // URLs of the pages of each term, indexed by taxonomy
+            let taxonomies: BTreeMap<&str, BTreeMap<String, Vec<&str>>> = config
+                .taxonomies
+                .iter()
+                .map(|name| {
+                    let terms = site
+                        .taxonomy(name)
+                        .into_iter()
+                        .map(|(term, pages)| {
+                            (term, pages.iter().map(|page| page.url.as_str()).collect())
+                        })
+                        .collect();
+                    (name.as_str(), terms)
+                })
+                .collect();
+
+            if json {
+                to_json(&taxonomies)?
+            } else {
+                taxonomies
+                    .iter()
+                    .flat_map(|(name, terms)| {
+                        terms
+                            .iter()
+                            .map(move |(term, urls)| format!("{name}\t{term}\t{}", urls.len()))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        },
+        ListKind::Urls => {
+            let urls: Vec<&str> = entries.iter().map(|entry| entry.url.as_str()).collect();
+
+            if json {
+                to_json(&urls)?
+            } else {
+                urls.join("\n")
+            }
+        },
+    };
+
+    println!("{output}");
+
+    Ok(())
+}
+
+/// Format pages or assets, one per line with tab-separated fields, or as JSON.
+fn format_items(items: &[Item], json: bool) -> Result<String, Error> {
+    if json {
+        return to_json(items);
+    }
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            [
+                item.url.to_owned(),
+                item.input_path
+                    .as_ref()
+                    .map(|input_path| input_path.display().to_string())
+                    .unwrap_or_default(),
+                item.title.unwrap_or_default().to_owned(),
+            ]
+            .join("\t")
+            .trim_end()
+            .to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Serialize the output as JSON.
+fn to_json<T>(value: &T) -> Result<String, Error>
+where
+    T: Serialize + ?Sized,
+{
+    serde_json::to_string_pretty(value).map_err(|error| Error::List {
+        source: error.into(),
+    })
+}
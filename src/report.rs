@@ -0,0 +1,142 @@
+//! Report the weight of the generated pages.
+
+use std::ops::AddAssign;
+
+use crate::{build, config::Config, error::Error, util::table::format_table};
+
+/// Size of a file, or a group of files.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Size {
+    /// Size in bytes.
+    pub(crate) raw: usize,
+
+    /// Estimated size in bytes when transferred, if precompression is enabled.
+    pub(crate) transfer: Option<usize>,
+}
+
+impl AddAssign for Size {
+    fn add_assign(&mut self, other: Self) {
+        self.raw += other.raw;
+        self.transfer = match (self.transfer, other.transfer) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+impl std::fmt::Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.transfer {
+            Some(transfer) => write!(f, "{} ({})", format_bytes(self.raw), format_bytes(transfer)),
+            None => write!(f, "{}", format_bytes(self.raw)),
+        }
+    }
+}
+
+/// Weight of a page of the generated site.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct PageReport {
+    /// URL of the page.
+    pub(crate) url: String,
+
+    /// Size of the HTML file.
+    pub(crate) html: Size,
+
+    /// Size of the CSS stylesheets loaded by the page.
+    pub(crate) css: Size,
+
+    /// Size of the JavaScript scripts loaded by the page.
+    pub(crate) js: Size,
+
+    /// Size of other assets loaded by the page (e.g. images).
+    pub(crate) other: Size,
+}
+
+impl PageReport {
+    /// Total size of the page and its assets.
+    pub(crate) fn total(&self) -> Size {
+        let mut total = self.html;
+        total += self.css;
+        total += self.js;
+        total += self.other;
+        total
+    }
+}
+
+/// Build the site, and print a table of the pages sorted by weight.
+///
+/// Sizes in parentheses are transfer size estimates, displayed when
+/// precompression is enabled.
+pub(super) fn report(config: &Config) -> Result<(), Error> {
+    let mut reports = build::report(config)?;
+
+    reports.sort_by(|a, b| {
+        b.total()
+            .raw
+            .cmp(&a.total().raw)
+            .then_with(|| a.url.cmp(&b.url))
+    });
+
+    let header = ["Page", "HTML", "CSS", "JS", "Other", "Total"].map(str::to_owned);
+
+    let rows: Vec<[String; 6]> = std::iter::once(header)
+        .chain(reports.iter().map(|report| {
+            [
+                report.url.to_owned(),
+                report.html.to_string(),
+                report.css.to_string(),
+                report.js.to_string(),
+                report.other.to_string(),
+                report.total().to_string(),
+            ]
+        }))
+        .collect();
+
+    println!("{}", format_table(&rows));
+
+    Ok(())
+}
+
+/// Format a size in bytes for humans (e.g. `1.5 KiB`).
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+
+    for next_unit in UNITS.iter().skip(1) {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next_unit;
+    }
+
+    format!("{value:.1} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn format_bytes() {
+        const CASES: [(usize, &str); 5] = [
+            (0, "0 B"),
+            (1023, "1023 B"),
+            (1536, "1.5 KiB"),
+            (3 * 1024 * 1024, "3.0 MiB"),
+            (5 * 1024 * 1024 * 1024, "5.0 GiB"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::format_bytes(input);
+            assert_eq!(
+                result, expected,
+                "\nformat_bytes({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -1,8 +1,24 @@
 //! Serve the site.
 
-use std::net::SocketAddr;
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use axum::Router;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use base64::Engine;
+use sha2::{Digest, Sha256};
 use tower_http::services::{ServeDir, ServeFile};
 
 use crate::{config::Config, error::Error};
@@ -10,32 +26,486 @@ use crate::{config::Config, error::Error};
 /// Relative path to the file to send for 404 errors.
 const NOT_FOUND_PATH: &str = "404/index.html";
 
+/// Maximum size of files which content is hashed for `ETag` headers.
+const MAX_ETAG_SIZE: usize = 8 * 1024 * 1024;
+
+/// Number of ports tried when the requested port is already in use.
+const MAX_PORT_ATTEMPTS: u16 = 10;
+
+/// Number of paths listed in each part of the summary of requests.
+const SUMMARY_SIZE: usize = 5;
+
+/// Statistics of the requests made to a path.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct PathStats {
+    /// Number of requests.
+    count: usize,
+
+    /// Sum of the durations of the requests.
+    total_duration: Duration,
+
+    /// Duration of the slowest request.
+    max_duration: Duration,
+
+    /// Determine whether the last request was answered with a 404 error.
+    not_found: bool,
+}
+
+/// Statistics of the requests, keyed by path.
+type RequestStats = Arc<Mutex<HashMap<String, PathStats>>>;
+
 /// Serve the site.
-pub(super) async fn serve(config: &Config) -> Result<(), Error> {
+///
+/// If `auth` is specified (e.g. `user:password`), requests must provide these
+/// credentials with HTTP basic authentication.
+pub(super) async fn serve(config: &Config, auth: Option<&str>) -> Result<(), Error> {
     let Some(output_dir) = config.output_dir.as_ref() else {
         return Err(Error::Serve {
             source: anyhow::anyhow!("No output directory specified"),
         });
     };
 
-    let serve_dir = ServeDir::new(output_dir)
-        .not_found_service(ServeFile::new(output_dir.join(NOT_FOUND_PATH)));
+    if auth.is_some_and(|auth| !auth.contains(':')) {
+        return Err(Error::Serve {
+            source: anyhow::anyhow!("Credentials must be given as \"user:password\""),
+        });
+    }
 
-    let router = Router::new().nest_service("/", serve_dir);
+    let stats = RequestStats::default();
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], config.serve_port));
+    let mut router = router(output_dir).layer(middleware::from_fn(revalidate));
 
-    tracing::info!("Listening on {}", addr);
+    if let Some(auth) = auth {
+        router = router.layer(middleware::from_fn_with_state(
+            Arc::<str>::from(auth),
+            authenticate,
+        ));
+    }
 
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
+    if config.serve_log_requests {
+        router = router.layer(middleware::from_fn_with_state(
+            Arc::clone(&stats),
+            log_request,
+        ));
+    }
+
+    let host = config.serve_host.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+    let listener = bind(host, config.serve_port, config.serve_strict_port)
+        .and_then(|listener| {
+            listener.set_nonblocking(true)?;
+            tokio::net::TcpListener::from_std(listener)
+        })
         .map_err(|error| Error::Serve {
-            source: error.into(),
+            source: anyhow::anyhow!(error).context(format!(
+                "While binding {}",
+                SocketAddr::from((host, config.serve_port))
+            )),
         })?;
 
+    if let Ok(addr) = listener.local_addr() {
+        tracing::info!("Listening on {}", addr);
+    }
+
+    // In-flight requests are completed before stopping
     axum::serve(listener, router.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .map_err(|error| Error::Serve {
             source: error.into(),
+        })?;
+
+    tracing::info!("Server stopped");
+
+    if config.serve_log_requests {
+        if let Ok(stats) = stats.lock() {
+            log_summary(&stats);
+        }
+    }
+
+    Ok(())
+}
+
+/// Bind a port of a local address.
+///
+/// If the port is already in use and `strict` is `false`, the next ports are
+/// tried.
+fn bind(host: IpAddr, port: u16, strict: bool) -> std::io::Result<TcpListener> {
+    let attempts = if strict { 1 } else { MAX_PORT_ATTEMPTS };
+
+    let mut result = TcpListener::bind(SocketAddr::from((host, port)));
+
+    for next_port in (1..attempts).filter_map(|offset| port.checked_add(offset)) {
+        if !result
+            .as_ref()
+            .is_err_and(|error| error.kind() == ErrorKind::AddrInUse)
+        {
+            break;
+        }
+
+        tracing::warn!(
+            "Port {} is in use, trying port {}",
+            next_port - 1,
+            next_port
+        );
+
+        result = TcpListener::bind(SocketAddr::from((host, next_port)));
+    }
+
+    result
+}
+
+/// Wait for a `SIGINT` (e.g. Ctrl-C) or `SIGTERM` signal.
+pub(super) async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            tracing::error!("Error: {:?}", error);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            },
+            Err(error) => {
+                tracing::error!("Error: {:?}", error);
+                std::future::pending::<()>().await;
+            },
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {},
+        _ = terminate => {},
+    }
+}
+
+/// Create the router that serves the files of the output directory.
+pub(super) fn router(output_dir: &Path) -> Router {
+    let serve_dir = ServeDir::new(output_dir)
+        .not_found_service(ServeFile::new(output_dir.join(NOT_FOUND_PATH)));
+
+    Router::new().nest_service("/", serve_dir)
+}
+
+/// Add `ETag` headers to responses, and answer `If-None-Match` requests.
+///
+/// Files are sent with `Cache-Control: no-cache`, so that browsers revalidate
+/// them on each request. `Last-Modified` and `If-Modified-Since` headers are
+/// handled by [`ServeDir`], but dates have a precision of one second, whereas
+/// the `ETag` (a hash of the content) changes on every rebuild that modifies
+/// the file.
+async fn revalidate(mut request: Request, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    // `If-None-Match` takes precedence over `If-Modified-Since`
+    if if_none_match.is_some() {
+        request.headers_mut().remove(header::IF_MODIFIED_SINCE);
+    }
+
+    let response = next.run(request).await;
+
+    let size = response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+
+    if response.status() != StatusCode::OK || !size.is_some_and(|size| size <= MAX_ETAG_SIZE) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+
+    let Ok(content) = axum::body::to_bytes(body, MAX_ETAG_SIZE).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let etag = format!("\"{:x}\"", Sha256::digest(&content));
+
+    parts
+        .headers
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        parts.headers.insert(header::ETAG, value);
+    }
+
+    if if_none_match.is_some_and(|if_none_match| matches_etag(&if_none_match, &etag)) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+
+    Response::from_parts(parts, Body::from(content))
+}
+
+/// Answer requests without valid credentials with a 401 error.
+async fn authenticate(State(auth): State<Arc<str>>, request: Request, next: Next) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+
+    if is_authorized(authorization, &auth) {
+        return next.run(request).await;
+    }
+
+    (StatusCode::UNAUTHORIZED, [(
+        header::WWW_AUTHENTICATE,
+        "Basic realm=\"vitrine\", charset=\"UTF-8\"",
+    )])
+        .into_response()
+}
+
+/// Determine whether a `Authorization` header provides the expected
+/// credentials (e.g. `user:password`) with the basic scheme.
+fn is_authorized(authorization: Option<&str>, auth: &str) -> bool {
+    let Some((scheme, credentials)) = authorization.and_then(|value| value.trim().split_once(' '))
+    else {
+        return false;
+    };
+
+    scheme.eq_ignore_ascii_case("basic")
+        && base64::engine::general_purpose::STANDARD
+            .decode(credentials.trim())
+            .is_ok_and(|credentials| constant_time_eq(&credentials, auth.as_bytes()))
+}
+
+/// Compare two byte strings in constant time.
+///
+/// Both strings are hashed first, so that the time does not depend on their
+/// lengths, nor on the position of the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    Sha256::digest(a)
+        .iter()
+        .zip(Sha256::digest(b).iter())
+        .fold(0, |difference, (x, y)| difference | (x ^ y))
+        == 0
+}
+
+/// Log the method, path, status, size and duration of a request.
+///
+/// The duration is measured until the headers of the response are ready, and
+/// the size is given by the `Content-Length` header, if any.
+async fn log_request(State(stats): State<RequestStats>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_owned();
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let duration = start.elapsed();
+    let status = response.status();
+
+    let size = response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("-");
+
+    tracing::info!(
+        "{} {} {} {} {:.1?}",
+        method,
+        path,
+        status.as_u16(),
+        size,
+        duration
+    );
+
+    if let Ok(mut stats) = stats.lock() {
+        let path_stats = stats.entry(path).or_default();
+        path_stats.count += 1;
+        path_stats.total_duration += duration;
+        path_stats.max_duration = path_stats.max_duration.max(duration);
+        path_stats.not_found = status == StatusCode::NOT_FOUND;
+    }
+
+    response
+}
+
+/// Log the slowest and most requested paths, and the paths not found.
+fn log_summary(stats: &HashMap<String, PathStats>) {
+    if stats.is_empty() {
+        return;
+    }
+
+    tracing::info!("Most requested paths:");
+    for (path, path_stats) in top_paths(stats, |path_stats| path_stats.count) {
+        tracing::info!("  {} {}", path_stats.count, path);
+    }
+
+    tracing::info!("Slowest paths:");
+    for (path, path_stats) in top_paths(stats, |path_stats| path_stats.max_duration) {
+        tracing::info!(
+            "  {:.1?} (average {:.1?}) {}",
+            path_stats.max_duration,
+            path_stats.total_duration / path_stats.count.max(1) as u32,
+            path
+        );
+    }
+
+    let mut not_found: Vec<&str> = stats
+        .iter()
+        .filter(|(_, path_stats)| path_stats.not_found)
+        .map(|(path, _)| path.as_str())
+        .collect();
+
+    if !not_found.is_empty() {
+        not_found.sort_unstable();
+        tracing::warn!("Paths not found: {}", not_found.join(", "));
+    }
+}
+
+/// Return the [`SUMMARY_SIZE`] paths with the greatest value of `key`, in
+/// decreasing order.
+///
+/// Paths with the same value are sorted alphabetically.
+fn top_paths<K, F>(stats: &HashMap<String, PathStats>, key: F) -> Vec<(&str, &PathStats)>
+where
+    K: Ord,
+    F: Fn(&PathStats) -> K,
+{
+    let mut paths: Vec<(&str, &PathStats)> = stats
+        .iter()
+        .map(|(path, path_stats)| (path.as_str(), path_stats))
+        .collect();
+
+    paths.sort_by(|x, y| key(y.1).cmp(&key(x.1)).then_with(|| x.0.cmp(y.0)));
+    paths.truncate(SUMMARY_SIZE);
+    paths
+}
+
+/// Determine whether a `If-None-Match` header matches an entity tag.
+///
+/// Weak entity tags (e.g. `W/"abc"`) match their strong equivalent.
+fn matches_etag(if_none_match: &str, etag: &str) -> bool {
+    let etag = etag.trim_start_matches("W/");
+
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        net::{IpAddr, Ipv4Addr},
+        time::Duration,
+    };
+
+    use super::PathStats;
+
+    #[test]
+    fn top_paths() {
+        let stats: HashMap<String, PathStats> = [
+            ("/", 3, 10),
+            ("/about", 1, 50),
+            ("/favicon.ico", 3, 1),
+            ("/a", 1, 1),
+            ("/b", 1, 1),
+            ("/c", 1, 1),
+        ]
+        .into_iter()
+        .map(|(path, count, duration)| {
+            (path.to_owned(), PathStats {
+                count,
+                total_duration: Duration::from_millis(duration * count as u64),
+                max_duration: Duration::from_millis(duration),
+                not_found: false,
+            })
         })
+        .collect();
+
+        let result: Vec<&str> = super::top_paths(&stats, |path_stats| path_stats.count)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+
+        assert_eq!(result, ["/", "/favicon.ico", "/a", "/about", "/b"]);
+
+        let result: Vec<&str> = super::top_paths(&stats, |path_stats| path_stats.max_duration)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+
+        assert_eq!(result, ["/about", "/", "/a", "/b", "/c"]);
+    }
+
+    #[test]
+    fn bind() {
+        let host = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        let listener = super::bind(host, 0, true).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let result = super::bind(host, port, true);
+        assert!(result.is_err(), "\nbind({port}, true) expected an error");
+
+        let result = super::bind(host, port, false)
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        assert_ne!(
+            result, port,
+            "\nbind({port}, false) expected another port but received {result:?}"
+        );
+    }
+
+    #[test]
+    fn is_authorized() {
+        const CASES: [(Option<&str>, bool); 6] = [
+            (Some("Basic dXNlcjpwYXNz"), true),
+            (Some("basic  dXNlcjpwYXNz "), true),
+            (Some("Basic dXNlcjp3cm9uZw=="), false),
+            (Some("Bearer dXNlcjpwYXNz"), false),
+            (Some("Basic"), false),
+            (None, false),
+        ];
+
+        for (authorization, expected) in CASES {
+            let result = super::is_authorized(authorization, "user:pass");
+            assert_eq!(
+                result, expected,
+                "\nis_authorized({authorization:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn matches_etag() {
+        const CASES: [(&str, &str, bool); 5] = [
+            ("\"abc\"", "\"abc\"", true),
+            ("\"xyz\", \"abc\"", "\"abc\"", true),
+            ("W/\"abc\"", "\"abc\"", true),
+            ("*", "\"abc\"", true),
+            ("\"xyz\"", "\"abc\"", false),
+        ];
+
+        for (if_none_match, etag, expected) in CASES {
+            let result = super::matches_etag(if_none_match, etag);
+            assert_eq!(
+                result, expected,
+                "\nmatches_etag({if_none_match:?}, {etag:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
 }
@@ -0,0 +1,185 @@
+//! Query the pages of a built site.
+//!
+//! A [`Site`] is returned by [`crate::build`], so that pages can be used to
+//! generate custom artifacts (e.g. a newsletter) without parsing output files.
+
+use std::collections::BTreeMap;
+
+/// Built site.
+#[derive(Clone, Debug, Default)]
+pub struct Site {
+    /// Pages, sorted by URL.
+    pub(crate) pages: Vec<Page>,
+}
+
+/// Page of a built site.
+#[derive(Clone, Debug, Default)]
+pub struct Page {
+    /// URL of the page (e.g. `/blog/hello/`).
+    pub url: String,
+
+    /// Title of the page.
+    pub title: Option<String>,
+
+    /// Date of the page.
+    pub date: Option<String>,
+
+    /// HTML content of the page, as written to the output file.
+    pub content: String,
+
+    /// Metadata of the page (e.g. front matter fields).
+    pub data: serde_json::Value,
+}
+
+impl Site {
+    /// Create a site from a list of pages.
+    pub(crate) fn new(mut pages: Vec<Page>) -> Self {
+        pages.sort_by(|a, b| a.url.cmp(&b.url));
+        Self { pages }
+    }
+
+    /// Iterate over all pages, sorted by URL.
+    pub fn pages(&self) -> impl Iterator<Item = &Page> {
+        self.pages.iter()
+    }
+
+    /// Iterate over pages located under a URL prefix (e.g. `blog/`).
+    ///
+    /// The page located at the prefix itself (e.g. `/blog/`) is excluded.
+    pub fn pages_in<S>(&self, prefix: S) -> impl Iterator<Item = &Page>
+    where
+        S: AsRef<str>,
+    {
+        let prefix = match prefix.as_ref().trim_matches('/') {
+            "" => "/".to_owned(),
+            prefix => format!("/{prefix}/"),
+        };
+
+        self.pages
+            .iter()
+            .filter(move |page| page.url.starts_with(&prefix) && page.url != prefix)
+    }
+
+    /// Group pages by the terms of a taxonomy (e.g. `tags`).
+    ///
+    /// Terms are read from the metadata of each page, and can be specified as a
+    /// string or an array of strings. Pages without terms are ignored.
+    pub fn taxonomy<S>(&self, key: S) -> BTreeMap<String, Vec<&Page>>
+    where
+        S: AsRef<str>,
+    {
+        let key = key.as_ref();
+
+        let mut taxonomy: BTreeMap<String, Vec<&Page>> = BTreeMap::new();
+
+        for page in self.pages.iter() {
+            let terms: Vec<&str> = match page.data.get(key) {
+                Some(serde_json::Value::String(term)) => vec![term.as_str()],
+                Some(serde_json::Value::Array(terms)) => {
+                    terms.iter().filter_map(|term| term.as_str()).collect()
+                },
+                _ => continue,
+            };
+
+            for term in terms {
+                taxonomy.entry(term.to_owned()).or_default().push(page);
+            }
+        }
+
+        taxonomy
+    }
+
+    /// Find a page by URL.
+    ///
+    /// Trailing slashes are ignored (e.g. `/blog` finds `/blog/`).
+    pub fn find_by_url<S>(&self, url: S) -> Option<&Page>
+    where
+        S: AsRef<str>,
+    {
+        let url = normalize_url(url.as_ref());
+
+        self.pages
+            .iter()
+            .find(|page| normalize_url(&page.url) == url)
+    }
+}
+
+/// Remove the trailing slashes of a URL, except for the root.
+fn normalize_url(url: &str) -> &str {
+    match url.trim_end_matches('/') {
+        "" => "/",
+        url => url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Page, Site};
+
+    fn site() -> Site {
+        let page = |url: &str, data: serde_json::Value| Page {
+            url: url.to_owned(),
+            data,
+            ..Default::default()
+        };
+
+        Site::new(vec![
+            page(
+                "/blog/second/",
+                serde_json::json!({ "tags": ["rust", "web"] }),
+            ),
+            page("/blog/first/", serde_json::json!({ "tags": "rust" })),
+            page("/blog/", serde_json::json!({})),
+            page("/", serde_json::json!({})),
+        ])
+    }
+
+    fn urls<'a>(pages: impl IntoIterator<Item = &'a Page>) -> Vec<&'a str> {
+        pages.into_iter().map(|page| page.url.as_str()).collect()
+    }
+
+    #[test]
+    fn pages() {
+        assert_eq!(urls(site().pages()), [
+            "/",
+            "/blog/",
+            "/blog/first/",
+            "/blog/second/"
+        ]);
+    }
+
+    #[test]
+    fn pages_in() {
+        let site = site();
+        assert_eq!(urls(site.pages_in("blog/")), [
+            "/blog/first/",
+            "/blog/second/"
+        ]);
+        assert_eq!(urls(site.pages_in("/blog")), [
+            "/blog/first/",
+            "/blog/second/"
+        ]);
+    }
+
+    #[test]
+    fn taxonomy() {
+        let site = site();
+        let tags = site.taxonomy("tags");
+        assert_eq!(urls(tags["rust"].iter().copied()), [
+            "/blog/first/",
+            "/blog/second/"
+        ]);
+        assert_eq!(urls(tags["web"].iter().copied()), ["/blog/second/"]);
+        assert!(site.taxonomy("categories").is_empty());
+    }
+
+    #[test]
+    fn find_by_url() {
+        let site = site();
+        assert_eq!(
+            site.find_by_url("/blog").map(|page| page.url.as_str()),
+            Some("/blog/")
+        );
+        assert!(site.find_by_url("/missing/").is_none());
+    }
+}
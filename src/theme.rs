@@ -0,0 +1,209 @@
+//! Fetch remote themes.
+//!
+//! A theme specified by a Git URL (e.g. `https://github.com/user/theme.git`)
+//! is cloned in the cache directory. A branch or a tag can be selected with a
+//! fragment (e.g. `https://github.com/user/theme.git#v1.0`). The commit of
+//! each theme is recorded in the lock file, so that builds are reproducible
+//! until the themes are updated with `vitrine update`.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::Error,
+    util::command::{run, run_string},
+};
+
+/// Name of the file recording the commits of remote themes.
+pub(crate) const LOCK_FILE_NAME: &str = "vitrine.lock";
+
+/// Content of the lock file.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Lock {
+    /// Locked themes, indexed by URL.
+    #[serde(default)]
+    themes: BTreeMap<String, LockedTheme>,
+}
+
+/// Version of a remote theme.
+#[derive(Debug, Deserialize, Serialize)]
+struct LockedTheme {
+    /// Hash of the checked out commit.
+    commit: String,
+}
+
+/// Check whether a theme is specified by a Git URL.
+pub(crate) fn is_remote(theme: &Path) -> bool {
+    theme.to_str().is_some_and(|theme| {
+        ["https://", "http://", "ssh://", "git://", "git@"]
+            .iter()
+            .any(|prefix| theme.starts_with(prefix))
+    })
+}
+
+/// Clone a remote theme in the cache directory, and return its directory.
+///
+/// The theme is checked out at the commit recorded in the lock file. If the
+/// theme is not recorded, the latest commit of the selected branch or tag is
+/// checked out and recorded.
+pub(crate) fn fetch(url: &str, cache_dir: Option<&Path>) -> Result<PathBuf, Error> {
+    let map_err = |error: anyhow::Error| Error::FetchTheme {
+        url: url.to_owned(),
+        source: error,
+    };
+
+    let cache_dir = cache_dir
+        .ok_or_else(|| anyhow::anyhow!("cache_dir is required to fetch remote themes"))
+        .map_err(map_err)?;
+
+    let (repository, reference) = url.split_once('#').unwrap_or((url, "HEAD"));
+
+    let dir = cache_dir.join("themes").join(hash(url));
+
+    let mut lock = read_lock().map_err(map_err)?;
+
+    if !dir.exists() {
+        tracing::info!("Cloning theme {:?}", url);
+
+        run(
+            Command::new("git")
+                .args(["clone", "--quiet", repository])
+                .arg(&dir),
+            None,
+        )
+        .map_err(map_err)?;
+    }
+
+    match lock.themes.get(url) {
+        Some(locked) => {
+            let head = git(Some(&dir), ["rev-parse", "HEAD"]).unwrap_or_default();
+
+            if head.trim() != locked.commit {
+                tracing::info!("Checking out theme {:?} at {}", url, locked.commit);
+
+                git(Some(&dir), ["fetch", "--quiet", "origin"])
+                    .and_then(|_| {
+                        git(Some(&dir), [
+                            "checkout",
+                            "--quiet",
+                            "--detach",
+                            &locked.commit,
+                        ])
+                    })
+                    .map_err(map_err)?;
+            }
+        },
+        None => {
+            tracing::info!("Fetching the latest commit of theme {:?}", url);
+
+            let commit = git(Some(&dir), ["fetch", "--quiet", "origin", reference])
+                .and_then(|_| {
+                    git(Some(&dir), [
+                        "checkout",
+                        "--quiet",
+                        "--detach",
+                        "FETCH_HEAD",
+                    ])
+                })
+                .and_then(|_| git(Some(&dir), ["rev-parse", "HEAD"]))
+                .map_err(map_err)?;
+
+            lock.themes.insert(url.to_owned(), LockedTheme {
+                commit: commit.trim().to_owned(),
+            });
+
+            write_lock(&lock).map_err(map_err)?;
+        },
+    }
+
+    Ok(dir)
+}
+
+/// Remove the lock file, so that the latest commits of remote themes are
+/// fetched and recorded when the configuration is loaded.
+pub(super) fn unlock() -> Result<(), Error> {
+    let lock_path = Path::new(LOCK_FILE_NAME);
+
+    if !lock_path.exists() {
+        return Ok(());
+    }
+
+    tracing::info!("Removing {:?}", lock_path);
+
+    std::fs::remove_file(lock_path).map_err(|error| Error::UpdateLock {
+        source: error.into(),
+    })
+}
+
+/// Read the lock file, if it exists.
+fn read_lock() -> anyhow::Result<Lock> {
+    let lock_path = Path::new(LOCK_FILE_NAME);
+
+    if !lock_path.exists() {
+        return Ok(Lock::default());
+    }
+
+    let content = std::fs::read_to_string(lock_path)?;
+
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Write the lock file.
+fn write_lock(lock: &Lock) -> anyhow::Result<()> {
+    tracing::info!("Writing {:?}", LOCK_FILE_NAME);
+
+    let content = serde_json::to_string_pretty(lock)?;
+
+    Ok(std::fs::write(LOCK_FILE_NAME, content + "\n")?)
+}
+
+/// Compute the name of the directory of a remote theme.
+fn hash(url: &str) -> String {
+    format!("{:x}", Sha256::digest(url))
+}
+
+/// Run a git command, in a directory if specified.
+fn git<'a, I>(dir: Option<&Path>, args: I) -> anyhow::Result<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut command = Command::new("git");
+
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+
+    command.args(args);
+
+    run_string(&mut command, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    #[test]
+    fn is_remote() {
+        const CASES: [(&str, bool); 5] = [
+            ("https://github.com/user/theme.git", true),
+            ("https://github.com/user/theme.git#v1.0", true),
+            ("git@github.com:user/theme.git", true),
+            ("themes/default", false),
+            ("/usr/share/vitrine/theme", false),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::is_remote(Path::new(input));
+            assert_eq!(
+                result, expected,
+                "\nis_remote({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
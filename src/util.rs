@@ -1,10 +1,18 @@
 //! Utility functions and structures.
 
+pub(crate) mod command;
 pub(crate) mod data;
+pub(crate) mod date_time;
 pub(crate) mod feed;
+pub(crate) mod file_content;
 pub(crate) mod from_js;
 pub(crate) mod from_lua;
 pub(crate) mod from_rhai;
 pub(crate) mod function;
+pub(crate) mod html;
+pub(crate) mod http;
 pub(crate) mod path;
+pub(crate) mod slug;
+pub(crate) mod table;
 pub(crate) mod r#unsafe;
+pub(crate) mod url;
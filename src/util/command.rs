@@ -0,0 +1,47 @@
+//! Run external commands.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Run a command and return its standard output.
+///
+/// If `input` is specified, it is written to the standard input.
+pub(crate) fn run(command: &mut Command, input: Option<&[u8]>) -> anyhow::Result<Vec<u8>> {
+    tracing::debug!("Running {:?}", command);
+
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| {
+            anyhow::anyhow!(error).context(format!("While running {:?}", command.get_program()))
+        })?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input)?;
+    }
+
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{:?} failed: {}",
+            command.get_program(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Run a command and return its standard output as a string.
+pub(crate) fn run_string(command: &mut Command, input: Option<&[u8]>) -> anyhow::Result<String> {
+    Ok(String::from_utf8(run(command, input)?)?)
+}
@@ -29,3 +29,49 @@ where
 
     Ok(v1.into())
 }
+
+/// Perform a deep merge of two values.
+///
+/// Objects are merged recursively. Other values of `v2` replace those of `v1`.
+pub(crate) fn deep_merge(v1: serde_json::Value, v2: serde_json::Value) -> serde_json::Value {
+    match (v1, v2) {
+        (serde_json::Value::Object(mut v1), serde_json::Value::Object(v2)) => {
+            for (key, value) in v2 {
+                let merged = match v1.remove(&key) {
+                    Some(previous) => deep_merge(previous, value),
+                    None => value,
+                };
+                v1.insert(key, merged);
+            }
+            v1.into()
+        },
+        (_, v2) => v2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn deep_merge() {
+        let v1 = serde_json::json!({
+            "base_url": "/theme",
+            "minify": true,
+            "layouts": { "content_key": "body", "page_key": "page" },
+        });
+        let v2 = serde_json::json!({
+            "base_url": "/site",
+            "layouts": { "content_key": "content" },
+        });
+
+        let result = super::deep_merge(v1, v2);
+
+        assert_eq!(
+            result,
+            serde_json::json!({
+                "base_url": "/site",
+                "minify": true,
+                "layouts": { "content_key": "content", "page_key": "page" },
+            })
+        );
+    }
+}
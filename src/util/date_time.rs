@@ -0,0 +1,349 @@
+//! Utility structures for dates and times.
+//!
+//! This module uses [`chrono`] under the hood.
+
+use std::time::SystemTime;
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    FixedOffset, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc,
+};
+
+/// Formats for dates and times with a time zone offset.
+const DATE_TIME_OFFSET_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S%.f %:z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+    "%Y-%m-%d %H:%M %:z",
+    "%Y-%m-%d %H:%M %z",
+];
+
+/// Formats for dates and times without time zone offset.
+const NAIVE_DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Formats for dates without time.
+const NAIVE_DATE_FORMATS: [&str; 2] = ["%Y-%m-%d", "%Y/%m/%d"];
+
+/// Date and time with a time zone offset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct DateTime(chrono::DateTime<FixedOffset>);
+
+impl DateTime {
+    /// Parse a date string.
+    ///
+    /// Dates without time zone offset are interpreted in UTC. See
+    /// [`DateTime::parse_with_offset`].
+    pub(crate) fn parse<S>(value: S) -> anyhow::Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        Self::parse_with_offset(value, FixedOffset::east_opt(0).unwrap())
+    }
+
+    /// Parse a date string, using `offset` when the string does not specify a
+    /// time zone offset.
+    ///
+    /// Supported formats are [RFC 3339][rfc3339] (e.g.
+    /// `2024-05-01T10:00:00+02:00`), [RFC 2822][rfc2822] (e.g. `Wed, 01 May
+    /// 2024 10:00:00 +0200`), and common front matter formats (e.g. `2024-05-01
+    /// 10:00`, `2024-05-01`).
+    ///
+    /// [rfc3339]: https://www.rfc-editor.org/rfc/rfc3339
+    /// [rfc2822]: https://www.rfc-editor.org/rfc/rfc2822
+    pub(crate) fn parse_with_offset<S>(value: S, offset: FixedOffset) -> anyhow::Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        let value = value.as_ref().trim();
+
+        if let Ok(date_time) = chrono::DateTime::parse_from_rfc3339(value) {
+            return Ok(Self(date_time));
+        }
+
+        if let Ok(date_time) = chrono::DateTime::parse_from_rfc2822(value) {
+            return Ok(Self(date_time));
+        }
+
+        if let Some(date_time) = DATE_TIME_OFFSET_FORMATS
+            .iter()
+            .find_map(|format| chrono::DateTime::parse_from_str(value, format).ok())
+        {
+            return Ok(Self(date_time));
+        }
+
+        let naive_date_time = NAIVE_DATE_TIME_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+            .or_else(|| {
+                NAIVE_DATE_FORMATS.iter().find_map(|format| {
+                    NaiveDate::parse_from_str(value, format)
+                        .ok()
+                        .and_then(|date| date.and_hms_opt(0, 0, 0))
+                })
+            })
+            .ok_or_else(|| anyhow::anyhow!("Invalid date {:?}", value))?;
+
+        offset
+            .from_local_datetime(&naive_date_time)
+            .single()
+            .map(Self)
+            .ok_or_else(|| anyhow::anyhow!("Invalid date {:?}", value))
+    }
+
+    /// Parse a date string in the time zone of the site, if specified.
+    ///
+    /// Dates without offset are interpreted in `timezone` (UTC by default),
+    /// and the result is converted to `timezone`.
+    pub(crate) fn parse_in_timezone<S>(
+        value: S,
+        timezone: Option<FixedOffset>,
+    ) -> anyhow::Result<Self>
+    where
+        S: AsRef<str>,
+    {
+        let offset = timezone.unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        Ok(Self::parse_with_offset(value, offset)?.to_timezone(timezone))
+    }
+
+    /// Return the same instant in the time zone of the site, if specified.
+    pub(crate) fn to_timezone(self, timezone: Option<FixedOffset>) -> Self {
+        match timezone {
+            Some(offset) => self.with_offset(offset),
+            None => self,
+        }
+    }
+
+    /// Return the same instant in another time zone offset.
+    pub(crate) fn with_offset(&self, offset: FixedOffset) -> Self {
+        Self(self.0.with_timezone(&offset))
+    }
+
+    /// Return the same instant in UTC.
+    pub(crate) fn to_utc(self) -> Self {
+        self.with_offset(FixedOffset::east_opt(0).unwrap())
+    }
+
+    /// Return the date shifted by a number of days.
+    pub(crate) fn add_days(self, days: i64) -> Self {
+        Self(self.0 + TimeDelta::days(days))
+    }
+
+    /// Format the date using a [`chrono::format::strftime`] pattern.
+    ///
+    /// Return an error if the pattern contains an invalid specifier.
+    pub(crate) fn format<S>(&self, pattern: S) -> anyhow::Result<String>
+    where
+        S: AsRef<str>,
+    {
+        let pattern = pattern.as_ref();
+        let items: Vec<Item> = StrftimeItems::new(pattern).collect();
+
+        if items.iter().any(|item| matches!(item, Item::Error)) {
+            return Err(anyhow::anyhow!("Invalid date format {:?}", pattern));
+        }
+
+        Ok(self.0.format_with_items(items.into_iter()).to_string())
+    }
+
+    /// Format the date according to [RFC 3339](https://www.rfc-editor.org/rfc/rfc3339).
+    pub(crate) fn to_rfc3339(self) -> String {
+        self.0.to_rfc3339()
+    }
+}
+
+impl From<SystemTime> for DateTime {
+    fn from(value: SystemTime) -> Self {
+        let date_time: chrono::DateTime<Utc> = value.into();
+        Self(date_time.fixed_offset())
+    }
+}
+
+impl std::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_rfc3339())
+    }
+}
+
+/// Determine whether a date string has no time (e.g. `2024-05-01`).
+pub(crate) fn is_date_only<S>(value: S) -> bool
+where
+    S: AsRef<str>,
+{
+    let value = value.as_ref().trim();
+
+    NAIVE_DATE_FORMATS
+        .iter()
+        .any(|format| NaiveDate::parse_from_str(value, format).is_ok())
+}
+
+/// Parse a time zone offset (e.g. `Z`, `UTC`, `+02:00`, `-0500`).
+pub(crate) fn parse_offset<S>(value: S) -> anyhow::Result<FixedOffset>
+where
+    S: AsRef<str>,
+{
+    let value = value.as_ref().trim();
+
+    if ["", "Z", "UTC", "GMT"].contains(&value) {
+        return Ok(FixedOffset::east_opt(0).unwrap());
+    }
+
+    let (sign, value) = if let Some(value) = value.strip_prefix('+') {
+        (1, value)
+    } else if let Some(value) = value.strip_prefix('-') {
+        (-1, value)
+    } else {
+        return Err(anyhow::anyhow!("Invalid time zone offset {:?}", value));
+    };
+
+    let (hours, minutes) = value
+        .split_once(':')
+        .or_else(|| (value.len() == 4).then(|| value.split_at(2)))
+        .unwrap_or((value, "0"));
+
+    let seconds = hours
+        .parse::<i32>()
+        .ok()
+        .zip(minutes.parse::<i32>().ok())
+        .filter(|(hours, minutes)| *hours < 24 && *minutes < 60)
+        .map(|(hours, minutes)| sign * (hours * 3600 + minutes * 60))
+        .ok_or_else(|| anyhow::anyhow!("Invalid time zone offset {:?}", value))?;
+
+    FixedOffset::east_opt(seconds)
+        .ok_or_else(|| anyhow::anyhow!("Invalid time zone offset {:?}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse() {
+        const CASES: [(&str, &str); 9] = [
+            ("2024-05-01T10:00:00+02:00", "2024-05-01T10:00:00+02:00"),
+            ("2024-05-01T10:00:00Z", "2024-05-01T10:00:00+00:00"),
+            (
+                "2024-05-01T10:00:00.5-05:00",
+                "2024-05-01T10:00:00.500-05:00",
+            ),
+            (
+                "Wed, 01 May 2024 10:00:00 +0200",
+                "2024-05-01T10:00:00+02:00",
+            ),
+            ("2024-05-01 10:00:00 +02:00", "2024-05-01T10:00:00+02:00"),
+            ("2024-05-01 10:00", "2024-05-01T10:00:00+00:00"),
+            ("2024-05-01T10:00:30", "2024-05-01T10:00:30+00:00"),
+            ("2024-05-01", "2024-05-01T00:00:00+00:00"),
+            ("2024/05/01", "2024-05-01T00:00:00+00:00"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::DateTime::parse(input).unwrap().to_rfc3339();
+            assert_eq!(
+                result, expected,
+                "\nparse({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+
+        assert!(super::DateTime::parse("yesterday").is_err());
+    }
+
+    #[test]
+    fn parse_with_offset() {
+        let offset = super::parse_offset("+02:00").unwrap();
+
+        const CASES: [(&str, &str); 3] = [
+            ("2024-05-01 10:00", "2024-05-01T10:00:00+02:00"),
+            ("2024-05-01", "2024-05-01T00:00:00+02:00"),
+            ("2024-05-01T10:00:00Z", "2024-05-01T10:00:00+00:00"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::DateTime::parse_with_offset(input, offset)
+                .unwrap()
+                .to_rfc3339();
+            assert_eq!(
+                result, expected,
+                "\nparse_with_offset({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_in_timezone() {
+        let timezone = Some(super::parse_offset("-05:00").unwrap());
+
+        const CASES: [(&str, &str); 3] = [
+            ("2024-05-01", "2024-05-01T00:00:00-05:00"),
+            ("2024-05-01 22:00", "2024-05-01T22:00:00-05:00"),
+            ("2024-05-02T01:00:00Z", "2024-05-01T20:00:00-05:00"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::DateTime::parse_in_timezone(input, timezone)
+                .unwrap()
+                .to_rfc3339();
+            assert_eq!(
+                result, expected,
+                "\nparse_in_timezone({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn is_date_only() {
+        const CASES: [(&str, bool); 4] = [
+            ("2024-05-01", true),
+            ("2024/05/01", true),
+            ("2024-05-01 10:00", false),
+            ("2024-05-01T10:00:00Z", false),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::is_date_only(input);
+            assert_eq!(
+                result, expected,
+                "\nis_date_only({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn format() {
+        let date_time = super::DateTime::parse("2024-05-01T22:30:00Z").unwrap();
+
+        assert_eq!(date_time.format("%d/%m/%Y").unwrap(), "01/05/2024");
+        assert_eq!(
+            date_time
+                .with_offset(super::parse_offset("+02:00").unwrap())
+                .format("%Y-%m-%d %H:%M %:z")
+                .unwrap(),
+            "2024-05-02 00:30 +02:00"
+        );
+        assert!(date_time.format("%Q").is_err());
+    }
+
+    #[test]
+    fn parse_offset() {
+        const CASES: [(&str, i32); 6] = [
+            ("Z", 0),
+            ("UTC", 0),
+            ("+02:00", 7200),
+            ("-0530", -19800),
+            ("+9", 32400),
+            ("-00:30", -1800),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::parse_offset(input).unwrap().local_minus_utc();
+            assert_eq!(
+                result, expected,
+                "\nparse_offset({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+
+        assert!(super::parse_offset("02:00").is_err());
+        assert!(super::parse_offset("+25:00").is_err());
+    }
+}
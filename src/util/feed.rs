@@ -1,3 +1,4 @@
 //! Utility structures for feeds.
 
 pub(crate) mod atom;
+pub(crate) mod opml;
@@ -0,0 +1,50 @@
+//! Utility structures for OPML files.
+//!
+//! The structures follow the [OPML 2.0](https://opml.org/spec2.opml) specification.
+
+use serde::Serialize;
+
+/// Version of the OPML specification.
+pub(crate) const VERSION: &str = "2.0";
+
+/// Root element.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Opml {
+    #[serde(rename = "@version")]
+    pub(crate) version: &'static str,
+    pub(crate) head: Head,
+    pub(crate) body: Body,
+}
+
+/// Metadata of the document.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Head {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+}
+
+/// Content of the document.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Body {
+    pub(crate) outline: Vec<Outline>,
+}
+
+/// Outline of a subscription list.
+///
+/// Each outline represents a feed, with `type` set to `rss` for RSS and Atom
+/// feeds.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Outline {
+    #[serde(rename = "@type")]
+    pub(crate) r#type: &'static str,
+    #[serde(rename = "@text")]
+    pub(crate) text: String,
+    #[serde(rename = "@title", skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    #[serde(rename = "@xmlUrl")]
+    pub(crate) xml_url: String,
+    #[serde(rename = "@htmlUrl", skip_serializing_if = "Option::is_none")]
+    pub(crate) html_url: Option<String>,
+    #[serde(rename = "@description", skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+}
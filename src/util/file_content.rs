@@ -0,0 +1,94 @@
+//! Binary content of files, read in memory or memory-mapped.
+
+use std::{fs::File, ops::Deref, path::Path};
+
+use memmap2::Mmap;
+
+/// Binary content of a file.
+///
+/// The content dereferences to a byte slice, whichever the variant.
+#[derive(Debug)]
+pub(crate) enum FileContent {
+    /// Content read in memory.
+    Bytes(Vec<u8>),
+
+    /// Content mapped in memory by the operating system, without loading the
+    /// whole file in heap memory.
+    Mapped(Mmap),
+}
+
+impl FileContent {
+    /// Read the content of a file.
+    ///
+    /// Files whose size is at least `mmap_threshold` bytes are memory-mapped.
+    /// If `mmap_threshold` is `None`, files are always read in memory.
+    pub(crate) fn read<P>(path: P, mmap_threshold: Option<usize>) -> std::io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        let Some(mmap_threshold) = mmap_threshold else {
+            return std::fs::read(path).map(Self::Bytes);
+        };
+
+        let file = File::open(path)?;
+
+        // Empty files cannot be mapped on some platforms
+        let len = file.metadata()?.len() as usize;
+
+        if len == 0 || len < mmap_threshold {
+            return std::fs::read(path).map(Self::Bytes);
+        }
+
+        // SAFETY: the mapping is read-only. Output files are not modified
+        // while their content is used, but the content is undefined if
+        // another process truncates the file meanwhile.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        Ok(Self::Mapped(mmap))
+    }
+}
+
+impl Deref for FileContent {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Bytes(bytes) => bytes,
+            Self::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::prelude::*;
+
+    use super::FileContent;
+
+    #[test]
+    fn read() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let file = dir.child("file.txt");
+        file.write_str("Hello").unwrap();
+
+        const CASES: [(Option<usize>, bool); 4] = [
+            (None, false),
+            (Some(0), true),
+            (Some(5), true),
+            (Some(6), false),
+        ];
+
+        for (mmap_threshold, expected) in CASES {
+            let content = FileContent::read(file.path(), mmap_threshold).unwrap();
+            let result = matches!(content, FileContent::Mapped(_));
+            assert_eq!(&*content, b"Hello");
+            assert_eq!(
+                result, expected,
+                "\nFileContent::read({mmap_threshold:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
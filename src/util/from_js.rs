@@ -40,6 +40,19 @@ impl FromJs for f64 {
     }
 }
 
+impl FromJs for usize {
+    fn from_js(value: JsValueFacade, _: Arc<QuickJsRuntimeFacade>) -> anyhow::Result<Self> {
+        if value.is_i32() {
+            Ok(usize::try_from(value.get_i32())?)
+        } else {
+            Err(anyhow::anyhow!(
+                "Expected integer, received {}",
+                value.get_value_type()
+            ))
+        }
+    }
+}
+
 impl FromJs for String {
     fn from_js(value: JsValueFacade, _: Arc<QuickJsRuntimeFacade>) -> anyhow::Result<Self> {
         if value.is_string() {
@@ -27,6 +27,26 @@ impl FromLua for f64 {
     }
 }
 
+impl FromLua for usize {
+    fn from_lua(value: mlua::Value, _: &mlua::Lua) -> anyhow::Result<Self> {
+        value
+            .as_usize()
+            .or_else(|| {
+                // Lua 5.1 represents all numbers as floats
+                value
+                    .as_f64()
+                    .filter(|number| *number >= 0.0 && number.fract() == 0.0)
+                    .map(|number| number as usize)
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Expected non-negative integer, received {}",
+                    value.type_name()
+                )
+            })
+    }
+}
+
 impl FromLua for String {
     fn from_lua(value: mlua::Value, _: &mlua::Lua) -> anyhow::Result<Self> {
         Ok(value
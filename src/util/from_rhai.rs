@@ -32,6 +32,16 @@ impl FromRhai for f64 {
     }
 }
 
+impl FromRhai for usize {
+    fn from_rhai(value: &Dynamic, _: Arc<Engine>, _: Arc<AST>) -> anyhow::Result<Self> {
+        let value = value
+            .to_owned()
+            .as_int()
+            .map_err(|error| anyhow::anyhow!("Expected int, received {}", error))?;
+        Ok(usize::try_from(value)?)
+    }
+}
+
 impl FromRhai for String {
     fn from_rhai(value: &Dynamic, _: Arc<Engine>, _: Arc<AST>) -> anyhow::Result<Self> {
         value
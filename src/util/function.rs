@@ -4,7 +4,10 @@ mod js;
 mod lua;
 mod rhai;
 
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use super::{from_js::FromJs, from_lua::FromLua, from_rhai::FromRhai};
 
@@ -54,6 +57,41 @@ impl Function {
     impl_function_call!(call_2(a1: A1, a2: A2));
 }
 
+/// Memoized results of function calls.
+///
+/// Results are indexed by function name and serialized arguments, so that pure
+/// functions (i.e. that always return the same result for the same arguments)
+/// are called once per distinct arguments.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Memo(Arc<Mutex<HashMap<String, serde_json::Value>>>);
+
+impl Memo {
+    /// Return the memoized result of a call, or call a function and memoize
+    /// its result.
+    pub(crate) fn get_or_call<A, R, F>(&self, name: &str, args: &A, f: F) -> anyhow::Result<R>
+    where
+        A: serde::Serialize + ?Sized,
+        R: serde::Serialize + serde::de::DeserializeOwned,
+        F: FnOnce() -> anyhow::Result<R>,
+    {
+        // Maps are serialized with sorted keys
+        let key = format!("{name}\n{}", serde_json::to_value(args)?);
+
+        if let Some(result) = self.0.lock().unwrap().get(&key) {
+            return Ok(R::deserialize(result)?);
+        }
+
+        let result = f()?;
+
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key, serde_json::to_value(&result)?);
+
+        Ok(result)
+    }
+}
+
 impl FromJs for Function {
     fn from_js(
         value: quickjs_runtime::values::JsValueFacade,
@@ -80,3 +118,28 @@ impl FromRhai for Function {
         )?))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    #[test]
+    fn memo_get_or_call() {
+        let memo = super::Memo::default();
+        let num_calls = Cell::new(0);
+
+        let call = |name: &str, value: i64| -> i64 {
+            memo.get_or_call(name, &value, || {
+                num_calls.set(num_calls.get() + 1);
+                Ok(value * 2)
+            })
+            .unwrap()
+        };
+
+        assert_eq!(call("double", 1), 2);
+        assert_eq!(call("double", 1), 2);
+        assert_eq!(call("double", 2), 4);
+        assert_eq!(call("twice", 1), 2);
+        assert_eq!(num_calls.get(), 3);
+    }
+}
@@ -0,0 +1,22 @@
+//! Functions for HTML code.
+
+/// Escape special HTML characters, including quotes for attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Unescape the HTML characters escaped by [`escape`], as well as apostrophes.
+///
+/// `lol_html` returns attribute values as written in the source, so they must
+/// be unescaped before being compared to URLs.
+pub(crate) fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
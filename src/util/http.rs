@@ -0,0 +1,122 @@
+//! Fetch remote files with `curl`.
+
+use std::{path::Path, process::Command};
+
+use super::command::run;
+
+/// Arguments of `curl` common to all requests.
+///
+/// Requests fail on HTTP errors, follow redirects and only use HTTP(S).
+const CURL_ARGS: [&str; 8] = [
+    "--fail",
+    "--silent",
+    "--show-error",
+    "--location",
+    "--proto",
+    "=http,https",
+    "--proto-redir",
+    "=http,https",
+];
+
+/// Path of the file that discards output.
+#[cfg(windows)]
+const NULL_PATH: &str = "NUL";
+#[cfg(not(windows))]
+const NULL_PATH: &str = "/dev/null";
+
+/// Fetch the content of a URL.
+pub(crate) fn get(url: &str) -> anyhow::Result<String> {
+    check_url(url)?;
+
+    let output = run(Command::new("curl").args(CURL_ARGS).arg(url), None)?;
+
+    Ok(String::from_utf8(output)?)
+}
+
+/// Fetch the content of a URL, with the headers of the response.
+///
+/// If the request is redirected, the headers of the last response are
+/// returned.
+pub(crate) fn get_with_headers(url: &str) -> anyhow::Result<(String, String)> {
+    check_url(url)?;
+
+    let output = run(
+        Command::new("curl")
+            .args(CURL_ARGS)
+            .args(["--dump-header", "-"])
+            .arg(url),
+        None,
+    )?;
+
+    let mut output = String::from_utf8_lossy(&output).into_owned();
+    let mut headers = String::new();
+
+    // Each response of redirections has its own headers
+    while output.starts_with("HTTP/") {
+        let Some((response_headers, body)) = output.split_once("\r\n\r\n") else {
+            break;
+        };
+        headers = response_headers.to_owned();
+        output = body.to_owned();
+    }
+
+    Ok((headers, output))
+}
+
+/// Send a form to a URL with a `POST` request.
+pub(crate) fn post_form(url: &str, fields: &[(&str, &str)]) -> anyhow::Result<()> {
+    check_url(url)?;
+
+    let mut command = Command::new("curl");
+    command.args(CURL_ARGS).arg("--output").arg(NULL_PATH);
+
+    for (name, value) in fields {
+        command
+            .arg("--data-urlencode")
+            .arg(format!("{name}={value}"));
+    }
+
+    run(command.arg(url), None)?;
+
+    Ok(())
+}
+
+/// Download the content of a URL to a file.
+///
+/// The content is downloaded to a temporary file first, so that interrupted
+/// downloads are not reused.
+pub(crate) fn download(url: &str, path: &Path) -> anyhow::Result<()> {
+    check_url(url)?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+
+    let result = run(
+        Command::new("curl")
+            .args(CURL_ARGS)
+            .arg("--output")
+            .arg(&temp_path)
+            .arg(url),
+        None,
+    );
+
+    if let Err(error) = result {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(error);
+    }
+
+    Ok(std::fs::rename(&temp_path, path)?)
+}
+
+/// Check that a URL uses HTTP(S), so that it cannot be taken for an option of
+/// `curl`.
+fn check_url(url: &str) -> anyhow::Result<()> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        anyhow::bail!("Expected an HTTP(S) URL, received {url:?}");
+    }
+
+    Ok(())
+}
@@ -9,6 +9,17 @@ pub(crate) trait PathExt {
     ///
     /// This method calls [`normalize_path`] under the hood.
     fn normalize(&self) -> PathBuf;
+
+    /// Convert the path to a URL path, with `/` separators.
+    ///
+    /// This method calls [`path_to_url_path`] under the hood.
+    fn to_url_path(&self) -> Option<String>;
+
+    /// Join a URL path, with `/` separators, to the path.
+    ///
+    /// This method calls [`url_path_to_path`] and [`normalize_path`] under the
+    /// hood.
+    fn join_url_path(&self, url_path: &str) -> PathBuf;
 }
 
 impl<T> PathExt for T
@@ -18,6 +29,113 @@ where
     fn normalize(&self) -> PathBuf {
         self::normalize_path(self)
     }
+
+    fn to_url_path(&self) -> Option<String> {
+        self::path_to_url_path(self)
+    }
+
+    fn join_url_path(&self, url_path: &str) -> PathBuf {
+        self::normalize_path(self.as_ref().join(self::url_path_to_path(url_path)))
+    }
+}
+
+/// Convert a path to a URL path, with `/` separators.
+///
+/// Paths are considered relative to the root of the site, so that `foo/bar`
+/// and `/foo/bar` both return `/foo/bar`. On Windows, back slashes are
+/// replaced by `/`, and prefixes are kept in the URL path: drive letters as
+/// `/C:` and UNC prefixes as `//server/share`. Verbatim prefixes (e.g.
+/// `\\?\C:`), as returned by [`std::fs::canonicalize`], are converted like
+/// their non-verbatim equivalent.
+///
+/// Returns `None` if the path is not valid UTF-8, or has a prefix that cannot
+/// be represented in a URL (e.g. `\\.\COM1`).
+pub(crate) fn path_to_url_path<P>(path: P) -> Option<String>
+where
+    P: AsRef<Path>,
+{
+    use std::path::{Component, Prefix};
+
+    let mut url_path = String::new();
+
+    for component in path.as_ref().components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                    url_path.push('/');
+                    url_path.push(char::from(letter));
+                    url_path.push(':');
+                },
+                Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+                    url_path.push_str("//");
+                    url_path.push_str(server.to_str()?);
+                    url_path.push('/');
+                    url_path.push_str(share.to_str()?);
+                },
+                Prefix::Verbatim(_) | Prefix::DeviceNS(_) => return None,
+            },
+            Component::RootDir | Component::CurDir => {},
+            Component::ParentDir | Component::Normal(_) => {
+                url_path.push('/');
+                url_path.push_str(component.as_os_str().to_str()?);
+            },
+        }
+    }
+
+    if url_path.is_empty() {
+        url_path.push('/');
+    }
+
+    Some(url_path)
+}
+
+/// Convert a URL path, with `/` separators, to a path with native separators.
+///
+/// The returned path is relative, so that it can be joined to a base
+/// directory, including one with a verbatim prefix (e.g. `\\?\C:\site`) where
+/// `/` is not a separator. On Windows, the drive letters and UNC prefixes
+/// returned by [`path_to_url_path`] are converted back, hence an absolute path.
+pub(crate) fn url_path_to_path(url_path: &str) -> PathBuf {
+    #[cfg(windows)]
+    if let Some(path) = url_path_prefix(url_path) {
+        return path;
+    }
+
+    url_path
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect()
+}
+
+/// Convert a URL path starting with a drive letter (e.g. `/C:/foo`) or a UNC
+/// prefix (e.g. `//server/share/foo`) to an absolute path.
+#[cfg(windows)]
+fn url_path_prefix(url_path: &str) -> Option<PathBuf> {
+    let (prefix, rest) = if let Some(rest) = url_path.strip_prefix("//") {
+        let mut segments = rest.splitn(3, '/');
+        let server = segments.next().filter(|s| !s.is_empty())?;
+        let share = segments.next().filter(|s| !s.is_empty())?;
+        (
+            format!(r"\\{server}\{share}\"),
+            segments.next().unwrap_or_default(),
+        )
+    } else {
+        let rest = url_path.strip_prefix('/')?;
+        let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        match drive.as_bytes() {
+            [letter, b':'] if letter.is_ascii_alphabetic() => (format!("{drive}\\"), rest),
+            _ => return None,
+        }
+    };
+
+    Some(
+        rest.split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .fold(PathBuf::from(prefix), |mut path, segment| {
+                path.push(segment);
+                path
+            }),
+    )
 }
 
 /// Normalize a path by removing unnecessary separators and `.` and `..`
@@ -163,6 +281,130 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::path::Path;
+
+    #[test]
+    #[cfg(unix)]
+    fn path_to_url_path_unix() {
+        const CASES: [(&str, Option<&str>); 6] = [
+            ("", Some("/")),
+            ("/", Some("/")),
+            ("foo/bar.md", Some("/foo/bar.md")),
+            ("/foo/./bar.md", Some("/foo/bar.md")),
+            ("foo/../bar", Some("/foo/../bar")),
+            ("foo\\bar", Some("/foo\\bar")),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::path_to_url_path(input);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\npath_to_url_path({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn path_to_url_path_windows() {
+        const CASES: [(&str, Option<&str>); 9] = [
+            ("", Some("/")),
+            ("\\", Some("/")),
+            (r"foo\bar.md", Some("/foo/bar.md")),
+            ("foo/bar.md", Some("/foo/bar.md")),
+            (r"C:\foo\bar.md", Some("/C:/foo/bar.md")),
+            (r"\\?\C:\foo\bar.md", Some("/C:/foo/bar.md")),
+            (r"\\server\share\foo", Some("//server/share/foo")),
+            (r"\\?\UNC\server\share\foo", Some("//server/share/foo")),
+            (r"\\.\COM1", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::path_to_url_path(input);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\npath_to_url_path({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn url_path_to_path_unix() {
+        const CASES: [(&str, &str); 6] = [
+            ("", ""),
+            ("/", ""),
+            ("/foo/bar.md", "foo/bar.md"),
+            ("foo//./bar.md", "foo/bar.md"),
+            ("../foo", "../foo"),
+            ("/C:/foo", "C:/foo"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::url_path_to_path(input);
+            assert_eq!(
+                result,
+                Path::new(expected),
+                "\nurl_path_to_path({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn url_path_to_path_windows() {
+        const CASES: [(&str, &str); 7] = [
+            ("", ""),
+            ("/", ""),
+            ("/foo/bar.md", r"foo\bar.md"),
+            ("foo//./bar.md", r"foo\bar.md"),
+            ("/C:/foo/bar.md", r"C:\foo\bar.md"),
+            ("/C:", r"C:\"),
+            ("//server/share/foo", r"\\server\share\foo"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::url_path_to_path(input);
+            assert_eq!(
+                result.to_str().unwrap(),
+                expected,
+                "\nurl_path_to_path({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn join_url_path_windows() {
+        use super::PathExt;
+
+        const CASES: [(&str, &str, &str); 4] = [
+            (r"C:\site", "/foo/bar.md", r"C:\site\foo\bar.md"),
+            (r"\\?\C:\site", "foo/../bar.md", r"\\?\C:\site\bar.md"),
+            (
+                r"\\server\share\site",
+                "./foo/bar.md",
+                r"\\server\share\site\foo\bar.md",
+            ),
+            (
+                r"\\?\UNC\server\share\site",
+                "../foo",
+                r"\\?\UNC\server\share\foo",
+            ),
+        ];
+
+        for (base, input, expected) in CASES {
+            let result = base.join_url_path(input);
+            assert_eq!(
+                result.to_str().unwrap(),
+                expected,
+                "\n{base:?}.join_url_path({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
     #[test]
     #[cfg(unix)]
     fn normalize_path_unix() {
@@ -0,0 +1,142 @@
+//! Convert strings to URL slugs.
+//!
+//! A slug is a lowercase string made of words separated by dashes (e.g.
+//! `hello-world`), suitable for URLs and heading anchors.
+
+/// Strategy used to convert strings to slugs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum SlugStrategy {
+    /// Transliterate Unicode characters to ASCII (e.g. `Ĉu vi?` becomes
+    /// `cu-vi`).
+    #[default]
+    Transliterate,
+
+    /// Keep Unicode letters and digits (e.g. `Ĉu vi?` becomes `ĉu-vi`).
+    Unicode,
+
+    /// Keep ASCII letters and digits only, and drop other characters (e.g.
+    /// `Ĉu vi?` becomes `u-vi`).
+    Ascii,
+}
+
+impl SlugStrategy {
+    /// Convert a string to a slug.
+    pub(crate) fn slugify<S>(self, value: S) -> String
+    where
+        S: AsRef<str>,
+    {
+        (self.slugify_fn())(value.as_ref())
+    }
+
+    /// Return the function that converts strings to slugs.
+    pub(crate) fn slugify_fn(self) -> fn(&str) -> String {
+        match self {
+            Self::Transliterate => slugify_transliterate,
+            Self::Unicode => slugify_unicode,
+            Self::Ascii => slugify_ascii,
+        }
+    }
+}
+
+impl std::str::FromStr for SlugStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "transliterate" => Ok(Self::Transliterate),
+            "unicode" => Ok(Self::Unicode),
+            "ascii" => Ok(Self::Ascii),
+            _ => Err(anyhow::anyhow!(
+                "Unknown slug strategy {:?} (expected \"transliterate\", \"unicode\" or \"ascii\")",
+                value
+            )),
+        }
+    }
+}
+
+/// Convert a string to a slug, transliterating Unicode characters to ASCII.
+fn slugify_transliterate(value: &str) -> String {
+    slug::slugify(value)
+}
+
+/// Convert a string to a slug, keeping Unicode letters and digits.
+fn slugify_unicode(value: &str) -> String {
+    join_words(
+        value.chars().flat_map(char::to_lowercase),
+        char::is_alphanumeric,
+    )
+}
+
+/// Convert a string to a slug, keeping ASCII letters and digits only.
+fn slugify_ascii(value: &str) -> String {
+    join_words(value.chars().map(|c| c.to_ascii_lowercase()), |c| {
+        c.is_ascii_alphanumeric()
+    })
+}
+
+/// Join the words of a sequence of characters with dashes.
+///
+/// Characters that do not satisfy `is_word` are treated as separators.
+fn join_words<I, F>(chars: I, is_word: F) -> String
+where
+    I: Iterator<Item = char>,
+    F: Fn(char) -> bool,
+{
+    let mut slug = String::new();
+    let mut separator = false;
+
+    for c in chars {
+        if is_word(c) {
+            if separator && !slug.is_empty() {
+                slug.push('-');
+            }
+            slug.push(c);
+            separator = false;
+        } else if !c.is_alphanumeric() {
+            separator = true;
+        }
+    }
+
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlugStrategy;
+
+    #[test]
+    fn slugify() {
+        const CASES: [(SlugStrategy, &str, &str); 9] = [
+            (SlugStrategy::Transliterate, "Hello, World!", "hello-world"),
+            (
+                SlugStrategy::Transliterate,
+                "Ĉu vi parolas?",
+                "cu-vi-parolas",
+            ),
+            (SlugStrategy::Transliterate, "  Déjà vu  ", "deja-vu"),
+            (SlugStrategy::Unicode, "Hello, World!", "hello-world"),
+            (SlugStrategy::Unicode, "Ĉu vi parolas?", "ĉu-vi-parolas"),
+            (SlugStrategy::Unicode, "日本語 テキスト", "日本語-テキスト"),
+            (SlugStrategy::Ascii, "Hello, World!", "hello-world"),
+            (SlugStrategy::Ascii, "Ĉu vi parolas?", "u-vi-parolas"),
+            (SlugStrategy::Ascii, "C++ & Rust_2021", "c-rust-2021"),
+        ];
+
+        for (strategy, input, expected) in CASES {
+            let result = strategy.slugify(input);
+            assert_eq!(
+                result, expected,
+                "\n{strategy:?}.slugify({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            "unicode".parse::<SlugStrategy>().unwrap(),
+            SlugStrategy::Unicode
+        );
+        assert!("unknown".parse::<SlugStrategy>().is_err());
+    }
+}
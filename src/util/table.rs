@@ -0,0 +1,49 @@
+//! Format text tables.
+
+/// Format rows as a table with aligned columns.
+///
+/// The first column is aligned left, the other columns are aligned right.
+pub(crate) fn format_table<const N: usize>(rows: &[[String; N]]) -> String {
+    let widths: Vec<usize> = (0..N)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths.iter())
+                .enumerate()
+                .map(|(column, (cell, &width))| match column {
+                    0 => format!("{cell:<width$}"),
+                    _ => format!("{cell:>width$}"),
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn format_table() {
+        let rows = [
+            ["Page", "Total"].map(str::to_owned),
+            ["/", "1.5 KiB"].map(str::to_owned),
+            ["/about/", "12 B"].map(str::to_owned),
+        ];
+
+        assert_eq!(
+            super::format_table(&rows),
+            "Page       Total\n/        1.5 KiB\n/about/     12 B"
+        );
+    }
+}
@@ -0,0 +1,896 @@
+//! Utility structures for URLs.
+//!
+//! The structures follow the [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986) specification.
+
+/// URL.
+///
+/// ```text
+/// URI-reference = [ scheme ":" ] [ "//" authority ] path [ "?" query ] [ "#" fragment ]
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Url {
+    /// Scheme (e.g. `https`).
+    pub(crate) scheme: Option<String>,
+
+    /// Authority (e.g. `user@example.com:8080`).
+    pub(crate) authority: Option<UrlAuthority>,
+
+    /// Path (e.g. `/blog/index.html`).
+    pub(crate) path: UrlPath,
+
+    /// Query string, without the leading `?`.
+    pub(crate) query: Option<String>,
+
+    /// Fragment, without the leading `#`.
+    pub(crate) fragment: Option<String>,
+}
+
+impl Url {
+    /// Parse and validate a URL string.
+    ///
+    /// Unlike [`Url::from`], this function rejects invalid schemes, invalid
+    /// ports and characters that are not allowed in URLs. Positions reported in
+    /// errors are character indices in the input string.
+    pub(crate) fn parse<S>(value: S) -> Result<Self, UrlParseError>
+    where
+        S: AsRef<str>,
+    {
+        let value = value.as_ref();
+
+        for (position, (index, character)) in value.char_indices().enumerate() {
+            if character == '%' {
+                // Percent-encoded octets must be followed by two hexadecimal digits
+                if value[index + 1..]
+                    .chars()
+                    .take(2)
+                    .filter(|c| c.is_ascii_hexdigit())
+                    .count()
+                    != 2
+                {
+                    return Err(UrlParseError::InvalidPercentEncoding { position });
+                }
+            } else if character.is_control()
+                || character.is_whitespace()
+                || "\"<>\\^`{|}".contains(character)
+            {
+                return Err(UrlParseError::IllegalCharacter {
+                    character,
+                    position,
+                });
+            }
+        }
+
+        let url = Self::from(value);
+
+        // A `:` before any `/`, `?` or `#` delimits a scheme
+        if let Some(scheme) = value
+            .find(|c| ":/?#".contains(c))
+            .filter(|&index| value[index..].starts_with(':'))
+            .map(|index| &value[..index])
+        {
+            if url.scheme.is_none() {
+                let position = scheme
+                    .chars()
+                    .enumerate()
+                    .position(|(position, c)| {
+                        !(c.is_ascii_alphabetic()
+                            || position > 0 && (c.is_ascii_digit() || "+-.".contains(c)))
+                    })
+                    .unwrap_or_default();
+                return Err(UrlParseError::InvalidScheme { position });
+            }
+        }
+
+        if let Some(authority) = url.authority.as_ref() {
+            if let Some(port) = authority.port.as_ref() {
+                if !port.chars().all(|c| c.is_ascii_digit()) || port.parse::<u16>().is_err() {
+                    // Authority is located after `{scheme}://`
+                    let offset = url.scheme.as_ref().map_or(0, |scheme| scheme.len() + 1)
+                        + 2
+                        + authority.to_string().len()
+                        - port.len();
+                    return Err(UrlParseError::InvalidPort {
+                        port: port.to_owned(),
+                        position: value[..offset].chars().count(),
+                    });
+                }
+            }
+        }
+
+        Ok(url)
+    }
+
+    /// Normalize the URL.
+    ///
+    /// The scheme is converted to lowercase and the authority is normalized
+    /// with [`UrlAuthority::normalize`].
+    pub(crate) fn normalize(&self) -> Self {
+        Self {
+            scheme: self.scheme.as_ref().map(|scheme| scheme.to_lowercase()),
+            authority: self
+                .authority
+                .as_ref()
+                .map(|authority| authority.normalize()),
+            ..self.to_owned()
+        }
+    }
+
+    /// Convert the host to Unicode for display purposes.
+    ///
+    /// See [`UrlAuthority::to_unicode`].
+    pub(crate) fn to_unicode(&self) -> Self {
+        Self {
+            authority: self
+                .authority
+                .as_ref()
+                .map(|authority| authority.to_unicode()),
+            ..self.to_owned()
+        }
+    }
+}
+
+impl From<&str> for Url {
+    /// Split a string into URL components.
+    ///
+    /// This conversion never fails: a string that is not a valid URL is
+    /// interpreted as a path.
+    fn from(value: &str) -> Self {
+        let (value, fragment) = value
+            .split_once('#')
+            .map_or((value, None), |(value, fragment)| {
+                (value, Some(fragment.to_owned()))
+            });
+
+        let (value, query) = value
+            .split_once('?')
+            .map_or((value, None), |(value, query)| {
+                (value, Some(query.to_owned()))
+            });
+
+        // A scheme starts with a letter, followed by letters, digits, `+`, `-` or `.`
+        let (scheme, value) = value
+            .split_once(':')
+            .filter(|(scheme, _)| {
+                scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+            })
+            .map_or((None, value), |(scheme, value)| {
+                (Some(scheme.to_owned()), value)
+            });
+
+        let (authority, path) = if let Some(value) = value.strip_prefix("//") {
+            let (authority, path) = value
+                .find('/')
+                .map_or((value, ""), |index| value.split_at(index));
+            (Some(UrlAuthority::from(authority)), path)
+        } else {
+            (None, value)
+        };
+
+        Self {
+            scheme,
+            authority,
+            path: UrlPath::from(path),
+            query,
+            fragment,
+        }
+    }
+}
+
+impl From<String> for Url {
+    fn from(value: String) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+impl std::fmt::Display for Url {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(scheme) = self.scheme.as_ref() {
+            write!(f, "{scheme}:")?;
+        }
+        if let Some(authority) = self.authority.as_ref() {
+            write!(f, "//{authority}")?;
+        }
+        write!(f, "{}", self.path)?;
+        if let Some(query) = self.query.as_ref() {
+            write!(f, "?{query}")?;
+        }
+        if let Some(fragment) = self.fragment.as_ref() {
+            write!(f, "#{fragment}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned by [`Url::parse`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub(crate) enum UrlParseError {
+    #[error("Illegal character {character:?} at position {position}")]
+    IllegalCharacter { character: char, position: usize },
+    #[error("Invalid percent-encoding at position {position}")]
+    InvalidPercentEncoding { position: usize },
+    #[error("Invalid port {port:?} at position {position}")]
+    InvalidPort { port: String, position: usize },
+    #[error("Invalid scheme at position {position}")]
+    InvalidScheme { position: usize },
+}
+
+/// URL authority.
+///
+/// ```text
+/// authority = [ userinfo "@" ] host [ ":" port ]
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct UrlAuthority {
+    /// User information, without the trailing `@`.
+    pub(crate) userinfo: Option<String>,
+
+    /// Host name or IP address.
+    pub(crate) host: String,
+
+    /// Port, without the leading `:`.
+    pub(crate) port: Option<String>,
+}
+
+impl UrlAuthority {
+    /// Normalize the authority.
+    ///
+    /// The host is converted to lowercase, and internationalized domain names
+    /// are converted to their ASCII form using [Punycode][rfc] (e.g.
+    /// `bücher.example` becomes `xn--bcher-kva.example`). Hosts that cannot be
+    /// converted (e.g. IPv6 addresses) are only converted to lowercase.
+    ///
+    /// [rfc]: https://www.rfc-editor.org/rfc/rfc3492
+    pub(crate) fn normalize(&self) -> Self {
+        let host = if self.host.starts_with('[') {
+            // IP literal
+            self.host.to_lowercase()
+        } else {
+            idna::domain_to_ascii(&self.host).unwrap_or_else(|_| self.host.to_lowercase())
+        };
+
+        Self {
+            host,
+            ..self.to_owned()
+        }
+    }
+
+    /// Convert the host to Unicode for display purposes.
+    ///
+    /// This method is the inverse of [`UrlAuthority::normalize`] for
+    /// internationalized domain names (e.g. `xn--bcher-kva.example` becomes
+    /// `bücher.example`).
+    pub(crate) fn to_unicode(&self) -> Self {
+        let (host, result) = idna::domain_to_unicode(&self.host);
+
+        Self {
+            host: if result.is_ok() {
+                host
+            } else {
+                self.host.to_owned()
+            },
+            ..self.to_owned()
+        }
+    }
+}
+
+impl From<&str> for UrlAuthority {
+    fn from(value: &str) -> Self {
+        let (userinfo, value) = value
+            .rsplit_once('@')
+            .map_or((None, value), |(userinfo, value)| {
+                (Some(userinfo.to_owned()), value)
+            });
+
+        // The port is located after the last `:`, unless it is inside an IP literal
+        let (host, port) = value
+            .rsplit_once(':')
+            .filter(|(_, port)| !port.contains(']'))
+            .map_or((value, None), |(host, port)| (host, Some(port.to_owned())));
+
+        Self {
+            userinfo,
+            host: host.to_owned(),
+            port,
+        }
+    }
+}
+
+impl std::fmt::Display for UrlAuthority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(userinfo) = self.userinfo.as_ref() {
+            write!(f, "{userinfo}@")?;
+        }
+        write!(f, "{}", self.host)?;
+        if let Some(port) = self.port.as_ref() {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
+
+/// URL path.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct UrlPath(String);
+
+impl UrlPath {
+    /// Return the path as a string slice.
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Return the last segment of the path, if not empty.
+    ///
+    /// For example, the file name of `/blog/index.html` is `index.html`, and
+    /// `/blog/` has no file name.
+    pub(crate) fn file_name(&self) -> Option<&str> {
+        Some(
+            self.0
+                .rsplit_once('/')
+                .map_or(self.0.as_str(), |(_, file_name)| file_name),
+        )
+        .filter(|file_name| !file_name.is_empty())
+    }
+
+    /// Return the file name without its extension, if any.
+    ///
+    /// Similarly to [`std::path::Path::file_stem`], a file name starting with
+    /// `.` and containing no other `.` has no extension (e.g. the stem of
+    /// `/.htaccess` is `.htaccess`).
+    pub(crate) fn file_stem(&self) -> Option<&str> {
+        self.file_name()
+            .map(|file_name| Self::split_file_name(file_name).0)
+    }
+
+    /// Return the extension of the file name, if any.
+    ///
+    /// For example, the extension of `/style.scss` is `scss`.
+    pub(crate) fn extension(&self) -> Option<&str> {
+        self.file_name()
+            .and_then(|file_name| Self::split_file_name(file_name).1)
+    }
+
+    /// Return a copy of the path with the extension replaced.
+    ///
+    /// If `extension` is empty, the extension is removed. If the path has no
+    /// file name (e.g. `/blog/`), it is returned unchanged.
+    pub(crate) fn with_extension<S>(&self, extension: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        let extension = extension.as_ref();
+
+        let (Some(file_name), Some(stem)) = (self.file_name(), self.file_stem()) else {
+            return self.to_owned();
+        };
+
+        // The file name is always located at the end of the path
+        let dir = &self.0[..self.0.len() - file_name.len()];
+
+        if extension.is_empty() {
+            Self(format!("{dir}{stem}"))
+        } else {
+            Self(format!("{dir}{stem}.{extension}"))
+        }
+    }
+
+    /// Determine whether the path ends with the given segments.
+    ///
+    /// Similarly to [`std::path::Path::ends_with`], only whole segments match
+    /// (e.g. `/blog/index.html` ends with `index.html` and `blog/index.html`,
+    /// but not with `dex.html`).
+    pub(crate) fn ends_with<S>(&self, suffix: S) -> bool
+    where
+        S: AsRef<str>,
+    {
+        let suffix = suffix.as_ref();
+
+        if suffix.is_empty() {
+            return true;
+        }
+
+        self.0
+            .strip_suffix(suffix)
+            .map(|prefix| prefix.is_empty() || prefix.ends_with('/') && !suffix.starts_with('/'))
+            .unwrap_or(false)
+    }
+
+    /// Return a relative reference from `base` to this path.
+    ///
+    /// Both paths must be absolute. As in URL resolution, the last segment of
+    /// `base` is ignored, so a page located at `/blog/` (or `/blog/index.html`)
+    /// refers to `/style.css` as `../style.css`. When the path is the directory
+    /// of `base`, `./` is returned.
+    pub(crate) fn relative_to(&self, base: &UrlPath) -> Self {
+        debug_assert!(self.0.starts_with('/') && base.0.starts_with('/'));
+
+        let segments: Vec<_> = self.0.split('/').collect();
+        let (_, dirs) = segments.split_last().unwrap();
+
+        let base_segments: Vec<_> = base.0.split('/').collect();
+        let (_, base_dirs) = base_segments.split_last().unwrap();
+
+        let common = dirs
+            .iter()
+            .zip(base_dirs.iter())
+            .take_while(|(x, y)| x == y)
+            .count();
+
+        let mut path = "../".repeat(base_dirs.len() - common);
+        path.push_str(&segments[common..].join("/"));
+
+        if path.is_empty() {
+            return Self::from("./");
+        }
+
+        // A first segment containing `:` would be interpreted as a scheme
+        if path
+            .split('/')
+            .next()
+            .is_some_and(|segment| segment.contains(':'))
+        {
+            path.insert_str(0, "./");
+        }
+
+        Self(path)
+    }
+
+    /// Resolve a reference relative to this path.
+    ///
+    /// This path must be absolute. As in URL resolution, the last segment of
+    /// this path is replaced by `reference`, then `.` and `..` segments are
+    /// removed, so `../style.css` from `/blog/post` refers to `/style.css`. An
+    /// absolute `reference` is returned as is, without dot segments.
+    ///
+    /// This is the inverse of [`UrlPath::relative_to`].
+    pub(crate) fn join(&self, reference: &UrlPath) -> Self {
+        debug_assert!(self.0.starts_with('/'));
+
+        let merged = if reference.0.starts_with('/') {
+            reference.0.to_owned()
+        } else {
+            let (dir, _) = self.0.rsplit_once('/').unwrap();
+            format!("{}/{}", dir, reference.0)
+        };
+
+        let mut segments: Vec<&str> = Vec::new();
+        let mut last = "";
+
+        for segment in merged.split('/').skip(1) {
+            last = segment;
+            match segment {
+                "." => {},
+                ".." => {
+                    segments.pop();
+                },
+                segment => segments.push(segment),
+            }
+        }
+
+        let mut path = format!("/{}", segments.join("/"));
+
+        // Keep the trailing slash of directories
+        if matches!(last, "." | "..") && !path.ends_with('/') {
+            path.push('/');
+        }
+
+        Self(path)
+    }
+
+    /// Split a file name into stem and extension.
+    fn split_file_name(file_name: &str) -> (&str, Option<&str>) {
+        file_name
+            .rsplit_once('.')
+            .filter(|(stem, _)| !stem.is_empty())
+            .map_or((file_name, None), |(stem, extension)| {
+                (stem, Some(extension))
+            })
+    }
+}
+
+impl From<&str> for UrlPath {
+    fn from(value: &str) -> Self {
+        Self(value.to_owned())
+    }
+}
+
+impl From<String> for UrlPath {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl std::fmt::Display for UrlPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Normalize a URL string.
+///
+/// This function is a shortcut for [`Url::normalize`].
+pub(crate) fn normalize_url<S>(url: S) -> String
+where
+    S: AsRef<str>,
+{
+    Url::from(url.as_ref()).normalize().to_string()
+}
+
+/// Return the absolute URL of the root of the site (e.g.
+/// `https://example.com/blog`), to which URLs of pages are appended.
+///
+/// `base_url` is returned if it specifies a host, otherwise it is appended to
+/// `site_url`. Returns `None` if neither specifies a host.
+pub(crate) fn site_base_url(site_url: Option<&str>, base_url: &str) -> Option<String> {
+    if Url::from(base_url).authority.is_some() {
+        return Some(base_url.to_owned());
+    }
+
+    let url = format!("{}{}", site_url?.trim_end_matches('/'), base_url);
+
+    Url::from(url.as_str()).authority.is_some().then_some(url)
+}
+
+/// Resolve a reference (e.g. a link) relative to an absolute URL.
+pub(crate) fn resolve_url(base: &str, reference: &str) -> String {
+    let base = Url::from(base);
+    let reference = Url::from(reference.trim());
+
+    if reference.scheme.is_some() {
+        return reference.to_string();
+    }
+
+    let url = if reference.authority.is_some() {
+        Url {
+            scheme: base.scheme,
+            ..reference
+        }
+    } else if reference.path.as_str().is_empty() {
+        Url {
+            query: reference.query.or(base.query),
+            fragment: None,
+            ..base
+        }
+    } else {
+        // The path of a URL with an authority is absolute
+        let base_path = match base.path.as_str() {
+            "" => UrlPath::from("/"),
+            _ => base.path.to_owned(),
+        };
+        Url {
+            path: base_path.join(&reference.path),
+            query: reference.query,
+            fragment: None,
+            ..base
+        }
+    };
+
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_display() {
+        const CASES: [&str; 8] = [
+            "",
+            "/",
+            "/blog/index.html",
+            "../style.css?v=1#top",
+            "https://example.com",
+            "https://user@example.com:8080/blog?page=2#title",
+            "http://[::1]:8000/",
+            "mailto:john@example.com",
+        ];
+
+        for input in CASES {
+            let result = super::Url::from(input).to_string();
+            assert_eq!(
+                result, input,
+                "\nUrl::from({input:?}) expected {input:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_valid() {
+        const CASES: [&str; 7] = [
+            "",
+            "/blog",
+            "/blog/caf%C3%A9?page=2#title",
+            "https://example.com:8080/",
+            "https://bücher.example/",
+            "mailto:john@example.com",
+            "./page.md",
+        ];
+
+        for input in CASES {
+            let result = super::Url::parse(input);
+            assert!(
+                result.is_ok(),
+                "\nUrl::parse({input:?}) expected Ok but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        use super::UrlParseError;
+
+        let cases = [
+            ("/my page", UrlParseError::IllegalCharacter {
+                character: ' ',
+                position: 3,
+            }),
+            ("/a<b>", UrlParseError::IllegalCharacter {
+                character: '<',
+                position: 2,
+            }),
+            ("/caf%C", UrlParseError::InvalidPercentEncoding {
+                position: 4,
+            }),
+            ("1http://example.com", UrlParseError::InvalidScheme {
+                position: 0,
+            }),
+            ("ht_tp://example.com", UrlParseError::InvalidScheme {
+                position: 2,
+            }),
+            ("http://example.com:80a/", UrlParseError::InvalidPort {
+                port: "80a".to_owned(),
+                position: 19,
+            }),
+            ("http://bü.example:99999", UrlParseError::InvalidPort {
+                port: "99999".to_owned(),
+                position: 18,
+            }),
+        ];
+
+        for (input, expected) in cases {
+            let result = super::Url::parse(input);
+            assert_eq!(
+                result,
+                Err(expected.to_owned()),
+                "\nUrl::parse({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn url_path_extension() {
+        const CASES: [(&str, Option<&str>, Option<&str>); 8] = [
+            ("", None, None),
+            ("/", None, None),
+            ("/blog/", None, None),
+            ("/blog", Some("blog"), None),
+            ("/style.scss", Some("style"), Some("scss")),
+            ("/archive.tar.gz", Some("archive.tar"), Some("gz")),
+            ("/.htaccess", Some(".htaccess"), None),
+            ("page.md", Some("page"), Some("md")),
+        ];
+
+        for (input, stem, extension) in CASES {
+            let path = super::UrlPath::from(input);
+            assert_eq!(
+                path.file_stem(),
+                stem,
+                "\nfile_stem({input:?}) expected {stem:?} but received {:?}",
+                path.file_stem()
+            );
+            assert_eq!(
+                path.extension(),
+                extension,
+                "\nextension({input:?}) expected {extension:?} but received {:?}",
+                path.extension()
+            );
+        }
+    }
+
+    #[test]
+    fn url_path_with_extension() {
+        const CASES: [(&str, &str, &str); 7] = [
+            ("/style.scss", "css", "/style.css"),
+            ("/script.ts", "js", "/script.js"),
+            ("/blog/index.md", "", "/blog/index"),
+            ("/blog", "html", "/blog.html"),
+            ("/blog/", "html", "/blog/"),
+            ("/.htaccess", "txt", "/.htaccess.txt"),
+            ("/archive.tar.gz", "zip", "/archive.tar.zip"),
+        ];
+
+        for (input, extension, expected) in CASES {
+            let result = super::UrlPath::from(input).with_extension(extension);
+            assert_eq!(
+                result.as_str(),
+                expected,
+                "\nwith_extension({input:?}, {extension:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn url_path_ends_with() {
+        const CASES: [(&str, &str, bool); 8] = [
+            ("/blog/index.html", "index.html", true),
+            ("/blog/index.html", "blog/index.html", true),
+            ("/blog/index.html", "/blog/index.html", true),
+            ("/blog/index.html", "dex.html", false),
+            ("/blog/index.html", "", true),
+            ("/index", "index", true),
+            ("/blog", "/log", false),
+            ("/a/blog", "/blog", false),
+        ];
+
+        for (input, suffix, expected) in CASES {
+            let result = super::UrlPath::from(input).ends_with(suffix);
+            assert_eq!(
+                result, expected,
+                "\nends_with({input:?}, {suffix:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn url_path_relative_to() {
+        const CASES: [(&str, &str, &str); 10] = [
+            ("/style.css", "/", "style.css"),
+            ("/style.css", "/blog/", "../style.css"),
+            ("/style.css", "/blog/index.html", "../style.css"),
+            ("/blog/post", "/blog/", "post"),
+            ("/blog/", "/blog/", "./"),
+            ("/", "/blog/", "../"),
+            ("/blog", "/blog/", "../blog"),
+            ("/img/logo.png", "/blog/2024/post/", "../../../img/logo.png"),
+            ("/blog/2024/post/", "/blog/2023/", "../2024/post/"),
+            ("/a:b", "/", "./a:b"),
+        ];
+
+        for (input, base, expected) in CASES {
+            let result = super::UrlPath::from(input).relative_to(&super::UrlPath::from(base));
+            assert_eq!(
+                result.as_str(),
+                expected,
+                "\nrelative_to({input:?}, {base:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn url_path_join() {
+        const CASES: [(&str, &str, &str); 8] = [
+            ("/", "style.css", "/style.css"),
+            ("/blog/", "../style.css", "/style.css"),
+            ("/blog/post", "../style.css", "/style.css"),
+            ("/blog/post/", "./img.png", "/blog/post/img.png"),
+            ("/blog/", "./", "/blog/"),
+            ("/blog/2024/post/", "../../", "/blog/"),
+            ("/blog/", "/a/../b", "/b"),
+            ("/", "../../a", "/a"),
+        ];
+
+        for (base, input, expected) in CASES {
+            let result = super::UrlPath::from(base).join(&super::UrlPath::from(input));
+            assert_eq!(
+                result.as_str(),
+                expected,
+                "\njoin({base:?}, {input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_url() {
+        const CASES: [(&str, &str); 7] = [
+            ("/blog", "/blog"),
+            ("HTTPS://Example.COM/Blog", "https://example.com/Blog"),
+            ("https://bücher.example/", "https://xn--bcher-kva.example/"),
+            (
+                "https://user@BÜCHER.example:8080/?q#f",
+                "https://user@xn--bcher-kva.example:8080/?q#f",
+            ),
+            ("https://例え.テスト/", "https://xn--r8jz45g.xn--zckzah/"),
+            ("http://[::1]:8000/", "http://[::1]:8000/"),
+            ("http://[FE80::1]/", "http://[fe80::1]/"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::normalize_url(input);
+            assert_eq!(
+                result, expected,
+                "\nnormalize_url({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn to_unicode() {
+        const CASES: [(&str, &str); 2] = [
+            ("https://xn--bcher-kva.example/", "https://bücher.example/"),
+            ("https://example.com/", "https://example.com/"),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::Url::from(input).to_unicode().to_string();
+            assert_eq!(
+                result, expected,
+                "\nto_unicode({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn site_base_url() {
+        const CASES: [(Option<&str>, &str, Option<&str>); 5] = [
+            (Some("https://example.com"), "", Some("https://example.com")),
+            (
+                Some("https://example.com/"),
+                "/blog",
+                Some("https://example.com/blog"),
+            ),
+            (
+                Some("https://example.com"),
+                "https://example.org/blog",
+                Some("https://example.org/blog"),
+            ),
+            (None, "https://example.org", Some("https://example.org")),
+            (None, "/blog", None),
+        ];
+
+        for (site_url, base_url, expected) in CASES {
+            let result = super::site_base_url(site_url, base_url);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\nsite_base_url({site_url:?}, {base_url:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_url() {
+        const CASES: [(&str, &str, &str); 6] = [
+            (
+                "https://example.com",
+                "webmention",
+                "https://example.com/webmention",
+            ),
+            (
+                "https://example.com/blog/post",
+                "https://webmention.io/example.com/webmention",
+                "https://webmention.io/example.com/webmention",
+            ),
+            (
+                "https://example.com/blog/post",
+                "/webmention",
+                "https://example.com/webmention",
+            ),
+            (
+                "https://example.com/blog/post",
+                "webmention?v=1",
+                "https://example.com/blog/webmention?v=1",
+            ),
+            (
+                "https://example.com/blog/post?id=1",
+                "",
+                "https://example.com/blog/post?id=1",
+            ),
+            (
+                "https://example.com/blog/post",
+                "//webmention.example.com/",
+                "https://webmention.example.com/",
+            ),
+        ];
+
+        for (base, reference, expected) in CASES {
+            let result = super::resolve_url(base, reference);
+            assert_eq!(
+                result, expected,
+                "\nresolve_url({base:?}, {reference:?}) expected {expected:?} but received \
+                 {result:?}"
+            );
+        }
+    }
+}
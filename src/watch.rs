@@ -1,10 +1,12 @@
 //! Watch for file changes.
 
 use std::{
-    path::Path,
+    future::Future,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
+use globset::{Glob, GlobSetBuilder};
 use notify_debouncer_full::{
     new_debouncer,
     notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher},
@@ -13,18 +15,36 @@ use notify_debouncer_full::{
 
 use crate::{config::Config, error::Error};
 
+/// Maximum number of pending debounced events.
+///
+/// When a rebuild takes longer than the debounce delay, the watcher thread
+/// blocks instead of buffering an unbounded number of events.
+const EVENT_CHANNEL_CAPACITY: usize = 16;
+
 /// Watch for file changes.
 ///
 /// Call a given function when a file has been created, modified or deleted in
-/// input, data, or layout directory.
-pub(super) async fn watch<F>(config: &Config, callback: F) -> Result<(), Error>
+/// input, data, layout, includes or theme directory, or when one of `files`
+/// has been modified (e.g. files imported in pages). The function receives the
+/// paths of the changed files. Changes to the output directory and to the
+/// outputs of `run_before_build` commands are ignored.
+///
+/// Watching stops when `shutdown` completes.
+pub(super) async fn watch<S, F>(
+    config: &Config,
+    files: &[PathBuf],
+    shutdown: S,
+    mut callback: F,
+) -> Result<(), Error>
 where
-    F: Fn() -> Result<(), Error>,
+    S: Future<Output = ()>,
+    F: FnMut(&[PathBuf]) -> Result<(), Error>,
 {
-    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(EVENT_CHANNEL_CAPACITY);
 
     let event_handler = move |result| {
-        if let Err(error) = sender.send(result) {
+        // Called from the debouncer thread, outside of the async runtime
+        if let Err(error) = sender.blocking_send(result) {
             tracing::error!("Error: {:?}", error);
         }
     };
@@ -36,21 +56,56 @@ where
             }
         })?;
 
-    add_watch_path(&mut debouncer, &config.input_dir)?;
+    let dirs: Vec<&PathBuf> = std::iter::once(&config.input_dir)
+        .chain(config.extra_input_dirs.iter())
+        .chain(config.data_dir.iter())
+        .chain(config.layouts_dir.iter())
+        .chain(config.includes_dir.iter())
+        .chain(config.theme.iter())
+        .collect();
 
-    if let Some(data_dir) = config.data_dir.as_ref() {
-        add_watch_path(&mut debouncer, data_dir)?;
+    for dir in dirs.iter() {
+        add_watch_path(&mut debouncer, dir, RecursiveMode::Recursive)?;
     }
 
-    if let Some(layouts_dir) = config.layouts_dir.as_ref() {
-        add_watch_path(&mut debouncer, layouts_dir)?;
+    // Files located in watched directories are already watched
+    for file in files
+        .iter()
+        .filter(|file| !dirs.iter().any(|dir| file.starts_with(dir)))
+    {
+        add_watch_path(&mut debouncer, file, RecursiveMode::NonRecursive)?;
     }
 
+    // Files written by commands run before builds, relative to the input
+    // directory, which must not trigger rebuilds
+    let command_outputs = config
+        .run_before_build
+        .iter()
+        .flat_map(|before_build| before_build.outputs.iter())
+        .try_fold(GlobSetBuilder::new(), |mut builder, pattern| {
+            builder.add(Glob::new(pattern)?);
+            Ok(builder)
+        })
+        .and_then(|builder| builder.build())
+        .map_err(|error| Error::Watch {
+            source: error.into(),
+        })?;
+
     tracing::info!("Watching for file changes");
 
     let mut last_callback_time = Instant::now();
 
-    while let Some(result) = receiver.recv().await {
+    tokio::pin!(shutdown);
+
+    loop {
+        let result = tokio::select! {
+            result = receiver.recv() => match result {
+                Some(result) => result,
+                None => break,
+            },
+            _ = &mut shutdown => break,
+        };
+
         match result {
             Ok(events) => {
                 let events: Vec<_> = events
@@ -76,6 +131,12 @@ where
                             })
                             .unwrap_or(true)
                     })
+                    .filter(|event| {
+                        !event.paths.iter().all(|path| {
+                            path.strip_prefix(&config.input_dir)
+                                .is_ok_and(|path| command_outputs.is_match(path))
+                        })
+                    })
                     .collect();
 
                 if events.is_empty() {
@@ -85,17 +146,24 @@ where
                 let mut paths: Vec<_> = events
                     .iter()
                     .flat_map(|event| &event.paths)
-                    .filter_map(|path| path.to_str())
+                    .cloned()
                     .collect();
 
                 paths.sort();
                 paths.dedup();
 
-                tracing::info!("Files changed: {}", paths.join(", "));
+                tracing::info!(
+                    "Files changed: {}",
+                    paths
+                        .iter()
+                        .filter_map(|path| path.to_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
 
                 last_callback_time = Instant::now();
 
-                if let Some(error) = (callback)().err() {
+                if let Some(error) = (callback)(&paths).err() {
                     tracing::error!("{:?}", error);
                 }
             },
@@ -103,12 +171,19 @@ where
         }
     }
 
+    // Unblock the watcher thread if it is sending events, and wait for it
+    drop(receiver);
+    debouncer.stop();
+
+    tracing::info!("Stopped watching for file changes");
+
     Ok(())
 }
 
 fn add_watch_path<P>(
     debouncer: &mut Debouncer<RecommendedWatcher, FileIdMap>,
     path: P,
+    recursive_mode: RecursiveMode,
 ) -> Result<(), Error>
 where
     P: AsRef<Path>,
@@ -119,12 +194,12 @@ where
 
     debouncer
         .watcher()
-        .watch(path, RecursiveMode::Recursive)
+        .watch(path, recursive_mode)
         .map_err(|error| Error::Watch {
             source: error.into(),
         })?;
 
-    debouncer.cache().add_root(path, RecursiveMode::Recursive);
+    debouncer.cache().add_root(path, recursive_mode);
 
     Ok(())
 }
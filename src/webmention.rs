@@ -0,0 +1,339 @@
+//! Send webmentions for outbound links.
+//!
+//! The [Webmention](https://www.w3.org/TR/webmention/) protocol notifies a
+//! site when a page links to it. Requests are made with the `curl` command.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    error::Error,
+    site::Site,
+    util::{
+        http,
+        url::{resolve_url, site_base_url, Url},
+    },
+};
+
+/// Links already mentioned, keyed by source URL.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+struct State(BTreeMap<String, BTreeSet<String>>);
+
+impl State {
+    /// Read the state from a file.
+    ///
+    /// If the file does not exist, no webmention was sent yet, and an empty
+    /// state is returned.
+    fn read(path: &Path) -> anyhow::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Write the state to a file.
+    fn write(&self, path: &Path) -> anyhow::Result<()> {
+        Ok(std::fs::write(path, serde_json::to_string_pretty(self)?)?)
+    }
+}
+
+/// Send webmentions for the links added to or removed from the pages of a
+/// built site.
+///
+/// Links are compared with those of the previous run, read from
+/// `webmention.state_file`. Removed links are mentioned again, so that the
+/// target can update or delete the mention. Links of which the target has no
+/// webmention endpoint are considered as mentioned. Failed webmentions are
+/// reported, and sent again on the next run.
+///
+/// If `dry_run` is `true`, links are displayed but no request is made.
+pub(super) fn send(config: &Config, site: &Site, dry_run: bool) -> Result<(), Error> {
+    let Some(webmention_config) = config.webmention.as_ref() else {
+        return Err(Error::Webmention {
+            source: anyhow::anyhow!("No webmention configuration specified"),
+        });
+    };
+
+    let url_prefix = site_base_url(config.site_url.as_deref(), &config.base_url);
+
+    let Some((url_prefix, site_host)) = url_prefix.and_then(|url_prefix| {
+        let site_host = host(&url_prefix)?;
+        Some((url_prefix, site_host))
+    }) else {
+        return Err(Error::Webmention {
+            source: anyhow::anyhow!("site_url or base_url must specify the domain of the site"),
+        });
+    };
+
+    let state_file = &webmention_config.state_file;
+
+    let previous = State::read(state_file).map_err(|error| Error::Webmention {
+        source: error.context(format!("While reading state file {state_file:?}")),
+    })?;
+
+    let current = State(
+        site.pages()
+            .map(|page| {
+                let source = format!("{}{}", url_prefix, page.url);
+                (source, outbound_links(&page.content, &site_host))
+            })
+            .filter(|(_, links)| !links.is_empty())
+            .collect(),
+    );
+
+    // Links that are added or removed, with the source of the mention
+    let mut mentions: Vec<(&str, &str)> = Vec::new();
+
+    for (source, links) in current.0.iter() {
+        let previous_links = previous.0.get(source);
+        for target in links {
+            if previous_links.map_or(true, |previous_links| !previous_links.contains(target)) {
+                mentions.push((source.as_str(), target.as_str()));
+            }
+        }
+    }
+
+    for (source, links) in previous.0.iter() {
+        let current_links = current.0.get(source);
+        for target in links {
+            if current_links.map_or(true, |current_links| !current_links.contains(target)) {
+                mentions.push((source.as_str(), target.as_str()));
+            }
+        }
+    }
+
+    tracing::info!("Sending {} webmentions", mentions.len());
+
+    if dry_run {
+        for (source, target) in mentions {
+            println!("{source} -> {target}");
+        }
+        return Ok(());
+    }
+
+    // Failed mentions are kept out of the state, to be sent again
+    let mut state = current.clone();
+    let mut num_failures = 0;
+
+    for (source, target) in mentions {
+        let result = endpoint(target).and_then(|endpoint| {
+            let Some(endpoint) = endpoint else {
+                tracing::debug!("No webmention endpoint for {:?}", target);
+                return Ok(());
+            };
+            tracing::info!("Sending webmention {:?} -> {:?}", source, target);
+            http::post_form(&endpoint, &[("source", source), ("target", target)])
+        });
+
+        if let Err(error) = result {
+            tracing::warn!(
+                "Could not send webmention {:?} -> {:?}: {}",
+                source,
+                target,
+                error
+            );
+
+            num_failures += 1;
+
+            let previous_links = previous.0.get(source);
+            let links = state.0.entry(source.to_owned()).or_default();
+            if previous_links.is_some_and(|previous_links| previous_links.contains(target)) {
+                links.insert(target.to_owned());
+            } else {
+                links.remove(target);
+            }
+        }
+    }
+
+    state.0.retain(|_, links| !links.is_empty());
+
+    state.write(state_file).map_err(|error| Error::Webmention {
+        source: error.context(format!("While writing state file {state_file:?}")),
+    })?;
+
+    if num_failures > 0 {
+        tracing::warn!(
+            "{} webmentions will be sent again on the next run",
+            num_failures
+        );
+    }
+
+    Ok(())
+}
+
+/// Return the links of a HTML page to other sites.
+///
+/// Only absolute HTTP(S) links are returned, without their fragment.
+fn outbound_links(content: &str, site_host: &str) -> BTreeSet<String> {
+    let links = RefCell::new(BTreeSet::new());
+
+    let _ = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("a[href]", |element| {
+            let href = element.get_attribute("href").unwrap_or_default();
+            let href = href.split_once('#').map_or(href.as_str(), |(href, _)| href);
+
+            if host(href).is_some_and(|host| host != site_host) {
+                links.borrow_mut().insert(href.to_owned());
+            }
+
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    });
+
+    links.into_inner()
+}
+
+/// Discover the webmention endpoint of a target URL.
+///
+/// The endpoint is given by a `Link` header, or else by the first `<link>` or
+/// `<a>` element with the `webmention` link type. Returns `None` if the
+/// target has no endpoint.
+fn endpoint(target: &str) -> anyhow::Result<Option<String>> {
+    let (headers, content) = http::get_with_headers(target)?;
+
+    let href = header_endpoint(&headers).or_else(|| html_endpoint(&content));
+
+    Ok(href.map(|href| resolve_url(target, &href)))
+}
+
+/// Return the webmention endpoint given by the `Link` headers of a response.
+fn header_endpoint(headers: &str) -> Option<String> {
+    headers
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("link"))
+        .flat_map(|(_, value)| value.split(','))
+        .find_map(|link| {
+            let (href, params) = link.trim().strip_prefix('<')?.split_once('>')?;
+
+            params
+                .split(';')
+                .filter_map(|param| param.split_once('='))
+                .any(|(name, value)| {
+                    name.trim().eq_ignore_ascii_case("rel")
+                        && value
+                            .trim()
+                            .trim_matches('"')
+                            .split_whitespace()
+                            .any(|rel| rel.eq_ignore_ascii_case("webmention"))
+                })
+                .then(|| href.to_owned())
+        })
+}
+
+/// Return the webmention endpoint given by the elements of a HTML page.
+fn html_endpoint(content: &str) -> Option<String> {
+    let href = RefCell::new(None);
+
+    let _ = lol_html::rewrite_str(content, lol_html::RewriteStrSettings {
+        element_content_handlers: vec![lol_html::element!("link[href], a[href]", |element| {
+            let is_webmention = element.get_attribute("rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("webmention"))
+            });
+
+            if is_webmention && href.borrow().is_none() {
+                *href.borrow_mut() = element.get_attribute("href");
+            }
+
+            Ok(())
+        })],
+        ..lol_html::RewriteStrSettings::default()
+    });
+
+    href.into_inner()
+}
+
+/// Return the normalized host of an absolute HTTP(S) URL.
+fn host(url: &str) -> Option<String> {
+    let url = Url::from(url.trim()).normalize();
+
+    if !url
+        .scheme
+        .as_ref()
+        .is_some_and(|scheme| matches!(scheme.as_str(), "http" | "https"))
+    {
+        return None;
+    }
+
+    url.authority
+        .map(|authority| authority.host)
+        .filter(|host| !host.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn outbound_links() {
+        let content = concat!(
+            "<a href=\"https://other.com/post#comments\">Post</a>",
+            "<a href=\"https://example.com/about\">About</a>",
+            "<a href=\"/blog\">Blog</a>",
+            "<a href=\"mailto:someone@other.com\">Email</a>",
+        );
+
+        let result = super::outbound_links(content, "example.com");
+
+        assert_eq!(result.into_iter().collect::<Vec<_>>(), [
+            "https://other.com/post"
+        ]);
+    }
+
+    #[test]
+    fn header_endpoint() {
+        const CASES: [(&str, Option<&str>); 4] = [
+            (
+                "HTTP/1.1 200 OK\r\nLink: <https://example.com/webmention>; rel=\"webmention\"",
+                Some("https://example.com/webmention"),
+            ),
+            (
+                "link: </style.css>; rel=preload, </webmention>; rel=\"other webmention\"",
+                Some("/webmention"),
+            ),
+            ("Link: <https://example.com/feed>; rel=\"alternate\"", None),
+            ("Content-Type: text/html", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::header_endpoint(input);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\nheader_endpoint({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn html_endpoint() {
+        const CASES: [(&str, Option<&str>); 4] = [
+            (
+                "<link rel=\"webmention\" href=\"/webmention\">",
+                Some("/webmention"),
+            ),
+            (
+                "<a rel=\"me\" href=\"/me\"></a><a rel=\"webmention\" href=\"/a\"></a>",
+                Some("/a"),
+            ),
+            ("<link rel=\"webmention\" href=\"\">", Some("")),
+            ("<link rel=\"stylesheet\" href=\"/style.css\">", None),
+        ];
+
+        for (input, expected) in CASES {
+            let result = super::html_endpoint(input);
+            assert_eq!(
+                result.as_deref(),
+                expected,
+                "\nhtml_endpoint({input:?}) expected {expected:?} but received {result:?}"
+            );
+        }
+    }
+}
@@ -78,6 +78,26 @@ const myVar: string = "abc";
     Ok(())
 }
 
+#[test]
+fn tsx() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("app.tsx")
+        .write_str(r#"const app: JSX.Element = <p class="a">Hi</p>;"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/app.js")
+        .assert(predicate::path::is_file())
+        .assert(predicate::str::contains("JSX.Element").not())
+        .assert(predicate::str::contains("React.createElement(\"p\""));
+
+    Ok(())
+}
+
 #[test]
 fn javascript() -> Result<(), Box<dyn std::error::Error>> {
     let dir = assert_fs::TempDir::new()?;
@@ -260,3 +280,2899 @@ layout: page.tera
 
     Ok(())
 }
+
+#[test]
+fn fail_invalid_base_url() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.md").write_str("Home")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("--base-url").arg("/my blog");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("base_url"))
+        .stderr(predicate::str::contains("position 3"));
+
+    Ok(())
+}
+
+#[test]
+fn absolute_base_url() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.md").write_str("Home")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("--base-url")
+        .arg("https://example.com/blog");
+
+    cmd.assert().success();
+
+    dir.child("_site/blog/index.html")
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn fail_invalid_front_matter_url() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.md").write_str(
+        r#"---
+url: /custom url
+---
+Custom
+"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("position 7"));
+
+    Ok(())
+}
+
+#[test]
+fn relative_urls() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "relative_urls": true, "minify": false }"#)?;
+    dir.child("style.css").write_str("body { margin: 0; }")?;
+    dir.child("index.md")
+        .write_str("[About](./blog/about.md)")?;
+    dir.child("blog/about.md")
+        .write_str("<link rel=\"stylesheet\" href=\"/style.css\">\n\n[Home](../index.md)")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("href=\"blog/about/index.html\""));
+
+    dir.child("_site/blog/about/index.html")
+        .assert(predicate::str::contains("href=\"../../style.css\""))
+        .assert(predicate::str::contains("href=\"../../index.html\""));
+
+    Ok(())
+}
+
+#[test]
+fn layout_format_date() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.md").write_str(
+        r#"---
+layout: page.tera
+date: 2024-05-01 22:30
+---
+Home
+"#,
+    )?;
+
+    dir.child("_layouts/page.tera").write_str(
+        r#"<time>{{ date | format_date(format="%d/%m/%Y %H:%M", offset="+02:00") }}</time>"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("02/05/2024 00:30"));
+
+    Ok(())
+}
+
+#[test]
+fn git_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{ "git_metadata": true, "sitemap": { "url_prefix": "https://example.com" } }"#,
+    )?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\nHome")?;
+    dir.child("_layouts/page.tera")
+        .write_str(r#"<p>{{ date }}|{{ updated }}|{{ contributors | join(sep=",") }}</p>"#)?;
+    dir.child("café.md").write_str("Café")?;
+
+    let git = |args: &[&str], author: &str, date: &str| {
+        Command::new("git")
+            .current_dir(&dir)
+            .args(args)
+            .env("GIT_AUTHOR_NAME", author)
+            .env("GIT_AUTHOR_EMAIL", "author@example.com")
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_NAME", author)
+            .env("GIT_COMMITTER_EMAIL", "author@example.com")
+            .env("GIT_COMMITTER_DATE", date)
+            .output()
+    };
+
+    git(&["init", "--quiet"], "", "")?;
+    git(&["add", "."], "", "")?;
+    git(
+        &["commit", "--quiet", "-m", "First"],
+        "Alice",
+        "2024-01-01T10:00:00+00:00",
+    )?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\nHome page")?;
+    git(
+        &["commit", "--quiet", "-am", "Second"],
+        "Bob",
+        "2024-02-01T10:00:00+00:00",
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(
+            "2024-01-01T10:00:00+00:00|2024-02-01T10:00:00+00:00|Alice,Bob",
+        ));
+
+    // Dates of files with non-ASCII names are read too
+    dir.child("_site/sitemap.xml")
+        .assert(predicate::str::contains("2024-02-01T10:00:00+00:00"))
+        .assert(predicate::str::contains("2024-01-01T10:00:00+00:00"));
+
+    Ok(())
+}
+
+#[test]
+fn timezone() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{ "timezone": "-05:00", "sitemap": { "url_prefix": "https://example.com" } }"#,
+    )?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\ndate: 2024-05-01\n---\nHome")?;
+    dir.child("_layouts/page.tera")
+        .write_str(r#"<p>{{ date | format_date(format="%Y-%m-%d %:z") }}</p>"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("<p>2024-05-01 -05:00</p>"));
+
+    dir.child("_site/sitemap.xml")
+        .assert(predicate::str::contains("2024-05-01T00:00:00-05:00"));
+
+    Ok(())
+}
+
+#[test]
+fn reproducible() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "sitemap": { "url_prefix": "https://example.com" } }"#)?;
+    dir.child("index.md").write_str("Home")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("--reproducible")
+        .env("SOURCE_DATE_EPOCH", "86400");
+
+    cmd.assert().success();
+
+    dir.child("_site/sitemap.xml")
+        .assert(predicate::str::contains("1970-01-02T00:00:00+00:00"));
+
+    Ok(())
+}
+
+#[test]
+fn analytics() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "analytics": { "provider": "plausible", "site_id": "example.com" }
+        }"#,
+    )?;
+    dir.child("index.html")
+        .write_str("<html><head><title>Home</title></head><body></body></html>")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(r#"data-domain="example.com""#));
+
+    Ok(())
+}
+
+#[test]
+fn import_map() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "base_url": "/blog",
+            "import_map": { "imports": { "lit": "/vendor/lit.js" } }
+        }"#,
+    )?;
+    dir.child("index.html").write_str(
+        r#"<html><head><script type="module" src="/app.js"></script></head><body></body></html>"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(
+            r#"<head><script type="importmap">{"imports":{"lit":"/blog/vendor/lit.js"}}</script>"#,
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn preload() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "preload": { "image": "prefetch" }
+        }"#,
+    )?;
+    dir.child("index.html").write_str(
+        r#"<head><link rel="stylesheet" href="/style.css"></head><img src="hero.jpg">"#,
+    )?;
+    dir.child("style.css")
+        .write_str("@font-face { src: url(fonts/a.woff2); }")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(
+            r#"<link rel="preload" href="/style.css" as="style">"#,
+        ))
+        .assert(predicate::str::contains(
+            r#"<link rel="prefetch" href="hero.jpg" as="image">"#,
+        ))
+        .assert(predicate::str::contains(
+            r#"<link rel="preload" href="/fonts/a.woff2" as="font" type="font/woff2" crossorigin>"#,
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn slug_strategy() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "slug_strategy": "unicode", "minify": false }"#)?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\n# Ĉu vi parolas?")?;
+    dir.child("_layouts/page.tera")
+        .write_str(r#"<p>{{ "Déjà vu" | slugify }}</p>{{ content | safe }}"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("<p>déjà-vu</p>"))
+        .assert(predicate::str::contains("id=\"ĉu-vi-parolas\""));
+
+    Ok(())
+}
+
+#[test]
+fn fail_invalid_slug_strategy() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "slug_strategy": "unknown" }"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("slug_strategy"));
+
+    Ok(())
+}
+
+#[test]
+fn hard_link_assets() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "hard_link_assets": true }"#)?;
+    dir.child("video.bin").write_str("Video")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    // Build twice to overwrite existing links
+    cmd.assert().success();
+    cmd.assert().success();
+
+    dir.child("_site/video.bin").assert("Video");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        assert_eq!(
+            dir.child("video.bin").metadata()?.ino(),
+            dir.child("_site/video.bin").metadata()?.ino()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn hard_link_assets_keep_input() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    const SVG: &str = "<svg xmlns=\"http://www.w3.org/2000/svg\">  <!-- Icon -->  </svg>";
+
+    dir.child("icon.svg").write_str(SVG)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    // The first build links the file, the second one writes minified content
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "hard_link_assets": true, "minify": false }"#)?;
+    cmd.assert().success();
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "hard_link_assets": true, "minify": true }"#)?;
+    cmd.assert().success();
+
+    dir.child("icon.svg").assert(SVG);
+    dir.child("_site/icon.svg")
+        .assert(predicate::str::contains("Icon").not());
+
+    Ok(())
+}
+
+#[test]
+fn syntax_highlight_stylesheet() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "syntax_highlight": {
+                "stylesheets": [{
+                    "theme": "InspiredGitHub",
+                    "dark_theme": "base16-ocean.dark",
+                    "url": "/highlight.css",
+                    "link": true
+                }]
+            }
+        }"#,
+    )?;
+    dir.child("index.html")
+        .write_str("<html><head><title>Home</title></head><body></body></html>")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/highlight.css")
+        .assert(predicate::str::contains(
+            "@media (prefers-color-scheme: dark)",
+        ))
+        .assert(predicate::str::contains(
+            r#":root[data-theme="dark"] .code {"#,
+        ));
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(
+            "<link rel=\"stylesheet\" href=\"/highlight.css\"></head>",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn color_scheme() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r##"{
+            "minify": false,
+            "color_scheme": {
+                "light": { "bg": "#fff" },
+                "dark": { "bg": "#000" },
+                "syntax_theme": "InspiredGitHub",
+                "syntax_dark_theme": "base16-ocean.dark",
+                "link": true
+            }
+        }"##,
+    )?;
+    dir.child("index.html")
+        .write_str("<html><head><title>Home</title></head><body></body></html>")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/color-scheme.css")
+        .assert(predicate::str::contains("--bg: #fff;"))
+        .assert(predicate::str::contains(r#":root[data-theme="dark"] {"#))
+        .assert(predicate::str::contains(
+            r#":root[data-theme="dark"] .code {"#,
+        ));
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(
+            "<link rel=\"stylesheet\" href=\"/color-scheme.css\"></head>",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn fail_invalid_color_scheme_attribute() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "color_scheme": { "attribute": "data-theme]" } }"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("color_scheme.attribute"));
+
+    Ok(())
+}
+
+#[test]
+fn syntax_highlight_custom_syntaxes_and_themes() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "syntax_highlight": {
+                "syntaxes_dir": "_syntaxes",
+                "themes_dir": "_themes",
+                "stylesheets": [{ "theme": "Toy", "url": "/toy.css" }]
+            }
+        }"#,
+    )?;
+    dir.child("_syntaxes/toy.sublime-syntax").write_str(
+        r#"%YAML 1.2
+---
+name: Toy
+file_extensions: [toy]
+scope: source.toy
+contexts:
+  main:
+    - match: '\bfn\b'
+      scope: keyword.toy
+"#,
+    )?;
+    dir.child("_themes/Toy.tmTheme").write_str(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+  <key>name</key>
+  <string>Toy</string>
+  <key>settings</key>
+  <array>
+    <dict>
+      <key>settings</key>
+      <dict>
+        <key>background</key>
+        <string>#123456</string>
+      </dict>
+    </dict>
+  </array>
+</dict>
+</plist>
+"#,
+    )?;
+    dir.child("index.md").write_str("```toy\nfn main\n```")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(
+            "<span class=\"keyword toy\">fn</span>",
+        ));
+
+    dir.child("_site/toy.css")
+        .assert(predicate::str::contains("#123456"));
+
+    dir.child("_site/_syntaxes")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn syntax_highlight_copy_button() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false, "syntax_highlight": { "copy_button": true } }"#)?;
+    dir.child("index.md")
+        .write_str("```rust,title=\"src/main.rs\"\nfn main() {}\n```")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(
+            "<figcaption class=\"code-title\">src/main.rs</figcaption>",
+        ))
+        .assert(predicate::str::contains(
+            "<button type=\"button\" class=\"code-copy\" aria-label=\"Copy code\">Copy</button>",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn syntax_highlight_formatter_title() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.lua").write_str(
+        r#"
+        return {
+            minify = false,
+            syntax_highlight = {
+                formatter = function(code, attributes)
+                    return "<pre class=\"custom\">" .. code .. "</pre>"
+                end,
+            },
+        }
+        "#,
+    )?;
+    dir.child("index.md")
+        .write_str("```rust,title=\"src/main.rs\"\nfn main() {}\n```")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(concat!(
+            "<figure class=\"code-block\"><figcaption \
+             class=\"code-title\">src/main.rs</figcaption>",
+            "<pre class=\"custom\">fn main() {}\n</pre></figure>",
+        )));
+
+    Ok(())
+}
+
+#[test]
+fn images_attributes() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "images": { "dimensions": true, "lazy_loading": true, "async_decoding": true }
+        }"#,
+    )?;
+    // PNG signature and header of a 3x2 image
+    const PNG: [u8; 29] = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x06, 0x00, 0x00, 0x00,
+    ];
+    dir.child("image.png").write_binary(&PNG)?;
+    dir.child("my image.png").write_binary(&PNG)?;
+    dir.child("blog/index.md").write_str(concat!(
+        "![Image](../image.png)\n\n<img src=\"/image.png\" width=\"10\">\n\n",
+        "<img src=\"/my%20image.png\">",
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/blog/index.html")
+        .assert(predicate::str::contains(
+            "width=\"3\" height=\"2\" loading=\"lazy\" decoding=\"async\"",
+        ))
+        .assert(predicate::str::contains("width=\"10\" loading=\"lazy\""))
+        .assert(predicate::str::contains(
+            "<img src=\"/my%20image.png\" width=\"3\" height=\"2\"",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn images_localize_remote() -> Result<(), Box<dyn std::error::Error>> {
+    const FILE_NAME: &str = "e4ef6dac74be94de68812e8c729d0a32f5a6c3efb83e3e905f1979c2bf2fc80b.png";
+
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{ "minify": false, "images": { "dimensions": true, "localize_remote": true } }"#,
+    )?;
+    // Image previously downloaded from `https://example.com/photo.png`
+    dir.child(format!(".vitrine-cache/images/{FILE_NAME}"))
+        .write_binary(&[
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x06, 0x00, 0x00,
+            0x00,
+        ])?;
+    dir.child("index.md")
+        .write_str("![Photo](https://example.com/photo.png)")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(format!(
+            "src=\"/images/remote/{FILE_NAME}\" alt=\"Photo\" width=\"3\" height=\"2\""
+        )));
+
+    dir.child(format!("_site/images/remote/{FILE_NAME}"))
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn rewrite_srcset_and_meta_urls() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "base_url": "/site", "minify": false }"#)?;
+    dir.child("small.png").write_str("")?;
+    dir.child("large.png").write_str("")?;
+    dir.child("index.html").write_str(concat!(
+        r#"<html><head><meta property="og:image" content="./large.png"></head><body>"#,
+        r#"<picture><source srcset="./small.png 1x, ./large.png 2x">"#,
+        r#"<img src="./small.png" srcset="./large.png 800w"></picture></body></html>"#,
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/site/index.html")
+        .assert(predicate::str::contains(
+            r#"<meta property="og:image" content="/site/large.png">"#,
+        ))
+        .assert(predicate::str::contains(
+            r#"<source srcset="/site/small.png 1x, /site/large.png 2x">"#,
+        ))
+        .assert(predicate::str::contains(
+            r#"<img src="/site/small.png" srcset="/site/large.png 800w">"#,
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn rewrite_css_urls() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "relative_urls": true, "minify": false }"#)?;
+    dir.child("images/bg.png").write_str("")?;
+    dir.child("blog/post.html").write_str(concat!(
+        r#"<style>body { background: url("../images/bg.png"); }</style>"#,
+        r#"<div style="background-image: url(/images/bg.png)"></div>"#,
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/blog/post/index.html")
+        .assert(predicate::str::contains(
+            r#"body { background: url("../../images/bg.png"); }"#,
+        ))
+        .assert(predicate::str::contains(
+            r#"style="background-image: url(../../images/bg.png)""#,
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn subresource_integrity() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "subresource_integrity": true, "minify": false }"#)?;
+    dir.child("script.js")
+        .write_str("alert('Hello, world.');")?;
+    dir.child("blog/index.html").write_str(
+        r#"<script src="../script.js"></script><script src="https://example.com/a.js"></script>"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/blog/index.html")
+        .assert(predicate::str::contains(concat!(
+            r#"<script src="/script.js" integrity="sha384-"#,
+            r#"H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO" "#,
+            r#"crossorigin="anonymous"></script>"#,
+        )))
+        .assert(predicate::str::contains(
+            r#"<script src="https://example.com/a.js"></script>"#,
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn content_security_policy() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "content_security_policy": {
+                "policy": "default-src 'self'; img-src *",
+                "headers_url": "/_headers"
+            }
+        }"#,
+    )?;
+    dir.child("index.html").write_str(concat!(
+        r#"<html><head><title>Home</title><script>alert('Hello, world.');</script></head>"#,
+        r#"<body></body></html>"#,
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    const POLICY: &str = "default-src 'self'; img-src *; script-src 'self' \
+                          'sha256-qznLcsROx4GACP2dm0UCKCzCG+HiZ1guq6ZZDob/Tng='";
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(format!(
+            "<head><meta http-equiv=\"Content-Security-Policy\" content=\"{POLICY}\">"
+        )));
+
+    dir.child("_site/_headers")
+        .assert(format!("/\n  Content-Security-Policy: {POLICY}\n"));
+
+    Ok(())
+}
+
+#[test]
+fn fail_content_security_policy_hosting_headers() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "content_security_policy": {
+                "policy": "default-src 'self'",
+                "headers_url": "/_headers"
+            },
+            "hosting": { "providers": ["cloudflare"] }
+        }"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "content_security_policy.headers_url",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn external_links() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "base_url": "https://example.com",
+            "minify": false,
+            "external_links": { "target_blank": true, "class": "external" }
+        }"#,
+    )?;
+    dir.child("index.html").write_str(concat!(
+        r#"<a href="https://other.org/" rel="nofollow">Other</a>"#,
+        r#"<a href="https://example.com/about">About</a>"#,
+        r#"<a href="/blog">Blog</a>"#,
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html").assert(concat!(
+        r#"<a href="https://other.org/" rel="nofollow noopener noreferrer" "#,
+        r#"target="_blank" class="external">Other</a>"#,
+        r#"<a href="https://example.com/about">About</a>"#,
+        r#"<a href="/blog">Blog</a>"#,
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn sanitize() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false, "sanitize": {} }"#)?;
+    dir.child("index.md").write_str(
+        "Hello <script>alert(1)</script><img src=\"x.png\" onerror=\"alert(2)\">\n\n<a \
+         href=\"javascript:alert(3)\">Link</a>",
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("alert").not())
+        .assert(predicate::str::contains("<img src=\"x.png\">"))
+        .assert(predicate::str::contains("<a>Link</a>"));
+
+    Ok(())
+}
+
+#[test]
+fn precompress() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "precompress": { "brotli": false, "min_size": 100 } }"#)?;
+    dir.child("index.html")
+        .write_str(&"<p>Hello, world!</p>".repeat(10))?;
+    dir.child("small.css").write_str("p{color:red}")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html.gz")
+        .assert(predicate::path::exists());
+    dir.child("_site/index.html.br")
+        .assert(predicate::path::missing());
+    dir.child("_site/small.css.gz")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn minify_svg() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "svg": { "precision": 1 } }"#)?;
+    dir.child("icon.svg").write_str(concat!(
+        "<?xml version=\"1.0\"?>\n",
+        "<!-- Comment -->\n",
+        "<svg xmlns=\"http://www.w3.org/2000/svg\">\n",
+        "  <path d=\"M 0.123 1.456\" />\n",
+        "</svg>\n",
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/icon.svg")
+        .assert("<svg xmlns=\"http://www.w3.org/2000/svg\"><path d=\"M .1 1.5\"/></svg>");
+
+    Ok(())
+}
+
+#[test]
+fn check_a11y() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.html")
+        .write_str(r#"<html lang="en"><h1>Title</h1><h2>Section</h2></html>"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("check").arg("--a11y");
+
+    cmd.assert().success();
+
+    dir.child("blog/index.html")
+        .write_str(r#"<html><h1>Title</h1><h3>Section</h3><img src="a.png"></html>"#)?;
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("img-alt"))
+        .stdout(predicate::str::contains("heading-order"))
+        .stdout(predicate::str::contains("html-lang"));
+
+    dir.child("_site").assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn check_fragments() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.html").write_str(concat!(
+        r##"<h1 id="title">Title</h1>"##,
+        r##"<a href="#title">Top</a><a href="/blog/#intro">Blog</a>"##,
+    ))?;
+    dir.child("blog/index.html")
+        .write_str(r#"<h2 id="intro">Intro</h2>"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("check").arg("--fragments");
+
+    cmd.assert().success();
+
+    dir.child("blog/index.html")
+        .write_str(r#"<h2 id="introduction">Introduction</h2>"#)?;
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("broken-fragment"))
+        .stdout(predicate::str::contains("/blog/#intro"));
+
+    Ok(())
+}
+
+#[test]
+fn check_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{ "diagnostics": { "img-alt": "warn", "html-lang": "allow", "V0025": "deny" } }"#,
+    )?;
+    dir.child("index.html")
+        .write_str(r#"<html><h1>Title</h1><img src="a.png"></html>"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("check").arg("--a11y");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("[V0021 img-alt]"))
+        .stdout(predicate::str::contains("html-lang").not());
+
+    dir.child("index.html")
+        .write_str(r#"<html><h1>Title</h1><h3>Section</h3></html>"#)?;
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("[V0025 heading-order]"));
+
+    Ok(())
+}
+
+#[test]
+fn check_report() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.html")
+        .write_str(r#"<html lang="en"><img src="a.png"></html>"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .args(["check", "--format", "sarif", "--output", "report.sarif"]);
+
+    cmd.assert().failure();
+
+    dir.child("report.sarif")
+        .assert(predicate::str::contains(r#""version": "2.1.0""#))
+        .assert(predicate::str::contains(r#""ruleId": "V0021""#))
+        .assert(predicate::str::contains(r#""uri": "index.html""#));
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).args(["check", "--format", "json"]);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains(r#""rule": "img-alt""#))
+        .stdout(predicate::str::contains(r#""input_path": "index.html""#));
+
+    Ok(())
+}
+
+#[test]
+fn github_annotations() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.html")
+        .write_str(r#"<html lang="en"><img src="a.png"></html>"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .args(["--message-format", "github", "check", "--a11y"]);
+
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "::error file=index.html,title=V0021 img-alt::/: Image \"a.png\" has no alt attribute",
+    ));
+
+    dir.child("post.md").write_str("---\ntitle: [\n---\nPost")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).args(["--message-format", "github"]);
+
+    cmd.assert().failure().stdout(predicate::str::contains(
+        "::error file=post.md,line=2,title=",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn fail_check_diagnostics_unknown_rule() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "diagnostics": { "V9999": "allow" } }"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("check");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown rule \"V9999\""));
+
+    Ok(())
+}
+
+#[test]
+fn fail_check_diagnostics_unknown_level() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "diagnostics": { "img-alt": "error" } }"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("check");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown level \"error\""));
+
+    Ok(())
+}
+
+#[test]
+fn fail_deploy_without_config() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.html").write_str("<p>Hello</p>")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("deploy").arg("--dry-run");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("No deploy configuration"));
+
+    Ok(())
+}
+
+#[test]
+fn fail_serve_auth_env() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.html").write_str("<p>Hello</p>")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("--serve")
+        .env("VITRINE_AUTH", "user");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("user:password"));
+
+    Ok(())
+}
+
+#[test]
+fn hosting() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "hosting": {
+                "providers": ["cloudflare", "netlify", "vercel"],
+                "redirects": [{ "from": "/old/", "to": "/new/" }],
+                "headers": [{ "url": "/*", "values": { "X-Frame-Options": "DENY" } }]
+            }
+        }"#,
+    )?;
+    dir.child("index.html").write_str("<p>Hello</p>")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/_redirects").assert("/old/ /new/ 301\n");
+    dir.child("_site/_headers")
+        .assert("/*\n  X-Frame-Options: DENY\n");
+    dir.child("_site/netlify.toml")
+        .assert(predicate::str::contains("[[redirects]]"));
+    dir.child("_site/vercel.json")
+        .assert(predicate::str::contains("\"statusCode\": 301"));
+
+    Ok(())
+}
+
+#[test]
+fn hosting_base_url() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "base_url": "/blog",
+            "hosting": {
+                "providers": ["cloudflare"],
+                "redirects": [{ "from": "/old/", "to": "/new/" }]
+            }
+        }"#,
+    )?;
+    dir.child("index.html").write_str("<p>Hello</p>")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/blog/index.html")
+        .assert(predicate::path::exists());
+    dir.child("_site/_redirects")
+        .assert("/blog/old/ /blog/new/ 301\n");
+    dir.child("_site/blog/_redirects")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn archive() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let archive_dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.html").write_str("<p>Home</p>")?;
+    dir.child("blog/index.html").write_str("<p>Blog</p>")?;
+
+    let archive_path = archive_dir.child("site.tar.gz");
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("--archive")
+        .arg(archive_path.path());
+
+    cmd.assert().success();
+
+    let first = std::fs::read(archive_path.path())?;
+
+    cmd.assert().success();
+
+    let second = std::fs::read(archive_path.path())?;
+
+    // Archives do not depend on file modification times
+    assert_eq!(first, second);
+
+    Ok(())
+}
+
+#[test]
+fn deploy_diff_from_state_file() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let state_dir = assert_fs::TempDir::new()?;
+
+    let state_file = state_dir.child("state.json");
+    let diff_file = state_dir.child("diff.json");
+
+    dir.child("vitrine.config.json").write_str(&format!(
+        r#"{{
+            "deploy": {{
+                "target": "s3",
+                "destination": "s3://bucket",
+                "state_file": {:?}
+            }}
+        }}"#,
+        state_file.path()
+    ))?;
+    dir.child("index.html").write_str("<p>Home</p>")?;
+
+    // The previous deployment contained a different home page and a removed page
+    state_file.write_str(r#"{ "index.html": "0", "old/index.html": "0" }"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("deploy")
+        .arg("--dry-run")
+        .arg("--diff")
+        .arg(diff_file.path());
+
+    cmd.assert().success();
+
+    diff_file.assert(predicate::str::contains("\"index.html\""));
+    diff_file.assert(predicate::str::contains("\"old/index.html\""));
+
+    // Dry runs do not update the state file
+    state_file.assert(predicate::str::contains("old/index.html"));
+
+    Ok(())
+}
+
+#[test]
+fn fail_front_matter_schema() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "front_matter_schemas": [{
+                "section": "blog",
+                "fields": {
+                    "title": { "required": true, "type": "string" },
+                    "status": { "values": ["draft", "published"] }
+                }
+            }]
+        }"#,
+    )?;
+    dir.child("index.md").write_str("# Home")?;
+    dir.child("blog/post.md")
+        .write_str("---\nstatus: archived\n---\n# Post")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("field \"status\" must be one of"))
+        .stdout(predicate::str::contains("field \"title\" is required"));
+
+    Ok(())
+}
+
+#[test]
+fn front_matter_defaults() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.lua").write_str(
+        r#"
+        return {
+            front_matter_defaults = function(page)
+                return { section = page.path:match("^(%w+)/"), title = "Default" }
+            end,
+        }
+        "#,
+    )?;
+    dir.child("_layouts/page.tera")
+        .write_str("<p>{{ section }} {{ title }}</p>")?;
+    dir.child("blog/post.md")
+        .write_str("---\nlayout: page.tera\ntitle: Post\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/blog/post/index.html")
+        .assert(predicate::str::contains("<p>blog Post</p>"));
+
+    Ok(())
+}
+
+#[test]
+fn generate_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.lua").write_str(
+        r##"
+        return {
+            global_data = { products = { "chair", "table" } },
+            generate_pages = function(data)
+                local pages = {}
+                for _, name in ipairs(data.products) do
+                    table.insert(pages, {
+                        path = "products/" .. name .. ".md",
+                        content = "# " .. name,
+                        data = { layout = "product.tera", title = name },
+                    })
+                end
+                return pages
+            end,
+        }
+        "##,
+    )?;
+    dir.child("_layouts/product.tera")
+        .write_str("<title>{{ title }}</title>{{ content | safe }}")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/products/chair/index.html")
+        .assert(predicate::str::contains("<title>chair</title>"))
+        .assert(predicate::str::contains("chair</h1>"));
+    dir.child("_site/products/table/index.html")
+        .assert(predicate::str::contains("<title>table</title>"));
+
+    Ok(())
+}
+
+#[test]
+fn collections() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "collections": [{
+                "name": "blog",
+                "directory": "blog",
+                "layout": "post.tera",
+                "sort_by": "-date",
+                "url_prefix": "/articles",
+                "paginate": 2,
+                "feed": {
+                    "url": "/articles/feed.xml",
+                    "title": "Articles",
+                    "author": [],
+                    "category": [],
+                    "contributor": []
+                }
+            }]
+        }"#,
+    )?;
+    dir.child("_layouts/post.tera").write_str(concat!(
+        "<h1>{{ title }}</h1>",
+        "<p>{% if previous %}{{ previous.title }}{% endif %}|",
+        "{% if next %}{{ next.title }}{% endif %}</p>",
+    ))?;
+    dir.child("_layouts/list.tera").write_str(concat!(
+        "{% for item in pagination.items %}<a href=\"{{ item.url }}\"></a>{% endfor %}",
+        "<p>{{ pagination.page }}/{{ pagination.num_pages }}</p>",
+    ))?;
+    dir.child("blog/index.md")
+        .write_str("---\nlayout: list.tera\n---\n")?;
+    dir.child("blog/first.md")
+        .write_str("---\ntitle: First\ndate: 2024-01-01\n---\n")?;
+    dir.child("blog/second.md")
+        .write_str("---\ntitle: Second\ndate: 2024-02-01\n---\n")?;
+    dir.child("blog/third.md")
+        .write_str("---\ntitle: Third\ndate: 2024-03-01\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/articles/first/index.html")
+        .assert(predicate::str::contains("<h1>First</h1>"));
+    dir.child("_site/articles/second/index.html")
+        .assert(predicate::str::contains("<p>Third|First</p>"));
+    dir.child("_site/articles/index.html")
+        .assert(predicate::str::contains(
+            "<a href=\"/articles/third\"></a><a href=\"/articles/second\"></a><p>1/2</p>",
+        ));
+    dir.child("_site/articles/page/2/index.html")
+        .assert(predicate::str::contains(
+            "<a href=\"/articles/first\"></a><p>2/2</p>",
+        ));
+    dir.child("_site/articles/feed.xml")
+        .assert(predicate::str::contains("/articles/second"))
+        .assert(predicate::str::contains("/articles\"").not());
+
+    Ok(())
+}
+
+#[test]
+fn site_url() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "feeds": [{
+                "url": "/feed.xml",
+                "title": "Feed",
+                "author": [],
+                "category": [],
+                "contributor": []
+            }],
+            "sitemap": {}
+        }"#,
+    )?;
+    dir.child("post.html").write_str(concat!(
+        "<html><head><meta property=\"og:image\" content=\"/image.png\"></head>",
+        "<body></body></html>"
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .args(["--base-url", "/site"])
+        .args(["--site-url", "https://example.com"]);
+
+    cmd.assert().success();
+
+    dir.child("_site/site/feed.xml")
+        .assert(predicate::str::contains(
+            "href=\"https://example.com/site/post\"",
+        ));
+    dir.child("_site/site/sitemap.xml")
+        .assert(predicate::str::contains(
+            "<loc>https://example.com/site/post</loc>",
+        ));
+    dir.child("_site/site/post/index.html")
+        .assert(predicate::str::contains(
+            "content=\"https://example.com/image.png\"",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn fail_invalid_site_url() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .args(["--site-url", "https://example.com/blog"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("site_url"));
+
+    Ok(())
+}
+
+#[test]
+fn breadcrumbs() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false }"#)?;
+    dir.child("_layouts/page.tera").write_str(concat!(
+        "{% for ancestor in page.ancestors %}",
+        "<a href=\"{{ ancestor.url }}\">{{ ancestor.title }}</a>",
+        "{% endfor %}",
+    ))?;
+    dir.child("index.md")
+        .write_str("---\ntitle: Home\nlayout: page.tera\n---\n")?;
+    dir.child("docs/guide/index.md")
+        .write_str("---\ntitle: Guide\nlayout: page.tera\n---\n")?;
+    dir.child("docs/guide/install.md")
+        .write_str("---\ntitle: Install\nlayout: page.tera\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html").assert("");
+    dir.child("_site/docs/guide/index.html")
+        .assert("<a href=\"/\">Home</a>");
+    dir.child("_site/docs/guide/install/index.html")
+        .assert("<a href=\"/\">Home</a><a href=\"/docs/guide\">Guide</a>");
+
+    Ok(())
+}
+
+#[test]
+fn site_navigation() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false, "navigation": {} }"#)?;
+    dir.child("_layouts/page.tera").write_str(concat!(
+        "{% for item in site.nav %}",
+        "<a{% if item.active %} class=\"active\"{% endif %}>{{ item.title }}</a>",
+        "{% for child in item.children %}",
+        "<a{% if child.current %} class=\"current\"{% endif %}>{{ child.title }}</a>",
+        "{% endfor %}",
+        "{% endfor %}",
+    ))?;
+    dir.child("docs/index.md")
+        .write_str("---\ntitle: Docs\nweight: 1\nlayout: page.tera\n---\n")?;
+    dir.child("docs/usage.md")
+        .write_str("---\ntitle: Usage\nweight: 2\nlayout: page.tera\n---\n")?;
+    dir.child("docs/install.md")
+        .write_str("---\ntitle: Install\nweight: 1\nlayout: page.tera\n---\n")?;
+    dir.child("about.md")
+        .write_str("---\ntitle: About\nlayout: page.tera\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/docs/usage/index.html").assert(concat!(
+        "<a class=\"active\">Docs</a><a>Install</a><a class=\"current\">Usage</a>",
+        "<a>About</a>",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn unused_assets() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false, "unused_assets": { "skip": true } }"#)?;
+    dir.child("index.html")
+        .write_str("<link rel=\"stylesheet\" href=\"style.css\"><img src=\"used.png\">")?;
+    dir.child("style.css")
+        .write_str("body { background: url(\"background.png\"); }")?;
+    dir.child("used.png").write_str("")?;
+    dir.child("background.png").write_str("")?;
+    dir.child("unused.png").write_str("")?;
+    dir.child("robots.txt").write_str("")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Unused asset"))
+        .stdout(predicate::str::contains("unused.png"));
+
+    dir.child("_site/used.png")
+        .assert(predicate::path::exists());
+    dir.child("_site/background.png")
+        .assert(predicate::path::exists());
+    dir.child("_site/robots.txt")
+        .assert(predicate::path::exists());
+    dir.child("_site/unused.png")
+        .assert(predicate::path::exists().not());
+
+    Ok(())
+}
+
+#[test]
+fn build_report() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false }"#)?;
+    dir.child("index.html")
+        .write_str("<link rel=\"stylesheet\" href=\"/style.css\"><p>Home</p>")?;
+    dir.child("about.html").write_str("<p>About</p>")?;
+    dir.child("style.css").write_str("body { margin: 0; }")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("build").arg("--report");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(concat!(
+            "Page     HTML   CSS   JS  Other  Total\n",
+            "/        52 B  19 B  0 B    0 B   71 B\n",
+            "/about/  12 B   0 B  0 B    0 B   12 B\n",
+        )));
+
+    dir.child("_site/index.html")
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn bench() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.md").write_str("# Home")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("bench")
+        .arg("--runs")
+        .arg("2")
+        .arg("--save-baseline");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("cold builds (2 runs)"))
+        .stdout(predicate::str::contains("warm builds (2 runs)"))
+        .stdout(predicate::str::contains("markdown"));
+
+    dir.child(".vitrine-bench.json")
+        .assert(predicate::str::contains("\"total\""));
+    dir.child("_site").assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn list() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "taxonomies": ["tags"] }"#)?;
+    dir.child("blog/post.md")
+        .write_str("---\ntitle: Post\ntags: [rust]\n---\n# Post")?;
+    dir.child("style.css").write_str("body {}")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("list").arg("pages");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("/blog/post/\tblog/post.md\tPost"));
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("list")
+        .arg("assets")
+        .arg("--json");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(r#""url": "/style.css""#))
+        .stdout(predicate::str::contains("/blog/post/").not());
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("list").arg("taxonomies");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("tags\trust\t1"));
+
+    dir.child("_site").assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn ignore_files() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child(".gitignore")
+        .write_str("node_modules/\n*.swp\n!keep.swp\n")?;
+    dir.child(".vitrineignore").write_str("/drafts\n")?;
+    dir.child("index.md").write_str("# Home")?;
+    dir.child("node_modules/lib/index.js").write_str("")?;
+    dir.child("index.md.swp").write_str("")?;
+    dir.child("keep.swp").write_str("")?;
+    dir.child("drafts/post.md").write_str("# Draft")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::path::exists());
+    dir.child("_site/keep.swp")
+        .assert(predicate::path::exists());
+    dir.child("_site/node_modules")
+        .assert(predicate::path::missing());
+    dir.child("_site/index.md.swp")
+        .assert(predicate::path::missing());
+    dir.child("_site/drafts").assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("shared/about.md").write_str("# About")?;
+    dir.child("site/index.md").write_str("# Home")?;
+    std::os::unix::fs::symlink(dir.child("shared").path(), dir.child("site/shared").path())?;
+    // Cycle
+    std::os::unix::fs::symlink(dir.child("site").path(), dir.child("site/loop").path())?;
+
+    let build = |symlinks: &str| -> Result<_, Box<dyn std::error::Error>> {
+        dir.child("site/vitrine.config.json")
+            .write_str(&format!(r#"{{ "symlinks": "{symlinks}" }}"#))?;
+        let _ = std::fs::remove_dir_all(dir.child("site/_site").path());
+        let mut cmd = Command::cargo_bin("vitrine")?;
+        cmd.current_dir(dir.child("site").path());
+        Ok(cmd.assert())
+    };
+
+    build("skip")?.success();
+    dir.child("site/_site/shared")
+        .assert(predicate::path::missing());
+
+    build("follow")?.success();
+    dir.child("site/_site/shared/about/index.html")
+        .assert(predicate::path::exists());
+
+    build("error")?.failure();
+
+    Ok(())
+}
+
+#[test]
+fn diff() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("old/index.html")
+        .write_str("<p>Hello</p><p>World</p>")?;
+    dir.child("old/removed.txt").write_str("")?;
+    dir.child("new/index.html")
+        .write_str("<p>Hello</p><p>Vitrine</p>")?;
+    dir.child("new/added.txt").write_str("")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("diff").arg("old").arg("new");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("A added.txt"))
+        .stdout(predicate::str::contains("M index.html"))
+        .stdout(predicate::str::contains("D removed.txt"))
+        .stdout(predicate::str::contains("-World\n+Vitrine"));
+
+    Ok(())
+}
+
+#[test]
+fn extra_input_dirs() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "input_dir": "content",
+            "extra_input_dirs": ["shared"],
+            "minify": false
+        }"#,
+    )?;
+    dir.child("content/index.md")
+        .write_str("[About](about.md)")?;
+    dir.child("shared/index.md").write_str("Shared home")?;
+    dir.child("shared/about.md").write_str("# About")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(r#"href="/about""#))
+        .assert(predicate::str::contains("Shared home").not());
+    dir.child("_site/about/index.html")
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn copy_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "input_dir": "site",
+            "copy_paths": [
+                { "from": "static/robots.txt", "to": "/" },
+                { "from": "static/fonts", "to": "/assets/fonts/" },
+                { "from": "vendor/**/*.json", "to": "/vendor/", "minify": true }
+            ]
+        }"#,
+    )?;
+    dir.child("site/index.md").write_str("# Home")?;
+    dir.child("static/robots.txt").write_str("User-agent: *")?;
+    dir.child("static/fonts/sans/regular.woff2").write_str("")?;
+    dir.child("vendor/lib/data.json")
+        .write_str("{ \"a\": 1 }")?;
+    dir.child("vendor/lib/index.js").write_str("")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/robots.txt").assert("User-agent: *");
+    dir.child("_site/assets/fonts/sans/regular.woff2")
+        .assert(predicate::path::exists());
+    dir.child("_site/vendor/lib/data.json").assert(r#"{"a":1}"#);
+    dir.child("_site/vendor/lib/index.js")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+// Files differing only by case cannot be created on case-insensitive file
+// systems
+#[cfg(target_os = "linux")]
+#[test]
+fn url_collisions() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("Blog/post.md").write_str("# Post")?;
+    dir.child("blog/post.md").write_str("# Post")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("/Blog/post"))
+        .stderr(predicate::str::contains("/blog/post"));
+
+    Ok(())
+}
+
+#[test]
+fn markdown_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false }"#)?;
+    dir.child("index.md").write_str("Hello")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("<p>Hello</p>"));
+
+    // Replace the cached result, to check that the next build uses it
+    let cache_files: Vec<_> =
+        std::fs::read_dir(dir.child(".vitrine-cache/markdown"))?.collect::<Result<_, _>>()?;
+
+    assert_eq!(cache_files.len(), 1);
+
+    std::fs::write(cache_files[0].path(), "<p>Cached</p>\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("<p>Cached</p>"));
+
+    Ok(())
+}
+
+#[test]
+fn layouts_memoize() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.lua").write_str(
+        r#"
+        local num_calls = 0
+        local function count(value)
+            num_calls = num_calls + 1
+            return value .. num_calls
+        end
+        return {
+            layouts = {
+                filters = { count = count, pure_count = count },
+                memoize = { "pure_count" },
+            },
+        }
+        "#,
+    )?;
+    dir.child("_layouts/page.tera").write_str(concat!(
+        "{{ \"a\" | pure_count }} {{ \"a\" | pure_count }} {{ \"b\" | pure_count }} ",
+        "{{ \"a\" | count }} {{ \"a\" | count }}",
+    ))?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("a1 a1 b2 a3 a4"));
+
+    Ok(())
+}
+
+#[test]
+fn layouts_builtins() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("_layouts/page.tera").write_str(concat!(
+        "{{ summary | markdown(inline=true) }}\n",
+        "{{ tags | jsonify }}\n",
+        "{% for post in posts | where(attribute=\"tags\", value=\"rust\") %}",
+        "{{ post.title }} ",
+        "{% endfor %}",
+    ))?;
+    dir.child("index.md").write_str(concat!(
+        "---\n",
+        "layout: page.tera\n",
+        "summary: Hello *world*\n",
+        "tags: [a, b]\n",
+        "posts:\n",
+        "  - { title: A, tags: [rust] }\n",
+        "  - { title: B, tags: [web] }\n",
+        "  - { title: C, tags: [rust, web] }\n",
+        "---\n",
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html").assert(concat!(
+        "Hello <em>world</em>\n",
+        "[\"a\",\"b\"]\n",
+        "A C ",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn layouts_url_for() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("_layouts/page.tera").write_str(concat!(
+        "<a href=\"{{ url_for(path=\"posts/hello.md\") }}\"></a>",
+        "<link rel=\"stylesheet\" href=\"{{ asset_url(path=\"/style.css\") }}\">",
+    ))?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\n")?;
+    dir.child("posts/hello.md").write_str("# Hello")?;
+    dir.child("style.css").write_str("body { color: red; }")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("--base-url").arg("/blog");
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html").assert(concat!(
+        "<a href=\"/blog/posts/hello/\"></a>",
+        "<link rel=\"stylesheet\" href=\"/blog/style.css\">",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn layouts_image() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false }"#)?;
+    dir.child("_layouts/page.tera")
+        .write_str("{{ image(path=\"photo.png\", alt=\"Photo\") }}\n{{ content }}")?;
+    // PNG signature and header of a 3x2 image
+    dir.child("photo.png").write_binary(&[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x06, 0x00, 0x00, 0x00,
+    ])?;
+    dir.child("photo.avif").write_str("")?;
+    dir.child("index.md").write_str(concat!(
+        "---\nlayout: page.tera\n---\n",
+        "{{< image \"photo.png\" alt=\"Photo\" caption=\"A photo\" >}}\n",
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains(concat!(
+            "<picture><source type=\"image/avif\" srcset=\"/photo.avif\">",
+            "<img src=\"/photo.png\" alt=\"Photo\" width=\"3\" height=\"2\" loading=\"lazy\" ",
+            "decoding=\"async\"></picture>\n",
+        )))
+        .assert(predicate::str::contains(
+            "</picture><figcaption>A photo</figcaption></figure>",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn layouts_documents() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "sitemap": { "url_prefix": "https://example.com", "documents": true }
+        }"#,
+    )?;
+    dir.child("_layouts/page.tera").write_str(concat!(
+        "{% for file in site.files %}",
+        "{{ file.url }} {{ file.format }} {{ file.size }} {{ file.title }} {{ file.pages }}",
+        "{% endfor %}",
+    ))?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\n")?;
+    dir.child("files/report.pdf").write_str(concat!(
+        "%PDF-1.4\n",
+        "1 0 obj << /Type /Page >> endobj\n",
+        "2 0 obj << /Title (Annual report) >> endobj\n",
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert("/files/report.pdf pdf 86 Annual report 1");
+
+    dir.child("_site/sitemap.xml")
+        .assert(predicate::str::contains(
+            "<loc>https://example.com/files/report.pdf</loc>",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn blogroll() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "blogroll": { "layout": "blogroll.tera" }
+        }"#,
+    )?;
+    dir.child("_data/blogroll.json").write_str(
+        r#"[
+            {
+                "feed_url": "https://example.com/feed.xml",
+                "title": "Example & co",
+                "site_url": "https://example.com"
+            }
+        ]"#,
+    )?;
+    dir.child("_layouts/blogroll.tera")
+        .write_str("<h1>{{ title }}</h1>{{ content | safe }}{{ feeds | length }}")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/blogroll.opml")
+        .assert(predicate::str::contains("<title>Blogroll</title>"))
+        .assert(predicate::str::contains(
+            r#"xmlUrl="https://example.com/feed.xml""#,
+        ))
+        .assert(predicate::str::contains(r#"text="Example &amp; co""#));
+
+    dir.child("_site/blogroll/index.html")
+        .assert(predicate::str::contains("<h1>Blogroll</h1>"))
+        .assert(predicate::str::contains(
+            r#"<a href="https://example.com">Example &amp; co</a>"#,
+        ))
+        .assert(predicate::str::contains("</ul>\n1"));
+
+    Ok(())
+}
+
+#[test]
+fn layouts_events() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "site_url": "https://example.com",
+            "events": { "title": "Meetups" }
+        }"#,
+    )?;
+    dir.child("_layouts/page.tera").write_str(concat!(
+        "{% for event in site.upcoming_events %}",
+        "{{ event.title }} {{ event.start }} {{ event.all_day }}",
+        "{% endfor %}",
+    ))?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\n")?;
+    dir.child("events/workshop.md").write_str(concat!(
+        "---\n",
+        "title: Workshop\n",
+        "event:\n",
+        "  start: 2024-04-01 18:00\n",
+        "  end: 2024-04-01 20:00\n",
+        "---\n",
+    ))?;
+    dir.child("events/conference.md").write_str(concat!(
+        "---\n",
+        "title: Conference\n",
+        "event:\n",
+        "  start: 2024-06-01\n",
+        "  end: 2024-06-02\n",
+        "  location: Paris, France\n",
+        "---\n",
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("--reproducible")
+        .env("SOURCE_DATE_EPOCH", "1714521600");
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert("Conference 2024-06-01T00:00:00+00:00 true");
+
+    dir.child("_site/events.ics")
+        .assert(predicate::str::starts_with("BEGIN:VCALENDAR\r\n"))
+        .assert(predicate::str::contains("X-WR-CALNAME:Meetups\r\n"))
+        .assert(predicate::str::contains("DTSTART:20240401T180000Z\r\n"))
+        .assert(predicate::str::contains("DTSTART;VALUE=DATE:20240601\r\n"))
+        .assert(predicate::str::contains("DTEND;VALUE=DATE:20240603\r\n"))
+        .assert(predicate::str::contains("LOCATION:Paris\\, France\r\n"))
+        .assert(predicate::str::contains("DTSTAMP:20240501T000000Z\r\n"));
+
+    Ok(())
+}
+
+#[test]
+fn api() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "api": { "paginate": 2 },
+            "collections": [{ "name": "blog", "directory": "blog" }],
+            "taxonomies": ["tags"]
+        }"#,
+    )?;
+    dir.child("index.md").write_str("Home")?;
+    dir.child("blog/first.md")
+        .write_str("---\ntitle: First\ntags: [news]\n---\nHello")?;
+    dir.child("blog/second.md")
+        .write_str("---\ntitle: Second\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/api/index.json")
+        .assert(predicate::str::contains(r#""pages":"/api/pages/1.json""#))
+        .assert(predicate::str::contains(
+            r#""blog":"/api/collections/blog/1.json""#,
+        ))
+        .assert(predicate::str::contains(
+            r#""tags":"/api/taxonomies/tags.json""#,
+        ));
+
+    dir.child("_site/api/pages/1.json")
+        .assert(predicate::str::contains(r#""num_pages":2"#))
+        .assert(predicate::str::contains(
+            r#""next_url":"/api/pages/2.json""#,
+        ))
+        .assert(predicate::str::contains(
+            r#""api_url":"/api/content/blog/first/index.json""#,
+        ));
+    dir.child("_site/api/pages/2.json")
+        .assert(predicate::str::contains(
+            r#""api_url":"/api/content/blog/second/index.json""#,
+        ));
+
+    dir.child("_site/api/content/blog/first/index.json")
+        .assert(predicate::str::contains("<p>Hello</p>"))
+        .assert(predicate::str::contains(r#""title":"First""#));
+
+    dir.child("_site/api/collections/blog/1.json")
+        .assert(predicate::str::contains(r#""url":"/blog/second""#));
+
+    dir.child("_site/api/taxonomies/tags.json")
+        .assert(predicate::str::contains(r#""term":"news""#));
+    dir.child("_site/api/taxonomies/tags/news/1.json")
+        .assert(predicate::str::contains(r#""url":"/blog/first""#));
+
+    Ok(())
+}
+
+#[test]
+fn activitypub() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "site_url": "https://example.com",
+            "activitypub": {
+                "username": "blog",
+                "limit": 1
+            }
+        }"#,
+    )?;
+    dir.child("index.md").write_str("Home")?;
+    dir.child("first.md")
+        .write_str("---\ntitle: First\ndate: 2024-01-01\n---\n")?;
+    dir.child("second.md")
+        .write_str("---\ntitle: Second\ndate: 2024-02-01\n---\nHello")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/.well-known/webfinger")
+        .assert(predicate::str::contains(
+            r#""subject":"acct:blog@example.com""#,
+        ))
+        .assert(predicate::str::contains(
+            r#""href":"https://example.com/activitypub/actor.json""#,
+        ));
+
+    dir.child("_site/activitypub/actor.json")
+        .assert(predicate::str::contains(r#""preferredUsername":"blog""#))
+        .assert(predicate::str::contains(
+            r#""outbox":"https://example.com/activitypub/outbox.json""#,
+        ));
+
+    dir.child("_site/activitypub/outbox.json")
+        .assert(predicate::str::contains(r#""totalItems":1"#))
+        .assert(predicate::str::contains(r#""name":"Second""#))
+        .assert(predicate::str::contains("<p>Hello</p>"))
+        .assert(predicate::str::contains("First").not());
+
+    Ok(())
+}
+
+#[test]
+fn newsletter() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "site_url": "https://example.com",
+            "newsletter": {
+                "pages": ["/about"],
+                "latest": 1
+            }
+        }"#,
+    )?;
+    dir.child("style.css").write_str("p { color: red }")?;
+    dir.child("about.html").write_str(concat!(
+        "<html><head><link rel=\"stylesheet\" href=\"/style.css\"></head>",
+        "<body><p><a href=\"/blog\">Blog</a></p><script>x()</script></body></html>"
+    ))?;
+    dir.child("first.md")
+        .write_str("---\ntitle: First\ndate: 2024-01-01\n---\n")?;
+    dir.child("second.md")
+        .write_str("---\ntitle: Second\ndate: 2024-02-01\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/newsletter/about.html")
+        .assert(predicate::str::contains(r#"<p style="color: red">"#))
+        .assert(predicate::str::contains(
+            r#"href="https://example.com/blog""#,
+        ))
+        .assert(predicate::str::contains("max-width: 600px"))
+        .assert(predicate::str::contains("<script>").not())
+        .assert(predicate::str::contains("stylesheet").not());
+
+    dir.child("_site/newsletter/second.html")
+        .assert(predicate::path::exists());
+    dir.child("_site/newsletter/first.html")
+        .assert(predicate::path::exists().not());
+
+    dir.child("_site/about/index.html")
+        .assert(predicate::str::contains("<script>"));
+
+    Ok(())
+}
+
+#[test]
+fn fragments() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "fragments": {
+                "items": [
+                    { "name": "header", "selector": "body > header" },
+                    { "name": "missing", "selector": "footer" }
+                ]
+            }
+        }"#,
+    )?;
+    dir.child("index.html").write_str(concat!(
+        "<html><body><header><a href=\"/about\">About</a></header>",
+        "<main>Home</main></body></html>"
+    ))?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("no element matches \"footer\""));
+
+    dir.child("_site/fragments/header.html")
+        .assert(r#"<header><a href="/about">About</a></header>"#);
+
+    dir.child("_site/fragments/manifest.json")
+        .assert(predicate::str::contains(
+            r#""url": "/fragments/header.html""#,
+        ))
+        .assert(predicate::str::contains("missing").not());
+
+    Ok(())
+}
+
+#[test]
+fn tokens() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "tokens": {
+                "values": { "VERSION": "1.0 <beta>" },
+                "env": ["VITRINE_TEST_COMMIT"]
+            }
+        }"#,
+    )?;
+    dir.child("index.html")
+        .write_str("<p>%%VERSION%% %%VITRINE_TEST_COMMIT%% \\%%VERSION%%</p>")?;
+    dir.child("version.json")
+        .write_str(r#"{ "version": "%%VERSION%%", "date": "%%BUILD_DATE%%" }"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .env("VITRINE_TEST_COMMIT", "abc123")
+        .env("SOURCE_DATE_EPOCH", "0")
+        .arg("--reproducible");
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert("<p>1.0 &lt;beta&gt; abc123 %%VERSION%%</p>");
+
+    dir.child("_site/version.json")
+        .assert(predicate::str::contains(
+            r#""version": "1.0 <beta>", "date": "1970-01-01T00:00:00+00:00""#,
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn fail_tokens_missing_env() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "tokens": { "env": ["VITRINE_TEST_MISSING"] } }"#)?;
+    dir.child("index.html")
+        .write_str("%%VITRINE_TEST_MISSING%%")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).env_remove("VITRINE_TEST_MISSING");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("VITRINE_TEST_MISSING"));
+
+    Ok(())
+}
+
+#[test]
+fn fail_keep_going() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.md").write_str("Home")?;
+    dir.child("broken.md").write_str(
+        r#"---
+url: /custom url
+---
+Broken
+"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("--keep-going");
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("broken.md"))
+        .stderr(predicate::str::contains("1 entry failed"));
+
+    dir.child("_site/index.html")
+        .assert(predicate::path::is_file());
+    dir.child("_site/broken/index.html")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn fail_keep_going_layout() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("_layouts/page.tera")
+        .write_str("<h1>{{ title }}</h1>")?;
+    dir.child("_layouts/broken.tera")
+        .write_str("<h1>{{ missing }}</h1>")?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\ntitle: Home\n---\n")?;
+    dir.child("about.md")
+        .write_str("---\nlayout: page.tera\ntitle: About\n---\n")?;
+    dir.child("broken.md")
+        .write_str("---\nlayout: broken.tera\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).arg("--keep-going");
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("broken.md"))
+        .stderr(predicate::str::contains("1 entry failed"));
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("<h1>Home</h1>"));
+    dir.child("_site/about/index.html")
+        .assert(predicate::str::contains("<h1>About</h1>"));
+    dir.child("_site/broken/index.html")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn run_before_build() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "run_before_build": [
+                {
+                    "command": "echo 'body {}' > \"$VITRINE_INPUT_DIR/app.css\"",
+                    "outputs": ["app.css"]
+                }
+            ]
+        }"#,
+    )?;
+    dir.child("index.md").write_str("# Home")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/app.css")
+        .assert(predicate::str::contains("body"));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn run_after_build() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "run_after_build": ["ls \"$VITRINE_OUTPUT_DIR\" > files.txt"] }"#)?;
+    dir.child("index.md").write_str("# Home")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("files.txt")
+        .assert(predicate::str::contains("index.html"));
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "run_after_build": ["exit 3"] }"#)?;
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("While running command \"exit 3\""));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn tailwind() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = assert_fs::TempDir::new()?;
+
+    // Fake executable that appends a rule for each class found in pages
+    dir.child("tailwindcss").write_str(
+        r#"#!/bin/sh
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --input) input="$2"; shift ;;
+        --output) output="$2"; shift ;;
+        --content) content="$2"; shift ;;
+    esac
+    shift
+done
+cat "$input" > "$output"
+grep -oh 'class="[^"]*"' $content | sed 's/class="\(.*\)"/.\1 { color: red }/' >> "$output"
+"#,
+    )?;
+    std::fs::set_permissions(
+        dir.child("tailwindcss").path(),
+        std::fs::Permissions::from_mode(0o755),
+    )?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "ignore": ["tailwindcss"],
+            "tailwind": { "stylesheets": ["/style.css"], "command": "./tailwindcss" }
+        }"#,
+    )?;
+    dir.child("index.html")
+        .write_str(r#"<p class="text-lg">Home</p>"#)?;
+    dir.child("style.css").write_str("body { margin: 0 }")?;
+    dir.child("other.css").write_str("p {}")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/style.css")
+        .assert(predicate::str::contains("body"))
+        .assert(predicate::str::contains(".text-lg"));
+    dir.child("_site/other.css")
+        .assert(predicate::str::contains(".text-lg").not());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn fonts() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    const FILE_NAME: &str = "5b4b9e73d09f0ce389f765c9ea2d5373b404ec40473b7a77c56e4debc7847140.ttf";
+
+    let dir = assert_fs::TempDir::new()?;
+
+    // Fake executable that copies the font without subsetting it
+    dir.child("pyftsubset").write_str(
+        r#"#!/bin/sh
+for arg in "$@"; do
+    case "$arg" in
+        --output-file=*) output="${arg#--output-file=}" ;;
+    esac
+done
+cp "$1" "$output"
+"#,
+    )?;
+    std::fs::set_permissions(
+        dir.child("pyftsubset").path(),
+        std::fs::Permissions::from_mode(0o755),
+    )?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "minify": false,
+            "ignore": ["pyftsubset"],
+            "fonts": {
+                "command": "./pyftsubset",
+                "items": [{ "url": "https://example.com/inter.ttf", "family": "Inter" }]
+            }
+        }"#,
+    )?;
+    // Font previously downloaded from `https://example.com/inter.ttf`
+    dir.child(format!(".vitrine-cache/fonts/downloads/{FILE_NAME}"))
+        .write_str("font")?;
+    dir.child("index.html").write_str("<p>Café</p>")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/fonts.css")
+        .assert(predicate::str::contains(r#"font-family: "Inter";"#))
+        .assert(predicate::str::contains(
+            r#"src: url("/fonts/inter-400-normal.795ea3efa43d0872.woff2") format("woff2");"#,
+        ));
+    dir.child("_site/fonts/inter-400-normal.795ea3efa43d0872.woff2")
+        .assert("font");
+
+    Ok(())
+}
+
+#[test]
+fn fail_export_pdf_no_match() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("index.md").write_str("Home")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir).args(["export", "pdf", "/docs/**"]);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("No page matches \"/docs/**\""));
+
+    Ok(())
+}
+
+#[test]
+fn webmention_send_dry_run() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+    let state_dir = assert_fs::TempDir::new()?;
+
+    let state_file = state_dir.child("webmention.json");
+
+    dir.child("vitrine.config.json").write_str(&format!(
+        r#"{{
+            "site_url": "https://example.com",
+            "webmention": {{
+                "state_file": {:?}
+            }}
+        }}"#,
+        state_file.path()
+    ))?;
+    dir.child("index.html").write_str(concat!(
+        "<a href=\"https://other.com/post\">Post</a>",
+        "<a href=\"https://example.com/about\">About</a>",
+    ))?;
+
+    // The previous run mentioned a link that was removed since
+    state_file.write_str(r#"{ "https://example.com/": ["https://removed.com/"] }"#)?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir)
+        .arg("webmention")
+        .arg("send")
+        .arg("--dry-run");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("-> https://other.com/post"))
+        .stdout(predicate::str::contains("-> https://removed.com/"))
+        .stdout(predicate::str::contains("-> https://example.com/about").not());
+
+    // Dry runs do not update the state file
+    state_file.assert(predicate::str::contains("removed.com"));
+
+    Ok(())
+}
+
+#[test]
+fn fail_layouts_url_for_missing() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("_layouts/page.tera")
+        .write_str("{{ url_for(path=\"missing.md\") }}")?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn fail_layouts_error_location() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("_layouts/page.tera")
+        .write_str("<h1>{{ title }}</h1>\n<p>{{ author }}</p>\n")?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\ntitle: Home\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--> page.tera:2:7"))
+        .stderr(predicate::str::contains("2 | <p>{{ author }}</p>"));
+
+    Ok(())
+}
+
+#[test]
+fn theme() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("theme/vitrine.config.json")
+        .write_str(r#"{ "global_data": { "title": "Theme", "footer": "Theme footer" } }"#)?;
+    dir.child("theme/_layouts/page.tera")
+        .write_str(r#"<p>{{ title }}|{{ footer }}|{% include "nav.tera" %}</p>"#)?;
+    dir.child("theme/_layouts/nav.tera")
+        .write_str("Theme nav")?;
+    dir.child("theme/robots.txt").write_str("Theme robots")?;
+    dir.child("theme/humans.txt").write_str("Theme humans")?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "theme": "theme", "global_data": { "title": "Site" } }"#)?;
+    dir.child("_layouts/nav.tera").write_str("Site nav")?;
+    dir.child("robots.txt").write_str("Site robots")?;
+    dir.child("index.md")
+        .write_str("---\nlayout: page.tera\n---\n")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("Site|Theme footer|Site nav"));
+    dir.child("_site/robots.txt").assert("Site robots");
+    dir.child("_site/humans.txt").assert("Theme humans");
+    dir.child("_site/vitrine.config.json")
+        .assert(predicate::path::missing());
+    dir.child("_site/theme").assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn includes() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false }"#)?;
+    dir.child("index.md").write_str(
+        "{{< include \"install.md\" region=\"unix\" >}}\n\n{{</* include \"install.md\" */>}}",
+    )?;
+    dir.child("_includes/install.md").write_str(
+        "<!-- ANCHOR: unix -->\nRun *make install*.\n<!-- ANCHOR_END: unix -->\nRun setup.exe.",
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("<em>make install</em>"))
+        .assert(predicate::str::contains("setup.exe").not())
+        .assert(predicate::str::contains("{{&lt; include"));
+    dir.child("_site/_includes/install/index.html")
+        .assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn code_directive() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json").write_str(
+        r#"{
+            "input_dir": "docs",
+            "minify": false
+        }"#,
+    )?;
+    dir.child("docs/index.md").write_str(
+        "{{< code file=\"../examples/main.rs\" region=\"setup\" title=\"src\\main.rs `a`\" >}}",
+    )?;
+    dir.child("examples/main.rs").write_str(
+        "fn main() {\n    // ANCHOR: setup\n    let answer = 42;\n    // ANCHOR_END: setup\n    \
+         println!(\"{answer}\");\n}\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/index.html")
+        .assert(predicate::str::contains("language-rs"))
+        .assert(predicate::str::contains("src\\main.rs `a`</figcaption>"))
+        .assert(predicate::str::contains("answer"))
+        .assert(predicate::str::contains("println").not());
+
+    Ok(())
+}
+
+#[test]
+fn markdown_links() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = assert_fs::TempDir::new()?;
+
+    dir.child("vitrine.config.json")
+        .write_str(r#"{ "minify": false }"#)?;
+    dir.child("docs/index.md").write_str(
+        "[Guide](./getting-started.md#install) [API](/docs/api.md?v=2) [Missing](missing.md)",
+    )?;
+    dir.child("docs/getting-started.md")
+        .write_str("# Install")?;
+    dir.child("docs/api.md").write_str("# API")?;
+
+    let mut cmd = Command::cargo_bin("vitrine")?;
+    cmd.current_dir(&dir);
+
+    cmd.assert().success();
+
+    dir.child("_site/docs/index.html")
+        .assert(predicate::str::contains(
+            r#"href="/docs/getting-started#install""#,
+        ))
+        .assert(predicate::str::contains(r#"href="/docs/api?v=2""#))
+        .assert(predicate::str::contains(r#"href="missing.md""#));
+
+    Ok(())
+}
@@ -2,6 +2,7 @@
 
 use proc_macro::TokenStream;
 use quote::quote;
+use syn::ext::IdentExt;
 
 use super::VitrineAttribute;
 
@@ -28,7 +29,7 @@ pub fn impl_from_rhai_macro(ast: &syn::DeriveInput) -> TokenStream {
     let fields =
         fields.named.iter().map(|field| {
             let field_ident = field.ident.as_ref().unwrap();
-            let field_ident_str = field_ident.to_string();
+            let field_ident_str = field_ident.unraw().to_string();
 
             // Get supported attributes
             let field_attrs: Vec<VitrineAttribute> = field